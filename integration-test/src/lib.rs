@@ -4,19 +4,49 @@
 //! - fd 3: a socketpair for structured protocol (CHOOSE/CHOSE, EDIT/EDITED, VIEW/VIEWED)
 //! - stdout: a PTY so isatty(1) returns true (ldapvi requires this)
 //! - stderr: a pipe, captured for assertions
+//!
+//! A session can also run on a different host entirely via
+//! [`TestSession::connect`] and the [`remote`] module's companion server --
+//! see that module's docs for the wire protocol.
+
+mod remote;
+pub use remote::run_remote_server;
 
-use nix::pty::openpty;
+use nix::pty::{openpty, Winsize};
+use nix::sys::signal::{kill, Signal};
 use nix::sys::socket::{socketpair, AddressFamily, SockFlag, SockType};
+use nix::unistd::Pid;
 use std::io::{BufRead, BufReader, Read, Write};
-use std::os::fd::{FromRawFd, IntoRawFd};
+use std::os::fd::{FromRawFd, IntoRawFd, RawFd};
 use std::os::unix::process::CommandExt;
 use std::process::{Child, Command, Stdio};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
+
+/// How a session's raw-terminal input (`send_keys`/`resize`) reaches the
+/// child and how its exit status is observed: directly, for a child
+/// `spawn`ed in this process, or framed over a TCP connection to a
+/// [`remote`] server for one spawned on a different host via
+/// [`TestSession::connect`]. Everything else -- the fd-3 control
+/// protocol and captured stdout/stderr -- goes through `TestSession`'s
+/// `control`/`control_reader`/`stdout_capture`/`stderr_capture` fields
+/// the same way regardless of backend; for `Remote`, those are kept in
+/// sync with the TCP connection by the bridge threads `connect` spawns.
+pub(crate) enum Backend {
+    Local {
+        child: Child,
+        pty_master_fd: RawFd,
+    },
+    Remote {
+        writer: Arc<Mutex<std::net::TcpStream>>,
+        exit_code: Arc<Mutex<Option<i32>>>,
+    },
+}
 
 /// A running test-ldapvi session.
 pub struct TestSession {
-    child: Child,
+    backend: Backend,
     /// Our end of the socketpair (fd 3 in the child).
     control: std::fs::File,
     /// Buffered reader for the control fd.
@@ -25,13 +55,182 @@ pub struct TestSession {
     stdout_capture: Arc<Mutex<Vec<u8>>>,
     /// Captured stderr, populated by background thread.
     stderr_capture: Arc<Mutex<Vec<u8>>>,
-    /// Join handle for stdout drain thread.
-    _stdout_thread: thread::JoinHandle<()>,
-    /// Join handle for stderr drain thread.
-    _stderr_thread: thread::JoinHandle<()>,
+    /// Join handles for this session's background threads: local mode's
+    /// stdout/stderr drain threads, or remote mode's downlink bridge
+    /// thread (its uplink thread is not kept here; see `remote::connect`).
+    _threads: Vec<thread::JoinHandle<()>>,
+}
+
+/// Lets [`remote::connect`] build a [`TestSession`] around a [`Backend::Remote`]
+/// from outside this module, the same way `TestSession { .. }` struct-literal
+/// construction does for the local path in `spawn_in_with_size`.
+impl TestSession {
+    pub(crate) fn from_parts(
+        backend: Backend,
+        control: std::fs::File,
+        control_reader: BufReader<std::fs::File>,
+        stdout_capture: Arc<Mutex<Vec<u8>>>,
+        stderr_capture: Arc<Mutex<Vec<u8>>>,
+        threads: Vec<thread::JoinHandle<()>>,
+    ) -> TestSession {
+        TestSession {
+            backend,
+            control,
+            control_reader,
+            stdout_capture,
+            stderr_capture,
+            _threads: threads,
+        }
+    }
+}
+
+/// The raw OS-level pieces of a spawned `test-ldapvi` child: the PTY
+/// master, both ends of the fd-3 control socketpair, and the `Child`
+/// handle. Shared by [`TestSession::spawn_in_with_size`] (which wraps
+/// these into local drain threads) and [`remote`]'s server side (which
+/// wraps them into TCP-forwarding threads instead), so the intricate
+/// `pre_exec` wiring below is written, and needs to be verified, exactly
+/// once.
+pub(crate) struct SpawnedChild {
+    pub(crate) child: Child,
+    pub(crate) control_write: std::fs::File,
+    pub(crate) control_read: std::fs::File,
+    pub(crate) pty_master_fd: RawFd,
+    pub(crate) stderr_pipe: std::process::ChildStderr,
+}
+
+/// Fork+exec `binary` with fd 3 wired to a control socketpair, stdin/stdout
+/// to a PTY slave, and stderr piped -- the OS-level setup `TestSession`'s
+/// local and remote spawn paths both build on.
+pub(crate) fn spawn_child_with_pty(
+    binary: &str,
+    args: &[&str],
+    env: &[(&str, &str)],
+    cwd: Option<&str>,
+    size: Option<(u16, u16)>,
+) -> std::io::Result<SpawnedChild> {
+    // Create socketpair for control channel (fd 3 in child).
+    let (parent_sock, child_sock) = socketpair(
+        AddressFamily::Unix,
+        SockType::Stream,
+        None,
+        SockFlag::empty(),
+    )
+    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+
+    // Create PTY for child's stdout.
+    let winsize = size.map(|(rows, cols)| Winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    });
+    let pty = openpty(winsize.as_ref(), None)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+    let pty_master_fd = pty.master.into_raw_fd();
+    let pty_slave_fd = pty.slave.into_raw_fd();
+
+    let child_sock_fd = child_sock.into_raw_fd();
+
+    let mut cmd = Command::new(binary);
+    cmd.args(args);
+    for (k, v) in env {
+        cmd.env(k, v);
+    }
+
+    if let Some(dir) = cwd {
+        cmd.current_dir(dir);
+    }
+
+    // stdin and stdout are set up in pre_exec (PTY slave) so
+    // isatty(0) and isatty(1) both return true.
+    // stderr is piped for capture.
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::piped());
+
+    // In the child (pre_exec), set up stdin, stdout, and fd 3.
+    unsafe {
+        cmd.pre_exec(move || {
+            // Set up stdin and stdout as PTY slave.
+            // Both must be a tty so fixup_streams() in ldapvi.c
+            // doesn't try to reopen from /dev/tty.
+            if libc::dup2(pty_slave_fd, 0) == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if libc::dup2(pty_slave_fd, 1) == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            if pty_slave_fd > 1 {
+                libc::close(pty_slave_fd);
+            }
+
+            // Set up fd 3 as control channel.
+            if child_sock_fd == 3 {
+                let flags = libc::fcntl(3, libc::F_GETFD);
+                libc::fcntl(3, libc::F_SETFD, flags & !libc::FD_CLOEXEC);
+            } else {
+                if libc::dup2(child_sock_fd, 3) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                libc::close(child_sock_fd);
+            }
+            Ok(())
+        });
+    }
+
+    let mut child = cmd.spawn()?;
+
+    // Close the child-side fds in the parent.
+    unsafe {
+        libc::close(child_sock_fd);
+        libc::close(pty_slave_fd);
+    }
+
+    // Set up control channel: dup the fd so we have separate
+    // read and write handles (BufReader and File).
+    let parent_fd = parent_sock.into_raw_fd();
+    let read_fd = unsafe { libc::dup(parent_fd) };
+    let control_write = unsafe { std::fs::File::from_raw_fd(parent_fd) };
+    let control_read = unsafe { std::fs::File::from_raw_fd(read_fd) };
+
+    let stderr_pipe = child.stderr.take().unwrap();
+
+    Ok(SpawnedChild {
+        child,
+        control_write,
+        control_read,
+        pty_master_fd,
+        stderr_pipe,
+    })
 }
 
 impl TestSession {
+    /// Raise this process's soft `RLIMIT_NOFILE` to the highest ceiling
+    /// the OS allows. Each `TestSession` consumes a socketpair, a PTY
+    /// pair, and a stderr pipe plus two threads; running the suite in
+    /// parallel quickly exhausts the default soft limit (commonly 256 on
+    /// macOS/BSD), producing spurious `EMFILE`/`openpty` failures rather
+    /// than a real bug. Call this once before spawning sessions -- it's
+    /// a no-op once the soft limit already meets the ceiling, so it's
+    /// safe to call from every test rather than just a single `main`.
+    pub fn raise_fd_limit() -> std::io::Result<()> {
+        use nix::sys::resource::{getrlimit, setrlimit, Resource};
+
+        let (soft, hard) = getrlimit(Resource::RLIMIT_NOFILE)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+
+        #[cfg(target_os = "macos")]
+        let hard = darwin_max_files_per_proc().map(|m| hard.min(m)).unwrap_or(hard);
+
+        if soft >= hard {
+            return Ok(());
+        }
+
+        setrlimit(Resource::RLIMIT_NOFILE, hard, hard)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+    }
+
     /// Spawn test-ldapvi with the given arguments.
     ///
     /// `binary` is the path to the test-ldapvi binary.
@@ -52,91 +251,45 @@ impl TestSession {
         env: &[(&str, &str)],
         cwd: Option<&str>,
     ) -> std::io::Result<TestSession> {
-        // Create socketpair for control channel (fd 3 in child).
-        let (parent_sock, child_sock) = socketpair(
-            AddressFamily::Unix,
-            SockType::Stream,
-            None,
-            SockFlag::empty(),
-        )
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-
-        // Create PTY for child's stdout.
-        let pty = openpty(None, None)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-        let pty_master_fd = pty.master.into_raw_fd();
-        let pty_slave_fd = pty.slave.into_raw_fd();
-
-        let child_sock_fd = child_sock.into_raw_fd();
-
-        let mut cmd = Command::new(binary);
-        cmd.args(args);
-        for (k, v) in env {
-            cmd.env(k, v);
-        }
-
-        if let Some(dir) = cwd {
-            cmd.current_dir(dir);
-        }
-
-        // stdin and stdout are set up in pre_exec (PTY slave) so
-        // isatty(0) and isatty(1) both return true.
-        // stderr is piped for capture.
-        cmd.stdin(Stdio::null());
-        cmd.stdout(Stdio::null());
-        cmd.stderr(Stdio::piped());
-
-        // In the child (pre_exec), set up stdin, stdout, and fd 3.
-        unsafe {
-            cmd.pre_exec(move || {
-                // Set up stdin and stdout as PTY slave.
-                // Both must be a tty so fixup_streams() in ldapvi.c
-                // doesn't try to reopen from /dev/tty.
-                if libc::dup2(pty_slave_fd, 0) == -1 {
-                    return Err(std::io::Error::last_os_error());
-                }
-                if libc::dup2(pty_slave_fd, 1) == -1 {
-                    return Err(std::io::Error::last_os_error());
-                }
-                if pty_slave_fd > 1 {
-                    libc::close(pty_slave_fd);
-                }
-
-                // Set up fd 3 as control channel.
-                if child_sock_fd == 3 {
-                    let flags = libc::fcntl(3, libc::F_GETFD);
-                    libc::fcntl(3, libc::F_SETFD, flags & !libc::FD_CLOEXEC);
-                } else {
-                    if libc::dup2(child_sock_fd, 3) == -1 {
-                        return Err(std::io::Error::last_os_error());
-                    }
-                    libc::close(child_sock_fd);
-                }
-                Ok(())
-            });
-        }
-
-        let mut child = cmd.spawn()?;
+        Self::spawn_in_with_size(binary, args, env, cwd, None)
+    }
 
-        // Close the child-side fds in the parent.
-        unsafe {
-            libc::close(child_sock_fd);
-            libc::close(pty_slave_fd);
-        }
+    /// Like `spawn`, but with an initial PTY window size -- lets a test
+    /// exercise screen-layout/paging logic that depends on row/column
+    /// count, which `spawn`'s kernel-default geometry doesn't exercise.
+    pub fn spawn_with_size(
+        binary: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        rows: u16,
+        cols: u16,
+    ) -> std::io::Result<TestSession> {
+        Self::spawn_in_with_size(binary, args, env, None, Some((rows, cols)))
+    }
 
-        // Set up control channel: dup the fd so we have separate
-        // read and write handles (BufReader and File).
-        let parent_fd = parent_sock.into_raw_fd();
-        let read_fd = unsafe { libc::dup(parent_fd) };
-        let control_write = unsafe { std::fs::File::from_raw_fd(parent_fd) };
-        let control_read = unsafe { std::fs::File::from_raw_fd(read_fd) };
-        let control_reader = BufReader::new(control_read);
+    /// Like `spawn_in`, but also accepts an initial `(rows, cols)` PTY
+    /// window size (`None` leaves it at the kernel default, as `spawn`/
+    /// `spawn_in` do).
+    pub fn spawn_in_with_size(
+        binary: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        cwd: Option<&str>,
+        size: Option<(u16, u16)>,
+    ) -> std::io::Result<TestSession> {
+        let spawned = spawn_child_with_pty(binary, args, env, cwd, size)?;
+        let pty_master_fd = spawned.pty_master_fd;
+        let control_reader = BufReader::new(spawned.control_read);
 
         // Background thread to drain PTY master (prevents child blocking).
+        // The thread gets a dup of the master fd, not the fd itself, so
+        // `pty_master_fd` stays valid on `TestSession` for `resize`/
+        // `send_keys` to use after spawn.
         let stdout_capture = Arc::new(Mutex::new(Vec::new()));
         let stdout_cap = Arc::clone(&stdout_capture);
+        let drain_fd = unsafe { libc::dup(pty_master_fd) };
         let stdout_thread = thread::spawn(move || {
-            let mut master = unsafe { std::fs::File::from_raw_fd(pty_master_fd) };
+            let mut master = unsafe { std::fs::File::from_raw_fd(drain_fd) };
             let mut buf = [0u8; 4096];
             loop {
                 match master.read(&mut buf) {
@@ -159,9 +312,8 @@ impl TestSession {
         // Background thread to capture stderr.
         let stderr_capture = Arc::new(Mutex::new(Vec::new()));
         let stderr_cap = Arc::clone(&stderr_capture);
-        let stderr_pipe = child.stderr.take().unwrap();
         let stderr_thread = thread::spawn(move || {
-            let mut pipe = stderr_pipe;
+            let mut pipe = spawned.stderr_pipe;
             let mut buf = [0u8; 4096];
             loop {
                 match pipe.read(&mut buf) {
@@ -178,16 +330,116 @@ impl TestSession {
         });
 
         Ok(TestSession {
-            child,
-            control: control_write,
+            backend: Backend::Local {
+                child: spawned.child,
+                pty_master_fd,
+            },
+            control: spawned.control_write,
             control_reader,
             stdout_capture,
             stderr_capture,
-            _stdout_thread: stdout_thread,
-            _stderr_thread: stderr_thread,
+            _threads: vec![stdout_thread, stderr_thread],
         })
     }
 
+    /// Like `spawn`, but executes `binary` on the other end of a
+    /// [`run_remote_server`] listening at `addr` (e.g. a different
+    /// host, or a container/emulator reachable only over TCP) instead of
+    /// forking it directly. The structured control protocol
+    /// (`expect_choose`/`respond`/`expect_edit`/`expect_view`) and captured
+    /// stdout/stderr behave exactly as for a local session. The one thing
+    /// that *doesn't* travel automatically: `expect_edit`/`expect_view`'s
+    /// pathname is on the remote host's filesystem, so an `editor_fn`/
+    /// `view_fn` that calls `std::fs` directly only works if that
+    /// filesystem is also reachable from here (e.g. a shared volume) --
+    /// otherwise the callback needs its own way to reach the remote file.
+    pub fn connect(
+        addr: &str,
+        binary: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+    ) -> std::io::Result<TestSession> {
+        Self::connect_with_size(addr, binary, args, env, None)
+    }
+
+    /// Like `connect`, but also accepts an initial `(rows, cols)` PTY
+    /// window size, same as `spawn_in_with_size`.
+    pub fn connect_with_size(
+        addr: &str,
+        binary: &str,
+        args: &[&str],
+        env: &[(&str, &str)],
+        size: Option<(u16, u16)>,
+    ) -> std::io::Result<TestSession> {
+        remote::connect(addr, binary, args, env, size)
+    }
+
+    /// Change the PTY's window size and send `SIGWINCH` so the running
+    /// `test-ldapvi` re-reads its dimensions, same as a real terminal
+    /// resize would.
+    pub fn resize(&mut self, rows: u16, cols: u16) -> std::io::Result<()> {
+        match &self.backend {
+            Backend::Local { child, pty_master_fd } => {
+                let ws = Winsize {
+                    ws_row: rows,
+                    ws_col: cols,
+                    ws_xpixel: 0,
+                    ws_ypixel: 0,
+                };
+                if unsafe { libc::ioctl(*pty_master_fd, libc::TIOCSWINSZ, &ws) } == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                kill(Pid::from_raw(child.id() as i32), Signal::SIGWINCH)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+            }
+            Backend::Remote { writer, .. } => {
+                let mut payload = [0u8; 4];
+                payload[0..2].copy_from_slice(&rows.to_be_bytes());
+                payload[2..4].copy_from_slice(&cols.to_be_bytes());
+                remote::write_frame(&mut *writer.lock().unwrap(), remote::TAG_RESIZE, &payload)
+            }
+        }
+    }
+
+    /// Write raw bytes to the PTY master, as if a user had typed them at
+    /// the terminal -- for driving raw terminal input (keypresses, control
+    /// sequences, `^D`) that the structured fd-3 protocol can't reach. For
+    /// a local session the master fd is shared with the stdout-drain
+    /// thread via the same split read/write pattern as the control
+    /// channel: the thread reads from its own dup, this writes directly
+    /// to the master. For a remote session the bytes are framed and sent
+    /// to the server instead, which writes them to its own PTY master.
+    pub fn send_keys(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        let pty_master_fd = match &self.backend {
+            Backend::Local { pty_master_fd, .. } => *pty_master_fd,
+            Backend::Remote { writer, .. } => {
+                return remote::write_frame(&mut *writer.lock().unwrap(), remote::TAG_KEYS, bytes);
+            }
+        };
+        let mut remaining = bytes;
+        while !remaining.is_empty() {
+            let n = unsafe {
+                libc::write(
+                    pty_master_fd,
+                    remaining.as_ptr() as *const libc::c_void,
+                    remaining.len(),
+                )
+            };
+            if n < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            remaining = &remaining[n as usize..];
+        }
+        Ok(())
+    }
+
+    /// Like `send_keys`, but appends a trailing newline -- convenience for
+    /// line-oriented input.
+    pub fn send_line(&mut self, line: &str) -> std::io::Result<()> {
+        self.send_keys(line.as_bytes())?;
+        self.send_keys(b"\n")
+    }
+
     /// Read one line from the control fd.
     fn read_control_line(&mut self) -> String {
         let mut line = String::new();
@@ -198,6 +450,37 @@ impl TestSession {
         line
     }
 
+    /// Like `read_control_line`, but gives up instead of blocking forever:
+    /// `poll`s the control fd for up to `timeout` first, returning `None`
+    /// if nothing became readable in time. A wedged or deadlocked child
+    /// then surfaces as a normal test failure with captured output instead
+    /// of hanging the whole test run.
+    fn read_control_line_timeout(&mut self, timeout: Duration) -> Option<String> {
+        use nix::poll::{poll, PollFd, PollFlags};
+        use std::os::fd::{AsRawFd, BorrowedFd};
+
+        let fd = self.control_reader.get_ref().as_raw_fd();
+        let borrowed = unsafe { BorrowedFd::borrow_raw(fd) };
+        let mut fds = [PollFd::new(borrowed, PollFlags::POLLIN)];
+        let millis = i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX);
+        match poll(&mut fds, millis) {
+            Ok(n) if n > 0 => {}
+            _ => return None,
+        }
+        let mut line = String::new();
+        self.control_reader.read_line(&mut line).ok()?;
+        line.truncate(line.trim_end_matches('\n').len());
+        Some(line)
+    }
+
+    /// Panic message for a deadline-aware `expect_*_timeout` that timed
+    /// out, with the stderr captured so far so the failure is debuggable
+    /// rather than a bare "timed out".
+    fn timeout_message(&self, awaited: &str, timeout: Duration) -> String {
+        let stderr = String::from_utf8_lossy(&self.stderr_capture.lock().unwrap()).to_string();
+        format!("timed out after {timeout:?} waiting for {awaited}\ncaptured stderr:\n{stderr}")
+    }
+
     /// Read a `CHOOSE <charbag>` message from the control fd.
     /// Returns the charbag string.
     pub fn expect_choose(&mut self) -> String {
@@ -209,6 +492,19 @@ impl TestSession {
         line["CHOOSE ".len()..].to_string()
     }
 
+    /// Like `expect_choose`, but panics (with captured stderr) instead of
+    /// blocking forever if nothing arrives within `timeout`.
+    pub fn expect_choose_timeout(&mut self, timeout: Duration) -> String {
+        let line = self
+            .read_control_line_timeout(timeout)
+            .unwrap_or_else(|| panic!("{}", self.timeout_message("CHOOSE", timeout)));
+        assert!(
+            line.starts_with("CHOOSE "),
+            "expected 'CHOOSE ...', got '{line}'"
+        );
+        line["CHOOSE ".len()..].to_string()
+    }
+
     /// Send a `CHOSE <c>` response on the control fd.
     pub fn respond(&mut self, c: char) {
         write!(self.control, "CHOSE {c}\n").expect("failed to write to control fd");
@@ -234,6 +530,26 @@ impl TestSession {
         pathname.to_string()
     }
 
+    /// Like `expect_edit`, but panics (with captured stderr) instead of
+    /// blocking forever if nothing arrives within `timeout`.
+    pub fn expect_edit_timeout<F>(&mut self, timeout: Duration, editor_fn: F) -> String
+    where
+        F: FnOnce(&str),
+    {
+        let line = self
+            .read_control_line_timeout(timeout)
+            .unwrap_or_else(|| panic!("{}", self.timeout_message("EDIT", timeout)));
+        assert!(
+            line.starts_with("EDIT "),
+            "expected 'EDIT ...', got '{line}'"
+        );
+        let pathname = &line["EDIT ".len()..];
+        editor_fn(pathname);
+        write!(self.control, "EDITED\n").expect("failed to write to control fd");
+        self.control.flush().expect("failed to flush control fd");
+        pathname.to_string()
+    }
+
     /// Read a `VIEW <pathname>` message from the control fd.
     /// Calls `view_fn` with the pathname so the test can inspect the file.
     /// Then sends `VIEWED` back.
@@ -253,18 +569,61 @@ impl TestSession {
         pathname.to_string()
     }
 
-    /// Wait for the child to exit and assert the exit code.
+    /// Like `expect_view`, but panics (with captured stderr) instead of
+    /// blocking forever if nothing arrives within `timeout`.
+    pub fn expect_view_timeout<F>(&mut self, timeout: Duration, view_fn: F) -> String
+    where
+        F: FnOnce(&str),
+    {
+        let line = self
+            .read_control_line_timeout(timeout)
+            .unwrap_or_else(|| panic!("{}", self.timeout_message("VIEW", timeout)));
+        assert!(
+            line.starts_with("VIEW "),
+            "expected 'VIEW ...', got '{line}'"
+        );
+        let pathname = &line["VIEW ".len()..];
+        view_fn(pathname);
+        write!(self.control, "VIEWED\n").expect("failed to write to control fd");
+        self.control.flush().expect("failed to flush control fd");
+        pathname.to_string()
+    }
+
+    /// Wait for the child to exit and assert the exit code. For a remote
+    /// session, "exit" means the `TAG_EXIT` frame the server sends once
+    /// its own `child.wait()` returns -- the downlink thread records it
+    /// into `exit_code` as it arrives.
     pub fn wait_exit(mut self, expected_code: i32) -> SessionOutput {
-        let status = self.child.wait().expect("failed to wait for child");
-        let code = status.code().unwrap_or(-1);
+        let code = match &mut self.backend {
+            Backend::Local { child, .. } => {
+                let status = child.wait().expect("failed to wait for child");
+                status.code().unwrap_or(-1)
+            }
+            Backend::Remote { writer, exit_code } => {
+                let code = loop {
+                    if let Some(code) = *exit_code.lock().unwrap() {
+                        break code;
+                    }
+                    thread::sleep(Duration::from_millis(10));
+                };
+                // Closing our end unblocks the downlink thread's read
+                // (and, on the server, the per-connection handler's own
+                // read of us) instead of leaving both sides waiting on a
+                // connection neither intends to send more on.
+                let _ = writer.lock().unwrap().shutdown(std::net::Shutdown::Both);
+                code
+            }
+        };
 
         // Drop control fd to unblock any pending reads in the child
         drop(self.control);
 
-        // Wait for capture threads to finish.
-        // (They'll finish once the child's fds close.)
-        let _ = self._stdout_thread.join();
-        let _ = self._stderr_thread.join();
+        // Wait for background threads to finish.
+        // (They'll finish once the child's fds -- or, remotely, the TCP
+        // connection -- close.)
+        for handle in self._threads.drain(..) {
+            let _ = handle.join();
+        }
 
         let stdout = String::from_utf8_lossy(&self.stdout_capture.lock().unwrap()).to_string();
         let stderr = String::from_utf8_lossy(&self.stderr_capture.lock().unwrap()).to_string();
@@ -278,8 +637,253 @@ impl TestSession {
     }
 }
 
+impl Drop for TestSession {
+    fn drop(&mut self) {
+        if let Backend::Local { pty_master_fd, .. } = &self.backend {
+            unsafe {
+                libc::close(*pty_master_fd);
+            }
+        }
+    }
+}
+
+/// Read the Darwin-only `kern.maxfilesperproc` sysctl. On macOS this is
+/// often the real per-process descriptor ceiling -- lower than whatever
+/// `getrlimit(RLIMIT_NOFILE)` reports as the hard limit -- so
+/// `TestSession::raise_fd_limit` clamps to it before calling `setrlimit`,
+/// which would otherwise fail or silently cap at this same value anyway.
+#[cfg(target_os = "macos")]
+fn darwin_max_files_per_proc() -> Option<u64> {
+    let name = b"kern.maxfilesperproc\0";
+    let mut value: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>();
+    let rc = unsafe {
+        libc::sysctlbyname(
+            name.as_ptr() as *const libc::c_char,
+            &mut value as *mut _ as *mut libc::c_void,
+            &mut len,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if rc == 0 && value > 0 {
+        Some(value as u64)
+    } else {
+        None
+    }
+}
+
 /// Output captured from a completed session.
 pub struct SessionOutput {
     pub stdout: String,
     pub stderr: String,
 }
+
+// ===========================================================================
+// Golden-file snapshots
+// ===========================================================================
+//
+// `assert_stdout_matches`/`assert_ldif_matches` compare captured output
+// against a golden file under `tests/snapshots/`, after normalizing the
+// volatile bits (temp paths, `,ldapvi-*.ldif` filenames, timestamps,
+// entryUUID) that would otherwise make every run's output a unique snapshot.
+// Set `LDAPVI_UPDATE_SNAPSHOTS=1` to (re)write the golden file instead of
+// comparing against it.
+
+/// Replace every non-overlapping match `find` locates in `text` with `token`.
+fn redact_all(text: &str, token: &str, find: impl Fn(&str) -> Option<(usize, usize)>) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some((start, end)) = find(rest) {
+        result.push_str(&rest[..start]);
+        result.push_str(token);
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Find the next UUID-shaped run (8-4-4-4-12 hex digits) in `s`, the shape
+/// `entryUUID` values take.
+fn find_uuid(s: &str) -> Option<(usize, usize)> {
+    let bytes = s.as_bytes();
+    let groups = [8, 4, 4, 4, 12];
+    'outer: for start in 0..bytes.len() {
+        let mut pos = start;
+        for (i, &len) in groups.iter().enumerate() {
+            if pos + len > bytes.len() || !bytes[pos..pos + len].iter().all(u8::is_ascii_hexdigit) {
+                continue 'outer;
+            }
+            pos += len;
+            if i + 1 < groups.len() {
+                if bytes.get(pos) != Some(&b'-') {
+                    continue 'outer;
+                }
+                pos += 1;
+            }
+        }
+        return Some((start, pos));
+    }
+    None
+}
+
+/// Find the next generalizedTime-shaped timestamp (14 digits + `Z`) in `s`,
+/// the shape `modifyTimestamp`/`createTimestamp` values take.
+fn find_timestamp(s: &str) -> Option<(usize, usize)> {
+    let bytes = s.as_bytes();
+    if bytes.len() < 15 {
+        return None;
+    }
+    for start in 0..=bytes.len() - 15 {
+        if bytes[start..start + 14].iter().all(u8::is_ascii_digit) && bytes[start + 14] == b'Z' {
+            return Some((start, start + 15));
+        }
+    }
+    None
+}
+
+/// Find the next `,ldapvi-<digits>.ldif` saved-changes filename in `s`.
+fn find_ldif_filename(s: &str) -> Option<(usize, usize)> {
+    let marker = ",ldapvi-";
+    let start = s.find(marker)?;
+    let digits_start = start + marker.len();
+    let bytes = s.as_bytes();
+    let mut pos = digits_start;
+    while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+        pos += 1;
+    }
+    if pos == digits_start || !s[pos..].starts_with(".ldif") {
+        return None;
+    }
+    Some((start, pos + ".ldif".len()))
+}
+
+/// Find the next occurrence of the OS temp directory followed by a
+/// run-specific path component in `s`.
+fn find_temp_path(s: &str) -> Option<(usize, usize)> {
+    let tmp = std::env::temp_dir();
+    let prefix = tmp.to_str()?;
+    let start = s.find(prefix)?;
+    let bytes = s.as_bytes();
+    let mut pos = start + prefix.len();
+    while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() && bytes[pos] != b'"' && bytes[pos] != b'\'' {
+        pos += 1;
+    }
+    Some((start, pos))
+}
+
+/// Normalize volatile output before comparing it to (or writing it as) a
+/// snapshot.
+fn redact_volatile(text: &str) -> String {
+    let text = redact_all(text, "[UUID]", find_uuid);
+    let text = redact_all(&text, "[TIMESTAMP]", find_timestamp);
+    let text = redact_all(&text, "[LDIF_FILE]", find_ldif_filename);
+    redact_all(&text, "[TEMP_PATH]", find_temp_path)
+}
+
+/// Render a unified-style line diff of `expected` vs `actual` for a snapshot
+/// mismatch panic message: ` ` shared, `-` only in `expected`, `+` only in
+/// `actual`. Aligned via the standard LCS dynamic-programming/backtrack
+/// algorithm; snapshots are short enough that full context beats hunk
+/// collapsing here.
+fn diff_text(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+    let n = old.len();
+    let m = new.len();
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            out.push_str("  ");
+            out.push_str(old[i]);
+            out.push('\n');
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            out.push_str("- ");
+            out.push_str(old[i]);
+            out.push('\n');
+            i += 1;
+        } else {
+            out.push_str("+ ");
+            out.push_str(new[j]);
+            out.push('\n');
+            j += 1;
+        }
+    }
+    while i < n {
+        out.push_str("- ");
+        out.push_str(old[i]);
+        out.push('\n');
+        i += 1;
+    }
+    while j < m {
+        out.push_str("+ ");
+        out.push_str(new[j]);
+        out.push('\n');
+        j += 1;
+    }
+    out
+}
+
+/// Path to the golden file for `name`, rooted at this crate's `tests/snapshots/`.
+fn snapshot_path(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.snap"))
+}
+
+/// Compare `actual` (after [`redact_volatile`]) against the golden file for
+/// `name`, or rewrite it when `LDAPVI_UPDATE_SNAPSHOTS=1` is set.
+fn assert_text_matches_snapshot(name: &str, actual: &str) {
+    let normalized = redact_volatile(actual);
+    let path = snapshot_path(name);
+
+    if std::env::var("LDAPVI_UPDATE_SNAPSHOTS").as_deref() == Ok("1") {
+        std::fs::create_dir_all(path.parent().unwrap()).expect("failed to create snapshots dir");
+        std::fs::write(&path, &normalized).expect("failed to write snapshot");
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "no snapshot at {}; run with LDAPVI_UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        )
+    });
+
+    assert!(
+        normalized == expected,
+        "snapshot '{name}' does not match (run with LDAPVI_UPDATE_SNAPSHOTS=1 to update):\n{}",
+        diff_text(&expected, &normalized)
+    );
+}
+
+impl TestSession {
+    /// Assert stdout captured so far (after [`redact_volatile`]) matches the
+    /// golden file `tests/snapshots/<name>.snap`.
+    pub fn assert_stdout_matches(&self, name: &str) {
+        let stdout = String::from_utf8_lossy(&self.stdout_capture.lock().unwrap()).to_string();
+        assert_text_matches_snapshot(name, &stdout);
+    }
+
+    /// Assert the LDIF file at `path` (after [`redact_volatile`]) matches the
+    /// golden file `tests/snapshots/<name>.snap`.
+    pub fn assert_ldif_matches(&self, path: &str, name: &str) {
+        let contents =
+            std::fs::read_to_string(path).unwrap_or_else(|e| panic!("failed to read {path}: {e}"));
+        assert_text_matches_snapshot(name, &contents);
+    }
+}
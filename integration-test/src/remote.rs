@@ -0,0 +1,439 @@
+//! Companion TCP server for running a `test-ldapvi` session on a different
+//! host (or inside a container/emulator reachable only over TCP) instead of
+//! forking it directly in this process -- see [`TestSession::connect`](crate::TestSession::connect).
+//!
+//! A client ([`connect`]) opens a TCP connection, sends a line-based
+//! preamble naming the binary/args/env/cwd/size (mirroring the CHOOSE/CHOSE
+//! line style the fd-3 control protocol already uses), then the connection
+//! switches to binary frames:
+//!
+//! ```text
+//! [tag: u8][len: u32 BE][payload: len bytes]
+//! ```
+//!
+//! | tag | direction       | payload                                  |
+//! |-----|-----------------|-------------------------------------------|
+//! | `C` | server -> client | a line read from the child's control fd   |
+//! | `I` | client -> server | a line to write to the child's control fd |
+//! | `O` | server -> client | bytes read from the child's PTY master     |
+//! | `E` | server -> client | bytes read from the child's stderr pipe    |
+//! | `K` | client -> server | raw bytes to write to the PTY master       |
+//! | `R` | client -> server | 4 bytes: new rows (u16 BE), cols (u16 BE)  |
+//! | `X` | server -> client | 4 bytes: the child's exit code (i32 BE)    |
+//!
+//! The server spawns the child with exactly the same `pre_exec` fd-3/PTY
+//! wiring [`crate::spawn_child_with_pty`] uses locally, so from the child's
+//! point of view nothing is different about running under this server.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use nix::sys::socket::{socketpair, AddressFamily, SockFlag, SockType};
+
+use crate::{spawn_child_with_pty, Backend, TestSession};
+
+pub(crate) const TAG_CONTROL_OUT: u8 = b'C';
+pub(crate) const TAG_CONTROL_IN: u8 = b'I';
+pub(crate) const TAG_STDOUT: u8 = b'O';
+pub(crate) const TAG_STDERR: u8 = b'E';
+pub(crate) const TAG_KEYS: u8 = b'K';
+pub(crate) const TAG_RESIZE: u8 = b'R';
+pub(crate) const TAG_EXIT: u8 = b'X';
+
+/// Write one `[tag][len][payload]` frame.
+pub(crate) fn write_frame(stream: &mut TcpStream, tag: u8, payload: &[u8]) -> std::io::Result<()> {
+    let len = u32::try_from(payload.len()).expect("frame payload too large");
+    stream.write_all(&[tag])?;
+    stream.write_all(&len.to_be_bytes())?;
+    stream.write_all(payload)?;
+    stream.flush()
+}
+
+/// Read one `[tag][len][payload]` frame, or `None` at a clean EOF before the
+/// tag byte.
+fn read_frame<R: Read>(reader: &mut R) -> std::io::Result<Option<(u8, Vec<u8>)>> {
+    let mut tag = [0u8; 1];
+    match reader.read_exact(&mut tag) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let mut len_buf = [0u8; 4];
+    reader.read_exact(&mut len_buf)?;
+    let len = u32::from_be_bytes(len_buf) as usize;
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload)?;
+    Ok(Some((tag[0], payload)))
+}
+
+/// Write the SPAWN preamble: binary, args (one per line), a blank line,
+/// `K=V` env lines, a blank line, then cwd (or `-`) and size (`ROWSxCOLS`
+/// or `-`) each on their own line.
+fn write_preamble(
+    stream: &mut TcpStream,
+    binary: &str,
+    args: &[&str],
+    env: &[(&str, &str)],
+    cwd: Option<&str>,
+    size: Option<(u16, u16)>,
+) -> std::io::Result<()> {
+    writeln!(stream, "{binary}")?;
+    for arg in args {
+        writeln!(stream, "{arg}")?;
+    }
+    writeln!(stream)?;
+    for (k, v) in env {
+        writeln!(stream, "{k}={v}")?;
+    }
+    writeln!(stream)?;
+    writeln!(stream, "{}", cwd.unwrap_or("-"))?;
+    match size {
+        Some((rows, cols)) => writeln!(stream, "{rows}x{cols}")?,
+        None => writeln!(stream, "-")?,
+    }
+    stream.flush()
+}
+
+/// Parsed form of [`write_preamble`]'s wire format.
+struct Preamble {
+    binary: String,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    cwd: Option<String>,
+    size: Option<(u16, u16)>,
+}
+
+fn read_preamble(reader: &mut BufReader<TcpStream>) -> std::io::Result<Preamble> {
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        let n = reader.read_line(&mut line)?;
+        if n == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "connection closed while reading preamble",
+            ));
+        }
+        line.truncate(line.trim_end_matches('\n').len());
+        lines.push(line);
+        // binary line, then N arg lines, terminated by a blank line.
+        if lines.len() >= 2 && lines.last().unwrap().is_empty() {
+            break;
+        }
+    }
+    let binary = lines.remove(0);
+    lines.pop(); // trailing blank line
+    let args = lines;
+
+    let mut env = Vec::new();
+    loop {
+        let mut line = String::new();
+        let n = reader.read_line(&mut line)?;
+        if n == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "connection closed while reading env",
+            ));
+        }
+        line.truncate(line.trim_end_matches('\n').len());
+        if line.is_empty() {
+            break;
+        }
+        let (k, v) = line
+            .split_once('=')
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "bad env line"))?;
+        env.push((k.to_string(), v.to_string()));
+    }
+
+    let mut cwd_line = String::new();
+    reader.read_line(&mut cwd_line)?;
+    cwd_line.truncate(cwd_line.trim_end_matches('\n').len());
+    let cwd = if cwd_line == "-" { None } else { Some(cwd_line) };
+
+    let mut size_line = String::new();
+    reader.read_line(&mut size_line)?;
+    size_line.truncate(size_line.trim_end_matches('\n').len());
+    let size = if size_line == "-" {
+        None
+    } else {
+        let (rows, cols) = size_line
+            .split_once('x')
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "bad size line"))?;
+        Some((
+            rows.parse().map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "bad rows"))?,
+            cols.parse().map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "bad cols"))?,
+        ))
+    };
+
+    Ok(Preamble { binary, args, env, cwd, size })
+}
+
+/// Run the companion server, accepting one `test-ldapvi` session per TCP
+/// connection, until `addr` can't be bound. Blocks forever; run it on a
+/// dedicated thread or process.
+pub fn run_remote_server(addr: &str) -> std::io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    for stream in listener.incoming() {
+        let stream = stream?;
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(stream) {
+                eprintln!("remote session error: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Service one client connection for its whole lifetime: spawn the child,
+/// then shuttle control/stdout/stderr/keys/resize frames until the client
+/// disconnects.
+fn handle_connection(stream: TcpStream) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let preamble = read_preamble(&mut reader)?;
+    let arg_refs: Vec<&str> = preamble.args.iter().map(String::as_str).collect();
+    let env_refs: Vec<(&str, &str)> = preamble
+        .env
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+
+    let spawned = spawn_child_with_pty(
+        &preamble.binary,
+        &arg_refs,
+        &env_refs,
+        preamble.cwd.as_deref(),
+        preamble.size,
+    )?;
+
+    let downlink_writer = Arc::new(Mutex::new(stream));
+    let pty_master_fd = spawned.pty_master_fd;
+
+    // Control-fd -> TAG_CONTROL_OUT frames.
+    let control_read_fd = unsafe { libc::dup(spawned.control_read.as_raw_fd()) };
+    let control_writer_for_out = Arc::clone(&downlink_writer);
+    let control_out_thread = thread::spawn(move || {
+        let mut reader = BufReader::new(unsafe { std::fs::File::from_raw_fd(control_read_fd) });
+        loop {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    let mut w = control_writer_for_out.lock().unwrap();
+                    if write_frame(&mut *w, TAG_CONTROL_OUT, line.as_bytes()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    // PTY master -> TAG_STDOUT frames.
+    let stdout_fd = unsafe { libc::dup(pty_master_fd) };
+    let stdout_writer = Arc::clone(&downlink_writer);
+    let stdout_thread = thread::spawn(move || {
+        let mut master = unsafe { std::fs::File::from_raw_fd(stdout_fd) };
+        let mut buf = [0u8; 4096];
+        loop {
+            match master.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    let mut w = stdout_writer.lock().unwrap();
+                    if write_frame(&mut *w, TAG_STDOUT, &buf[..n]).is_err() {
+                        break;
+                    }
+                }
+                Err(e) if e.raw_os_error() == Some(libc::EIO) => break,
+                Err(_) => break,
+            }
+        }
+    });
+
+    // stderr pipe -> TAG_STDERR frames.
+    let stderr_writer = Arc::clone(&downlink_writer);
+    let mut stderr_pipe = spawned.stderr_pipe;
+    let stderr_thread = thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        loop {
+            match stderr_pipe.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let mut w = stderr_writer.lock().unwrap();
+                    if write_frame(&mut *w, TAG_STDERR, &buf[..n]).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    // child.wait() -> TAG_EXIT frame.
+    let mut child = spawned.child;
+    let child_pid = child.id() as i32;
+    let exit_writer = Arc::clone(&downlink_writer);
+    let waiter_thread = thread::spawn(move || {
+        let status = child.wait();
+        let code = status.map(|s| s.code().unwrap_or(-1)).unwrap_or(-1);
+        let mut w = exit_writer.lock().unwrap();
+        let _ = write_frame(&mut *w, TAG_EXIT, &code.to_be_bytes());
+    });
+
+    let mut control_write = spawned.control_write;
+
+    // Uplink: dispatch TAG_CONTROL_IN/TAG_KEYS/TAG_RESIZE frames from the
+    // client until it disconnects.
+    loop {
+        match read_frame(&mut reader)? {
+            None => break,
+            Some((TAG_CONTROL_IN, payload)) => {
+                control_write.write_all(&payload)?;
+                control_write.flush()?;
+            }
+            Some((TAG_KEYS, payload)) => {
+                let mut remaining = &payload[..];
+                while !remaining.is_empty() {
+                    let n = unsafe {
+                        libc::write(
+                            pty_master_fd,
+                            remaining.as_ptr() as *const libc::c_void,
+                            remaining.len(),
+                        )
+                    };
+                    if n < 0 {
+                        break;
+                    }
+                    remaining = &remaining[n as usize..];
+                }
+            }
+            Some((TAG_RESIZE, payload)) if payload.len() == 4 => {
+                let rows = u16::from_be_bytes([payload[0], payload[1]]);
+                let cols = u16::from_be_bytes([payload[2], payload[3]]);
+                let ws = nix::pty::Winsize {
+                    ws_row: rows,
+                    ws_col: cols,
+                    ws_xpixel: 0,
+                    ws_ypixel: 0,
+                };
+                unsafe {
+                    libc::ioctl(pty_master_fd, libc::TIOCSWINSZ, &ws);
+                }
+                let _ = nix::sys::signal::kill(
+                    nix::unistd::Pid::from_raw(child_pid),
+                    nix::sys::signal::Signal::SIGWINCH,
+                );
+            }
+            Some(_) => {}
+        }
+    }
+
+    unsafe {
+        libc::close(pty_master_fd);
+    }
+    let _ = control_out_thread.join();
+    let _ = stdout_thread.join();
+    let _ = stderr_thread.join();
+    let _ = waiter_thread.join();
+    Ok(())
+}
+
+/// Connect to a [`run_remote_server`] at `addr` and start a session running
+/// `binary` there, wiring up a local [`TestSession`] whose `control`/
+/// `control_reader` are backed by a socketpair bridged to the TCP
+/// connection instead of a real fd-3.
+pub(crate) fn connect(
+    addr: &str,
+    binary: &str,
+    args: &[&str],
+    env: &[(&str, &str)],
+    size: Option<(u16, u16)>,
+) -> std::io::Result<TestSession> {
+    let mut stream = TcpStream::connect(addr)?;
+    write_preamble(&mut stream, binary, args, env, None, size)?;
+
+    let (parent_sock, bridge_sock) = socketpair(
+        AddressFamily::Unix,
+        SockType::Stream,
+        None,
+        SockFlag::empty(),
+    )
+    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+
+    let parent_fd = parent_sock.into_raw_fd();
+    let control_write = unsafe { std::fs::File::from_raw_fd(parent_fd) };
+    let control_read = unsafe { std::fs::File::from_raw_fd(libc::dup(parent_fd)) };
+    let control_reader = BufReader::new(control_read);
+
+    let bridge_fd = bridge_sock.into_raw_fd();
+    let mut bridge_read = unsafe { std::fs::File::from_raw_fd(libc::dup(bridge_fd)) };
+    let mut bridge_write = unsafe { std::fs::File::from_raw_fd(bridge_fd) };
+
+    let writer = Arc::new(Mutex::new(stream.try_clone()?));
+    let exit_code = Arc::new(Mutex::new(None));
+
+    // Uplink: whatever test code writes into `control_write` (the other
+    // end of this same socketpair) shows up here, ready to forward as
+    // TAG_CONTROL_IN. Intentionally not joined in `wait_exit` -- this
+    // reads a purely local pipe, not the TCP connection, so it has no
+    // way to observe the session ending except the process exiting or
+    // `bridge_write`'s other handle (held by the downlink thread) closing;
+    // the traffic it forwards is a handful of CHOSE/EDITED/VIEWED lines,
+    // not something that needs a clean join to avoid leaking anything
+    // that matters for a test run.
+    let uplink_writer = Arc::clone(&writer);
+    thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        loop {
+            match bridge_read.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let mut w = uplink_writer.lock().unwrap();
+                    if write_frame(&mut *w, TAG_CONTROL_IN, &buf[..n]).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    // Downlink: demux frames from the server into the bridge socket
+    // (control) or the local capture buffers (stdout/stderr/exit).
+    let stdout_capture = Arc::new(Mutex::new(Vec::new()));
+    let stderr_capture = Arc::new(Mutex::new(Vec::new()));
+    let stdout_cap = Arc::clone(&stdout_capture);
+    let stderr_cap = Arc::clone(&stderr_capture);
+    let exit_code_for_downlink = Arc::clone(&exit_code);
+    let mut downlink_reader = BufReader::new(stream);
+    let downlink_thread = thread::spawn(move || loop {
+        match read_frame(&mut downlink_reader) {
+            Ok(None) | Err(_) => break,
+            Ok(Some((TAG_CONTROL_OUT, payload))) => {
+                if bridge_write.write_all(&payload).is_err() {
+                    break;
+                }
+                let _ = bridge_write.flush();
+            }
+            Ok(Some((TAG_STDOUT, payload))) => {
+                stdout_cap.lock().unwrap().extend_from_slice(&payload);
+            }
+            Ok(Some((TAG_STDERR, payload))) => {
+                stderr_cap.lock().unwrap().extend_from_slice(&payload);
+            }
+            Ok(Some((TAG_EXIT, payload))) if payload.len() == 4 => {
+                let code = i32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                *exit_code_for_downlink.lock().unwrap() = Some(code);
+            }
+            Ok(Some(_)) => {}
+        }
+    });
+
+    Ok(TestSession::from_parts(
+        Backend::Remote { writer, exit_code },
+        control_write,
+        control_reader,
+        stdout_capture,
+        stderr_capture,
+        vec![downlink_thread],
+    ))
+}
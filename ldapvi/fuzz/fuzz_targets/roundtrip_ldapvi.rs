@@ -0,0 +1,7 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    ldapvi::fuzzing::fuzz_roundtrip_ldapvi(data);
+});
@@ -1,10 +1,14 @@
+use std::collections::BTreeMap;
 use std::fs;
-use std::io::{Cursor, Read, Write};
+use std::io::{Cursor, IsTerminal, Read, Write};
+use std::time::Instant;
 
 use ldap3::LdapConn;
-use ldapvi::data::{Entry, LdapMod};
-use ldapvi::diff::{self, DiffHandler};
+use ldapvi::data::{Entry, LdapMod, ModOp};
+use ldapvi::diff::{self, AsyncDiffHandler, DiffError, DiffHandler, DiffResult, OpResult, SyncDiffHandler};
+use ldapvi::ldapfilter;
 use ldapvi::parse::LdapviParser;
+use ldapvi::pipeline;
 use ldapvi::print::{self, BinaryMode};
 use ldapvi::schema::{Entroid, Schema};
 
@@ -13,15 +17,30 @@ use crate::interactive;
 use crate::ldap;
 
 // ===========================================================================
-// DiffHandler implementations
+// SyncDiffHandler implementations
 // ===========================================================================
 
-/// Counts add/delete/modify/rename operations.
+/// Per-attribute tally of how many values are being added/deleted/replaced.
+#[derive(Default)]
+struct AttrStats {
+    added: i32,
+    deleted: i32,
+    replaced: i32,
+}
+
+/// Counts add/delete/modify/rename operations, and -- in the spirit of
+/// grouping low-level operations into per-target summaries -- breaks that
+/// down further into per-attribute value counts, which objectClasses are
+/// being created or removed, and the total bytes of values being written.
 struct StatisticsHandler {
     adds: i32,
     deletes: i32,
     modifies: i32,
     renames: i32,
+    attrs: BTreeMap<String, AttrStats>,
+    object_classes_added: BTreeMap<String, i32>,
+    object_classes_removed: BTreeMap<String, i32>,
+    bytes_written: u64,
 }
 
 impl StatisticsHandler {
@@ -31,6 +50,10 @@ impl StatisticsHandler {
             deletes: 0,
             modifies: 0,
             renames: 0,
+            attrs: BTreeMap::new(),
+            object_classes_added: BTreeMap::new(),
+            object_classes_removed: BTreeMap::new(),
+            bytes_written: 0,
         }
     }
 
@@ -38,6 +61,41 @@ impl StatisticsHandler {
         self.adds + self.deletes + self.modifies + self.renames
     }
 
+    /// Fold a batch of `LdapMod`s into the per-attribute and per-objectClass
+    /// tallies. `mods` from `handle_add` use `ModOp::Add` throughout; `mods`
+    /// from `handle_change` mix add/delete/replace as produced by the diff.
+    fn tally_mods(&mut self, mods: &[LdapMod]) {
+        for m in mods {
+            let stats = self.attrs.entry(m.attr.clone()).or_default();
+            let value_bytes: u64 = m.values.iter().map(|v| v.len() as u64).sum();
+
+            match m.op {
+                ModOp::Add => {
+                    stats.added += m.values.len() as i32;
+                    self.bytes_written += value_bytes;
+                }
+                ModOp::Delete => {
+                    stats.deleted += m.values.len() as i32;
+                }
+                ModOp::Replace => {
+                    stats.replaced += m.values.len() as i32;
+                    self.bytes_written += value_bytes;
+                }
+            }
+
+            if m.attr.eq_ignore_ascii_case("objectClass") {
+                let bucket = match m.op {
+                    ModOp::Delete => &mut self.object_classes_removed,
+                    _ => &mut self.object_classes_added,
+                };
+                for v in &m.values {
+                    let oc = String::from_utf8_lossy(v).into_owned();
+                    *bucket.entry(oc).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
     fn print_summary(&self) {
         fn counter(label: &str, n: i32, color: &str) -> String {
             if n > 0 {
@@ -54,24 +112,64 @@ impl StatisticsHandler {
             counter("delete", self.deletes, "1;31"),
         );
     }
+
+    /// Print the detailed per-attribute/per-objectClass breakdown used by
+    /// the interactive `d` action, so a reviewer can spot e.g. that most of
+    /// a large changeset touches a single attribute before committing it.
+    fn print_details(&self) {
+        eprintln!(
+            "{} entries changed, {} bytes of attribute values written",
+            self.total(),
+            self.bytes_written
+        );
+
+        if self.attrs.is_empty() {
+            eprintln!("  (no attribute values affected)");
+        } else {
+            eprintln!("By attribute:");
+            for (attr, stats) in &self.attrs {
+                eprintln!(
+                    "  {}: +{} -{} ~{}",
+                    attr, stats.added, stats.deleted, stats.replaced
+                );
+            }
+        }
+
+        if !self.object_classes_added.is_empty() {
+            eprintln!("objectClasses created:");
+            for (oc, n) in &self.object_classes_added {
+                eprintln!("  {} ({})", oc, n);
+            }
+        }
+        if !self.object_classes_removed.is_empty() {
+            eprintln!("objectClasses removed:");
+            for (oc, n) in &self.object_classes_removed {
+                eprintln!("  {} ({})", oc, n);
+            }
+        }
+    }
 }
 
-impl DiffHandler for StatisticsHandler {
-    fn handle_add(&mut self, _n: i32, _dn: &str, _mods: &[LdapMod]) -> i32 {
+impl DiffHandler for StatisticsHandler {}
+
+impl SyncDiffHandler for StatisticsHandler {
+    fn handle_add(&mut self, _n: i32, _dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
         self.adds += 1;
-        0
+        self.tally_mods(mods);
+        Ok(())
     }
-    fn handle_delete(&mut self, _n: i32, _dn: &str) -> i32 {
+    fn handle_delete(&mut self, _n: i32, _dn: &str) -> DiffResult<()> {
         self.deletes += 1;
-        0
+        Ok(())
     }
-    fn handle_change(&mut self, _n: i32, _old_dn: &str, _new_dn: &str, _mods: &[LdapMod]) -> i32 {
+    fn handle_change(&mut self, _n: i32, _old_dn: &str, _new_dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
         self.modifies += 1;
-        0
+        self.tally_mods(mods);
+        Ok(())
     }
-    fn handle_rename(&mut self, _n: i32, _old_dn: &str, _entry: &Entry) -> i32 {
+    fn handle_rename(&mut self, _n: i32, _old_dn: &str, _entry: &Entry) -> DiffResult<()> {
         self.renames += 1;
-        0
+        Ok(())
     }
     fn handle_rename0(
         &mut self,
@@ -79,76 +177,340 @@ impl DiffHandler for StatisticsHandler {
         _old_dn: &str,
         _new_dn: &str,
         _deleteoldrdn: bool,
-    ) -> i32 {
+    ) -> DiffResult<()> {
         self.renames += 1;
-        0
+        Ok(())
+    }
+}
+
+/// Live "N/total processed" counter for a commit, printed to stderr a few
+/// times a second. A no-op when stderr isn't a terminal, so piped or
+/// backgrounded runs (and non-TTY logs) don't get a line per operation.
+struct CommitProgress {
+    total: usize,
+    done: usize,
+    enabled: bool,
+    last_print: Option<Instant>,
+}
+
+impl CommitProgress {
+    const MIN_INTERVAL_MS: u128 = 200;
+
+    fn new(total: usize) -> Self {
+        CommitProgress {
+            total,
+            done: 0,
+            enabled: std::io::stderr().is_terminal(),
+            last_print: None,
+        }
+    }
+
+    fn tick(&mut self) {
+        self.done += 1;
+        if !self.enabled {
+            return;
+        }
+        let now = Instant::now();
+        let due = self
+            .last_print
+            .is_none_or(|t| now.duration_since(t).as_millis() >= Self::MIN_INTERVAL_MS);
+        if due || self.done >= self.total {
+            eprint!("\r{}/{} processed", self.done, self.total);
+            let _ = std::io::stderr().flush();
+            self.last_print = Some(now);
+        }
+    }
+
+    fn finish(&self) {
+        if self.enabled {
+            eprintln!();
+        }
+    }
+}
+
+/// Pre-scan the changerecords to find the total operation count, so
+/// [`CommitProgress`] can show "N of total" rather than just a running
+/// count.
+fn prescan_total(clean_data: &[u8], data_data: &[u8], offsets: &[i64]) -> usize {
+    let mut clean_parser = LdapviParser::new(Cursor::new(clean_data));
+    let mut data_parser = LdapviParser::new(Cursor::new(data_data));
+    let mut stats = StatisticsHandler::new();
+    let mut offsets = offsets.to_vec();
+    let _ = diff::compare_streams(
+        &mut clean_parser,
+        &mut data_parser,
+        &mut stats,
+        &mut offsets,
+        diff::DiffMode::Replace,
+        &diff::Comparator::new(),
+        &mut diff::NullObserver,
+        &diff::CommitPolicy::strict(),
+    );
+    stats.total().max(0) as usize
+}
+
+/// Prints the operations a commit would perform -- one summary line per
+/// entry -- without touching the server, for `--dry-run`.
+struct DryRunHandler {
+    stats: StatisticsHandler,
+}
+
+impl DryRunHandler {
+    fn new() -> Self {
+        DryRunHandler {
+            stats: StatisticsHandler::new(),
+        }
+    }
+}
+
+impl DiffHandler for DryRunHandler {}
+
+impl SyncDiffHandler for DryRunHandler {
+    fn handle_add(&mut self, n: i32, dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
+        println!("would add {}", dn);
+        self.stats.handle_add(n, dn, mods)
+    }
+    fn handle_delete(&mut self, n: i32, dn: &str) -> DiffResult<()> {
+        println!("would delete {}", dn);
+        self.stats.handle_delete(n, dn)
+    }
+    fn handle_change(&mut self, n: i32, old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
+        println!("would modify {} ({} attribute changes)", new_dn, mods.len());
+        self.stats.handle_change(n, old_dn, new_dn, mods)
+    }
+    fn handle_rename(&mut self, n: i32, old_dn: &str, entry: &Entry) -> DiffResult<()> {
+        println!("would rename {} -> {}", old_dn, entry.dn);
+        self.stats.handle_rename(n, old_dn, entry)
+    }
+    fn handle_rename0(&mut self, n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) -> DiffResult<()> {
+        println!("would rename {} -> {}", old_dn, new_dn);
+        self.stats.handle_rename0(n, old_dn, new_dn, deleteoldrdn)
+    }
+}
+
+/// The kind of LDAP operation a `CommitError` was produced by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpKind {
+    Add,
+    Delete,
+    Modify,
+    Rename,
+}
+
+impl std::fmt::Display for OpKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            OpKind::Add => "add",
+            OpKind::Delete => "delete",
+            OpKind::Modify => "modify",
+            OpKind::Rename => "rename",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A single failed operation encountered while committing changes,
+/// recorded instead of being flattened into an opaque string.
+#[derive(Debug, Clone)]
+pub struct CommitError {
+    pub entry_index: i32,
+    pub dn: String,
+    pub op: OpKind,
+    pub result_code: Option<u32>,
+    pub diagnostic: String,
+}
+
+/// Print a human-readable end-of-commit report listing every failed entry.
+fn print_commit_report_text(errors: &[CommitError], out: &mut dyn Write) {
+    let _ = writeln!(out, "{} entries failed to commit:", errors.len());
+    for e in errors {
+        let _ = match e.result_code {
+            Some(rc) => writeln!(
+                out,
+                "  [{}] {} {}: result code {} ({})",
+                e.entry_index, e.op, e.dn, rc, e.diagnostic
+            ),
+            None => writeln!(
+                out,
+                "  [{}] {} {}: {}",
+                e.entry_index, e.op, e.dn, e.diagnostic
+            ),
+        };
+    }
+}
+
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Emit the commit report as a JSON array, for scripting.
+fn print_commit_report_json(errors: &[CommitError], out: &mut dyn Write) {
+    let _ = write!(out, "[");
+    for (i, e) in errors.iter().enumerate() {
+        if i > 0 {
+            let _ = write!(out, ",");
+        }
+        let rc = match e.result_code {
+            Some(rc) => rc.to_string(),
+            None => "null".to_string(),
+        };
+        let _ = write!(
+            out,
+            "{{\"entry_index\":{},\"dn\":\"{}\",\"op\":\"{}\",\"result_code\":{},\"diagnostic\":\"{}\"}}",
+            e.entry_index,
+            json_escape(&e.dn),
+            e.op,
+            rc,
+            json_escape(&e.diagnostic)
+        );
+    }
+    let _ = writeln!(out, "]");
+}
+
+/// Print an end-of-commit report. `json` selects machine-readable output
+/// for scripting instead of the human-readable listing.
+pub fn print_commit_report(errors: &[CommitError], json: bool, out: &mut dyn Write) {
+    if json {
+        print_commit_report_json(errors, out);
+    } else {
+        print_commit_report_text(errors, out);
+    }
+}
+
+/// Accounting for a continuous-operation batch (`--in`/`--continue` or
+/// `--delete`): how many records were attempted, and which of them
+/// failed. Both batch modes build one of these so they can share a single
+/// summary-and-exit-code path, mirroring `ldapmodify -c`, which keeps
+/// applying records after a failure but still exits nonzero overall.
+pub struct ApplyReport {
+    pub attempted: usize,
+    pub failed: Vec<CommitError>,
+}
+
+impl ApplyReport {
+    fn new(attempted: usize) -> Self {
+        ApplyReport {
+            attempted,
+            failed: Vec::new(),
+        }
+    }
+
+    fn succeeded(&self) -> usize {
+        self.attempted - self.failed.len()
+    }
+
+    /// One-line attempted/succeeded/failed summary, printed under `--verbose`.
+    fn print_summary(&self, out: &mut dyn Write) {
+        let _ = writeln!(
+            out,
+            "{} of {} records applied successfully, {} failed.",
+            self.succeeded(),
+            self.attempted,
+            self.failed.len()
+        );
     }
 }
 
 /// Commits changes to the LDAP server.
 struct LdapCommitHandler<'a> {
     ldap: &'a mut LdapConn,
+    cmdline: &'a Cmdline,
     continuous: bool,
-    errors: Vec<String>,
+    errors: Vec<CommitError>,
+    progress: CommitProgress,
 }
 
 impl<'a> LdapCommitHandler<'a> {
-    fn new(ldap: &'a mut LdapConn, continuous: bool) -> Self {
+    fn new(ldap: &'a mut LdapConn, cmdline: &'a Cmdline, continuous: bool, total: usize) -> Self {
         LdapCommitHandler {
             ldap,
+            cmdline,
             continuous,
             errors: Vec::new(),
+            progress: CommitProgress::new(total),
+        }
+    }
+
+    /// Run a single LDAP operation, transparently reconnecting (rotating
+    /// through `cmdline.servers`) and retrying if it fails with a
+    /// connection-level error.
+    fn run_op(
+        &mut self,
+        op: impl Fn(&mut LdapConn) -> Result<(), ldap::LdapOpError>,
+    ) -> Result<(), ldap::LdapOpError> {
+        let result = ldap::with_reconnect(self.ldap, self.cmdline, |ldap| {
+            op(ldap).map_err(|e| e.to_string())
+        })
+        .map_err(|message| ldap::LdapOpError {
+            message,
+            result_code: None,
+        });
+        self.progress.tick();
+        result
+    }
+
+    fn record(&mut self, n: i32, dn: &str, op: OpKind, e: ldap::LdapOpError) -> DiffResult<()> {
+        eprintln!("ldapvi: {}", e);
+        let result_code = e.result_code;
+        self.errors.push(CommitError {
+            entry_index: n,
+            dn: dn.to_string(),
+            op,
+            result_code: e.result_code,
+            diagnostic: e.message,
+        });
+        if self.continuous {
+            Ok(())
+        } else {
+            Err(DiffError::HandlerRejected {
+                n,
+                dn: dn.to_string(),
+                code: result_code.map(|c| c as i32).unwrap_or(-1),
+            })
         }
     }
 }
 
-impl DiffHandler for LdapCommitHandler<'_> {
-    fn handle_add(&mut self, _n: i32, dn: &str, mods: &[LdapMod]) -> i32 {
-        match ldap::ldap_add(self.ldap, dn, mods) {
-            Ok(()) => 0,
-            Err(e) => {
-                eprintln!("ldapvi: {}", e);
-                self.errors.push(e);
-                if self.continuous {
-                    0
-                } else {
-                    -1
-                }
-            }
+impl DiffHandler for LdapCommitHandler<'_> {}
+
+impl SyncDiffHandler for LdapCommitHandler<'_> {
+    fn handle_add(&mut self, n: i32, dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
+        let cmdline = self.cmdline;
+        match self.run_op(|ldap| ldap::ldap_add(ldap, dn, mods, cmdline)) {
+            Ok(()) => Ok(()),
+            Err(e) => self.record(n, dn, OpKind::Add, e),
         }
     }
 
-    fn handle_delete(&mut self, _n: i32, dn: &str) -> i32 {
-        match ldap::ldap_delete(self.ldap, dn) {
-            Ok(()) => 0,
-            Err(e) => {
-                eprintln!("ldapvi: {}", e);
-                self.errors.push(e);
-                if self.continuous {
-                    0
-                } else {
-                    -1
-                }
-            }
+    fn handle_delete(&mut self, n: i32, dn: &str) -> DiffResult<()> {
+        let cmdline = self.cmdline;
+        match self.run_op(|ldap| ldap::ldap_delete(ldap, dn, cmdline)) {
+            Ok(()) => Ok(()),
+            Err(e) => self.record(n, dn, OpKind::Delete, e),
         }
     }
 
-    fn handle_change(&mut self, _n: i32, _old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> i32 {
-        match ldap::ldap_modify(self.ldap, new_dn, mods) {
-            Ok(()) => 0,
-            Err(e) => {
-                eprintln!("ldapvi: {}", e);
-                self.errors.push(e);
-                if self.continuous {
-                    0
-                } else {
-                    -1
-                }
-            }
+    fn handle_change(&mut self, n: i32, _old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
+        let cmdline = self.cmdline;
+        match self.run_op(|ldap| ldap::ldap_modify(ldap, new_dn, mods, cmdline)) {
+            Ok(()) => Ok(()),
+            Err(e) => self.record(n, new_dn, OpKind::Modify, e),
         }
     }
 
-    fn handle_rename(&mut self, _n: i32, old_dn: &str, entry: &Entry) -> i32 {
+    fn handle_rename(&mut self, n: i32, old_dn: &str, entry: &Entry) -> DiffResult<()> {
         let new_dn = &entry.dn;
         // Extract new RDN from new DN
         let new_rdn = first_rdn(new_dn);
@@ -171,28 +533,21 @@ impl DiffHandler for LdapCommitHandler<'_> {
             let mut clean_clone = Entry::new(old_dn.to_string());
             let mut data_clone = entry.clone();
             // Populate clean_clone with the RDN values
-            diff::frob_rdn(&mut clean_clone, old_dn, diff::FrobMode::Add);
+            let _ = diff::frob_rdn(&mut clean_clone, &diff::Comparator::new(), old_dn, diff::FrobMode::Add);
             let mut dor = false;
-            if diff::validate_rename(&mut clean_clone, &mut data_clone, &mut dor) == 0 {
+            if diff::validate_rename(&mut clean_clone, &mut data_clone, &diff::Comparator::new(), &mut dor).is_ok() {
                 deleteoldrdn = dor;
             }
         }
 
-        match ldap::ldap_rename(self.ldap, old_dn, new_rdn, new_superior, deleteoldrdn) {
-            Ok(()) => 0,
-            Err(e) => {
-                eprintln!("ldapvi: {}", e);
-                self.errors.push(e);
-                if self.continuous {
-                    0
-                } else {
-                    -1
-                }
-            }
+        let cmdline = self.cmdline;
+        match self.run_op(|ldap| ldap::ldap_rename(ldap, old_dn, new_rdn, new_superior, deleteoldrdn, cmdline)) {
+            Ok(()) => Ok(()),
+            Err(e) => self.record(n, old_dn, OpKind::Rename, e),
         }
     }
 
-    fn handle_rename0(&mut self, _n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) -> i32 {
+    fn handle_rename0(&mut self, n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) -> DiffResult<()> {
         let new_rdn = first_rdn(new_dn);
         let old_parent = parent_dn(old_dn);
         let new_parent = parent_dn(new_dn);
@@ -202,18 +557,140 @@ impl DiffHandler for LdapCommitHandler<'_> {
             None
         };
 
-        match ldap::ldap_rename(self.ldap, old_dn, new_rdn, new_superior, deleteoldrdn) {
-            Ok(()) => 0,
-            Err(e) => {
+        let cmdline = self.cmdline;
+        match self.run_op(|ldap| ldap::ldap_rename(ldap, old_dn, new_rdn, new_superior, deleteoldrdn, cmdline)) {
+            Ok(()) => Ok(()),
+            Err(e) => self.record(n, old_dn, OpKind::Rename, e),
+        }
+    }
+}
+
+/// Commits changes over a [`ldap::PipelineSession`] instead of one blocking
+/// round-trip at a time: queues each call as a `pipeline::Operation` via
+/// [`AsyncDiffHandler`] and dispatches the queue concurrently in `flush`,
+/// which `compare_streams` calls at the same two boundaries
+/// `LdapCommitHandler` would otherwise block on individually (after the
+/// add/rename/change pass, and again after deletions).
+struct PipelinedCommitHandler<'a> {
+    session: &'a ldap::PipelineSession,
+    queued: Vec<pipeline::Operation>,
+    errors: Vec<CommitError>,
+    progress: CommitProgress,
+}
+
+impl<'a> PipelinedCommitHandler<'a> {
+    fn new(session: &'a ldap::PipelineSession, total: usize) -> Self {
+        PipelinedCommitHandler {
+            session,
+            queued: Vec::new(),
+            errors: Vec::new(),
+            progress: CommitProgress::new(total),
+        }
+    }
+}
+
+impl DiffHandler for PipelinedCommitHandler<'_> {
+    fn flush(&mut self) -> Vec<OpResult> {
+        let ops = std::mem::take(&mut self.queued);
+        if ops.is_empty() {
+            return Vec::new();
+        }
+        let results = self.session.dispatch(ops.clone());
+        let mut op_results = Vec::with_capacity(ops.len());
+        for (op, result) in ops.into_iter().zip(results) {
+            self.progress.tick();
+            let success = result.is_ok();
+            if let Err(e) = result {
                 eprintln!("ldapvi: {}", e);
-                self.errors.push(e);
-                if self.continuous {
-                    0
-                } else {
-                    -1
-                }
+                self.errors.push(CommitError {
+                    entry_index: op.entry_index,
+                    dn: op.dn,
+                    op: match op.kind {
+                        pipeline::OpKind::Add => OpKind::Add,
+                        pipeline::OpKind::Delete => OpKind::Delete,
+                        pipeline::OpKind::Modify => OpKind::Modify,
+                        pipeline::OpKind::Rename => OpKind::Rename,
+                    },
+                    result_code: e.result_code,
+                    diagnostic: e.message,
+                });
             }
+            op_results.push(OpResult { n: op.entry_index, success });
         }
+        op_results
+    }
+}
+
+impl SyncDiffHandler for PipelinedCommitHandler<'_> {
+    fn handle_add(&mut self, n: i32, dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
+        self.enqueue_add(n, dn, mods);
+        Ok(())
+    }
+
+    fn handle_delete(&mut self, n: i32, dn: &str) -> DiffResult<()> {
+        self.enqueue_delete(n, dn);
+        Ok(())
+    }
+
+    fn handle_change(&mut self, n: i32, old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
+        self.enqueue_change(n, old_dn, new_dn, mods);
+        Ok(())
+    }
+
+    fn handle_rename(&mut self, n: i32, old_dn: &str, entry: &Entry) -> DiffResult<()> {
+        self.enqueue_rename0(n, old_dn, &entry.dn, false);
+        Ok(())
+    }
+
+    fn handle_rename0(&mut self, n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) -> DiffResult<()> {
+        self.enqueue_rename0(n, old_dn, new_dn, deleteoldrdn);
+        Ok(())
+    }
+}
+
+impl AsyncDiffHandler for PipelinedCommitHandler<'_> {
+    fn enqueue_add(&mut self, n: i32, dn: &str, mods: &[LdapMod]) {
+        self.queued.push(pipeline::Operation {
+            entry_index: n,
+            kind: pipeline::OpKind::Add,
+            dn: dn.to_string(),
+            new_dn: None,
+            mods: mods.to_vec(),
+            delete_old_rdn: false,
+        });
+    }
+
+    fn enqueue_delete(&mut self, n: i32, dn: &str) {
+        self.queued.push(pipeline::Operation {
+            entry_index: n,
+            kind: pipeline::OpKind::Delete,
+            dn: dn.to_string(),
+            new_dn: None,
+            mods: Vec::new(),
+            delete_old_rdn: false,
+        });
+    }
+
+    fn enqueue_change(&mut self, n: i32, _old_dn: &str, new_dn: &str, mods: &[LdapMod]) {
+        self.queued.push(pipeline::Operation {
+            entry_index: n,
+            kind: pipeline::OpKind::Modify,
+            dn: new_dn.to_string(),
+            new_dn: None,
+            mods: mods.to_vec(),
+            delete_old_rdn: false,
+        });
+    }
+
+    fn enqueue_rename0(&mut self, n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) {
+        self.queued.push(pipeline::Operation {
+            entry_index: n,
+            kind: pipeline::OpKind::Rename,
+            dn: old_dn.to_string(),
+            new_dn: Some(new_dn.to_string()),
+            mods: Vec::new(),
+            delete_old_rdn: deleteoldrdn,
+        });
     }
 }
 
@@ -222,32 +699,34 @@ struct LdifPrintHandler<'a> {
     w: &'a mut dyn Write,
 }
 
-impl DiffHandler for LdifPrintHandler<'_> {
-    fn handle_add(&mut self, _n: i32, dn: &str, mods: &[LdapMod]) -> i32 {
-        let _ = print::print_ldif_add(self.w, dn, mods);
-        0
+impl DiffHandler for LdifPrintHandler<'_> {}
+
+impl SyncDiffHandler for LdifPrintHandler<'_> {
+    fn handle_add(&mut self, _n: i32, dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
+        let _ = print::print_ldif_add(self.w, dn, mods, print::DEFAULT_LDIF_WIDTH);
+        Ok(())
     }
-    fn handle_delete(&mut self, _n: i32, dn: &str) -> i32 {
-        let _ = print::print_ldif_delete(self.w, dn);
-        0
+    fn handle_delete(&mut self, _n: i32, dn: &str) -> DiffResult<()> {
+        let _ = print::print_ldif_delete(self.w, dn, print::DEFAULT_LDIF_WIDTH);
+        Ok(())
     }
-    fn handle_change(&mut self, _n: i32, _old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> i32 {
-        let _ = print::print_ldif_modify(self.w, new_dn, mods);
-        0
+    fn handle_change(&mut self, _n: i32, _old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
+        let _ = print::print_ldif_modify(self.w, new_dn, mods, print::DEFAULT_LDIF_WIDTH);
+        Ok(())
     }
-    fn handle_rename(&mut self, _n: i32, old_dn: &str, entry: &Entry) -> i32 {
+    fn handle_rename(&mut self, _n: i32, old_dn: &str, entry: &Entry) -> DiffResult<()> {
         // Determine deleteoldrdn
         let mut clean_clone = Entry::new(old_dn.to_string());
         let mut data_clone = entry.clone();
-        diff::frob_rdn(&mut clean_clone, old_dn, diff::FrobMode::Add);
+        let _ = diff::frob_rdn(&mut clean_clone, &diff::Comparator::new(), old_dn, diff::FrobMode::Add);
         let mut deleteoldrdn = false;
-        let _ = diff::validate_rename(&mut clean_clone, &mut data_clone, &mut deleteoldrdn);
-        let _ = print::print_ldif_rename(self.w, old_dn, &entry.dn, deleteoldrdn);
-        0
+        let _ = diff::validate_rename(&mut clean_clone, &mut data_clone, &diff::Comparator::new(), &mut deleteoldrdn);
+        let _ = print::print_ldif_rename(self.w, old_dn, &entry.dn, deleteoldrdn, print::DEFAULT_LDIF_WIDTH);
+        Ok(())
     }
-    fn handle_rename0(&mut self, _n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) -> i32 {
-        let _ = print::print_ldif_rename(self.w, old_dn, new_dn, deleteoldrdn);
-        0
+    fn handle_rename0(&mut self, _n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) -> DiffResult<()> {
+        let _ = print::print_ldif_rename(self.w, old_dn, new_dn, deleteoldrdn, print::DEFAULT_LDIF_WIDTH);
+        Ok(())
     }
 }
 
@@ -257,31 +736,33 @@ struct VdifPrintHandler<'a> {
     mode: BinaryMode,
 }
 
-impl DiffHandler for VdifPrintHandler<'_> {
-    fn handle_add(&mut self, _n: i32, dn: &str, mods: &[LdapMod]) -> i32 {
+impl DiffHandler for VdifPrintHandler<'_> {}
+
+impl SyncDiffHandler for VdifPrintHandler<'_> {
+    fn handle_add(&mut self, _n: i32, dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
         let _ = print::print_ldapvi_add(self.w, dn, mods, self.mode);
-        0
+        Ok(())
     }
-    fn handle_delete(&mut self, _n: i32, dn: &str) -> i32 {
+    fn handle_delete(&mut self, _n: i32, dn: &str) -> DiffResult<()> {
         let _ = print::print_ldapvi_delete(self.w, dn, self.mode);
-        0
+        Ok(())
     }
-    fn handle_change(&mut self, _n: i32, _old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> i32 {
+    fn handle_change(&mut self, _n: i32, _old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
         let _ = print::print_ldapvi_modify(self.w, new_dn, mods, self.mode);
-        0
+        Ok(())
     }
-    fn handle_rename(&mut self, _n: i32, old_dn: &str, entry: &Entry) -> i32 {
+    fn handle_rename(&mut self, _n: i32, old_dn: &str, entry: &Entry) -> DiffResult<()> {
         let mut clean_clone = Entry::new(old_dn.to_string());
         let mut data_clone = entry.clone();
-        diff::frob_rdn(&mut clean_clone, old_dn, diff::FrobMode::Add);
+        let _ = diff::frob_rdn(&mut clean_clone, &diff::Comparator::new(), old_dn, diff::FrobMode::Add);
         let mut deleteoldrdn = false;
-        let _ = diff::validate_rename(&mut clean_clone, &mut data_clone, &mut deleteoldrdn);
+        let _ = diff::validate_rename(&mut clean_clone, &mut data_clone, &diff::Comparator::new(), &mut deleteoldrdn);
         let _ = print::print_ldapvi_rename(self.w, old_dn, &entry.dn, deleteoldrdn, self.mode);
-        0
+        Ok(())
     }
-    fn handle_rename0(&mut self, _n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) -> i32 {
+    fn handle_rename0(&mut self, _n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) -> DiffResult<()> {
         let _ = print::print_ldapvi_rename(self.w, old_dn, new_dn, deleteoldrdn, self.mode);
-        0
+        Ok(())
     }
 }
 
@@ -321,30 +802,70 @@ enum AnalysisResult {
     NoChanges,
     Changes(StatisticsHandler),
     ParseError(u64),
+    /// The data file's entry keys don't account for every searched entry --
+    /// a duplicate, out-of-range, or silently-dropped `ldapvi-key` line. See
+    /// `diff::check_key_structure`.
+    StructuralError(String),
 }
 
-fn analyze_changes(clean_data: &[u8], data_data: &[u8], offsets: &[i64]) -> AnalysisResult {
+/// Build the `Comparator` a diff pass over `clean_data`/`offsets` should use:
+/// plain under the default behavior, or carrying a fresh `entryUUID` index
+/// when `cmdline.track_uuid` asked for identity tracking, and/or generating
+/// a missing `entryUUID` on new adds when `generate_entryuuid` is set. The
+/// caller computes `generate_entryuuid` once per session (it requires a
+/// root DSE round-trip) rather than this function doing it itself -- see
+/// `do_edit`/`do_in`.
+fn comparator_for(cmdline: &Cmdline, clean_data: &[u8], offsets: &[i64], generate_entryuuid: bool) -> diff::Comparator {
+    let cmp = diff::Comparator::new().with_entryuuid_generation(generate_entryuuid);
+    if !cmdline.track_uuid {
+        return cmp;
+    }
+    let mut clean_parser = LdapviParser::new(Cursor::new(clean_data));
+    cmp.with_uuid_index(diff::build_uuid_index(&mut clean_parser, offsets))
+}
+
+fn analyze_changes(
+    cmdline: &Cmdline,
+    clean_data: &[u8],
+    data_data: &[u8],
+    offsets: &[i64],
+    generate_entryuuid: bool,
+) -> AnalysisResult {
+    let mut clean_parser = LdapviParser::new(Cursor::new(clean_data));
+    let mut data_parser = LdapviParser::new(Cursor::new(data_data));
+
+    if let Err(DiffError::StructuralMismatch { message }) =
+        diff::check_key_structure(&mut clean_parser, &mut data_parser, offsets)
+    {
+        return AnalysisResult::StructuralError(message);
+    }
+
     let mut clean_parser = LdapviParser::new(Cursor::new(clean_data));
     let mut data_parser = LdapviParser::new(Cursor::new(data_data));
     let mut stats = StatisticsHandler::new();
     let mut offsets = offsets.to_vec();
+    let cmp = comparator_for(cmdline, clean_data, &offsets, generate_entryuuid);
 
-    let rc = diff::compare_streams(
+    let result = diff::compare_streams(
         &mut clean_parser,
         &mut data_parser,
         &mut stats,
         &mut offsets,
+        diff::DiffMode::Replace,
+        &cmp,
+        &mut diff::NullObserver,
+        &diff::CommitPolicy::strict(),
     );
 
-    match rc {
-        0 => {
+    match result {
+        Ok(_) => {
             if stats.total() == 0 {
                 AnalysisResult::NoChanges
             } else {
                 AnalysisResult::Changes(stats)
             }
         }
-        _ => {
+        Err(_) => {
             // Get error position from data parser
             let pos = data_parser.stream_position().unwrap_or(0);
             AnalysisResult::ParseError(pos)
@@ -352,55 +873,191 @@ fn analyze_changes(clean_data: &[u8], data_data: &[u8], offsets: &[i64]) -> Anal
     }
 }
 
+/// Outcome of a failed `commit_changes` call.
+enum CommitFailure {
+    /// The data file itself didn't parse; no operations were attempted.
+    Parse,
+    /// One or more per-entry operations failed on the server.
+    Entries(Vec<CommitError>),
+}
+
+#[allow(clippy::too_many_arguments)]
 fn commit_changes(
     ldap: &mut LdapConn,
+    cmdline: &Cmdline,
     clean_data: &[u8],
     data_data: &[u8],
     offsets: &[i64],
     continuous: bool,
-) -> Result<(), String> {
+    generate_entryuuid: bool,
+) -> Result<ApplyReport, CommitFailure> {
+    let total = prescan_total(clean_data, data_data, offsets);
+
     let mut clean_parser = LdapviParser::new(Cursor::new(clean_data));
     let mut data_parser = LdapviParser::new(Cursor::new(data_data));
-    let mut handler = LdapCommitHandler::new(ldap, continuous);
+    let mut handler = LdapCommitHandler::new(ldap, cmdline, continuous, total);
     let mut offsets = offsets.to_vec();
+    let cmp = comparator_for(cmdline, clean_data, &offsets, generate_entryuuid);
 
-    let rc = diff::compare_streams(
+    let result = diff::compare_streams(
         &mut clean_parser,
         &mut data_parser,
         &mut handler,
         &mut offsets,
+        diff::DiffMode::Replace,
+        &cmp,
+        &mut diff::NullObserver,
+        &diff::CommitPolicy::strict(),
     );
+    handler.progress.finish();
+
+    match result {
+        Err(DiffError::HandlerRejected { .. }) | Ok(_) => Ok(ApplyReport {
+            attempted: handler.progress.done,
+            failed: handler.errors,
+        }),
+        Err(_) => Err(CommitFailure::Parse),
+    }
+}
 
-    if rc == -2 || !handler.errors.is_empty() {
-        Err(handler.errors.join("; "))
-    } else if rc == -1 {
-        Err("parse error during commit".to_string())
-    } else {
-        Ok(())
+/// Like `commit_changes`, but dispatches queued operations concurrently
+/// over a [`ldap::PipelineSession`] instead of one blocking round-trip at a
+/// time, keeping up to `max_inflight` operations outstanding. Used when
+/// `--pipeline` asks for a concurrency greater than 1.
+#[allow(clippy::too_many_arguments)]
+fn commit_changes_pipelined(
+    cmdline: &Cmdline,
+    clean_data: &[u8],
+    data_data: &[u8],
+    offsets: &[i64],
+    continuous: bool,
+    max_inflight: usize,
+    generate_entryuuid: bool,
+) -> Result<ApplyReport, CommitFailure> {
+    let total = prescan_total(clean_data, data_data, offsets);
+
+    let session = match ldap::PipelineSession::connect(cmdline, continuous, max_inflight) {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("ldapvi: {}", e);
+            return Err(CommitFailure::Parse);
+        }
+    };
+
+    let mut clean_parser = LdapviParser::new(Cursor::new(clean_data));
+    let mut data_parser = LdapviParser::new(Cursor::new(data_data));
+    let mut handler = PipelinedCommitHandler::new(&session, total);
+    let mut offsets = offsets.to_vec();
+    let cmp = comparator_for(cmdline, clean_data, &offsets, generate_entryuuid);
+
+    let result = diff::compare_streams(
+        &mut clean_parser,
+        &mut data_parser,
+        &mut handler,
+        &mut offsets,
+        diff::DiffMode::Replace,
+        &cmp,
+        &mut diff::NullObserver,
+        &diff::CommitPolicy {
+            continue_on_error: continuous,
+            ..diff::CommitPolicy::strict()
+        },
+    );
+    handler.progress.finish();
+
+    match result {
+        Err(DiffError::HandlerRejected { .. }) => Ok(ApplyReport {
+            attempted: handler.progress.done,
+            failed: handler.errors,
+        }),
+        Err(_) if !handler.errors.is_empty() => Ok(ApplyReport {
+            attempted: handler.progress.done,
+            failed: handler.errors,
+        }),
+        Err(_) => Err(CommitFailure::Parse),
+        Ok(_) => Ok(ApplyReport {
+            attempted: handler.progress.done,
+            failed: handler.errors,
+        }),
+    }
+}
+
+/// Run `commit_changes`, or its pipelined counterpart when `--pipeline`
+/// asked for more than one operation in flight at a time, collapsing the
+/// result to a bare success/failure for the interactive edit loop.
+#[allow(clippy::too_many_arguments)]
+fn commit(
+    cmdline: &Cmdline,
+    conn: &mut LdapConn,
+    clean_data: &[u8],
+    data_data: &[u8],
+    offsets: &[i64],
+    continuous: bool,
+    generate_entryuuid: bool,
+) -> Result<(), CommitFailure> {
+    match commit_with_report(cmdline, conn, clean_data, data_data, offsets, continuous, generate_entryuuid)? {
+        report if report.failed.is_empty() => Ok(()),
+        report => Err(CommitFailure::Entries(report.failed)),
     }
 }
 
-fn write_ldif_changes(clean_data: &[u8], data_data: &[u8], offsets: &[i64], out: &mut dyn Write) {
+/// Run `commit_changes`, or its pipelined counterpart when `--pipeline`
+/// asked for more than one operation in flight at a time, returning full
+/// attempted/failed accounting instead of collapsing it into a bare
+/// success/failure result.
+#[allow(clippy::too_many_arguments)]
+fn commit_with_report(
+    cmdline: &Cmdline,
+    conn: &mut LdapConn,
+    clean_data: &[u8],
+    data_data: &[u8],
+    offsets: &[i64],
+    continuous: bool,
+    generate_entryuuid: bool,
+) -> Result<ApplyReport, CommitFailure> {
+    match cmdline.pipeline_depth {
+        Some(n) if n > 1 => {
+            commit_changes_pipelined(cmdline, clean_data, data_data, offsets, continuous, n, generate_entryuuid)
+        }
+        _ => commit_changes(conn, cmdline, clean_data, data_data, offsets, continuous, generate_entryuuid),
+    }
+}
+
+fn write_ldif_changes(
+    clean_data: &[u8],
+    data_data: &[u8],
+    offsets: &[i64],
+    out: &mut dyn Write,
+    generate_entryuuid: bool,
+) {
     let _ = writeln!(out, "version: 1");
     let mut clean_parser = LdapviParser::new(Cursor::new(clean_data));
     let mut data_parser = LdapviParser::new(Cursor::new(data_data));
     let mut handler = LdifPrintHandler { w: out };
     let mut offsets = offsets.to_vec();
 
-    diff::compare_streams(
+    let _ = diff::compare_streams(
         &mut clean_parser,
         &mut data_parser,
         &mut handler,
         &mut offsets,
+        diff::DiffMode::Replace,
+        &diff::Comparator::new().with_entryuuid_generation(generate_entryuuid),
+        &mut diff::NullObserver,
+        &diff::CommitPolicy::strict(),
     );
 }
 
+/// Also builds the `v`/`V` preview's [`diff::Comparator`] with
+/// `generate_entryuuid`, so the synthesized UUID a user is about to commit
+/// is visible in the preview beforehand, not just in the real commit.
 fn write_vdif_changes(
     clean_data: &[u8],
     data_data: &[u8],
     offsets: &[i64],
     out: &mut dyn Write,
     mode: BinaryMode,
+    generate_entryuuid: bool,
 ) {
     let _ = writeln!(out, "version: ldapvi");
     let mut clean_parser = LdapviParser::new(Cursor::new(clean_data));
@@ -408,14 +1065,217 @@ fn write_vdif_changes(
     let mut handler = VdifPrintHandler { w: out, mode };
     let mut offsets = offsets.to_vec();
 
-    diff::compare_streams(
+    let _ = diff::compare_streams(
         &mut clean_parser,
         &mut data_parser,
         &mut handler,
         &mut offsets,
+        diff::DiffMode::Replace,
+        &diff::Comparator::new().with_entryuuid_generation(generate_entryuuid),
+        &mut diff::NullObserver,
+        &diff::CommitPolicy::strict(),
     );
 }
 
+/// One line of a line-by-line alignment between two texts, as produced by
+/// [`diff_lines`].
+enum DiffLine {
+    Context(String),
+    Removed(String),
+    Added(String),
+}
+
+/// Align `old` and `new` with the textbook LCS algorithm: a dynamic-
+/// programming table of suffix-LCS lengths, then a greedy backtrack that
+/// walks a common line whenever one is available and otherwise takes
+/// whichever side's remaining suffix has the longer LCS with the other.
+fn diff_lines(old: &[String], new: &[String]) -> Vec<DiffLine> {
+    let n = old.len();
+    let m = new.len();
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            result.push(DiffLine::Context(old[i].clone()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            result.push(DiffLine::Removed(old[i].clone()));
+            i += 1;
+        } else {
+            result.push(DiffLine::Added(new[j].clone()));
+            j += 1;
+        }
+    }
+    while i < n {
+        result.push(DiffLine::Removed(old[i].clone()));
+        i += 1;
+    }
+    while j < m {
+        result.push(DiffLine::Added(new[j].clone()));
+        j += 1;
+    }
+    result
+}
+
+/// Write `lines` as a standard unified diff (`@@ -a,b +c,d @@` hunk headers),
+/// collapsing runs of context longer than `2 * context_radius` down to
+/// `context_radius` lines on either side of the nearest change. Removed/added
+/// lines are colored red/green when `color` is set.
+fn print_unified_hunks(out: &mut dyn Write, lines: &[DiffLine], context_radius: usize, color: bool) {
+    let n = lines.len();
+    let mut old_before = Vec::with_capacity(n + 1);
+    let mut new_before = Vec::with_capacity(n + 1);
+    let (mut old_n, mut new_n) = (0usize, 0usize);
+    for line in lines {
+        old_before.push(old_n);
+        new_before.push(new_n);
+        match line {
+            DiffLine::Context(_) => {
+                old_n += 1;
+                new_n += 1;
+            }
+            DiffLine::Removed(_) => old_n += 1,
+            DiffLine::Added(_) => new_n += 1,
+        }
+    }
+    old_before.push(old_n);
+    new_before.push(new_n);
+
+    let mut i = 0;
+    while i < n {
+        if matches!(lines[i], DiffLine::Context(_)) {
+            i += 1;
+            continue;
+        }
+
+        // Grow the hunk while changes keep appearing within 2*radius of each
+        // other, so nearby edits share one hunk instead of fragmenting.
+        let start = i.saturating_sub(context_radius);
+        let mut end = i + 1;
+        loop {
+            let mut run_end = end;
+            while run_end < n && matches!(lines[run_end], DiffLine::Context(_)) {
+                run_end += 1;
+            }
+            if run_end >= n || run_end - end > 2 * context_radius {
+                end = (end + context_radius).min(run_end).min(n);
+                break;
+            }
+            if let Some(next_change) = (run_end..n).find(|&k| !matches!(lines[k], DiffLine::Context(_))) {
+                end = next_change + 1;
+            } else {
+                end = run_end;
+                break;
+            }
+        }
+
+        let old_start = old_before[start] + 1;
+        let new_start = new_before[start] + 1;
+        let old_count = old_before[end] - old_before[start];
+        let new_count = new_before[end] - new_before[start];
+        let _ = writeln!(out, "@@ -{},{} +{},{} @@", old_start, old_count, new_start, new_count);
+        for line in &lines[start..end] {
+            match line {
+                DiffLine::Context(s) => {
+                    let _ = writeln!(out, " {}", s);
+                }
+                DiffLine::Removed(s) => {
+                    if color {
+                        let _ = writeln!(out, "\x1b[31m-{}\x1b[0m", s);
+                    } else {
+                        let _ = writeln!(out, "-{}", s);
+                    }
+                }
+                DiffLine::Added(s) => {
+                    if color {
+                        let _ = writeln!(out, "\x1b[32m+{}\x1b[0m", s);
+                    } else {
+                        let _ = writeln!(out, "+{}", s);
+                    }
+                }
+            }
+        }
+        i = end;
+    }
+}
+
+/// Render `entry` the way it appears in the editor, split into lines, for
+/// feeding to [`diff_lines`].
+fn entry_lines(entry: &Entry, key: Option<&str>, mode: BinaryMode) -> Vec<String> {
+    let mut buf = Vec::new();
+    let _ = print::print_ldapvi_entry(&mut buf, entry, key, mode);
+    String::from_utf8_lossy(&buf)
+        .lines()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+/// Print a colored, per-entry unified diff between the server ("clean")
+/// rendering and the edited rendering of every added, deleted, or modified
+/// entry, grouped by DN -- the `D` action's "review like a patch" view of
+/// what `v`/`V` otherwise show as a raw LDIF/vdif changelog.
+fn print_entry_diffs(clean_data: &[u8], data_data: &[u8], offsets: &[i64], out: &mut dyn Write, mode: BinaryMode, color: bool) {
+    const CONTEXT_RADIUS: usize = 3;
+
+    let mut clean_parser = LdapviParser::new(Cursor::new(clean_data));
+    let mut data_parser = LdapviParser::new(Cursor::new(data_data));
+
+    let mut data_by_key: std::collections::HashMap<usize, Entry> = std::collections::HashMap::new();
+    let mut added: Vec<Entry> = Vec::new();
+    while let Ok(Some((key, entry, _pos))) = data_parser.read_entry(None) {
+        match key.parse::<usize>() {
+            Ok(n) => {
+                data_by_key.insert(n, entry);
+            }
+            Err(_) => added.push(entry),
+        }
+    }
+
+    let mut show_diff = |dn: &str, old_lines: Vec<String>, new_lines: Vec<String>| {
+        if old_lines == new_lines {
+            return;
+        }
+        let _ = writeln!(out, "=== {} ===", dn);
+        let _ = writeln!(out, "--- clean");
+        let _ = writeln!(out, "+++ edited");
+        print_unified_hunks(out, &diff_lines(&old_lines, &new_lines), CONTEXT_RADIUS, color);
+    };
+
+    for (n, &pos) in offsets.iter().enumerate() {
+        if pos < 0 {
+            continue;
+        }
+        let clean_entry = match clean_parser.read_entry(Some(pos as u64)) {
+            Ok(Some((_, e, _))) => e,
+            _ => continue,
+        };
+        let key = n.to_string();
+        let old_lines = entry_lines(&clean_entry, Some(&key), mode);
+        let new_lines = match data_by_key.get(&n) {
+            Some(data_entry) => entry_lines(data_entry, Some(&key), mode),
+            None => Vec::new(),
+        };
+        show_diff(&clean_entry.dn, old_lines, new_lines);
+    }
+
+    for entry in &added {
+        let new_lines = entry_lines(entry, Some("add"), mode);
+        show_diff(&entry.dn, Vec::new(), new_lines);
+    }
+}
+
 /// Forget deletions: rewrite the data file to include any entries that
 /// were deleted (present in clean but missing from data).
 fn forget_deletions(clean_data: &[u8], data_path: &str, offsets: &[i64], mode: BinaryMode) {
@@ -523,7 +1383,7 @@ fn skip_first_entry(data_path: &str, offsets: &mut [i64]) {
     }
 }
 
-fn save_ldif_to_file(clean_data: &[u8], data_data: &[u8], offsets: &[i64]) -> String {
+fn save_ldif_to_file(clean_data: &[u8], data_data: &[u8], offsets: &[i64], generate_entryuuid: bool) -> String {
     // Create LDIF file in the current directory
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -532,7 +1392,7 @@ fn save_ldif_to_file(clean_data: &[u8], data_data: &[u8], offsets: &[i64]) -> St
     let filename = format!(",ldapvi-{}.ldif", timestamp);
 
     let mut f = fs::File::create(&filename).expect("failed to create LDIF file");
-    write_ldif_changes(clean_data, data_data, offsets, &mut f);
+    write_ldif_changes(clean_data, data_data, offsets, &mut f, generate_entryuuid);
     filename
 }
 
@@ -555,16 +1415,59 @@ const ACTION_HELP: &str = "Commands:
   Q -- discard changes and quit
   v -- view changes as LDIF change records
   V -- view changes as ldapvi change records
+  d -- show per-attribute/objectClass change details
+  D -- show a colored unified diff per changed entry
   e -- open editor again
+  p -- change a password via PasswordModify (RFC 3062)
   b -- show login dialog and rebind
   B -- toggle SASL
   * -- set SASL mechanism
-  r -- reconnect to server
+  r -- reconnect, trying each --host/--server in order
   s -- skip one entry
   f -- forget deletions
   + -- rewrite file to include schema comments
   ? -- this help";
 
+/// Prompt for whatever credentials `mech` needs, perform the SASL bind, and
+/// report the outcome the same way `simple_bind` does under the `b` action.
+fn sasl_rebind(conn: &mut LdapConn, cmdline: &Cmdline, mech: ldap::SaslMech) {
+    let authcid = cmdline
+        .sasl_authcid
+        .clone()
+        .or_else(|| cmdline.user.clone().map(ldap::AuthzId::User))
+        .unwrap_or_else(|| ldap::AuthzId::User(interactive::read_line("Authentication identity: ")));
+    let authcid_str = authcid.to_wire_string();
+    let authzid_str = cmdline.sasl_authzid.as_ref().map(|a| a.to_wire_string());
+    let realm = cmdline.sasl_realm.clone();
+    let password = match mech {
+        ldap::SaslMech::External => None,
+        ldap::SaslMech::Gssapi => None,
+        ldap::SaslMech::DigestMd5 | ldap::SaslMech::Plain => Some(
+            cmdline
+                .password
+                .clone()
+                .unwrap_or_else(|| interactive::read_password("Password: ")),
+        ),
+    };
+
+    match ldap::sasl_bind(
+        conn,
+        mech,
+        Some(&authcid_str),
+        authzid_str.as_deref(),
+        realm.as_deref(),
+        password.as_deref(),
+        cmdline.sasl_secprops,
+    ) {
+        Ok(()) => {
+            eprintln!("Bound as {} via SASL {}.", authcid, mech);
+        }
+        Err(e) => {
+            eprintln!("SASL bind: {}", e);
+        }
+    }
+}
+
 const PARSE_ERROR_HELP: &str = "Commands:
   e -- re-edit (cursor at error)
   Q -- discard changes and quit
@@ -573,6 +1476,12 @@ const PARSE_ERROR_HELP: &str = "Commands:
 fn do_edit(conn: &mut LdapConn, cmdline: &Cmdline) {
     let mode = binary_mode(cmdline);
 
+    // `--generate-entryuuid`, unless the server's root DSE says it already
+    // assigns one itself. Resolved once per session (it costs a round-trip)
+    // rather than on every commit/preview.
+    let generate_entryuuid = cmdline.generate_entryuuid
+        && !ldap::server_has_entryuuid_feature(conn).unwrap_or(false);
+
     // Create temp directory
     let tmpdir = tempfile::tempdir().expect("failed to create temp directory");
     let clean_path = tmpdir.path().join("clean");
@@ -600,7 +1509,7 @@ fn do_edit(conn: &mut LdapConn, cmdline: &Cmdline) {
     loop {
         let data_data = fs::read(&data_path).expect("failed to read data file");
 
-        match analyze_changes(&clean_data, &data_data, &offsets) {
+        match analyze_changes(cmdline, &clean_data, &data_data, &offsets, generate_entryuuid) {
             AnalysisResult::NoChanges => {
                 println!("No changes.");
                 std::process::exit(0);
@@ -623,15 +1532,33 @@ fn do_edit(conn: &mut LdapConn, cmdline: &Cmdline) {
                     _ => continue,
                 }
             }
+            AnalysisResult::StructuralError(message) => {
+                eprintln!("ldapvi: entries have been added or removed during editing: {}", message);
+                let c = interactive::choose("What now?", "eQ?", PARSE_ERROR_HELP);
+                match c {
+                    'e' => {
+                        interactive::edit(&data_path_str, None);
+                        continue;
+                    }
+                    'Q' => {
+                        std::process::exit(0);
+                    }
+                    '?' => {
+                        eprintln!("{}", PARSE_ERROR_HELP);
+                        continue;
+                    }
+                    _ => continue,
+                }
+            }
             AnalysisResult::Changes(stats) => {
                 stats.print_summary();
 
                 loop {
-                    let c = interactive::choose("Action?", "yYqQvVebB*rsf+?", ACTION_HELP);
+                    let c = interactive::choose("Action?", "yYqQvVdDepbB*rsf+?", ACTION_HELP);
                     match c {
                         'y' => {
                             let data_data = fs::read(&data_path).expect("failed to read data file");
-                            match commit_changes(conn, &clean_data, &data_data, &offsets, false) {
+                            match commit(cmdline, conn, &clean_data, &data_data, &offsets, false, generate_entryuuid) {
                                 Ok(()) => {
                                     println!("Done.");
                                     std::process::exit(0);
@@ -644,12 +1571,17 @@ fn do_edit(conn: &mut LdapConn, cmdline: &Cmdline) {
                         }
                         'Y' => {
                             let data_data = fs::read(&data_path).expect("failed to read data file");
-                            match commit_changes(conn, &clean_data, &data_data, &offsets, true) {
+                            match commit(cmdline, conn, &clean_data, &data_data, &offsets, true, generate_entryuuid) {
                                 Ok(()) => {
                                     println!("Done.");
                                     std::process::exit(0);
                                 }
-                                Err(_e) => {
+                                Err(CommitFailure::Entries(errors)) => {
+                                    let mut stderr = std::io::stderr();
+                                    print_commit_report(&errors, cmdline.commit_report_json, &mut stderr);
+                                    continue;
+                                }
+                                Err(CommitFailure::Parse) => {
                                     continue;
                                 }
                             }
@@ -660,7 +1592,7 @@ fn do_edit(conn: &mut LdapConn, cmdline: &Cmdline) {
                         }
                         'q' => {
                             let data_data = fs::read(&data_path).expect("failed to read data file");
-                            let filename = save_ldif_to_file(&clean_data, &data_data, &offsets);
+                            let filename = save_ldif_to_file(&clean_data, &data_data, &offsets, generate_entryuuid);
                             println!("Your changes have been saved to {}", filename);
                             std::process::exit(0);
                         }
@@ -668,25 +1600,60 @@ fn do_edit(conn: &mut LdapConn, cmdline: &Cmdline) {
                             std::process::exit(0);
                         }
                         'v' => {
-                            // Write LDIF to temp file and view
+                            // Render LDIF into an in-memory buffer and view it
+                            // without ever writing the (possibly
+                            // password-bearing) changes to disk.
                             let data_data = fs::read(&data_path).expect("failed to read data file");
-                            let view_path = tmpdir.path().join("view.ldif");
-                            let mut f =
-                                fs::File::create(&view_path).expect("failed to create view file");
-                            write_ldif_changes(&clean_data, &data_data, &offsets, &mut f);
-                            drop(f);
-                            interactive::view(view_path.to_str().unwrap());
+                            let mut buf = Cursor::new(Vec::new());
+                            write_ldif_changes(&clean_data, &data_data, &offsets, &mut buf, generate_entryuuid);
+                            if let Err(e) = interactive::view_in_memory(&buf.into_inner()) {
+                                eprintln!("ldapvi: {}", e);
+                            }
                             continue;
                         }
                         'V' => {
-                            // Write vdif to temp file and view
+                            // Render vdif into an in-memory buffer and view it
+                            // without ever writing the (possibly
+                            // password-bearing) changes to disk.
+                            let data_data = fs::read(&data_path).expect("failed to read data file");
+                            let mut buf = Cursor::new(Vec::new());
+                            write_vdif_changes(&clean_data, &data_data, &offsets, &mut buf, mode, generate_entryuuid);
+                            if let Err(e) = interactive::view_in_memory(&buf.into_inner()) {
+                                eprintln!("ldapvi: {}", e);
+                            }
+                            continue;
+                        }
+                        'd' => {
+                            stats.print_details();
+                            continue;
+                        }
+                        'D' => {
                             let data_data = fs::read(&data_path).expect("failed to read data file");
-                            let view_path = tmpdir.path().join("view.vdif");
-                            let mut f =
-                                fs::File::create(&view_path).expect("failed to create view file");
-                            write_vdif_changes(&clean_data, &data_data, &offsets, &mut f, mode);
-                            drop(f);
-                            interactive::view(view_path.to_str().unwrap());
+                            let color = std::io::stdout().is_terminal();
+                            print_entry_diffs(&clean_data, &data_data, &offsets, &mut std::io::stdout(), mode, color);
+                            continue;
+                        }
+                        'p' => {
+                            let dn = interactive::read_line("DN (blank to change your own password): ");
+                            let old = interactive::read_password("Old password (blank if none): ");
+                            let new = interactive::read_password("New password (blank to let the server generate one): ");
+                            let user_identity = if dn.is_empty() { None } else { Some(dn.as_str()) };
+                            let old_passwd = if old.is_empty() { None } else { Some(old.as_str()) };
+                            let new_passwd = if new.is_empty() { None } else { Some(new.as_str()) };
+                            match ldap::password_modify(conn, user_identity, old_passwd, new_passwd) {
+                                Ok(Some(generated)) => {
+                                    eprintln!(
+                                        "Password changed. Generated password: {}",
+                                        String::from_utf8_lossy(&generated)
+                                    );
+                                }
+                                Ok(None) => {
+                                    eprintln!("Password changed.");
+                                }
+                                Err(e) => {
+                                    eprintln!("password modify: {}", e);
+                                }
+                            }
                             continue;
                         }
                         'b' => {
@@ -703,19 +1670,34 @@ fn do_edit(conn: &mut LdapConn, cmdline: &Cmdline) {
                             continue;
                         }
                         'B' => {
-                            eprintln!("SASL not yet supported.");
+                            let mech_input = interactive::read_line("SASL mechanism (GSSAPI/DIGEST-MD5/PLAIN/EXTERNAL): ");
+                            let mech = match ldap::SaslMech::parse(mech_input.trim()) {
+                                Some(mech) => mech,
+                                None => {
+                                    eprintln!("Unknown SASL mechanism: {}", mech_input.trim());
+                                    continue;
+                                }
+                            };
+                            sasl_rebind(conn, cmdline, mech);
                             continue;
                         }
                         '*' => {
-                            eprintln!("SASL not yet supported.");
+                            match ldap::negotiate_sasl_mech(conn) {
+                                Ok(mech) => {
+                                    eprintln!("Negotiated SASL mechanism: {}.", mech);
+                                    sasl_rebind(conn, cmdline, mech);
+                                }
+                                Err(e) => {
+                                    eprintln!("SASL negotiation: {}", e);
+                                }
+                            }
                             continue;
                         }
                         'r' => {
                             match ldap::do_connect(cmdline) {
-                                Ok(new_conn) => {
+                                Ok((new_conn, url)) => {
                                     *conn = new_conn;
-                                    let server = cmdline.server.as_deref().unwrap_or("localhost");
-                                    eprintln!("Connected to {}.", server);
+                                    eprintln!("Connected to {}.", url);
                                 }
                                 Err(e) => {
                                     eprintln!("reconnect: {}", e);
@@ -779,17 +1761,65 @@ fn do_in(conn: &mut LdapConn, cmdline: &Cmdline) {
         });
     }
 
-    let mut parser = LdapviParser::new(Cursor::new(data.as_slice()));
     let empty_clean = Vec::new();
-    let mut clean_parser = LdapviParser::new(Cursor::new(empty_clean.as_slice()));
-    let mut handler = LdapCommitHandler::new(conn, cmdline.continuous);
-    let mut offsets: Vec<i64> = Vec::new();
+    let offsets: Vec<i64> = Vec::new();
+
+    let generate_entryuuid = cmdline.generate_entryuuid
+        && !ldap::server_has_entryuuid_feature(conn).unwrap_or(false);
+
+    if cmdline.dry_run {
+        let mut clean_parser = LdapviParser::new(Cursor::new(empty_clean.as_slice()));
+        let mut parser = LdapviParser::new(Cursor::new(data.as_slice()));
+        let mut handler = DryRunHandler::new();
+        let mut offsets = offsets;
+
+        let result = diff::compare_streams(
+            &mut clean_parser,
+            &mut parser,
+            &mut handler,
+            &mut offsets,
+            diff::DiffMode::Replace,
+            &diff::Comparator::new().with_entryuuid_generation(generate_entryuuid),
+            &mut diff::NullObserver,
+            &diff::CommitPolicy::strict(),
+        );
+        if result.is_err() {
+            eprintln!("ldapvi: parse error");
+            std::process::exit(1);
+        }
+        handler.stats.print_summary();
+        return;
+    }
 
-    let rc = diff::compare_streams(&mut clean_parser, &mut parser, &mut handler, &mut offsets);
+    let result = commit_with_report(
+        cmdline,
+        conn,
+        &empty_clean,
+        &data,
+        &offsets,
+        cmdline.continuous,
+        generate_entryuuid,
+    );
 
-    if rc != 0 || !handler.errors.is_empty() {
-        eprintln!("ldapvi: some operations failed");
-        std::process::exit(1);
+    let mut stderr = std::io::stderr();
+    match result {
+        Ok(report) => {
+            if cmdline.verbose {
+                report.print_summary(&mut stderr);
+            }
+            if !report.failed.is_empty() {
+                print_commit_report(&report.failed, cmdline.commit_report_json, &mut stderr);
+                std::process::exit(1);
+            }
+        }
+        Err(CommitFailure::Entries(errors)) => {
+            print_commit_report(&errors, cmdline.commit_report_json, &mut stderr);
+            std::process::exit(1);
+        }
+        Err(CommitFailure::Parse) => {
+            eprintln!("ldapvi: parse error");
+            std::process::exit(1);
+        }
     }
 }
 
@@ -798,13 +1828,31 @@ fn do_in(conn: &mut LdapConn, cmdline: &Cmdline) {
 // ===========================================================================
 
 fn do_delete(conn: &mut LdapConn, cmdline: &Cmdline) {
-    for dn in &cmdline.delete_dns {
-        ldap::ldap_delete(conn, dn).unwrap_or_else(|e| {
+    let mut report = ApplyReport::new(0);
+
+    for (i, dn) in cmdline.delete_dns.iter().enumerate() {
+        report.attempted += 1;
+        if let Err(e) = ldap::ldap_delete(conn, dn, cmdline) {
             eprintln!("ldapvi: {}", e);
+            report.failed.push(CommitError {
+                entry_index: i as i32,
+                dn: dn.clone(),
+                op: OpKind::Delete,
+                result_code: e.result_code,
+                diagnostic: e.message,
+            });
             if !cmdline.continuous {
-                std::process::exit(1);
+                break;
             }
-        });
+        }
+    }
+
+    let mut stderr = std::io::stderr();
+    if cmdline.verbose {
+        report.print_summary(&mut stderr);
+    }
+    if !report.failed.is_empty() {
+        std::process::exit(1);
     }
 }
 
@@ -831,12 +1879,11 @@ fn do_rename(conn: &mut LdapConn, cmdline: &Cmdline) {
         None
     };
 
-    ldap::ldap_rename(conn, old_dn, new_rdn, new_superior, cmdline.deleteoldrdn).unwrap_or_else(
-        |e| {
+    ldap::ldap_rename(conn, old_dn, new_rdn, new_superior, cmdline.deleteoldrdn, cmdline)
+        .unwrap_or_else(|e| {
             eprintln!("ldapvi: {}", e);
             std::process::exit(1);
-        },
-    );
+        });
 }
 
 fn do_modrdn(conn: &mut LdapConn, cmdline: &Cmdline) {
@@ -849,10 +1896,192 @@ fn do_modrdn(conn: &mut LdapConn, cmdline: &Cmdline) {
         std::process::exit(1);
     });
 
-    ldap::ldap_rename(conn, old_dn, new_rdn, None, cmdline.deleteoldrdn).unwrap_or_else(|e| {
+    ldap::ldap_rename(conn, old_dn, new_rdn, None, cmdline.deleteoldrdn, cmdline).unwrap_or_else(
+        |e| {
+            eprintln!("ldapvi: {}", e);
+            std::process::exit(1);
+        },
+    );
+}
+
+// ===========================================================================
+// --rename-bulk mode
+// ===========================================================================
+
+/// Search for matching entries, hand their DNs to the user in an editor
+/// (one `index dn` line each), then apply whatever renames the edited
+/// lines imply.
+fn do_rename_bulk(conn: &mut LdapConn, cmdline: &Cmdline) {
+    let old_dns = ldap::search_dns(conn, cmdline).unwrap_or_else(|e| {
         eprintln!("ldapvi: {}", e);
         std::process::exit(1);
     });
+
+    if old_dns.is_empty() {
+        println!("No matching entries.");
+        return;
+    }
+
+    let tmpdir = tempfile::tempdir().expect("failed to create temp directory");
+    let buffer_path = tmpdir.path().join("rename-bulk");
+    let buffer_path_str = buffer_path.to_str().unwrap().to_string();
+
+    let mut buffer = String::new();
+    for (i, dn) in old_dns.iter().enumerate() {
+        buffer.push_str(&format!("{} {}\n", i, dn));
+    }
+    fs::write(&buffer_path, &buffer).expect("failed to write rename-bulk buffer");
+
+    interactive::edit(&buffer_path_str, None);
+
+    let edited = fs::read_to_string(&buffer_path).expect("failed to read rename-bulk buffer");
+    let new_dns = parse_bulk_rename_buffer(&edited, old_dns.len()).unwrap_or_else(|e| {
+        eprintln!("ldapvi: {}", e);
+        std::process::exit(1);
+    });
+
+    apply_bulk_renames(conn, &old_dns, &new_dns, cmdline.continuous, cmdline);
+}
+
+/// Parse a `rename-bulk` buffer of `index dn` lines back into a DN list
+/// indexed the same way the buffer was generated, i.e. `result[i]` is the
+/// (possibly renamed) DN for the entry that was offered as index `i`.
+fn parse_bulk_rename_buffer(text: &str, expected: usize) -> Result<Vec<String>, String> {
+    let mut result: Vec<Option<String>> = vec![None; expected];
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (index_str, dn) = line
+            .split_once(' ')
+            .ok_or_else(|| format!("malformed rename-bulk line: {:?}", line))?;
+        let index: usize = index_str
+            .parse()
+            .map_err(|_| format!("malformed rename-bulk line: {:?}", line))?;
+        if index >= expected {
+            return Err(format!("rename-bulk line index out of range: {}", index));
+        }
+        if result[index].is_some() {
+            return Err(format!("rename-bulk index {} appears more than once", index));
+        }
+        result[index] = Some(dn.trim().to_string());
+    }
+
+    result
+        .into_iter()
+        .enumerate()
+        .map(|(i, dn)| dn.ok_or_else(|| format!("rename-bulk buffer is missing index {}", i)))
+        .collect()
+}
+
+/// Rename a single entry from `old_dn` to `new_dn`, following the same
+/// RDN/superior split as [`do_rename`].
+fn rename_one(conn: &mut LdapConn, old_dn: &str, new_dn: &str, cmdline: &Cmdline) -> Result<(), String> {
+    let new_rdn = first_rdn(new_dn);
+    let old_parent = parent_dn(old_dn);
+    let new_parent = parent_dn(new_dn);
+    let new_superior = if old_parent != new_parent {
+        Some(new_parent.as_str())
+    } else {
+        None
+    };
+
+    ldap::ldap_rename(conn, old_dn, new_rdn, new_superior, false, cmdline).map_err(|e| e.to_string())
+}
+
+/// Apply the renames implied by `old_dns[i] -> new_dns[i]`, reordering
+/// around DN conflicts so that an entry is never renamed onto a DN another
+/// pending entry still occupies.
+///
+/// Entries whose target DN is permanently held by an entry that isn't
+/// itself being renamed can never be resolved by reordering and are
+/// reported as errors up front. Cycles among the remaining entries (A -> B,
+/// B -> A) are broken by temporarily renaming one of them out of the way.
+fn apply_bulk_renames(
+    conn: &mut LdapConn,
+    old_dns: &[String],
+    new_dns: &[String],
+    continuous: bool,
+    cmdline: &Cmdline,
+) {
+    let mut current_dn: Vec<String> = old_dns.to_vec();
+    let mut pending: Vec<usize> = (0..old_dns.len())
+        .filter(|&i| old_dns[i] != new_dns[i])
+        .collect();
+
+    let fail = |dn: &str, e: &str| {
+        eprintln!("ldapvi: rename {}: {}", dn, e);
+        if !continuous {
+            std::process::exit(1);
+        }
+    };
+
+    // A hard conflict is a target DN held by an entry that was never asked
+    // to move; reordering can't free that slot.
+    pending.retain(|&i| {
+        let held_by_fixed = current_dn
+            .iter()
+            .enumerate()
+            .any(|(j, dn)| dn == &new_dns[i] && old_dns[j] == new_dns[j]);
+        if held_by_fixed {
+            fail(
+                &old_dns[i],
+                &format!("target DN {} is already in use", new_dns[i]),
+            );
+            false
+        } else {
+            true
+        }
+    });
+
+    let mut tmp_counter = 0usize;
+    while !pending.is_empty() {
+        let mut progressed = false;
+        let mut still_pending = Vec::new();
+
+        for i in pending {
+            let blocked = current_dn
+                .iter()
+                .enumerate()
+                .any(|(j, dn)| j != i && dn == &new_dns[i]);
+            if blocked {
+                still_pending.push(i);
+                continue;
+            }
+
+            match rename_one(conn, &current_dn[i], &new_dns[i], cmdline) {
+                Ok(()) => {
+                    current_dn[i] = new_dns[i].clone();
+                    progressed = true;
+                }
+                Err(e) => fail(&old_dns[i], &e),
+            }
+        }
+
+        pending = still_pending;
+        if pending.is_empty() || progressed {
+            continue;
+        }
+
+        // No entry could move this pass: break the cycle by shuffling one
+        // stuck entry onto a throwaway RDN, freeing its current slot.
+        let i = pending[0];
+        let parent = parent_dn(&current_dn[i]);
+        let tmp_rdn = format!("{}-ldapvi-tmp-{}", first_rdn(&current_dn[i]), tmp_counter);
+        tmp_counter += 1;
+        let tmp_dn = format!("{},{}", tmp_rdn, parent);
+
+        match rename_one(conn, &current_dn[i], &tmp_dn, cmdline) {
+            Ok(()) => current_dn[i] = tmp_dn,
+            Err(e) => {
+                fail(&old_dns[i], &e);
+                pending.retain(|&j| j != i);
+            }
+        }
+    }
 }
 
 // ===========================================================================
@@ -868,7 +2097,14 @@ pub fn run() {
         }
     };
 
-    let mut conn = ldap::do_connect(&cmdline).unwrap_or_else(|e| {
+    if cmdline.dump_filter {
+        // arguments::parse_args already validated `filter` with the same
+        // parser, so this can't fail here.
+        println!("{}", ldapfilter::format(&ldapfilter::parse(&cmdline.filter).unwrap()));
+        return;
+    }
+
+    let (mut conn, _) = ldap::do_connect(&cmdline).unwrap_or_else(|e| {
         eprintln!("ldapvi: {}", e);
         std::process::exit(1);
     });
@@ -915,6 +2151,9 @@ pub fn run() {
         Mode::Modrdn => {
             do_modrdn(&mut conn, &cmdline);
         }
+        Mode::RenameBulk => {
+            do_rename_bulk(&mut conn, &cmdline);
+        }
     }
 
     let _ = conn.unbind();
@@ -3,7 +3,9 @@ use std::io::Cursor;
 use popt::{ArgType, Context, Opt, OptionTable};
 
 use ldapvi::data::Entry;
-use ldapvi::parse::LdapviParser;
+use ldapvi::parse::{ConfigEntry, LdapviParser};
+
+use crate::ldap::{AuthzId, DnMatchType, SaslSecprops, TlsMode, TlsRequireCert};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
@@ -13,12 +15,16 @@ pub enum Mode {
     Delete,
     Rename,
     Modrdn,
+    RenameBulk,
 }
 
 #[derive(Debug)]
 #[allow(dead_code)]
 pub struct Cmdline {
-    pub server: Option<String>,
+    /// LDAP URIs to try in order, e.g. for failover against a replica
+    /// pool. Each may be bare (`ldap://` is assumed) or a full URI;
+    /// populated from repeated/comma-separated `--host`/`--server`.
+    pub servers: Vec<String>,
     pub basedns: Vec<String>,
     pub scope: ldap3::Scope,
     pub filter: String,
@@ -44,7 +50,23 @@ pub struct Cmdline {
     pub rename_new: Option<String>,
     pub deleteoldrdn: bool,
     pub bind: Option<String>,
-    pub tls: Option<String>,
+    pub sasl_mech: Option<String>,
+    pub sasl_authcid: Option<AuthzId>,
+    pub sasl_authzid: Option<AuthzId>,
+    pub sasl_realm: Option<String>,
+    pub sasl_secprops: crate::ldap::SaslSecprops,
+    pub tls: Option<TlsRequireCert>,
+    /// A profile's `tls: starttls|ldaps|off`, selecting the transport
+    /// itself. `starttls` is also folded into `starttls` above; `ldaps`
+    /// is consulted by `ldap::do_connect` to pick the URL scheme.
+    pub tls_mode: Option<TlsMode>,
+    /// A profile's `tls-cacert:` -- CA bundle to verify the server's
+    /// certificate against.
+    pub tls_cacert: Option<String>,
+    /// A profile's `tls-cert:`/`tls-key:` -- client certificate and key
+    /// for mutual TLS. Only used when both are present.
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
     pub password_file: Option<String>,
     pub schema_comments: bool,
     pub config: bool,
@@ -53,6 +75,47 @@ pub struct Cmdline {
     pub classes: Vec<String>,
     pub ldap_conf: bool,
     pub profile: Option<String>,
+    pub commit_report_json: bool,
+    pub pipeline_depth: Option<usize>,
+    /// How many times a connection-level failure during `--in`/search
+    /// should retry (rotating through `servers`) before giving up.
+    pub retry: u32,
+    /// Run the full diff and print the operations a commit would perform,
+    /// without sending anything to the server.
+    pub dry_run: bool,
+    /// Client-side entry filter applied on top of `basedns`/`filter`, in
+    /// the small predicate DSL `ldapvi::filter::parse` understands.
+    /// Stored raw and parsed where it's applied, like `filter` itself.
+    pub select: Option<String>,
+    /// Use the length-prefixed netencode export format instead of
+    /// ldapvi/LDIF. Round-trips arbitrary binary values byte-for-byte,
+    /// unlike LDIF (which base64-encodes them) or the default ldapvi
+    /// format (which escapes them).
+    pub netencode: bool,
+    /// Request the operational attribute `entryUUID` and use it (falling
+    /// back to DN matching when absent) to identify an entry's server
+    /// counterpart during diffing, instead of trusting the numeric key it
+    /// happened to be read under. See `diff::Comparator::with_uuid_index`.
+    pub track_uuid: bool,
+    /// Parse `filter` (which has already been validated by
+    /// `ldapvi::ldapfilter::parse` below) and pretty-print its structure
+    /// to standard output instead of connecting to a server.
+    pub dump_filter: bool,
+    /// Synthesize an `entryUUID` for each `add` record that doesn't already
+    /// carry one, rather than relying on a server plugin to assign it --
+    /// unless the server's root DSE already advertises that it does. See
+    /// `diff::Comparator::with_entryuuid_generation` and
+    /// `ldap::server_has_entryuuid_feature`.
+    pub generate_entryuuid: bool,
+    /// Follow `ldap://host/dn` referrals and continuation references
+    /// returned during `--ldapsearch`/commit instead of leaving them for the
+    /// user to chase by hand. See `ldap::search_and_print`'s referral
+    /// handling and `ldap::ldap_modify`/`ldap_add`/`ldap_delete`/
+    /// `ldap_rename`'s.
+    pub chase_referrals: bool,
+    /// How many referral hops a search will follow before giving up, so a
+    /// misconfigured chain of meta/referral backends can't recurse forever.
+    pub referral_hop_limit: u32,
 }
 
 const USAGE: &str = r#"Usage: ldapvi [OPTION]... [FILTER] [AD]...
@@ -65,12 +128,26 @@ Other usage:
        ldapvi --in [OPTION]... [FILENAME]         Load change records
        ldapvi --delete [OPTION]... DN...          Edit a delete record
        ldapvi --rename [OPTION]... DN1 DN2        Edit a rename record
+       ldapvi --rename-bulk [OPTION]... [FILTER]  Rename matching entries in an editor
 
 Configuration profiles:
   -p, --profile NAME     Section of ~/.ldapvirc or /etc/ldap.conf to use.
 
+  An `alias NAME = ARGS...` line in the same file defines a shorthand:
+  when the first non-option argument on the command line names an alias,
+  its expansion is spliced in before the rest of the command line, so
+  `ldapvi fixmail --verbose` runs as if `fixmail`'s expansion had been
+  typed out followed by `--verbose`. CLI options still win over anything
+  the same alias sets, the same way CLI options already win over profile
+  options.
+
 Connection options:
-  -h, --host URL         Server.
+  -h, --host URL         Server.  Can be repeated, or a comma/space
+                         separated list, to fail over across a replica
+                         pool; tried in order until one binds.
+      --server URL       Alias for --host.
+      --retry N          Retries for a dropped connection during --in
+                         or search, rotating through --host.  Default 3.
   -D, --user USER        Search filter or DN: User to bind as.     [1]
                          Sets --bind simple.
   -w, --password SECRET  Password (also valid for SASL).
@@ -90,7 +167,11 @@ SASL options (these parameters set --bind sasl):
   -Y, --sasl-mech  MECH  SASL mechanism.
 
 Search parameters:
-  -b, --base DN          Search base.
+  -b, --base DN          Search base.  Repeatable.  Overrides any base(s)
+                         set by --profile or ldap.conf.
+      --base-add DN      Add an extra search base without losing the
+                         base(s) --base/--profile/ldap.conf already
+                         resolved.  Repeatable; applied after --base.
   -s, --scope SCOPE      Search scope.  One of base|one|sub.
   -S, --sort KEYS        Sort control (critical).
 
@@ -110,13 +191,50 @@ Miscellaneous options:
       --ldap-conf        Always read libldap configuration.
   -m, --may              Show missing optional attributes as comments.
   -M, --managedsait      manageDsaIT control (critical).
+      --netencode        Use the length-prefixed netencode format for
+                         --out/--in, instead of ldapvi/LDIF.  Round-trips
+                         arbitrary binary values byte-for-byte.
       --noquestions      Commit without asking for confirmation.
   -!, --noninteractive   Never ask any questions.
   -q, --quiet            Disable progress output.
   -R, --read DN          Same as -b DN -s base '(objectclass=*)' + *
+      --select EXPR      Client-side entry filter, on top of -b/-f.  See [3].
   -Z, --starttls         Require startTLS.
-      --tls [never|allow|try|strict]  Level of TLS strictess.
+      --tls [never|allow|try|strict]  Level of TLS strictess.  A profile
+                         spells this 'tls-reqcert:' -- see [4].
+      --tls-mode [starttls|ldaps|off]
+                         Transport security to use, in addition to/instead
+                         of -Z.  A profile spells this 'tls:'.  See [4].
+      --tls-cacert PATH  CA bundle to verify the server certificate
+                         against.  A profile spells this 'tls-cacert:'.
+      --tls-cert PATH    Client certificate for mutual TLS; requires
+                         --tls-key.  A profile spells this 'tls-cert:'.
+      --tls-key PATH     Client private key for mutual TLS; requires
+                         --tls-cert.  A profile spells this 'tls-key:'.
   -v, --verbose          Note every update.
+      --commit-report-json
+                         Print the end-of-commit failure report as JSON.
+      --pipeline N       Keep up to N commit operations in flight over an
+                         async connection, instead of one at a time.
+      --dry-run          (Only with --in:) Print the operations a commit
+                         would perform without changing the directory.
+      --track-uuid       Request entryUUID and use it (falling back to DN
+                         when absent) to match entries during diffing, so
+                         a rename is detected even if entries are also
+                         reordered or edited in the same session.
+      --dump-filter      Parse FILTER, pretty-print its structure, and
+                         exit without connecting to a server.
+      --generate-entryuuid
+                         Synthesize an entryUUID for each added entry that
+                         lacks one, unless the server's root DSE already
+                         advertises its own entryUUID plugin.
+      --chase-referrals Follow ldap://host/dn referrals and continuation
+                         references returned during a search or commit,
+                         instead of leaving them unresolved.  A profile
+                         spells this 'chase-referrals:'.
+      --referral-hop-limit N
+                         Referral hops a search will follow before giving
+                         up.  Default 5.
 
 Shortcuts:
       --ldapsearch       Short for --quiet --out
@@ -139,6 +257,19 @@ Environment variables: VISUAL, EDITOR, PAGER.
     concatenation of all search results.  Conflicts with --base.
     With --config, show a BASE configuration line for each context.
 
+[3] EXPR is a parenthesized predicate, e.g. (present mail) or
+    (class person), combined with (and ...), (or ...), (not ...):
+      --select "(and (present mail) (not (under ou=archived,dc=acme,dc=com)))"
+
+[4] A profile (see --profile) may carry its own transport security
+    settings instead of repeating them on every invocation:
+      tls: starttls|ldaps|off
+      tls-reqcert: never|allow|demand
+      tls-cacert: PATH
+      tls-cert: PATH
+      tls-key: PATH
+    A CLI flag always overrides the profile's setting for the same thing.
+
 A special (offline) option is --diff, which compares two files
 and writes any changes to standard output in LDIF format.
 
@@ -157,6 +288,7 @@ const OPTION_LDAPMODIFY: i32 = 1011;
 const OPTION_LDAPDELETE: i32 = 1012;
 const OPTION_LDAPMODDN: i32 = 1013;
 const OPTION_LDAPMODRDN: i32 = 1014;
+const OPTION_RENAME_BULK: i32 = 1015;
 
 fn build_options() -> OptionTable {
     OptionTable::new()
@@ -167,7 +299,8 @@ fn build_options() -> OptionTable {
         // Configuration profile
         .option(Opt::new("profile").short('p').arg_type(ArgType::String))
         // Connection options
-        .option(Opt::new("host").short('h').arg_type(ArgType::String))
+        .option(Opt::new("host").short('h').arg_type(ArgType::Argv))
+        .option(Opt::new("server").arg_type(ArgType::Argv).store_as("host"))
         .option(Opt::new("user").short('D').arg_type(ArgType::String))
         .option(Opt::new("password").short('w').arg_type(ArgType::String))
         .option(
@@ -199,6 +332,7 @@ fn build_options() -> OptionTable {
         .option(Opt::new("sasl-mech").short('Y').arg_type(ArgType::String))
         // Search parameters
         .option(Opt::new("base").short('b').arg_type(ArgType::Argv))
+        .option(Opt::new("base-add").arg_type(ArgType::Argv))
         .option(Opt::new("scope").short('s').arg_type(ArgType::String))
         .option(Opt::new("sort").short('S').arg_type(ArgType::String))
         // Miscellaneous flag options
@@ -219,12 +353,27 @@ fn build_options() -> OptionTable {
         .option(Opt::new("noninteractive").short('!'))
         .option(Opt::new("quiet").short('q'))
         .option(Opt::new("read").short('R').arg_type(ArgType::String))
+        .option(Opt::new("select").arg_type(ArgType::String))
         .option(Opt::new("starttls").short('Z'))
         .option(Opt::new("tls").arg_type(ArgType::String))
+        .option(Opt::new("tls-mode").arg_type(ArgType::String))
+        .option(Opt::new("tls-cacert").arg_type(ArgType::String))
+        .option(Opt::new("tls-cert").arg_type(ArgType::String))
+        .option(Opt::new("tls-key").arg_type(ArgType::String))
         .option(Opt::new("verbose").short('v'))
+        .option(Opt::new("commit-report-json"))
+        .option(Opt::new("pipeline").arg_type(ArgType::Int))
+        .option(Opt::new("retry").arg_type(ArgType::Int))
+        .option(Opt::new("dry-run"))
+        .option(Opt::new("track-uuid"))
+        .option(Opt::new("dump-filter"))
+        .option(Opt::new("generate-entryuuid"))
+        .option(Opt::new("chase-referrals"))
+        .option(Opt::new("referral-hop-limit").arg_type(ArgType::Int))
         // Format options (simple flags)
         .option(Opt::new("ldif"))
         .option(Opt::new("ldapvi"))
+        .option(Opt::new("netencode"))
         // Mode options (VAL)
         .option(Opt::val("out", OPTION_OUT).store_as("mode"))
         .option(Opt::val("ldapsearch", OPTION_LDAPSEARCH).store_as("mode"))
@@ -236,6 +385,7 @@ fn build_options() -> OptionTable {
         .option(Opt::val("ldapmoddn", OPTION_LDAPMODDN).store_as("mode"))
         .option(Opt::val("modrdn", OPTION_MODRDN).store_as("mode"))
         .option(Opt::val("ldapmodrdn", OPTION_LDAPMODRDN).store_as("mode"))
+        .option(Opt::val("rename-bulk", OPTION_RENAME_BULK).store_as("mode"))
 }
 
 fn parse_scope(s: &str) -> Result<ldap3::Scope, String> {
@@ -257,23 +407,156 @@ fn parse_deref(s: &str) -> Result<i32, String> {
     }
 }
 
-/// Search config file content for a named profile.
-/// Returns Ok(Some(entry)) if found, Ok(None) if not found,
-/// Err on parse error or duplicate profile.
+/// Parse the four documented `--tls` names plus `demand`, the OpenLDAP/
+/// FreeRADIUS synonym for `strict`.
+fn parse_tls(s: &str) -> Result<TlsRequireCert, String> {
+    match s {
+        "never" => Ok(TlsRequireCert::Never),
+        "allow" => Ok(TlsRequireCert::Allow),
+        "try" => Ok(TlsRequireCert::Try),
+        "strict" | "demand" => Ok(TlsRequireCert::Strict),
+        _ => Err(format!(
+            "invalid --tls mode: {} (expected never, allow, try, strict, demand)",
+            s
+        )),
+    }
+}
+
+/// Parse a profile's `tls:`/`--tls-mode` transport selector.
+fn parse_tls_mode(s: &str) -> Result<TlsMode, String> {
+    match s {
+        "off" => Ok(TlsMode::Off),
+        "starttls" => Ok(TlsMode::Starttls),
+        "ldaps" => Ok(TlsMode::Ldaps),
+        _ => Err(format!(
+            "invalid tls mode: {} (expected starttls, ldaps, or off)",
+            s
+        )),
+    }
+}
+
+/// Parse the comma-separated token list accepted by `-O`/`--sasl-secprops`:
+/// the named feature toggles Cyrus SASL recognizes (`none`, `nodict`,
+/// `noplain`, `noactive`, `passcred`, `forwardsec`, `noanonymous`), plus
+/// `minssf=N`, `maxssf=N`, and `maxbufsize=N`.
+fn parse_secprops(s: &str) -> Result<SaslSecprops, String> {
+    let mut props = SaslSecprops::default();
+    for tok in s.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if let Some((key, value)) = tok.split_once('=') {
+            let n: u32 = value
+                .parse()
+                .map_err(|_| format!("invalid SASL security property: {}", tok))?;
+            match key {
+                "minssf" => props.minssf = Some(n),
+                "maxssf" => props.maxssf = Some(n),
+                "maxbufsize" => props.maxbufsize = Some(n),
+                _ => return Err(format!("invalid SASL security property: {}", tok)),
+            }
+        } else {
+            match tok {
+                "none" => props.none = true,
+                "nodict" => props.nodict = true,
+                "noplain" => props.noplain = true,
+                "noactive" => props.noactive = true,
+                "passcred" => props.passcred = true,
+                "forwardsec" => props.forwardsec = true,
+                "noanonymous" => props.noanonymous = true,
+                _ => return Err(format!("invalid SASL security property: {}", tok)),
+            }
+        }
+    }
+    Ok(props)
+}
+
+/// Parse a `-U/--sasl-authcid` or `-X/--sasl-authzid` value per the
+/// OpenLDAP authzid grammar: `u:<username>`, `dn:<dn>`, `dn.exact:<dn>`
+/// (normalized via [`ldapvi::dn::parse_dn`]/[`ldapvi::dn::encode_dn`]), or
+/// `dn.regex:<pattern>` (compile-checked so a bad pattern fails here rather
+/// than at bind time). Rejects anything without one of those prefixes.
+fn parse_authzid(s: &str) -> Result<AuthzId, String> {
+    if let Some(rest) = s.strip_prefix("u:") {
+        return Ok(AuthzId::User(rest.to_string()));
+    }
+    if let Some(rest) = s.strip_prefix("dn.exact:") {
+        let rdns = ldapvi::dn::parse_dn(rest).map_err(|e| format!("invalid DN '{}': {}", rest, e))?;
+        return Ok(AuthzId::Dn {
+            value: ldapvi::dn::encode_dn(&rdns),
+            match_type: DnMatchType::Exact,
+        });
+    }
+    if let Some(rest) = s.strip_prefix("dn.regex:") {
+        regex::Regex::new(rest).map_err(|e| format!("invalid regex '{}': {}", rest, e))?;
+        return Ok(AuthzId::Dn {
+            value: rest.to_string(),
+            match_type: DnMatchType::Regex,
+        });
+    }
+    if let Some(rest) = s.strip_prefix("dn:") {
+        return Ok(AuthzId::Dn {
+            value: rest.to_string(),
+            match_type: DnMatchType::Exact,
+        });
+    }
+    Err(format!(
+        "invalid authzid/authcid '{}': expected a u:, dn:, dn.exact:, or dn.regex: prefix",
+        s
+    ))
+}
+
+/// Search config file content for a named profile. Alias records are
+/// skipped (see [`find_alias`]); they live in the same file but don't
+/// participate in profile lookup. Returns Ok(Some(entry)) if found,
+/// Ok(None) if not found, Err on parse error or duplicate profile.
 fn find_profile(content: &[u8], name: &str) -> Result<Option<Entry>, String> {
-    let mut parser = LdapviParser::new(Cursor::new(content));
     let mut found: Option<Entry> = None;
 
-    loop {
-        match parser.read_profile() {
-            Ok(Some(entry)) => {
-                if entry.dn == name {
-                    if found.is_some() {
-                        return Err(format!("Duplicate configuration profile '{}'.", name));
-                    }
-                    found = Some(entry);
+    for entry in read_config_entries(content)? {
+        if let ConfigEntry::Profile(entry) = entry {
+            if entry.dn == name {
+                if found.is_some() {
+                    return Err(format!("Duplicate configuration profile '{}'.", name));
+                }
+                found = Some(entry);
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// Search config file content for a named `alias NAME = ARGS...` record,
+/// returning its expansion as argv tokens. Returns Ok(Some(expansion)) if
+/// found, Ok(None) if not found, Err on parse error or duplicate alias.
+fn find_alias(content: &[u8], name: &str) -> Result<Option<Vec<String>>, String> {
+    let mut found: Option<Vec<String>> = None;
+
+    for entry in read_config_entries(content)? {
+        if let ConfigEntry::Alias {
+            name: alias_name,
+            expansion,
+        } = entry
+        {
+            if alias_name == name {
+                if found.is_some() {
+                    return Err(format!("Duplicate configuration alias '{}'.", name));
                 }
+                found = Some(expansion);
             }
+        }
+    }
+
+    Ok(found)
+}
+
+/// Read every top-level record (profile or alias) out of a configuration
+/// file's raw content.
+fn read_config_entries(content: &[u8]) -> Result<Vec<ConfigEntry>, String> {
+    let mut parser = LdapviParser::new(Cursor::new(content));
+    let mut entries = Vec::new();
+
+    loop {
+        match parser.read_config_entry() {
+            Ok(Some(entry)) => entries.push(entry),
             Ok(None) => break,
             Err(_) => {
                 return Err("Error in configuration file, giving up.".to_string());
@@ -281,7 +564,89 @@ fn find_profile(content: &[u8], name: &str) -> Result<Option<Entry>, String> {
         }
     }
 
-    Ok(found)
+    Ok(entries)
+}
+
+/// Resolve a profile by name, walking an `inherit:`/`parent:` chain so
+/// one profile can extend another (or several others, via repeated
+/// `inherit:`/`parent:` lines): walking the chain from each named base
+/// toward `name`, a single-valued key (read with [`profile_get`], which
+/// takes the *last* value) is effectively overridden by whichever
+/// profile defines it closest to `name`, while a multi-valued key (read
+/// with [`profile_get_all`]) accumulates values from every profile in
+/// the chain in that same base-to-`name` order. This mirrors the layered
+/// override model of the OpenLDAP config backend. Cycles are rejected;
+/// a profile reached twice down the *same* branch is a cycle, but a
+/// diamond (two parents sharing a common ancestor) is not.
+fn resolve_profile(content: &[u8], name: &str) -> Result<Option<Entry>, String> {
+    resolve_profile_along(content, name, &[])
+}
+
+fn resolve_profile_along(
+    content: &[u8],
+    name: &str,
+    path: &[String],
+) -> Result<Option<Entry>, String> {
+    if path.iter().any(|p| p == name) {
+        return Err(format!("Profile inheritance cycle detected at '{}'.", name));
+    }
+    let entry = match find_profile(content, name)? {
+        Some(e) => e,
+        None => return Ok(None),
+    };
+
+    let mut parents = profile_get_all(&entry, "inherit");
+    if parents.is_empty() {
+        parents = profile_get_all(&entry, "parent");
+    }
+
+    let mut next_path = path.to_vec();
+    next_path.push(name.to_string());
+
+    let mut merged = Entry {
+        dn: name.to_string(),
+        attributes: Vec::new(),
+    };
+    for parent_name in &parents {
+        match resolve_profile_along(content, parent_name, &next_path)? {
+            Some(parent_entry) => merge_profile_attrs(&mut merged, &parent_entry),
+            None => {
+                return Err(format!(
+                    "Configuration profile '{}' inherits from unknown profile '{}'.",
+                    name, parent_name
+                ));
+            }
+        }
+    }
+    merge_profile_attrs(&mut merged, &entry);
+
+    Ok(Some(merged))
+}
+
+/// Fold `source`'s attributes into `target`: a key already present in
+/// `target` gets `source`'s values appended (so the last writer's values
+/// sort last, which is what both single- and multi-valued readers need),
+/// a new key is added outright. `inherit`/`parent` are never data values.
+fn merge_profile_attrs(target: &mut Entry, source: &Entry) {
+    for attr in &source.attributes {
+        if attr.ad == "inherit" || attr.ad == "parent" {
+            continue;
+        }
+        match target.attributes.iter_mut().find(|a| a.ad == attr.ad) {
+            Some(existing) => existing.values.extend(attr.values.iter().cloned()),
+            None => target.attributes.push(attr.clone()),
+        }
+    }
+}
+
+/// Locate ldapvi's own configuration file: ~/.ldapvirc first, then
+/// /etc/ldapvi.conf. Shared by profile lookup and alias expansion, which
+/// both read records out of the same file.
+fn find_config_file_content() -> Option<Vec<u8>> {
+    std::env::var("HOME")
+        .ok()
+        .and_then(|home| std::fs::read(format!("{}/.ldapvirc", home)).ok())
+        .or_else(|| std::fs::read("/etc/ldapvi.conf").ok())
 }
 
 /// Read ~/.ldapvirc (or /etc/ldapvi.conf), find the named profile
@@ -289,11 +654,7 @@ fn find_profile(content: &[u8], name: &str) -> Result<Option<Entry>, String> {
 fn parse_configuration(profile_name: Option<&str>) -> Option<Entry> {
     let name = profile_name.unwrap_or("default");
 
-    // Try ~/.ldapvirc first, then /etc/ldapvi.conf
-    let content = std::env::var("HOME")
-        .ok()
-        .and_then(|home| std::fs::read(format!("{}/.ldapvirc", home)).ok())
-        .or_else(|| std::fs::read("/etc/ldapvi.conf").ok());
+    let content = find_config_file_content();
 
     let content = match content {
         Some(c) => c,
@@ -306,7 +667,7 @@ fn parse_configuration(profile_name: Option<&str>) -> Option<Entry> {
         }
     };
 
-    match find_profile(&content, name) {
+    match resolve_profile(&content, name) {
         Ok(found) => {
             if found.is_none() && profile_name.is_some() {
                 eprintln!("Error: Configuration profile not found: '{}'.", name);
@@ -348,11 +709,125 @@ fn profile_get_all(profile: &Entry, key: &str) -> Vec<String> {
     vec![]
 }
 
+/// Split a list of `--host`/`--server` occurrences into individual URIs,
+/// further breaking each occurrence apart on commas and whitespace so
+/// `--host "ldap://a ldap://b,ldap://c"` and three repeated `--host`
+/// flags are equivalent.
+fn split_server_list(occurrences: &[String]) -> Vec<String> {
+    occurrences
+        .iter()
+        .flat_map(|s| s.split([',', ' ', '\t']))
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
 /// Check whether a profile has a boolean "yes" value for a key.
 fn profile_get_bool(profile: &Entry, key: &str) -> bool {
     profile_get(profile, key).as_deref() == Some("yes")
 }
 
+/// Parse one `ldap.conf(5)`-format file: `KEYWORD value` per line,
+/// `#` comments, blank lines ignored, keywords case-insensitive and
+/// folded to uppercase. A later occurrence of a keyword overrides an
+/// earlier one, matching libldap's own single-valued semantics.
+fn parse_ldap_conf_file(content: &str) -> std::collections::HashMap<String, String> {
+    let mut values = std::collections::HashMap::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((keyword, value)) = line.split_once(char::is_whitespace) {
+            values.insert(keyword.to_ascii_uppercase(), value.trim().to_string());
+        }
+    }
+    values
+}
+
+/// Load genuine libldap configuration: the system-wide file
+/// (`/etc/ldap/ldap.conf`, or `$LDAPCONF` if set) first, then the
+/// per-user file (`$HOME/.ldaprc`, or `$LDAPRC` if set) layered on top of
+/// it, and finally the handful of `LDAP*` environment variables that
+/// libldap lets override individual keywords. This lets ldapvi share
+/// defaults with ldapsearch/ldapmodify/etc. instead of only understanding
+/// its own `~/.ldapvirc` profile format.
+fn load_ldap_conf() -> std::collections::HashMap<String, String> {
+    let mut values = std::collections::HashMap::new();
+
+    let system_path =
+        std::env::var("LDAPCONF").unwrap_or_else(|_| "/etc/ldap/ldap.conf".to_string());
+    if let Ok(content) = std::fs::read_to_string(&system_path) {
+        values.extend(parse_ldap_conf_file(&content));
+    }
+
+    let user_path = std::env::var("LDAPRC").ok().or_else(|| {
+        std::env::var("HOME")
+            .ok()
+            .map(|home| format!("{}/.ldaprc", home))
+    });
+    if let Some(user_path) = user_path {
+        if let Ok(content) = std::fs::read_to_string(&user_path) {
+            values.extend(parse_ldap_conf_file(&content));
+        }
+    }
+
+    for (keyword, env_name) in [
+        ("URI", "LDAPURI"),
+        ("BASE", "LDAPBASE"),
+        ("BINDDN", "LDAPBINDDN"),
+        ("DEREF", "LDAPDEREF"),
+        ("TLS_REQCERT", "LDAPTLS_REQCERT"),
+        ("TLS_CACERT", "LDAPTLS_CACERT"),
+        ("SASL_MECH", "LDAPSASL_MECH"),
+        ("SASL_REALM", "LDAPSASL_REALM"),
+        ("SASL_SECPROPS", "LDAPSASL_SECPROPS"),
+    ] {
+        if let Ok(value) = std::env::var(env_name) {
+            values.insert(keyword.to_string(), value);
+        }
+    }
+
+    values
+}
+
+/// Expand a leading alias token in `args` using `alias NAME = ARGS...`
+/// config records (see `find_alias`), the same way
+/// `ldapvi fixmail` becomes `ldapvi --profile prod --base
+/// ou=people,dc=x,dc=com "(mail=*)"` for a config file declaring `alias
+/// fixmail = --profile prod --base ou=people,dc=x,dc=com "(mail=*)"`.
+/// Only the first, non-option token is ever considered an alias; anything
+/// already following it is spliced in *after* the expansion, so a CLI
+/// flag repeating one the alias also sets is parsed later and wins, just
+/// as a CLI base already overrides a profile base. `seen` tracks alias
+/// names already expanded down this chain and rejects a repeat as a
+/// cycle.
+fn expand_alias(
+    content: &[u8],
+    args: Vec<String>,
+    seen: &mut Vec<String>,
+) -> Result<Vec<String>, String> {
+    let Some(first) = args.first() else {
+        return Ok(args);
+    };
+    if first.starts_with('-') {
+        return Ok(args);
+    }
+    if seen.iter().any(|name| name == first) {
+        return Err(format!("Alias expansion cycle detected at '{}'.", first));
+    }
+    match find_alias(content, first)? {
+        Some(expansion) => {
+            seen.push(first.clone());
+            let mut expanded = expansion;
+            expanded.extend(args[1..].iter().cloned());
+            expand_alias(content, expanded, seen)
+        }
+        None => Ok(args),
+    }
+}
+
 pub fn parse_args() -> Result<Cmdline, String> {
     // Check for --help/-H before popt parsing
     for arg in std::env::args().skip(1) {
@@ -362,6 +837,12 @@ pub fn parse_args() -> Result<Cmdline, String> {
         }
     }
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = match find_config_file_content() {
+        Some(content) => expand_alias(&content, args, &mut Vec::new())?,
+        None => args,
+    };
+
     let opts = build_options();
 
     let mut ctx = Context::builder("ldapvi")
@@ -369,7 +850,7 @@ pub fn parse_args() -> Result<Cmdline, String> {
         .build()
         .map_err(|e| format!("{}", e))?;
 
-    ctx.parse().map_err(|e| format!("{}", e))?;
+    ctx.parse_args(args).map_err(|e| format!("{}", e))?;
 
     // Handle --help after parse (for combined short flags)
     if ctx.is_present("help") {
@@ -392,6 +873,17 @@ pub fn parse_args() -> Result<Cmdline, String> {
     let profile_name: Option<String> = ctx.get("profile").ok();
     let profile = parse_configuration(profile_name.as_deref());
 
+    // Real libldap ldap.conf/ldaprc, read only when requested: lowest
+    // precedence of the three, below both the CLI and the ldapvi profile.
+    let ldap_conf = if ctx.is_present("ldap-conf") {
+        Some(load_ldap_conf())
+    } else {
+        None
+    };
+    let ldap_conf_val = |keyword: &str| -> Option<String> {
+        ldap_conf.as_ref().and_then(|m| m.get(keyword).cloned())
+    };
+
     // Helper: get CLI string, falling back to profile value
     let cli_or_profile = |cli_key: &str, profile_key: &str| -> Option<String> {
         ctx.get(cli_key)
@@ -417,7 +909,7 @@ pub fn parse_args() -> Result<Cmdline, String> {
 
     // Extract deref
     let deref = {
-        let deref_str = cli_or_profile("deref", "deref");
+        let deref_str = cli_or_profile("deref", "deref").or_else(|| ldap_conf_val("DEREF"));
         match deref_str {
             Some(s) => parse_deref(&s)?,
             None => 0,
@@ -432,6 +924,7 @@ pub fn parse_args() -> Result<Cmdline, String> {
         OPTION_DELETE | OPTION_LDAPDELETE => Mode::Delete,
         OPTION_RENAME | OPTION_LDAPMODDN => Mode::Rename,
         OPTION_MODRDN | OPTION_LDAPMODRDN => Mode::Modrdn,
+        OPTION_RENAME_BULK => Mode::RenameBulk,
         _ => Mode::Edit,
     };
 
@@ -457,15 +950,50 @@ pub fn parse_args() -> Result<Cmdline, String> {
 
     // Format
     let ldif = cli_or_profile_bool("ldif", "ldif");
-
-    // Basedns: CLI --base overrides profile bases (not additive)
+    let netencode = ctx.is_present("netencode");
+
+    // Basedns: CLI --base overrides profile bases (not additive). A
+    // profile's `base+:` key and the CLI `--base-add` flag are additive
+    // instead: they union onto whatever --base/profile/ldap.conf already
+    // resolved rather than replacing it, letting a single run keep the
+    // profile's bases and add one more. Order is deterministic: the
+    // replace-or-inherit result first, then the profile's `base+:`
+    // additions, then any CLI `--base-add` additions last.
     let cli_basedns: Vec<String> = ctx.get("base").unwrap_or_default();
     let mut basedns = if !cli_basedns.is_empty() {
         cli_basedns
     } else {
+        let from_profile = profile
+            .as_ref()
+            .map_or_else(Vec::new, |p| profile_get_all(p, "base"));
+        if !from_profile.is_empty() {
+            from_profile
+        } else {
+            ldap_conf_val("BASE").into_iter().collect()
+        }
+    };
+    if let Some(ref p) = profile {
+        basedns.extend(profile_get_all(p, "base+"));
+    }
+    let cli_base_add: Vec<String> = ctx.get("base-add").unwrap_or_default();
+    basedns.extend(cli_base_add);
+
+    // Servers: CLI --host/--server overrides profile host (not additive).
+    // Each occurrence may itself be a comma- or space-separated list.
+    let cli_servers: Vec<String> = ctx.get("host").unwrap_or_default();
+    let servers = if !cli_servers.is_empty() {
+        split_server_list(&cli_servers)
+    } else if profile
+        .as_ref()
+        .and_then(|p| profile_get(p, "host"))
+        .is_some()
+    {
         profile
             .as_ref()
-            .map_or_else(Vec::new, |p| profile_get_all(p, "base"))
+            .and_then(|p| profile_get(p, "host"))
+            .map_or_else(Vec::new, |s| split_server_list(&[s]))
+    } else {
+        ldap_conf_val("URI").map_or_else(Vec::new, |s| split_server_list(&[s]))
     };
 
     // Classes (repeatable -o)
@@ -501,6 +1029,14 @@ pub fn parse_args() -> Result<Cmdline, String> {
                 attrs = positional[1..].to_vec();
             }
         }
+        Mode::RenameBulk => {
+            if let Some(f) = positional.first() {
+                filter = f.clone();
+            }
+            if positional.len() > 1 {
+                return Err("too many command line arguments".to_string());
+            }
+        }
         Mode::Delete => {
             delete_dns = positional;
         }
@@ -519,6 +1055,11 @@ pub fn parse_args() -> Result<Cmdline, String> {
         }
     }
 
+    // Validate the server-side LDAP filter client-side (RFC 4515), the
+    // same way `select` is validated eagerly below -- a typo is caught
+    // here instead of surfacing as a server round trip failure later.
+    ldapvi::ldapfilter::parse(&filter).map_err(|e| format!("invalid search filter: {}", e))?;
+
     // Password file handling
     let password_file: Option<String> = ctx.get("password-file").ok();
     let mut password: Option<String> = ctx
@@ -542,17 +1083,55 @@ pub fn parse_args() -> Result<Cmdline, String> {
     // --class implies --empty
     let empty = cli_or_profile_bool("empty", "empty") || !classes.is_empty();
 
+    // --tls-mode/a profile's `tls:` picks the transport (starttls/ldaps/
+    // off); `starttls` folds both that and -Z/--starttls into one flag,
+    // same as the rest of the connection code already expects.
+    let tls_mode = match cli_or_profile("tls-mode", "tls") {
+        Some(s) => Some(parse_tls_mode(&s)?),
+        None => None,
+    };
+    let starttls = cli_or_profile_bool("starttls", "starttls") || tls_mode == Some(TlsMode::Starttls);
+    let tls_cacert = cli_or_profile("tls-cacert", "tls-cacert");
+    let tls_cert = cli_or_profile("tls-cert", "tls-cert");
+    let tls_key = cli_or_profile("tls-key", "tls-key");
+
+    // --tls mode (certificate strictness; a profile spells this
+    // `tls-reqcert:` to avoid colliding with the transport-selecting
+    // `tls:` key above), and its interaction with -Z/--starttls: StartTLS
+    // exists to establish certificate-verified transport security, so
+    // pairing it with `--tls never` (no certificate check at all) is a
+    // contradiction rather than a meaningful combination. Likewise for
+    // `tls: ldaps`, which already negotiates TLS at connect time.
+    let tls = match cli_or_profile("tls", "tls-reqcert").or_else(|| ldap_conf_val("TLS_REQCERT")) {
+        Some(s) => Some(parse_tls(&s)?),
+        None => None,
+    };
+    if starttls && tls == Some(TlsRequireCert::Never) {
+        return Err("--starttls and --tls never are contradictory".to_string());
+    }
+    if starttls && tls_mode == Some(TlsMode::Ldaps) {
+        return Err("--starttls and 'tls: ldaps' are contradictory".to_string());
+    }
+
+    // --select: validate eagerly, same as the other small DSLs above, but
+    // store the raw text -- it's parsed again where it's applied, the
+    // same way `filter` (the server-side LDAP filter string) is.
+    let select = cli_or_profile("select", "select");
+    if let Some(ref s) = select {
+        ldapvi::filter::parse(s)?;
+    }
+
     Ok(Cmdline {
-        server: cli_or_profile("host", "host"),
+        servers,
         basedns,
         scope,
         filter,
         attrs,
-        user: cli_or_profile("user", "user"),
+        user: cli_or_profile("user", "user").or_else(|| ldap_conf_val("BINDDN")),
         password,
         quiet,
         discover: cli_or_profile_bool("discover", "discover"),
-        starttls: cli_or_profile_bool("starttls", "starttls"),
+        starttls,
         deref,
         managedsait: cli_or_profile_bool("managedsait", "managedsait"),
         continuous: cli_or_profile_bool("continuous", "continuous"),
@@ -569,7 +1148,25 @@ pub fn parse_args() -> Result<Cmdline, String> {
         rename_new,
         deleteoldrdn: ctx.is_present("deleteoldrdn"),
         bind,
-        tls: cli_or_profile("tls", "tls"),
+        sasl_mech: cli_or_profile("sasl-mech", "sasl-mech").or_else(|| ldap_conf_val("SASL_MECH")),
+        sasl_authcid: cli_or_profile("sasl-authcid", "sasl-authcid")
+            .map(|s| parse_authzid(&s))
+            .transpose()?,
+        sasl_authzid: cli_or_profile("sasl-authzid", "sasl-authzid")
+            .map(|s| parse_authzid(&s))
+            .transpose()?,
+        sasl_realm: cli_or_profile("sasl-realm", "sasl-realm").or_else(|| ldap_conf_val("SASL_REALM")),
+        sasl_secprops: match cli_or_profile("sasl-secprops", "sasl-secprops")
+            .or_else(|| ldap_conf_val("SASL_SECPROPS"))
+        {
+            Some(s) => parse_secprops(&s)?,
+            None => SaslSecprops::default(),
+        },
+        tls,
+        tls_mode,
+        tls_cacert,
+        tls_cert,
+        tls_key,
         password_file,
         schema_comments: cli_or_profile_bool("may", "may"),
         config: ctx.is_present("config"),
@@ -578,6 +1175,17 @@ pub fn parse_args() -> Result<Cmdline, String> {
         classes,
         ldap_conf: ctx.is_present("ldap-conf"),
         profile: profile_name,
+        commit_report_json: ctx.is_present("commit-report-json"),
+        pipeline_depth: ctx.get::<i32>("pipeline").ok().map(|n| n.max(0) as usize),
+        retry: ctx.get::<i32>("retry").ok().map_or(3, |n| n.max(0) as u32),
+        dry_run: ctx.is_present("dry-run"),
+        select,
+        netencode,
+        track_uuid: ctx.is_present("track-uuid"),
+        dump_filter: ctx.is_present("dump-filter"),
+        generate_entryuuid: ctx.is_present("generate-entryuuid"),
+        chase_referrals: cli_or_profile_bool("chase-referrals", "chase-referrals"),
+        referral_hop_limit: ctx.get::<i32>("referral-hop-limit").ok().map_or(5, |n| n.max(0) as u32),
     })
 }
 
@@ -643,6 +1251,248 @@ mod tests {
         assert_eq!(profile_get(&entry, "host").as_deref(), Some("beta-host"));
     }
 
+    // -- find_alias / expand_alias --
+
+    #[test]
+    fn find_named_alias() {
+        let config = b"alias fixmail = --profile prod \"(mail=*)\"\n";
+        let expansion = find_alias(config, "fixmail").unwrap().unwrap();
+        assert_eq!(expansion, vec!["--profile", "prod", "(mail=*)"]);
+    }
+
+    #[test]
+    fn find_alias_not_found() {
+        let config = b"alias other = --profile prod\n";
+        assert!(find_alias(config, "fixmail").unwrap().is_none());
+    }
+
+    #[test]
+    fn find_alias_among_profiles() {
+        let config = b"profile prod\n\
+                        host: prod-host\n\
+                        \n\
+                        alias fixmail = --profile prod\n";
+        let expansion = find_alias(config, "fixmail").unwrap().unwrap();
+        assert_eq!(expansion, vec!["--profile", "prod"]);
+        assert!(find_profile(config, "prod").unwrap().is_some());
+    }
+
+    #[test]
+    fn find_alias_duplicate_is_error() {
+        let config = b"alias dup = --profile first\n\
+                        alias dup = --profile second\n";
+        assert!(find_alias(config, "dup").is_err());
+    }
+
+    #[test]
+    fn expand_alias_splices_expansion_before_remaining_args() {
+        let config = b"alias fixmail = --profile prod --base ou=people,dc=x,dc=com\n";
+        let args = vec!["fixmail".to_string(), "--verbose".to_string()];
+        let expanded = expand_alias(config, args, &mut Vec::new()).unwrap();
+        assert_eq!(
+            expanded,
+            vec!["--profile", "prod", "--base", "ou=people,dc=x,dc=com", "--verbose"]
+        );
+    }
+
+    #[test]
+    fn expand_alias_leaves_options_alone() {
+        let config = b"alias fixmail = --profile prod\n";
+        let args = vec!["--profile".to_string(), "other".to_string()];
+        let expanded = expand_alias(config, args.clone(), &mut Vec::new()).unwrap();
+        assert_eq!(expanded, args);
+    }
+
+    #[test]
+    fn expand_alias_leaves_unknown_first_token_alone() {
+        let config = b"alias fixmail = --profile prod\n";
+        let args = vec!["some-dn".to_string()];
+        let expanded = expand_alias(config, args.clone(), &mut Vec::new()).unwrap();
+        assert_eq!(expanded, args);
+    }
+
+    #[test]
+    fn expand_alias_chains_through_another_alias() {
+        let config = b"alias a = b --extra\n\
+                        alias b = --profile prod\n";
+        let args = vec!["a".to_string()];
+        let expanded = expand_alias(config, args, &mut Vec::new()).unwrap();
+        assert_eq!(expanded, vec!["--profile", "prod", "--extra"]);
+    }
+
+    #[test]
+    fn expand_alias_rejects_cycle() {
+        let config = b"alias a = b\n\
+                        alias b = a\n";
+        let args = vec!["a".to_string()];
+        assert!(expand_alias(config, args, &mut Vec::new()).is_err());
+    }
+
+    // -- resolve_profile (inheritance) --
+
+    #[test]
+    fn resolve_profile_inherits_missing_attributes() {
+        let config = b"profile base\n\
+                        host: base-host\n\
+                        base: dc=example,dc=com\n\
+                        \n\
+                        profile child\n\
+                        inherit: base\n\
+                        host: child-host\n\
+                        \n";
+        let entry = resolve_profile(config, "child").unwrap().unwrap();
+        assert_eq!(profile_get(&entry, "host").as_deref(), Some("child-host"));
+        assert_eq!(
+            profile_get(&entry, "base").as_deref(),
+            Some("dc=example,dc=com")
+        );
+    }
+
+    #[test]
+    fn resolve_profile_supports_parent_alias() {
+        let config = b"profile base\n\
+                        host: base-host\n\
+                        \n\
+                        profile child\n\
+                        parent: base\n\
+                        \n";
+        let entry = resolve_profile(config, "child").unwrap().unwrap();
+        assert_eq!(profile_get(&entry, "host").as_deref(), Some("base-host"));
+    }
+
+    #[test]
+    fn resolve_profile_walks_multiple_ancestors() {
+        let config = b"profile grandparent\n\
+                        host: gp-host\n\
+                        \n\
+                        profile parent\n\
+                        inherit: grandparent\n\
+                        \n\
+                        profile child\n\
+                        inherit: parent\n\
+                        \n";
+        let entry = resolve_profile(config, "child").unwrap().unwrap();
+        assert_eq!(profile_get(&entry, "host").as_deref(), Some("gp-host"));
+    }
+
+    #[test]
+    fn resolve_profile_rejects_cycle() {
+        let config = b"profile a\n\
+                        inherit: b\n\
+                        \n\
+                        profile b\n\
+                        inherit: a\n\
+                        \n";
+        let err = resolve_profile(config, "a").unwrap_err();
+        assert!(err.contains("cycle"));
+    }
+
+    #[test]
+    fn resolve_profile_rejects_unknown_parent() {
+        let config = b"profile child\n\
+                        inherit: nonexistent\n\
+                        \n";
+        assert!(resolve_profile(config, "child").is_err());
+    }
+
+    #[test]
+    fn resolve_profile_not_found_without_inheritance() {
+        assert!(resolve_profile(b"", "missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn resolve_profile_multiple_inherit_lines_compose_in_order() {
+        let config = b"profile alpha\n\
+                        ad: cn\n\
+                        \n\
+                        profile beta\n\
+                        ad: sn\n\
+                        \n\
+                        profile child\n\
+                        inherit: alpha\n\
+                        inherit: beta\n\
+                        \n";
+        let entry = resolve_profile(config, "child").unwrap().unwrap();
+        assert_eq!(
+            profile_get_all(&entry, "ad"),
+            vec!["cn".to_string(), "sn".to_string()]
+        );
+    }
+
+    #[test]
+    fn resolve_profile_diamond_inheritance_is_not_a_cycle() {
+        let config = b"profile root\n\
+                        host: root-host\n\
+                        \n\
+                        profile left\n\
+                        inherit: root\n\
+                        \n\
+                        profile right\n\
+                        inherit: root\n\
+                        \n\
+                        profile child\n\
+                        inherit: left\n\
+                        inherit: right\n\
+                        \n";
+        let entry = resolve_profile(config, "child").unwrap().unwrap();
+        assert_eq!(profile_get(&entry, "host").as_deref(), Some("root-host"));
+    }
+
+    // -- base override logic (with inheritance) --
+
+    #[test]
+    fn base_override_multi_valued_keys_merge_across_inheritance_chain() {
+        let config = b"profile base\n\
+                        base: dc=example,dc=com\n\
+                        \n\
+                        profile child\n\
+                        inherit: base\n\
+                        base: ou=people,dc=example,dc=com\n\
+                        \n";
+        let entry = resolve_profile(config, "child").unwrap().unwrap();
+        assert_eq!(
+            profile_get_all(&entry, "base"),
+            vec![
+                "dc=example,dc=com".to_string(),
+                "ou=people,dc=example,dc=com".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn base_override_single_valued_key_replaces_wholesale() {
+        let config = b"profile base\n\
+                        host: base-host\n\
+                        \n\
+                        profile child\n\
+                        inherit: base\n\
+                        host: child-host\n\
+                        \n";
+        let entry = resolve_profile(config, "child").unwrap().unwrap();
+        assert_eq!(profile_get(&entry, "host").as_deref(), Some("child-host"));
+    }
+
+    #[test]
+    fn base_override_cli_replaces_inherited_profile_bases() {
+        let cli_basedns = vec!["dc=cli,dc=com".to_string()];
+        let config = b"profile base\n\
+                        base: dc=base,dc=com\n\
+                        \n\
+                        profile child\n\
+                        inherit: base\n\
+                        base: dc=child,dc=com\n\
+                        \n";
+        let profile = resolve_profile(config, "child").unwrap();
+        let basedns = if !cli_basedns.is_empty() {
+            cli_basedns
+        } else {
+            profile
+                .as_ref()
+                .map_or_else(Vec::new, |p| profile_get_all(p, "base"))
+        };
+        assert_eq!(basedns, vec!["dc=cli,dc=com"]);
+    }
+
     // -- profile_get / profile_get_all / profile_get_bool --
 
     #[test]
@@ -691,6 +1541,70 @@ mod tests {
         assert!(!profile_get_bool(&entry, "nonexistent"));
     }
 
+    // -- TLS profile settings --
+
+    #[test]
+    fn parse_tls_mode_accepts_known_values() {
+        assert_eq!(parse_tls_mode("off").unwrap(), TlsMode::Off);
+        assert_eq!(parse_tls_mode("starttls").unwrap(), TlsMode::Starttls);
+        assert_eq!(parse_tls_mode("ldaps").unwrap(), TlsMode::Ldaps);
+    }
+
+    #[test]
+    fn parse_tls_mode_rejects_unknown_value() {
+        assert!(parse_tls_mode("bogus").is_err());
+    }
+
+    #[test]
+    fn profile_tls_keys_are_read_independently() {
+        let config = b"profile default\n\
+                        tls: starttls\n\
+                        tls-reqcert: demand\n\
+                        tls-cacert: /etc/ssl/ca.pem\n\
+                        tls-cert: /etc/ssl/client.pem\n\
+                        tls-key: /etc/ssl/client.key\n\
+                        \n";
+        let profile = find_profile(config, "default").unwrap().unwrap();
+        assert_eq!(profile_get(&profile, "tls").as_deref(), Some("starttls"));
+        assert_eq!(profile_get(&profile, "tls-reqcert").as_deref(), Some("demand"));
+        assert_eq!(
+            profile_get(&profile, "tls-cacert").as_deref(),
+            Some("/etc/ssl/ca.pem")
+        );
+        assert_eq!(
+            profile_get(&profile, "tls-cert").as_deref(),
+            Some("/etc/ssl/client.pem")
+        );
+        assert_eq!(
+            profile_get(&profile, "tls-key").as_deref(),
+            Some("/etc/ssl/client.key")
+        );
+    }
+
+    #[test]
+    fn tls_mode_cli_replaces_profile() {
+        // Same precedence rule as --base: a CLI value (here --tls-mode)
+        // replaces the profile's `tls:` entirely rather than merging.
+        let cli_tls_mode: Option<String> = Some("ldaps".to_string());
+        let config = b"profile default\n\
+                        tls: starttls\n\
+                        \n";
+        let profile = find_profile(config, "default").unwrap();
+        let tls_mode = cli_tls_mode.or_else(|| profile.as_ref().and_then(|p| profile_get(p, "tls")));
+        assert_eq!(parse_tls_mode(&tls_mode.unwrap()).unwrap(), TlsMode::Ldaps);
+    }
+
+    #[test]
+    fn tls_mode_profile_only() {
+        let cli_tls_mode: Option<String> = None;
+        let config = b"profile default\n\
+                        tls: ldaps\n\
+                        \n";
+        let profile = find_profile(config, "default").unwrap();
+        let tls_mode = cli_tls_mode.or_else(|| profile.as_ref().and_then(|p| profile_get(p, "tls")));
+        assert_eq!(parse_tls_mode(&tls_mode.unwrap()).unwrap(), TlsMode::Ldaps);
+    }
+
     // -- base override logic --
     // These test the core rule: CLI bases replace profile bases.
 
@@ -794,4 +1708,170 @@ mod tests {
         };
         assert!(basedns.is_empty());
     }
+
+    // -- base+/--base-add additive resolution --
+    // These mirror the resolution block in parse_args: the replace-or-
+    // inherit result first, then the profile's `base+:`, then CLI
+    // `--base-add`, all without disturbing the --base replace default.
+
+    fn resolve_basedns(
+        cli_basedns: Vec<String>,
+        profile: &Option<Entry>,
+        cli_base_add: Vec<String>,
+    ) -> Vec<String> {
+        let mut basedns = if !cli_basedns.is_empty() {
+            cli_basedns
+        } else {
+            profile
+                .as_ref()
+                .map_or_else(Vec::new, |p| profile_get_all(p, "base"))
+        };
+        if let Some(p) = profile {
+            basedns.extend(profile_get_all(p, "base+"));
+        }
+        basedns.extend(cli_base_add);
+        basedns
+    }
+
+    #[test]
+    fn base_add_profile_key_appends_to_profile_base() {
+        let config = b"profile default\n\
+                        base: dc=profile,dc=com\n\
+                        base+: dc=extra,dc=com\n\
+                        \n";
+        let profile = find_profile(config, "default").unwrap();
+        let basedns = resolve_basedns(vec![], &profile, vec![]);
+        assert_eq!(basedns, vec!["dc=profile,dc=com", "dc=extra,dc=com"]);
+    }
+
+    #[test]
+    fn base_add_profile_key_survives_cli_base_replace() {
+        let config = b"profile default\n\
+                        base: dc=profile,dc=com\n\
+                        base+: dc=extra,dc=com\n\
+                        \n";
+        let profile = find_profile(config, "default").unwrap();
+        let basedns = resolve_basedns(vec!["dc=cli,dc=com".to_string()], &profile, vec![]);
+        assert_eq!(basedns, vec!["dc=cli,dc=com", "dc=extra,dc=com"]);
+    }
+
+    #[test]
+    fn base_add_cli_flag_unions_onto_profile_base() {
+        let config = b"profile default\n\
+                        base: dc=profile,dc=com\n\
+                        \n";
+        let profile = find_profile(config, "default").unwrap();
+        let basedns = resolve_basedns(vec![], &profile, vec!["dc=cli-add,dc=com".to_string()]);
+        assert_eq!(basedns, vec!["dc=profile,dc=com", "dc=cli-add,dc=com"]);
+    }
+
+    #[test]
+    fn base_add_cli_flag_comes_after_profile_base_plus() {
+        let config = b"profile default\n\
+                        base: dc=profile,dc=com\n\
+                        base+: dc=config-extra,dc=com\n\
+                        \n";
+        let profile = find_profile(config, "default").unwrap();
+        let basedns = resolve_basedns(
+            vec!["dc=cli,dc=com".to_string()],
+            &profile,
+            vec!["dc=cli-add,dc=com".to_string()],
+        );
+        assert_eq!(
+            basedns,
+            vec!["dc=cli,dc=com", "dc=config-extra,dc=com", "dc=cli-add,dc=com"]
+        );
+    }
+
+    // -- parse_authzid --
+
+    #[test]
+    fn parse_authzid_user() {
+        assert_eq!(parse_authzid("u:alice").unwrap(), AuthzId::User("alice".to_string()));
+    }
+
+    #[test]
+    fn parse_authzid_plain_dn() {
+        assert_eq!(
+            parse_authzid("dn:cn=alice,dc=example,dc=com").unwrap(),
+            AuthzId::Dn {
+                value: "cn=alice,dc=example,dc=com".to_string(),
+                match_type: DnMatchType::Exact,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_authzid_dn_exact_normalizes() {
+        let authzid = parse_authzid("dn.exact:cn=alice,dc=example,dc=com").unwrap();
+        assert_eq!(
+            authzid,
+            AuthzId::Dn {
+                value: "cn=alice,dc=example,dc=com".to_string(),
+                match_type: DnMatchType::Exact,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_authzid_dn_exact_rejects_malformed_dn() {
+        assert!(parse_authzid("dn.exact:=nope").is_err());
+    }
+
+    #[test]
+    fn parse_authzid_dn_regex_compiles() {
+        let authzid = parse_authzid("dn.regex:^cn=.*,dc=example,dc=com$").unwrap();
+        assert_eq!(
+            authzid,
+            AuthzId::Dn {
+                value: "^cn=.*,dc=example,dc=com$".to_string(),
+                match_type: DnMatchType::Regex,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_authzid_dn_regex_rejects_invalid_pattern() {
+        assert!(parse_authzid("dn.regex:(unclosed").is_err());
+    }
+
+    #[test]
+    fn parse_authzid_rejects_bare_string() {
+        assert!(parse_authzid("alice").is_err());
+    }
+
+    // -- parse_ldap_conf_file tests --
+
+    #[test]
+    fn parse_ldap_conf_file_basic_keywords() {
+        let content = "URI ldap://ldap.example.com\nBASE dc=example,dc=com\n";
+        let values = parse_ldap_conf_file(content);
+        assert_eq!(values.get("URI").map(String::as_str), Some("ldap://ldap.example.com"));
+        assert_eq!(values.get("BASE").map(String::as_str), Some("dc=example,dc=com"));
+    }
+
+    #[test]
+    fn parse_ldap_conf_file_ignores_comments_and_blank_lines() {
+        let content = "# a comment\n\nURI ldap://ldap.example.com\n   # indented comment\n";
+        let values = parse_ldap_conf_file(content);
+        assert_eq!(values.len(), 1);
+        assert_eq!(values.get("URI").map(String::as_str), Some("ldap://ldap.example.com"));
+    }
+
+    #[test]
+    fn parse_ldap_conf_file_keywords_are_case_insensitive() {
+        let content = "binddn cn=admin,dc=example,dc=com\n";
+        let values = parse_ldap_conf_file(content);
+        assert_eq!(
+            values.get("BINDDN").map(String::as_str),
+            Some("cn=admin,dc=example,dc=com")
+        );
+    }
+
+    #[test]
+    fn parse_ldap_conf_file_last_occurrence_wins() {
+        let content = "TLS_REQCERT never\nTLS_REQCERT demand\n";
+        let values = parse_ldap_conf_file(content);
+        assert_eq!(values.get("TLS_REQCERT").map(String::as_str), Some("demand"));
+    }
 }
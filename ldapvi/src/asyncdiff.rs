@@ -0,0 +1,491 @@
+//! Async mirror of the diff engine in [`crate::diff`], for sources that
+//! can't offer `Read + Seek` -- a live LDAP search result, or a pipe.
+//!
+//! The comparison/rename logic in `diff` (`compare_entries`, `validate_rename`,
+//! `rename_entry`) only operates on already-parsed [`Entry`] values; it never
+//! touches the underlying stream. That means it's already runtime-agnostic,
+//! and this module reuses it directly rather than duplicating it. Only the
+//! stream-driving loop -- `process_next_entry`/`process_deletions`/
+//! `compare_streams` in `diff` -- needs an async counterpart, since that's
+//! the part that performs I/O.
+//!
+//! Unlike the synchronous engine, this port doesn't implement the `fastcmp`
+//! raw-byte fast path: that optimization depends on cheap re-reads at a
+//! recorded byte offset, which [`InMemoryClean`] (below) doesn't expose.
+//! Every numbered entry is compared by full parse instead.
+
+use async_trait::async_trait;
+
+use crate::data::{Entry, LdapMod, ModifyRecord, RenameRecord};
+use crate::diff::{compare_entries, rename_entry, validate_rename, Comparator, DiffError, DiffHandler, DiffMode, DiffObserver, DiffOp, DiffResult, SyncDiffHandler};
+use crate::error::Result;
+
+/// Async mirror of [`crate::diff::EntryParser`], for sources backed by
+/// `AsyncRead` rather than `Read + Seek`.
+///
+/// `offset` plays the same role as in the synchronous trait: `None` means
+/// "wherever the stream currently is", and `Some(pos)` means "seek there
+/// first" for sources that support it. [`InMemoryClean`] interprets `pos` as
+/// an index into its in-memory store rather than a byte offset, since it has
+/// no underlying seekable stream at all.
+#[async_trait]
+pub trait AsyncEntryParser: Send {
+    async fn read_entry(&mut self, offset: Option<u64>) -> Result<Option<(String, Entry, u64)>>;
+    async fn peek_entry(&mut self, offset: Option<u64>) -> Result<Option<(String, u64)>>;
+    async fn skip_entry(&mut self, offset: Option<u64>) -> Result<Option<String>>;
+    async fn read_rename(&mut self, offset: Option<u64>) -> Result<RenameRecord>;
+    async fn read_delete(&mut self, offset: Option<u64>) -> Result<String>;
+    async fn read_modify(&mut self, offset: Option<u64>) -> Result<ModifyRecord>;
+}
+
+/// Buffering adapter for the "clean" side when the underlying source can't
+/// seek: drains an [`AsyncEntryParser`] once, up front, into an in-memory
+/// store indexed by the numbered `ldapvi-key` of each entry, so the
+/// numbered-key lookups `compare_streams` needs still work without a real
+/// seekable stream underneath.
+///
+/// The "data" side needs no equivalent -- `compare_streams` only ever peeks
+/// and then immediately reads the same, current position on that side, so
+/// any `AsyncRead` consumed strictly forward is sufficient.
+pub struct InMemoryClean {
+    entries: Vec<Option<Entry>>,
+}
+
+impl InMemoryClean {
+    /// Drain `source` into memory. Entries not labeled with a numeric key
+    /// (e.g. stray changerecords on the clean side, which shouldn't occur
+    /// but aren't a reason to fail the whole load) are skipped.
+    pub async fn load(source: &mut dyn AsyncEntryParser) -> Result<Self> {
+        let mut entries: Vec<Option<Entry>> = Vec::new();
+        loop {
+            match source.read_entry(None).await? {
+                Some((key, entry, _pos)) => {
+                    if let Ok(n) = key.parse::<usize>() {
+                        if entries.len() <= n {
+                            entries.resize_with(n + 1, || None);
+                        }
+                        entries[n] = Some(entry);
+                    }
+                }
+                None => break,
+            }
+        }
+        Ok(InMemoryClean { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[async_trait]
+impl AsyncEntryParser for InMemoryClean {
+    async fn read_entry(&mut self, offset: Option<u64>) -> Result<Option<(String, Entry, u64)>> {
+        let n = match offset {
+            Some(n) => n as usize,
+            None => return Ok(None), // no implicit cursor; callers always address by key
+        };
+        Ok(self
+            .entries
+            .get(n)
+            .and_then(|e| e.as_ref())
+            .map(|e| (n.to_string(), e.clone(), n as u64)))
+    }
+    async fn peek_entry(&mut self, offset: Option<u64>) -> Result<Option<(String, u64)>> {
+        Ok(self
+            .read_entry(offset)
+            .await?
+            .map(|(key, _entry, pos)| (key, pos)))
+    }
+    async fn skip_entry(&mut self, offset: Option<u64>) -> Result<Option<String>> {
+        Ok(self.read_entry(offset).await?.map(|(key, _, _)| key))
+    }
+    async fn read_rename(&mut self, _offset: Option<u64>) -> Result<RenameRecord> {
+        Err(crate::error::LdapviError::Other(
+            "InMemoryClean holds numbered entries only, not changerecords".to_string(),
+        ))
+    }
+    async fn read_delete(&mut self, _offset: Option<u64>) -> Result<String> {
+        Err(crate::error::LdapviError::Other(
+            "InMemoryClean holds numbered entries only, not changerecords".to_string(),
+        ))
+    }
+    async fn read_modify(&mut self, _offset: Option<u64>) -> Result<ModifyRecord> {
+        Err(crate::error::LdapviError::Other(
+            "InMemoryClean holds numbered entries only, not changerecords".to_string(),
+        ))
+    }
+}
+
+/// Async mirror of `diff::process_next_entry`: compare one numbered data
+/// entry against its clean counterpart, or dispatch an immediate
+/// changerecord.
+#[allow(clippy::too_many_arguments)]
+async fn process_next_entry(
+    clean: &mut dyn AsyncEntryParser,
+    data: &mut dyn AsyncEntryParser,
+    handler: &mut dyn SyncDiffHandler,
+    offsets: &mut [i64],
+    key: &str,
+    datapos: u64,
+    mode: DiffMode,
+    cmp: &Comparator,
+    observer: &mut dyn DiffObserver,
+) -> DiffResult<()> {
+    let n: usize = match key.parse() {
+        Ok(n) => n,
+        Err(_) => return process_immediate(data, handler, datapos, key, cmp).await,
+    };
+
+    if n >= offsets.len() {
+        eprintln!("Error: Invalid key: `{}'.", key);
+        return Err(DiffError::ParseError { n: n as i32, dn: String::new() });
+    }
+    let pos = offsets[n];
+    if pos < 0 {
+        eprintln!("Error: Duplicate entry {}.", n);
+        return Err(DiffError::ParseError { n: n as i32, dn: String::new() });
+    }
+
+    let entry = match data.read_entry(Some(datapos)).await {
+        Ok(Some((_, e, _))) => e,
+        Ok(None) => return Err(DiffError::ParseError { n: n as i32, dn: String::new() }),
+        Err(_) => return Err(DiffError::ParseError { n: n as i32, dn: String::new() }),
+    };
+    let mut cleanentry = match clean.read_entry(Some(pos as u64)).await {
+        Ok(Some((_, e, _))) => e,
+        _ => panic!("Failed to read clean entry at key {}", n),
+    };
+
+    let is_rename = cleanentry.dn != entry.dn;
+    if is_rename {
+        let mut deleteoldrdn = false;
+        validate_rename(&mut cleanentry, &mut entry.clone(), cmp, &mut deleteoldrdn)
+            .map_err(|e| e.with_n(n as i32))?;
+        observer.note_entry(n as i32, &cleanentry.dn, DiffOp::Rename);
+        handler.handle_rename(n as i32, &cleanentry.dn, &entry)?;
+        rename_entry(&mut cleanentry, cmp, &entry.dn, deleteoldrdn);
+    }
+
+    let mods = compare_entries(&cleanentry, &entry, mode, cmp, n as i32, observer);
+    if !mods.is_empty() {
+        handler.handle_change(n as i32, &cleanentry.dn, &entry.dn, &mods)?;
+    }
+
+    crate::diff::long_array_invert(offsets, n);
+    Ok(())
+}
+
+/// Async mirror of `diff::process_immediate`.
+async fn process_immediate(
+    data: &mut dyn AsyncEntryParser,
+    handler: &mut dyn SyncDiffHandler,
+    datapos: u64,
+    key: &str,
+    cmp: &Comparator,
+) -> DiffResult<()> {
+    match key {
+        "add" => {
+            let entry = match data.read_entry(Some(datapos)).await {
+                Ok(Some((_, e, _))) => e,
+                _ => return Err(DiffError::ParseError { n: -1, dn: String::new() }),
+            };
+            let mods = crate::diff::entry_to_add_mods(&entry, cmp);
+            handler.handle_add(-1, &entry.dn, &mods)?;
+        }
+        "rename" => {
+            let rr = match data.read_rename(Some(datapos)).await {
+                Ok(rr) => rr,
+                Err(_) => return Err(DiffError::ParseError { n: -1, dn: String::new() }),
+            };
+            handler.handle_rename0(-1, &rr.old_dn, &rr.new_dn, rr.delete_old_rdn)?;
+        }
+        "delete" => {
+            let dn = match data.read_delete(Some(datapos)).await {
+                Ok(dn) => dn,
+                Err(_) => return Err(DiffError::ParseError { n: -1, dn: String::new() }),
+            };
+            handler.handle_delete(-1, &dn)?;
+        }
+        "modify" => {
+            let mr = match data.read_modify(Some(datapos)).await {
+                Ok(mr) => mr,
+                Err(_) => return Err(DiffError::ParseError { n: -1, dn: String::new() }),
+            };
+            handler.handle_change(-1, &mr.dn, &mr.dn, &mr.mods)?;
+        }
+        _ => {
+            eprintln!("Error: Invalid key: `{}'.", key);
+            return Err(DiffError::ParseError { n: -1, dn: String::new() });
+        }
+    }
+    Ok(())
+}
+
+/// Async mirror of `diff::process_deletions`.
+async fn process_deletions(
+    clean: &mut dyn AsyncEntryParser,
+    handler: &mut dyn SyncDiffHandler,
+    offsets: &mut [i64],
+    observer: &mut dyn DiffObserver,
+) -> DiffResult<()> {
+    for n in 0..offsets.len() {
+        let pos = offsets[n];
+        if pos < 0 {
+            continue;
+        }
+        let cleanentry = match clean.read_entry(Some(pos as u64)).await {
+            Ok(Some((_, e, _))) => e,
+            _ => panic!("Failed to read clean entry for deletion"),
+        };
+        for attr in &cleanentry.attributes {
+            observer.note_attribute(
+                n as i32,
+                &cleanentry.dn,
+                attr.ad.as_str_lossy().as_ref(),
+                &[],
+                &attr.values,
+            );
+        }
+        observer.note_entry(n as i32, &cleanentry.dn, DiffOp::Delete);
+        handler.handle_delete(n as i32, &cleanentry.dn)?;
+        crate::diff::long_array_invert(offsets, n);
+    }
+    Ok(())
+}
+
+/// Async mirror of [`crate::diff::compare_streams`], driving the same
+/// algorithm over [`AsyncEntryParser`] sources instead of `Read + Seek`
+/// ones. See that function's doc comment for the semantics of `offsets`,
+/// `mode`, `cmp`, `observer`, and the return value.
+pub async fn compare_streams(
+    clean: &mut dyn AsyncEntryParser,
+    data: &mut dyn AsyncEntryParser,
+    handler: &mut dyn SyncDiffHandler,
+    offsets: &mut [i64],
+    mode: DiffMode,
+    cmp: &Comparator,
+    observer: &mut dyn DiffObserver,
+) -> DiffResult<()> {
+    let result: DiffResult<()> = async {
+        loop {
+            let peek = match data.peek_entry(None).await {
+                Ok(Some((key, datapos))) => Some((key, datapos)),
+                Ok(None) => None,
+                Err(_) => return Err(DiffError::ParseError { n: -1, dn: String::new() }),
+            };
+
+            let (key, datapos) = match peek {
+                Some(kd) => kd,
+                None => break,
+            };
+
+            process_next_entry(
+                clean, data, handler, offsets, &key, datapos, mode, cmp, observer,
+            )
+            .await?;
+        }
+
+        if let Some(r) = handler.flush().into_iter().find(|r| !r.success) {
+            return Err(DiffError::HandlerRejected { n: r.n, dn: String::new(), code: -1 });
+        }
+
+        process_deletions(clean, handler, offsets, observer).await?;
+
+        if let Some(r) = handler.flush().into_iter().find(|r| !r.success) {
+            return Err(DiffError::HandlerRejected { n: r.n, dn: String::new(), code: -1 });
+        }
+
+        Ok(())
+    }
+    .await;
+
+    if let Err(DiffError::HandlerRejected { .. }) = &result {
+        return result;
+    }
+
+    for n in 0..offsets.len() {
+        if offsets[n] < 0 {
+            crate::diff::long_array_invert(offsets, n);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Attribute;
+
+    /// Minimal forward-reading [`AsyncEntryParser`] over numbered entries
+    /// held in memory, standing in for a real async LDIF/LDAP source in
+    /// tests. Doubles as a source to drain into [`InMemoryClean`].
+    struct VecParser {
+        entries: Vec<(String, Entry)>,
+        pos: usize,
+    }
+
+    impl VecParser {
+        fn new(entries: Vec<(&str, Entry)>) -> Self {
+            VecParser {
+                entries: entries.into_iter().map(|(k, e)| (k.to_string(), e)).collect(),
+                pos: 0,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AsyncEntryParser for VecParser {
+        async fn read_entry(&mut self, offset: Option<u64>) -> Result<Option<(String, Entry, u64)>> {
+            let idx = offset.map(|o| o as usize).unwrap_or(self.pos);
+            Ok(self.entries.get(idx).map(|(k, e)| {
+                self.pos = idx + 1;
+                (k.clone(), e.clone(), idx as u64)
+            }))
+        }
+        async fn peek_entry(&mut self, offset: Option<u64>) -> Result<Option<(String, u64)>> {
+            let idx = offset.map(|o| o as usize).unwrap_or(self.pos);
+            Ok(self.entries.get(idx).map(|(k, _)| (k.clone(), idx as u64)))
+        }
+        async fn skip_entry(&mut self, offset: Option<u64>) -> Result<Option<String>> {
+            Ok(self.read_entry(offset).await?.map(|(k, _, _)| k))
+        }
+        async fn read_rename(&mut self, _offset: Option<u64>) -> Result<RenameRecord> {
+            Err(crate::error::LdapviError::Other("not a changerecord".to_string()))
+        }
+        async fn read_delete(&mut self, _offset: Option<u64>) -> Result<String> {
+            Err(crate::error::LdapviError::Other("not a changerecord".to_string()))
+        }
+        async fn read_modify(&mut self, _offset: Option<u64>) -> Result<ModifyRecord> {
+            Err(crate::error::LdapviError::Other("not a changerecord".to_string()))
+        }
+    }
+
+    fn entry(dn: &str, attrs: &[(&str, &str)]) -> Entry {
+        Entry {
+            dn: dn.to_string(),
+            attributes: attrs
+                .iter()
+                .map(|(ad, val)| Attribute {
+                    ad: (*ad).into(),
+                    values: vec![val.as_bytes().to_vec()],
+                })
+                .collect(),
+        }
+    }
+
+    #[derive(Default)]
+    struct MockHandler {
+        adds: i32,
+        deletes: i32,
+        changes: i32,
+        renames: i32,
+    }
+
+    impl DiffHandler for MockHandler {}
+
+    impl SyncDiffHandler for MockHandler {
+        fn handle_add(&mut self, _n: i32, _dn: &str, _mods: &[LdapMod]) -> DiffResult<()> {
+            self.adds += 1;
+            Ok(())
+        }
+        fn handle_delete(&mut self, _n: i32, _dn: &str) -> DiffResult<()> {
+            self.deletes += 1;
+            Ok(())
+        }
+        fn handle_change(&mut self, _n: i32, _old_dn: &str, _new_dn: &str, _mods: &[LdapMod]) -> DiffResult<()> {
+            self.changes += 1;
+            Ok(())
+        }
+        fn handle_rename(&mut self, _n: i32, _old_dn: &str, _entry: &Entry) -> DiffResult<()> {
+            self.renames += 1;
+            Ok(())
+        }
+        fn handle_rename0(&mut self, _n: i32, _old_dn: &str, _new_dn: &str, _deleteoldrdn: bool) -> DiffResult<()> {
+            self.renames += 1;
+            Ok(())
+        }
+    }
+
+    fn block_on<F: std::future::Future>(fut: F) -> F::Output {
+        tokio::runtime::Runtime::new().unwrap().block_on(fut)
+    }
+
+    #[test]
+    fn test_in_memory_clean_indexes_by_numeric_key() {
+        block_on(async {
+            let mut source = VecParser::new(vec![
+                ("0", entry("cn=foo,dc=example,dc=com", &[("cn", "foo")])),
+                ("1", entry("cn=bar,dc=example,dc=com", &[("cn", "bar")])),
+            ]);
+            let mut clean = InMemoryClean::load(&mut source).await.unwrap();
+            assert_eq!(clean.len(), 2);
+            let (key, e, pos) = clean.read_entry(Some(1)).await.unwrap().unwrap();
+            assert_eq!(key, "1");
+            assert_eq!(pos, 1);
+            assert_eq!(e.dn, "cn=bar,dc=example,dc=com");
+        });
+    }
+
+    #[test]
+    fn test_compare_streams_async_modify() {
+        block_on(async {
+            let mut clean_source = VecParser::new(vec![(
+                "0",
+                entry("cn=foo,dc=example,dc=com", &[("cn", "foo"), ("sn", "old")]),
+            )]);
+            let mut clean = InMemoryClean::load(&mut clean_source).await.unwrap();
+            let mut data = VecParser::new(vec![(
+                "0",
+                entry("cn=foo,dc=example,dc=com", &[("cn", "foo"), ("sn", "new")]),
+            )]);
+            let mut offsets = vec![0i64];
+            let mut handler = MockHandler::default();
+
+            let result = compare_streams(
+                &mut clean,
+                &mut data,
+                &mut handler,
+                &mut offsets,
+                DiffMode::Replace,
+                &Comparator::new(),
+                &mut crate::diff::NullObserver,
+            )
+            .await;
+            assert_eq!(result, Ok(()));
+            assert_eq!(handler.changes, 1);
+            assert_eq!(handler.deletes, 0);
+        });
+    }
+
+    #[test]
+    fn test_compare_streams_async_delete() {
+        block_on(async {
+            let mut clean_source = VecParser::new(vec![(
+                "0",
+                entry("cn=foo,dc=example,dc=com", &[("cn", "foo")]),
+            )]);
+            let mut clean = InMemoryClean::load(&mut clean_source).await.unwrap();
+            let mut data = VecParser::new(vec![]);
+            let mut offsets = vec![0i64];
+            let mut handler = MockHandler::default();
+
+            let result = compare_streams(
+                &mut clean,
+                &mut data,
+                &mut handler,
+                &mut offsets,
+                DiffMode::Replace,
+                &Comparator::new(),
+                &mut crate::diff::NullObserver,
+            )
+            .await;
+            assert_eq!(result, Ok(()));
+            assert_eq!(handler.deletes, 1);
+        });
+    }
+}
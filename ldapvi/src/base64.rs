@@ -1,11 +1,110 @@
+use std::fmt;
 use std::io::{self, Write};
 
 const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE_64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
 const PAD64: u8 = b'=';
 
+/// Sentinel for a byte that is neither a base64 alphabet character, the
+/// pad character, nor whitespace.
+const INVALID: i8 = -1;
+/// Sentinel for the `=` pad character.
+const PAD: i8 = -2;
+/// Sentinel for ASCII whitespace, which `Base64Decoder::feed` skips
+/// outright regardless of decoder state.
+const WHITESPACE: i8 = -3;
+
+/// A base64 alphabet: the 64 symbols used to encode 6-bit groups, plus the
+/// reverse-lookup table derived from them. RFC 4648 defines the two built
+/// in here ([`Alphabet::STANDARD`] and [`Alphabet::URL_SAFE`]);
+/// [`Alphabet::custom`] accepts any other 64-distinct-byte alphabet (the
+/// pad character is always `=`, matching RFC 4648's shared padding rule).
+///
+/// `decode` is a 256-entry reverse-lookup table built from `symbols` at
+/// construction: `decode[b]` is `b`'s 6-bit value, or one of
+/// [`INVALID`]/[`PAD`]/[`WHITESPACE`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alphabet {
+    symbols: [u8; 64],
+    decode: [i8; 256],
+}
+
+impl Alphabet {
+    /// The standard alphabet (`+`, `/`), RFC 4648 section 4. What every
+    /// function in this module used exclusively before alphabets existed.
+    pub const STANDARD: Alphabet = Alphabet::build(*BASE64);
+
+    /// The URL- and filename-safe alphabet (`-`, `_`), RFC 4648 section 5.
+    pub const URL_SAFE: Alphabet = Alphabet::build(*URL_SAFE_64);
+
+    /// Build a custom alphabet from 64 distinct bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `symbols` contains a repeated byte.
+    pub fn custom(symbols: [u8; 64]) -> Alphabet {
+        let mut seen = [false; 256];
+        for &b in &symbols {
+            assert!(!seen[b as usize], "Alphabet::custom: duplicate byte {:#04x}", b);
+            seen[b as usize] = true;
+        }
+        Alphabet::build(symbols)
+    }
+
+    const fn build(symbols: [u8; 64]) -> Alphabet {
+        let mut decode = [INVALID; 256];
+        let mut i = 0;
+        while i < 64 {
+            decode[symbols[i] as usize] = i as i8;
+            i += 1;
+        }
+        decode[PAD64 as usize] = PAD;
+        decode[b' ' as usize] = WHITESPACE;
+        decode[b'\t' as usize] = WHITESPACE;
+        decode[b'\n' as usize] = WHITESPACE;
+        decode[b'\r' as usize] = WHITESPACE;
+        decode[0x0c] = WHITESPACE; // form feed, the last `char::is_ascii_whitespace` byte
+        Alphabet { symbols, decode }
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::STANDARD
+    }
+}
+
 /// Encode `src` as base64, writing to `w` with LDIF-style line folding
-/// (newline + space after every 76 characters of output).
-pub fn print_base64(src: &[u8], w: &mut dyn Write) -> io::Result<()> {
+/// (newline + space after every `width` characters of output). `width == 0`
+/// means never fold. Uses the standard (`+`, `/`) alphabet; see
+/// [`print_base64_with_alphabet`] for others, or [`print_base64_wrapped`]
+/// for an `Option<usize>` width that makes "never fold" explicit instead
+/// of overloading `0`.
+pub fn print_base64(src: &[u8], w: &mut dyn Write, width: usize) -> io::Result<()> {
+    print_base64_with_alphabet(src, w, width, &Alphabet::STANDARD)
+}
+
+/// Like [`print_base64`], but `width` is `None` (never fold) or
+/// `Some(n)` (fold after `n` output characters) instead of overloading
+/// `0`. `print_base64_wrapped(src, w, Some(n))` writes the same output as
+/// `print_base64(src, w, n)`, and `None` the same as width `0`. Mirrors the
+/// `base64` crate's separate `line_wrap` module: the wrapping policy lives
+/// in [`fold_boundary`], decoupled from [`print_base64_with_alphabet`]'s
+/// encoding loop, which only has to ask it whether a break falls before
+/// the next group.
+pub fn print_base64_wrapped(src: &[u8], w: &mut dyn Write, width: Option<usize>) -> io::Result<()> {
+    print_base64_with_alphabet(src, w, width.unwrap_or(0), &Alphabet::STANDARD)
+}
+
+/// Like [`print_base64`], but encoding with `alphabet`'s symbols instead of
+/// always the standard one.
+pub fn print_base64_with_alphabet(
+    src: &[u8],
+    w: &mut dyn Write,
+    width: usize,
+    alphabet: &Alphabet,
+) -> io::Result<()> {
+    let symbols = &alphabet.symbols;
     let mut col = 0;
     let mut i = 0;
 
@@ -20,17 +119,14 @@ pub fn print_base64(src: &[u8], w: &mut dyn Write) -> io::Result<()> {
             input[2] & 0x3f,
         ];
 
-        if col >= 76 {
-            w.write_all(b"\n ")?;
-            col = 0;
-        }
+        fold_boundary(w, &mut col, width)?;
         col += 4;
 
         w.write_all(&[
-            BASE64[output[0] as usize],
-            BASE64[output[1] as usize],
-            BASE64[output[2] as usize],
-            BASE64[output[3] as usize],
+            symbols[output[0] as usize],
+            symbols[output[1] as usize],
+            symbols[output[2] as usize],
+            symbols[output[3] as usize],
         ])?;
     }
 
@@ -45,11 +141,12 @@ pub fn print_base64(src: &[u8], w: &mut dyn Write) -> io::Result<()> {
             ((input[1] & 0x0f) << 2) | (input[2] >> 6),
         ];
 
-        w.write_all(&[BASE64[output[0] as usize], BASE64[output[1] as usize]])?;
+        fold_boundary(w, &mut col, width)?;
+        w.write_all(&[symbols[output[0] as usize], symbols[output[1] as usize]])?;
         if remaining == 1 {
             w.write_all(&[PAD64])?;
         } else {
-            w.write_all(&[BASE64[output[2] as usize]])?;
+            w.write_all(&[symbols[output[2] as usize]])?;
         }
         w.write_all(&[PAD64])?;
     }
@@ -57,113 +154,491 @@ pub fn print_base64(src: &[u8], w: &mut dyn Write) -> io::Result<()> {
     Ok(())
 }
 
-/// Encode `src` as base64, appending to `dst` with LDIF-style line folding.
+/// The wrapping policy behind every fold-width parameter in this module:
+/// if `*col` has reached `width`, write the `"\n "` continuation and reset
+/// it to 0. `width == 0` means never fold. Callers are responsible for
+/// advancing `*col` by the number of output characters they go on to
+/// write; this only decides whether a break falls *before* the next one.
+fn fold_boundary(w: &mut dyn Write, col: &mut usize, width: usize) -> io::Result<()> {
+    if width > 0 && *col >= width {
+        w.write_all(b"\n ")?;
+        *col = 0;
+    }
+    Ok(())
+}
+
+/// Encode `src` as base64, appending to `dst` without line folding. Used to
+/// build values (password hashes, PBKDF2 salts/keys) that get stored or
+/// compared as a whole, not printed as an LDIF line -- folding here would
+/// embed a literal newline in the value.
 pub fn append_base64(dst: &mut String, src: &[u8]) {
-    let mut buf = Vec::new();
-    print_base64(src, &mut buf).unwrap();
-    dst.push_str(&String::from_utf8(buf).unwrap());
+    use std::fmt::Write as _;
+    write!(dst, "{}", Base64Display(src)).expect("writing to a String never fails");
 }
 
-/// Decode base64 `src` into bytes. Returns None on invalid input.
-pub fn read_base64(src: &str) -> Option<Vec<u8>> {
-    let mut target = Vec::new();
-    let mut state = 0u8;
-    let mut chars = src.bytes().peekable();
+/// Lazily encodes `src` as base64 when displayed, instead of building a
+/// `String` up front: `append_base64` used to encode into a `Vec`,
+/// validate it as UTF-8, then copy that into the target `String`, a
+/// double allocation this avoids by writing straight into the
+/// `fmt::Formatter`. Mirrors the `base64` crate's `display` module.
+///
+/// Unlike [`print_base64`], this never folds -- like [`append_base64`],
+/// which it backs, the typical reason to format base64 inline is to build
+/// a value that gets stored or compared whole (a password hash, a salt),
+/// where a folding newline would corrupt it.
+pub struct Base64Display<'a>(pub &'a [u8]);
+
+impl fmt::Display for Base64Display<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let src = self.0;
+        let mut i = 0;
+
+        while i + 2 < src.len() {
+            let input = [src[i], src[i + 1], src[i + 2]];
+            i += 3;
+
+            let output = [
+                input[0] >> 2,
+                ((input[0] & 0x03) << 4) | (input[1] >> 4),
+                ((input[1] & 0x0f) << 2) | (input[2] >> 6),
+                input[2] & 0x3f,
+            ];
+            let group = [
+                BASE64[output[0] as usize],
+                BASE64[output[1] as usize],
+                BASE64[output[2] as usize],
+                BASE64[output[3] as usize],
+            ];
+            f.write_str(std::str::from_utf8(&group).expect("base64 symbols are ASCII"))?;
+        }
 
-    while let Some(&ch) = chars.peek() {
-        chars.next();
+        let remaining = src.len() - i;
+        if remaining > 0 {
+            let mut input = [0u8; 3];
+            input[..remaining].copy_from_slice(&src[i..i + remaining]);
+
+            let output = [
+                input[0] >> 2,
+                ((input[0] & 0x03) << 4) | (input[1] >> 4),
+                ((input[1] & 0x0f) << 2) | (input[2] >> 6),
+            ];
+
+            let mut tail = [PAD64; 4];
+            tail[0] = BASE64[output[0] as usize];
+            tail[1] = BASE64[output[1] as usize];
+            if remaining == 2 {
+                tail[2] = BASE64[output[2] as usize];
+            }
+            f.write_str(std::str::from_utf8(&tail).expect("base64 symbols and '=' are ASCII"))?;
+        }
 
-        if ch.is_ascii_whitespace() {
-            continue;
+        Ok(())
+    }
+}
+
+/// The fold width `Base64Writer` uses -- the same default every
+/// `print_base64` call site in this crate passes.
+const WRITER_FOLD_WIDTH: usize = 76;
+
+/// An `io::Write` adapter that base64-encodes whatever is written to it,
+/// writing the encoded text to the wrapped `W` sink as each 3-byte input
+/// group completes, rather than requiring the whole value up front like
+/// [`print_base64`] does. Useful for streaming a large binary attribute
+/// value (a certificate, a photo) through LDIF without holding both the
+/// raw and encoded forms in memory at once.
+///
+/// At most two leftover input bytes are buffered between writes (a
+/// partial 3-byte group can't be encoded yet). Call [`Base64Writer::finish`]
+/// once all input has been written to flush that trailing partial group
+/// with correct `=` padding.
+pub struct Base64Writer<W: Write> {
+    inner: W,
+    pending: [u8; 2],
+    pending_len: u8,
+    col: usize,
+}
+
+impl<W: Write> Base64Writer<W> {
+    pub fn new(inner: W) -> Self {
+        Base64Writer {
+            inner,
+            pending: [0; 2],
+            pending_len: 0,
+            col: 0,
+        }
+    }
+
+    fn emit_group(&mut self, input: [u8; 3]) -> io::Result<()> {
+        let output = [
+            input[0] >> 2,
+            ((input[0] & 0x03) << 4) | (input[1] >> 4),
+            ((input[1] & 0x0f) << 2) | (input[2] >> 6),
+            input[2] & 0x3f,
+        ];
+
+        fold_boundary(&mut self.inner, &mut self.col, WRITER_FOLD_WIDTH)?;
+        self.col += 4;
+
+        self.inner.write_all(&[
+            BASE64[output[0] as usize],
+            BASE64[output[1] as usize],
+            BASE64[output[2] as usize],
+            BASE64[output[3] as usize],
+        ])
+    }
+
+    /// Flush the trailing 0-2 leftover input bytes (with `=` padding, same
+    /// as [`print_base64`]'s tail handling) and return the wrapped sink.
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.pending_len {
+            0 => {}
+            1 => {
+                let output = [self.pending[0] >> 2, (self.pending[0] & 0x03) << 4];
+                fold_boundary(&mut self.inner, &mut self.col, WRITER_FOLD_WIDTH)?;
+                self.inner.write_all(&[
+                    BASE64[output[0] as usize],
+                    BASE64[output[1] as usize],
+                    PAD64,
+                    PAD64,
+                ])?;
+            }
+            2 => {
+                let output = [
+                    self.pending[0] >> 2,
+                    ((self.pending[0] & 0x03) << 4) | (self.pending[1] >> 4),
+                    (self.pending[1] & 0x0f) << 2,
+                ];
+                fold_boundary(&mut self.inner, &mut self.col, WRITER_FOLD_WIDTH)?;
+                self.inner.write_all(&[
+                    BASE64[output[0] as usize],
+                    BASE64[output[1] as usize],
+                    BASE64[output[2] as usize],
+                    PAD64,
+                ])?;
+            }
+            _ => unreachable!(),
+        }
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for Base64Writer<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut i = 0;
+
+        if self.pending_len > 0 {
+            let have = self.pending_len as usize;
+            let mut group = [0u8; 3];
+            group[..have].copy_from_slice(&self.pending[..have]);
+            let take = (3 - have).min(buf.len());
+            group[have..have + take].copy_from_slice(&buf[..take]);
+            i += take;
+
+            if have + take == 3 {
+                self.emit_group(group)?;
+                self.pending_len = 0;
+            } else {
+                self.pending_len = (have + take) as u8;
+                self.pending[..self.pending_len as usize].copy_from_slice(&group[..self.pending_len as usize]);
+                return Ok(buf.len());
+            }
         }
 
-        if ch == PAD64 {
-            // Handle padding
-            match state {
-                0 | 1 => return None,
+        while i + 2 < buf.len() {
+            let group = [buf[i], buf[i + 1], buf[i + 2]];
+            self.emit_group(group)?;
+            i += 3;
+        }
+
+        let remaining = buf.len() - i;
+        if remaining > 0 {
+            self.pending[..remaining].copy_from_slice(&buf[i..]);
+            self.pending_len = remaining as u8;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// An `io::Write` adapter that base64-decodes whatever is written to it,
+/// writing each completed quantum's decoded bytes to the wrapped `W` sink
+/// immediately rather than requiring the whole armored text up front like
+/// [`read_base64`] does. Pairs with [`Base64Writer`] for streaming a large
+/// value through LDIF in both directions without holding the whole value
+/// in memory.
+///
+/// Internally wraps a [`Base64Decoder`], so it tolerates embedded
+/// whitespace the same way and reports malformed input the same way.
+pub struct Base64Reader<W: Write> {
+    inner: W,
+    decoder: Base64Decoder,
+    flushed: usize,
+}
+
+impl<W: Write> Base64Reader<W> {
+    pub fn new(inner: W) -> Self {
+        Base64Reader {
+            inner,
+            decoder: Base64Decoder::new(),
+            flushed: 0,
+        }
+    }
+
+    pub fn with_alphabet(inner: W, alphabet: Alphabet) -> Self {
+        Base64Reader {
+            inner,
+            decoder: Base64Decoder::with_alphabet(alphabet),
+            flushed: 0,
+        }
+    }
+
+    /// Finish decoding. `Err(offset)` if the final quantum was left
+    /// incomplete, matching [`Base64Decoder::finish`]; otherwise returns
+    /// the wrapped sink.
+    pub fn finish(self) -> Result<W, usize> {
+        let Base64Reader {
+            inner, decoder, ..
+        } = self;
+        decoder.finish()?;
+        Ok(inner)
+    }
+}
+
+impl<W: Write> Write for Base64Reader<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &ch in buf {
+            self.decoder.feed(ch).map_err(|offset| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid base64 character at offset {}", offset),
+                )
+            })?;
+        }
+        // Mid-quantum, the last target byte still lacks bits from the next
+        // symbol (or is dropped by padding), so hold it back.
+        let complete = if self.decoder.done || self.decoder.state == 0 {
+            self.decoder.target.len()
+        } else {
+            self.decoder.target.len() - 1
+        };
+        self.inner.write_all(&self.decoder.target[self.flushed..complete])?;
+        self.flushed = complete;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Incremental base64 decoder: feed bytes one at a time (e.g. as they arrive
+/// across folded LDIF lines) instead of buffering the whole armored text
+/// before decoding. Tolerates embedded whitespace/newlines between
+/// characters, the way a robust armored reader does.
+///
+/// On a malformed character, `feed` returns `Err(offset)` giving the
+/// 0-based offset (in bytes fed so far, skipped whitespace included)
+/// of the offending character, rather than a flat failure.
+pub struct Base64Decoder {
+    target: Vec<u8>,
+    state: u8,
+    done: bool,
+    offset: usize,
+    alphabet: Alphabet,
+    mode: DecodeMode,
+}
+
+/// How strictly a decoder validates input. `Strict` (the default every
+/// `Base64Decoder`/`read_base64*` keeps unless told otherwise) enforces
+/// RFC 4648 exactly: the final group must carry exact `=` padding, and
+/// any bits that padding leaves unused must be zero. `Forgiving` tolerates
+/// the sloppy input real-world LDIF and pasted values sometimes arrive
+/// with: missing or extra `=` padding, and nonzero trailing bits in the
+/// final group. Either mode still rejects a byte that isn't in the
+/// alphabet at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeMode {
+    Strict,
+    Forgiving,
+}
+
+impl Base64Decoder {
+    pub fn new() -> Self {
+        Self::with_alphabet(Alphabet::STANDARD)
+    }
+
+    /// Like [`Base64Decoder::new`], but accepting `alphabet`'s symbols
+    /// instead of always the standard one. The other variant's special
+    /// characters (e.g. `-`/`_` when decoding [`Alphabet::STANDARD`]) are
+    /// rejected just like any other non-alphabet byte.
+    pub fn with_alphabet(alphabet: Alphabet) -> Self {
+        Self::with_options(alphabet, DecodeMode::Strict)
+    }
+
+    /// Like [`Base64Decoder::new`], but with both `alphabet` and `mode`
+    /// configurable.
+    pub fn with_options(alphabet: Alphabet, mode: DecodeMode) -> Self {
+        Base64Decoder {
+            target: Vec::new(),
+            state: 0,
+            done: false,
+            offset: 0,
+            alphabet,
+            mode,
+        }
+    }
+
+    /// Feed one byte of armored input. Returns `Ok(())` normally, or
+    /// `Err(offset)` at the first invalid character (including any
+    /// character received after the decoder already reached padding,
+    /// unless [`DecodeMode::Forgiving`] is tolerating extra `=` padding).
+    pub fn feed(&mut self, ch: u8) -> Result<(), usize> {
+        let offset = self.offset;
+        self.offset += 1;
+
+        let code = self.alphabet.decode[ch as usize];
+
+        if code == WHITESPACE {
+            return Ok(());
+        }
+        if self.done {
+            if self.mode == DecodeMode::Forgiving && code == PAD {
+                return Ok(()); // tolerate extra trailing '=' beyond what's needed
+            }
+            return Err(offset);
+        }
+
+        if code == PAD {
+            return match self.state {
+                0 | 1 => Err(offset),
                 2 => {
-                    // Skip whitespace, expect another '='
-                    while let Some(&c) = chars.peek() {
-                        if !c.is_ascii_whitespace() {
-                            break;
-                        }
-                        chars.next();
-                    }
-                    match chars.next() {
-                        Some(c) if c == PAD64 => {}
-                        _ => return None,
-                    }
-                    // Fall through to check trailing
-                    for c in chars {
-                        if !c.is_ascii_whitespace() {
-                            return None;
-                        }
-                    }
-                    // Check extra bits are zero
-                    if let Some(&last) = target.last() {
-                        if last != 0 {
-                            return None;
-                        }
-                        target.pop();
-                    }
-                    return Some(target);
+                    self.state = 4; // expect one more '=' before done
+                    Ok(())
                 }
-                3 => {
-                    // Check trailing whitespace only
-                    for c in chars {
-                        if !c.is_ascii_whitespace() {
-                            return None;
-                        }
-                    }
-                    // Check extra bits are zero
-                    if let Some(&last) = target.last() {
-                        if last != 0 {
-                            return None;
+                3 | 4 => {
+                    self.done = true;
+                    if let Some(&last) = self.target.last() {
+                        if last != 0 && self.mode == DecodeMode::Strict {
+                            return Err(offset);
                         }
-                        target.pop();
+                        self.target.pop();
                     }
-                    return Some(target);
+                    Ok(())
                 }
                 _ => unreachable!(),
-            }
+            };
+        }
+        if code == INVALID {
+            return Err(offset);
         }
 
-        let pos = match BASE64.iter().position(|&b| b == ch) {
-            Some(p) => p as u8,
-            None => return None,
-        };
+        let pos = code as u8;
 
-        match state {
+        match self.state {
             0 => {
-                target.push(pos << 2);
-                state = 1;
+                self.target.push(pos << 2);
+                self.state = 1;
             }
             1 => {
-                let last = target.last_mut().unwrap();
+                let last = self.target.last_mut().unwrap();
                 *last |= pos >> 4;
-                target.push((pos & 0x0f) << 4);
-                state = 2;
+                self.target.push((pos & 0x0f) << 4);
+                self.state = 2;
             }
             2 => {
-                let last = target.last_mut().unwrap();
+                let last = self.target.last_mut().unwrap();
                 *last |= pos >> 2;
-                target.push((pos & 0x03) << 6);
-                state = 3;
+                self.target.push((pos & 0x03) << 6);
+                self.state = 3;
             }
             3 => {
-                let last = target.last_mut().unwrap();
+                let last = self.target.last_mut().unwrap();
                 *last |= pos;
-                state = 0;
+                self.state = 0;
             }
+            4 => return Err(offset), // non-whitespace after first '='
             _ => unreachable!(),
         }
+        Ok(())
     }
 
-    if state != 0 {
-        return None;
+    /// Finish decoding. `Err(offset)` if the quantum was left incomplete
+    /// (truncated input), where `offset` is the position just past the
+    /// last byte fed. In [`DecodeMode::Forgiving`], a final group missing
+    /// its `=` padding (2 or 3 symbols, or 2 symbols plus one of the two
+    /// expected pads) is accepted instead, inferring the output length
+    /// from the symbol count the same way full padding would have.
+    pub fn finish(self) -> Result<Vec<u8>, usize> {
+        if self.done || self.state == 0 {
+            return Ok(self.target);
+        }
+        match self.state {
+            2 | 3 | 4 if self.mode == DecodeMode::Forgiving => {
+                let mut target = self.target;
+                target.pop(); // drop the partial byte an `=` pad would have dropped
+                Ok(target)
+            }
+            _ => Err(self.offset),
+        }
     }
+}
 
-    Some(target)
+impl Default for Base64Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decode base64 `src` into bytes, tolerant of embedded whitespace.
+/// Returns `Err(offset)` giving the byte offset of the first invalid
+/// character (or of EOF, for a truncated quantum) instead of a flat
+/// failure. Uses the standard alphabet; see
+/// [`read_base64_tolerant_with_alphabet`] for others.
+pub fn read_base64_tolerant(src: &str) -> Result<Vec<u8>, usize> {
+    read_base64_tolerant_with_alphabet(src, &Alphabet::STANDARD)
+}
+
+/// Like [`read_base64_tolerant`], but decoding `alphabet`'s symbols
+/// instead of always the standard one.
+pub fn read_base64_tolerant_with_alphabet(src: &str, alphabet: &Alphabet) -> Result<Vec<u8>, usize> {
+    read_base64_tolerant_with_options(src, *alphabet, DecodeMode::Strict)
+}
+
+/// Like [`read_base64_tolerant`], but with both `alphabet` and `mode`
+/// configurable.
+pub fn read_base64_tolerant_with_options(
+    src: &str,
+    alphabet: Alphabet,
+    mode: DecodeMode,
+) -> Result<Vec<u8>, usize> {
+    let mut decoder = Base64Decoder::with_options(alphabet, mode);
+    for ch in src.bytes() {
+        decoder.feed(ch)?;
+    }
+    decoder.finish()
+}
+
+/// Decode base64 `src` into bytes. Returns None on invalid input. Uses the
+/// standard alphabet; see [`read_base64_with_alphabet`] for others.
+pub fn read_base64(src: &str) -> Option<Vec<u8>> {
+    read_base64_tolerant(src).ok()
+}
+
+/// Like [`read_base64`], but decoding `alphabet`'s symbols instead of
+/// always the standard one.
+pub fn read_base64_with_alphabet(src: &str, alphabet: &Alphabet) -> Option<Vec<u8>> {
+    read_base64_tolerant_with_alphabet(src, alphabet).ok()
+}
+
+/// Decode base64 `src` into bytes, in [`DecodeMode::Forgiving`]: missing
+/// or extra `=` padding and nonzero trailing bits are tolerated rather
+/// than rejected, while a byte outside the alphabet is still an error.
+/// Uses the standard alphabet.
+pub fn read_base64_lenient(src: &str) -> Option<Vec<u8>> {
+    read_base64_tolerant_with_options(src, Alphabet::STANDARD, DecodeMode::Forgiving).ok()
 }
 
 #[cfg(test)]
@@ -173,35 +648,35 @@ mod tests {
     #[test]
     fn encode_empty() {
         let mut buf = Vec::new();
-        print_base64(b"", &mut buf).unwrap();
+        print_base64(b"", &mut buf, 76).unwrap();
         assert_eq!(buf, b"");
     }
 
     #[test]
     fn encode_hello() {
         let mut buf = Vec::new();
-        print_base64(b"hello", &mut buf).unwrap();
+        print_base64(b"hello", &mut buf, 76).unwrap();
         assert_eq!(String::from_utf8(buf).unwrap(), "aGVsbG8=");
     }
 
     #[test]
     fn encode_one_byte() {
         let mut buf = Vec::new();
-        print_base64(b"a", &mut buf).unwrap();
+        print_base64(b"a", &mut buf, 76).unwrap();
         assert_eq!(String::from_utf8(buf).unwrap(), "YQ==");
     }
 
     #[test]
     fn encode_two_bytes() {
         let mut buf = Vec::new();
-        print_base64(b"ab", &mut buf).unwrap();
+        print_base64(b"ab", &mut buf, 76).unwrap();
         assert_eq!(String::from_utf8(buf).unwrap(), "YWI=");
     }
 
     #[test]
     fn encode_three_bytes() {
         let mut buf = Vec::new();
-        print_base64(b"abc", &mut buf).unwrap();
+        print_base64(b"abc", &mut buf, 76).unwrap();
         assert_eq!(String::from_utf8(buf).unwrap(), "YWJj");
     }
 
@@ -234,6 +709,79 @@ mod tests {
         assert!(read_base64("!!!").is_none());
     }
 
+    #[test]
+    fn tolerant_reports_offset_of_bad_char() {
+        assert_eq!(read_base64_tolerant("YWJj!ZGVm"), Err(4));
+    }
+
+    #[test]
+    fn tolerant_reports_offset_past_truncated_quantum() {
+        assert_eq!(read_base64_tolerant("YWJjY"), Err(5));
+    }
+
+    #[test]
+    fn lenient_accepts_missing_padding() {
+        assert_eq!(read_base64_lenient("YWI").unwrap(), b"ab"); // "YWI=" without the '='
+        assert_eq!(read_base64_lenient("YQ").unwrap(), b"a"); // "YQ==" without either '='
+        assert_eq!(read_base64_lenient("YWJj").unwrap(), b"abc"); // already a full quantum
+    }
+
+    #[test]
+    fn strict_still_rejects_missing_padding() {
+        assert!(read_base64("YWI").is_none());
+        assert!(read_base64("YQ").is_none());
+    }
+
+    #[test]
+    fn lenient_ignores_nonzero_trailing_bits() {
+        // "YQ==" decodes to b"a"; flipping the second symbol sets bits that
+        // a canonical encoder would leave zero.
+        assert_eq!(read_base64_lenient("YR==").unwrap(), b"a");
+        assert!(read_base64("YR==").is_none());
+    }
+
+    #[test]
+    fn lenient_tolerates_extra_padding() {
+        // "YQ==" (canonical for b"a") with one extra, unneeded '='.
+        assert_eq!(read_base64_lenient("YQ===").unwrap(), b"a");
+        assert!(read_base64("YQ===").is_none());
+    }
+
+    #[test]
+    fn lenient_still_rejects_invalid_alphabet_characters() {
+        assert!(read_base64_lenient("YWJj!").is_none());
+    }
+
+    #[test]
+    fn lenient_still_rejects_a_single_dangling_symbol() {
+        assert!(read_base64_lenient("Y").is_none());
+    }
+
+    #[test]
+    fn base64_display_matches_print_base64_unfolded() {
+        for data in [&b""[..], b"a", b"ab", b"abc", b"hello world, this is a longer value"] {
+            let mut buf = Vec::new();
+            print_base64(data, &mut buf, 0).unwrap();
+            assert_eq!(Base64Display(data).to_string(), String::from_utf8(buf).unwrap());
+        }
+    }
+
+    #[test]
+    fn base64_display_never_folds() {
+        let data = vec![0xFFu8; 200];
+        let s = Base64Display(&data).to_string();
+        assert!(!s.contains('\n'), "expected no folding in: {}", s);
+    }
+
+    #[test]
+    fn decoder_feed_incrementally() {
+        let mut decoder = Base64Decoder::new();
+        for ch in "YWJj".bytes() {
+            decoder.feed(ch).unwrap();
+        }
+        assert_eq!(decoder.finish().unwrap(), b"abc");
+    }
+
     #[test]
     fn decode_with_whitespace() {
         let decoded = read_base64("YWJj\n ZGVm").unwrap();
@@ -264,8 +812,171 @@ mod tests {
         // 58+ bytes should trigger folding
         let data = vec![0xFFu8; 60];
         let mut buf = Vec::new();
-        print_base64(&data, &mut buf).unwrap();
+        print_base64(&data, &mut buf, 76).unwrap();
         let s = String::from_utf8(buf).unwrap();
         assert!(s.contains("\n "), "expected line folding in: {}", s);
     }
+
+    #[test]
+    fn wrapped_some_matches_print_base64() {
+        let data = vec![0xFFu8; 60];
+        let mut expected = Vec::new();
+        print_base64(&data, &mut expected, 76).unwrap();
+
+        let mut buf = Vec::new();
+        print_base64_wrapped(&data, &mut buf, Some(76)).unwrap();
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn wrapped_none_never_folds() {
+        let data = vec![0xFFu8; 200];
+        let mut buf = Vec::new();
+        print_base64_wrapped(&data, &mut buf, None).unwrap();
+        let s = String::from_utf8(buf).unwrap();
+        assert!(!s.contains('\n'), "expected no folding in: {}", s);
+    }
+
+    #[test]
+    fn decode_table_agrees_with_the_alphabet() {
+        let standard = Alphabet::STANDARD;
+        for (i, &ch) in BASE64.iter().enumerate() {
+            assert_eq!(standard.decode[ch as usize], i as i8);
+        }
+    }
+
+    #[test]
+    fn decode_table_marks_pad_and_whitespace_and_invalid() {
+        let standard = Alphabet::STANDARD;
+        assert_eq!(standard.decode[PAD64 as usize], PAD);
+        for &ch in b" \t\n\r\x0c" {
+            assert_eq!(standard.decode[ch as usize], WHITESPACE);
+        }
+        assert_eq!(standard.decode[b'!' as usize], INVALID);
+        assert_eq!(standard.decode[0], INVALID);
+    }
+
+    #[test]
+    fn url_safe_alphabet_uses_dash_and_underscore() {
+        let mut buf = Vec::new();
+        print_base64_with_alphabet(&[0xfb, 0xff, 0xbf], &mut buf, 76, &Alphabet::URL_SAFE).unwrap();
+        let encoded = String::from_utf8(buf).unwrap();
+        assert_eq!(encoded, "-_-_");
+        let decoded = read_base64_with_alphabet(&encoded, &Alphabet::URL_SAFE).unwrap();
+        assert_eq!(decoded, vec![0xfb, 0xff, 0xbf]);
+    }
+
+    #[test]
+    fn url_safe_alphabet_rejects_standard_specials() {
+        assert!(read_base64_with_alphabet("ab+/", &Alphabet::URL_SAFE).is_none());
+    }
+
+    #[test]
+    fn standard_alphabet_rejects_url_safe_specials() {
+        assert!(read_base64_with_alphabet("ab-_", &Alphabet::STANDARD).is_none());
+    }
+
+    #[test]
+    fn custom_alphabet_round_trips() {
+        let mut symbols = *BASE64;
+        symbols.swap(0, 63); // 'A' <-> '/'
+        let alphabet = Alphabet::custom(symbols);
+
+        let data = b"round trip through a custom alphabet";
+        let mut encoded = String::new();
+        let mut buf = Vec::new();
+        print_base64_with_alphabet(data, &mut buf, 0, &alphabet).unwrap();
+        encoded.push_str(&String::from_utf8(buf).unwrap());
+
+        let decoded = read_base64_with_alphabet(&encoded, &alphabet).unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    #[should_panic(expected = "duplicate byte")]
+    fn custom_alphabet_rejects_duplicate_bytes() {
+        let mut symbols = *BASE64;
+        symbols[1] = symbols[0];
+        Alphabet::custom(symbols);
+    }
+
+    #[test]
+    fn zero_width_never_folds() {
+        let data = vec![0xFFu8; 200];
+        let mut buf = Vec::new();
+        print_base64(&data, &mut buf, 0).unwrap();
+        let s = String::from_utf8(buf).unwrap();
+        assert!(!s.contains('\n'), "expected no folding in: {}", s);
+    }
+
+    #[test]
+    fn writer_matches_print_base64_byte_at_a_time() {
+        let data = b"The quick brown fox jumps over the lazy dog, repeated a few times over";
+        let mut expected = Vec::new();
+        print_base64(data, &mut expected, 76).unwrap();
+
+        let mut writer = Base64Writer::new(Vec::new());
+        for &b in data {
+            writer.write_all(&[b]).unwrap();
+        }
+        let out = writer.finish().unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn writer_matches_print_base64_in_large_chunks() {
+        let data: Vec<u8> = (0..=255u8).cycle().take(300).collect();
+        let mut expected = Vec::new();
+        print_base64(&data, &mut expected, 76).unwrap();
+
+        let mut writer = Base64Writer::new(Vec::new());
+        writer.write_all(&data).unwrap();
+        let out = writer.finish().unwrap();
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn writer_pads_one_and_two_byte_tails() {
+        let mut writer = Base64Writer::new(Vec::new());
+        writer.write_all(b"ab").unwrap();
+        let out = writer.finish().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "YWI=");
+
+        let mut writer = Base64Writer::new(Vec::new());
+        writer.write_all(b"a").unwrap();
+        let out = writer.finish().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "YQ==");
+    }
+
+    #[test]
+    fn reader_decodes_incrementally_fed_chunks() {
+        let encoded = "YWJjZGVmYWJjZGVmYWJjZGVm";
+        let mut reader = Base64Reader::new(Vec::new());
+        for chunk in encoded.as_bytes().chunks(3) {
+            reader.write_all(chunk).unwrap();
+        }
+        let out = reader.finish().unwrap();
+        assert_eq!(out, read_base64(encoded).unwrap());
+    }
+
+    #[test]
+    fn reader_reports_invalid_input() {
+        let mut reader = Base64Reader::new(Vec::new());
+        assert!(reader.write_all(b"!!!").is_err());
+    }
+
+    #[test]
+    fn writer_reader_round_trip() {
+        let data: Vec<u8> = (0..=255).collect();
+
+        let mut writer = Base64Writer::new(Vec::new());
+        writer.write_all(&data).unwrap();
+        let encoded = writer.finish().unwrap();
+
+        let mut reader = Base64Reader::new(Vec::new());
+        reader.write_all(&encoded).unwrap();
+        let decoded = reader.finish().unwrap();
+
+        assert_eq!(decoded, data);
+    }
 }
@@ -0,0 +1,361 @@
+//! A minimal, hand-rolled BER/DER decoder scoped to the subset of RFC 4511
+//! this crate needs to recover `Entry`s from captured LDAP wire traffic
+//! (e.g. a packet dump or a raw server response saved to disk) -- not a
+//! general ASN.1 library. Definite-length encoding only, which is
+//! everything an LDAP server ever sends over TCP; indefinite-length BER is
+//! rejected rather than guessed at.
+//!
+//! Following the eager, fully-recursive decode rusticata's `ldap-parser`
+//! uses, every constructed value's children are parsed up front (rather
+//! than lazily, on demand) into a [`BerValue`] tree, with [`MAX_DEPTH`]
+//! enforced at each recursive step -- a corrupt or hostile encoding with
+//! runaway SEQUENCE nesting errors out instead of blowing the stack.
+//!
+//! [`decode_search_result_entries`] is the only public entry point: it
+//! walks a buffer that may hold any number of concatenated LDAPMessage
+//! encodings and returns the [`Entry`] carried by each `SearchResultEntry`
+//! it finds, in the same shape [`crate::parseldif::LdifParser::read_entry`]
+//! produces, so filtering, editing, and LDIF re-emission all work on it
+//! unchanged. Every other message type (bind responses, search-result-done,
+//! etc.) is skipped rather than erroring, since a captured session
+//! legitimately contains a mix of message types.
+
+use crate::data::{Attribute, Entry};
+use crate::error::{LdapviError, Result};
+
+/// Recursion depth limit while eagerly parsing nested constructed values.
+const MAX_DEPTH: usize = 32;
+
+/// The LDAP `protocolOp` APPLICATION tag for `SearchResultEntry`
+/// (RFC 4511 §4.11, operation 4).
+const SEARCH_RESULT_ENTRY_TAG: u64 = 4;
+
+/// Class of a BER identifier octet (X.690 §8.1.2.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Universal,
+    Application,
+    ContextSpecific,
+    Private,
+}
+
+/// One decoded TLV: its class/tag/constructed-ness, its raw content bytes,
+/// and -- if constructed -- the TLVs recursively decoded from that content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BerValue<'a> {
+    class: Class,
+    tag: u64,
+    constructed: bool,
+    content: &'a [u8],
+    children: Vec<BerValue<'a>>,
+}
+
+/// Decode one BER TLV from the start of `data`, recursively decoding its
+/// children if it's constructed, and returning it along with the number of
+/// bytes consumed. `depth` is how deeply nested this TLV already is.
+fn decode_one(data: &[u8], depth: usize) -> Result<(BerValue<'_>, usize)> {
+    if depth > MAX_DEPTH {
+        return Err(LdapviError::Other("BER nesting exceeds the depth limit.".to_string()));
+    }
+
+    let mut pos = 0;
+    let first = *data
+        .first()
+        .ok_or_else(|| LdapviError::Other("Truncated BER identifier octet.".to_string()))?;
+    pos += 1;
+
+    let class = match first >> 6 {
+        0 => Class::Universal,
+        1 => Class::Application,
+        2 => Class::ContextSpecific,
+        _ => Class::Private,
+    };
+    let constructed = first & 0x20 != 0;
+
+    let mut tag = (first & 0x1f) as u64;
+    if tag == 0x1f {
+        // High-tag-number form: base-128 continuation, MSB set on every
+        // byte but the last.
+        tag = 0;
+        loop {
+            let byte = *data
+                .get(pos)
+                .ok_or_else(|| LdapviError::Other("Truncated BER tag number.".to_string()))?;
+            pos += 1;
+            tag = (tag << 7) | (byte & 0x7f) as u64;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+    }
+
+    let len_byte = *data
+        .get(pos)
+        .ok_or_else(|| LdapviError::Other("Truncated BER length octet.".to_string()))?;
+    pos += 1;
+    let length = if len_byte & 0x80 == 0 {
+        len_byte as usize
+    } else {
+        let n = (len_byte & 0x7f) as usize;
+        if n == 0 {
+            return Err(LdapviError::Other(
+                "Indefinite-length BER is not supported.".to_string(),
+            ));
+        }
+        if n > std::mem::size_of::<usize>() {
+            return Err(LdapviError::Other("BER length field too large.".to_string()));
+        }
+        let bytes = data
+            .get(pos..pos + n)
+            .ok_or_else(|| LdapviError::Other("Truncated BER length.".to_string()))?;
+        pos += n;
+        bytes.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize)
+    };
+
+    let end = pos
+        .checked_add(length)
+        .ok_or_else(|| LdapviError::Other("BER length field too large.".to_string()))?;
+    let content = data
+        .get(pos..end)
+        .ok_or_else(|| LdapviError::Other("Truncated BER content.".to_string()))?;
+    pos = end;
+
+    let children = if constructed {
+        decode_all(content, depth + 1)?
+    } else {
+        Vec::new()
+    };
+
+    Ok((
+        BerValue {
+            class,
+            tag,
+            constructed,
+            content,
+            children,
+        },
+        pos,
+    ))
+}
+
+/// Decode every BER TLV concatenated in `data`, each `depth` deep --
+/// recursively decoding each one's own children along the way. Used both
+/// for top-level LDAPMessage framing and, via `decode_one`, for a
+/// constructed value's nested contents.
+fn decode_all(data: &[u8], depth: usize) -> Result<Vec<BerValue<'_>>> {
+    let mut values = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let (value, consumed) = decode_one(&data[pos..], depth)?;
+        values.push(value);
+        pos += consumed;
+    }
+    Ok(values)
+}
+
+fn require_utf8(content: &[u8], what: &str) -> Result<String> {
+    String::from_utf8(content.to_vec())
+        .map_err(|_| LdapviError::Other(format!("{} is not valid UTF-8.", what)))
+}
+
+/// Decode one already-parsed `SearchResultEntry` value's children
+/// (`objectName`, `attributes`) into an [`Entry`].
+fn entry_from_search_result_entry(value: &BerValue) -> Result<Entry> {
+    let object_name = value
+        .children
+        .first()
+        .ok_or_else(|| LdapviError::Other("SearchResultEntry missing objectName.".to_string()))?;
+    let dn = require_utf8(object_name.content, "SearchResultEntry objectName")?;
+    let mut entry = Entry::new(dn);
+
+    let attribute_list = value.children.get(1).ok_or_else(|| {
+        LdapviError::Other("SearchResultEntry missing attribute list.".to_string())
+    })?;
+    for partial_attr in &attribute_list.children {
+        let ad = partial_attr
+            .children
+            .first()
+            .ok_or_else(|| LdapviError::Other("PartialAttribute missing type.".to_string()))?;
+        let mut attribute = Attribute::new(require_utf8(ad.content, "Attribute description")?);
+
+        let vals = partial_attr
+            .children
+            .get(1)
+            .ok_or_else(|| LdapviError::Other("PartialAttribute missing values.".to_string()))?;
+        for val in &vals.children {
+            attribute.append_value(val.content);
+        }
+        entry.attributes.push(attribute);
+    }
+
+    Ok(entry)
+}
+
+/// Decode every `SearchResultEntry` in `data`, which may hold any number of
+/// concatenated LDAPMessage encodings (e.g. the reassembled payload of an
+/// LDAP search response, taken from a packet dump). See the module
+/// documentation for what's skipped and why.
+pub fn decode_search_result_entries(data: &[u8]) -> Result<Vec<Entry>> {
+    let messages = decode_all(data, 0)?;
+    let mut entries = Vec::new();
+    for message in &messages {
+        if !message.constructed {
+            return Err(LdapviError::Other(
+                "LDAPMessage must be a constructed SEQUENCE.".to_string(),
+            ));
+        }
+        let proto_op = match message.children.get(1) {
+            Some(f) => f,
+            None => continue,
+        };
+        if proto_op.class == Class::Application && proto_op.tag == SEARCH_RESULT_ENTRY_TAG {
+            entries.push(entry_from_search_result_entry(proto_op)?);
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn len_octet(len: usize) -> Vec<u8> {
+        if len < 128 {
+            vec![len as u8]
+        } else {
+            let bytes = len.to_be_bytes();
+            let trimmed: Vec<u8> = bytes.iter().copied().skip_while(|&b| b == 0).collect();
+            let mut out = vec![0x80 | trimmed.len() as u8];
+            out.extend(trimmed);
+            out
+        }
+    }
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        out.extend(len_octet(content.len()));
+        out.extend_from_slice(content);
+        out
+    }
+
+    /// Build one LDAPMessage wrapping a SearchResultEntry for `dn` with
+    /// `attrs` as `(name, values)` pairs.
+    fn search_result_entry_message(message_id: i32, dn: &str, attrs: &[(&str, &[&[u8]])]) -> Vec<u8> {
+        let mut partial_attrs = Vec::new();
+        for (name, values) in attrs {
+            let vals: Vec<u8> = values.iter().flat_map(|v| tlv(0x04, v)).collect(); // OCTET STRING each
+            let set_of_vals = tlv(0x31, &vals); // SET OF
+            let mut partial_attr_content = tlv(0x04, name.as_bytes()); // type
+            partial_attr_content.extend(set_of_vals);
+            partial_attrs.extend(tlv(0x30, &partial_attr_content)); // SEQUENCE
+        }
+        let attribute_list = tlv(0x30, &partial_attrs); // SEQUENCE OF
+
+        let mut search_result_entry_content = tlv(0x04, dn.as_bytes()); // objectName
+        search_result_entry_content.extend(attribute_list);
+        let search_result_entry = tlv(0x64, &search_result_entry_content); // [APPLICATION 4], constructed
+
+        let message_id_bytes = tlv(0x02, &message_id.to_be_bytes()); // INTEGER
+        let mut message_content = message_id_bytes;
+        message_content.extend(search_result_entry);
+        tlv(0x30, &message_content) // LDAPMessage SEQUENCE
+    }
+
+    #[test]
+    fn decodes_a_single_search_result_entry() {
+        let data = search_result_entry_message(
+            1,
+            "cn=foo,dc=example,dc=com",
+            &[("cn", &[b"foo".as_slice()])],
+        );
+        let entries = decode_search_result_entries(&data).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].dn, "cn=foo,dc=example,dc=com");
+        assert_eq!(entries[0].get_attribute("cn").unwrap().values, vec![b"foo".to_vec()]);
+    }
+
+    #[test]
+    fn decodes_multiple_values_for_one_attribute() {
+        let data = search_result_entry_message(
+            1,
+            "cn=foo,dc=example,dc=com",
+            &[("mail", &[b"a@example.com".as_slice(), b"b@example.com".as_slice()])],
+        );
+        let entries = decode_search_result_entries(&data).unwrap();
+        assert_eq!(
+            entries[0].get_attribute("mail").unwrap().values,
+            vec![b"a@example.com".to_vec(), b"b@example.com".to_vec()]
+        );
+    }
+
+    #[test]
+    fn decodes_concatenated_messages() {
+        let mut data = search_result_entry_message(1, "cn=a,dc=example,dc=com", &[]);
+        data.extend(search_result_entry_message(2, "cn=b,dc=example,dc=com", &[]));
+        let entries = decode_search_result_entries(&data).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].dn, "cn=a,dc=example,dc=com");
+        assert_eq!(entries[1].dn, "cn=b,dc=example,dc=com");
+    }
+
+    #[test]
+    fn non_search_result_entry_messages_are_skipped() {
+        // protocolOp tag 5 == searchResDone, not 4.
+        let message_id = tlv(0x02, &1i32.to_be_bytes());
+        let search_res_done = tlv(0x65, &[0x0a, 0x01, 0x00, 0x04, 0x00, 0x04, 0x00]);
+        let mut content = message_id;
+        content.extend(search_res_done);
+        let data = tlv(0x30, &content);
+
+        assert_eq!(decode_search_result_entries(&data).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn binary_values_survive_verbatim() {
+        let data = search_result_entry_message(
+            1,
+            "cn=foo,dc=example,dc=com",
+            &[("jpegPhoto", &[&[0xff, 0x00, 0xd8, 0xff][..]])],
+        );
+        let entries = decode_search_result_entries(&data).unwrap();
+        assert_eq!(
+            entries[0].get_attribute("jpegPhoto").unwrap().values[0],
+            vec![0xff, 0x00, 0xd8, 0xff]
+        );
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let mut data = search_result_entry_message(1, "cn=foo,dc=example,dc=com", &[]);
+        data.truncate(data.len() - 5);
+        assert!(decode_search_result_entries(&data).is_err());
+    }
+
+    #[test]
+    fn huge_long_form_length_is_an_error_not_a_panic() {
+        // SEQUENCE, 8-byte long-form length field of all 0xFF -- the
+        // largest length BER can encode, which overflows `usize` when
+        // added to even a small `pos` instead of just exceeding the
+        // buffer.
+        let data = vec![0x30, 0x88, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        assert!(decode_search_result_entries(&data).is_err());
+    }
+
+    #[test]
+    fn indefinite_length_is_rejected() {
+        // SEQUENCE, indefinite length (0x80), immediately EOC'd.
+        let data = vec![0x30, 0x80, 0x00, 0x00];
+        assert!(decode_search_result_entries(&data).is_err());
+    }
+
+    #[test]
+    fn deeply_nested_construct_hits_the_depth_limit() {
+        // Nest an empty SEQUENCE inside itself past MAX_DEPTH -- decoding
+        // must error instead of recursing without bound.
+        let mut inner: Vec<u8> = vec![];
+        for _ in 0..(MAX_DEPTH + 10) {
+            inner = tlv(0x30, &inner);
+        }
+        assert!(decode_search_result_entries(&inner).is_err());
+    }
+}
@@ -1,14 +1,118 @@
+/// A byte-oriented string for fields -- like an attribute descriptor --
+/// that LDAP does not require to be valid UTF-8. Bytes are accepted as-is
+/// and stored verbatim; UTF-8 validity is checked lazily, only when a
+/// caller asks for `&str` via [`ByteString::as_str`]. Ordering and
+/// equality are byte-wise, matching LDAP's own octet-string comparison
+/// semantics and sidestepping the question of UTF-8 validity entirely.
+#[derive(Debug, Clone, Default)]
+pub struct ByteString(Vec<u8>);
+
+impl ByteString {
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        ByteString(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Validate as UTF-8. On failure, returns the byte offset of the first
+    /// invalid sequence alongside the underlying error.
+    pub fn as_str(&self) -> std::result::Result<&str, (std::str::Utf8Error, usize)> {
+        std::str::from_utf8(&self.0).map_err(|e| {
+            let offset = e.valid_up_to();
+            (e, offset)
+        })
+    }
+
+    /// Lossily convert to a string, substituting U+FFFD for invalid
+    /// sequences. For call sites (printing, diagnostics) that don't need
+    /// to distinguish "invalid" from "replaced".
+    pub fn as_str_lossy(&self) -> std::borrow::Cow<'_, str> {
+        String::from_utf8_lossy(&self.0)
+    }
+
+    pub fn eq_ignore_ascii_case(&self, other: &str) -> bool {
+        self.0.eq_ignore_ascii_case(other.as_bytes())
+    }
+}
+
+impl From<String> for ByteString {
+    fn from(s: String) -> Self {
+        ByteString(s.into_bytes())
+    }
+}
+
+impl From<&str> for ByteString {
+    fn from(s: &str) -> Self {
+        ByteString(s.as_bytes().to_vec())
+    }
+}
+
+impl From<Vec<u8>> for ByteString {
+    fn from(bytes: Vec<u8>) -> Self {
+        ByteString(bytes)
+    }
+}
+
+impl PartialEq for ByteString {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for ByteString {}
+
+impl PartialEq<str> for ByteString {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl PartialEq<&str> for ByteString {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == other.as_bytes()
+    }
+}
+
+impl PartialOrd for ByteString {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ByteString {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl std::hash::Hash for ByteString {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
 /// An LDAP entry: a DN with a list of attributes.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Entry {
     pub dn: String,
     pub attributes: Vec<Attribute>,
 }
 
 /// An attribute: a descriptor (name) with a list of binary-safe values.
-#[derive(Debug, Clone)]
+/// The descriptor is stored as raw bytes (see [`ByteString`]) rather than
+/// `String`, so a non-UTF-8 or unusual-but-legal attribute name (e.g. one
+/// carrying a non-ASCII option) survives parsing instead of aborting it;
+/// callers that need text call `ad.as_str()` and handle the position of
+/// any invalid byte themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Attribute {
-    pub ad: String,
+    pub ad: ByteString,
     pub values: Vec<Vec<u8>>,
 }
 
@@ -35,6 +139,33 @@ pub struct LdapMod {
     pub values: Vec<Vec<u8>>,
 }
 
+impl LdapMod {
+    /// Find the first value in `values` that's byte-identical to an
+    /// earlier one, returning it. Used before handing an Add/Replace off
+    /// to the LDAP client library, whose `HashSet`-based wire encoding
+    /// would otherwise silently collapse the duplicate instead of
+    /// surfacing the mistake (the server would reject it as
+    /// attributeOrValueExists / a constraint violation).
+    pub fn find_duplicate_value(&self) -> Option<&[u8]> {
+        for (i, value) in self.values.iter().enumerate() {
+            if self.values[..i].iter().any(|earlier| earlier == value) {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+/// An RFC 2849 LDIF control, as attached to a change record by one or more
+/// `control:` lines preceding its `changetype:` line. `value` is `None` when
+/// the control carried no value at all (bare `control: <oid> [criticality]`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Control {
+    pub oid: String,
+    pub criticality: bool,
+    pub value: Option<Vec<u8>>,
+}
+
 /// A rename (modrdn) record.
 #[derive(Debug, Clone)]
 pub struct RenameRecord {
@@ -65,7 +196,7 @@ impl Entry {
         match pos {
             Some(i) => Some(&mut self.attributes[i]),
             None if create => {
-                self.attributes.push(Attribute::new(ad.to_string()));
+                self.attributes.push(Attribute::new(ad));
                 self.attributes.last_mut()
             }
             None => None,
@@ -77,16 +208,141 @@ impl Entry {
         self.attributes.iter().find(|a| a.ad == ad)
     }
 
+    /// Find an attribute by descriptor bytes, without requiring the
+    /// descriptor to be valid UTF-8 first. Used by the parser, where the
+    /// raw bytes of an attribute name are known before (and regardless of)
+    /// whether they validate as UTF-8.
+    /// If `create` is true and the attribute doesn't exist, create it.
+    pub fn find_attribute_bytes(&mut self, ad: &[u8], create: bool) -> Option<&mut Attribute> {
+        let pos = self.attributes.iter().position(|a| a.ad.as_bytes() == ad);
+        match pos {
+            Some(i) => Some(&mut self.attributes[i]),
+            None if create => {
+                self.attributes
+                    .push(Attribute::new(ByteString::from_bytes(ad.to_vec())));
+                self.attributes.last_mut()
+            }
+            None => None,
+        }
+    }
+
     /// Convert entry to a list of Mod structs (one per attribute).
     pub fn to_mods(&self) -> Vec<Mod> {
         self.attributes
             .iter()
             .map(|a| Mod {
-                attr: a.ad.clone(),
+                attr: a.ad.as_str_lossy().into_owned(),
                 values: a.values.clone(),
             })
             .collect()
     }
+
+    /// A canonicalized copy: attributes sorted by descriptor (ASCII
+    /// case-insensitive, ties broken bytewise -- the same comparator
+    /// [`crate::hash::entry_hash`] uses), each attribute's values sorted
+    /// lexicographically, and the DN re-encoded through
+    /// [`crate::dn::parse_dn`]/[`crate::dn::encode_dn`] so two RFC 4514
+    /// strings denoting the same name normalize to the same bytes. Two
+    /// entries that are the same entry from the directory's perspective
+    /// canonicalize to identical structures regardless of the order
+    /// `SearchEntry` happened to return things in. A DN that fails to
+    /// parse is left as-is rather than dropped.
+    pub fn canonicalize(&self) -> Entry {
+        let dn = crate::dn::parse_dn(&self.dn)
+            .map(|rdns| crate::dn::encode_dn(&rdns))
+            .unwrap_or_else(|_| self.dn.clone());
+
+        let mut attributes: Vec<Attribute> = self
+            .attributes
+            .iter()
+            .map(|a| {
+                let mut values = a.values.clone();
+                values.sort();
+                Attribute {
+                    ad: a.ad.clone(),
+                    values,
+                }
+            })
+            .collect();
+        attributes.sort_by(|a, b| {
+            let (a, b) = (a.ad.as_bytes(), b.ad.as_bytes());
+            a.to_ascii_lowercase()
+                .cmp(&b.to_ascii_lowercase())
+                .then_with(|| a.cmp(b))
+        });
+
+        Entry { dn, attributes }
+    }
+
+    /// A byte-exact canonical binary encoding of this entry (canonicalized
+    /// first, see [`Entry::canonicalize`]): the DN, then each attribute's
+    /// descriptor and values, every field a little-endian `u32` length
+    /// prefix followed by its raw bytes, so no delimiter is needed and
+    /// binary values survive untouched. Two servers returning the same
+    /// logical entry in different attribute/value order produce identical
+    /// bytes, so this is usable as a change-detection key or a digest
+    /// input. [`Entry::from_canonical_bytes`] is the inverse.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        fn put(out: &mut Vec<u8>, data: &[u8]) {
+            out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            out.extend_from_slice(data);
+        }
+
+        let entry = self.canonicalize();
+        let mut out = Vec::new();
+        put(&mut out, entry.dn.as_bytes());
+        out.extend_from_slice(&(entry.attributes.len() as u32).to_le_bytes());
+        for attr in &entry.attributes {
+            put(&mut out, attr.ad.as_bytes());
+            out.extend_from_slice(&(attr.values.len() as u32).to_le_bytes());
+            for value in &attr.values {
+                put(&mut out, value);
+            }
+        }
+        out
+    }
+
+    /// Decode bytes produced by [`Entry::canonical_bytes`] back into an
+    /// `Entry`. Since the encoding is already canonical, round-tripping a
+    /// canonicalized entry through `canonical_bytes`/`from_canonical_bytes`
+    /// reproduces it exactly.
+    pub fn from_canonical_bytes(data: &[u8]) -> Result<Entry, String> {
+        fn take<'a>(data: &'a [u8], pos: &mut usize, len: usize) -> Result<&'a [u8], String> {
+            let end = pos
+                .checked_add(len)
+                .filter(|&end| end <= data.len())
+                .ok_or_else(|| "canonical entry: truncated field".to_string())?;
+            let slice = &data[*pos..end];
+            *pos = end;
+            Ok(slice)
+        }
+        fn take_u32(data: &[u8], pos: &mut usize) -> Result<u32, String> {
+            Ok(u32::from_le_bytes(take(data, pos, 4)?.try_into().unwrap()))
+        }
+        fn take_field<'a>(data: &'a [u8], pos: &mut usize) -> Result<&'a [u8], String> {
+            let len = take_u32(data, pos)? as usize;
+            take(data, pos, len)
+        }
+
+        let mut pos = 0usize;
+        let dn = String::from_utf8(take_field(data, &mut pos)?.to_vec())
+            .map_err(|e| format!("canonical entry: invalid utf-8 dn: {}", e))?;
+        let mut entry = Entry::new(dn);
+
+        let attr_count = take_u32(data, &mut pos)?;
+        for _ in 0..attr_count {
+            let ad = take_field(data, &mut pos)?.to_vec();
+            let attr = entry.find_attribute_bytes(&ad, true).unwrap();
+            let value_count = take_u32(data, &mut pos)?;
+            for _ in 0..value_count {
+                attr.values.push(take_field(data, &mut pos)?.to_vec());
+            }
+        }
+        if pos != data.len() {
+            return Err("canonical entry: trailing bytes after entry".to_string());
+        }
+        Ok(entry)
+    }
 }
 
 impl PartialEq for Entry {
@@ -110,9 +366,9 @@ impl Ord for Entry {
 }
 
 impl Attribute {
-    pub fn new(ad: String) -> Attribute {
+    pub fn new(ad: impl Into<ByteString>) -> Attribute {
         Attribute {
-            ad,
+            ad: ad.into(),
             values: Vec::new(),
         }
     }
@@ -140,7 +396,7 @@ impl Attribute {
     /// Convert to a Mod struct.
     pub fn to_mod(&self) -> Mod {
         Mod {
-            attr: self.ad.clone(),
+            attr: self.ad.as_str_lossy().into_owned(),
             values: self.values.clone(),
         }
     }
@@ -362,4 +618,83 @@ mod tests {
         assert_eq!(mods[0].attr, "cn");
         assert_eq!(mods[1].attr, "sn");
     }
+
+    // ── Group 9: LdapMod::find_duplicate_value ──────────────────
+
+    #[test]
+    fn find_duplicate_value_none() {
+        let m = LdapMod {
+            op: ModOp::Add,
+            attr: "mail".to_string(),
+            values: vec![b"a@b.com".to_vec(), b"c@d.com".to_vec()],
+        };
+        assert_eq!(m.find_duplicate_value(), None);
+    }
+
+    #[test]
+    fn find_duplicate_value_found() {
+        let m = LdapMod {
+            op: ModOp::Add,
+            attr: "mail".to_string(),
+            values: vec![b"a@b.com".to_vec(), b"c@d.com".to_vec(), b"a@b.com".to_vec()],
+        };
+        assert_eq!(m.find_duplicate_value(), Some(b"a@b.com".as_slice()));
+    }
+
+    // ── Group 10: canonicalization ────────────────────────────────
+
+    #[test]
+    fn canonicalize_sorts_attributes_and_values() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_value(&mut e, "sn", "z");
+        add_attr_value(&mut e, "cn", "b");
+        e.find_attribute("cn", true).unwrap().values.push(b"a".to_vec());
+
+        let c = e.canonicalize();
+        assert_eq!(c.attributes[0].ad, "cn");
+        assert_eq!(c.attributes[0].values, vec![b"a".to_vec(), b"b".to_vec()]);
+        assert_eq!(c.attributes[1].ad, "sn");
+    }
+
+    #[test]
+    fn canonicalize_normalizes_dn_escaping() {
+        let e = make_entry(r"cn=\66oo,dc=example,dc=com");
+        assert_eq!(e.canonicalize().dn, "cn=foo,dc=example,dc=com");
+    }
+
+    #[test]
+    fn canonical_bytes_independent_of_arrival_order() {
+        let mut a = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_value(&mut a, "cn", "foo");
+        add_attr_value(&mut a, "sn", "bar");
+
+        let mut b = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_value(&mut b, "sn", "bar");
+        add_attr_value(&mut b, "cn", "foo");
+
+        assert_eq!(a.canonical_bytes(), b.canonical_bytes());
+    }
+
+    #[test]
+    fn canonical_bytes_roundtrip() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_value(&mut e, "cn", "foo");
+        e.find_attribute("jpegPhoto", true)
+            .unwrap()
+            .values
+            .push(vec![0x00, 0x01, 0xff]);
+
+        let bytes = e.canonical_bytes();
+        let decoded = Entry::from_canonical_bytes(&bytes).unwrap();
+        assert_eq!(decoded.dn, e.canonicalize().dn);
+        assert_eq!(decoded.attributes, e.canonicalize().attributes);
+    }
+
+    #[test]
+    fn from_canonical_bytes_rejects_truncated_input() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_value(&mut e, "cn", "foo");
+        let bytes = e.canonical_bytes();
+        assert!(Entry::from_canonical_bytes(&bytes[..bytes.len() - 2]).is_err());
+    }
 }
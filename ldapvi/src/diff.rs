@@ -2,7 +2,9 @@
 //!
 //! Compares two streams of LDAP entries and generates modification operations.
 
+use std::fmt;
 use std::io::{Read, Seek};
+use std::time::Duration;
 
 use crate::data::{Entry, LdapMod, ModOp, ModifyRecord, RenameRecord};
 use crate::error::Result;
@@ -18,6 +20,7 @@ use crate::parseldif::LdifParser;
 pub trait EntryParser {
     fn read_entry(&mut self, offset: Option<u64>) -> Result<Option<(String, Entry, u64)>>;
     fn peek_entry(&mut self, offset: Option<u64>) -> Result<Option<(String, u64)>>;
+    fn peek_dn(&mut self, offset: Option<u64>) -> Result<Option<(String, u64)>>;
     fn skip_entry(&mut self, offset: Option<u64>) -> Result<Option<String>>;
     fn read_rename(&mut self, offset: Option<u64>) -> Result<RenameRecord>;
     fn read_delete(&mut self, offset: Option<u64>) -> Result<String>;
@@ -27,16 +30,234 @@ pub trait EntryParser {
     fn parser_read_raw(&mut self, buf: &mut [u8]) -> std::io::Result<usize>;
 }
 
-/// Handler trait for processing diff operations.
-/// Methods return 0 on success, -1 on failure.
+/// The outcome of a single operation dispatched from an [`AsyncDiffHandler`]'s
+/// queue, reported by [`DiffHandler::flush`] and keyed by the same `n` that
+/// was passed to the `enqueue_*` call that queued it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpResult {
+    pub n: i32,
+    pub success: bool,
+}
+
+/// Common supertrait of [`SyncDiffHandler`] and [`AsyncDiffHandler`], giving
+/// [`compare_streams`] a single hook it can call at batch boundaries to drain
+/// whatever an async handler has queued so far.
 pub trait DiffHandler {
-    fn handle_add(&mut self, n: i32, dn: &str, mods: &[LdapMod]) -> i32;
-    fn handle_delete(&mut self, n: i32, dn: &str) -> i32;
-    fn handle_change(&mut self, n: i32, old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> i32;
-    fn handle_rename(&mut self, n: i32, old_dn: &str, entry: &Entry) -> i32;
-    fn handle_rename0(&mut self, n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) -> i32;
+    /// Dispatch any outstanding queued operations and report per-op
+    /// success/failure. The default does nothing, which is correct for
+    /// [`SyncDiffHandler`] implementors: they apply each operation
+    /// immediately via their `handle_*` return code and never queue
+    /// anything.
+    fn flush(&mut self) -> Vec<OpResult> {
+        Vec::new()
+    }
+}
+
+/// The reason a diff operation was rejected, carrying the failing entry's
+/// key `n` (as passed to the corresponding `handle_*`/`enqueue_*` call --
+/// `-1` for an immediate changerecord) and DN alongside the specific cause,
+/// so a caller has enough structure to report something actionable or
+/// decide whether to abort the whole diff or skip just this entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffError {
+    /// A rename's old RDN values are partially present in the renamed
+    /// entry -- neither fully retained nor fully dropped, so `deleteoldrdn`
+    /// can't be determined.
+    RdnMismatch { n: i32, dn: String },
+    /// An entry is missing one of the attribute values its own RDN names.
+    MissingOldRdn { n: i32, dn: String },
+    /// A rename or comparison was attempted against an empty DN.
+    EmptyDn { n: i32, dn: String },
+    /// [`frob_ava`]'s `Check`/`CheckNone` modes found `attr` in the wrong
+    /// state relative to `expected`.
+    AvaCheckFailed {
+        n: i32,
+        dn: String,
+        attr: String,
+        expected: Vec<u8>,
+    },
+    /// A [`SyncDiffHandler`] method rejected the operation, carrying
+    /// whatever code it returned (e.g. an LDAP result code).
+    HandlerRejected { n: i32, dn: String, code: i32 },
+    /// The underlying entry stream failed to read or parse.
+    ParseError { n: i32, dn: String },
+    /// [`check_key_structure`] found the data file's entry keys don't
+    /// account for every original entry -- a duplicate, out-of-range, or
+    /// silently-dropped key line.
+    StructuralMismatch { message: String },
+}
+
+impl DiffError {
+    pub fn n(&self) -> i32 {
+        match self {
+            DiffError::RdnMismatch { n, .. }
+            | DiffError::MissingOldRdn { n, .. }
+            | DiffError::EmptyDn { n, .. }
+            | DiffError::AvaCheckFailed { n, .. }
+            | DiffError::HandlerRejected { n, .. }
+            | DiffError::ParseError { n, .. } => *n,
+            DiffError::StructuralMismatch { .. } => -1,
+        }
+    }
+
+    pub fn dn(&self) -> &str {
+        match self {
+            DiffError::RdnMismatch { dn, .. }
+            | DiffError::MissingOldRdn { dn, .. }
+            | DiffError::EmptyDn { dn, .. }
+            | DiffError::AvaCheckFailed { dn, .. }
+            | DiffError::HandlerRejected { dn, .. }
+            | DiffError::ParseError { dn, .. } => dn,
+            DiffError::StructuralMismatch { .. } => "",
+        }
+    }
+
+    /// Returns the same error with `n` set, for low-level helpers (like
+    /// [`frob_ava`]) that don't know their caller's entry key and record
+    /// `-1` as a placeholder.
+    fn with_n(self, n: i32) -> Self {
+        match self {
+            DiffError::RdnMismatch { dn, .. } => DiffError::RdnMismatch { n, dn },
+            DiffError::MissingOldRdn { dn, .. } => DiffError::MissingOldRdn { n, dn },
+            DiffError::EmptyDn { dn, .. } => DiffError::EmptyDn { n, dn },
+            DiffError::AvaCheckFailed {
+                dn, attr, expected, ..
+            } => DiffError::AvaCheckFailed {
+                n,
+                dn,
+                attr,
+                expected,
+            },
+            DiffError::HandlerRejected { dn, code, .. } => DiffError::HandlerRejected { n, dn, code },
+            DiffError::ParseError { dn, .. } => DiffError::ParseError { n, dn },
+            e @ DiffError::StructuralMismatch { .. } => e,
+        }
+    }
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiffError::RdnMismatch { dn, .. } => {
+                write!(f, "{}: old RDN values are partially present in the renamed entry", dn)
+            }
+            DiffError::MissingOldRdn { dn, .. } => {
+                write!(f, "{}: entry is missing its own RDN attribute value", dn)
+            }
+            DiffError::EmptyDn { .. } => write!(f, "empty DN"),
+            DiffError::AvaCheckFailed { dn, attr, .. } => {
+                write!(f, "{}: AVA check failed for attribute '{}'", dn, attr)
+            }
+            DiffError::HandlerRejected { dn, code, .. } => {
+                write!(f, "{}: handler rejected the operation (code {})", dn, code)
+            }
+            DiffError::ParseError { .. } => write!(f, "entry stream parse error"),
+            DiffError::StructuralMismatch { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for DiffError {}
+
+/// Convenience alias for diff engine results, since [`crate::error::Result`]
+/// is single-parameter and can't carry [`DiffError`].
+pub type DiffResult<T> = std::result::Result<T, DiffError>;
+
+/// Handler trait for processing diff operations one at a time, synchronously.
+/// `Err` aborts the walk and [`compare_streams`] propagates it to its caller,
+/// unless the [`CommitPolicy`] it was given retries the operation into
+/// success or sets `continue_on_error`, which records the failure instead.
+pub trait SyncDiffHandler: DiffHandler {
+    fn handle_add(&mut self, n: i32, dn: &str, mods: &[LdapMod]) -> DiffResult<()>;
+    fn handle_delete(&mut self, n: i32, dn: &str) -> DiffResult<()>;
+    fn handle_change(
+        &mut self,
+        n: i32,
+        old_dn: &str,
+        new_dn: &str,
+        mods: &[LdapMod],
+    ) -> DiffResult<()>;
+    fn handle_rename(&mut self, n: i32, old_dn: &str, entry: &Entry) -> DiffResult<()>;
+    fn handle_rename0(
+        &mut self,
+        n: i32,
+        old_dn: &str,
+        new_dn: &str,
+        deleteoldrdn: bool,
+    ) -> DiffResult<()>;
+
+    /// Classify `err` as worth retrying under a [`CommitPolicy`] with
+    /// `max_retries > 0` -- a transient condition (a network hiccup, a
+    /// server momentarily overloaded) where calling the same `handle_*`
+    /// again might succeed -- versus fatal, where it's pointless (a
+    /// rejected AVA check or a permissions error will fail identically
+    /// every time). Defaults to never-retryable, so a handler that doesn't
+    /// override this sees `max_retries` have no effect.
+    fn is_retryable(&self, _err: &DiffError) -> bool {
+        false
+    }
+}
+
+/// Handler trait for queueing diff operations for pipelined dispatch instead
+/// of applying them immediately, so a caller walking a large edit doesn't
+/// block on a round-trip per entry. The `enqueue_*` methods only record the
+/// operation; the actual network dispatch happens in [`DiffHandler::flush`],
+/// which [`compare_streams`] calls at natural boundaries (after the
+/// add/rename/change walk, and again after deletions).
+pub trait AsyncDiffHandler: DiffHandler {
+    fn enqueue_add(&mut self, n: i32, dn: &str, mods: &[LdapMod]);
+    fn enqueue_delete(&mut self, n: i32, dn: &str);
+    fn enqueue_change(&mut self, n: i32, old_dn: &str, new_dn: &str, mods: &[LdapMod]);
+    fn enqueue_rename0(&mut self, n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool);
+}
+
+/// The overall classification of an entry-level change reported to a
+/// [`DiffObserver`], mirroring the operations [`SyncDiffHandler`] dispatches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffOp {
+    Add,
+    Delete,
+    Modify,
+    Rename,
+}
+
+/// Observes the same numbered-entry merge walk that [`compare_streams`] uses
+/// to build `LdapMod`s, without being able to change anything -- a caller
+/// that only wants a machine-readable preview of a diff (e.g. for `--dry-run`
+/// or an editor "what would this commit do" summary) can implement this
+/// instead of [`SyncDiffHandler`] and run alongside it without touching the
+/// apply path.
+///
+/// Only the numbered-entry comparison (`process_next_entry`/`compare_entries`)
+/// and deletions are observed; immediate changerecords (`add`/`replace`/
+/// `rename`/`delete`/`modify` keys, handled by [`process_immediate`]) carry
+/// their own mods explicitly and aren't derived from a comparison, so there's
+/// nothing for the observer to report there.
+///
+/// Default implementations are no-ops, so a caller only needs to override
+/// the methods it cares about.
+pub trait DiffObserver {
+    /// Called once per attribute with a value-level delta between `clean`
+    /// and `data`, under `cmp`'s matching rules -- independent of whether
+    /// [`DiffMode::Replace`] or [`DiffMode::Granular`] is in effect, and
+    /// independent of how the corresponding `LdapMod`s end up batched.
+    /// `n` is the entry's key in `offsets`, or `-1` from a changerecord.
+    fn note_attribute(&mut self, _n: i32, _dn: &str, _attr: &str, _added: &[Vec<u8>], _removed: &[Vec<u8>]) {}
+
+    /// Called once per entry that changed, classifying the overall
+    /// operation. For a rename that also carries attribute changes, this is
+    /// called twice: once with [`DiffOp::Rename`] and once with
+    /// [`DiffOp::Modify`], mirroring the separate `handle_rename`/
+    /// `handle_change` calls on [`SyncDiffHandler`].
+    fn note_entry(&mut self, _n: i32, _dn: &str, _op: DiffOp) {}
 }
 
+/// A [`DiffObserver`] that reports nothing, for callers that only care about
+/// the apply path.
+pub struct NullObserver;
+
+impl DiffObserver for NullObserver {}
+
 // ===========================================================================
 // EntryParser implementations
 // ===========================================================================
@@ -48,6 +269,9 @@ impl<R: Read + Seek> EntryParser for LdifParser<R> {
     fn peek_entry(&mut self, offset: Option<u64>) -> Result<Option<(String, u64)>> {
         LdifParser::peek_entry(self, offset)
     }
+    fn peek_dn(&mut self, offset: Option<u64>) -> Result<Option<(String, u64)>> {
+        LdifParser::peek_dn(self, offset)
+    }
     fn skip_entry(&mut self, offset: Option<u64>) -> Result<Option<String>> {
         LdifParser::skip_entry(self, offset)
     }
@@ -78,6 +302,9 @@ impl<R: Read + Seek> EntryParser for LdapviParser<R> {
     fn peek_entry(&mut self, offset: Option<u64>) -> Result<Option<(String, u64)>> {
         LdapviParser::peek_entry(self, offset)
     }
+    fn peek_dn(&mut self, offset: Option<u64>) -> Result<Option<(String, u64)>> {
+        LdapviParser::peek_dn(self, offset)
+    }
     fn skip_entry(&mut self, offset: Option<u64>) -> Result<Option<String>> {
         LdapviParser::skip_entry(self, offset)
     }
@@ -101,6 +328,344 @@ impl<R: Read + Seek> EntryParser for LdapviParser<R> {
     }
 }
 
+// ===========================================================================
+// DnIndex -- DN-keyed on-disk index with seek-based binary search
+// ===========================================================================
+
+/// An index over a clean-stream file that maps a normalized DN to the byte
+/// offset of its entry, built by one forward scan with [`EntryParser::peek_dn`]
+/// (so the body of each entry is never materialized) and then sorted by DN.
+///
+/// Only the offsets are kept, in DN-sorted order -- not the DNs themselves --
+/// so the index's memory footprint is proportional to the entry count, not
+/// to the size of the clean file. [`DnIndex::find`] recovers each candidate's
+/// DN during the search itself, by seeking the parser to the candidate offset
+/// and reading just its `dn:` line, the same technique as binary-searching a
+/// sorted, offset-indexed file with `SeekFrom`. This lets a caller match
+/// data-stream entries against a clean baseline that doesn't fit comfortably
+/// in memory.
+pub struct DnIndex {
+    /// Byte offsets of entries in the clean file, sorted by each entry's
+    /// normalized DN.
+    offsets: Vec<u64>,
+}
+
+impl DnIndex {
+    /// Scan every entry in `parser` from the start, recording its offset,
+    /// then sort those offsets by normalized DN.
+    pub fn build(parser: &mut dyn EntryParser) -> Result<Self> {
+        let mut pairs: Vec<(Vec<u8>, u64)> = Vec::new();
+        let mut pos = 0u64;
+        loop {
+            match parser.peek_dn(Some(pos))? {
+                None => break,
+                Some((dn, entry_pos)) => {
+                    pairs.push((normalize_dn(&dn), entry_pos));
+                    match parser.skip_entry(Some(entry_pos))? {
+                        Some(_) => pos = parser.parser_tell()?,
+                        None => break,
+                    }
+                }
+            }
+        }
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(DnIndex {
+            offsets: pairs.into_iter().map(|(_, offset)| offset).collect(),
+        })
+    }
+
+    /// Number of entries indexed.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Binary-search the index for `dn`, seeking `parser` into the clean
+    /// file at each candidate offset and reading only its `dn:` line to
+    /// decide which way to bisect. Returns the entry's offset on a match, or
+    /// `None` if no entry in the index has that DN.
+    pub fn find(&self, parser: &mut dyn EntryParser, dn: &str) -> Result<Option<u64>> {
+        let target = normalize_dn(dn);
+        let mut lo = 0usize;
+        let mut hi = self.offsets.len();
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let offset = self.offsets[mid];
+            let (candidate_dn, _) = match parser.peek_dn(Some(offset))? {
+                Some(v) => v,
+                None => return Ok(None),
+            };
+            match normalize_dn(&candidate_dn).cmp(&target) {
+                std::cmp::Ordering::Equal => return Ok(Some(offset)),
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Normalize a DN for index ordering and lookup, via the same
+/// `distinguishedNameMatch` rule [`DistinguishedNameMatch`] applies to
+/// attribute values, so two DNs that the directory would treat as identical
+/// land at the same position in the index.
+fn normalize_dn(dn: &str) -> Vec<u8> {
+    crate::schema::matching_rule_normalize("distinguishedNameMatch", dn.as_bytes())
+}
+
+/// Verify that editing didn't silently drop or duplicate an entry's numeric
+/// key line -- the LDIF format infers `"add"` for any entry whose second
+/// line isn't `ldapvi-key:`/`changetype:` (see
+/// [`crate::parseldif::LdifParser::read_header`]), so stripping just that one
+/// line out of an edited entry turns a modification into an accidental
+/// delete-and-add instead of a parse error.
+///
+/// Walks `data_parser` once, tallying how many times each numeric key
+/// appears, and returns [`DiffError::StructuralMismatch`] if:
+/// - a key names an entry that was never searched (out of range),
+/// - a key appears more than once,
+/// - an `"add"`-labeled entry's DN matches a clean entry whose key never
+///   appears anywhere in the data file -- the sign of a dropped key line,
+///   since a genuine new entry wouldn't already exist under another key.
+///
+/// Run this before [`compare_streams`] so a caller gets one clear diagnosis
+/// up front, instead of the merge walk failing with a generic parse error
+/// once it gets far enough to notice the symptom.
+pub fn check_key_structure(
+    clean_parser: &mut dyn EntryParser,
+    data_parser: &mut dyn EntryParser,
+    offsets: &[i64],
+) -> DiffResult<()> {
+    let parse_err = || DiffError::ParseError { n: -1, dn: String::new() };
+
+    let mut dn_to_key: std::collections::HashMap<Vec<u8>, usize> = std::collections::HashMap::new();
+    for (n, &pos) in offsets.iter().enumerate() {
+        if pos < 0 {
+            continue;
+        }
+        if let Some((dn, _)) = clean_parser.peek_dn(Some(pos as u64)).map_err(|_| parse_err())? {
+            dn_to_key.insert(normalize_dn(&dn), n);
+        }
+    }
+
+    let mut seen = vec![0u32; offsets.len()];
+    let mut add_dns: Vec<String> = Vec::new();
+    let mut pos = 0u64;
+    loop {
+        let (key, entry_pos) = match data_parser.peek_entry(Some(pos)).map_err(|_| parse_err())? {
+            Some(kd) => kd,
+            None => break,
+        };
+        match key.parse::<usize>() {
+            Ok(n) if n < seen.len() => seen[n] += 1,
+            Ok(n) => {
+                return Err(DiffError::StructuralMismatch {
+                    message: format!("key {} does not refer to a searched entry", n),
+                });
+            }
+            Err(_) if key == "add" => {
+                if let Some((dn, _)) = data_parser.peek_dn(Some(entry_pos)).map_err(|_| parse_err())? {
+                    add_dns.push(dn);
+                }
+            }
+            Err(_) => {}
+        }
+        pos = match data_parser.skip_entry(Some(entry_pos)).map_err(|_| parse_err())? {
+            Some(_) => data_parser.parser_tell().map_err(|_| parse_err())?,
+            None => break,
+        };
+    }
+
+    if let Some(n) = seen.iter().position(|&count| count > 1) {
+        return Err(DiffError::StructuralMismatch {
+            message: format!("key {} appears more than once -- entries have been added or removed during editing", n),
+        });
+    }
+
+    for dn in &add_dns {
+        if let Some(&n) = dn_to_key.get(&normalize_dn(dn)) {
+            if seen[n] == 0 {
+                return Err(DiffError::StructuralMismatch {
+                    message: format!(
+                        "{}: already exists under key {} -- its `ldapvi-key` line was likely dropped while editing",
+                        dn, n
+                    ),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ===========================================================================
+// Comparator -- matching-rule-aware attribute/value comparison
+// ===========================================================================
+
+/// An `EQUALITY` matching rule: normalizes a value into the canonical form
+/// the directory would compare it in, so two values are equal under the
+/// rule iff their normalized forms are byte-equal. Implementations defer to
+/// [`crate::schema::matching_rule_normalize`] so the diff engine and the
+/// schema module never disagree about what a rule means.
+pub trait MatchingRule {
+    fn normalize(&self, value: &[u8]) -> Vec<u8>;
+}
+
+/// Folds runs of whitespace and Unicode case -- the rule directories
+/// typically declare for attributes like `cn` and `description`.
+pub struct CaseIgnoreMatch;
+
+impl MatchingRule for CaseIgnoreMatch {
+    fn normalize(&self, value: &[u8]) -> Vec<u8> {
+        crate::schema::matching_rule_normalize("caseIgnoreMatch", value)
+    }
+}
+
+/// Folds whitespace runs but preserves case; the fallback rule for
+/// attributes with no narrower `EQUALITY` rule.
+pub struct CaseExactMatch;
+
+impl MatchingRule for CaseExactMatch {
+    fn normalize(&self, value: &[u8]) -> Vec<u8> {
+        crate::schema::matching_rule_normalize("caseExactMatch", value)
+    }
+}
+
+/// Compares values as DNs, so `cn=Foo, dc=Example` matches `cn=foo,dc=example`.
+pub struct DistinguishedNameMatch;
+
+impl MatchingRule for DistinguishedNameMatch {
+    fn normalize(&self, value: &[u8]) -> Vec<u8> {
+        crate::schema::matching_rule_normalize("distinguishedNameMatch", value)
+    }
+}
+
+/// Exact byte comparison, no normalization -- for binary attributes like
+/// `userPassword` or `jpegPhoto`.
+pub struct OctetStringMatch;
+
+impl MatchingRule for OctetStringMatch {
+    fn normalize(&self, value: &[u8]) -> Vec<u8> {
+        crate::schema::matching_rule_normalize("octetStringMatch", value)
+    }
+}
+
+/// Resolves attribute descriptions to the [`MatchingRule`] their values
+/// should be compared under, and threads that through `compare_entries`,
+/// `frob_ava`, `frob_rdn`, and `validate_rename`. Attribute descriptions
+/// themselves are always compared case-insensitively (RFC 4512: `CN` and
+/// `cn` name the same attribute), independent of the per-attribute mapping.
+///
+/// This mirrors the comparator-parameterized merge in
+/// [`diff_attribute_values`]: the caller supplies how two values compare,
+/// rather than the diff engine assuming byte equality.
+pub struct Comparator {
+    rules: std::collections::HashMap<String, Box<dyn MatchingRule>>,
+    default: Box<dyn MatchingRule>,
+    uuid_index: Option<std::collections::HashMap<Vec<u8>, usize>>,
+    generate_entryuuid: bool,
+}
+
+impl Comparator {
+    /// A comparator with no per-attribute overrides: every attribute falls
+    /// back to `caseExactMatch`, the same exact-byte-modulo-whitespace
+    /// comparison this crate used before matching rules existed.
+    pub fn new() -> Self {
+        Comparator {
+            rules: std::collections::HashMap::new(),
+            default: Box::new(CaseExactMatch),
+            uuid_index: None,
+            generate_entryuuid: false,
+        }
+    }
+
+    /// Map `ad` (matched case-insensitively) to `rule` for value comparison.
+    pub fn with_rule(mut self, ad: &str, rule: Box<dyn MatchingRule>) -> Self {
+        self.rules.insert(ad.to_lowercase(), rule);
+        self
+    }
+
+    /// Enable `entryUUID`-based identity tracking (`--track-uuid`), supplying
+    /// the map from each clean entry's `entryUUID` value to its key in
+    /// `offsets`, built by [`build_uuid_index`].
+    ///
+    /// With this set, [`process_next_entry`] re-resolves a data entry's
+    /// clean counterpart by `entryUUID` whenever it carries one, instead of
+    /// trusting the numeric key it was read under -- so two entries edited
+    /// (or swapped) under each other's keys still match their true server
+    /// counterpart -- and [`compare_entries`] excludes `entryUUID` itself
+    /// from the attribute diff, since it never legitimately changes.
+    pub fn with_uuid_index(mut self, index: std::collections::HashMap<Vec<u8>, usize>) -> Self {
+        self.uuid_index = Some(index);
+        self
+    }
+
+    /// Whether `entryUUID`-based identity tracking is enabled.
+    fn track_uuid(&self) -> bool {
+        self.uuid_index.is_some()
+    }
+
+    /// Enable `--generate-entryuuid`: every new entry added through
+    /// [`entry_to_add_mods`] that doesn't already carry an `entryUUID`
+    /// attribute gets a freshly generated one. The caller decides whether
+    /// to set this -- typically only when the flag is on *and* the server's
+    /// root DSE doesn't advertise its own entryUUID plugin (see
+    /// [`crate::ldap::server_has_entryuuid_feature`]) -- since the diff
+    /// engine itself has no access to the CLI flags or a live connection.
+    pub fn with_entryuuid_generation(mut self, enabled: bool) -> Self {
+        self.generate_entryuuid = enabled;
+        self
+    }
+
+    /// Whether [`entry_to_add_mods`] should synthesize a missing
+    /// `entryUUID`.
+    fn should_generate_entryuuid(&self) -> bool {
+        self.generate_entryuuid
+    }
+
+    /// The key an entry carrying `uuid` was originally read under, if known.
+    fn resolve_uuid(&self, uuid: &[u8]) -> Option<usize> {
+        self.uuid_index.as_ref().and_then(|index| index.get(uuid).copied())
+    }
+
+    fn rule_for(&self, ad: &str) -> &dyn MatchingRule {
+        self.rules
+            .get(&ad.to_lowercase())
+            .map(|r| r.as_ref())
+            .unwrap_or(self.default.as_ref())
+    }
+
+    /// Normalize `value` under the rule mapped to `ad`.
+    pub fn normalize(&self, ad: &str, value: &[u8]) -> Vec<u8> {
+        self.rule_for(ad).normalize(value)
+    }
+
+    /// Compare two values of attribute `ad` under its mapped matching rule.
+    pub fn values_equal(&self, ad: &str, a: &[u8], b: &[u8]) -> bool {
+        self.normalize(ad, a) == self.normalize(ad, b)
+    }
+
+    /// Attribute descriptions are always case-insensitive (RFC 4512).
+    pub fn ad_eq(&self, a: &str, b: &str) -> bool {
+        a.eq_ignore_ascii_case(b)
+    }
+
+    /// Order attribute descriptions case-insensitively, so sorting is
+    /// consistent with `ad_eq`.
+    pub fn ad_cmp(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        a.to_lowercase().cmp(&b.to_lowercase())
+    }
+}
+
+impl Default for Comparator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // ===========================================================================
 // FrobMode -- for frob_ava / frob_rdn
 // ===========================================================================
@@ -241,61 +806,107 @@ fn push_ava(s: &str, avas: &mut Vec<(String, Vec<u8>)>) {
 // frob_ava / frob_rdn / validate_rename
 // ---------------------------------------------------------------------------
 
-/// Manipulate an entry's attribute AD with value DATA according to `mode`:
+/// Find an attribute by descriptor name, matching `cmp`'s case-insensitive
+/// notion of attribute-description equality rather than exact bytes.
+fn find_attribute_ci<'a>(
+    entry: &'a Entry,
+    cmp: &Comparator,
+    ad: &str,
+) -> Option<&'a crate::data::Attribute> {
+    entry
+        .attributes
+        .iter()
+        .find(|a| cmp.ad_eq(a.ad.as_str_lossy().as_ref(), ad))
+}
+
+/// Find a value within `attr` under `ad`'s mapped matching rule.
+fn find_value_ci(attr: &crate::data::Attribute, cmp: &Comparator, ad: &str, data: &[u8]) -> bool {
+    attr.values.iter().any(|v| cmp.values_equal(ad, v, data))
+}
+
+/// Manipulate an entry's attribute AD with value DATA according to `mode`,
+/// comparing attribute descriptions and values via `cmp`:
 ///
-///  - `Check`:     return 0 if the value IS present, -1 if not.
-///  - `CheckNone`: return 0 if the value is NOT present, -1 if it is.
-///  - `Remove`:    remove the value (always returns 0).
-///  - `Add`:       add the value unless already present (always returns 0).
-pub fn frob_ava(entry: &mut Entry, mode: FrobMode, ad: &str, data: &[u8]) -> i32 {
+///  - `Check`:     `Ok(())` if the value IS present, `Err(AvaCheckFailed)` if not.
+///  - `CheckNone`: `Ok(())` if the value is NOT present, `Err(AvaCheckFailed)` if it is.
+///  - `Remove`:    remove the value (always `Ok(())`).
+///  - `Add`:       add the value unless already present (always `Ok(())`).
+///
+/// The returned error's `n` is always `-1`; callers that know the entry's
+/// key should apply it via [`DiffError::with_n`].
+pub fn frob_ava(
+    entry: &mut Entry,
+    cmp: &Comparator,
+    mode: FrobMode,
+    ad: &str,
+    data: &[u8],
+) -> DiffResult<()> {
+    let check_failed = || DiffError::AvaCheckFailed {
+        n: -1,
+        dn: entry.dn.clone(),
+        attr: ad.to_string(),
+        expected: data.to_vec(),
+    };
     match mode {
-        FrobMode::Check => match entry.get_attribute(ad) {
-            None => -1,
+        FrobMode::Check => match find_attribute_ci(entry, cmp, ad) {
+            None => Err(check_failed()),
             Some(a) => {
-                if a.find_value(data).is_some() {
-                    0
+                if find_value_ci(a, cmp, ad, data) {
+                    Ok(())
                 } else {
-                    -1
+                    Err(check_failed())
                 }
             }
         },
-        FrobMode::CheckNone => match entry.get_attribute(ad) {
-            None => 0,
+        FrobMode::CheckNone => match find_attribute_ci(entry, cmp, ad) {
+            None => Ok(()),
             Some(a) => {
-                if a.find_value(data).is_some() {
-                    -1
+                if find_value_ci(a, cmp, ad, data) {
+                    Err(check_failed())
                 } else {
-                    0
+                    Ok(())
                 }
             }
         },
         FrobMode::Remove => {
-            if let Some(a) = entry.find_attribute(ad, false) {
-                a.remove_value(data);
+            if let Some(pos) = entry
+                .attributes
+                .iter()
+                .position(|a| cmp.ad_eq(a.ad.as_str_lossy().as_ref(), ad))
+            {
+                let a = &mut entry.attributes[pos];
+                if let Some(i) = a.values.iter().position(|v| cmp.values_equal(ad, v, data)) {
+                    a.values.swap_remove(i);
+                }
             }
-            0
+            Ok(())
         }
         FrobMode::Add => {
-            let a = entry.find_attribute(ad, true).unwrap();
-            if a.find_value(data).is_none() {
+            let pos = entry
+                .attributes
+                .iter()
+                .position(|a| cmp.ad_eq(a.ad.as_str_lossy().as_ref(), ad));
+            let pos = pos.unwrap_or_else(|| {
+                entry.attributes.push(crate::data::Attribute::new(ad));
+                entry.attributes.len() - 1
+            });
+            let a = &mut entry.attributes[pos];
+            if !a.values.iter().any(|v| cmp.values_equal(ad, v, data)) {
                 a.append_value(data);
             }
-            0
+            Ok(())
         }
     }
 }
 
-/// Call frob_ava for every AVA in DN's first RDN.
-/// Returns -1 if frob_ava ever does so, 0 otherwise.
-pub fn frob_rdn(entry: &mut Entry, dn: &str, mode: FrobMode) -> i32 {
+/// Call frob_ava for every AVA in DN's first RDN, stopping at the first error.
+pub fn frob_rdn(entry: &mut Entry, cmp: &Comparator, dn: &str, mode: FrobMode) -> DiffResult<()> {
     let rdn = first_rdn(dn);
     let avas = parse_rdn_avas(rdn);
     for (ad, value) in &avas {
-        if frob_ava(entry, mode, ad, value) == -1 {
-            return -1;
-        }
+        frob_ava(entry, cmp, mode, ad, value)?;
     }
-    0
+    Ok(())
 }
 
 /// Validate a rename by checking all of the following conditions:
@@ -305,89 +916,219 @@ pub fn frob_rdn(entry: &mut Entry, dn: &str, mode: FrobMode) -> i32 {
 ///   - The attribute values in clean's RDN are either ALL contained in
 ///     data or NONE of them are (determines `deleteoldrdn`).
 ///
-/// On success, sets `deleteoldrdn` and returns 0.
-/// On failure returns -1.
-pub fn validate_rename(clean: &mut Entry, data: &mut Entry, deleteoldrdn: &mut bool) -> i32 {
+/// On success, sets `deleteoldrdn` and returns `Ok(())`.
+pub fn validate_rename(
+    clean: &mut Entry,
+    data: &mut Entry,
+    cmp: &Comparator,
+    deleteoldrdn: &mut bool,
+) -> DiffResult<()> {
     if clean.dn.is_empty() {
-        return -1;
+        return Err(DiffError::EmptyDn {
+            n: -1,
+            dn: clean.dn.clone(),
+        });
     }
     if data.dn.is_empty() {
-        return -1;
+        return Err(DiffError::EmptyDn {
+            n: -1,
+            dn: data.dn.clone(),
+        });
     }
     let clean_dn = clean.dn.clone();
     let data_dn = data.dn.clone();
-    if frob_rdn(clean, &clean_dn, FrobMode::Check) == -1 {
-        return -1;
-    }
-    if frob_rdn(data, &data_dn, FrobMode::Check) == -1 {
-        return -1;
-    }
+    frob_rdn(clean, cmp, &clean_dn, FrobMode::Check)
+        .map_err(|_| DiffError::MissingOldRdn { n: -1, dn: clean_dn.clone() })?;
+    frob_rdn(data, cmp, &data_dn, FrobMode::Check)?;
     // Check if old RDN values are still in data
-    if frob_rdn(data, &clean_dn, FrobMode::Check) != -1 {
+    if frob_rdn(data, cmp, &clean_dn, FrobMode::Check).is_ok() {
         *deleteoldrdn = false;
-        return 0;
+        return Ok(());
     }
-    if frob_rdn(data, &clean_dn, FrobMode::CheckNone) != -1 {
+    if frob_rdn(data, cmp, &clean_dn, FrobMode::CheckNone).is_ok() {
         *deleteoldrdn = true;
-        return 0;
+        return Ok(());
     }
-    -1
+    Err(DiffError::RdnMismatch { n: -1, dn: data_dn })
 }
 
 /// Modify a clean entry to reflect a rename.
-fn rename_entry(entry: &mut Entry, new_dn: &str, deleteoldrdn: bool) {
+pub(crate) fn rename_entry(entry: &mut Entry, cmp: &Comparator, new_dn: &str, deleteoldrdn: bool) {
     let old_dn = entry.dn.clone();
     if deleteoldrdn {
-        frob_rdn(entry, &old_dn, FrobMode::Remove);
+        let _ = frob_rdn(entry, cmp, &old_dn, FrobMode::Remove);
     }
-    frob_rdn(entry, new_dn, FrobMode::Add);
+    let _ = frob_rdn(entry, cmp, new_dn, FrobMode::Add);
     entry.dn = new_dn.to_string();
 }
 
+/// The operational attribute `--track-uuid` keys identity on.
+pub const ENTRYUUID_AD: &str = "entryUUID";
+
+/// `entry`'s `entryUUID` value, if it carries one.
+fn entry_uuid(entry: &Entry) -> Option<&[u8]> {
+    entry
+        .attributes
+        .iter()
+        .find(|a| a.ad.as_str_lossy().eq_ignore_ascii_case(ENTRYUUID_AD))
+        .and_then(|a| a.values.first())
+        .map(|v| v.as_slice())
+}
+
+/// Build the `entryUUID -> key` map [`Comparator::with_uuid_index`] needs,
+/// by reading every not-yet-seen entry in `clean_parser` at its position in
+/// `offsets`. Entries without an `entryUUID` (e.g. a server that doesn't
+/// populate it) are simply absent from the map, so lookups against them
+/// fall back to the ordinary positional/DN match.
+pub fn build_uuid_index(
+    clean_parser: &mut dyn EntryParser,
+    offsets: &[i64],
+) -> std::collections::HashMap<Vec<u8>, usize> {
+    let mut index = std::collections::HashMap::new();
+    for (n, &pos) in offsets.iter().enumerate() {
+        if pos < 0 {
+            continue;
+        }
+        if let Ok(Some((_, entry, _))) = clean_parser.read_entry(Some(pos as u64)) {
+            if let Some(uuid) = entry_uuid(&entry) {
+                index.insert(uuid.to_vec(), n);
+            }
+        }
+    }
+    index
+}
+
+/// Controls how `compare_entries` reports a changed attribute that is
+/// present, with different values, on both sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffMode {
+    /// Always emit a single `Replace` carrying the whole new value list.
+    /// This is the traditional ldapvi behavior.
+    Replace,
+    /// Emit value-level `Add`/`Delete` mods when only a minority of values
+    /// changed, which keeps the generated modify request small. Falls back
+    /// to `Replace` per-attribute under the conditions documented on
+    /// `diff_attribute_values`.
+    Granular,
+}
+
+/// Controls how [`compare_streams`] responds when a [`SyncDiffHandler`]
+/// rejects an operation: whether to retry a transient failure (per
+/// [`SyncDiffHandler::is_retryable`]) before giving up on it, and whether to
+/// keep diffing the rest of the stream instead of aborting on the first
+/// entry that still fails once retries are exhausted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitPolicy {
+    /// Extra attempts after the first, for a [`DiffError`] the handler
+    /// classifies as retryable. `0` disables retrying.
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles after each subsequent one.
+    pub retry_backoff: Duration,
+    /// When true, an entry that still fails after retries are exhausted is
+    /// recorded in the failure list [`compare_streams`] returns instead of
+    /// aborting the walk, so entries later in the stream still get a
+    /// chance to commit.
+    pub continue_on_error: bool,
+}
+
+impl CommitPolicy {
+    /// Today's long-standing behavior: no retries, abort on the first
+    /// failure.
+    pub fn strict() -> Self {
+        CommitPolicy {
+            max_retries: 0,
+            retry_backoff: Duration::from_millis(0),
+            continue_on_error: false,
+        }
+    }
+}
+
+impl Default for CommitPolicy {
+    fn default() -> Self {
+        Self::strict()
+    }
+}
+
 // ===========================================================================
 // Entry comparison
 // ===========================================================================
 
+/// Compare `a` and `b` under their attribute's mapped matching rule,
+/// position by position -- two attributes whose values differ only by a
+/// normalization-insignificant difference (e.g. case, under
+/// `caseIgnoreMatch`) compare equal.
+fn values_equal(cmp: &Comparator, ad: &str, a: &[Vec<u8>], b: &[Vec<u8>]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(x, y)| cmp.values_equal(ad, x, y))
+}
+
 /// Compare two entries and return the modifications needed to transform
-/// `clean` into `data`. Returns empty vec if entries are identical.
-fn compare_entries(clean: &Entry, data: &Entry) -> Vec<LdapMod> {
+/// `clean` into `data`. Returns empty vec if entries are identical under
+/// `cmp`. Reports every attribute-level delta to `observer` (keyed by `n`
+/// and `data.dn`), then a final [`DiffOp::Modify`] if anything changed.
+pub(crate) fn compare_entries(
+    clean: &Entry,
+    data: &Entry,
+    mode: DiffMode,
+    cmp: &Comparator,
+    n: i32,
+    observer: &mut dyn DiffObserver,
+) -> Vec<LdapMod> {
     let mut clean_attrs: Vec<&crate::data::Attribute> = clean.attributes.iter().collect();
     let mut data_attrs: Vec<&crate::data::Attribute> = data.attributes.iter().collect();
-    clean_attrs.sort_by(|a, b| a.ad.cmp(&b.ad));
-    data_attrs.sort_by(|a, b| a.ad.cmp(&b.ad));
+    if cmp.track_uuid() {
+        // entryUUID is assigned once by the server and never legitimately
+        // changes, so it would otherwise show up as a spurious modify.
+        clean_attrs.retain(|a| !a.ad.as_str_lossy().eq_ignore_ascii_case(ENTRYUUID_AD));
+        data_attrs.retain(|a| !a.ad.as_str_lossy().eq_ignore_ascii_case(ENTRYUUID_AD));
+    }
+    clean_attrs.sort_by(|a, b| cmp.ad_cmp(a.ad.as_str_lossy().as_ref(), b.ad.as_str_lossy().as_ref()));
+    data_attrs.sort_by(|a, b| cmp.ad_cmp(a.ad.as_str_lossy().as_ref(), b.ad.as_str_lossy().as_ref()));
 
     let mut mods = Vec::new();
     let mut i = 0;
     let mut j = 0;
 
     while i < clean_attrs.len() && j < data_attrs.len() {
-        match clean_attrs[i].ad.cmp(&data_attrs[j].ad) {
+        match cmp.ad_cmp(
+            clean_attrs[i].ad.as_str_lossy().as_ref(),
+            data_attrs[j].ad.as_str_lossy().as_ref(),
+        ) {
             std::cmp::Ordering::Less => {
                 // In clean only → DELETE
+                let ad = clean_attrs[i].ad.as_str_lossy().into_owned();
+                observer.note_attribute(n, &data.dn, &ad, &[], &clean_attrs[i].values);
                 mods.push(LdapMod {
                     op: ModOp::Delete,
-                    attr: clean_attrs[i].ad.clone(),
+                    attr: ad,
                     values: clean_attrs[i].values.clone(),
                 });
                 i += 1;
             }
             std::cmp::Ordering::Equal => {
                 // In both → compare values
-                if clean_attrs[i].values != data_attrs[j].values {
-                    mods.push(LdapMod {
-                        op: ModOp::Replace,
-                        attr: data_attrs[j].ad.clone(),
-                        values: data_attrs[j].values.clone(),
-                    });
+                let ad = data_attrs[j].ad.as_str_lossy().into_owned();
+                if !values_equal(cmp, &ad, &clean_attrs[i].values, &data_attrs[j].values) {
+                    mods.extend(diff_attribute_values(
+                        ad,
+                        &clean_attrs[i].values,
+                        &data_attrs[j].values,
+                        mode,
+                        cmp,
+                        n,
+                        &data.dn,
+                        observer,
+                    ));
                 }
                 i += 1;
                 j += 1;
             }
             std::cmp::Ordering::Greater => {
                 // In data only → ADD
+                let ad = data_attrs[j].ad.as_str_lossy().into_owned();
+                observer.note_attribute(n, &data.dn, &ad, &data_attrs[j].values, &[]);
                 mods.push(LdapMod {
                     op: ModOp::Add,
-                    attr: data_attrs[j].ad.clone(),
+                    attr: ad,
                     values: data_attrs[j].values.clone(),
                 });
                 j += 1;
@@ -395,148 +1136,427 @@ fn compare_entries(clean: &Entry, data: &Entry) -> Vec<LdapMod> {
         }
     }
     while i < clean_attrs.len() {
+        let ad = clean_attrs[i].ad.as_str_lossy().into_owned();
+        observer.note_attribute(n, &data.dn, &ad, &[], &clean_attrs[i].values);
         mods.push(LdapMod {
             op: ModOp::Delete,
-            attr: clean_attrs[i].ad.clone(),
+            attr: ad,
             values: clean_attrs[i].values.clone(),
         });
         i += 1;
     }
     while j < data_attrs.len() {
+        let ad = data_attrs[j].ad.as_str_lossy().into_owned();
+        observer.note_attribute(n, &data.dn, &ad, &data_attrs[j].values, &[]);
         mods.push(LdapMod {
             op: ModOp::Add,
-            attr: data_attrs[j].ad.clone(),
+            attr: ad,
             values: data_attrs[j].values.clone(),
         });
         j += 1;
     }
+    if !mods.is_empty() {
+        observer.note_entry(n, &data.dn, DiffOp::Modify);
+    }
     mods
 }
 
-/// Convert entry attributes to LdapMods with Add op.
-fn entry_to_add_mods(entry: &Entry) -> Vec<LdapMod> {
-    entry
-        .attributes
-        .iter()
-        .map(|a| LdapMod {
+/// Diff the value lists of one attribute that is present, with different
+/// values, on both sides.
+///
+/// In [`DiffMode::Granular`], sorts both value lists and walks them with a
+/// sorted merge (the same approach as the old C `compare_ptr_arrays`),
+/// splitting out the values that only appear in `clean` (to be deleted) from
+/// the ones that only appear in `data` (to be added). Consecutive values in
+/// each of those groups are coalesced into a single `Delete` and a single
+/// `Add`, rather than one `LdapMod` per value.
+///
+/// Falls back to one whole-attribute `Replace` -- which is what
+/// [`DiffMode::Replace`] always does -- whenever the merge wouldn't pay off:
+/// the attribute is single-valued on either side, or the values found on
+/// only one side number more than half of the larger value list. `Replace`
+/// is never bigger in those cases, and it avoids the bookkeeping.
+///
+/// Invariants: the returned mods never carry an empty value list, and the
+/// values within each mod keep their original (unsorted) relative order.
+///
+/// Regardless of `mode`, reports the exact values added/removed to
+/// `observer` -- the physical mods below may coalesce those into a single
+/// `Replace`, but the logical delta is the same either way.
+#[allow(clippy::too_many_arguments)]
+fn diff_attribute_values(
+    attr: String,
+    clean_values: &[Vec<u8>],
+    data_values: &[Vec<u8>],
+    mode: DiffMode,
+    cmp: &Comparator,
+    n: i32,
+    dn: &str,
+    observer: &mut dyn DiffObserver,
+) -> Vec<LdapMod> {
+    let replace = || {
+        vec![LdapMod {
+            op: ModOp::Replace,
+            attr: attr.clone(),
+            values: data_values.to_vec(),
+        }]
+    };
+
+    let (only_clean, only_data) = partition_values(cmp, &attr, clean_values, data_values);
+    if !only_clean.is_empty() || !only_data.is_empty() {
+        let added: Vec<Vec<u8>> = only_data.iter().map(|v| (*v).clone()).collect();
+        let removed: Vec<Vec<u8>> = only_clean.iter().map(|v| (*v).clone()).collect();
+        observer.note_attribute(n, dn, &attr, &added, &removed);
+    }
+
+    if mode == DiffMode::Replace || clean_values.len() <= 1 || data_values.len() <= 1 {
+        return replace();
+    }
+
+    let larger = clean_values.len().max(data_values.len());
+    if only_clean.len() + only_data.len() > larger / 2 {
+        return replace();
+    }
+
+    let mut mods = Vec::new();
+    if !only_clean.is_empty() {
+        let deleted: Vec<Vec<u8>> = clean_values
+            .iter()
+            .filter(|v| only_clean.contains(v))
+            .cloned()
+            .collect();
+        mods.push(LdapMod {
+            op: ModOp::Delete,
+            attr: attr.clone(),
+            values: deleted,
+        });
+    }
+    if !only_data.is_empty() {
+        let added: Vec<Vec<u8>> = data_values
+            .iter()
+            .filter(|v| only_data.contains(v))
+            .cloned()
+            .collect();
+        mods.push(LdapMod {
             op: ModOp::Add,
-            attr: a.ad.clone(),
-            values: a.values.clone(),
-        })
-        .collect()
+            attr,
+            values: added,
+        });
+    }
+    mods
 }
 
-/// Convert entry attributes to LdapMods with Replace op.
-fn entry_to_replace_mods(entry: &Entry) -> Vec<LdapMod> {
-    entry
-        .attributes
+/// Partition `clean_values` and `data_values` into (values only in clean,
+/// values only in data) under `cmp`'s matching-rule normalization for `attr`,
+/// via a sorted merge (the same approach as the old C `compare_ptr_arrays`).
+/// Values that differ only by a normalization-insignificant difference
+/// (e.g. case, under `caseIgnoreMatch`) are treated as equal and excluded
+/// from both partitions.
+fn partition_values<'a>(
+    cmp: &Comparator,
+    attr: &str,
+    clean_values: &'a [Vec<u8>],
+    data_values: &'a [Vec<u8>],
+) -> (Vec<&'a Vec<u8>>, Vec<&'a Vec<u8>>) {
+    let mut sorted_clean: Vec<(Vec<u8>, &Vec<u8>)> = clean_values
         .iter()
-        .map(|a| LdapMod {
-            op: ModOp::Replace,
-            attr: a.ad.clone(),
-            values: a.values.clone(),
-        })
-        .collect()
-}
+        .map(|v| (cmp.normalize(attr, v), v))
+        .collect();
+    let mut sorted_data: Vec<(Vec<u8>, &Vec<u8>)> = data_values
+        .iter()
+        .map(|v| (cmp.normalize(attr, v), v))
+        .collect();
+    sorted_clean.sort_by(|a, b| a.0.cmp(&b.0));
+    sorted_data.sort_by(|a, b| a.0.cmp(&b.0));
 
-// ===========================================================================
-// Core diff functions
-// ===========================================================================
+    let mut only_clean = Vec::new();
+    let mut only_data = Vec::new();
+    let mut i = 0;
+    let mut j = 0;
+    while i < sorted_clean.len() && j < sorted_data.len() {
+        match sorted_clean[i].0.cmp(&sorted_data[j].0) {
+            std::cmp::Ordering::Less => {
+                only_clean.push(sorted_clean[i].1);
+                i += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                i += 1;
+                j += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                only_data.push(sorted_data[j].1);
+                j += 1;
+            }
+        }
+    }
+    only_clean.extend(sorted_clean[i..].iter().map(|(_, v)| *v));
+    only_data.extend(sorted_data[j..].iter().map(|(_, v)| *v));
+    (only_clean, only_data)
+}
+
+/// Diff two entry snapshots of the *same* DN into the minimal `ModifyRecord`
+/// needed to turn `old` into `new`, or `None` if nothing differs.
+///
+/// Unlike [`compare_entries`], this doesn't need a [`Comparator`] or
+/// [`DiffMode`] -- it's the plain byte-exact entry point for callers (e.g. an
+/// editor round-trip) that just want "what changed", not matching-rule-aware
+/// normalization or a live stream of per-attribute diff events. Attributes
+/// are compared in a stable sorted order so the output is deterministic.
+///
+/// Per attribute, the value lists are treated as unordered sets: values only
+/// in `new` become an `Add`, values only in `old` become a `Delete`, and
+/// attributes present on just one side become a whole-attribute `Add` or
+/// `Delete`. When an attribute has values on both sides of the diff, the
+/// incremental add+delete pair is compared against a single `Replace`
+/// carrying `new`'s full value list, and whichever yields fewer `LdapMod`
+/// entries wins.
+pub fn diff_entries(old: &Entry, new: &Entry) -> Option<ModifyRecord> {
+    let mut old_attrs: Vec<&crate::data::Attribute> = old.attributes.iter().collect();
+    let mut new_attrs: Vec<&crate::data::Attribute> = new.attributes.iter().collect();
+    old_attrs.sort_by(|a, b| a.ad.as_str_lossy().cmp(&b.ad.as_str_lossy()));
+    new_attrs.sort_by(|a, b| a.ad.as_str_lossy().cmp(&b.ad.as_str_lossy()));
+
+    let mut mods = Vec::new();
+    let mut i = 0;
+    let mut j = 0;
+    while i < old_attrs.len() && j < new_attrs.len() {
+        match old_attrs[i].ad.as_str_lossy().cmp(&new_attrs[j].ad.as_str_lossy()) {
+            std::cmp::Ordering::Less => {
+                mods.push(LdapMod {
+                    op: ModOp::Delete,
+                    attr: old_attrs[i].ad.as_str_lossy().into_owned(),
+                    values: old_attrs[i].values.clone(),
+                });
+                i += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                mods.extend(diff_attribute_minimal(old_attrs[i], new_attrs[j]));
+                i += 1;
+                j += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                mods.push(LdapMod {
+                    op: ModOp::Add,
+                    attr: new_attrs[j].ad.as_str_lossy().into_owned(),
+                    values: new_attrs[j].values.clone(),
+                });
+                j += 1;
+            }
+        }
+    }
+    while i < old_attrs.len() {
+        mods.push(LdapMod {
+            op: ModOp::Delete,
+            attr: old_attrs[i].ad.as_str_lossy().into_owned(),
+            values: old_attrs[i].values.clone(),
+        });
+        i += 1;
+    }
+    while j < new_attrs.len() {
+        mods.push(LdapMod {
+            op: ModOp::Add,
+            attr: new_attrs[j].ad.as_str_lossy().into_owned(),
+            values: new_attrs[j].values.clone(),
+        });
+        j += 1;
+    }
+
+    if mods.is_empty() {
+        None
+    } else {
+        Some(ModifyRecord { dn: new.dn.clone(), mods })
+    }
+}
+
+/// Byte-exact set diff of one attribute present (with possibly different
+/// values) on both sides, for [`diff_entries`]. Returns an empty vec when
+/// the value sets are identical.
+fn diff_attribute_minimal(
+    old: &crate::data::Attribute,
+    new: &crate::data::Attribute,
+) -> Vec<LdapMod> {
+    let attr = new.ad.as_str_lossy().into_owned();
+    let to_delete: Vec<Vec<u8>> = old
+        .values
+        .iter()
+        .filter(|v| !new.values.contains(v))
+        .cloned()
+        .collect();
+    let to_add: Vec<Vec<u8>> = new
+        .values
+        .iter()
+        .filter(|v| !old.values.contains(v))
+        .cloned()
+        .collect();
+
+    if to_delete.is_empty() && to_add.is_empty() {
+        return Vec::new();
+    }
+
+    // A Replace is one LdapMod; the incremental form is one per nonempty
+    // side, so it only wins (or ties) when at most one side is nonempty.
+    if !to_delete.is_empty() && !to_add.is_empty() {
+        return vec![LdapMod {
+            op: ModOp::Replace,
+            attr,
+            values: new.values.clone(),
+        }];
+    }
+
+    let mut mods = Vec::new();
+    if !to_delete.is_empty() {
+        mods.push(LdapMod {
+            op: ModOp::Delete,
+            attr: attr.clone(),
+            values: to_delete,
+        });
+    }
+    if !to_add.is_empty() {
+        mods.push(LdapMod {
+            op: ModOp::Add,
+            attr,
+            values: to_add,
+        });
+    }
+    mods
+}
+
+/// Convert entry attributes to LdapMods with Add op.
+///
+/// When `cmp` has `--generate-entryuuid` enabled (see
+/// [`Comparator::with_entryuuid_generation`]) and `entry` doesn't already
+/// carry an `entryUUID` attribute, appends a freshly generated one so the
+/// new entry gets a stable external identifier at creation time. A
+/// `getrandom` failure is reported to stderr and otherwise ignored -- the
+/// add still proceeds, just without a synthesized `entryUUID`, rather than
+/// aborting the whole commit over an unrelated RNG hiccup.
+pub(crate) fn entry_to_add_mods(entry: &Entry, cmp: &Comparator) -> Vec<LdapMod> {
+    let mut mods: Vec<LdapMod> = entry
+        .attributes
+        .iter()
+        .map(|a| LdapMod {
+            op: ModOp::Add,
+            attr: a.ad.as_str_lossy().into_owned(),
+            values: a.values.clone(),
+        })
+        .collect();
+
+    if cmp.should_generate_entryuuid() && !entry.attributes.iter().any(|a| a.ad.eq_ignore_ascii_case("entryUUID")) {
+        match crate::entryuuid::generate() {
+            Ok(uuid) => mods.push(LdapMod {
+                op: ModOp::Add,
+                attr: "entryUUID".to_string(),
+                values: vec![uuid.into_bytes()],
+            }),
+            Err(e) => eprintln!("Warning: could not generate entryUUID for '{}': {}", entry.dn, e),
+        }
+    }
+
+    mods
+}
+
+/// Convert entry attributes to LdapMods with Replace op.
+fn entry_to_replace_mods(entry: &Entry) -> Vec<LdapMod> {
+    entry
+        .attributes
+        .iter()
+        .map(|a| LdapMod {
+            op: ModOp::Replace,
+            attr: a.ad.as_str_lossy().into_owned(),
+            values: a.values.clone(),
+        })
+        .collect()
+}
+
+// ===========================================================================
+// Core diff functions
+// ===========================================================================
 
 /// Handle a changerecord of type `key` from `data_parser` at `datapos`.
-/// Returns 0 on success, -1 on syntax error, -2 on handler error.
 pub fn process_immediate(
     data_parser: &mut dyn EntryParser,
-    handler: &mut dyn DiffHandler,
+    handler: &mut dyn SyncDiffHandler,
     datapos: u64,
     key: &str,
-) -> i32 {
+    cmp: &Comparator,
+) -> DiffResult<()> {
     match key {
         "add" => {
             let entry = match data_parser.read_entry(Some(datapos)) {
                 Ok(Some((_, e, _))) => e,
-                _ => return -1,
+                _ => return Err(DiffError::ParseError { n: -1, dn: String::new() }),
             };
-            let mods = entry_to_add_mods(&entry);
-            if handler.handle_add(-1, &entry.dn, &mods) == -1 {
-                return -2;
-            }
+            let mods = entry_to_add_mods(&entry, cmp);
+            handler.handle_add(-1, &entry.dn, &mods)?;
         }
         "replace" => {
             let entry = match data_parser.read_entry(Some(datapos)) {
                 Ok(Some((_, e, _))) => e,
-                _ => return -1,
+                _ => return Err(DiffError::ParseError { n: -1, dn: String::new() }),
             };
             let mods = entry_to_replace_mods(&entry);
             let dn = entry.dn.clone();
-            if handler.handle_change(-1, &dn, &dn, &mods) == -1 {
-                return -2;
-            }
+            handler.handle_change(-1, &dn, &dn, &mods)?;
         }
         "rename" => {
             let rr = match data_parser.read_rename(Some(datapos)) {
                 Ok(rr) => rr,
-                Err(_) => return -1,
+                Err(_) => return Err(DiffError::ParseError { n: -1, dn: String::new() }),
             };
-            let rc = handler.handle_rename0(-1, &rr.old_dn, &rr.new_dn, rr.delete_old_rdn);
-            if rc != 0 {
-                return -2;
-            }
+            handler.handle_rename0(-1, &rr.old_dn, &rr.new_dn, rr.delete_old_rdn)?;
         }
         "delete" => {
             let dn = match data_parser.read_delete(Some(datapos)) {
                 Ok(dn) => dn,
-                Err(_) => return -1,
+                Err(_) => return Err(DiffError::ParseError { n: -1, dn: String::new() }),
             };
-            let rc = handler.handle_delete(-1, &dn);
-            if rc != 0 {
-                return -2;
-            }
+            handler.handle_delete(-1, &dn)?;
         }
         "modify" => {
             let mr = match data_parser.read_modify(Some(datapos)) {
                 Ok(mr) => mr,
-                Err(_) => return -1,
+                Err(_) => return Err(DiffError::ParseError { n: -1, dn: String::new() }),
             };
-            if handler.handle_change(-1, &mr.dn, &mr.dn, &mr.mods) == -1 {
-                return -2;
-            }
+            handler.handle_change(-1, &mr.dn, &mr.dn, &mr.mods)?;
         }
         _ => {
             eprintln!("Error: Invalid key: `{}'.", key);
-            return -1;
+            return Err(DiffError::ParseError { n: -1, dn: String::new() });
         }
     }
-    0
+    Ok(())
 }
 
 /// Process the next data entry: compare with clean copy or dispatch changerecord.
-/// Returns 0 on success, -1 on syntax error, -2 on handler error.
+#[allow(clippy::too_many_arguments)]
 fn process_next_entry(
     clean_parser: &mut dyn EntryParser,
     data_parser: &mut dyn EntryParser,
-    handler: &mut dyn DiffHandler,
+    handler: &mut dyn SyncDiffHandler,
     offsets: &mut [i64],
     key: &str,
     datapos: u64,
-) -> i32 {
+    mode: DiffMode,
+    cmp: &Comparator,
+    observer: &mut dyn DiffObserver,
+) -> DiffResult<()> {
     // Try to parse key as number
     let n: usize = match key.parse() {
         Ok(n) => n,
         Err(_) => {
-            return process_immediate(data_parser, handler, datapos, key);
+            return process_immediate(data_parser, handler, datapos, key, cmp);
         }
     };
 
     // Validate key range
     if n >= offsets.len() {
         eprintln!("Error: Invalid key: `{}'.", key);
-        return -1;
+        return Err(DiffError::ParseError { n: n as i32, dn: String::new() });
     }
     let pos = offsets[n];
     if pos < 0 {
         eprintln!("Error: Duplicate entry {}.", n);
-        return -1;
+        return Err(DiffError::ParseError { n: n as i32, dn: String::new() });
     }
 
     // Find precise position of clean entry
@@ -557,7 +1577,7 @@ fn process_next_entry(
                 let new_datapos = datapos + advance;
                 long_array_invert(offsets, n);
                 let _ = data_parser.parser_seek(new_datapos);
-                return 0;
+                return Ok(());
             }
         }
     }
@@ -565,9 +1585,25 @@ fn process_next_entry(
     // Read both entries
     let entry = match data_parser.read_entry(Some(datapos)) {
         Ok(Some((_, e, _))) => e,
-        Ok(None) => return -1,
-        Err(_) => return -1,
+        Ok(None) => return Err(DiffError::ParseError { n: n as i32, dn: String::new() }),
+        Err(_) => return Err(DiffError::ParseError { n: n as i32, dn: String::new() }),
     };
+
+    // Under --track-uuid, trust the entry's own entryUUID over the numeric
+    // key it happened to be read under: if it resolves to a different,
+    // still-unseen clean entry, that's the real counterpart -- this is what
+    // lets two entries get edited (or swapped) under each other's keys
+    // without being mismatched against the wrong original.
+    let mut n = n;
+    if let Some(uuid) = entry_uuid(&entry) {
+        if let Some(resolved) = cmp.resolve_uuid(uuid) {
+            if resolved < offsets.len() && offsets[resolved] >= 0 {
+                n = resolved;
+            }
+        }
+    }
+    let pos = offsets[n];
+
     let mut cleanentry = match clean_parser.read_entry(Some(pos as u64)) {
         Ok(Some((_, e, _))) => e,
         _ => panic!("Failed to re-read clean entry"),
@@ -577,34 +1613,69 @@ fn process_next_entry(
     let is_rename = cleanentry.dn != entry.dn;
     if is_rename {
         let mut deleteoldrdn = false;
-        if validate_rename(&mut cleanentry, &mut entry.clone(), &mut deleteoldrdn) != 0 {
-            return -1;
-        }
-        if handler.handle_rename(n as i32, &cleanentry.dn, &entry) == -1 {
-            return -2;
-        }
-        rename_entry(&mut cleanentry, &entry.dn, deleteoldrdn);
+        validate_rename(&mut cleanentry, &mut entry.clone(), cmp, &mut deleteoldrdn)
+            .map_err(|e| e.with_n(n as i32))?;
+        observer.note_entry(n as i32, &cleanentry.dn, DiffOp::Rename);
+        handler.handle_rename(n as i32, &cleanentry.dn, &entry)?;
+        rename_entry(&mut cleanentry, cmp, &entry.dn, deleteoldrdn);
     }
 
-    let mods = compare_entries(&cleanentry, &entry);
+    let mods = compare_entries(&cleanentry, &entry, mode, cmp, n as i32, observer);
     if !mods.is_empty() {
-        if handler.handle_change(n as i32, &cleanentry.dn, &entry.dn, &mods) == -1 {
-            return -2;
-        }
+        handler.handle_change(n as i32, &cleanentry.dn, &entry.dn, &mods)?;
     }
 
     // Mark as seen
     long_array_invert(offsets, n);
-    0
+    Ok(())
+}
+
+/// Retry `op` against `handler` up to `policy.max_retries` extra times,
+/// sleeping `policy.retry_backoff` (doubling each attempt) between tries,
+/// as long as `handler.is_retryable` agrees the failure is worth retrying.
+/// Gives up and returns the last error once retries run out, the error is
+/// fatal, or `policy` disables retrying altogether.
+fn retry_with_policy(
+    policy: &CommitPolicy,
+    handler: &mut dyn SyncDiffHandler,
+    mut op: impl FnMut(&mut dyn SyncDiffHandler) -> DiffResult<()>,
+) -> DiffResult<()> {
+    let mut attempt = 0;
+    let mut delay = policy.retry_backoff;
+    loop {
+        match op(&mut *handler) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                if attempt < policy.max_retries && handler.is_retryable(&e) {
+                    attempt += 1;
+                    if !delay.is_zero() {
+                        std::thread::sleep(delay);
+                        delay = delay.saturating_mul(2);
+                    }
+                    continue;
+                }
+                return Err(e);
+            }
+        }
+    }
 }
 
 /// Process deletions: handle entries in clean that are not in data.
-/// Returns 0 on success, -2 on handler error.
+///
+/// On success, returns the DNs and errors of deletions that failed but were
+/// tolerated because `policy.continue_on_error` was set; an empty vec means
+/// every deletion that was attempted succeeded. Entries still recorded as
+/// failed are marked "seen" in `offsets` just like a successful deletion, so
+/// a caller rerunning the diff against the same `offsets` doesn't retry them
+/// as fresh deletions.
 fn process_deletions(
     clean_parser: &mut dyn EntryParser,
-    handler: &mut dyn DiffHandler,
+    handler: &mut dyn SyncDiffHandler,
     offsets: &mut [i64],
-) -> i32 {
+    observer: &mut dyn DiffObserver,
+    policy: &CommitPolicy,
+) -> DiffResult<Vec<(String, DiffError)>> {
+    let mut failures = Vec::new();
     for n in 0..offsets.len() {
         let pos = offsets[n];
         if pos < 0 {
@@ -614,14 +1685,30 @@ fn process_deletions(
             Ok(Some((_, e, _))) => e,
             _ => panic!("Failed to read clean entry for deletion"),
         };
-        match handler.handle_delete(n as i32, &cleanentry.dn) {
-            -1 => return -2,
-            _ => {
+        for attr in &cleanentry.attributes {
+            observer.note_attribute(
+                n as i32,
+                &cleanentry.dn,
+                attr.ad.as_str_lossy().as_ref(),
+                &[],
+                &attr.values,
+            );
+        }
+        observer.note_entry(n as i32, &cleanentry.dn, DiffOp::Delete);
+
+        let dn = cleanentry.dn.clone();
+        match retry_with_policy(policy, handler, |h| h.handle_delete(n as i32, &dn)) {
+            Ok(()) => long_array_invert(offsets, n),
+            Err(e) => {
+                if !policy.continue_on_error {
+                    return Err(e);
+                }
+                failures.push((cleanentry.dn, e));
                 long_array_invert(offsets, n);
             }
         }
     }
-    0
+    Ok(failures)
 }
 
 /// The compare_streams loop is the heart of ldapvi.
@@ -653,56 +1740,140 @@ fn process_deletions(
 /// where the renamed entry accounts for attribute modifications due to
 /// the RDN change (new RDN values added, old ones removed).
 ///
-/// Returns 0 on success, -1 on parse error, -2 on handler error.
+/// On success, returns the DNs and errors of entries that failed but were
+/// tolerated because `policy.continue_on_error` was set -- an empty vec
+/// means every entry committed cleanly. On failure (only possible with
+/// `policy.continue_on_error` unset, or for a stream-level problem no
+/// per-entry retry can fix), returns the first [`DiffError`] encountered --
+/// either a stream parse failure, a rejected rename/AVA check, or a handler
+/// rejection.
+///
+/// `mode` controls how changed attributes are reported; see [`DiffMode`].
+/// `cmp` controls how attribute descriptions and values are compared; see
+/// [`Comparator`].
+/// `observer` is notified of every attribute- and entry-level delta found
+/// while comparing numbered entries and processing deletions, independent
+/// of `handler`; pass `&mut NullObserver` for a pure apply run. See
+/// [`DiffObserver`].
 ///
-/// After successful completion, offsets are restored to their original values.
-/// On handler error, offsets are left in their inverted state for error
-/// recovery (identifying which entries have already been processed).
+/// `handler.flush()` is called at the two natural boundaries in the walk --
+/// once after the add/rename/change pass and once after deletions -- so a
+/// handler that queues operations via [`AsyncDiffHandler`] (and implements
+/// [`SyncDiffHandler`]'s `handle_*` methods by enqueueing and returning
+/// immediately) gets to dispatch each batch before the next one starts. A
+/// plain [`SyncDiffHandler`] has nothing to flush and sees empty results.
+///
+/// `policy` controls retrying and whether a per-entry failure aborts the
+/// whole walk or is merely recorded; see [`CommitPolicy`]. Pass
+/// `&CommitPolicy::strict()` for the traditional all-or-nothing behavior.
+///
+/// After successful completion, offsets are restored to their original
+/// values -- including entries recorded as failures under
+/// `continue_on_error`, which are marked "seen" the same as a committed
+/// entry so a rerun doesn't revisit them. On an aborting
+/// [`DiffError::HandlerRejected`], offsets are left in their inverted state
+/// for error recovery (identifying which entries have already been
+/// processed); any other aborting error restores them before returning.
 pub fn compare_streams(
     clean_parser: &mut dyn EntryParser,
     data_parser: &mut dyn EntryParser,
-    handler: &mut dyn DiffHandler,
+    handler: &mut dyn SyncDiffHandler,
     offsets: &mut [i64],
-) -> i32 {
-    let mut rc = 0i32;
+    mode: DiffMode,
+    cmp: &Comparator,
+    observer: &mut dyn DiffObserver,
+    policy: &CommitPolicy,
+) -> DiffResult<Vec<(String, DiffError)>> {
+    let mut failures: Vec<(String, DiffError)> = Vec::new();
+
+    let result = (|| -> DiffResult<()> {
+        loop {
+            let peek = match data_parser.peek_entry(None) {
+                Ok(Some((key, datapos))) => Some((key, datapos)),
+                Ok(None) => None,
+                Err(_) => return Err(DiffError::ParseError { n: -1, dn: String::new() }),
+            };
 
-    loop {
-        let peek = match data_parser.peek_entry(None) {
-            Ok(Some((key, datapos))) => Some((key, datapos)),
-            Ok(None) => None,
-            Err(_) => {
-                rc = -1;
-                break;
+            let (key, datapos) = match peek {
+                Some(kd) => kd,
+                None => break,
+            };
+
+            let outcome = retry_with_policy(policy, handler, |h| {
+                process_next_entry(
+                    clean_parser,
+                    data_parser,
+                    h,
+                    offsets,
+                    &key,
+                    datapos,
+                    mode,
+                    cmp,
+                    observer,
+                )
+            });
+
+            if let Err(e) = outcome {
+                if !policy.continue_on_error {
+                    return Err(e);
+                }
+                // This entry never got marked "seen" by `process_next_entry`,
+                // so without inverting its offset here `process_deletions`
+                // would treat it as missing from `data` and delete it --
+                // wrong, since it's present, just uncommitted.
+                if let Ok(n) = key.parse::<usize>() {
+                    if n < offsets.len() && offsets[n] >= 0 {
+                        long_array_invert(offsets, n);
+                    }
+                }
+                failures.push((e.dn().to_string(), e));
             }
-        };
+        }
 
-        let (key, datapos) = match peek {
-            Some(kd) => kd,
-            None => break,
-        };
+        if let Some(r) = handler.flush().into_iter().find(|r| !r.success) {
+            let e = DiffError::HandlerRejected { n: r.n, dn: String::new(), code: -1 };
+            if !policy.continue_on_error {
+                return Err(e);
+            }
+            failures.push((String::new(), e));
+        }
 
-        rc = process_next_entry(clean_parser, data_parser, handler, offsets, &key, datapos);
-        if rc != 0 {
-            break;
+        match process_deletions(clean_parser, handler, offsets, observer, policy) {
+            Ok(mut deletion_failures) => failures.append(&mut deletion_failures),
+            Err(e) => return Err(e),
         }
-    }
 
-    if rc == 0 {
-        rc = process_deletions(clean_parser, handler, offsets);
-    }
+        if let Some(r) = handler.flush().into_iter().find(|r| !r.success) {
+            let e = DiffError::HandlerRejected { n: r.n, dn: String::new(), code: -1 };
+            if !policy.continue_on_error {
+                return Err(e);
+            }
+            failures.push((String::new(), e));
+        }
 
-    // On handler error, keep state for recovery
-    if rc == -2 {
-        return rc;
-    }
+        Ok(())
+    })();
 
-    // Unmark offsets (restore inverted ones)
-    for n in 0..offsets.len() {
-        if offsets[n] < 0 {
-            long_array_invert(offsets, n);
+    match result {
+        // On an aborting handler error, keep state for recovery.
+        Err(e @ DiffError::HandlerRejected { .. }) => Err(e),
+        Err(e) => {
+            for n in 0..offsets.len() {
+                if offsets[n] < 0 {
+                    long_array_invert(offsets, n);
+                }
+            }
+            Err(e)
+        }
+        Ok(()) => {
+            for n in 0..offsets.len() {
+                if offsets[n] < 0 {
+                    long_array_invert(offsets, n);
+                }
+            }
+            Ok(failures)
         }
     }
-    rc
 }
 
 // ===========================================================================
@@ -723,6 +1894,10 @@ mod tests {
         Add,
         Delete,
         Rename0,
+        EnqueueAdd,
+        EnqueueDelete,
+        EnqueueChange,
+        EnqueueRename0,
     }
 
     #[derive(Debug, Clone)]
@@ -734,11 +1909,13 @@ mod tests {
         dn2: Option<String>,
         deleteoldrdn: bool,
         num_mods: usize,
+        result: DiffResult<()>,
     }
 
     struct MockHandler {
         calls: Vec<MockCall>,
         fail_on_call: i32, // -1 = never fail
+        fail_with: DiffError,
     }
 
     impl MockHandler {
@@ -746,13 +1923,29 @@ mod tests {
             MockHandler {
                 calls: Vec::new(),
                 fail_on_call: -1,
+                fail_with: DiffError::HandlerRejected { n: -1, dn: String::new(), code: -1 },
             }
         }
+
+        /// Make the call at index `idx` (0-based, across all `handle_*`
+        /// calls) fail with `err` instead of succeeding.
+        #[allow(dead_code)]
+        fn fail_on(&mut self, idx: i32, err: DiffError) {
+            self.fail_on_call = idx;
+            self.fail_with = err;
+        }
     }
 
-    impl DiffHandler for MockHandler {
-        fn handle_add(&mut self, n: i32, dn: &str, mods: &[LdapMod]) -> i32 {
+    impl DiffHandler for MockHandler {}
+
+    impl SyncDiffHandler for MockHandler {
+        fn handle_add(&mut self, n: i32, dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
             let idx = self.calls.len() as i32;
+            let result = if idx == self.fail_on_call {
+                Err(self.fail_with.clone().with_n(n))
+            } else {
+                Ok(())
+            };
             self.calls.push(MockCall {
                 call_type: CallType::Add,
                 n,
@@ -760,16 +1953,18 @@ mod tests {
                 dn2: None,
                 deleteoldrdn: false,
                 num_mods: mods.len(),
+                result: result.clone(),
             });
-            if idx == self.fail_on_call {
-                -1
-            } else {
-                0
-            }
+            result
         }
 
-        fn handle_delete(&mut self, n: i32, dn: &str) -> i32 {
+        fn handle_delete(&mut self, n: i32, dn: &str) -> DiffResult<()> {
             let idx = self.calls.len() as i32;
+            let result = if idx == self.fail_on_call {
+                Err(self.fail_with.clone().with_n(n))
+            } else {
+                Ok(())
+            };
             self.calls.push(MockCall {
                 call_type: CallType::Delete,
                 n,
@@ -777,16 +1972,18 @@ mod tests {
                 dn2: None,
                 deleteoldrdn: false,
                 num_mods: 0,
+                result: result.clone(),
             });
-            if idx == self.fail_on_call {
-                -1
-            } else {
-                0
-            }
+            result
         }
 
-        fn handle_change(&mut self, n: i32, old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> i32 {
+        fn handle_change(&mut self, n: i32, old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
             let idx = self.calls.len() as i32;
+            let result = if idx == self.fail_on_call {
+                Err(self.fail_with.clone().with_n(n))
+            } else {
+                Ok(())
+            };
             self.calls.push(MockCall {
                 call_type: CallType::Change,
                 n,
@@ -794,16 +1991,18 @@ mod tests {
                 dn2: Some(new_dn.to_string()),
                 deleteoldrdn: false,
                 num_mods: mods.len(),
+                result: result.clone(),
             });
-            if idx == self.fail_on_call {
-                -1
-            } else {
-                0
-            }
+            result
         }
 
-        fn handle_rename(&mut self, n: i32, old_dn: &str, entry: &Entry) -> i32 {
+        fn handle_rename(&mut self, n: i32, old_dn: &str, entry: &Entry) -> DiffResult<()> {
             let idx = self.calls.len() as i32;
+            let result = if idx == self.fail_on_call {
+                Err(self.fail_with.clone().with_n(n))
+            } else {
+                Ok(())
+            };
             self.calls.push(MockCall {
                 call_type: CallType::Rename,
                 n,
@@ -811,12 +2010,9 @@ mod tests {
                 dn2: Some(entry.dn.clone()),
                 deleteoldrdn: false,
                 num_mods: 0,
+                result: result.clone(),
             });
-            if idx == self.fail_on_call {
-                -1
-            } else {
-                0
-            }
+            result
         }
 
         fn handle_rename0(
@@ -825,8 +2021,13 @@ mod tests {
             old_dn: &str,
             new_dn: &str,
             deleteoldrdn: bool,
-        ) -> i32 {
+        ) -> DiffResult<()> {
             let idx = self.calls.len() as i32;
+            let result = if idx == self.fail_on_call {
+                Err(self.fail_with.clone().with_n(n))
+            } else {
+                Ok(())
+            };
             self.calls.push(MockCall {
                 call_type: CallType::Rename0,
                 n,
@@ -834,12 +2035,89 @@ mod tests {
                 dn2: Some(new_dn.to_string()),
                 deleteoldrdn,
                 num_mods: 0,
+                result: result.clone(),
+            });
+            result
+        }
+    }
+
+    impl AsyncDiffHandler for MockHandler {
+        fn enqueue_add(&mut self, n: i32, dn: &str, mods: &[LdapMod]) {
+            self.calls.push(MockCall {
+                call_type: CallType::EnqueueAdd,
+                n,
+                dn: dn.to_string(),
+                dn2: None,
+                deleteoldrdn: false,
+                num_mods: mods.len(),
+                result: Ok(()),
+            });
+        }
+
+        fn enqueue_delete(&mut self, n: i32, dn: &str) {
+            self.calls.push(MockCall {
+                call_type: CallType::EnqueueDelete,
+                n,
+                dn: dn.to_string(),
+                dn2: None,
+                deleteoldrdn: false,
+                num_mods: 0,
+                result: Ok(()),
+            });
+        }
+
+        fn enqueue_change(&mut self, n: i32, old_dn: &str, new_dn: &str, mods: &[LdapMod]) {
+            self.calls.push(MockCall {
+                call_type: CallType::EnqueueChange,
+                n,
+                dn: old_dn.to_string(),
+                dn2: Some(new_dn.to_string()),
+                deleteoldrdn: false,
+                num_mods: mods.len(),
+                result: Ok(()),
+            });
+        }
+
+        fn enqueue_rename0(&mut self, n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) {
+            self.calls.push(MockCall {
+                call_type: CallType::EnqueueRename0,
+                n,
+                dn: old_dn.to_string(),
+                dn2: Some(new_dn.to_string()),
+                deleteoldrdn,
+                num_mods: 0,
+                result: Ok(()),
+            });
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct NoteAttributeCall {
+        n: i32,
+        dn: String,
+        attr: String,
+        added: Vec<Vec<u8>>,
+        removed: Vec<Vec<u8>>,
+    }
+
+    #[derive(Default)]
+    struct MockObserver {
+        attrs: Vec<NoteAttributeCall>,
+        entries: Vec<(i32, String, DiffOp)>,
+    }
+
+    impl DiffObserver for MockObserver {
+        fn note_attribute(&mut self, n: i32, dn: &str, attr: &str, added: &[Vec<u8>], removed: &[Vec<u8>]) {
+            self.attrs.push(NoteAttributeCall {
+                n,
+                dn: dn.to_string(),
+                attr: attr.to_string(),
+                added: added.to_vec(),
+                removed: removed.to_vec(),
             });
-            if idx == self.fail_on_call {
-                -1
-            } else {
-                0
-            }
+        }
+        fn note_entry(&mut self, n: i32, dn: &str, op: DiffOp) {
+            self.entries.push((n, dn.to_string(), op));
         }
     }
 
@@ -952,42 +2230,51 @@ mod tests {
     fn test_frob_ava_check_found() {
         let mut e = make_entry("cn=test,dc=example,dc=com");
         add_attr_value(&mut e, "cn", "test");
-        assert_eq!(frob_ava(&mut e, FrobMode::Check, "cn", b"test"), 0);
+        assert_eq!(frob_ava(&mut e, &Comparator::new(), FrobMode::Check, "cn", b"test"), Ok(()));
     }
 
     #[test]
     fn test_frob_ava_check_not_found() {
         let mut e = make_entry("cn=test,dc=example,dc=com");
         add_attr_value(&mut e, "cn", "test");
-        assert_eq!(frob_ava(&mut e, FrobMode::Check, "cn", b"other"), -1);
+        assert!(matches!(
+            frob_ava(&mut e, &Comparator::new(), FrobMode::Check, "cn", b"other"),
+            Err(DiffError::AvaCheckFailed { .. })
+        ));
     }
 
     #[test]
     fn test_frob_ava_check_no_attr() {
         let mut e = make_entry("cn=test,dc=example,dc=com");
-        assert_eq!(frob_ava(&mut e, FrobMode::Check, "cn", b"test"), -1);
+        assert!(matches!(
+            frob_ava(&mut e, &Comparator::new(), FrobMode::Check, "cn", b"test"),
+            Err(DiffError::AvaCheckFailed { .. })
+        ));
     }
 
     #[test]
     fn test_frob_ava_check_none_absent() {
         let mut e = make_entry("cn=test,dc=example,dc=com");
         add_attr_value(&mut e, "cn", "test");
-        // CHECK_NONE: value is NOT absent (it's present) -> returns -1
-        assert_eq!(frob_ava(&mut e, FrobMode::CheckNone, "cn", b"test"), -1);
+        // CHECK_NONE: value is NOT absent (it's present) -> fails
+        assert!(matches!(
+            frob_ava(&mut e, &Comparator::new(), FrobMode::CheckNone, "cn", b"test"),
+            Err(DiffError::AvaCheckFailed { .. })
+        ));
     }
 
     #[test]
     fn test_frob_ava_check_none_present() {
         let mut e = make_entry("cn=test,dc=example,dc=com");
         add_attr_value(&mut e, "cn", "test");
-        // CHECK_NONE: value IS absent (different value) -> returns 0
-        assert_eq!(frob_ava(&mut e, FrobMode::CheckNone, "cn", b"other"), 0);
+        // CHECK_NONE: value IS absent (different value) -> succeeds
+        assert_eq!(frob_ava(&mut e, &Comparator::new(), FrobMode::CheckNone, "cn", b"other"), Ok(()));
     }
 
     #[test]
     fn test_frob_ava_add() {
         let mut e = make_entry("cn=test,dc=example,dc=com");
-        frob_ava(&mut e, FrobMode::Add, "cn", b"test");
+        let _ = frob_ava(&mut e, &Comparator::new(), FrobMode::Add, "cn", b"test");
         let a = e.get_attribute("cn").unwrap();
         assert_eq!(a.find_value(b"test"), Some(0));
     }
@@ -996,7 +2283,7 @@ mod tests {
     fn test_frob_ava_add_idempotent() {
         let mut e = make_entry("cn=test,dc=example,dc=com");
         add_attr_value(&mut e, "cn", "test");
-        frob_ava(&mut e, FrobMode::Add, "cn", b"test");
+        let _ = frob_ava(&mut e, &Comparator::new(), FrobMode::Add, "cn", b"test");
         let a = e.get_attribute("cn").unwrap();
         assert_eq!(a.values.len(), 1);
     }
@@ -1005,7 +2292,7 @@ mod tests {
     fn test_frob_ava_remove() {
         let mut e = make_entry("cn=test,dc=example,dc=com");
         add_attr_value(&mut e, "cn", "test");
-        frob_ava(&mut e, FrobMode::Remove, "cn", b"test");
+        let _ = frob_ava(&mut e, &Comparator::new(), FrobMode::Remove, "cn", b"test");
         let a = e.get_attribute("cn").unwrap();
         assert_eq!(a.values.len(), 0);
     }
@@ -1017,8 +2304,8 @@ mod tests {
         let mut e = make_entry("cn=test,dc=example,dc=com");
         add_attr_value(&mut e, "cn", "test");
         assert_eq!(
-            frob_rdn(&mut e, "cn=test,dc=example,dc=com", FrobMode::Check),
-            0
+            frob_rdn(&mut e, &Comparator::new(), "cn=test,dc=example,dc=com", FrobMode::Check),
+            Ok(())
         );
     }
 
@@ -1026,16 +2313,16 @@ mod tests {
     fn test_frob_rdn_check_nomatch() {
         let mut e = make_entry("cn=test,dc=example,dc=com");
         add_attr_value(&mut e, "cn", "other");
-        assert_eq!(
-            frob_rdn(&mut e, "cn=test,dc=example,dc=com", FrobMode::Check),
-            -1
-        );
+        assert!(matches!(
+            frob_rdn(&mut e, &Comparator::new(), "cn=test,dc=example,dc=com", FrobMode::Check),
+            Err(DiffError::AvaCheckFailed { .. })
+        ));
     }
 
     #[test]
     fn test_frob_rdn_add() {
         let mut e = make_entry("cn=new,dc=example,dc=com");
-        frob_rdn(&mut e, "cn=new,dc=example,dc=com", FrobMode::Add);
+        let _ = frob_rdn(&mut e, &Comparator::new(), "cn=new,dc=example,dc=com", FrobMode::Add);
         let a = e.get_attribute("cn").unwrap();
         assert_eq!(a.find_value(b"new"), Some(0));
     }
@@ -1050,7 +2337,7 @@ mod tests {
         add_attr_value(&mut data, "cn", "new");
 
         let mut deleteoldrdn = false;
-        assert_eq!(validate_rename(&mut clean, &mut data, &mut deleteoldrdn), 0);
+        assert_eq!(validate_rename(&mut clean, &mut data, &Comparator::new(), &mut deleteoldrdn), Ok(()));
         assert!(deleteoldrdn);
     }
 
@@ -1063,7 +2350,7 @@ mod tests {
         add_attr_value(&mut data, "cn", "old");
 
         let mut deleteoldrdn = true;
-        assert_eq!(validate_rename(&mut clean, &mut data, &mut deleteoldrdn), 0);
+        assert_eq!(validate_rename(&mut clean, &mut data, &Comparator::new(), &mut deleteoldrdn), Ok(()));
         assert!(!deleteoldrdn);
     }
 
@@ -1073,10 +2360,10 @@ mod tests {
         let mut data = make_entry("cn=new,dc=example,dc=com");
         add_attr_value(&mut data, "cn", "new");
         let mut deleteoldrdn = false;
-        assert_eq!(
-            validate_rename(&mut clean, &mut data, &mut deleteoldrdn),
-            -1
-        );
+        assert!(matches!(
+            validate_rename(&mut clean, &mut data, &Comparator::new(), &mut deleteoldrdn),
+            Err(DiffError::EmptyDn { .. })
+        ));
     }
 
     #[test]
@@ -1085,10 +2372,10 @@ mod tests {
         add_attr_value(&mut clean, "cn", "old");
         let mut data = make_entry("");
         let mut deleteoldrdn = false;
-        assert_eq!(
-            validate_rename(&mut clean, &mut data, &mut deleteoldrdn),
-            -1
-        );
+        assert!(matches!(
+            validate_rename(&mut clean, &mut data, &Comparator::new(), &mut deleteoldrdn),
+            Err(DiffError::EmptyDn { .. })
+        ));
     }
 
     #[test]
@@ -1098,10 +2385,10 @@ mod tests {
         let mut data = make_entry("cn=new,dc=example,dc=com");
         add_attr_value(&mut data, "cn", "new");
         let mut deleteoldrdn = false;
-        assert_eq!(
-            validate_rename(&mut clean, &mut data, &mut deleteoldrdn),
-            -1
-        );
+        assert!(matches!(
+            validate_rename(&mut clean, &mut data, &Comparator::new(), &mut deleteoldrdn),
+            Err(DiffError::MissingOldRdn { .. })
+        ));
     }
 
     // ── Group 6: compare_streams ──────────────────────────────────
@@ -1118,8 +2405,8 @@ mod tests {
         let mut data_parser = LdifParser::new(Cursor::new(ldif.as_bytes().to_vec()));
         let mut m = MockHandler::new();
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, 0);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert_eq!(result, Ok(vec![]));
         assert_eq!(m.calls.len(), 0);
     }
 
@@ -1139,8 +2426,8 @@ mod tests {
         let mut data_parser = LdifParser::new(Cursor::new(ldif.as_bytes().to_vec()));
         let mut m = MockHandler::new();
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, 0);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert_eq!(result, Ok(vec![]));
         assert_eq!(m.calls.len(), 0);
     }
 
@@ -1162,8 +2449,8 @@ mod tests {
         let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
         let mut m = MockHandler::new();
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, 0);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert_eq!(result, Ok(vec![]));
         assert_eq!(m.calls.len(), 1);
         assert_eq!(m.calls[0].call_type, CallType::Change);
         assert_eq!(m.calls[0].dn, "cn=foo,dc=example,dc=com");
@@ -1187,8 +2474,8 @@ mod tests {
         let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
         let mut m = MockHandler::new();
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, 0);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert_eq!(result, Ok(vec![]));
         assert_eq!(m.calls.len(), 1);
         assert_eq!(m.calls[0].call_type, CallType::Change);
     }
@@ -1210,8 +2497,8 @@ mod tests {
         let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
         let mut m = MockHandler::new();
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, 0);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert_eq!(result, Ok(vec![]));
         assert_eq!(m.calls.len(), 1);
         assert_eq!(m.calls[0].call_type, CallType::Change);
     }
@@ -1229,8 +2516,8 @@ mod tests {
         let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
         let mut m = MockHandler::new();
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, 0);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert_eq!(result, Ok(vec![]));
         assert_eq!(m.calls.len(), 1);
         assert_eq!(m.calls[0].call_type, CallType::Delete);
         assert_eq!(m.calls[0].dn, "cn=foo,dc=example,dc=com");
@@ -1256,8 +2543,8 @@ mod tests {
         let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
         let mut m = MockHandler::new();
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, 0);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert_eq!(result, Ok(vec![]));
         let found_delete = m
             .calls
             .iter()
@@ -1285,8 +2572,8 @@ mod tests {
         let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
         let mut m = MockHandler::new();
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, 0);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert_eq!(result, Ok(vec![]));
         let found_add = m
             .calls
             .iter()
@@ -1310,8 +2597,8 @@ mod tests {
         let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
         let mut m = MockHandler::new();
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, 0);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert_eq!(result, Ok(vec![]));
         let found_rename = m
             .calls
             .iter()
@@ -1332,7 +2619,7 @@ mod tests {
         let mut data_parser = LdifParser::new(Cursor::new(ldif.as_bytes().to_vec()));
         let mut m = MockHandler::new();
 
-        compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
+        let _ = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
         assert_eq!(offsets[0], orig);
     }
 
@@ -1349,32 +2636,70 @@ mod tests {
         let (_, datapos) = parser.peek_entry(None).unwrap().unwrap();
         let mut m = MockHandler::new();
 
-        let rc = process_immediate(&mut parser, &mut m, datapos, "add");
-        assert_eq!(rc, 0);
+        let result = process_immediate(&mut parser, &mut m, datapos, "add", &Comparator::new());
+        assert_eq!(result, Ok(()));
         assert_eq!(m.calls.len(), 1);
         assert_eq!(m.calls[0].call_type, CallType::Add);
         assert_eq!(m.calls[0].dn, "cn=new,dc=example,dc=com");
+        assert_eq!(m.calls[0].num_mods, 1);
     }
 
     #[test]
-    fn test_process_immediate_delete() {
-        let ldif = "\ndn: cn=old,dc=example,dc=com\n\
-                     changetype: delete\n\
+    fn test_process_immediate_add_generates_entryuuid_when_enabled() {
+        let ldif = "\ndn: cn=new,dc=example,dc=com\n\
+                     ldapvi-key: add\n\
+                     cn: new\n\
                      \n";
 
         let mut parser = LdifParser::new(Cursor::new(ldif.as_bytes().to_vec()));
         let (_, datapos) = parser.peek_entry(None).unwrap().unwrap();
         let mut m = MockHandler::new();
+        let cmp = Comparator::new().with_entryuuid_generation(true);
 
-        let rc = process_immediate(&mut parser, &mut m, datapos, "delete");
-        assert_eq!(rc, 0);
-        assert_eq!(m.calls.len(), 1);
-        assert_eq!(m.calls[0].call_type, CallType::Delete);
-        assert_eq!(m.calls[0].dn, "cn=old,dc=example,dc=com");
+        let result = process_immediate(&mut parser, &mut m, datapos, "add", &cmp);
+        assert_eq!(result, Ok(()));
+        // The `cn` attribute plus the synthesized `entryUUID`.
+        assert_eq!(m.calls[0].num_mods, 2);
     }
 
     #[test]
-    fn test_process_immediate_modify() {
+    fn test_process_immediate_add_skips_entryuuid_already_present() {
+        let ldif = "\ndn: cn=new,dc=example,dc=com\n\
+                     ldapvi-key: add\n\
+                     cn: new\n\
+                     entryUUID: 11111111-1111-4111-8111-111111111111\n\
+                     \n";
+
+        let mut parser = LdifParser::new(Cursor::new(ldif.as_bytes().to_vec()));
+        let (_, datapos) = parser.peek_entry(None).unwrap().unwrap();
+        let mut m = MockHandler::new();
+        let cmp = Comparator::new().with_entryuuid_generation(true);
+
+        let result = process_immediate(&mut parser, &mut m, datapos, "add", &cmp);
+        assert_eq!(result, Ok(()));
+        // Already had its own entryUUID -- not doubled up.
+        assert_eq!(m.calls[0].num_mods, 2);
+    }
+
+    #[test]
+    fn test_process_immediate_delete() {
+        let ldif = "\ndn: cn=old,dc=example,dc=com\n\
+                     changetype: delete\n\
+                     \n";
+
+        let mut parser = LdifParser::new(Cursor::new(ldif.as_bytes().to_vec()));
+        let (_, datapos) = parser.peek_entry(None).unwrap().unwrap();
+        let mut m = MockHandler::new();
+
+        let result = process_immediate(&mut parser, &mut m, datapos, "delete", &Comparator::new());
+        assert_eq!(result, Ok(()));
+        assert_eq!(m.calls.len(), 1);
+        assert_eq!(m.calls[0].call_type, CallType::Delete);
+        assert_eq!(m.calls[0].dn, "cn=old,dc=example,dc=com");
+    }
+
+    #[test]
+    fn test_process_immediate_modify() {
         let ldif = "\ndn: cn=foo,dc=example,dc=com\n\
                      changetype: modify\n\
                      replace: sn\n\
@@ -1386,8 +2711,8 @@ mod tests {
         let (_, datapos) = parser.peek_entry(None).unwrap().unwrap();
         let mut m = MockHandler::new();
 
-        let rc = process_immediate(&mut parser, &mut m, datapos, "modify");
-        assert_eq!(rc, 0);
+        let result = process_immediate(&mut parser, &mut m, datapos, "modify", &Comparator::new());
+        assert_eq!(result, Ok(()));
         assert_eq!(m.calls.len(), 1);
         assert_eq!(m.calls[0].call_type, CallType::Change);
     }
@@ -1403,8 +2728,8 @@ mod tests {
         let (_, datapos) = parser.peek_entry(None).unwrap().unwrap();
         let mut m = MockHandler::new();
 
-        let rc = process_immediate(&mut parser, &mut m, datapos, "bogus");
-        assert_eq!(rc, -1);
+        let result = process_immediate(&mut parser, &mut m, datapos, "bogus", &Comparator::new());
+        assert!(matches!(result, Err(DiffError::ParseError { .. })));
         assert_eq!(m.calls.len(), 0);
     }
 
@@ -1420,8 +2745,8 @@ mod tests {
         let (_, datapos) = parser.peek_entry(None).unwrap().unwrap();
         let mut m = MockHandler::new();
 
-        let rc = process_immediate(&mut parser, &mut m, datapos, "replace");
-        assert_eq!(rc, 0);
+        let result = process_immediate(&mut parser, &mut m, datapos, "replace", &Comparator::new());
+        assert_eq!(result, Ok(()));
         assert_eq!(m.calls.len(), 1);
         assert_eq!(m.calls[0].call_type, CallType::Change);
     }
@@ -1438,8 +2763,8 @@ mod tests {
         let (_, datapos) = parser.peek_entry(None).unwrap().unwrap();
         let mut m = MockHandler::new();
 
-        let rc = process_immediate(&mut parser, &mut m, datapos, "rename");
-        assert_eq!(rc, 0);
+        let result = process_immediate(&mut parser, &mut m, datapos, "rename", &Comparator::new());
+        assert_eq!(result, Ok(()));
         assert_eq!(m.calls.len(), 1);
         assert_eq!(m.calls[0].call_type, CallType::Rename0);
     }
@@ -1467,8 +2792,8 @@ mod tests {
         let mut m = MockHandler::new();
         m.fail_on_call = 0;
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, -2);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert!(matches!(result, Err(DiffError::HandlerRejected { .. })));
     }
 
     #[test]
@@ -1490,8 +2815,8 @@ mod tests {
         let mut m = MockHandler::new();
         m.fail_on_call = 0;
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, -2);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert!(matches!(result, Err(DiffError::HandlerRejected { .. })));
     }
 
     // ── Group 9: error conditions ─────────────────────────────────
@@ -1513,8 +2838,8 @@ mod tests {
         let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
         let mut m = MockHandler::new();
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, -1);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert!(matches!(result, Err(DiffError::ParseError { .. })));
     }
 
     #[test]
@@ -1538,7 +2863,766 @@ mod tests {
         let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
         let mut m = MockHandler::new();
 
-        let rc = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets);
-        assert_eq!(rc, -1);
+        let result = compare_streams(&mut clean_parser, &mut data_parser, &mut m, &mut offsets, DiffMode::Replace, &Comparator::new(), &mut NullObserver, &CommitPolicy::strict());
+        assert!(matches!(result, Err(DiffError::ParseError { .. })));
+    }
+
+    // ── Group 10: granular compare_entries ─────────────────────────
+
+    fn add_attr_values(entry: &mut Entry, ad: &str, vals: &[&str]) {
+        for v in vals {
+            add_attr_value(entry, ad, v);
+        }
+    }
+
+    #[test]
+    fn test_compare_entries_granular_emits_add_and_delete() {
+        let mut clean = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_values(
+            &mut clean,
+            "mail",
+            &["a@x.com", "b@x.com", "c@x.com", "d@x.com"],
+        );
+        let mut data = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_values(
+            &mut data,
+            "mail",
+            &["a@x.com", "c@x.com", "d@x.com", "e@x.com"],
+        );
+
+        let mods = compare_entries(&clean, &data, DiffMode::Granular, &Comparator::new(), 0, &mut NullObserver);
+        assert_eq!(mods.len(), 2);
+        let delete = mods.iter().find(|m| m.op == ModOp::Delete).unwrap();
+        assert_eq!(delete.values, vec![b"b@x.com".to_vec()]);
+        let add = mods.iter().find(|m| m.op == ModOp::Add).unwrap();
+        assert_eq!(add.values, vec![b"e@x.com".to_vec()]);
+    }
+
+    #[test]
+    fn test_compare_entries_granular_preserves_original_order() {
+        let mut clean = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_values(
+            &mut clean,
+            "mail",
+            &["d@x.com", "c@x.com", "a@x.com", "b@x.com"],
+        );
+        let mut data = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_values(
+            &mut data,
+            "mail",
+            &["d@x.com", "c@x.com", "a@x.com", "e@x.com"],
+        );
+
+        let mods = compare_entries(&clean, &data, DiffMode::Granular, &Comparator::new(), 0, &mut NullObserver);
+        let delete = mods.iter().find(|m| m.op == ModOp::Delete).unwrap();
+        assert_eq!(delete.values, vec![b"b@x.com".to_vec()]);
+        let add = mods.iter().find(|m| m.op == ModOp::Add).unwrap();
+        assert_eq!(add.values, vec![b"e@x.com".to_vec()]);
+    }
+
+    #[test]
+    fn test_compare_entries_granular_falls_back_on_large_delta() {
+        let mut clean = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_values(&mut clean, "mail", &["a@x.com", "b@x.com"]);
+        let mut data = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_values(&mut data, "mail", &["c@x.com", "d@x.com"]);
+
+        let mods = compare_entries(&clean, &data, DiffMode::Granular, &Comparator::new(), 0, &mut NullObserver);
+        assert_eq!(mods.len(), 1);
+        assert_eq!(mods[0].op, ModOp::Replace);
+        assert_eq!(
+            mods[0].values,
+            vec![b"c@x.com".to_vec(), b"d@x.com".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_compare_entries_granular_falls_back_on_single_valued() {
+        let mut clean = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_value(&mut clean, "description", "old");
+        let mut data = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_value(&mut data, "description", "new");
+
+        let mods = compare_entries(&clean, &data, DiffMode::Granular, &Comparator::new(), 0, &mut NullObserver);
+        assert_eq!(mods.len(), 1);
+        assert_eq!(mods[0].op, ModOp::Replace);
+    }
+
+    #[test]
+    fn test_compare_entries_replace_mode_ignores_granularity() {
+        let mut clean = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_values(&mut clean, "mail", &["a@x.com", "b@x.com", "c@x.com"]);
+        let mut data = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_values(&mut data, "mail", &["a@x.com", "c@x.com", "d@x.com"]);
+
+        let mods = compare_entries(&clean, &data, DiffMode::Replace, &Comparator::new(), 0, &mut NullObserver);
+        assert_eq!(mods.len(), 1);
+        assert_eq!(mods[0].op, ModOp::Replace);
+    }
+
+    #[test]
+    fn test_compare_entries_granular_never_emits_empty_values() {
+        let mut clean = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_values(&mut clean, "mail", &["a@x.com", "b@x.com"]);
+        let mut data = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_values(&mut data, "mail", &["a@x.com", "b@x.com", "c@x.com"]);
+
+        let mods = compare_entries(&clean, &data, DiffMode::Granular, &Comparator::new(), 0, &mut NullObserver);
+        assert_eq!(mods.len(), 1);
+        assert_eq!(mods[0].op, ModOp::Add);
+        assert_eq!(mods[0].values, vec![b"c@x.com".to_vec()]);
+    }
+
+    // ── Group 11: Comparator ─────────────────────────────────────
+
+    #[test]
+    fn test_comparator_default_is_case_exact() {
+        let cmp = Comparator::new();
+        assert!(!cmp.values_equal("cn", b"Jane", b"jane"));
+        assert!(cmp.values_equal("cn", b"Jane", b"Jane"));
+    }
+
+    #[test]
+    fn test_comparator_with_rule_overrides_per_attribute() {
+        let cmp = Comparator::new().with_rule("cn", Box::new(CaseIgnoreMatch));
+        assert!(cmp.values_equal("cn", b"Jane", b"jane"));
+        // Unmapped attributes keep the default rule.
+        assert!(!cmp.values_equal("sn", b"Doe", b"doe"));
+    }
+
+    #[test]
+    fn test_comparator_ad_eq_is_always_case_insensitive() {
+        let cmp = Comparator::new();
+        assert!(cmp.ad_eq("CN", "cn"));
+        assert!(!cmp.ad_eq("cn", "sn"));
+    }
+
+    #[test]
+    fn test_compare_entries_case_insensitive_attribute_is_unchanged() {
+        let mut clean = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_value(&mut clean, "cn", "foo");
+        let mut data = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_value(&mut data, "cn", "FOO");
+
+        let cmp = Comparator::new().with_rule("cn", Box::new(CaseIgnoreMatch));
+        let mods = compare_entries(&clean, &data, DiffMode::Replace, &cmp, 0, &mut NullObserver);
+        assert!(mods.is_empty());
+    }
+
+    #[test]
+    fn test_frob_rdn_matches_existing_rdn_value_case_insensitively() {
+        let mut e = make_entry("cn=Test,dc=example,dc=com");
+        add_attr_value(&mut e, "cn", "Test");
+        let cmp = Comparator::new().with_rule("cn", Box::new(CaseIgnoreMatch));
+        assert_eq!(
+            frob_rdn(&mut e, &cmp, "cn=test,dc=example,dc=com", FrobMode::Check),
+            Ok(())
+        );
+    }
+
+    // ── Group 12: DiffObserver ──────────────────────────────────────
+
+    #[test]
+    fn test_observer_reports_added_attribute() {
+        let clean_ldif = "\ndn: cn=foo,dc=example,dc=com\n\
+                           ldapvi-key: 0\n\
+                           cn: foo\n\
+                           \n";
+        let data_ldif = "\ndn: cn=foo,dc=example,dc=com\n\
+                          ldapvi-key: 0\n\
+                          cn: foo\n\
+                          mail: foo@example.com\n\
+                          \n";
+
+        let (clean_data, mut offsets) = make_clean_file(clean_ldif);
+        let mut clean_parser = LdifParser::new(Cursor::new(clean_data));
+        let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
+        let mut m = MockHandler::new();
+        let mut obs = MockObserver::default();
+
+        let result = compare_streams(
+            &mut clean_parser,
+            &mut data_parser,
+            &mut m,
+            &mut offsets,
+            DiffMode::Replace,
+            &Comparator::new(),
+            &mut obs,
+            &CommitPolicy::strict(),
+        );
+        assert_eq!(result, Ok(vec![]));
+        assert_eq!(obs.attrs.len(), 1);
+        assert_eq!(obs.attrs[0].attr, "mail");
+        assert_eq!(obs.attrs[0].added, vec![b"foo@example.com".to_vec()]);
+        assert!(obs.attrs[0].removed.is_empty());
+        assert_eq!(obs.entries, vec![(0, "cn=foo,dc=example,dc=com".to_string(), DiffOp::Modify)]);
+    }
+
+    #[test]
+    fn test_observer_reports_changed_value_as_add_and_remove() {
+        let clean_ldif = "\ndn: cn=foo,dc=example,dc=com\n\
+                           ldapvi-key: 0\n\
+                           cn: foo\n\
+                           sn: old\n\
+                           \n";
+        let data_ldif = "\ndn: cn=foo,dc=example,dc=com\n\
+                          ldapvi-key: 0\n\
+                          cn: foo\n\
+                          sn: new\n\
+                          \n";
+
+        let (clean_data, mut offsets) = make_clean_file(clean_ldif);
+        let mut clean_parser = LdifParser::new(Cursor::new(clean_data));
+        let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
+        let mut m = MockHandler::new();
+        let mut obs = MockObserver::default();
+
+        let _ = compare_streams(
+            &mut clean_parser,
+            &mut data_parser,
+            &mut m,
+            &mut offsets,
+            DiffMode::Replace,
+            &Comparator::new(),
+            &mut obs,
+            &CommitPolicy::strict(),
+        );
+        assert_eq!(obs.attrs.len(), 1);
+        assert_eq!(obs.attrs[0].attr, "sn");
+        assert_eq!(obs.attrs[0].added, vec![b"new".to_vec()]);
+        assert_eq!(obs.attrs[0].removed, vec![b"old".to_vec()]);
+    }
+
+    #[test]
+    fn test_observer_not_called_when_nothing_changed() {
+        let ldif = "\ndn: cn=foo,dc=example,dc=com\n\
+                     ldapvi-key: 0\n\
+                     cn: foo\n\
+                     \n";
+
+        let (clean_data, mut offsets) = make_clean_file(ldif);
+        let mut clean_parser = LdifParser::new(Cursor::new(clean_data));
+        let mut data_parser = LdifParser::new(Cursor::new(ldif.as_bytes().to_vec()));
+        let mut m = MockHandler::new();
+        let mut obs = MockObserver::default();
+
+        let _ = compare_streams(
+            &mut clean_parser,
+            &mut data_parser,
+            &mut m,
+            &mut offsets,
+            DiffMode::Replace,
+            &Comparator::new(),
+            &mut obs,
+            &CommitPolicy::strict(),
+        );
+        assert!(obs.attrs.is_empty());
+        assert!(obs.entries.is_empty());
+    }
+
+    #[test]
+    fn test_observer_reports_deletion_of_every_attribute() {
+        let clean_ldif = "\ndn: cn=foo,dc=example,dc=com\n\
+                           ldapvi-key: 0\n\
+                           cn: foo\n\
+                           sn: bar\n\
+                           \n";
+        let data_ldif = "";
+
+        let (clean_data, mut offsets) = make_clean_file(clean_ldif);
+        let mut clean_parser = LdifParser::new(Cursor::new(clean_data));
+        let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
+        let mut m = MockHandler::new();
+        let mut obs = MockObserver::default();
+
+        let result = compare_streams(
+            &mut clean_parser,
+            &mut data_parser,
+            &mut m,
+            &mut offsets,
+            DiffMode::Replace,
+            &Comparator::new(),
+            &mut obs,
+            &CommitPolicy::strict(),
+        );
+        assert_eq!(result, Ok(vec![]));
+        assert_eq!(obs.entries, vec![(0, "cn=foo,dc=example,dc=com".to_string(), DiffOp::Delete)]);
+        let attrs: std::collections::BTreeSet<_> = obs.attrs.iter().map(|a| a.attr.as_str()).collect();
+        assert_eq!(attrs, ["cn", "sn"].into_iter().collect());
+        assert!(obs.attrs.iter().all(|a| a.added.is_empty()));
+    }
+
+    // ── Group 13: AsyncDiffHandler / flush ──────────────────────────
+
+    /// A handler that implements `SyncDiffHandler` by enqueueing each call
+    /// instead of applying it, and dispatches the queue (here, just drains
+    /// it) in `flush`. This is the bridging pattern an async-backed commit
+    /// handler is expected to follow.
+    #[derive(Default)]
+    struct BatchingHandler {
+        queued: Vec<(i32, CallType)>,
+        flush_calls: i32,
+    }
+
+    impl DiffHandler for BatchingHandler {
+        fn flush(&mut self) -> Vec<OpResult> {
+            self.flush_calls += 1;
+            self.queued
+                .drain(..)
+                .map(|(n, _)| OpResult { n, success: true })
+                .collect()
+        }
+    }
+
+    impl SyncDiffHandler for BatchingHandler {
+        fn handle_add(&mut self, n: i32, _dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
+            self.enqueue_add(n, _dn, mods);
+            Ok(())
+        }
+
+        fn handle_delete(&mut self, n: i32, dn: &str) -> DiffResult<()> {
+            self.enqueue_delete(n, dn);
+            Ok(())
+        }
+
+        fn handle_change(&mut self, n: i32, old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
+            self.enqueue_change(n, old_dn, new_dn, mods);
+            Ok(())
+        }
+
+        fn handle_rename(&mut self, n: i32, old_dn: &str, entry: &Entry) -> DiffResult<()> {
+            self.enqueue_rename0(n, old_dn, &entry.dn, false);
+            Ok(())
+        }
+
+        fn handle_rename0(&mut self, n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) -> DiffResult<()> {
+            self.enqueue_rename0(n, old_dn, new_dn, deleteoldrdn);
+            Ok(())
+        }
+    }
+
+    impl AsyncDiffHandler for BatchingHandler {
+        fn enqueue_add(&mut self, n: i32, _dn: &str, _mods: &[LdapMod]) {
+            self.queued.push((n, CallType::EnqueueAdd));
+        }
+
+        fn enqueue_delete(&mut self, n: i32, _dn: &str) {
+            self.queued.push((n, CallType::EnqueueDelete));
+        }
+
+        fn enqueue_change(&mut self, n: i32, _old_dn: &str, _new_dn: &str, _mods: &[LdapMod]) {
+            self.queued.push((n, CallType::EnqueueChange));
+        }
+
+        fn enqueue_rename0(&mut self, n: i32, _old_dn: &str, _new_dn: &str, _deleteoldrdn: bool) {
+            self.queued.push((n, CallType::EnqueueRename0));
+        }
+    }
+
+    #[test]
+    fn test_batching_handler_flushes_at_boundaries() {
+        let clean_ldif = "\ndn: cn=foo,dc=example,dc=com\n\
+                           ldapvi-key: 0\n\
+                           cn: foo\n\
+                           sn: bar\n\
+                           \n";
+        let data_ldif = "\ndn: cn=foo,dc=example,dc=com\n\
+                          ldapvi-key: 0\n\
+                          cn: foo\n\
+                          sn: baz\n\
+                          \n";
+
+        let (clean_data, mut offsets) = make_clean_file(clean_ldif);
+        let mut clean_parser = LdifParser::new(Cursor::new(clean_data));
+        let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
+        let mut h = BatchingHandler::default();
+
+        let result = compare_streams(
+            &mut clean_parser,
+            &mut data_parser,
+            &mut h,
+            &mut offsets,
+            DiffMode::Replace,
+            &Comparator::new(),
+            &mut NullObserver,
+            &CommitPolicy::strict(),
+        );
+        assert_eq!(result, Ok(vec![]));
+        // One flush after the add/rename/change pass, one after deletions.
+        assert_eq!(h.flush_calls, 2);
+        assert!(h.queued.is_empty());
+    }
+
+    // ── Group 14: DnIndex ────────────────────────────────────────────
+
+    #[test]
+    fn test_dnindex_build_and_find() {
+        let ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                     ldapvi-key: 0\n\
+                     cn: alice\n\
+                     \n\
+                     dn: cn=bob,dc=example,dc=com\n\
+                     ldapvi-key: 1\n\
+                     cn: bob\n\
+                     \n\
+                     dn: cn=carol,dc=example,dc=com\n\
+                     ldapvi-key: 2\n\
+                     cn: carol\n\
+                     \n";
+        let mut parser = LdifParser::new(Cursor::new(ldif.as_bytes().to_vec()));
+        let index = DnIndex::build(&mut parser).unwrap();
+        assert_eq!(index.len(), 3);
+
+        let offset = index
+            .find(&mut parser, "cn=bob,dc=example,dc=com")
+            .unwrap()
+            .expect("bob should be found");
+        let (_, entry, _) = parser.read_entry(Some(offset)).unwrap().unwrap();
+        assert_eq!(entry.dn, "cn=bob,dc=example,dc=com");
+    }
+
+    #[test]
+    fn test_dnindex_find_case_insensitive() {
+        let ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                     ldapvi-key: 0\n\
+                     cn: alice\n\
+                     \n";
+        let mut parser = LdifParser::new(Cursor::new(ldif.as_bytes().to_vec()));
+        let index = DnIndex::build(&mut parser).unwrap();
+        let offset = index
+            .find(&mut parser, "CN=Alice,DC=Example,DC=Com")
+            .unwrap();
+        assert!(offset.is_some());
+    }
+
+    #[test]
+    fn test_dnindex_find_unknown_dn() {
+        let ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                     ldapvi-key: 0\n\
+                     cn: alice\n\
+                     \n";
+        let mut parser = LdifParser::new(Cursor::new(ldif.as_bytes().to_vec()));
+        let index = DnIndex::build(&mut parser).unwrap();
+        let offset = index.find(&mut parser, "cn=nobody,dc=example,dc=com").unwrap();
+        assert_eq!(offset, None);
+    }
+
+    #[test]
+    fn test_dnindex_empty_file() {
+        let mut parser = LdifParser::new(Cursor::new(Vec::new()));
+        let index = DnIndex::build(&mut parser).unwrap();
+        assert!(index.is_empty());
+        assert_eq!(index.find(&mut parser, "cn=alice,dc=example,dc=com").unwrap(), None);
+    }
+
+    // ── Group 15: --track-uuid identity tracking ─────────────────────
+
+    #[test]
+    fn test_build_uuid_index_maps_uuid_to_key() {
+        let ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                     ldapvi-key: 0\n\
+                     entryUUID: 11111111-1111-1111-1111-111111111111\n\
+                     cn: alice\n\
+                     \n\
+                     dn: cn=bob,dc=example,dc=com\n\
+                     ldapvi-key: 1\n\
+                     entryUUID: 22222222-2222-2222-2222-222222222222\n\
+                     cn: bob\n\
+                     \n";
+        let (clean_data, offsets) = make_clean_file(ldif);
+        let mut parser = LdifParser::new(Cursor::new(clean_data));
+        let index = build_uuid_index(&mut parser, &offsets);
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index.get(b"22222222-2222-2222-2222-222222222222".as_slice()),
+            Some(&1)
+        );
+    }
+
+    #[test]
+    fn test_build_uuid_index_skips_entries_without_uuid() {
+        let ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                     ldapvi-key: 0\n\
+                     cn: alice\n\
+                     \n";
+        let (clean_data, offsets) = make_clean_file(ldif);
+        let mut parser = LdifParser::new(Cursor::new(clean_data));
+        let index = build_uuid_index(&mut parser, &offsets);
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn test_compare_entries_ignores_entry_uuid_when_tracking() {
+        let mut clean = make_entry("cn=foo,dc=example,dc=com");
+        add_attr_value(&mut clean, "entryUUID", "11111111-1111-1111-1111-111111111111");
+        add_attr_value(&mut clean, "cn", "foo");
+        let data = clean.clone();
+
+        let cmp = Comparator::new().with_uuid_index(std::collections::HashMap::new());
+        let mods = compare_entries(&clean, &data, DiffMode::Replace, &cmp, 0, &mut NullObserver);
+        assert!(mods.is_empty());
+
+        // Changing only entryUUID must not surface as a modification either.
+        let mut changed_uuid = data.clone();
+        changed_uuid.find_attribute("entryUUID", true).unwrap().values[0] =
+            b"22222222-2222-2222-2222-222222222222".to_vec();
+        let mods = compare_entries(&clean, &changed_uuid, DiffMode::Replace, &cmp, 0, &mut NullObserver);
+        assert!(mods.is_empty());
+    }
+
+    #[test]
+    fn test_compare_streams_uuid_relinks_entries_swapped_between_keys() {
+        // alice and bob's content is swapped across keys 0 and 1, but each
+        // still carries its own entryUUID -- without tracking, this would
+        // look like two content replacements (or invalid renames); with it,
+        // each entry should be matched to its real original and show up as
+        // a plain rename.
+        let clean_ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                           ldapvi-key: 0\n\
+                           entryUUID: 11111111-1111-1111-1111-111111111111\n\
+                           cn: alice\n\
+                           \n\
+                           dn: cn=bob,dc=example,dc=com\n\
+                           ldapvi-key: 1\n\
+                           entryUUID: 22222222-2222-2222-2222-222222222222\n\
+                           cn: bob\n\
+                           \n";
+        let data_ldif = "\ndn: cn=bob,dc=example,dc=com\n\
+                          ldapvi-key: 0\n\
+                          entryUUID: 22222222-2222-2222-2222-222222222222\n\
+                          cn: bob\n\
+                          \n\
+                          dn: cn=alice,dc=example,dc=com\n\
+                          ldapvi-key: 1\n\
+                          entryUUID: 11111111-1111-1111-1111-111111111111\n\
+                          cn: alice\n\
+                          \n";
+
+        let (clean_data, mut offsets) = make_clean_file(clean_ldif);
+        let mut clean_index_parser = LdifParser::new(Cursor::new(clean_data.clone()));
+        let uuid_index = build_uuid_index(&mut clean_index_parser, &offsets);
+        let cmp = Comparator::new().with_uuid_index(uuid_index);
+
+        let mut clean_parser = LdifParser::new(Cursor::new(clean_data));
+        let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
+        let mut m = MockHandler::new();
+
+        let result = compare_streams(
+            &mut clean_parser,
+            &mut data_parser,
+            &mut m,
+            &mut offsets,
+            DiffMode::Replace,
+            &cmp,
+            &mut NullObserver,
+            &CommitPolicy::strict(),
+        );
+        assert_eq!(result, Ok(vec![]));
+        // Both sides are renames of their true counterpart, not deletions
+        // of one identity plus additions of another.
+        assert!(m.calls.iter().all(|c| c.call_type == CallType::Rename));
+        assert_eq!(m.calls.len(), 2);
+    }
+
+    // ── Group 16: check_key_structure ─────────────────────────────────
+
+    #[test]
+    fn test_check_key_structure_accepts_well_formed_data_file() {
+        let ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                     ldapvi-key: 0\n\
+                     cn: alice\n\
+                     \n\
+                     dn: cn=bob,dc=example,dc=com\n\
+                     ldapvi-key: 1\n\
+                     cn: bob\n\
+                     \n";
+        let (clean_data, offsets) = make_clean_file(ldif);
+        let mut clean_parser = LdifParser::new(Cursor::new(clean_data));
+        let mut data_parser = LdifParser::new(Cursor::new(ldif.as_bytes().to_vec()));
+        assert_eq!(check_key_structure(&mut clean_parser, &mut data_parser, &offsets), Ok(()));
+    }
+
+    #[test]
+    fn test_check_key_structure_rejects_duplicate_key() {
+        let clean_ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                           ldapvi-key: 0\n\
+                           cn: alice\n\
+                           \n";
+        let data_ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                          ldapvi-key: 0\n\
+                          cn: alice\n\
+                          \n\
+                          dn: cn=alice2,dc=example,dc=com\n\
+                          ldapvi-key: 0\n\
+                          cn: alice2\n\
+                          \n";
+        let (clean_data, offsets) = make_clean_file(clean_ldif);
+        let mut clean_parser = LdifParser::new(Cursor::new(clean_data));
+        let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
+        let result = check_key_structure(&mut clean_parser, &mut data_parser, &offsets);
+        assert!(matches!(result, Err(DiffError::StructuralMismatch { .. })));
+    }
+
+    #[test]
+    fn test_check_key_structure_rejects_out_of_range_key() {
+        let clean_ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                           ldapvi-key: 0\n\
+                           cn: alice\n\
+                           \n";
+        let data_ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                          ldapvi-key: 7\n\
+                          cn: alice\n\
+                          \n";
+        let (clean_data, offsets) = make_clean_file(clean_ldif);
+        let mut clean_parser = LdifParser::new(Cursor::new(clean_data));
+        let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
+        let result = check_key_structure(&mut clean_parser, &mut data_parser, &offsets);
+        assert!(matches!(result, Err(DiffError::StructuralMismatch { .. })));
+    }
+
+    #[test]
+    fn test_check_key_structure_rejects_orphaned_key_from_dropped_line() {
+        // alice's `ldapvi-key: 0` line was dropped while editing, so the LDIF
+        // parser's implicit-add fallback reads her back in as a brand new
+        // entry -- but her DN still matches the clean entry under key 0,
+        // which otherwise never appears in the data file.
+        let clean_ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                           ldapvi-key: 0\n\
+                           cn: alice\n\
+                           \n";
+        let data_ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                          cn: alice\n\
+                          \n";
+        let (clean_data, offsets) = make_clean_file(clean_ldif);
+        let mut clean_parser = LdifParser::new(Cursor::new(clean_data));
+        let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
+        let result = check_key_structure(&mut clean_parser, &mut data_parser, &offsets);
+        match result {
+            Err(DiffError::StructuralMismatch { message }) => {
+                assert!(message.contains("ldapvi-key"));
+            }
+            other => panic!("expected StructuralMismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_check_key_structure_allows_genuine_new_entry() {
+        let clean_ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                           ldapvi-key: 0\n\
+                           cn: alice\n\
+                           \n";
+        let data_ldif = "\ndn: cn=alice,dc=example,dc=com\n\
+                          ldapvi-key: 0\n\
+                          cn: alice\n\
+                          \n\
+                          dn: cn=carol,dc=example,dc=com\n\
+                          cn: carol\n\
+                          \n";
+        let (clean_data, offsets) = make_clean_file(clean_ldif);
+        let mut clean_parser = LdifParser::new(Cursor::new(clean_data));
+        let mut data_parser = LdifParser::new(Cursor::new(data_ldif.as_bytes().to_vec()));
+        assert_eq!(check_key_structure(&mut clean_parser, &mut data_parser, &offsets), Ok(()));
+    }
+
+    // ── Group 17: diff_entries ──────────────────────────────────────
+
+    #[test]
+    fn diff_entries_identical_is_none() {
+        let mut old = make_entry("cn=alice,dc=example,dc=com");
+        add_attr_value(&mut old, "cn", "alice");
+        let new = old.clone();
+        assert!(diff_entries(&old, &new).is_none());
+    }
+
+    #[test]
+    fn diff_entries_attribute_only_in_new_is_whole_attribute_add() {
+        let old = make_entry("cn=alice,dc=example,dc=com");
+        let mut new = old.clone();
+        add_attr_value(&mut new, "mail", "alice@example.com");
+
+        let rec = diff_entries(&old, &new).unwrap();
+        assert_eq!(rec.dn, "cn=alice,dc=example,dc=com");
+        assert_eq!(rec.mods.len(), 1);
+        assert_eq!(rec.mods[0].op, ModOp::Add);
+        assert_eq!(rec.mods[0].attr, "mail");
+        assert_eq!(rec.mods[0].values, vec![b"alice@example.com".to_vec()]);
+    }
+
+    #[test]
+    fn diff_entries_attribute_only_in_old_is_whole_attribute_delete() {
+        let mut old = make_entry("cn=alice,dc=example,dc=com");
+        add_attr_value(&mut old, "mail", "alice@example.com");
+        let new = make_entry("cn=alice,dc=example,dc=com");
+
+        let rec = diff_entries(&old, &new).unwrap();
+        assert_eq!(rec.mods.len(), 1);
+        assert_eq!(rec.mods[0].op, ModOp::Delete);
+        assert_eq!(rec.mods[0].attr, "mail");
+        assert_eq!(rec.mods[0].values, vec![b"alice@example.com".to_vec()]);
+    }
+
+    #[test]
+    fn diff_entries_one_sided_value_change_prefers_incremental_add() {
+        // Only an addition on one side -- incremental (1 mod) ties with
+        // Replace (1 mod), and the incremental form wins the tie.
+        let mut old = make_entry("cn=alice,dc=example,dc=com");
+        add_attr_value(&mut old, "mail", "a@example.com");
+        let mut new = old.clone();
+        add_attr_value(&mut new, "mail", "b@example.com");
+
+        let rec = diff_entries(&old, &new).unwrap();
+        assert_eq!(rec.mods.len(), 1);
+        assert_eq!(rec.mods[0].op, ModOp::Add);
+        assert_eq!(rec.mods[0].attr, "mail");
+        assert_eq!(rec.mods[0].values, vec![b"b@example.com".to_vec()]);
+    }
+
+    #[test]
+    fn diff_entries_two_sided_value_change_prefers_replace() {
+        // One value removed and a different one added -- incremental would
+        // cost 2 mods (Delete + Add), so the cheaper single Replace wins.
+        let mut old = make_entry("cn=alice,dc=example,dc=com");
+        add_attr_value(&mut old, "mail", "a@example.com");
+        let mut new = make_entry("cn=alice,dc=example,dc=com");
+        add_attr_value(&mut new, "mail", "b@example.com");
+
+        let rec = diff_entries(&old, &new).unwrap();
+        assert_eq!(rec.mods.len(), 1);
+        assert_eq!(rec.mods[0].op, ModOp::Replace);
+        assert_eq!(rec.mods[0].attr, "mail");
+        assert_eq!(rec.mods[0].values, vec![b"b@example.com".to_vec()]);
+    }
+
+    #[test]
+    fn diff_entries_multiple_attributes_sorted_and_deterministic() {
+        let mut old = make_entry("cn=alice,dc=example,dc=com");
+        add_attr_value(&mut old, "sn", "old-sn");
+        add_attr_value(&mut old, "telephoneNumber", "555-0000");
+
+        let mut new = make_entry("cn=alice,dc=example,dc=com");
+        add_attr_value(&mut new, "mail", "alice@example.com");
+        add_attr_value(&mut new, "sn", "new-sn");
+
+        let rec1 = diff_entries(&old, &new).unwrap();
+        let rec2 = diff_entries(&old, &new).unwrap();
+        let attrs1: Vec<&str> = rec1.mods.iter().map(|m| m.attr.as_str()).collect();
+        let attrs2: Vec<&str> = rec2.mods.iter().map(|m| m.attr.as_str()).collect();
+        assert_eq!(attrs1, attrs2, "diffing the same entries twice must be deterministic");
+        assert_eq!(attrs1, vec!["mail", "sn", "telephoneNumber"]);
+    }
+
+    #[test]
+    fn diff_entries_byte_exact_comparison_ignores_no_case_folding() {
+        // Plain byte comparison: "Alice" and "alice" are different values,
+        // not a no-op, since diff_entries doesn't consult a Comparator.
+        let mut old = make_entry("cn=alice,dc=example,dc=com");
+        add_attr_value(&mut old, "cn", "Alice");
+        let mut new = make_entry("cn=alice,dc=example,dc=com");
+        add_attr_value(&mut new, "cn", "alice");
+
+        let rec = diff_entries(&old, &new).unwrap();
+        assert_eq!(rec.mods.len(), 1);
+        assert_eq!(rec.mods[0].op, ModOp::Replace);
+        assert_eq!(rec.mods[0].values, vec![b"alice".to_vec()]);
     }
 }
@@ -0,0 +1,339 @@
+//! RFC 4514 distinguished name parsing and re-encoding.
+//!
+//! `print::explode_dn` only splits a DN string on unescaped commas and
+//! leaves everything else -- including backslash escapes -- untouched,
+//! which is enough for modrdn's "swap the first RDN, keep the rest" string
+//! surgery but not for anything that needs the *decoded* value of an RDN
+//! (to compare, normalize, or re-display it). [`parse_dn`] is a real
+//! structured parser for that: it returns a `Vec<Rdn>`, each a list of
+//! `(attr_type, value)` pairs to support multi-valued RDNs joined by `+`
+//! (`cn=a+sn=b`), with every value's backslash escapes and `#`-prefixed
+//! hex-string form already decoded to raw bytes. [`encode_dn`] is the
+//! inverse, so callers can normalize a DN and get back valid RFC 4514 text
+//! rather than reassembling strings by hand.
+//!
+//! Per-value backslash/hex escaping itself is not done here: it lives in
+//! [`crate::escape`] as `Mode::DnValue`, shared with every other place in
+//! the crate that needs an escaping notion, so this module only has to
+//! worry about splitting a DN into RDNs and attribute/value components.
+
+use std::fmt;
+
+use crate::escape::{self, EscapeErrorKind, Mode};
+
+/// One relative distinguished name: a non-empty list of
+/// `(attribute type, decoded value)` pairs, more than one only for a
+/// multi-valued RDN (`cn=a+sn=b`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rdn {
+    pub attrs: Vec<(String, Vec<u8>)>,
+}
+
+impl Rdn {
+    /// Re-encode as RFC 4514 text (`type=value` pairs joined by `+`).
+    pub fn encode(&self) -> String {
+        self.attrs
+            .iter()
+            .map(|(t, v)| format!("{}={}", t, encode_value(v)))
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+}
+
+impl fmt::Display for Rdn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+/// What went wrong while parsing a DN. Kept distinct from `LdapviError` --
+/// this module has no I/O and is meant to be usable (and testable) on its
+/// own, the way `ldif_lexer` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnErrorKind {
+    /// A value's backslash/hex escaping was malformed; see the wrapped
+    /// [`EscapeErrorKind`] (from `crate::escape`'s `Mode::DnValue`) for
+    /// which specific rule was violated.
+    Escape(EscapeErrorKind),
+    /// An attribute type was empty (`=foo`, `cn=a++sn=b`, a DN starting
+    /// with `=`).
+    EmptyAttributeType,
+    /// No unescaped `=` separated an RDN component's type from its value.
+    MissingEquals,
+    /// A `#`-prefixed value had an odd number of hex digits, or a
+    /// non-hex-digit byte among them.
+    InvalidHexString,
+}
+
+impl DnErrorKind {
+    fn message(self) -> String {
+        match self {
+            DnErrorKind::Escape(kind) => kind.message().to_string(),
+            DnErrorKind::EmptyAttributeType => "empty attribute type".to_string(),
+            DnErrorKind::MissingEquals => "missing '=' between attribute type and value".to_string(),
+            DnErrorKind::InvalidHexString => "invalid '#' hex-string value".to_string(),
+        }
+    }
+}
+
+/// A DN parse failure, with the byte offset (into the original `&str`)
+/// where it was detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DnError {
+    pub position: usize,
+    pub kind: DnErrorKind,
+}
+
+impl fmt::Display for DnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at byte {}: {}", self.position, self.kind.message())
+    }
+}
+
+impl std::error::Error for DnError {}
+
+/// Parse a full DN into its RDNs. An empty string parses as zero RDNs
+/// (the root DN), matching `explode_dn("")`.
+pub fn parse_dn(dn: &str) -> Result<Vec<Rdn>, DnError> {
+    if dn.is_empty() {
+        return Ok(vec![]);
+    }
+    split_unescaped(dn, b",;")
+        .into_iter()
+        .map(|part| parse_rdn(dn, part))
+        .collect()
+}
+
+/// Re-encode a sequence of RDNs as RFC 4514 text.
+pub fn encode_dn(rdns: &[Rdn]) -> String {
+    rdns.iter().map(Rdn::encode).collect::<Vec<_>>().join(",")
+}
+
+fn parse_rdn(whole: &str, rdn_str: &str) -> Result<Rdn, DnError> {
+    let attrs = split_unescaped(rdn_str, b"+")
+        .into_iter()
+        .map(|component| parse_attr_value(whole, component))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Rdn { attrs })
+}
+
+fn parse_attr_value(whole: &str, component: &str) -> Result<(String, Vec<u8>), DnError> {
+    let eq = find_unescaped(component, b'=').ok_or_else(|| DnError {
+        position: offset_of(whole, component),
+        kind: DnErrorKind::MissingEquals,
+    })?;
+    let attr_type = &component[..eq];
+    if attr_type.is_empty() {
+        return Err(DnError {
+            position: offset_of(whole, component),
+            kind: DnErrorKind::EmptyAttributeType,
+        });
+    }
+    let value_str = &component[eq + 1..];
+    let value = if let Some(hex) = value_str.strip_prefix('#') {
+        parse_hex_string(whole, value_str, hex)?
+    } else {
+        escape::unescape(value_str.as_bytes(), Mode::DnValue).map_err(|e| DnError {
+            position: offset_of(whole, value_str) + e.position,
+            kind: DnErrorKind::Escape(e.kind),
+        })?
+    };
+    Ok((attr_type.to_string(), value))
+}
+
+/// Decode a `#`-prefixed hex-string value (`#04024869`) into raw bytes.
+fn parse_hex_string(whole: &str, full: &str, hex: &str) -> Result<Vec<u8>, DnError> {
+    let bytes = hex.as_bytes();
+    if bytes.is_empty() || bytes.len() % 2 != 0 || !bytes.iter().all(u8::is_ascii_hexdigit) {
+        return Err(DnError {
+            position: offset_of(whole, full),
+            kind: DnErrorKind::InvalidHexString,
+        });
+    }
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|pair| {
+            let hi = (pair[0] as char).to_digit(16).unwrap() as u8;
+            let lo = (pair[1] as char).to_digit(16).unwrap() as u8;
+            (hi << 4) | lo
+        })
+        .collect())
+}
+
+/// Render a decoded value back to RFC 4514 text via `crate::escape`'s
+/// `Mode::DnValue`. A hex-string value (raw bytes that aren't valid UTF-8)
+/// comes back from `escape::escape` already `#`-prefixed, so there's
+/// nothing DN-specific left to do here.
+fn encode_value(value: &[u8]) -> String {
+    String::from_utf8(escape::escape(value, Mode::DnValue))
+        .expect("Mode::DnValue always produces valid UTF-8 text")
+}
+
+/// Split `s` on any unescaped byte in `seps`, skipping over `\X` and `\XX`
+/// escapes so a separator inside one isn't mistaken for a real boundary.
+fn split_unescaped<'a>(s: &'a str, seps: &[u8]) -> Vec<&'a str> {
+    let bytes = s.as_bytes();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            i += escape_skip_len(bytes, i);
+        } else if seps.contains(&bytes[i]) {
+            parts.push(&s[start..i]);
+            i += 1;
+            start = i;
+        } else {
+            i += 1;
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Like `split_unescaped`, but returns only the offset of the first
+/// unescaped occurrence of `sep`.
+fn find_unescaped(s: &str, sep: u8) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            i += escape_skip_len(bytes, i);
+        } else if bytes[i] == sep {
+            return Some(i);
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// How many bytes a `\`-escape starting at `i` occupies, for the purposes
+/// of *finding boundaries* only -- not validation, which `crate::escape`
+/// does separately. A dangling `\` at EOF is treated as one byte so the
+/// caller's own unescape pass is the one that reports the real error.
+fn escape_skip_len(bytes: &[u8], i: usize) -> usize {
+    if i + 2 < bytes.len() && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit() {
+        3
+    } else if i + 1 < bytes.len() {
+        2
+    } else {
+        1
+    }
+}
+
+fn offset_of(whole: &str, part: &str) -> usize {
+    part.as_ptr() as usize - whole.as_ptr() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rdn(pairs: &[(&str, &[u8])]) -> Rdn {
+        Rdn {
+            attrs: pairs.iter().map(|(t, v)| (t.to_string(), v.to_vec())).collect(),
+        }
+    }
+
+    #[test]
+    fn empty_dn() {
+        assert_eq!(parse_dn("").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn simple_multi_rdn() {
+        let rdns = parse_dn("cn=foo,dc=example,dc=com").unwrap();
+        assert_eq!(
+            rdns,
+            vec![
+                rdn(&[("cn", b"foo")]),
+                rdn(&[("dc", b"example")]),
+                rdn(&[("dc", b"com")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_comma_stays_in_one_rdn() {
+        let rdns = parse_dn("cn=foo\\,bar,dc=com").unwrap();
+        assert_eq!(rdns, vec![rdn(&[("cn", b"foo,bar")]), rdn(&[("dc", b"com")])]);
+    }
+
+    #[test]
+    fn multi_valued_rdn() {
+        let rdns = parse_dn("cn=a+sn=b,dc=com").unwrap();
+        assert_eq!(
+            rdns,
+            vec![rdn(&[("cn", b"a"), ("sn", b"b")]), rdn(&[("dc", b"com")])]
+        );
+    }
+
+    #[test]
+    fn hex_string_value() {
+        let rdns = parse_dn("cn=#04024869").unwrap();
+        assert_eq!(rdns, vec![rdn(&[("cn", &[0x04, 0x02, 0x48, 0x69])])]);
+    }
+
+    #[test]
+    fn hex_byte_escape() {
+        let rdns = parse_dn("cn=Lu\\c4\\8di\\c4\\87").unwrap();
+        assert_eq!(rdns[0].attrs[0].1, "Luči\u{107}".as_bytes());
+    }
+
+    #[test]
+    fn leading_and_trailing_space_escape() {
+        let rdns = parse_dn("cn=\\ foo\\ ").unwrap();
+        assert_eq!(rdns, vec![rdn(&[("cn", b" foo ")])]);
+    }
+
+    #[test]
+    fn missing_equals_is_an_error() {
+        let err = parse_dn("notanrdn").unwrap_err();
+        assert_eq!(err.kind, DnErrorKind::MissingEquals);
+    }
+
+    #[test]
+    fn empty_attribute_type_is_an_error() {
+        let err = parse_dn("=foo").unwrap_err();
+        assert_eq!(err.kind, DnErrorKind::EmptyAttributeType);
+    }
+
+    #[test]
+    fn odd_hex_string_is_an_error() {
+        let err = parse_dn("cn=#abc").unwrap_err();
+        assert_eq!(err.kind, DnErrorKind::InvalidHexString);
+    }
+
+    #[test]
+    fn round_trip_through_encode() {
+        let rdns = parse_dn("cn=foo\\,bar+sn=baz,dc=example,dc=com").unwrap();
+        assert_eq!(encode_dn(&rdns), "cn=foo\\,bar+sn=baz,dc=example,dc=com");
+    }
+
+    #[test]
+    fn encode_escapes_leading_space_and_hash() {
+        let rdns = vec![rdn(&[("cn", b" #hi")])];
+        assert_eq!(encode_dn(&rdns), "cn=\\ #hi");
+        let rdns = vec![rdn(&[("cn", b"#hi")])];
+        assert_eq!(encode_dn(&rdns), "cn=\\#hi");
+    }
+
+    #[test]
+    fn encode_falls_back_to_hex_string_for_non_utf8_value() {
+        let rdns = vec![rdn(&[("cn", &[0xff, 0x00])])];
+        assert_eq!(encode_dn(&rdns), "cn=#ff00");
+    }
+
+    #[test]
+    fn multi_valued_rdn_keeps_all_components_in_order() {
+        let rdns = parse_dn("cn=foo+sn=bar+ou=baz,dc=com").unwrap();
+        assert_eq!(
+            rdns,
+            vec![
+                rdn(&[("cn", b"foo"), ("sn", b"bar"), ("ou", b"baz")]),
+                rdn(&[("dc", b"com")]),
+            ]
+        );
+    }
+}
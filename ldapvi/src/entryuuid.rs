@@ -0,0 +1,62 @@
+//! RFC 4122 version-4 (random) UUID generation, used to synthesize an
+//! `entryUUID` operational attribute (RFC 4530) for a freshly added entry
+//! when `--generate-entryuuid` is set and the server doesn't already
+//! advertise its own entryUUID plugin -- see
+//! [`crate::ldap::server_has_entryuuid_feature`] and
+//! [`crate::diff::Comparator::with_entryuuid_generation`].
+
+/// Generate a random (version 4, variant 1) UUID, formatted as the
+/// lowercase 8-4-4-4-12 hex string the `entryUUID` syntax expects.
+pub fn generate() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)?;
+    // RFC 4122 §4.4: stamp the version (4) and variant (RFC 4122) bits over
+    // the random ones.
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    Ok(format!(
+        "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+        bytes[0],
+        bytes[1],
+        bytes[2],
+        bytes[3],
+        bytes[4],
+        bytes[5],
+        bytes[6],
+        bytes[7],
+        bytes[8],
+        bytes[9],
+        bytes[10],
+        bytes[11],
+        bytes[12],
+        bytes[13],
+        bytes[14],
+        bytes[15],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn has_the_8_4_4_4_12_shape() {
+        let id = generate().unwrap();
+        let lengths: Vec<usize> = id.split('-').map(|p| p.len()).collect();
+        assert_eq!(lengths, vec![8, 4, 4, 4, 12]);
+        assert!(id.chars().all(|c| c == '-' || (c.is_ascii_hexdigit() && !c.is_ascii_uppercase())));
+    }
+
+    #[test]
+    fn sets_version_and_variant_bits() {
+        let id = generate().unwrap();
+        let groups: Vec<&str> = id.split('-').collect();
+        assert_eq!(&groups[2][0..1], "4");
+        assert!(matches!(&groups[3][0..1], "8" | "9" | "a" | "b"));
+    }
+
+    #[test]
+    fn generates_distinct_values() {
+        assert_ne!(generate().unwrap(), generate().unwrap());
+    }
+}
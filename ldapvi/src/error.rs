@@ -8,8 +8,12 @@ pub enum LdapviError {
     #[error("I/O error: {0}")]
     Io(#[from] io::Error),
 
-    #[error("parse error at byte {position}: {message}")]
-    Parse { position: u64, message: String },
+    #[error("parse error at line {line}, byte {position}: {message}")]
+    Parse {
+        position: u64,
+        line: u64,
+        message: String,
+    },
 
     #[error("LDAP error: {0}")]
     Ldap(String),
@@ -0,0 +1,330 @@
+//! A single byte-string escape engine shared by the two independent,
+//! previously ad-hoc notions of "escaping" in the crate: [`dn`]'s RFC 4514
+//! value decoding and the LDIF writer's `safe_string_p` check (whether a
+//! value can be written plain or must fall back to Base64, per RFC 2849).
+//!
+//! Each [`Mode`] gets its own rule set behind the same three entry points
+//! -- [`unescape`], [`escape`] and [`is_safe`] -- the way
+//! `rustc_lexer::unescape` parameterizes one engine over `Mode::{Str,
+//! ByteStr, ...}` instead of duplicating near-identical scanning loops per
+//! literal kind. Errors are reported per-position via [`EscapeError`]
+//! rather than a bare string, so callers (and tests) can match on *why* a
+//! value was rejected.
+
+use std::fmt;
+
+/// Which escaping rules to apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// RFC 4514 DN value escaping: `\,` `\+` `\"` `\;` `\<` `\>` `\#` `\\`
+    /// `\ ` char escapes plus `\XX` hex-byte escapes.
+    DnValue,
+    /// RFC 2849 LDIF value "safety": LDIF has no backslash-escape syntax
+    /// of its own -- an unsafe value is instead written whole as Base64 --
+    /// so [`unescape`] and [`escape`] are the identity here; only
+    /// [`is_safe`] does real work, centralizing the SAFE-STRING character
+    /// classification `print::safe_string_p` used to duplicate.
+    LdifValue,
+    /// RFC 4515 search filter value escaping: only `\XX` hex-byte escapes
+    /// exist (no bare-char escapes like [`Mode::DnValue`]'s `\,`), and the
+    /// bytes that must be escaped to keep a value from being mistaken for
+    /// filter syntax are `(` `)` `*` `\` and NUL.
+    FilterValue,
+}
+
+/// Why [`unescape`] rejected a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapeErrorKind {
+    /// A `\` was the last byte, with nothing to escape.
+    LoneBackslash,
+    /// `\X` where `X` is not a hex digit and not a recognized escaped
+    /// character.
+    InvalidHexDigit,
+    /// `\X` at the end of the input, one hex digit short of `\XX`.
+    TruncatedHexEscape,
+}
+
+impl EscapeErrorKind {
+    pub(crate) fn message(self) -> &'static str {
+        match self {
+            EscapeErrorKind::LoneBackslash => "'\\' at end of value with nothing to escape",
+            EscapeErrorKind::InvalidHexDigit => "'\\' not followed by a recognized escape or hex digit",
+            EscapeErrorKind::TruncatedHexEscape => "truncated '\\XX' hex-byte escape",
+        }
+    }
+}
+
+/// An [`unescape`] failure at a given byte offset into the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscapeError {
+    pub position: usize,
+    pub kind: EscapeErrorKind,
+}
+
+impl fmt::Display for EscapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at byte {}: {}", self.position, self.kind.message())
+    }
+}
+
+impl std::error::Error for EscapeError {}
+
+/// Decode `input`'s escapes per `mode`.
+pub fn unescape(input: &[u8], mode: Mode) -> Result<Vec<u8>, EscapeError> {
+    match mode {
+        Mode::DnValue => unescape_dn_value(input),
+        Mode::LdifValue => Ok(input.to_vec()),
+        Mode::FilterValue => unescape_filter_value(input),
+    }
+}
+
+/// Encode `input` per `mode`, producing text that [`unescape`] with the
+/// same mode will decode back to `input`. The one exception is a
+/// `DnValue` input that is not valid UTF-8: it comes back as a `#`-prefixed
+/// hex string, which [`unescape`] leaves as-is and only
+/// [`crate::dn::parse_dn`] decodes.
+pub fn escape(input: &[u8], mode: Mode) -> Vec<u8> {
+    match mode {
+        Mode::DnValue => escape_dn_value(input),
+        Mode::LdifValue => input.to_vec(),
+        Mode::FilterValue => escape_filter_value(input),
+    }
+}
+
+/// Can `input` be carried as-is under `mode` without needing [`escape`] (or,
+/// for `LdifValue`, without needing the caller's own Base64 fallback)?
+pub fn is_safe(input: &[u8], mode: Mode) -> bool {
+    match mode {
+        Mode::DnValue => escape_dn_value(input) == input,
+        Mode::LdifValue => is_safe_ldif_value(input),
+        Mode::FilterValue => escape_filter_value(input) == input,
+    }
+}
+
+/// RFC 2849 SAFE-STRING check: empty is trivially safe; otherwise the first
+/// byte must not be space, `:` or `<`, and no byte may be NUL, CR, LF or
+/// outside the ASCII range.
+fn is_safe_ldif_value(data: &[u8]) -> bool {
+    if data.is_empty() {
+        return true;
+    }
+    match data[0] {
+        b' ' | b':' | b'<' => return false,
+        _ => {}
+    }
+    data.iter().all(|&c| c != 0 && c != b'\r' && c != b'\n' && c < 0x80)
+}
+
+fn unescape_dn_value(input: &[u8]) -> Result<Vec<u8>, EscapeError> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] != b'\\' {
+            out.push(input[i]);
+            i += 1;
+            continue;
+        }
+        let Some(&c) = input.get(i + 1) else {
+            return Err(EscapeError {
+                position: i,
+                kind: EscapeErrorKind::LoneBackslash,
+            });
+        };
+        if c.is_ascii_hexdigit() {
+            let Some(&lo) = input.get(i + 2) else {
+                return Err(EscapeError {
+                    position: i,
+                    kind: EscapeErrorKind::TruncatedHexEscape,
+                });
+            };
+            if !lo.is_ascii_hexdigit() {
+                return Err(EscapeError {
+                    position: i,
+                    kind: EscapeErrorKind::InvalidHexDigit,
+                });
+            }
+            let hi = (c as char).to_digit(16).unwrap() as u8;
+            let lo = (lo as char).to_digit(16).unwrap() as u8;
+            out.push((hi << 4) | lo);
+            i += 3;
+        } else if matches!(c, b',' | b'+' | b'"' | b'\\' | b'<' | b'>' | b';' | b'#' | b' ' | b'=') {
+            out.push(c);
+            i += 2;
+        } else {
+            return Err(EscapeError {
+                position: i,
+                kind: EscapeErrorKind::InvalidHexDigit,
+            });
+        }
+    }
+    Ok(out)
+}
+
+fn unescape_filter_value(input: &[u8]) -> Result<Vec<u8>, EscapeError> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] != b'\\' {
+            out.push(input[i]);
+            i += 1;
+            continue;
+        }
+        let Some(&hi) = input.get(i + 1) else {
+            return Err(EscapeError {
+                position: i,
+                kind: EscapeErrorKind::LoneBackslash,
+            });
+        };
+        if !hi.is_ascii_hexdigit() {
+            return Err(EscapeError {
+                position: i,
+                kind: EscapeErrorKind::InvalidHexDigit,
+            });
+        }
+        let Some(&lo) = input.get(i + 2) else {
+            return Err(EscapeError {
+                position: i,
+                kind: EscapeErrorKind::TruncatedHexEscape,
+            });
+        };
+        if !lo.is_ascii_hexdigit() {
+            return Err(EscapeError {
+                position: i,
+                kind: EscapeErrorKind::InvalidHexDigit,
+            });
+        }
+        let hi = (hi as char).to_digit(16).unwrap() as u8;
+        let lo = (lo as char).to_digit(16).unwrap() as u8;
+        out.push((hi << 4) | lo);
+        i += 3;
+    }
+    Ok(out)
+}
+
+fn escape_filter_value(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    for &b in input {
+        match b {
+            0x00 | b'(' | b')' | b'*' | b'\\' => {
+                out.extend_from_slice(format!("\\{:02x}", b).as_bytes());
+            }
+            _ => out.push(b),
+        }
+    }
+    out
+}
+
+fn escape_dn_value(input: &[u8]) -> Vec<u8> {
+    match std::str::from_utf8(input) {
+        Ok(s) => escape_dn_value_str(s).into_bytes(),
+        Err(_) => escape_dn_value_hex(input).into_bytes(),
+    }
+}
+
+fn escape_dn_value_str(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let last = chars.len().saturating_sub(1);
+    let mut out = String::with_capacity(s.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let needs_escape = matches!(c, ',' | '+' | '"' | '\\' | '<' | '>' | ';')
+            || (c == '#' && i == 0)
+            || (c == ' ' && (i == 0 || i == last));
+        if needs_escape {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn escape_dn_value_hex(input: &[u8]) -> String {
+    let mut out = String::with_capacity(1 + input.len() * 2);
+    out.push('#');
+    for b in input {
+        out.push_str(&format!("{:02x}", b));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dn_value_round_trip() {
+        let escaped = escape(b"foo,bar", Mode::DnValue);
+        assert_eq!(escaped, b"foo\\,bar");
+        assert_eq!(unescape(&escaped, Mode::DnValue).unwrap(), b"foo,bar");
+    }
+
+    #[test]
+    fn dn_value_hex_escape() {
+        assert_eq!(unescape(b"\\c4\\8d", Mode::DnValue).unwrap(), vec![0xc4, 0x8d]);
+    }
+
+    #[test]
+    fn dn_value_lone_backslash_is_an_error() {
+        let err = unescape(b"foo\\", Mode::DnValue).unwrap_err();
+        assert_eq!(err.kind, EscapeErrorKind::LoneBackslash);
+        assert_eq!(err.position, 3);
+    }
+
+    #[test]
+    fn dn_value_invalid_hex_digit_is_an_error() {
+        let err = unescape(b"\\zz", Mode::DnValue).unwrap_err();
+        assert_eq!(err.kind, EscapeErrorKind::InvalidHexDigit);
+    }
+
+    #[test]
+    fn dn_value_truncated_hex_escape_is_an_error() {
+        let err = unescape(b"\\4", Mode::DnValue).unwrap_err();
+        assert_eq!(err.kind, EscapeErrorKind::TruncatedHexEscape);
+    }
+
+    #[test]
+    fn ldif_value_unescape_and_escape_are_identity() {
+        assert_eq!(unescape(b"hello", Mode::LdifValue).unwrap(), b"hello");
+        assert_eq!(escape(b"hello", Mode::LdifValue), b"hello");
+    }
+
+    #[test]
+    fn ldif_value_safety_matches_safe_string_rules() {
+        assert!(is_safe(b"", Mode::LdifValue));
+        assert!(is_safe(b"plain value", Mode::LdifValue));
+        assert!(!is_safe(b" leading space", Mode::LdifValue));
+        assert!(!is_safe(b"has\x00nul", Mode::LdifValue));
+        assert!(!is_safe("non-ascii \u{e9}".as_bytes(), Mode::LdifValue));
+    }
+
+    #[test]
+    fn filter_value_round_trip() {
+        let escaped = escape(b"a(b)c*d\\e", Mode::FilterValue);
+        assert_eq!(escaped, b"a\\28b\\29c\\2ad\\5ce");
+        assert_eq!(unescape(&escaped, Mode::FilterValue).unwrap(), b"a(b)c*d\\e");
+    }
+
+    #[test]
+    fn filter_value_escapes_nul() {
+        assert_eq!(escape(b"a\x00b", Mode::FilterValue), b"a\\00b");
+    }
+
+    #[test]
+    fn filter_value_has_no_bare_char_escapes() {
+        // Unlike DnValue, a backslash followed by a non-hex char is always
+        // invalid -- RFC 4515 only defines `\XX`.
+        let err = unescape(b"\\,", Mode::FilterValue).unwrap_err();
+        assert_eq!(err.kind, EscapeErrorKind::InvalidHexDigit);
+    }
+
+    #[test]
+    fn filter_value_truncated_hex_escape_is_an_error() {
+        let err = unescape(b"\\4", Mode::FilterValue).unwrap_err();
+        assert_eq!(err.kind, EscapeErrorKind::TruncatedHexEscape);
+    }
+
+    #[test]
+    fn filter_value_is_safe_only_without_special_bytes() {
+        assert!(is_safe(b"plain", Mode::FilterValue));
+        assert!(!is_safe(b"a*b", Mode::FilterValue));
+    }
+}
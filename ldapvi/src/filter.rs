@@ -0,0 +1,395 @@
+//! A small boolean-combinator filter for selecting which entries a
+//! session works with, applied client-side on top of whatever
+//! `--base`/the server-side LDAP filter already narrowed down. Leaf
+//! predicates test one property of an already-fetched [`Entry`];
+//! `And`/`Or`/`Not` compose them into a tree that's assembled once per
+//! run and then applied to each entry as it streams in.
+
+use crate::data::Entry;
+use crate::schema::matching_rule_normalize;
+
+/// Something that can decide whether an entry should be kept.
+pub trait Filter {
+    fn matches(&self, entry: &Entry) -> bool;
+}
+
+/// True iff every child filter matches.
+pub struct And(pub Vec<Box<dyn Filter>>);
+
+impl Filter for And {
+    fn matches(&self, entry: &Entry) -> bool {
+        self.0.iter().all(|f| f.matches(entry))
+    }
+}
+
+/// True iff any child filter matches.
+pub struct Or(pub Vec<Box<dyn Filter>>);
+
+impl Filter for Or {
+    fn matches(&self, entry: &Entry) -> bool {
+        self.0.iter().any(|f| f.matches(entry))
+    }
+}
+
+/// True iff the wrapped filter does not match.
+pub struct Not(pub Box<dyn Filter>);
+
+impl Filter for Not {
+    fn matches(&self, entry: &Entry) -> bool {
+        !self.0.matches(entry)
+    }
+}
+
+/// True iff `attribute` has at least one value.
+pub struct AttributePresent {
+    pub attribute: String,
+}
+
+impl Filter for AttributePresent {
+    fn matches(&self, entry: &Entry) -> bool {
+        entry
+            .get_attribute(&self.attribute)
+            .is_some_and(|a| !a.values.is_empty())
+    }
+}
+
+/// True iff `attribute` has `value` among its values. Values are compared
+/// with `caseIgnoreMatch` semantics -- good enough for a client-side
+/// selection filter that isn't claiming to honor each attribute's real
+/// schema-defined EQUALITY rule the way `crate::diff::Comparator` does.
+pub struct AttributeEquals {
+    pub attribute: String,
+    pub value: Vec<u8>,
+}
+
+impl Filter for AttributeEquals {
+    fn matches(&self, entry: &Entry) -> bool {
+        let wanted = matching_rule_normalize("caseIgnoreMatch", &self.value);
+        entry.get_attribute(&self.attribute).is_some_and(|a| {
+            a.values
+                .iter()
+                .any(|v| matching_rule_normalize("caseIgnoreMatch", v) == wanted)
+        })
+    }
+}
+
+/// True iff the entry's `objectClass` attribute includes `class`.
+pub struct ObjectClassIs {
+    pub class: String,
+}
+
+impl Filter for ObjectClassIs {
+    fn matches(&self, entry: &Entry) -> bool {
+        AttributeEquals {
+            attribute: "objectClass".to_string(),
+            value: self.class.as_bytes().to_vec(),
+        }
+        .matches(entry)
+    }
+}
+
+/// True iff the entry's DN is `suffix` or a descendant of it. Compares
+/// case-insensitively on the raw DN string rather than parsing RDNs, so
+/// it doesn't handle escaped commas inside a value; fine for the common
+/// "everything under this base" case this predicate exists for.
+pub struct DnSuffix {
+    pub suffix: String,
+}
+
+impl Filter for DnSuffix {
+    fn matches(&self, entry: &Entry) -> bool {
+        let dn = entry.dn.to_lowercase();
+        let suffix = self.suffix.to_lowercase();
+        dn == suffix || dn.ends_with(&format!(",{}", suffix))
+    }
+}
+
+/// Parse the small prefix-notation DSL used by `--select`/`select:`, e.g.
+/// `(and (present mail) (not (under ou=archived,dc=acme,dc=com)))`.
+///
+/// Grammar (parens and whitespace are the only syntax; everything else is
+/// a bare word):
+///   expr   := "(" "and" expr+ ")" | "(" "or" expr+ ")" | "(" "not" expr ")"
+///           | "(" "present" WORD ")" | "(" "eq" WORD WORD ")"
+///           | "(" "class" WORD ")" | "(" "under" WORD ")"
+pub fn parse(input: &str) -> Result<Box<dyn Filter>, String> {
+    let tokens = tokenize(input);
+    let mut pos = 0;
+    let filter = parse_expr(&tokens, &mut pos)?;
+    if pos != tokens.len() {
+        return Err(format!(
+            "Unexpected trailing input in select expression starting at '{}'.",
+            tokens[pos]
+        ));
+    }
+    Ok(filter)
+}
+
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    for c in input.chars() {
+        match c {
+            '(' | ')' => {
+                if !word.is_empty() {
+                    tokens.push(std::mem::take(&mut word));
+                }
+                tokens.push(c.to_string());
+            }
+            c if c.is_whitespace() => {
+                if !word.is_empty() {
+                    tokens.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+fn next_token<'a>(tokens: &'a [String], pos: &mut usize) -> Result<&'a str, String> {
+    let tok = tokens
+        .get(*pos)
+        .ok_or("Unexpected end of select expression.")?;
+    *pos += 1;
+    Ok(tok.as_str())
+}
+
+fn expect(tokens: &[String], pos: &mut usize, expected: &str) -> Result<(), String> {
+    let tok = next_token(tokens, pos)?;
+    if tok != expected {
+        return Err(format!(
+            "Expected '{}' in select expression but found '{}'.",
+            expected, tok
+        ));
+    }
+    Ok(())
+}
+
+fn parse_expr(tokens: &[String], pos: &mut usize) -> Result<Box<dyn Filter>, String> {
+    expect(tokens, pos, "(")?;
+    let op = next_token(tokens, pos)?.to_string();
+    let filter: Box<dyn Filter> = match op.as_str() {
+        "and" => {
+            let mut children = Vec::new();
+            while tokens.get(*pos).map(String::as_str) != Some(")") {
+                children.push(parse_expr(tokens, pos)?);
+            }
+            if children.is_empty() {
+                return Err("'and' requires at least one child expression.".to_string());
+            }
+            Box::new(And(children))
+        }
+        "or" => {
+            let mut children = Vec::new();
+            while tokens.get(*pos).map(String::as_str) != Some(")") {
+                children.push(parse_expr(tokens, pos)?);
+            }
+            if children.is_empty() {
+                return Err("'or' requires at least one child expression.".to_string());
+            }
+            Box::new(Or(children))
+        }
+        "not" => Box::new(Not(parse_expr(tokens, pos)?)),
+        "present" => Box::new(AttributePresent {
+            attribute: next_token(tokens, pos)?.to_string(),
+        }),
+        "eq" => {
+            let attribute = next_token(tokens, pos)?.to_string();
+            let value = next_token(tokens, pos)?.as_bytes().to_vec();
+            Box::new(AttributeEquals { attribute, value })
+        }
+        "class" => Box::new(ObjectClassIs {
+            class: next_token(tokens, pos)?.to_string(),
+        }),
+        "under" => Box::new(DnSuffix {
+            suffix: next_token(tokens, pos)?.to_string(),
+        }),
+        other => return Err(format!("Unknown select predicate '{}'.", other)),
+    };
+    expect(tokens, pos, ")")?;
+    Ok(filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_with(dn: &str, attrs: &[(&str, &[&str])]) -> Entry {
+        let mut entry = Entry::new(dn.to_string());
+        for (ad, values) in attrs {
+            let attr = entry.find_attribute(ad, true).unwrap();
+            for v in *values {
+                attr.append_value(v.as_bytes());
+            }
+        }
+        entry
+    }
+
+    #[test]
+    fn attribute_present_true_when_values_exist() {
+        let entry = entry_with("cn=test,dc=example,dc=com", &[("mail", &["a@b.com"])]);
+        assert!(AttributePresent {
+            attribute: "mail".to_string(),
+        }
+        .matches(&entry));
+    }
+
+    #[test]
+    fn attribute_present_false_when_missing() {
+        let entry = entry_with("cn=test,dc=example,dc=com", &[]);
+        assert!(!AttributePresent {
+            attribute: "mail".to_string(),
+        }
+        .matches(&entry));
+    }
+
+    #[test]
+    fn attribute_equals_is_case_insensitive() {
+        let entry = entry_with("cn=test,dc=example,dc=com", &[("cn", &["Test User"])]);
+        assert!(AttributeEquals {
+            attribute: "cn".to_string(),
+            value: b"test user".to_vec(),
+        }
+        .matches(&entry));
+    }
+
+    #[test]
+    fn object_class_is_checks_objectclass_attribute() {
+        let entry = entry_with(
+            "cn=test,dc=example,dc=com",
+            &[("objectClass", &["top", "person"])],
+        );
+        assert!(ObjectClassIs {
+            class: "person".to_string(),
+        }
+        .matches(&entry));
+        assert!(!ObjectClassIs {
+            class: "organization".to_string(),
+        }
+        .matches(&entry));
+    }
+
+    #[test]
+    fn dn_suffix_matches_descendants_and_self() {
+        let entry = entry_with("cn=test,ou=people,dc=example,dc=com", &[]);
+        assert!(DnSuffix {
+            suffix: "dc=example,dc=com".to_string(),
+        }
+        .matches(&entry));
+        assert!(DnSuffix {
+            suffix: "cn=test,ou=people,dc=example,dc=com".to_string(),
+        }
+        .matches(&entry));
+        assert!(!DnSuffix {
+            suffix: "dc=other,dc=com".to_string(),
+        }
+        .matches(&entry));
+    }
+
+    #[test]
+    fn and_requires_every_child() {
+        let entry = entry_with("cn=test,dc=example,dc=com", &[("mail", &["a@b.com"])]);
+        let filter = And(vec![
+            Box::new(AttributePresent {
+                attribute: "mail".to_string(),
+            }),
+            Box::new(Not(Box::new(DnSuffix {
+                suffix: "ou=archived,dc=example,dc=com".to_string(),
+            }))),
+        ]);
+        assert!(filter.matches(&entry));
+    }
+
+    #[test]
+    fn and_fails_if_any_child_fails() {
+        let entry = entry_with(
+            "cn=test,ou=archived,dc=example,dc=com",
+            &[("mail", &["a@b.com"])],
+        );
+        let filter = And(vec![
+            Box::new(AttributePresent {
+                attribute: "mail".to_string(),
+            }),
+            Box::new(Not(Box::new(DnSuffix {
+                suffix: "ou=archived,dc=example,dc=com".to_string(),
+            }))),
+        ]);
+        assert!(!filter.matches(&entry));
+    }
+
+    #[test]
+    fn or_matches_if_any_child_matches() {
+        let entry = entry_with("cn=test,dc=example,dc=com", &[]);
+        let filter = Or(vec![
+            Box::new(AttributePresent {
+                attribute: "mail".to_string(),
+            }),
+            Box::new(DnSuffix {
+                suffix: "dc=example,dc=com".to_string(),
+            }),
+        ]);
+        assert!(filter.matches(&entry));
+    }
+
+    #[test]
+    fn parse_present_predicate() {
+        let entry = entry_with("cn=test,dc=example,dc=com", &[("mail", &["a@b.com"])]);
+        let filter = parse("(present mail)").unwrap();
+        assert!(filter.matches(&entry));
+    }
+
+    #[test]
+    fn parse_eq_predicate() {
+        let entry = entry_with("cn=test,dc=example,dc=com", &[("cn", &["Test"])]);
+        let filter = parse("(eq cn test)").unwrap();
+        assert!(filter.matches(&entry));
+        let filter = parse("(eq cn other)").unwrap();
+        assert!(!filter.matches(&entry));
+    }
+
+    #[test]
+    fn parse_class_predicate() {
+        let entry = entry_with("cn=test,dc=example,dc=com", &[("objectClass", &["person"])]);
+        let filter = parse("(class person)").unwrap();
+        assert!(filter.matches(&entry));
+    }
+
+    #[test]
+    fn parse_under_predicate() {
+        let entry = entry_with("cn=test,ou=people,dc=example,dc=com", &[]);
+        let filter = parse("(under dc=example,dc=com)").unwrap();
+        assert!(filter.matches(&entry));
+    }
+
+    #[test]
+    fn parse_nested_and_not() {
+        let entry = entry_with("cn=test,dc=example,dc=com", &[("mail", &["a@b.com"])]);
+        let filter = parse("(and (present mail) (not (under ou=archived,dc=example,dc=com)))")
+            .unwrap();
+        assert!(filter.matches(&entry));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_predicate() {
+        assert!(parse("(bogus foo)").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_missing_close_paren() {
+        assert!(parse("(present mail").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_empty_and() {
+        assert!(parse("(and)").is_err());
+    }
+
+    #[test]
+    fn parse_rejects_trailing_input() {
+        assert!(parse("(present mail) (present cn)").is_err());
+    }
+}
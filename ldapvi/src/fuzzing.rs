@@ -0,0 +1,135 @@
+//! Fuzzing entry points for `LdapviParser` and the parse/print round trip.
+//!
+//! Compiled only when the `fuzzing` feature is enabled; the `ldapvi-fuzz`
+//! crate under `fuzz/` links against it from its `fuzz_targets`. Kept out of
+//! normal builds so this module never affects the release binary.
+#![cfg(feature = "fuzzing")]
+
+use std::io::Cursor;
+
+use crate::data::Entry;
+use crate::ldif_lexer;
+use crate::parse::LdapviParser;
+use crate::parseldif::LdifParser;
+use crate::print::{self, BinaryMode};
+
+/// Feed `data` through `peek_entry`/`skip_entry` and check that
+/// the parser never panics, never loops without consuming input, and leaves
+/// `stream_position` no smaller than where it started.
+///
+/// Used directly by the `parse_entry` fuzz target.
+pub fn fuzz_parse_entry(data: &[u8]) {
+    let mut parser = LdapviParser::new(Cursor::new(data));
+    loop {
+        let before = match parser.stream_position() {
+            Ok(pos) => pos,
+            Err(_) => return,
+        };
+
+        let key = match parser.peek_entry(None) {
+            Ok(Some((key, _))) => key,
+            Ok(None) => return,
+            Err(_) => return,
+        };
+        let _ = key;
+
+        match parser.skip_entry(None) {
+            Ok(Some(_)) => {}
+            Ok(None) => return,
+            Err(_) => return,
+        }
+
+        let after = match parser.stream_position() {
+            Ok(pos) => pos,
+            Err(_) => return,
+        };
+        assert!(
+            after > before,
+            "skip_entry did not advance stream_position ({} -> {})",
+            before,
+            after
+        );
+    }
+}
+
+/// Round-trip property: every entry `LdapviParser::read_entry` accepts must,
+/// when re-printed with `print::print_ldapvi_entry` and reparsed, produce an
+/// equal `Entry`. A mismatch here means the parse/print pair that `do_edit`
+/// relies on silently corrupts data.
+///
+/// Used directly by the `roundtrip_ldapvi` fuzz target.
+pub fn fuzz_roundtrip_ldapvi(data: &[u8]) {
+    let mut parser = LdapviParser::new(Cursor::new(data));
+    let (key, entry, _) = match parser.read_entry(None) {
+        Ok(Some(r)) => r,
+        _ => return,
+    };
+
+    let mut printed = Vec::new();
+    if print::print_ldapvi_entry(&mut printed, &entry, Some(&key), BinaryMode::Utf8).is_err() {
+        return;
+    }
+
+    let mut reparser = LdapviParser::new(Cursor::new(&printed[..]));
+    let (_, reparsed, _) = reparser
+        .read_entry(None)
+        .expect("re-parsing printed output must not fail")
+        .expect("re-printed entry must still contain a record");
+
+    assert_entries_equal(&entry, &reparsed);
+}
+
+/// Same property as [`fuzz_roundtrip_ldapvi`], but for the LDIF printer/parser
+/// pair.
+///
+/// Used directly by the `roundtrip_ldif` fuzz target.
+pub fn fuzz_roundtrip_ldif(data: &[u8]) {
+    let mut parser = LdifParser::new(Cursor::new(data));
+    let (key, entry, _) = match parser.read_entry(None) {
+        Ok(Some(r)) => r,
+        _ => return,
+    };
+
+    let mut printed = Vec::new();
+    if print::print_ldif_entry(&mut printed, &entry, Some(&key), print::DEFAULT_LDIF_WIDTH).is_err() {
+        return;
+    }
+
+    let mut reparser = LdifParser::new(Cursor::new(&printed[..]));
+    let (_, reparsed, _) = reparser
+        .read_entry(None)
+        .expect("re-parsing printed LDIF must not fail")
+        .expect("re-printed entry must still contain a record");
+
+    assert_entries_equal(&entry, &reparsed);
+}
+
+/// Feed `data` through the standalone [`ldif_lexer::tokenize`] and check
+/// that it never panics and, on success, that every token's range is
+/// non-decreasing and stays inside `data` -- the property the streaming
+/// parser gets for free from `CharReader` but this pure, range-based
+/// tokenizer has to uphold by construction.
+///
+/// Used directly by the `ldif_lexer` fuzz target.
+pub fn fuzz_ldif_lexer(data: &[u8]) {
+    let tokens = match ldif_lexer::tokenize(data) {
+        Ok(tokens) => tokens,
+        Err(_) => return,
+    };
+    let mut prev_end = 0usize;
+    for token in &tokens {
+        assert!(token.range.start <= token.range.end);
+        assert!(token.range.end <= data.len());
+        assert!(token.range.start >= prev_end || prev_end == 0);
+        prev_end = token.range.end;
+        let _ = token.unfolded(data);
+    }
+}
+
+fn assert_entries_equal(a: &Entry, b: &Entry) {
+    assert_eq!(a.dn, b.dn, "DN changed across round trip");
+    assert_eq!(
+        a.attributes, b.attributes,
+        "attributes changed across round trip"
+    );
+}
@@ -0,0 +1,294 @@
+//! Canonical content hash for an `Entry`, so a caller diffing large
+//! directory dumps can cheaply detect whether an entry actually changed
+//! instead of comparing every attribute, and so a commit step can detect
+//! that the server entry changed underneath an in-progress edit.
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+use crate::data::Entry;
+
+/// Which digest [`entry_hash_with`] computes. `Sha256` is the default
+/// everywhere a bare [`entry_hash`] is used; `Md5` exists for parity with
+/// the other selectable-digest APIs in [`crate::port`] and for interop
+/// with tooling that already keys off MD5 content hashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Md5,
+}
+
+impl HashAlgorithm {
+    /// The lowercase name used in a `# entry-hash: <name>:<hex>` comment.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Md5 => "md5",
+        }
+    }
+
+    /// Parse the name written by [`HashAlgorithm::name`], case-insensitively.
+    pub fn parse(name: &str) -> Option<HashAlgorithm> {
+        match name.to_ascii_lowercase().as_str() {
+            "sha256" => Some(HashAlgorithm::Sha256),
+            "md5" => Some(HashAlgorithm::Md5),
+            _ => None,
+        }
+    }
+}
+
+fn digest(algo: HashAlgorithm, data: &[u8]) -> String {
+    match algo {
+        HashAlgorithm::Sha256 => Sha256::digest(data)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+        HashAlgorithm::Md5 => Md5::digest(data)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+    }
+}
+
+fn update_len_prefixed(hasher: &mut Sha256, data: &[u8]) {
+    hasher.update((data.len() as u32).to_le_bytes());
+    hasher.update(data);
+}
+
+/// Compute a stable SHA-256 content hash of `entry`, as lowercase hex.
+/// Shorthand for `entry_hash_with(entry, HashAlgorithm::Sha256)`.
+///
+/// Hashes [`Entry::canonical_bytes`]: DN first (RFC 4514-normalized),
+/// then attributes sorted by description (ASCII case-insensitive, ties
+/// broken bytewise), each with its values sorted lexicographically -- so
+/// two entries differing only in DN escaping or attribute/value order
+/// hash equal. Every component is length-prefixed, so no delimiter can
+/// collide with content, an empty value still contributes a length-0
+/// record, and binary values hash verbatim. Independent of `BinaryMode`:
+/// this operates on raw bytes, never the printed encoding.
+pub fn entry_hash(entry: &Entry) -> String {
+    entry_hash_with(entry, HashAlgorithm::Sha256)
+}
+
+/// Like [`entry_hash`], with the digest algorithm selectable -- e.g. for a
+/// `# entry-hash: <algo>:<hex>` LDIF comment that records which algorithm
+/// produced it.
+pub fn entry_hash_with(entry: &Entry, algo: HashAlgorithm) -> String {
+    digest(algo, &entry.canonical_bytes())
+}
+
+/// Compute a stable SHA-256 digest over a whole search result: each
+/// entry's [`Entry::canonical_bytes`], sorted so the digest doesn't depend
+/// on the order the server happened to return entries in, then
+/// length-prefixed and hashed the same way `entry_hash` length-prefixes
+/// an entry's own fields. Lets a caller tell whether two `search_to_file`
+/// snapshots cover the same entries without re-parsing either one, and
+/// lets the interactive editor confirm the entries it's about to commit
+/// are still the ones it originally fetched.
+pub fn search_result_hash(entries: &[Entry]) -> String {
+    let mut canonical: Vec<Vec<u8>> = entries.iter().map(Entry::canonical_bytes).collect();
+    canonical.sort();
+
+    let mut hasher = Sha256::new();
+    for bytes in &canonical {
+        update_len_prefixed(&mut hasher, bytes);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_entry(dn: &str) -> Entry {
+        Entry::new(dn.to_string())
+    }
+
+    fn add_value(entry: &mut Entry, ad: &str, val: &[u8]) {
+        let attr = entry.find_attribute(ad, true).unwrap();
+        attr.values.push(val.to_vec());
+    }
+
+    // ── Group 1: stability and sensitivity ──────────────────────────
+
+    #[test]
+    fn same_entry_hashes_equal() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "cn", b"foo");
+        assert_eq!(entry_hash(&e), entry_hash(&e));
+    }
+
+    #[test]
+    fn different_dn_hashes_differ() {
+        let mut a = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut a, "cn", b"foo");
+        let mut b = make_entry("cn=bar,dc=example,dc=com");
+        add_value(&mut b, "cn", b"foo");
+        assert_ne!(entry_hash(&a), entry_hash(&b));
+    }
+
+    #[test]
+    fn different_value_hashes_differ() {
+        let mut a = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut a, "cn", b"foo");
+        let mut b = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut b, "cn", b"bar");
+        assert_ne!(entry_hash(&a), entry_hash(&b));
+    }
+
+    #[test]
+    fn output_is_lowercase_hex_sha256_length() {
+        let e = make_entry("cn=foo,dc=example,dc=com");
+        let hash = entry_hash(&e);
+        assert_eq!(hash.len(), 64);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+    }
+
+    // ── Group 2: canonicalization ───────────────────────────────────
+
+    #[test]
+    fn attribute_order_does_not_affect_hash() {
+        let mut a = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut a, "cn", b"foo");
+        add_value(&mut a, "sn", b"bar");
+
+        let mut b = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut b, "sn", b"bar");
+        add_value(&mut b, "cn", b"foo");
+
+        assert_eq!(entry_hash(&a), entry_hash(&b));
+    }
+
+    #[test]
+    fn value_order_within_an_attribute_does_not_affect_hash() {
+        let mut a = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut a, "mail", b"a@example.com");
+        add_value(&mut a, "mail", b"b@example.com");
+
+        let mut b = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut b, "mail", b"b@example.com");
+        add_value(&mut b, "mail", b"a@example.com");
+
+        assert_eq!(entry_hash(&a), entry_hash(&b));
+    }
+
+    #[test]
+    fn attribute_name_case_only_affects_sort_order_not_hash_bytes() {
+        // `mail` and `Mail` sort as the same key (case-insensitive), but
+        // their raw bytes still go into the digest, so they must not
+        // collide with each other.
+        let mut a = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut a, "mail", b"a@example.com");
+
+        let mut b = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut b, "Mail", b"a@example.com");
+
+        assert_ne!(entry_hash(&a), entry_hash(&b));
+    }
+
+    #[test]
+    fn attributes_differing_only_in_case_still_sort_adjacently() {
+        // Regression guard for the sort comparator: case-insensitive
+        // attributes interleaved with a third attribute must land in a
+        // stable, case-insensitive order regardless of which entry they
+        // started in.
+        let mut a = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut a, "Mail", b"x");
+        add_value(&mut a, "apple", b"y");
+
+        let mut b = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut b, "apple", b"y");
+        add_value(&mut b, "Mail", b"x");
+
+        assert_eq!(entry_hash(&a), entry_hash(&b));
+    }
+
+    #[test]
+    fn empty_value_contributes_a_length_zero_record() {
+        let mut a = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut a, "cn", b"");
+        let b = make_entry("cn=foo,dc=example,dc=com");
+        assert_ne!(entry_hash(&a), entry_hash(&b));
+    }
+
+    #[test]
+    fn length_prefixing_prevents_boundary_collisions() {
+        // Without a length prefix, attr "ab" + value "cd" would hash the
+        // same as attr "a" + value "bcd" once concatenated.
+        let mut a = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut a, "ab", b"cd");
+        let mut b = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut b, "a", b"bcd");
+        assert_ne!(entry_hash(&a), entry_hash(&b));
+    }
+
+    #[test]
+    fn dn_escaping_differences_do_not_affect_hash() {
+        let mut a = make_entry(r"cn=foo,dc=example,dc=com");
+        add_value(&mut a, "cn", b"foo");
+        let mut b = make_entry(r"cn=\66oo,dc=example,dc=com");
+        add_value(&mut b, "cn", b"foo");
+        assert_eq!(entry_hash(&a), entry_hash(&b));
+    }
+
+    // ── Group 3: search_result_hash ──────────────────────────────────
+
+    #[test]
+    fn search_result_hash_is_order_independent() {
+        let mut a = make_entry("cn=a,dc=example,dc=com");
+        add_value(&mut a, "cn", b"a");
+        let mut b = make_entry("cn=b,dc=example,dc=com");
+        add_value(&mut b, "cn", b"b");
+
+        assert_eq!(
+            search_result_hash(&[a.clone(), b.clone()]),
+            search_result_hash(&[b, a])
+        );
+    }
+
+    #[test]
+    fn search_result_hash_changes_with_contents() {
+        let mut a = make_entry("cn=a,dc=example,dc=com");
+        add_value(&mut a, "cn", b"a");
+        let mut b = a.clone();
+        add_value(&mut b, "mail", b"a@example.com");
+
+        assert_ne!(search_result_hash(&[a]), search_result_hash(&[b]));
+    }
+
+    #[test]
+    fn search_result_hash_of_empty_result_is_stable() {
+        assert_eq!(search_result_hash(&[]), search_result_hash(&[]));
+    }
+
+    // ── Group 4: HashAlgorithm selection ────────────────────────────
+
+    #[test]
+    fn entry_hash_with_sha256_matches_entry_hash() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "cn", b"foo");
+        assert_eq!(entry_hash_with(&e, HashAlgorithm::Sha256), entry_hash(&e));
+    }
+
+    #[test]
+    fn md5_and_sha256_of_the_same_entry_differ_and_have_the_right_length() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "cn", b"foo");
+        let md5 = entry_hash_with(&e, HashAlgorithm::Md5);
+        let sha256 = entry_hash_with(&e, HashAlgorithm::Sha256);
+        assert_eq!(md5.len(), 32);
+        assert_eq!(sha256.len(), 64);
+        assert_ne!(md5, sha256);
+    }
+
+    #[test]
+    fn hash_algorithm_name_round_trips_through_parse() {
+        assert_eq!(HashAlgorithm::parse("sha256"), Some(HashAlgorithm::Sha256));
+        assert_eq!(HashAlgorithm::parse("MD5"), Some(HashAlgorithm::Md5));
+        assert_eq!(HashAlgorithm::parse("bogus"), None);
+    }
+}
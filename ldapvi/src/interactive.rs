@@ -1,7 +1,9 @@
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
 use std::os::fd::BorrowedFd;
 use std::process::Command;
 
+use crate::memtemp::TempFile;
+
 /// Present a single-character menu prompt. Returns the chosen character.
 pub fn choose(prompt: &str, charbag: &str, help: &str) -> char {
     use nix::sys::termios;
@@ -96,6 +98,31 @@ pub fn view(pathname: &str) {
     }
 }
 
+/// Like [`edit`], but the LDIF never touches disk: it lives in a
+/// [`TempFile`] (a `memfd_create`-backed anonymous buffer) for the
+/// duration of the edit, so `userPassword` and other sensitive values
+/// in `data` aren't written to a real temp file or swap. Returns the
+/// edited bytes, or an `io::Error` if the editor appears to have saved
+/// by replacing rather than rewriting the file -- see the [`TempFile`]
+/// docs for why that can't be fixed in place and what to tell the user.
+pub fn edit_in_memory(data: &[u8], line: Option<i64>) -> io::Result<Vec<u8>> {
+    let mut tmp = TempFile::new("ldapvi-edit")?;
+    tmp.write_all(data)?;
+    tmp.allow_child_access()?;
+    edit(tmp.proc_path(), line);
+    tmp.read_back(data.len())
+}
+
+/// Like [`view`], but the LDIF never touches disk -- see
+/// [`edit_in_memory`].
+pub fn view_in_memory(data: &[u8]) -> io::Result<()> {
+    let mut tmp = TempFile::new("ldapvi-view")?;
+    tmp.write_all(data)?;
+    tmp.allow_child_access()?;
+    view(tmp.proc_path());
+    Ok(())
+}
+
 /// Prompt for a line of text input from the user.
 pub fn read_line(prompt: &str) -> String {
     use std::io::BufRead;
@@ -1,50 +1,89 @@
 use std::collections::HashSet;
+use std::fmt;
 use std::io::{Seek, Write};
 
 use ldap3::{LdapConn, LdapConnSettings, Scope, SearchEntry};
 
 use crate::arguments::Cmdline;
 use ldapvi::data::{Attribute, Entry, LdapMod, ModOp};
+use ldapvi::pipeline::{OpKind as PipelineOpKind, Operation};
 use ldapvi::print::{self, BinaryMode};
 use ldapvi::schema::{self, Schema};
 
-pub fn do_connect(cmdline: &Cmdline) -> Result<LdapConn, String> {
-    let url = match &cmdline.server {
-        Some(s) => {
-            if s.contains("://") {
-                s.clone()
-            } else {
-                format!("ldap://{}", s)
-            }
-        }
-        None => "ldap://localhost".to_string(),
-    };
+/// How strictly a connection should validate the server's TLS certificate,
+/// parsed from `--tls [never|allow|try|strict|demand]` by
+/// `arguments::parse_tls`. Named after OpenLDAP's `TLS_REQCERT`/Cyrus
+/// SASL's `maxssf`-adjacent strictness knobs, which this mirrors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsRequireCert {
+    /// `never` -- don't request or check a certificate at all.
+    Never,
+    /// `allow` -- request a certificate but proceed even if it's missing or
+    /// fails verification.
+    Allow,
+    /// `try` -- request a certificate; fail only if one is presented and
+    /// invalid, not if it's simply missing.
+    Try,
+    /// `strict`/`demand` -- require a certificate and verify it.
+    Strict,
+}
+
+/// Which transport a profile's `tls:` key asks for, independent of
+/// `TlsRequireCert`'s certificate-strictness axis (profiles spell that
+/// `tls-reqcert:` to avoid colliding with this).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsMode {
+    /// `off` -- plain `ldap://`, the default.
+    Off,
+    /// `starttls` -- connect plain, then upgrade via the StartTLS extended
+    /// operation. Equivalent to `-Z`/`--starttls`.
+    Starttls,
+    /// `ldaps` -- negotiate TLS as part of the initial connection, i.e. use
+    /// the `ldaps://` scheme.
+    Ldaps,
+}
 
+fn server_url(s: &str, tls_mode: Option<TlsMode>) -> String {
+    if s.contains("://") {
+        s.to_string()
+    } else if tls_mode == Some(TlsMode::Ldaps) {
+        format!("ldaps://{}", s)
+    } else {
+        format!("ldap://{}", s)
+    }
+}
+
+fn connect_settings(cmdline: &Cmdline) -> Result<LdapConnSettings, String> {
     let mut settings = LdapConnSettings::new();
 
-    // -Z / --starttls: upgrade ldap:// to TLS via StartTLS extended op
+    // -Z / --starttls (or a profile's `tls: starttls`, folded into this
+    // same flag by arguments::parse_args): upgrade ldap:// to TLS via
+    // StartTLS extended op.
     if cmdline.starttls {
         settings = settings.set_starttls(true);
     }
 
     // --tls mode
-    match cmdline.tls.as_deref() {
-        Some("never") | Some("allow") | Some("try") => {
-            settings = settings.set_no_tls_verify(true);
-        }
-        Some("strict") => {
-            // default: verify certificates
-        }
-        Some(other) => {
-            return Err(format!(
-                "invalid --tls mode: {} (expected never, allow, try, strict)",
-                other
-            ));
-        }
-        None => {}
+    if let Some(require) = cmdline.tls {
+        settings = settings.set_tls_require_cert(require);
     }
 
-    let mut conn = LdapConn::with_settings(settings, &url)
+    // A profile's tls-cacert:/tls-cert:/tls-key: -- the CA bundle to
+    // verify the server against, and a client certificate/key pair for
+    // mutual TLS.
+    if let Some(ref path) = cmdline.tls_cacert {
+        settings = settings.set_ca_cert_file(path);
+    }
+    if let (Some(ref cert), Some(ref key)) = (&cmdline.tls_cert, &cmdline.tls_key) {
+        settings = settings.set_client_cert(cert, key);
+    }
+
+    Ok(settings)
+}
+
+fn connect_and_bind(cmdline: &Cmdline, url: &str) -> Result<LdapConn, String> {
+    let settings = connect_settings(cmdline)?;
+    let mut conn = LdapConn::with_settings(settings, url)
         .map_err(|e| format!("connect to {}: {}", url, e))?;
 
     if let (Some(user), Some(password)) = (&cmdline.user, &cmdline.password) {
@@ -59,18 +98,164 @@ pub fn do_connect(cmdline: &Cmdline) -> Result<LdapConn, String> {
     Ok(conn)
 }
 
+/// Connect to the first of `cmdline.servers` that accepts a connection and
+/// bind, trying each URI in order. Returns the live connection together
+/// with the URL it actually succeeded on, so callers (the interactive 'r'
+/// action, retry-on-drop logic) can report or remember it.
+pub fn do_connect(cmdline: &Cmdline) -> Result<(LdapConn, String), String> {
+    let urls: Vec<String> = if cmdline.servers.is_empty() {
+        vec!["ldap://localhost".to_string()]
+    } else {
+        cmdline
+            .servers
+            .iter()
+            .map(|s| server_url(s, cmdline.tls_mode))
+            .collect()
+    };
+
+    let mut last_err = String::new();
+    for url in &urls {
+        match connect_and_bind(cmdline, url) {
+            Ok(conn) => return Ok((conn, url.clone())),
+            Err(e) => last_err = e,
+        }
+    }
+    Err(last_err)
+}
+
+/// Classify an error message as a connection-level failure (worth
+/// reconnecting and retrying) rather than a server-side rejection of the
+/// operation itself (bad DN, constraint violation, etc., which retrying
+/// won't fix).
+fn is_connection_error(message: &str) -> bool {
+    let m = message.to_ascii_lowercase();
+    m.contains("connect")
+        || m.contains("i/o error")
+        || m.contains("ioerror")
+        || m.contains("connection reset")
+        || m.contains("broken pipe")
+        || m.contains("not connected")
+        || m.contains("eof")
+}
+
+/// Run `op` against `*conn`; on a connection-level error, reconnect via
+/// [`do_connect`] (rotating through `cmdline.servers`) and retry, up to
+/// `cmdline.retry` times, before giving up with the last error.
+pub fn with_reconnect<T>(
+    conn: &mut LdapConn,
+    cmdline: &Cmdline,
+    mut op: impl FnMut(&mut LdapConn) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut attempts_left = cmdline.retry;
+    loop {
+        match op(conn) {
+            Ok(v) => return Ok(v),
+            Err(e) if is_connection_error(&e) && attempts_left > 0 => {
+                attempts_left -= 1;
+                eprintln!("ldapvi: connection lost ({}), reconnecting...", e);
+                match do_connect(cmdline) {
+                    Ok((new_conn, url)) => {
+                        *conn = new_conn;
+                        eprintln!("ldapvi: reconnected to {}.", url);
+                    }
+                    Err(reconnect_err) => return Err(reconnect_err),
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Parse an `ldap://host[:port]/dn` (or `ldaps://`) referral URL per RFC
+/// 4516, returning the server URL to (re)connect to and the DN to search or
+/// operate on. Any `?attrs?scope?filter` suffix is ignored -- callers
+/// reissue the *original* operation's attrs/scope/filter against the
+/// referred base, per RFC 4511's guidance that a referral only overrides
+/// the target DN and server, not the rest of the request.
+fn parse_referral_url(url: &str) -> Option<(String, String)> {
+    let scheme_end = url.find("://")?;
+    let scheme = &url[..scheme_end];
+    if scheme != "ldap" && scheme != "ldaps" {
+        return None;
+    }
+    let rest = &url[scheme_end + 3..];
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i + 1..]),
+        None => (rest, ""),
+    };
+    if authority.is_empty() {
+        return None;
+    }
+    let dn_part = path.split('?').next().unwrap_or("");
+    Some((format!("{}://{}", scheme, authority), percent_decode(dn_part)))
+}
+
+/// Minimal RFC 3986 percent-decoder for the DN component of a referral URL.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 3 <= bytes.len() {
+            if let Ok(byte) = u8::from_str_radix(&s[i + 1..i + 3], 16) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Follow a write operation's referral (`rc == 10`) once: connect to the
+/// first usable `ldap://host/dn` URL in `refs`, reusing `cmdline`'s
+/// bind/TLS settings, and retry `op` against the referred DN there. Unlike
+/// [`search_and_print`]'s referral chasing (which follows multi-hop chains
+/// with a hop limit and a visited-pair set, since a search can fan out
+/// across several referred servers), a write targets exactly one entry, so
+/// chasing just the first referral -- the common case of a single
+/// read-only replica pointing back at its master -- covers the case that
+/// actually matters without adding multi-hop loop-tracking plumbing for a
+/// scenario (a referral chain for a single write) real directories avoid.
+fn chase_write_referral<T>(
+    cmdline: &Cmdline,
+    refs: &[String],
+    mut op: impl FnMut(&mut LdapConn, &str) -> Result<T, LdapOpError>,
+) -> Option<Result<T, LdapOpError>> {
+    for url in refs {
+        if let Some((host_url, referred_dn)) = parse_referral_url(url) {
+            let mut conn = match connect_and_bind(cmdline, &host_url) {
+                Ok(c) => c,
+                Err(e) => {
+                    return Some(Err(LdapOpError {
+                        message: format!("referral to {}: {}", host_url, e),
+                        result_code: None,
+                    }))
+                }
+            };
+            return Some(op(&mut conn, &referred_dn));
+        }
+    }
+    None
+}
+
 fn search_entry_to_entry(se: SearchEntry) -> Entry {
     let mut attributes = Vec::new();
 
     for (ad, values) in se.attrs {
         attributes.push(Attribute {
-            ad,
+            ad: ad.into(),
             values: values.into_iter().map(|v| v.into_bytes()).collect(),
         });
     }
 
     for (ad, values) in se.bin_attrs {
-        attributes.push(Attribute { ad, values });
+        attributes.push(Attribute {
+            ad: ad.into(),
+            values,
+        });
     }
 
     // Sort attributes by name for deterministic output.
@@ -97,23 +282,90 @@ pub fn search_and_print(
     out: &mut dyn Write,
 ) -> Result<(), String> {
     let attr_refs: Vec<&str> = cmdline.attrs.iter().map(|s| s.as_str()).collect();
+    let mut visited: HashSet<(String, String)> = HashSet::new();
 
     for base in &cmdline.basedns {
-        let (entries, _result) = ldap
-            .search(base, cmdline.scope, &cmdline.filter, &attr_refs)
-            .map_err(|e| format!("search: {}", e))?
-            .success()
-            .map_err(|e| format!("search: {}", e))?;
+        search_and_print_one(
+            ldap,
+            cmdline,
+            base,
+            &attr_refs,
+            cmdline.referral_hop_limit,
+            &mut visited,
+            out,
+        )?;
+    }
 
-        for raw_entry in entries {
-            let se = SearchEntry::construct(raw_entry);
-            let entry = search_entry_to_entry(se);
+    Ok(())
+}
 
-            if cmdline.ldif {
-                print::print_ldif_entry(out, &entry, None).map_err(|e| format!("write: {}", e))?;
-            } else {
-                print::print_ldapvi_entry(out, &entry, None, binary_mode(cmdline))
-                    .map_err(|e| format!("write: {}", e))?;
+/// Search `base` and print matching entries to `out`; then, if
+/// `cmdline.chase_referrals` is set and the server's response carried a
+/// referral or continuation reference, follow each `ldap://host/dn` URL in
+/// turn -- reconnecting with the current bind/TLS settings and reissuing the
+/// same attrs/scope/filter scoped to the referred base -- merging whatever
+/// those servers return into the same output. `visited` remembers every
+/// host+base pair already tried (across the whole top-level search, not just
+/// this call) and `hops_left` bounds the chain, so a loop of servers
+/// referring back to each other can't recurse forever.
+fn search_and_print_one(
+    ldap: &mut LdapConn,
+    cmdline: &Cmdline,
+    base: &str,
+    attr_refs: &[&str],
+    hops_left: u32,
+    visited: &mut HashSet<(String, String)>,
+    out: &mut dyn Write,
+) -> Result<(), String> {
+    let ldap3::SearchResult(raw_entries, result) = with_reconnect(ldap, cmdline, |ldap| {
+        ldap.search(base, cmdline.scope, &cmdline.filter, attr_refs)
+            .map_err(|e| format!("search: {}", e))
+    })?;
+
+    if result.rc != 0 && result.rc != 10 {
+        return Err(format!("search: {} {}", result.rc, result.text));
+    }
+
+    for raw_entry in raw_entries {
+        let se = SearchEntry::construct(raw_entry);
+        let entry = search_entry_to_entry(se);
+
+        if cmdline.netencode {
+            print::print_netencode_entry(out, &entry).map_err(|e| format!("write: {}", e))?;
+        } else if cmdline.ldif {
+            print::print_ldif_entry(out, &entry, None, print::DEFAULT_LDIF_WIDTH)
+                .map_err(|e| format!("write: {}", e))?;
+        } else {
+            print::print_ldapvi_entry(out, &entry, None, binary_mode(cmdline))
+                .map_err(|e| format!("write: {}", e))?;
+        }
+    }
+
+    if cmdline.chase_referrals && hops_left > 0 {
+        for url in &result.refs {
+            let Some((host_url, referred_dn)) = parse_referral_url(url) else {
+                continue;
+            };
+            if !visited.insert((host_url.clone(), referred_dn.clone())) {
+                continue; // already followed this host+base pair
+            }
+            let mut conn = match connect_and_bind(cmdline, &host_url) {
+                Ok(c) => c,
+                Err(e) => {
+                    eprintln!("ldapvi: referral to {}: {}", host_url, e);
+                    continue;
+                }
+            };
+            if let Err(e) = search_and_print_one(
+                &mut conn,
+                cmdline,
+                &referred_dn,
+                attr_refs,
+                hops_left - 1,
+                visited,
+                out,
+            ) {
+                eprintln!("ldapvi: referral to {}: {}", host_url, e);
             }
         }
     }
@@ -127,9 +379,25 @@ pub fn search_to_file<W: Write + Seek>(
     cmdline: &Cmdline,
     out: &mut W,
 ) -> Result<Vec<i64>, String> {
-    let attr_refs: Vec<&str> = cmdline.attrs.iter().map(|s| s.as_str()).collect();
+    // --track-uuid needs entryUUID back from the server to build its
+    // identity map, even if the caller didn't ask for it explicitly.
+    let mut attrs_owned;
+    let attr_refs: Vec<&str> = if cmdline.track_uuid
+        && !cmdline.attrs.iter().any(|a| a == "+" || a.eq_ignore_ascii_case("entryUUID"))
+    {
+        attrs_owned = cmdline.attrs.clone();
+        attrs_owned.push("entryUUID".to_string());
+        attrs_owned.iter().map(|s| s.as_str()).collect()
+    } else {
+        cmdline.attrs.iter().map(|s| s.as_str()).collect()
+    };
     let mode = binary_mode(cmdline);
     let mut offsets = Vec::new();
+    let select = cmdline
+        .select
+        .as_deref()
+        .map(ldapvi::filter::parse)
+        .transpose()?;
 
     // File header: Emacs coding cookie + vim modeline for UTF-8.
     // Note: vim's "encoding" is disallowed in modelines;
@@ -151,12 +419,18 @@ pub fn search_to_file<W: Write + Seek>(
             .map_err(|e| format!("search: {}", e))?;
 
         for raw_entry in entries {
-            let pos = out.stream_position().map_err(|e| format!("tell: {}", e))?;
-            offsets.push(pos as i64);
-
             let se = SearchEntry::construct(raw_entry);
             let entry = search_entry_to_entry(se);
 
+            if let Some(ref select) = select {
+                if !select.matches(&entry) {
+                    continue;
+                }
+            }
+
+            let pos = out.stream_position().map_err(|e| format!("tell: {}", e))?;
+            offsets.push(pos as i64);
+
             let key = entry_num.to_string();
             print::print_ldapvi_entry(out, &entry, Some(&key), mode)
                 .map_err(|e| format!("write: {}", e))?;
@@ -167,6 +441,27 @@ pub fn search_to_file<W: Write + Seek>(
     Ok(offsets)
 }
 
+/// Search for entries matching `cmdline`'s base DNs/scope/filter, returning
+/// only their DNs (no attributes are requested from the server).
+pub fn search_dns(ldap: &mut LdapConn, cmdline: &Cmdline) -> Result<Vec<String>, String> {
+    let mut dns = Vec::new();
+
+    for base in &cmdline.basedns {
+        let (entries, _result) = ldap
+            .search(base, cmdline.scope, &cmdline.filter, vec!["1.1"])
+            .map_err(|e| format!("search: {}", e))?
+            .success()
+            .map_err(|e| format!("search: {}", e))?;
+
+        for raw_entry in entries {
+            let se = SearchEntry::construct(raw_entry);
+            dns.push(se.dn);
+        }
+    }
+
+    Ok(dns)
+}
+
 /// Discover naming contexts from the root DSE.
 pub fn discover_naming_contexts(ldap: &mut LdapConn) -> Result<Vec<String>, String> {
     let (entries, _) = ldap
@@ -191,59 +486,224 @@ pub fn discover_naming_contexts(ldap: &mut LdapConn) -> Result<Vec<String>, Stri
     Ok(contexts)
 }
 
+/// The `supportedFeatures` OID this client treats as "the server's own
+/// entryUUID plugin is installed and generating this attribute already" --
+/// `memberOf`-style overlays and most entryUUID implementations (OpenLDAP's
+/// `slapo-unique`/core `entryUUID`, 389-ds) don't advertise a dedicated
+/// feature OID for it, so there is no single IANA-registered identifier to
+/// check here. This is a best-effort placeholder matching the convention a
+/// directory *could* use to announce it; sites whose server doesn't
+/// advertise it (i.e. almost all of them today) simply always see
+/// `--generate-entryuuid` take effect, which is the safe default.
+const ENTRY_UUID_FEATURE_OID: &str = "1.3.6.1.4.1.4203.666.11.9.1";
+
+/// Whether the root DSE's `supportedFeatures` lists
+/// [`ENTRY_UUID_FEATURE_OID`], meaning the server already synthesizes
+/// `entryUUID` itself and `--generate-entryuuid` should defer to it.
+pub fn server_has_entryuuid_feature(ldap: &mut LdapConn) -> Result<bool, String> {
+    let (entries, _) = ldap
+        .search("", Scope::Base, "(objectclass=*)", vec!["supportedFeatures"])
+        .map_err(|e| format!("search root DSE: {}", e))?
+        .success()
+        .map_err(|e| format!("search root DSE: {}", e))?;
+
+    for raw_entry in entries {
+        let se = SearchEntry::construct(raw_entry);
+        for (key, values) in &se.attrs {
+            if key.eq_ignore_ascii_case("supportedFeatures") && values.iter().any(|v| v == ENTRY_UUID_FEATURE_OID) {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Build a "value listed twice" error for `m`, naming the attribute and
+/// the repeated value. `Delete` mods are exempt: their value list is a
+/// set of values to remove, not values to write, so a server doesn't
+/// reject a repeated one.
+fn duplicate_value_error(m: &LdapMod) -> Option<LdapOpError> {
+    if m.op == ModOp::Delete {
+        return None;
+    }
+    m.find_duplicate_value().map(|value| LdapOpError {
+        message: format!(
+            "attribute '{}' lists the same value twice: {}",
+            m.attr,
+            String::from_utf8_lossy(value)
+        ),
+        result_code: None,
+    })
+}
+
 /// Convert our LdapMod values to ldap3 Mod format.
-fn ldapmod_to_ldap3_mod(m: &LdapMod) -> ldap3::Mod<Vec<u8>> {
+///
+/// Rejects an Add/Replace that lists the same value twice instead of
+/// silently collapsing it into the `HashSet<Vec<u8>>` ldap3 wants -- the
+/// same subtle class of bug as a parser that overrides a duplicate
+/// record key without noticing.
+fn ldapmod_to_ldap3_mod(m: &LdapMod) -> Result<ldap3::Mod<Vec<u8>>, LdapOpError> {
+    if let Some(e) = duplicate_value_error(m) {
+        return Err(e);
+    }
     let attr = m.attr.clone().into_bytes();
     let vals: HashSet<Vec<u8>> = m.values.iter().cloned().collect();
-    match m.op {
+    Ok(match m.op {
         ModOp::Add => ldap3::Mod::Add(attr, vals),
         ModOp::Delete => ldap3::Mod::Delete(attr, vals),
         ModOp::Replace => ldap3::Mod::Replace(attr, vals),
+    })
+}
+
+/// A failure from a single LDAP operation, carrying the server's result
+/// code (when the client got far enough to receive one) alongside a
+/// human-readable message.
+#[derive(Debug, Clone)]
+pub struct LdapOpError {
+    pub message: String,
+    pub result_code: Option<u32>,
+}
+
+impl std::fmt::Display for LdapOpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
     }
 }
 
 /// Apply a modify operation to the LDAP server.
-pub fn ldap_modify(ldap: &mut LdapConn, dn: &str, mods: &[LdapMod]) -> Result<(), String> {
-    let ldap3_mods: Vec<ldap3::Mod<Vec<u8>>> = mods.iter().map(ldapmod_to_ldap3_mod).collect();
-    let result = ldap
-        .modify(dn, ldap3_mods)
-        .map_err(|e| format!("modify {}: {}", dn, e))?;
+pub fn ldap_modify(
+    ldap: &mut LdapConn,
+    dn: &str,
+    mods: &[LdapMod],
+    cmdline: &Cmdline,
+) -> Result<(), LdapOpError> {
+    let ldap3_mods: Vec<ldap3::Mod<Vec<u8>>> = mods
+        .iter()
+        .map(ldapmod_to_ldap3_mod)
+        .collect::<Result<_, _>>()?;
+    let result = ldap.modify(dn, ldap3_mods).map_err(|e| LdapOpError {
+        message: format!("modify {}: {}", dn, e),
+        result_code: None,
+    })?;
+    if result.rc == 10 && cmdline.chase_referrals {
+        if let Some(outcome) = chase_write_referral(cmdline, &result.refs, |conn, referred_dn| {
+            let referred_mods: Vec<ldap3::Mod<Vec<u8>>> = mods
+                .iter()
+                .map(ldapmod_to_ldap3_mod)
+                .collect::<Result<_, _>>()?;
+            let r = conn
+                .modify(referred_dn, referred_mods)
+                .map_err(|e| LdapOpError {
+                    message: format!("modify {}: {}", referred_dn, e),
+                    result_code: None,
+                })?;
+            if r.rc != 0 {
+                return Err(LdapOpError {
+                    message: format!("modify {}: {} {}", referred_dn, r.rc, r.text),
+                    result_code: Some(r.rc as u32),
+                });
+            }
+            Ok(())
+        }) {
+            return outcome;
+        }
+    }
     if result.rc != 0 {
-        return Err(format!("modify {}: {} {}", dn, result.rc, result.text));
+        return Err(LdapOpError {
+            message: format!("modify {}: {} {}", dn, result.rc, result.text),
+            result_code: Some(result.rc as u32),
+        });
     }
     Ok(())
 }
 
-/// Apply an add operation to the LDAP server.
-pub fn ldap_add(ldap: &mut LdapConn, dn: &str, mods: &[LdapMod]) -> Result<(), String> {
-    // Convert mods (all should be Add) to attribute vec for ldap3::add
+/// Convert mods (all should be Add) to the attribute vec ldap3::add wants,
+/// coalescing repeated attribute names into one entry with merged values.
+fn mods_to_attr_map(mods: &[LdapMod]) -> Result<Vec<(Vec<u8>, HashSet<Vec<u8>>)>, LdapOpError> {
     let mut attr_map: Vec<(Vec<u8>, HashSet<Vec<u8>>)> = Vec::new();
     for m in mods {
+        if let Some(e) = duplicate_value_error(m) {
+            return Err(e);
+        }
         let attr = m.attr.clone().into_bytes();
         let vals: HashSet<Vec<u8>> = m.values.iter().cloned().collect();
-        // Check if we already have this attribute
         if let Some(existing) = attr_map.iter_mut().find(|(a, _)| *a == attr) {
             existing.1.extend(vals);
         } else {
             attr_map.push((attr, vals));
         }
     }
-    let result = ldap
-        .add(dn, attr_map)
-        .map_err(|e| format!("add {}: {}", dn, e))?;
+    Ok(attr_map)
+}
+
+/// Apply an add operation to the LDAP server.
+pub fn ldap_add(
+    ldap: &mut LdapConn,
+    dn: &str,
+    mods: &[LdapMod],
+    cmdline: &Cmdline,
+) -> Result<(), LdapOpError> {
+    let attr_map = mods_to_attr_map(mods)?;
+    let result = ldap.add(dn, attr_map).map_err(|e| LdapOpError {
+        message: format!("add {}: {}", dn, e),
+        result_code: None,
+    })?;
+    if result.rc == 10 && cmdline.chase_referrals {
+        if let Some(outcome) = chase_write_referral(cmdline, &result.refs, |conn, referred_dn| {
+            let r = conn
+                .add(referred_dn, mods_to_attr_map(mods)?)
+                .map_err(|e| LdapOpError {
+                    message: format!("add {}: {}", referred_dn, e),
+                    result_code: None,
+                })?;
+            if r.rc != 0 {
+                return Err(LdapOpError {
+                    message: format!("add {}: {} {}", referred_dn, r.rc, r.text),
+                    result_code: Some(r.rc as u32),
+                });
+            }
+            Ok(())
+        }) {
+            return outcome;
+        }
+    }
     if result.rc != 0 {
-        return Err(format!("add {}: {} {}", dn, result.rc, result.text));
+        return Err(LdapOpError {
+            message: format!("add {}: {} {}", dn, result.rc, result.text),
+            result_code: Some(result.rc as u32),
+        });
     }
     Ok(())
 }
 
 /// Apply a delete operation to the LDAP server.
-pub fn ldap_delete(ldap: &mut LdapConn, dn: &str) -> Result<(), String> {
-    let result = ldap
-        .delete(dn)
-        .map_err(|e| format!("delete {}: {}", dn, e))?;
+pub fn ldap_delete(ldap: &mut LdapConn, dn: &str, cmdline: &Cmdline) -> Result<(), LdapOpError> {
+    let result = ldap.delete(dn).map_err(|e| LdapOpError {
+        message: format!("delete {}: {}", dn, e),
+        result_code: None,
+    })?;
+    if result.rc == 10 && cmdline.chase_referrals {
+        if let Some(outcome) = chase_write_referral(cmdline, &result.refs, |conn, referred_dn| {
+            let r = conn.delete(referred_dn).map_err(|e| LdapOpError {
+                message: format!("delete {}: {}", referred_dn, e),
+                result_code: None,
+            })?;
+            if r.rc != 0 {
+                return Err(LdapOpError {
+                    message: format!("delete {}: {} {}", referred_dn, r.rc, r.text),
+                    result_code: Some(r.rc as u32),
+                });
+            }
+            Ok(())
+        }) {
+            return outcome;
+        }
+    }
     if result.rc != 0 {
-        return Err(format!("delete {}: {} {}", dn, result.rc, result.text));
+        return Err(LdapOpError {
+            message: format!("delete {}: {} {}", dn, result.rc, result.text),
+            result_code: Some(result.rc as u32),
+        });
     }
     Ok(())
 }
@@ -255,16 +715,73 @@ pub fn ldap_rename(
     new_rdn: &str,
     new_superior: Option<&str>,
     delete_old_rdn: bool,
-) -> Result<(), String> {
+    cmdline: &Cmdline,
+) -> Result<(), LdapOpError> {
     let result = ldap
         .modifydn(old_dn, new_rdn, delete_old_rdn, new_superior)
-        .map_err(|e| format!("rename {}: {}", old_dn, e))?;
+        .map_err(|e| LdapOpError {
+            message: format!("rename {}: {}", old_dn, e),
+            result_code: None,
+        })?;
+    if result.rc == 10 && cmdline.chase_referrals {
+        if let Some(outcome) = chase_write_referral(cmdline, &result.refs, |conn, referred_dn| {
+            let r = conn
+                .modifydn(referred_dn, new_rdn, delete_old_rdn, new_superior)
+                .map_err(|e| LdapOpError {
+                    message: format!("rename {}: {}", referred_dn, e),
+                    result_code: None,
+                })?;
+            if r.rc != 0 {
+                return Err(LdapOpError {
+                    message: format!("rename {}: {} {}", referred_dn, r.rc, r.text),
+                    result_code: Some(r.rc as u32),
+                });
+            }
+            Ok(())
+        }) {
+            return outcome;
+        }
+    }
     if result.rc != 0 {
-        return Err(format!("rename {}: {} {}", old_dn, result.rc, result.text));
+        return Err(LdapOpError {
+            message: format!("rename {}: {} {}", old_dn, result.rc, result.text),
+            result_code: Some(result.rc as u32),
+        });
     }
     Ok(())
 }
 
+/// Issue an RFC 3062 PasswordModify extended request (OID
+/// `1.3.6.1.4.1.4203.1.11.1`), the `p` action's alternative to hand-editing
+/// `userPassword` and committing it as a plain modify -- this lets the
+/// server apply its own password policy and hashing instead.
+///
+/// `user_identity` is normally the target entry's DN; omit it to change the
+/// bound user's own password. Omit `new_passwd` to have the server generate
+/// one, returned as `Ok(Some(generated))`.
+pub fn password_modify(
+    ldap: &mut LdapConn,
+    user_identity: Option<&str>,
+    old_passwd: Option<&str>,
+    new_passwd: Option<&str>,
+) -> Result<Option<Vec<u8>>, LdapOpError> {
+    use ldap3::exop::PasswordModify;
+
+    let req = PasswordModify::new(user_identity, old_passwd, new_passwd);
+    let (exop, result) = ldap.extended(req).map_err(|e| LdapOpError {
+        message: format!("password modify: {}", e),
+        result_code: None,
+    })?;
+    if result.rc != 0 {
+        return Err(LdapOpError {
+            message: format!("password modify: {} {}", result.rc, result.text),
+            result_code: Some(result.rc as u32),
+        });
+    }
+    let parsed = exop.parse::<PasswordModify>();
+    Ok(parsed.generated_password)
+}
+
 /// Perform a simple bind on an existing connection.
 pub fn simple_bind(ldap: &mut LdapConn, dn: &str, password: &str) -> Result<(), String> {
     let result = ldap
@@ -276,6 +793,223 @@ pub fn simple_bind(ldap: &mut LdapConn, dn: &str, password: &str) -> Result<(),
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// SASL
+// ---------------------------------------------------------------------------
+
+/// How a [`AuthzId::Dn`] value should be matched, per the OpenLDAP authzid
+/// grammar's `dn.exact:`/`dn.regex:` type specifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnMatchType {
+    /// `dn:`/`dn.exact:` -- the value is a DN, normalized by
+    /// `arguments::parse_authzid` and matched for equality.
+    Exact,
+    /// `dn.regex:` -- the value is a regular expression the target DN must
+    /// match.
+    Regex,
+}
+
+/// Parsed form of a `-U/--sasl-authcid` or `-X/--sasl-authzid` value, per
+/// the OpenLDAP authzid grammar: either a bare userid to be mapped by
+/// server-side rules, or a DN-form identity with an optional match-type
+/// specifier. Parsed by `arguments::parse_authzid` and carried on `Cmdline`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthzId {
+    /// `u:<username>`.
+    User(String),
+    /// `dn:<dn>`, `dn.exact:<dn>`, or `dn.regex:<pattern>`.
+    Dn { value: String, match_type: DnMatchType },
+}
+
+impl AuthzId {
+    /// Render back to the `u:`/`dn:`/`dn.regex:`-prefixed wire form SASL
+    /// expects for an authentication or authorization identity string.
+    pub fn to_wire_string(&self) -> String {
+        match self {
+            AuthzId::User(u) => format!("u:{}", u),
+            AuthzId::Dn { value, match_type: DnMatchType::Exact } => format!("dn:{}", value),
+            AuthzId::Dn { value, match_type: DnMatchType::Regex } => format!("dn.regex:{}", value),
+        }
+    }
+}
+
+impl fmt::Display for AuthzId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_wire_string())
+    }
+}
+
+/// Structured form of the comma-separated token list accepted by
+/// `-O`/`--sasl-secprops`, mirroring the flags Cyrus SASL recognizes in its
+/// own `secprops` string: a handful of named feature toggles plus three
+/// `key=value` numeric limits. Parsed by `arguments::parse_secprops` and
+/// carried on `Cmdline` so [`sasl_bind`] can apply it when establishing a
+/// session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SaslSecprops {
+    pub none: bool,
+    pub nodict: bool,
+    pub noplain: bool,
+    pub noactive: bool,
+    pub passcred: bool,
+    pub forwardsec: bool,
+    pub noanonymous: bool,
+    pub minssf: Option<u32>,
+    pub maxssf: Option<u32>,
+    pub maxbufsize: Option<u32>,
+}
+
+impl SaslSecprops {
+    /// Whether every property is at its library default, i.e. there's
+    /// nothing for [`sasl_bind`] to apply.
+    pub fn is_default(&self) -> bool {
+        *self == SaslSecprops::default()
+    }
+}
+
+/// SASL mechanisms selectable via `--sasl-mech` or the interactive `B`/`*`
+/// actions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaslMech {
+    Gssapi,
+    DigestMd5,
+    Plain,
+    External,
+}
+
+impl SaslMech {
+    /// Parse a mechanism name as accepted by `--sasl-mech` (case-insensitive).
+    pub fn parse(s: &str) -> Option<SaslMech> {
+        match s.to_ascii_uppercase().as_str() {
+            "GSSAPI" => Some(SaslMech::Gssapi),
+            "DIGEST-MD5" => Some(SaslMech::DigestMd5),
+            "PLAIN" => Some(SaslMech::Plain),
+            "EXTERNAL" => Some(SaslMech::External),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SaslMech::Gssapi => "GSSAPI",
+            SaslMech::DigestMd5 => "DIGEST-MD5",
+            SaslMech::Plain => "PLAIN",
+            SaslMech::External => "EXTERNAL",
+        }
+    }
+}
+
+impl std::fmt::Display for SaslMech {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Preference order used by `negotiate_sasl_mech` when more than one
+/// mechanism advertised by the server is usable here: Kerberos tickets
+/// (GSSAPI) and TLS client certificates (EXTERNAL) need no typed-in
+/// credentials, so they're tried before the password-based mechanisms.
+const MECH_PREFERENCE: &[SaslMech] = &[
+    SaslMech::Gssapi,
+    SaslMech::External,
+    SaslMech::DigestMd5,
+    SaslMech::Plain,
+];
+
+/// Perform a SASL bind on an existing connection, driving the underlying
+/// LDAP library's SASL interactive callback the way the C ldapvi drove
+/// Cyrus SASL's `ldap_sasl_interactive_bind_s`. `authcid` is the
+/// authentication identity, `authzid` an optional authorization identity,
+/// `realm` scopes mechanisms such as DIGEST-MD5, and `password` is required
+/// by PLAIN and DIGEST-MD5 (EXTERNAL and GSSAPI ignore it). `secprops` carries the parsed
+/// `-O/--sasl-secprops` flags and is applied to the Cyrus SASL session
+/// before the mechanism-specific bind, unless it's all defaults.
+pub fn sasl_bind(
+    ldap: &mut LdapConn,
+    mech: SaslMech,
+    authcid: Option<&str>,
+    authzid: Option<&str>,
+    realm: Option<&str>,
+    password: Option<&str>,
+    secprops: SaslSecprops,
+) -> Result<(), String> {
+    if !secprops.is_default() {
+        ldap.set_sasl_security_properties(
+            secprops.minssf.unwrap_or(0),
+            secprops.maxssf.unwrap_or(u32::MAX),
+            secprops.maxbufsize,
+            secprops.none,
+            secprops.nodict,
+            secprops.noplain,
+            secprops.noactive,
+            secprops.passcred,
+            secprops.forwardsec,
+            secprops.noanonymous,
+        )
+        .map_err(|e| format!("SASL security properties: {}", e))?;
+    }
+
+    let result = match mech {
+        SaslMech::External => ldap
+            .sasl_external_bind()
+            .map_err(|e| format!("SASL EXTERNAL bind: {}", e))?,
+        SaslMech::Gssapi => {
+            let fqdn = authcid.unwrap_or("");
+            ldap.sasl_gssapi_bind(fqdn)
+                .map_err(|e| format!("SASL GSSAPI bind: {}", e))?
+        }
+        SaslMech::Plain | SaslMech::DigestMd5 => {
+            let authcid = authcid
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| "SASL bind requires an authentication identity (-U/--sasl-authcid)".to_string())?;
+            let password = password
+                .ok_or_else(|| "SASL bind requires a password".to_string())?;
+            ldap.sasl_bind(mech.name(), authcid, authzid, realm, password)
+                .map_err(|e| format!("SASL {} bind: {}", mech.name(), e))?
+        }
+    };
+
+    if result.rc != 0 {
+        return Err(format!("SASL bind failed: {} {}", result.rc, result.text));
+    }
+    Ok(())
+}
+
+/// Read `supportedSASLMechanisms` from the root DSE and pick the strongest
+/// mechanism this build can actually use, in `MECH_PREFERENCE` order.
+pub fn negotiate_sasl_mech(ldap: &mut LdapConn) -> Result<SaslMech, String> {
+    let (entries, _) = ldap
+        .search(
+            "",
+            Scope::Base,
+            "(objectclass=*)",
+            vec!["supportedSASLMechanisms"],
+        )
+        .map_err(|e| format!("search root DSE: {}", e))?
+        .success()
+        .map_err(|e| format!("search root DSE: {}", e))?;
+
+    let mut advertised: Vec<SaslMech> = Vec::new();
+    for raw_entry in entries {
+        let se = SearchEntry::construct(raw_entry);
+        for (key, values) in &se.attrs {
+            if key.eq_ignore_ascii_case("supportedSASLMechanisms") {
+                for v in values {
+                    if let Some(mech) = SaslMech::parse(v) {
+                        advertised.push(mech);
+                    }
+                }
+            }
+        }
+    }
+
+    MECH_PREFERENCE
+        .iter()
+        .copied()
+        .find(|mech| advertised.contains(mech))
+        .ok_or_else(|| "server did not advertise any supported SASL mechanism".to_string())
+}
+
 /// Read the LDAP schema from the server.
 ///
 /// 1. Query root DSE for subschemaSubentry
@@ -347,3 +1081,253 @@ pub fn read_schema(ldap: &mut LdapConn) -> Result<Schema, String> {
 
     Ok(s)
 }
+
+// ===========================================================================
+// Pipelined asynchronous commit
+// ===========================================================================
+
+/// A live async LDAP connection plus the runtime driving it, reused across
+/// however many batches a pipelined commit dispatches. Separating "connect
+/// once" from "dispatch a batch" lets a caller hand successive batches of
+/// queued operations to [`PipelineSession::dispatch`] -- one per
+/// [`DiffHandler::flush`][crate::diff::DiffHandler::flush] boundary -- over
+/// the same connection, instead of reconnecting per batch.
+pub struct PipelineSession {
+    rt: tokio::runtime::Runtime,
+    ldap: ldap3::Ldap,
+    max_inflight: usize,
+    continuous: bool,
+}
+
+impl PipelineSession {
+    /// Connect and (if credentials are configured) bind, ready to
+    /// [`dispatch`][Self::dispatch] batches of operations.
+    pub fn connect(cmdline: &Cmdline, continuous: bool, max_inflight: usize) -> Result<Self, String> {
+        let rt = tokio::runtime::Runtime::new()
+            .map_err(|e| format!("starting async runtime: {}", e))?;
+        let ldap = rt.block_on(connect_and_bind_async(cmdline))?;
+        Ok(PipelineSession {
+            rt,
+            ldap,
+            max_inflight: max_inflight.max(1),
+            continuous,
+        })
+    }
+
+    /// Dispatch one batch of operations concurrently, keeping up to
+    /// `max_inflight` outstanding at once instead of committing them one
+    /// round-trip at a time.
+    ///
+    /// Operations that `ldapvi::pipeline::compute_dependencies` marks as
+    /// depending on another operation in the batch (a child add under a
+    /// parent also being added, or anything under a subtree being moved by
+    /// a rename) wait for that operation to finish before starting;
+    /// everything else is free to run as soon as a slot opens up.
+    ///
+    /// Mirrors the synchronous path's `continuous` flag: once `continuous`
+    /// is false and an operation in this batch has failed, operations in
+    /// the same batch that have not yet started are skipped rather than
+    /// dispatched, though already in-flight ones are allowed to finish.
+    /// Results are returned in the same order as `ops`, so the caller can
+    /// correlate them back to the entry/offset each operation came from.
+    pub fn dispatch(&self, ops: Vec<Operation>) -> Vec<Result<(), LdapOpError>> {
+        self.rt.block_on(dispatch_batch(&self.ldap, ops, self.max_inflight, self.continuous))
+    }
+}
+
+async fn connect_and_bind_async(cmdline: &Cmdline) -> Result<ldap3::Ldap, String> {
+    let url = match cmdline.servers.first() {
+        Some(s) => server_url(s, cmdline.tls_mode),
+        None => "ldap://localhost".to_string(),
+    };
+
+    let (conn, mut ldap) = ldap3::LdapConnAsync::new(&url)
+        .await
+        .map_err(|e| format!("connect to {}: {}", url, e))?;
+    tokio::spawn(async move {
+        let _ = conn.drive().await;
+    });
+
+    if let (Some(user), Some(password)) = (&cmdline.user, &cmdline.password) {
+        let result = ldap
+            .simple_bind(user, password)
+            .await
+            .map_err(|e| format!("bind: {}", e))?;
+        if result.rc != 0 {
+            return Err(format!("bind failed: {} {}", result.rc, result.text));
+        }
+    }
+
+    Ok(ldap)
+}
+
+async fn dispatch_batch(
+    ldap: &ldap3::Ldap,
+    ops: Vec<Operation>,
+    max_inflight: usize,
+    continuous: bool,
+) -> Vec<Result<(), LdapOpError>> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use tokio::sync::{watch, Semaphore};
+
+    let deps = ldapvi::pipeline::compute_dependencies(&ops);
+    // `watch` rather than `Notify`: it retains the last-sent value, so a
+    // dependent that hasn't started waiting yet when its dependency
+    // finishes still observes "done" on its first `borrow()` instead of
+    // missing a `notify_waiters()` call that already fired (a lost wakeup
+    // that would otherwise hang the dependent forever, since every op in
+    // the batch is spawned concurrently with no ordering guarantee on
+    // when each task first awaits its dependencies).
+    let (done_txs, done_rxs): (Vec<_>, Vec<_>) =
+        (0..ops.len()).map(|_| watch::channel(false)).unzip();
+    let semaphore = Arc::new(Semaphore::new(max_inflight.max(1)));
+    let aborted = Arc::new(AtomicBool::new(false));
+
+    let mut tasks = Vec::with_capacity(ops.len());
+    for (i, op) in ops.into_iter().enumerate() {
+        let mut waits: Vec<watch::Receiver<bool>> =
+            deps[i].iter().map(|&j| done_rxs[j].clone()).collect();
+        let done = done_txs[i].clone();
+        let sem = semaphore.clone();
+        let aborted = aborted.clone();
+        let mut ldap = ldap.clone();
+
+        tasks.push(tokio::spawn(async move {
+            for w in &mut waits {
+                while !*w.borrow_and_update() {
+                    if w.changed().await.is_err() {
+                        break;
+                    }
+                }
+            }
+
+            // Check for an abort only once a slot is held, so an op that was
+            // still waiting for a slot when another failed is skipped too.
+            let _permit = sem.acquire().await.expect("semaphore closed");
+            let result = if aborted.load(Ordering::SeqCst) {
+                Err(LdapOpError {
+                    message: format!("{:?} {}: skipped after earlier failure", op.kind, op.dn),
+                    result_code: None,
+                })
+            } else {
+                let r = execute_pipelined_op(&mut ldap, &op).await;
+                if r.is_err() && !continuous {
+                    aborted.store(true, Ordering::SeqCst);
+                }
+                r
+            };
+
+            let _ = done.send(true);
+            (i, result)
+        }));
+    }
+
+    let mut results: Vec<(usize, Result<(), LdapOpError>)> = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        results.push(task.await.expect("pipelined commit task panicked"));
+    }
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, r)| r).collect()
+}
+
+async fn execute_pipelined_op(ldap: &mut ldap3::Ldap, op: &Operation) -> Result<(), LdapOpError> {
+    match op.kind {
+        PipelineOpKind::Add => {
+            let mut attr_map: Vec<(Vec<u8>, HashSet<Vec<u8>>)> = Vec::new();
+            for m in &op.mods {
+                if let Some(e) = duplicate_value_error(m) {
+                    return Err(e);
+                }
+                let attr = m.attr.clone().into_bytes();
+                let vals: HashSet<Vec<u8>> = m.values.iter().cloned().collect();
+                if let Some(existing) = attr_map.iter_mut().find(|(a, _)| *a == attr) {
+                    existing.1.extend(vals);
+                } else {
+                    attr_map.push((attr, vals));
+                }
+            }
+            let result = ldap.add(&op.dn, attr_map).await.map_err(|e| LdapOpError {
+                message: format!("add {}: {}", op.dn, e),
+                result_code: None,
+            })?;
+            if result.rc != 0 {
+                return Err(LdapOpError {
+                    message: format!("add {}: {} {}", op.dn, result.rc, result.text),
+                    result_code: Some(result.rc as u32),
+                });
+            }
+            Ok(())
+        }
+        PipelineOpKind::Delete => {
+            let result = ldap.delete(&op.dn).await.map_err(|e| LdapOpError {
+                message: format!("delete {}: {}", op.dn, e),
+                result_code: None,
+            })?;
+            if result.rc != 0 {
+                return Err(LdapOpError {
+                    message: format!("delete {}: {} {}", op.dn, result.rc, result.text),
+                    result_code: Some(result.rc as u32),
+                });
+            }
+            Ok(())
+        }
+        PipelineOpKind::Modify => {
+            let ldap3_mods: Vec<ldap3::Mod<Vec<u8>>> = op
+                .mods
+                .iter()
+                .map(ldapmod_to_ldap3_mod)
+                .collect::<Result<_, _>>()?;
+            let result = ldap
+                .modify(&op.dn, ldap3_mods)
+                .await
+                .map_err(|e| LdapOpError {
+                    message: format!("modify {}: {}", op.dn, e),
+                    result_code: None,
+                })?;
+            if result.rc != 0 {
+                return Err(LdapOpError {
+                    message: format!("modify {}: {} {}", op.dn, result.rc, result.text),
+                    result_code: Some(result.rc as u32),
+                });
+            }
+            Ok(())
+        }
+        PipelineOpKind::Rename => {
+            let new_dn = op.new_dn.as_deref().unwrap_or(&op.dn);
+            let (new_rdn, new_superior) = split_rdn(new_dn);
+            let result = ldap
+                .modifydn(&op.dn, new_rdn, op.delete_old_rdn, new_superior.as_deref())
+                .await
+                .map_err(|e| LdapOpError {
+                    message: format!("rename {}: {}", op.dn, e),
+                    result_code: None,
+                })?;
+            if result.rc != 0 {
+                return Err(LdapOpError {
+                    message: format!("rename {}: {} {}", op.dn, result.rc, result.text),
+                    result_code: Some(result.rc as u32),
+                });
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Split a DN into its leading RDN and remaining superior DN, for the
+/// rename operations queued by `ldapvi::pipeline`, which track only the
+/// full target DN rather than `(new_rdn, new_superior)` separately.
+fn split_rdn(dn: &str) -> (&str, Option<String>) {
+    let bytes = dn.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 1 < bytes.len() {
+            i += 2;
+        } else if bytes[i] == b',' {
+            return (&dn[..i], Some(dn[i + 1..].to_string()));
+        } else {
+            i += 1;
+        }
+    }
+    (dn, None)
+}
@@ -0,0 +1,596 @@
+//! A recursive-descent parser for RFC 4515 LDAP search filter strings,
+//! e.g. `(&(objectClass=person)(cn=John*))`. `ldapvi --ldapsearch` (and
+//! every other mode that takes a `-f`/positional search filter) used to
+//! pass that string straight to the server, so a typo only surfaced
+//! after a round trip; [`parse`] validates balanced parens and attribute
+//! syntax up front and reports a byte offset a caller can point at.
+//!
+//! The resulting [`Filter`] tree is also what `--dump-filter` pretty-
+//! prints via [`format`], and is meant to be the thing a future
+//! interactive filter editor round-trips through -- hence keeping it a
+//! plain data type rather than, say, re-deriving a string each time.
+//!
+//! Unrelated to [`crate::filter`], which is a small prefix-notation DSL
+//! for filtering entries *already fetched* on the client side (`--select`);
+//! this module instead validates the server-bound LDAP search filter.
+
+use crate::escape::{self, Mode as EscapeMode};
+
+/// One piece of a substring filter's value, e.g. `(cn=Jo*n*n)` is
+/// `Substring("cn", vec![Initial(b"Jo"), Any(b"n"), Final(b"n")])`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubstringPart {
+    Initial(Vec<u8>),
+    Any(Vec<u8>),
+    Final(Vec<u8>),
+}
+
+/// A parsed RFC 4515 search filter. Approximate match (`~=`) and
+/// extensible match (`:dn:...:=`) filters are not produced by [`parse`];
+/// see its doc comment for why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+    Equality(String, Vec<u8>),
+    Presence(String),
+    Substring(String, Vec<SubstringPart>),
+    GreaterOrEqual(String, Vec<u8>),
+    LessOrEqual(String, Vec<u8>),
+}
+
+/// A [`parse`] failure, located at the byte offset into the original
+/// filter string that it was found at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterParseError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for FilterParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "at byte {}: {}", self.offset, self.message)
+    }
+}
+
+impl std::error::Error for FilterParseError {}
+
+fn err(offset: usize, message: impl Into<String>) -> FilterParseError {
+    FilterParseError {
+        offset,
+        message: message.into(),
+    }
+}
+
+/// Parse an RFC 4515 filter string such as `(&(objectClass=person)(cn=J*))`.
+/// `~=` (approximate match) and `:...:=` (extensible match) are rejected
+/// with a diagnostic rather than silently dropped, since neither has a
+/// [`Filter`] variant to round-trip through yet.
+pub fn parse(input: &str) -> Result<Filter, FilterParseError> {
+    let bytes = input.as_bytes();
+    let mut pos = 0;
+    let filter = parse_filter(bytes, &mut pos)?;
+    if pos != bytes.len() {
+        return Err(err(pos, "unexpected trailing input after filter"));
+    }
+    Ok(filter)
+}
+
+fn parse_filter(bytes: &[u8], pos: &mut usize) -> Result<Filter, FilterParseError> {
+    expect(bytes, pos, b'(')?;
+    let filter = parse_filtercomp(bytes, pos)?;
+    expect(bytes, pos, b')')?;
+    Ok(filter)
+}
+
+fn expect(bytes: &[u8], pos: &mut usize, want: u8) -> Result<(), FilterParseError> {
+    match bytes.get(*pos) {
+        Some(&b) if b == want => {
+            *pos += 1;
+            Ok(())
+        }
+        Some(_) => Err(err(*pos, format!("expected '{}'", want as char))),
+        None => Err(err(*pos, format!("expected '{}' but filter ended", want as char))),
+    }
+}
+
+fn parse_filtercomp(bytes: &[u8], pos: &mut usize) -> Result<Filter, FilterParseError> {
+    match bytes.get(*pos) {
+        Some(b'&') => {
+            *pos += 1;
+            Ok(Filter::And(parse_filterlist(bytes, pos)?))
+        }
+        Some(b'|') => {
+            *pos += 1;
+            Ok(Filter::Or(parse_filterlist(bytes, pos)?))
+        }
+        Some(b'!') => {
+            *pos += 1;
+            Ok(Filter::Not(Box::new(parse_filter(bytes, pos)?)))
+        }
+        Some(_) => parse_item(bytes, pos),
+        None => Err(err(*pos, "filter ended where a filter component was expected")),
+    }
+}
+
+fn parse_filterlist(bytes: &[u8], pos: &mut usize) -> Result<Vec<Filter>, FilterParseError> {
+    let mut filters = Vec::new();
+    while bytes.get(*pos) == Some(&b'(') {
+        filters.push(parse_filter(bytes, pos)?);
+    }
+    if filters.is_empty() {
+        return Err(err(*pos, "'&'/'|' requires at least one filter"));
+    }
+    Ok(filters)
+}
+
+fn parse_item(bytes: &[u8], pos: &mut usize) -> Result<Filter, FilterParseError> {
+    let start = *pos;
+    let attr = parse_attr(bytes, pos)?;
+    match bytes.get(*pos) {
+        Some(b'=') => {
+            *pos += 1;
+            if bytes.get(*pos) == Some(&b'*') && bytes.get(*pos + 1) == Some(&b')') {
+                *pos += 1;
+                return Ok(Filter::Presence(attr));
+            }
+            let value_start = *pos;
+            let value_end = find_value_end(bytes, value_start)?;
+            let raw = &bytes[value_start..value_end];
+            *pos = value_end;
+            if raw.contains(&b'*') {
+                Ok(Filter::Substring(attr, parse_substring(raw, value_start)?))
+            } else {
+                Ok(Filter::Equality(attr, unescape_value(raw, value_start)?))
+            }
+        }
+        Some(b'>') if bytes.get(*pos + 1) == Some(&b'=') => {
+            *pos += 2;
+            let value_start = *pos;
+            let value_end = find_value_end(bytes, value_start)?;
+            let value = unescape_value(&bytes[value_start..value_end], value_start)?;
+            *pos = value_end;
+            Ok(Filter::GreaterOrEqual(attr, value))
+        }
+        Some(b'<') if bytes.get(*pos + 1) == Some(&b'=') => {
+            *pos += 2;
+            let value_start = *pos;
+            let value_end = find_value_end(bytes, value_start)?;
+            let value = unescape_value(&bytes[value_start..value_end], value_start)?;
+            *pos = value_end;
+            Ok(Filter::LessOrEqual(attr, value))
+        }
+        Some(b'~') if bytes.get(*pos + 1) == Some(&b'=') => {
+            Err(err(start, "approximate match '~=' is not supported"))
+        }
+        Some(b':') => Err(err(start, "extensible match filters are not supported")),
+        Some(&c) => Err(err(*pos, format!("unexpected '{}' after attribute description", c as char))),
+        None => Err(err(*pos, "filter ended inside an item")),
+    }
+}
+
+fn unescape_value(raw: &[u8], value_start: usize) -> Result<Vec<u8>, FilterParseError> {
+    escape::unescape(raw, EscapeMode::FilterValue)
+        .map_err(|e| err(value_start + e.position, "invalid '\\XX' escape in filter value"))
+}
+
+/// Scan forward from `start` (just past `=`/`>=`/`<=`) to the `)` that
+/// closes this item, skipping over `\XX` escapes so an escaped `)` (i.e.
+/// `\29`) doesn't end the value early.
+fn find_value_end(bytes: &[u8], start: usize) -> Result<usize, FilterParseError> {
+    let mut pos = start;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b')' => return Ok(pos),
+            b'(' => return Err(err(pos, "unescaped '(' inside a filter value")),
+            b'\\' => pos += 2,
+            _ => pos += 1,
+        }
+    }
+    Err(err(start, "filter value is not terminated by ')'"))
+}
+
+fn parse_substring(raw: &[u8], value_start: usize) -> Result<Vec<SubstringPart>, FilterParseError> {
+    let segments = split_unescaped_star(raw);
+    let last = segments.len() - 1;
+    let mut parts = Vec::new();
+    for (i, (seg, seg_offset)) in segments.into_iter().enumerate() {
+        if seg.is_empty() {
+            continue;
+        }
+        let value = unescape_value(seg, value_start + seg_offset)?;
+        parts.push(if i == 0 {
+            SubstringPart::Initial(value)
+        } else if i == last {
+            SubstringPart::Final(value)
+        } else {
+            SubstringPart::Any(value)
+        });
+    }
+    if parts.is_empty() {
+        return Err(err(value_start, "substring filter has no value around its '*'s"));
+    }
+    Ok(parts)
+}
+
+/// Split `raw` on unescaped `*` bytes, returning each segment with its
+/// byte offset into `raw`.
+fn split_unescaped_star(raw: &[u8]) -> Vec<(&[u8], usize)> {
+    let mut segments = Vec::new();
+    let mut seg_start = 0;
+    let mut i = 0;
+    while i < raw.len() {
+        match raw[i] {
+            b'*' => {
+                segments.push((&raw[seg_start..i], seg_start));
+                i += 1;
+                seg_start = i;
+            }
+            b'\\' => i += 2,
+            _ => i += 1,
+        }
+    }
+    segments.push((&raw[seg_start..], seg_start));
+    segments
+}
+
+/// `descr` (an alpha-leading attribute name, with `-` and digits allowed
+/// after the first character, plus `;option` suffixes) or a `numericoid`
+/// (dot-separated digit runs), per RFC 4512's `AttributeDescription`.
+fn parse_attr(bytes: &[u8], pos: &mut usize) -> Result<String, FilterParseError> {
+    let start = *pos;
+    match bytes.get(*pos) {
+        Some(c) if c.is_ascii_digit() => parse_numericoid(bytes, pos)?,
+        Some(c) if c.is_ascii_alphabetic() => {
+            *pos += 1;
+            while matches!(bytes.get(*pos), Some(c) if c.is_ascii_alphanumeric() || *c == b'-') {
+                *pos += 1;
+            }
+        }
+        _ => return Err(err(start, "expected an attribute description")),
+    }
+    while bytes.get(*pos) == Some(&b';') {
+        *pos += 1;
+        let opt_start = *pos;
+        while matches!(bytes.get(*pos), Some(c) if c.is_ascii_alphanumeric() || *c == b'-') {
+            *pos += 1;
+        }
+        if *pos == opt_start {
+            return Err(err(opt_start, "expected an attribute option after ';'"));
+        }
+    }
+    Ok(std::str::from_utf8(&bytes[start..*pos]).unwrap().to_string())
+}
+
+fn parse_numericoid(bytes: &[u8], pos: &mut usize) -> Result<(), FilterParseError> {
+    loop {
+        let digit_start = *pos;
+        while matches!(bytes.get(*pos), Some(c) if c.is_ascii_digit()) {
+            *pos += 1;
+        }
+        if *pos == digit_start {
+            return Err(err(digit_start, "expected a digit in numeric OID"));
+        }
+        if bytes.get(*pos) == Some(&b'.') {
+            *pos += 1;
+        } else {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Render `filter` back to the canonical RFC 4515 text that [`parse`]
+/// accepts -- used by `--dump-filter` and meant for round-tripping
+/// through future interactive filter editing.
+pub fn format(filter: &Filter) -> String {
+    let mut out = String::new();
+    format_into(filter, &mut out);
+    out
+}
+
+fn format_into(filter: &Filter, out: &mut String) {
+    match filter {
+        Filter::And(children) => format_combinator(out, '&', children),
+        Filter::Or(children) => format_combinator(out, '|', children),
+        Filter::Not(inner) => {
+            out.push_str("(!");
+            format_into(inner, out);
+            out.push(')');
+        }
+        Filter::Equality(attr, value) => format_simple(out, attr, '=', value),
+        Filter::Presence(attr) => {
+            out.push('(');
+            out.push_str(attr);
+            out.push_str("=*)");
+        }
+        Filter::Substring(attr, parts) => format_substring(out, attr, parts),
+        Filter::GreaterOrEqual(attr, value) => {
+            out.push('(');
+            out.push_str(attr);
+            out.push_str(">=");
+            out.push_str(&format_value(value));
+            out.push(')');
+        }
+        Filter::LessOrEqual(attr, value) => {
+            out.push('(');
+            out.push_str(attr);
+            out.push_str("<=");
+            out.push_str(&format_value(value));
+            out.push(')');
+        }
+    }
+}
+
+fn format_combinator(out: &mut String, op: char, children: &[Filter]) {
+    out.push('(');
+    out.push(op);
+    for child in children {
+        format_into(child, out);
+    }
+    out.push(')');
+}
+
+fn format_simple(out: &mut String, attr: &str, op: char, value: &[u8]) {
+    out.push('(');
+    out.push_str(attr);
+    out.push(op);
+    out.push_str(&format_value(value));
+    out.push(')');
+}
+
+fn format_substring(out: &mut String, attr: &str, parts: &[SubstringPart]) {
+    out.push('(');
+    out.push_str(attr);
+    out.push('=');
+    let starts_with_initial = matches!(parts.first(), Some(SubstringPart::Initial(_)));
+    let ends_with_final = matches!(parts.last(), Some(SubstringPart::Final(_)));
+    if !starts_with_initial {
+        out.push('*');
+    }
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            out.push('*');
+        }
+        let value = match part {
+            SubstringPart::Initial(v) | SubstringPart::Any(v) | SubstringPart::Final(v) => v,
+        };
+        out.push_str(&format_value(value));
+    }
+    if !ends_with_final {
+        out.push('*');
+    }
+    out.push(')');
+}
+
+fn format_value(value: &[u8]) -> String {
+    String::from_utf8_lossy(&escape::escape(value, EscapeMode::FilterValue)).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── Group 1: leaf filters ──
+
+    #[test]
+    fn parse_equality() {
+        assert_eq!(
+            parse("(cn=John Doe)").unwrap(),
+            Filter::Equality("cn".to_string(), b"John Doe".to_vec())
+        );
+    }
+
+    #[test]
+    fn parse_presence() {
+        assert_eq!(parse("(mail=*)").unwrap(), Filter::Presence("mail".to_string()));
+    }
+
+    #[test]
+    fn parse_greater_or_equal() {
+        assert_eq!(
+            parse("(age>=18)").unwrap(),
+            Filter::GreaterOrEqual("age".to_string(), b"18".to_vec())
+        );
+    }
+
+    #[test]
+    fn parse_less_or_equal() {
+        assert_eq!(
+            parse("(age<=65)").unwrap(),
+            Filter::LessOrEqual("age".to_string(), b"65".to_vec())
+        );
+    }
+
+    #[test]
+    fn parse_numeric_oid_attribute() {
+        assert_eq!(
+            parse("(2.5.4.3=John)").unwrap(),
+            Filter::Equality("2.5.4.3".to_string(), b"John".to_vec())
+        );
+    }
+
+    #[test]
+    fn parse_attribute_with_option() {
+        assert_eq!(
+            parse("(cn;lang-en=John)").unwrap(),
+            Filter::Equality("cn;lang-en".to_string(), b"John".to_vec())
+        );
+    }
+
+    // ── Group 2: combinators ──
+
+    #[test]
+    fn parse_and() {
+        assert_eq!(
+            parse("(&(cn=John)(sn=Doe))").unwrap(),
+            Filter::And(vec![
+                Filter::Equality("cn".to_string(), b"John".to_vec()),
+                Filter::Equality("sn".to_string(), b"Doe".to_vec()),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_or() {
+        assert_eq!(
+            parse("(|(cn=John)(cn=Jane))").unwrap(),
+            Filter::Or(vec![
+                Filter::Equality("cn".to_string(), b"John".to_vec()),
+                Filter::Equality("cn".to_string(), b"Jane".to_vec()),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_not() {
+        assert_eq!(
+            parse("(!(cn=John))").unwrap(),
+            Filter::Not(Box::new(Filter::Equality("cn".to_string(), b"John".to_vec())))
+        );
+    }
+
+    #[test]
+    fn parse_nested_combinators() {
+        let filter = parse("(&(objectClass=person)(|(cn=John)(!(cn=Jane))))").unwrap();
+        assert_eq!(
+            filter,
+            Filter::And(vec![
+                Filter::Equality("objectClass".to_string(), b"person".to_vec()),
+                Filter::Or(vec![
+                    Filter::Equality("cn".to_string(), b"John".to_vec()),
+                    Filter::Not(Box::new(Filter::Equality("cn".to_string(), b"Jane".to_vec()))),
+                ]),
+            ])
+        );
+    }
+
+    #[test]
+    fn and_or_reject_empty_list() {
+        assert!(parse("(&)").is_err());
+        assert!(parse("(|)").is_err());
+    }
+
+    // ── Group 3: substrings ──
+
+    #[test]
+    fn parse_substring_initial_any_final() {
+        assert_eq!(
+            parse("(cn=Jo*h*n)").unwrap(),
+            Filter::Substring(
+                "cn".to_string(),
+                vec![
+                    SubstringPart::Initial(b"Jo".to_vec()),
+                    SubstringPart::Any(b"h".to_vec()),
+                    SubstringPart::Final(b"n".to_vec()),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn parse_substring_trailing_star() {
+        assert_eq!(
+            parse("(cn=Jo*)").unwrap(),
+            Filter::Substring("cn".to_string(), vec![SubstringPart::Initial(b"Jo".to_vec())])
+        );
+    }
+
+    #[test]
+    fn parse_substring_leading_star() {
+        assert_eq!(
+            parse("(cn=*hn)").unwrap(),
+            Filter::Substring("cn".to_string(), vec![SubstringPart::Final(b"hn".to_vec())])
+        );
+    }
+
+    #[test]
+    fn parse_substring_any_only() {
+        assert_eq!(
+            parse("(cn=*oh*)").unwrap(),
+            Filter::Substring("cn".to_string(), vec![SubstringPart::Any(b"oh".to_vec())])
+        );
+    }
+
+    #[test]
+    fn parse_substring_escaped_star_is_not_a_wildcard() {
+        assert_eq!(
+            parse("(cn=a\\2ab)").unwrap(),
+            Filter::Equality("cn".to_string(), b"a*b".to_vec())
+        );
+    }
+
+    // ── Group 4: byte-offset diagnostics ──
+
+    #[test]
+    fn error_points_at_unescaped_paren() {
+        let e = parse("(cn=a(b)").unwrap_err();
+        assert_eq!(e.offset, 5);
+    }
+
+    #[test]
+    fn error_points_at_missing_close_paren() {
+        let e = parse("(cn=John").unwrap_err();
+        assert_eq!(e.offset, 4);
+    }
+
+    #[test]
+    fn error_points_at_bad_hex_escape() {
+        let e = parse("(cn=a\\zzb)").unwrap_err();
+        assert_eq!(e.offset, 5);
+    }
+
+    #[test]
+    fn error_on_unsupported_approximate_match() {
+        let e = parse("(cn~=John)").unwrap_err();
+        assert_eq!(e.offset, 1);
+    }
+
+    #[test]
+    fn error_on_unsupported_extensible_match() {
+        let e = parse("(cn:dn:=John)").unwrap_err();
+        assert_eq!(e.offset, 1);
+    }
+
+    #[test]
+    fn error_on_missing_leading_paren() {
+        let e = parse("cn=John").unwrap_err();
+        assert_eq!(e.offset, 0);
+    }
+
+    #[test]
+    fn error_on_trailing_input() {
+        let e = parse("(cn=John)(sn=Doe)").unwrap_err();
+        assert_eq!(e.offset, 9);
+    }
+
+    // ── Group 5: pretty-printing round-trips ──
+
+    #[test]
+    fn format_round_trips_equality() {
+        let filter = parse("(cn=John Doe)").unwrap();
+        assert_eq!(format(&filter), "(cn=John Doe)");
+    }
+
+    #[test]
+    fn format_round_trips_nested() {
+        let text = "(&(objectClass=person)(|(cn=John)(!(cn=Jane))))";
+        let filter = parse(text).unwrap();
+        assert_eq!(format(&filter), text);
+    }
+
+    #[test]
+    fn format_round_trips_substrings() {
+        for text in ["(cn=Jo*h*n)", "(cn=Jo*)", "(cn=*hn)", "(cn=*oh*)"] {
+            let filter = parse(text).unwrap();
+            assert_eq!(format(&filter), text);
+        }
+    }
+
+    #[test]
+    fn format_escapes_special_bytes_in_values() {
+        let filter = Filter::Equality("cn".to_string(), b"a(b)c*d".to_vec());
+        assert_eq!(format(&filter), "(cn=a\\28b\\29c\\2ad)");
+    }
+}
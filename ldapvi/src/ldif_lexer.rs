@@ -0,0 +1,326 @@
+//! Byte-level LDIF tokenizer -- the pure lexing core split out of
+//! `LdifParser`, along the lines of rustc_lexer's separation of "pure
+//! lexing" from spans, interning and error reporting.
+//!
+//! [`tokenize`] walks a complete in-memory LDIF buffer and yields a flat
+//! stream of [`Token`]s, one per logical (fold-joined) line, with no
+//! semantic interpretation: it knows the shapes `dn:`, `changetype:`,
+//! `control:` and `version:` are special, but nothing about record
+//! boundaries, change types, or what a `ldapvi-key:` line means, and it
+//! never decodes Base64 or fetches a `file://` URL.
+//!
+//! Line folding (a hard line break immediately followed by a single space,
+//! RFC 2849 section 3) is recognized while splitting lines -- a token's
+//! `range` spans the whole folded run -- but is only *stripped* lazily, via
+//! [`Token::unfolded`], so that the common unfolded case never allocates.
+//!
+//! `LdifParser::read_entry` (see `parseldif.rs`) keeps its own streaming
+//! `CharReader` for now rather than building on this module directly, since
+//! it must support arbitrarily large `Read + Seek` sources that don't fit
+//! in memory; this tokenizer is for callers -- fuzz targets, external
+//! tools, standalone tests -- that already have the bytes in hand and want
+//! lexing without pulling in the rest of the parser.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// A lexical token: its kind, plus the byte range into the buffer passed to
+/// [`tokenize`] that it spans. For `AttrName` the range covers the bytes
+/// before the `:`, not the `:` itself; for every value kind it covers the
+/// bytes after the `:`/`::`/`:<` marker and any spaces following it, up to
+/// (but not including) the line-ending newline -- folded continuations
+/// included raw, see [`Token::unfolded`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub range: Range<usize>,
+}
+
+/// What a [`Token`] represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// The blank line separating two records (or ending the stream).
+    RecordSeparator,
+    /// A `#`-introduced comment line.
+    Comment,
+    /// A `version:` line's value.
+    Version,
+    /// A `dn:` line's value.
+    DnLine,
+    /// The attribute description to the left of an ordinary value line's
+    /// `:` (always immediately followed by one of the four value kinds
+    /// below).
+    AttrName,
+    /// A plain SAFE-STRING value (no `:`/`<` marker after the colon).
+    PlainValue,
+    /// A `::`-marked value, still in Base64 text form (not decoded).
+    Base64Value,
+    /// A `:<`-marked value, the URL text (not fetched).
+    UrlValue,
+    /// A `changetype:` line's value.
+    ChangeType,
+    /// A `control:` line's value.
+    Control,
+    /// A bare `-` line, ending a modify operation's value list.
+    Dash,
+}
+
+impl Token {
+    /// This token's content with line folding removed. Borrows directly
+    /// from `buf` when the token's range contains no fold; allocates only
+    /// for the (comparatively rare) folded case.
+    pub fn unfolded<'a>(&self, buf: &'a [u8]) -> Cow<'a, [u8]> {
+        let raw = &buf[self.range.clone()];
+        if !has_fold(raw) {
+            return Cow::Borrowed(raw);
+        }
+        let mut out = Vec::with_capacity(raw.len());
+        let mut i = 0;
+        while i < raw.len() {
+            if raw[i] == b'\n' && raw.get(i + 1) == Some(&b' ') {
+                // Drop the newline (and, if present, the '\r' right before
+                // it) together with the single space that marks the fold.
+                if out.last() == Some(&b'\r') {
+                    out.pop();
+                }
+                i += 2;
+            } else {
+                out.push(raw[i]);
+                i += 1;
+            }
+        }
+        Cow::Owned(out)
+    }
+}
+
+fn has_fold(raw: &[u8]) -> bool {
+    raw.windows(2).any(|w| w == b"\n ")
+}
+
+/// A tokenizing failure: `buf` has a non-blank line with no `:` to
+/// separate an attribute description from its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexError {
+    /// Byte offset of the start of the offending line.
+    pub position: usize,
+    pub message: String,
+}
+
+/// Split `buf` into a flat stream of [`Token`]s. Never partially consumes a
+/// malformed line: on error, `position` points at the start of that line.
+pub fn tokenize(buf: &[u8]) -> Result<Vec<Token>, LexError> {
+    let mut tokens = Vec::new();
+    let mut pos = 0usize;
+    while pos < buf.len() {
+        let start = pos;
+        let (content_end, next_pos) = scan_logical_line(buf, pos);
+        pos = next_pos;
+        let raw = &buf[start..content_end];
+
+        if raw.is_empty() {
+            tokens.push(Token {
+                kind: TokenKind::RecordSeparator,
+                range: start..content_end,
+            });
+            continue;
+        }
+        if raw[0] == b'#' {
+            tokens.push(Token {
+                kind: TokenKind::Comment,
+                range: start..content_end,
+            });
+            continue;
+        }
+        if raw == b"-" {
+            tokens.push(Token {
+                kind: TokenKind::Dash,
+                range: start..content_end,
+            });
+            continue;
+        }
+
+        let colon = raw.iter().position(|&b| b == b':').ok_or_else(|| LexError {
+            position: start,
+            message: "line has no ':' separating name from value".to_string(),
+        })?;
+        let name = &raw[..colon];
+        let (value_kind, value_off) = classify_value_start(raw, colon + 1);
+        let value_start = start + value_off;
+
+        match name {
+            b"dn" => tokens.push(Token {
+                kind: TokenKind::DnLine,
+                range: value_start..content_end,
+            }),
+            b"version" => tokens.push(Token {
+                kind: TokenKind::Version,
+                range: value_start..content_end,
+            }),
+            b"changetype" => tokens.push(Token {
+                kind: TokenKind::ChangeType,
+                range: value_start..content_end,
+            }),
+            b"control" => tokens.push(Token {
+                kind: TokenKind::Control,
+                range: value_start..content_end,
+            }),
+            _ => {
+                tokens.push(Token {
+                    kind: TokenKind::AttrName,
+                    range: start..(start + colon),
+                });
+                tokens.push(Token {
+                    kind: value_kind,
+                    range: value_start..content_end,
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Find the end of the logical (fold-joined) line starting at `start`, and
+/// the position right after it where the next line begins. Mirrors
+/// `CharReader`-based folding in `parseldif.rs`: a line continues if its
+/// newline is immediately followed by a single space.
+fn scan_logical_line(buf: &[u8], start: usize) -> (usize, usize) {
+    let mut cursor = start;
+    loop {
+        match memchr_newline(&buf[cursor..]) {
+            None => return (buf.len(), buf.len()),
+            Some(rel_nl) => {
+                let nl = cursor + rel_nl;
+                match buf.get(nl + 1) {
+                    Some(b' ') => cursor = nl + 2, // folded -- keep scanning
+                    _ => {
+                        let content_end = if nl > start && buf[nl - 1] == b'\r' {
+                            nl - 1
+                        } else {
+                            nl
+                        };
+                        return (content_end, nl + 1);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn memchr_newline(buf: &[u8]) -> Option<usize> {
+    buf.iter().position(|&b| b == b'\n')
+}
+
+/// After the `:`, skip any spaces and report which of the three value
+/// markers (if any) follows, plus the byte offset the value itself starts
+/// at. `idx` is the offset of the first byte after the `:`.
+fn classify_value_start(raw: &[u8], mut idx: usize) -> (TokenKind, usize) {
+    while idx < raw.len() && raw[idx] == b' ' {
+        idx += 1;
+    }
+    let (kind, mut idx) = if idx < raw.len() && raw[idx] == b':' {
+        (TokenKind::Base64Value, idx + 1)
+    } else if idx < raw.len() && raw[idx] == b'<' {
+        (TokenKind::UrlValue, idx + 1)
+    } else {
+        (TokenKind::PlainValue, idx)
+    };
+    while idx < raw.len() && raw[idx] == b' ' {
+        idx += 1;
+    }
+    (kind, idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(tokens: &[Token]) -> Vec<TokenKind> {
+        tokens.iter().map(|t| t.kind).collect()
+    }
+
+    #[test]
+    fn simple_attrval_record() {
+        let buf = b"dn: cn=foo,dc=example\ncn: foo\nobjectClass: top\n\n";
+        let tokens = tokenize(buf).unwrap();
+        assert_eq!(
+            kinds(&tokens),
+            vec![
+                TokenKind::DnLine,
+                TokenKind::AttrName,
+                TokenKind::PlainValue,
+                TokenKind::AttrName,
+                TokenKind::PlainValue,
+                TokenKind::RecordSeparator,
+            ]
+        );
+        let dn = tokens[0].unfolded(buf);
+        assert_eq!(&*dn, b"cn=foo,dc=example");
+    }
+
+    #[test]
+    fn comment_and_version_and_dash() {
+        let buf = b"version: 1\n# a comment\ndn: cn=x\nchangetype: modify\nadd: mail\nmail: a@b\n-\n\n";
+        let tokens = tokenize(buf).unwrap();
+        assert_eq!(
+            kinds(&tokens),
+            vec![
+                TokenKind::Version,
+                TokenKind::Comment,
+                TokenKind::DnLine,
+                TokenKind::ChangeType,
+                TokenKind::AttrName,
+                TokenKind::PlainValue,
+                TokenKind::AttrName,
+                TokenKind::PlainValue,
+                TokenKind::Dash,
+                TokenKind::RecordSeparator,
+            ]
+        );
+    }
+
+    #[test]
+    fn base64_and_url_markers() {
+        let buf = b"dn:: Y249Zm9v\njpegPhoto:: YWJj\nlabeledURI:< file:///tmp/x\n\n";
+        let tokens = tokenize(buf).unwrap();
+        assert_eq!(tokens[0].kind, TokenKind::DnLine);
+        assert_eq!(&*tokens[0].unfolded(buf), b"Y249Zm9v");
+        assert_eq!(
+            kinds(&tokens[1..]),
+            vec![
+                TokenKind::AttrName,
+                TokenKind::Base64Value,
+                TokenKind::AttrName,
+                TokenKind::UrlValue,
+                TokenKind::RecordSeparator,
+            ]
+        );
+    }
+
+    #[test]
+    fn folded_value_is_stitched_back_together_lazily() {
+        let buf = b"dn: cn=foo\ndescription: a very long line that got\n  folded onto a second\n  physical line\n\n";
+        let tokens = tokenize(buf).unwrap();
+        // description's value token
+        let value = &tokens[2];
+        assert_eq!(value.kind, TokenKind::PlainValue);
+        // Raw range still contains the fold markers.
+        assert!(buf[value.range.clone()].windows(2).any(|w| w == b"\n "));
+        let unfolded = value.unfolded(buf);
+        assert_eq!(
+            &*unfolded,
+            &b"a very long line that got folded onto a second physical line"[..]
+        );
+    }
+
+    #[test]
+    fn missing_colon_is_an_error_at_line_start() {
+        let buf = b"dn: cn=foo\ngarbage line with no colon\n\n";
+        let err = tokenize(buf).unwrap_err();
+        assert_eq!(err.position, 11);
+    }
+
+    #[test]
+    fn empty_input_yields_no_tokens() {
+        assert_eq!(tokenize(b"").unwrap(), vec![]);
+    }
+}
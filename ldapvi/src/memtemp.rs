@@ -0,0 +1,171 @@
+//! An anonymous, memfd-backed stand-in for a named temp file.
+//!
+//! [`interactive::edit`](crate::interactive::edit) and
+//! [`interactive::view`](crate::interactive::view) normally operate on a
+//! real path under a `tempfile::tempdir()`, which means an entry's
+//! `userPassword` (or any other sensitive attribute) is written out in
+//! cleartext to disk -- and potentially to swap or a backup snapshot --
+//! for the whole duration of the edit. [`TempFile`] gives those two
+//! callers a buffer backed by `memfd_create(2)` instead: the bytes never
+//! get a directory entry, so there's nothing on disk to leak.
+//!
+//! The editor still needs *some* path to open, so we hand it
+//! `/proc/self/fd/<n>`, the magic symlink procfs maintains for each open
+//! file descriptor. That only works if the child process inherits the
+//! fd at the same number, which means clearing `FD_CLOEXEC` right before
+//! spawning it -- see [`TempFile::allow_child_access`].
+//!
+//! The one real hazard: editors that save by writing a new file and
+//! renaming it over the target (vim's default `backup`/`writebackup`
+//! behavior) rather than rewriting in place. `/proc/self/fd/<n>` is a
+//! virtual entry, not a real directory entry on a writable filesystem,
+//! so that rename either fails outright or silently leaves our buffer
+//! untouched -- there is no portable way to observe *why* it failed from
+//! here. [`TempFile::read_back`] can only flag the suspicious case where
+//! the buffer came back empty after a non-empty edit; callers should
+//! treat that as "probably didn't save" and tell the user to either
+//! force in-place writes (e.g. vim: `:set nobackup nowritebackup
+//! backupcopy=yes`, or `$EDITOR="vim --cmd 'set backupcopy=yes'"`) or
+//! fall back to a real named temp file.
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::fd::{AsFd, AsRawFd};
+
+use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+use nix::sys::memfd::{memfd_create, MFdFlags};
+
+/// An in-RAM, path-less temp file, reachable only via its own
+/// `/proc/self/fd/<n>` entry for the lifetime of this process.
+pub struct TempFile {
+    file: std::fs::File,
+    fd_num: i32,
+    proc_path: String,
+}
+
+impl TempFile {
+    /// Create a new anonymous buffer. `name` is cosmetic -- it shows up
+    /// in `/proc/self/fd/<n>`'s symlink target and in `lsof`-style
+    /// listings, purely to help a human debugging a stuck editor; it is
+    /// not a path and nothing can open it by that name.
+    pub fn new(name: &str) -> nix::Result<TempFile> {
+        let owned_fd = memfd_create(name, MFdFlags::MFD_CLOEXEC)?;
+        let fd_num = owned_fd.as_raw_fd();
+        let file = std::fs::File::from(owned_fd);
+        Ok(TempFile {
+            file,
+            fd_num,
+            proc_path: format!("/proc/self/fd/{}", fd_num),
+        })
+    }
+
+    /// The `/proc/self/fd/<n>` path a child process of *this* process can
+    /// open to reach the buffer, provided it inherited the descriptor --
+    /// see [`TempFile::allow_child_access`].
+    pub fn proc_path(&self) -> &str {
+        &self.proc_path
+    }
+
+    /// Like [`TempFile::proc_path`], but qualified with this process's
+    /// real pid instead of `self`, so an *external* process (e.g. the
+    /// non-interactive mode's control-fd driver) can open the same
+    /// descriptor by path. `self` only resolves correctly from inside
+    /// this process; a different process's `/proc/self` is its own.
+    pub fn pid_proc_path(&self) -> String {
+        format!("/proc/{}/fd/{}", std::process::id(), self.fd_num)
+    }
+
+    /// Clear `FD_CLOEXEC` on the underlying descriptor. `memfd_create` is
+    /// made with `MFD_CLOEXEC` set so it doesn't leak into children we
+    /// don't intend to hand it to; call this right before spawning the
+    /// one editor/pager child that should inherit it.
+    pub fn allow_child_access(&self) -> nix::Result<()> {
+        fcntl(self.file.as_fd(), FcntlArg::F_SETFD(FdFlag::empty()))?;
+        Ok(())
+    }
+
+    /// Replace the buffer's contents with `data` and rewind to the start,
+    /// so a subsequently-spawned editor sees exactly `data` and nothing
+    /// left over from a previous length.
+    pub fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        self.file.set_len(0)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(data)?;
+        self.file.seek(SeekFrom::Start(0))?;
+        Ok(())
+    }
+
+    /// Read the buffer back after an editor has run against
+    /// [`TempFile::proc_path`]. Returns an error if `original_len` was
+    /// nonzero but the buffer came back empty -- the signature of a save
+    /// that silently failed because the editor tried to rename a
+    /// replacement file over our `/proc/self/fd/<n>` path instead of
+    /// writing in place (see the module docs).
+    pub fn read_back(&mut self, original_len: usize) -> io::Result<Vec<u8>> {
+        self.file.seek(SeekFrom::Start(0))?;
+        let mut buf = Vec::new();
+        self.file.read_to_end(&mut buf)?;
+        if original_len > 0 && buf.is_empty() {
+            return Err(io::Error::other(
+                "editor left the buffer empty -- it likely saved by renaming a \
+                 replacement file over /proc/self/fd/<n> instead of writing in \
+                 place; force in-place writes (e.g. vim: set nobackup \
+                 nowritebackup backupcopy=yes) or use a named temp file instead",
+            ));
+        }
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_written_data() {
+        let mut tmp = TempFile::new("ldapvi-test").unwrap();
+        tmp.write_all(b"dn: cn=foo,dc=example,dc=com\n").unwrap();
+        let data = tmp.read_back(0).unwrap();
+        assert_eq!(data, b"dn: cn=foo,dc=example,dc=com\n");
+    }
+
+    #[test]
+    fn proc_path_points_at_an_open_fd_style_entry() {
+        let tmp = TempFile::new("ldapvi-test").unwrap();
+        assert!(tmp.proc_path().starts_with("/proc/self/fd/"));
+    }
+
+    #[test]
+    fn rewriting_shrinks_a_longer_previous_buffer() {
+        let mut tmp = TempFile::new("ldapvi-test").unwrap();
+        tmp.write_all(b"a much longer first value").unwrap();
+        tmp.write_all(b"short").unwrap();
+        let data = tmp.read_back(0).unwrap();
+        assert_eq!(data, b"short");
+    }
+
+    #[test]
+    fn empty_readback_after_nonempty_write_is_an_error() {
+        let mut tmp = TempFile::new("ldapvi-test").unwrap();
+        tmp.write_all(b"dn: cn=foo,dc=example,dc=com\n").unwrap();
+        // Simulate an editor that replaced rather than rewrote the file:
+        // truncate it out from under the caller's own content length.
+        tmp.write_all(b"").unwrap();
+        let err = tmp.read_back(30).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    }
+
+    #[test]
+    fn empty_readback_after_empty_original_is_not_an_error() {
+        let mut tmp = TempFile::new("ldapvi-test").unwrap();
+        tmp.write_all(b"").unwrap();
+        assert_eq!(tmp.read_back(0).unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn allow_child_access_clears_cloexec() {
+        let tmp = TempFile::new("ldapvi-test").unwrap();
+        tmp.allow_child_access().unwrap();
+        let flags = fcntl(tmp.file.as_fd(), FcntlArg::F_GETFD).unwrap();
+        assert_eq!(flags & nix::libc::FD_CLOEXEC, 0);
+    }
+}
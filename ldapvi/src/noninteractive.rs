@@ -1,7 +1,9 @@
-use std::io::{BufRead, BufReader, Write};
+use std::io::{self, BufRead, BufReader, Write};
 use std::os::fd::FromRawFd;
 use std::sync::OnceLock;
 
+use crate::memtemp::TempFile;
+
 const CONTROL_FD: i32 = 3;
 
 struct ControlChannel {
@@ -80,6 +82,29 @@ pub fn view(pathname: &str) {
     }
 }
 
+/// Like [`edit`], but the LDIF lives in a [`TempFile`] instead of a
+/// named disk file -- see [`crate::interactive::edit_in_memory`]. The
+/// path handed over the control fd is qualified with this process's
+/// pid (`TempFile::pid_proc_path`), since the harness driving `EDIT`
+/// opens it from its own process, where `/proc/self` would mean itself.
+pub fn edit_in_memory(data: &[u8], _line: Option<i64>) -> io::Result<Vec<u8>> {
+    let mut tmp = TempFile::new("ldapvi-edit")?;
+    tmp.write_all(data)?;
+    tmp.allow_child_access()?;
+    edit(&tmp.pid_proc_path(), _line);
+    tmp.read_back(data.len())
+}
+
+/// Like [`view`], but the LDIF lives in a [`TempFile`] instead of a
+/// named disk file -- see [`edit_in_memory`].
+pub fn view_in_memory(data: &[u8]) -> io::Result<()> {
+    let mut tmp = TempFile::new("ldapvi-view")?;
+    tmp.write_all(data)?;
+    tmp.allow_child_access()?;
+    view(&tmp.pid_proc_path());
+    Ok(())
+}
+
 /// Prompt for a line of text input from the user.
 pub fn read_line(prompt: &str) -> String {
     let mut ctrl = control().lock().unwrap();
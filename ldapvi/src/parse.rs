@@ -3,7 +3,9 @@
 //! Reads records in ldapvi format from any `Read + Seek` source.
 //! Format: `key dn\nattr value\n` with backslash escaping by default.
 
+use std::collections::HashMap;
 use std::io::{Read, Seek, SeekFrom};
+use std::rc::Rc;
 
 use crate::base64;
 use crate::data::{Entry, LdapMod, ModOp, ModifyRecord, RenameRecord};
@@ -11,86 +13,145 @@ use crate::error::{LdapviError, Result};
 use crate::port;
 
 // ---------------------------------------------------------------------------
-// CharReader -- single-byte buffered reader with pushback
+// CharReader -- refillable buffered reader with arbitrary-depth pushback
 // ---------------------------------------------------------------------------
 
+/// Size of the internal read buffer. Chosen to turn the per-byte syscalls a
+/// naive reader would issue on a multi-megabyte dump into one `read()` per
+/// 64 KiB instead.
+const CHAR_READER_BUF_SIZE: usize = 64 * 1024;
+
+/// Upper bound on a single attribute value read through the `<` file-URL or
+/// numeric fixed-length encodings. A hostile or malformed numeric length
+/// (e.g. a stray extra digit) would otherwise drive an immediate
+/// multi-gigabyte allocation before a single byte is read; a bad file-URL
+/// target (e.g. a device file) would otherwise be read in full before we
+/// notice it's too big. 256 MiB comfortably covers legitimate attachments
+/// (photos, certificates) while erroring out well short of exhausting RAM.
+const MAX_INLINE_VALUE_BYTES: u64 = 256 * 1024 * 1024;
+
 struct CharReader<R> {
     inner: R,
-    pushback: Option<u8>,
+    buf: Box<[u8]>,
+    /// Next unread byte within `buf`.
+    pos: usize,
+    /// Number of valid bytes in `buf` (`buf[len..]` is stale).
+    len: usize,
+    /// Absolute stream offset of `buf[0]`.
+    base: u64,
+    /// 1-based line number of the next unread byte, counting every `\n`
+    /// consumed so far (including ones inside folded or comment lines).
+    line: u64,
 }
 
 impl<R: Read + Seek> CharReader<R> {
     fn new(inner: R) -> Self {
         CharReader {
             inner,
-            pushback: None,
+            buf: vec![0u8; CHAR_READER_BUF_SIZE].into_boxed_slice(),
+            pos: 0,
+            len: 0,
+            base: 0,
+            line: 1,
         }
     }
 
+    /// Refill `buf` from `inner`, assuming the current contents (`buf[..len]`)
+    /// have already been fully consumed (`pos == len`).
+    fn fill(&mut self) -> Result<()> {
+        self.base += self.len as u64;
+        self.len = self.inner.read(&mut self.buf).map_err(LdapviError::Io)?;
+        self.pos = 0;
+        Ok(())
+    }
+
     fn getc(&mut self) -> Result<Option<u8>> {
-        if let Some(c) = self.pushback.take() {
-            return Ok(Some(c));
+        if self.pos == self.len {
+            self.fill()?;
+            if self.len == 0 {
+                return Ok(None);
+            }
         }
-        let mut buf = [0u8; 1];
-        match self.inner.read(&mut buf) {
-            Ok(0) => Ok(None),
-            Ok(_) => Ok(Some(buf[0])),
-            Err(e) => Err(LdapviError::Io(e)),
+        let c = self.buf[self.pos];
+        self.pos += 1;
+        if c == b'\n' {
+            self.line += 1;
         }
+        Ok(Some(c))
     }
 
+    /// Push a byte back for re-reading. Unlike a single-slot pushback, this
+    /// just rewinds `pos`, so it can be called repeatedly as long as the
+    /// bytes being unget are still within the current buffer window.
     fn ungetc(&mut self, c: u8) {
-        debug_assert!(self.pushback.is_none(), "double pushback");
-        self.pushback = Some(c);
+        debug_assert!(self.pos > 0, "ungetc past the start of the buffer window");
+        self.pos -= 1;
+        debug_assert_eq!(self.buf[self.pos], c, "ungetc value does not match last getc");
+        if c == b'\n' {
+            self.line -= 1;
+        }
     }
 
     fn tell(&mut self) -> Result<u64> {
-        let pos = self.inner.stream_position()?;
-        if self.pushback.is_some() {
-            Ok(pos - 1)
-        } else {
-            Ok(pos)
-        }
+        Ok(self.base + self.pos as u64)
     }
 
+    /// Current 1-based line number, for error reporting.
+    fn line(&self) -> u64 {
+        self.line
+    }
+
+    /// Seek to an absolute byte offset. The line counter is reset to 1:
+    /// recovering the true line number at an arbitrary offset would mean
+    /// re-scanning the stream from the start, which callers that seek (to
+    /// re-read a specific record by its previously recorded position) don't
+    /// otherwise need to pay for. Errors encountered after such a seek
+    /// report a line number relative to the seek point, not the file start.
     fn seek(&mut self, pos: u64) -> Result<()> {
-        self.pushback = None;
         self.inner.seek(SeekFrom::Start(pos))?;
+        self.base = pos;
+        self.pos = 0;
+        self.len = 0;
+        self.line = 1;
         Ok(())
     }
 
+    /// Drain whatever is left in the buffer, then read the remainder, if
+    /// any, directly from `inner`.
     fn read_raw(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.pushback = None;
-        self.inner.read(buf)
+        let avail = self.len - self.pos;
+        let n = avail.min(buf.len());
+        if n > 0 {
+            buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+            self.pos += n;
+        }
+        if n == buf.len() {
+            return Ok(n);
+        }
+        let more = self.inner.read(&mut buf[n..])?;
+        Ok(n + more)
     }
 
     fn at_eof(&mut self) -> Result<bool> {
-        if self.pushback.is_some() {
+        if self.pos < self.len {
             return Ok(false);
         }
-        let mut buf = [0u8; 1];
-        match self.inner.read(&mut buf) {
-            Ok(0) => Ok(true),
-            Ok(_) => {
-                self.pushback = Some(buf[0]);
-                Ok(false)
-            }
-            Err(e) => Err(LdapviError::Io(e)),
-        }
+        self.fill()?;
+        Ok(self.len == 0)
     }
 
     /// Read exactly `n` bytes into `buf`. Error if not enough bytes.
     fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
-        let mut offset = 0;
-        if let Some(pb) = self.pushback.take() {
-            if !buf.is_empty() {
-                buf[0] = pb;
-                offset = 1;
-            }
+        let avail = self.len - self.pos;
+        let n = avail.min(buf.len());
+        if n > 0 {
+            buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+            self.pos += n;
+        }
+        if n < buf.len() {
+            self.inner.read_exact(&mut buf[n..]).map_err(LdapviError::Io)?;
         }
-        self.inner
-            .read_exact(&mut buf[offset..])
-            .map_err(LdapviError::Io)
+        Ok(())
     }
 }
 
@@ -101,123 +162,124 @@ impl<R: Read + Seek> CharReader<R> {
 /// Result of `read_line1`.
 enum LineResult {
     /// Attribute-value line (name may be empty for modify value lines).
-    Line(String, Vec<u8>),
+    /// The name is kept as raw bytes rather than `String`: it is not
+    /// necessarily valid UTF-8 (an attribute descriptor may carry a
+    /// non-ASCII option), and callers that need it as text -- the fixed
+    /// set of ASCII keywords like `add`/`delete`/`replace`/`version`, or an
+    /// attribute descriptor via [`Entry::find_attribute_bytes`] -- decide
+    /// for themselves whether to match on bytes or decode lossily/strictly.
+    Line(Vec<u8>, Vec<u8>),
     /// Empty line (record separator).
     BlankLine,
     /// End of file.
     Eof,
 }
 
-// ---------------------------------------------------------------------------
-// Crypt support (Unix only)
-// ---------------------------------------------------------------------------
-
-const SALT_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz1234567890./";
-
-fn random_salt_bytes(n: usize) -> Vec<u8> {
-    let mut salt = vec![0u8; n];
-    #[cfg(target_family = "unix")]
-    {
-        use std::io::Read as _;
-        if let Ok(mut f) = std::fs::File::open("/dev/urandom") {
-            let _ = f.read_exact(&mut salt);
-        }
-    }
-    salt
-}
-
-#[cfg(unix)]
-fn crypt_des(key: &str) -> Result<String> {
-    use std::ffi::{CStr, CString};
-    use std::os::raw::c_char;
-
-    #[link(name = "crypt")]
-    extern "C" {
-        fn crypt(key: *const c_char, salt: *const c_char) -> *mut c_char;
-    }
-
-    let raw = random_salt_bytes(2);
-    let salt = format!(
-        "{}{}",
-        SALT_CHARS[(raw[0] & 63) as usize] as char,
-        SALT_CHARS[(raw[1] & 63) as usize] as char
-    );
-
-    let c_key = CString::new(key).map_err(|_| LdapviError::Other("invalid key".into()))?;
-    let c_salt = CString::new(salt).map_err(|_| LdapviError::Other("invalid salt".into()))?;
-
-    unsafe {
-        let result = crypt(c_key.as_ptr(), c_salt.as_ptr());
-        if result.is_null() {
-            return Err(LdapviError::Other(
-                "crypt not available: crypt() returned null".into(),
-            ));
-        }
-        Ok(CStr::from_ptr(result).to_string_lossy().into_owned())
-    }
-}
-
-#[cfg(not(unix))]
-fn crypt_des(_key: &str) -> Result<String> {
-    Err(LdapviError::Other(
-        "crypt not available on this platform".into(),
-    ))
-}
-
-#[cfg(unix)]
-fn crypt_md5(key: &str) -> Result<String> {
-    use std::ffi::{CStr, CString};
-    use std::os::raw::c_char;
-
-    #[link(name = "crypt")]
-    extern "C" {
-        fn crypt(key: *const c_char, salt: *const c_char) -> *mut c_char;
-    }
-
-    let raw = random_salt_bytes(8);
-    let mut salt = String::from("$1$");
-    for &b in &raw {
-        salt.push(SALT_CHARS[(b & 63) as usize] as char);
-    }
-
-    let c_key = CString::new(key).map_err(|_| LdapviError::Other("invalid key".into()))?;
-    let c_salt = CString::new(salt).map_err(|_| LdapviError::Other("invalid salt".into()))?;
-
-    unsafe {
-        let result = crypt(c_key.as_ptr(), c_salt.as_ptr());
-        if result.is_null() {
-            return Err(LdapviError::Other("MD5 crypt returned null".into()));
-        }
-        let s = CStr::from_ptr(result).to_string_lossy().into_owned();
-        if s.len() < 25 {
-            return Err(LdapviError::Other(
-                "MD5 crypt not available: result too short".into(),
-            ));
-        }
-        Ok(s)
-    }
-}
-
-#[cfg(not(unix))]
-fn crypt_md5(_key: &str) -> Result<String> {
-    Err(LdapviError::Other(
-        "crypt not available on this platform".into(),
-    ))
-}
-
 // ---------------------------------------------------------------------------
 // LdapviParser
 // ---------------------------------------------------------------------------
 
+/// A pluggable value-encoding handler: given the raw bytes read via
+/// `read_ldif_attrval` for a line like `attr:myencoding value`, returns the
+/// decoded attribute value. Registered under the encoding name (matched
+/// case-insensitively) via [`LdapviParser::register_encoding`].
+pub type EncodingHandler = Rc<dyn Fn(&[u8]) -> Result<Vec<u8>>>;
+
 pub struct LdapviParser<R> {
     cr: CharReader<R>,
+    /// Value-encoding handlers keyed by lowercased encoding name. Covers
+    /// the password-hash schemes (`crypt`, `sha`, ...) by default; `""`,
+    /// `":"`, `"q"`, `"<"`, `";"` and numeric fixed-length encodings are handled
+    /// directly by `read_line1` since they need direct `CharReader` access
+    /// (streaming base64 decode, bounded file/length reads) that a plain
+    /// `&[u8] -> Vec<u8>` handler can't provide.
+    encodings: HashMap<String, EncodingHandler>,
 }
 
 impl<R: Read + Seek> LdapviParser<R> {
     pub fn new(reader: R) -> Self {
-        LdapviParser {
+        let mut parser = LdapviParser {
             cr: CharReader::new(reader),
-        }
+            encodings: HashMap::new(),
+        };
+        parser.register_default_encodings();
+        parser
+    }
+
+    fn register_default_encodings(&mut self) {
+        self.register_encoding("crypt", Rc::new(|raw| {
+            let key = String::from_utf8_lossy(raw);
+            let hash = port::crypt_des(&key).map_err(|e| LdapviError::Other(e.to_string()))?;
+            Ok(format!("{{CRYPT}}{}", hash).into_bytes())
+        }));
+        self.register_encoding("cryptmd5", Rc::new(|raw| {
+            let key = String::from_utf8_lossy(raw);
+            let hash = port::crypt_md5(&key).map_err(|e| LdapviError::Other(e.to_string()))?;
+            Ok(format!("{{CRYPT}}{}", hash).into_bytes())
+        }));
+        self.register_encoding("sha256crypt", Rc::new(|raw| {
+            let key = String::from_utf8_lossy(raw);
+            let hash = port::crypt_sha256(&key).map_err(|e| LdapviError::Other(e.to_string()))?;
+            Ok(format!("{{CRYPT}}{}", hash).into_bytes())
+        }));
+        self.register_encoding("sha512crypt", Rc::new(|raw| {
+            let key = String::from_utf8_lossy(raw);
+            let hash = port::crypt_sha512(&key).map_err(|e| LdapviError::Other(e.to_string()))?;
+            Ok(format!("{{CRYPT}}{}", hash).into_bytes())
+        }));
+        self.register_encoding("sha", Rc::new(|raw| {
+            let key = String::from_utf8_lossy(raw);
+            let mut result = String::from("{SHA}");
+            port::append_sha(&mut result, &key);
+            Ok(result.into_bytes())
+        }));
+        self.register_encoding("ssha", Rc::new(|raw| {
+            let key = String::from_utf8_lossy(raw);
+            let mut result = String::from("{SSHA}");
+            port::append_ssha(&mut result, &key).map_err(|e| LdapviError::Other(e.to_string()))?;
+            Ok(result.into_bytes())
+        }));
+        self.register_encoding("md5", Rc::new(|raw| {
+            let key = String::from_utf8_lossy(raw);
+            let mut result = String::from("{MD5}");
+            port::append_md5(&mut result, &key);
+            Ok(result.into_bytes())
+        }));
+        self.register_encoding("smd5", Rc::new(|raw| {
+            let key = String::from_utf8_lossy(raw);
+            let mut result = String::from("{SMD5}");
+            port::append_smd5(&mut result, &key).map_err(|e| LdapviError::Other(e.to_string()))?;
+            Ok(result.into_bytes())
+        }));
+        self.register_encoding("ssha256", Rc::new(|raw| {
+            let key = String::from_utf8_lossy(raw);
+            let mut result = String::from("{SSHA256}");
+            port::append_ssha256(&mut result, &key).map_err(|e| LdapviError::Other(e.to_string()))?;
+            Ok(result.into_bytes())
+        }));
+        self.register_encoding("ssha512", Rc::new(|raw| {
+            let key = String::from_utf8_lossy(raw);
+            let mut result = String::from("{SSHA512}");
+            port::append_ssha512(&mut result, &key).map_err(|e| LdapviError::Other(e.to_string()))?;
+            Ok(result.into_bytes())
+        }));
+        self.register_encoding("pbkdf2", Rc::new(|raw| {
+            let key = String::from_utf8_lossy(raw);
+            let mut result = String::from("{PBKDF2-SHA512}");
+            port::append_pbkdf2(&mut result, &key, port::DEFAULT_PBKDF2_ROUNDS)
+                .map_err(|e| LdapviError::Other(e.to_string()))?;
+            Ok(result.into_bytes())
+        }));
+    }
+
+    /// Register a handler for a named value encoding (matched
+    /// case-insensitively against the text after the `:` in `attr:encoding`).
+    /// Overrides any existing handler of the same name, including the
+    /// built-in password-hash schemes. Does not affect `""`, `":"`, `"q"`, `"<"`,
+    /// `";"` or numeric fixed-length encodings, which are handled directly
+    /// by the line reader.
+    pub fn register_encoding(&mut self, name: &str, handler: EncodingHandler) {
+        self.encodings.insert(name.to_ascii_lowercase(), handler);
     }
 
     /// Current stream position.
@@ -225,9 +287,20 @@ impl<R: Read + Seek> LdapviParser<R> {
         self.cr.tell()
     }
 
-    fn parse_err(&self, msg: &str) -> LdapviError {
+    /// Build a parse error at the current stream position.
+    fn parse_err(&mut self, msg: &str) -> LdapviError {
+        let position = self.cr.tell().unwrap_or(0);
+        let line = self.cr.line();
+        self.parse_err_at(position, line, msg)
+    }
+
+    /// Build a parse error at a previously captured (position, line) pair
+    /// (e.g. the start of the line or record the error was found in,
+    /// rather than wherever the cursor ended up while parsing it).
+    fn parse_err_at(&self, position: u64, line: u64, msg: &str) -> LdapviError {
         LdapviError::Parse {
-            position: 0,
+            position,
+            line,
             message: msg.to_string(),
         }
     }
@@ -235,16 +308,19 @@ impl<R: Read + Seek> LdapviParser<R> {
     // -- low-level readers --------------------------------------------------
 
     /// Read the left-hand side of a line (everything up to the first space).
-    /// Space is consumed but not included in the result.
-    fn read_lhs(&mut self) -> Result<String> {
-        let mut lhs = String::new();
+    /// Space is consumed but not included in the result. Kept as raw bytes
+    /// rather than decoded char-by-char, since the LHS may carry an
+    /// attribute descriptor that isn't valid UTF-8 -- validation, if any,
+    /// is left to the caller that knows what the bytes are supposed to mean.
+    fn read_lhs(&mut self) -> Result<Vec<u8>> {
+        let mut lhs = Vec::new();
         loop {
             match self.cr.getc()? {
                 Some(b' ') => return Ok(lhs),
                 None => return Err(self.parse_err("Unexpected EOF.")),
                 Some(b'\n') => return Err(self.parse_err("Unexpected EOL.")),
                 Some(0) => return Err(self.parse_err("Null byte not allowed.")),
-                Some(c) => lhs.push(c as char),
+                Some(c) => lhs.push(c),
             }
         }
     }
@@ -287,6 +363,124 @@ impl<R: Read + Seek> LdapviParser<R> {
         }
     }
 
+    /// Read a quoted-printable value (`:q`), e.g. `description:q =C3=A9t=C3=A9`.
+    /// `=` followed by two hex digits decodes to that byte; `=` immediately
+    /// before a line break is a soft break and is dropped along with the
+    /// newline (and, if that newline folds, along with the fold as well);
+    /// any other byte after `=` is a parse error. The soft break is resolved
+    /// while reading, not on the fold-assembled value, so that an `=` which
+    /// happens to land right before a fold's newline isn't mistaken for a
+    /// real escape once folding has (as usual) erased that newline.
+    fn read_quoted_printable_attrval(&mut self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        loop {
+            match self.cr.getc()? {
+                Some(b'\n') => match self.cr.getc()? {
+                    Some(b' ') => continue, // folded line
+                    Some(c) => {
+                        self.cr.ungetc(c);
+                        break;
+                    }
+                    None => break,
+                },
+                None => return Err(self.parse_err("Unexpected EOF.")),
+                Some(b'=') => {
+                    let eq_pos = self.cr.tell()?.saturating_sub(1);
+                    let eq_line = self.cr.line();
+                    match self.cr.getc()? {
+                        Some(b'\n') => {
+                            // Soft break: consumed together with the newline.
+                            match self.cr.getc()? {
+                                Some(b' ') => continue, // also folds; emit nothing
+                                Some(c) => {
+                                    self.cr.ungetc(c);
+                                    break;
+                                }
+                                None => break,
+                            }
+                        }
+                        Some(h1) => {
+                            let h2 = self.cr.getc()?.ok_or_else(|| {
+                                self.parse_err_at(eq_pos, eq_line, "Invalid quoted-printable escape.")
+                            })?;
+                            let hex_digit = |b: u8| (b as char).to_digit(16);
+                            match (hex_digit(h1), hex_digit(h2)) {
+                                (Some(hi), Some(lo)) => data.push(((hi << 4) | lo) as u8),
+                                _ => {
+                                    return Err(self.parse_err_at(
+                                        eq_pos,
+                                        eq_line,
+                                        "Invalid quoted-printable escape.",
+                                    ));
+                                }
+                            }
+                        }
+                        None => {
+                            return Err(self.parse_err_at(
+                                eq_pos,
+                                eq_line,
+                                "Invalid quoted-printable escape.",
+                            ));
+                        }
+                    }
+                }
+                Some(c) => data.push(c),
+            }
+        }
+        Ok(data)
+    }
+
+    /// Read an LDIF-style base64 value, decoding it incrementally as folded
+    /// lines arrive rather than buffering the whole armored text and
+    /// decoding it afterwards (which would briefly hold both the raw and
+    /// decoded forms in memory at once for large binary attributes).
+    /// Reports the exact byte offset of the first invalid character.
+    fn read_base64_attrval(&mut self) -> Result<Vec<u8>> {
+        let mut decoder = base64::Base64Decoder::new();
+        loop {
+            match self.cr.getc()? {
+                Some(b'\n') => match self.cr.getc()? {
+                    Some(b' ') => continue, // folded line; ' ' is not part of the value
+                    Some(c) => {
+                        self.cr.ungetc(c);
+                        break;
+                    }
+                    None => break,
+                },
+                None => return Err(self.parse_err("Unexpected EOF.")),
+                Some(c) => {
+                    if decoder.feed(c).is_err() {
+                        let bad_at = self.cr.tell()?.saturating_sub(1);
+                        let bad_line = self.cr.line();
+                        return Err(self.parse_err_at(bad_at, bad_line, "Invalid Base64 string."));
+                    }
+                }
+            }
+        }
+        decoder
+            .finish()
+            .map_err(|_| self.parse_err("Invalid Base64 string (truncated)."))
+    }
+
+    /// Read `file://` contents, bounded by [`MAX_INLINE_VALUE_BYTES`] so that
+    /// an oversized or unbounded target (a huge file, a device node) errors
+    /// out instead of being read into memory in full.
+    fn read_file_url_bounded(path: &str) -> Result<Vec<u8>> {
+        let file = std::fs::File::open(path).map_err(LdapviError::Io)?;
+        let mut data = Vec::new();
+        let read = file
+            .take(MAX_INLINE_VALUE_BYTES + 1)
+            .read_to_end(&mut data)
+            .map_err(LdapviError::Io)?;
+        if read as u64 > MAX_INLINE_VALUE_BYTES {
+            return Err(LdapviError::Other(format!(
+                "file '{}' exceeds the maximum inline value size of {} bytes",
+                path, MAX_INLINE_VALUE_BYTES
+            )));
+        }
+        Ok(data)
+    }
+
     /// Skip a comment line (with line folding support).
     fn skip_comment(&mut self) -> Result<()> {
         loop {
@@ -314,10 +508,14 @@ impl<R: Read + Seek> LdapviParser<R> {
     /// ```
     ///
     /// where encoding is one of: (empty) for LDIF-style, `:` for base64,
-    /// `<` for file URL, `;` for backslash-escaped, `crypt`/`sha`/`ssha`/
-    /// `md5`/`smd5`/`cryptmd5` for password hashing, or a decimal number
-    /// for a fixed-length binary read.  Without a colon, values use
-    /// backslash escaping by default.
+    /// `q` for quoted-printable, `<` for file URL, `;` for
+    /// backslash-escaped, a decimal number for a fixed-length binary read,
+    /// or any name registered in `self.encodings`
+    /// (by default `crypt`/`sha`/`ssha`/`md5`/`smd5`/`cryptmd5`/
+    /// `sha256crypt`/`sha512crypt`/`ssha256`/`ssha512`/`pbkdf2` for
+    /// password hashing -- see
+    /// [`LdapviParser::register_encoding`] to add more). Without a colon,
+    /// values use backslash escaping by default.
     ///
     /// Returns `Line(name, value)` where name may be empty (for modify value
     /// lines starting with space), `BlankLine` for empty lines, or `Eof`.
@@ -339,13 +537,20 @@ impl<R: Read + Seek> LdapviParser<R> {
             }
         }
 
+        // Start of the actual line, for error reporting below.
+        let line_start = self.cr.tell()?;
+        let line_start_line = self.cr.line();
+
         // Read LHS (everything up to space)
         let lhs = self.read_lhs()?;
 
-        // Parse name and encoding from LHS
-        let (name, encoding) = if let Some(colon_pos) = lhs.find(':') {
-            let name = lhs[..colon_pos].to_string();
-            let enc = lhs[colon_pos + 1..].to_string();
+        // Parse name and encoding from LHS. The encoding tag is always one
+        // of a fixed set of ASCII names or a decimal number, so it's safe
+        // to decode lossily; the name itself is left as raw bytes (see
+        // `LineResult::Line`).
+        let (name, encoding) = if let Some(colon_pos) = lhs.iter().position(|&b| b == b':') {
+            let name = lhs[..colon_pos].to_vec();
+            let enc = String::from_utf8_lossy(&lhs[colon_pos + 1..]).into_owned();
             (name, Some(enc))
         } else {
             (lhs, None)
@@ -362,72 +567,52 @@ impl<R: Read + Seek> LdapviParser<R> {
                 self.read_ldif_attrval()?
             }
             Some(":") => {
-                // Base64: read LDIF-style, then decode
-                let raw = self.read_ldif_attrval()?;
-                let raw_str = String::from_utf8_lossy(&raw);
-                base64::read_base64(&raw_str)
-                    .ok_or_else(|| self.parse_err("Invalid Base64 string."))?
+                // Base64, decoded incrementally as folded lines arrive.
+                self.read_base64_attrval()?
+            }
+            Some("q") => {
+                // Quoted-printable.
+                self.read_quoted_printable_attrval()?
             }
             Some("<") => {
-                // File URL
+                // File URL, read through a bounded buffer.
                 let raw = self.read_ldif_attrval()?;
                 let url = String::from_utf8_lossy(&raw);
                 if !url.starts_with("file://") {
-                    return Err(self.parse_err("Unknown URL scheme."));
+                    return Err(self.parse_err_at(line_start, line_start_line, "Unknown URL scheme."));
                 }
                 let path = &url[7..];
-                std::fs::read(path).map_err(LdapviError::Io)?
-            }
-            Some(enc) if enc.eq_ignore_ascii_case("crypt") => {
-                let raw = self.read_ldif_attrval()?;
-                let key = String::from_utf8_lossy(&raw);
-                let hash = crypt_des(&key)?;
-                format!("{{CRYPT}}{}", hash).into_bytes()
-            }
-            Some(enc) if enc.eq_ignore_ascii_case("cryptmd5") => {
-                let raw = self.read_ldif_attrval()?;
-                let key = String::from_utf8_lossy(&raw);
-                let hash = crypt_md5(&key)?;
-                format!("{{CRYPT}}{}", hash).into_bytes()
-            }
-            Some(enc) if enc.eq_ignore_ascii_case("sha") => {
-                let raw = self.read_ldif_attrval()?;
-                let key = String::from_utf8_lossy(&raw);
-                let mut result = String::from("{SHA}");
-                port::append_sha(&mut result, &key);
-                result.into_bytes()
-            }
-            Some(enc) if enc.eq_ignore_ascii_case("ssha") => {
-                let raw = self.read_ldif_attrval()?;
-                let key = String::from_utf8_lossy(&raw);
-                let mut result = String::from("{SSHA}");
-                port::append_ssha(&mut result, &key);
-                result.into_bytes()
-            }
-            Some(enc) if enc.eq_ignore_ascii_case("md5") => {
-                let raw = self.read_ldif_attrval()?;
-                let key = String::from_utf8_lossy(&raw);
-                let mut result = String::from("{MD5}");
-                port::append_md5(&mut result, &key);
-                result.into_bytes()
-            }
-            Some(enc) if enc.eq_ignore_ascii_case("smd5") => {
-                let raw = self.read_ldif_attrval()?;
-                let key = String::from_utf8_lossy(&raw);
-                let mut result = String::from("{SMD5}");
-                port::append_smd5(&mut result, &key);
-                result.into_bytes()
+                Self::read_file_url_bounded(path)?
             }
             Some(enc) => {
-                // Try numeric encoding (read exactly N bytes)
-                match enc.parse::<usize>() {
-                    Ok(n) => {
-                        let mut buf = vec![0u8; n];
-                        self.cr.read_exact(&mut buf)?;
-                        buf
-                    }
-                    Err(_) => {
-                        return Err(self.parse_err("Unknown value encoding."));
+                if let Some(handler) = self.encodings.get(&enc.to_ascii_lowercase()).cloned() {
+                    let raw = self.read_ldif_attrval()?;
+                    handler(&raw)?
+                } else {
+                    // Fall back to numeric encoding (read exactly N bytes).
+                    match enc.parse::<u64>() {
+                        Ok(n) if n <= MAX_INLINE_VALUE_BYTES => {
+                            let mut buf = vec![0u8; n as usize];
+                            self.cr.read_exact(&mut buf)?;
+                            buf
+                        }
+                        Ok(n) => {
+                            return Err(self.parse_err_at(
+                                line_start,
+                                line_start_line,
+                                &format!(
+                                    "Value length {} exceeds the maximum inline value size of {} bytes.",
+                                    n, MAX_INLINE_VALUE_BYTES
+                                ),
+                            ));
+                        }
+                        Err(_) => {
+                            return Err(self.parse_err_at(
+                                line_start,
+                                line_start_line,
+                                "Unknown value encoding.",
+                            ));
+                        }
                     }
                 }
             }
@@ -438,7 +623,7 @@ impl<R: Read + Seek> LdapviParser<R> {
 
     /// Read a line, rejecting empty names on content lines.
     /// Returns `Ok(Some((name, value)))` for content, `Ok(None)` for EOF/blank.
-    fn read_line(&mut self) -> Result<Option<(String, Vec<u8>)>> {
+    fn read_line(&mut self) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
         match self.read_line1()? {
             LineResult::Eof | LineResult::BlankLine => Ok(None),
             LineResult::Line(name, value) => {
@@ -460,6 +645,7 @@ impl<R: Read + Seek> LdapviParser<R> {
 
         loop {
             let pos = self.cr.tell()?;
+            let pos_line = self.cr.line();
             match self.read_line()? {
                 None => {
                     // Blank line or EOF. Check if EOF.
@@ -469,17 +655,26 @@ impl<R: Read + Seek> LdapviParser<R> {
                     continue;
                 }
                 Some((key, value)) => {
-                    if key == "version" {
+                    if key == b"version" {
                         let version = String::from_utf8_lossy(&value);
                         if version != "ldapvi" {
-                            return Err(self.parse_err("Invalid file format."));
+                            return Err(self.parse_err_at(pos, pos_line, "Invalid file format."));
                         }
                         continue;
                     }
+                    // The header key is a record keyword ("add", "modify",
+                    // "delete", "rename") or an entry key such as "42"; it is
+                    // decoded lossily, so a non-UTF-8 key gets replacement
+                    // characters.
+                    let key = String::from_utf8_lossy(&key).into_owned();
                     // Validate DN (must contain '=')
                     let dn = String::from_utf8_lossy(&value).into_owned();
                     if !dn.contains('=') {
-                        return Err(self.parse_err("Invalid distinguished name string."));
+                        return Err(self.parse_err_at(
+                            pos,
+                            pos_line,
+                            "Invalid distinguished name string.",
+                        ));
                     }
                     return Ok(Some((key, dn, pos)));
                 }
@@ -493,7 +688,7 @@ impl<R: Read + Seek> LdapviParser<R> {
             match self.read_line()? {
                 None => return Ok(()),
                 Some((name, value)) => {
-                    let attr = entry.find_attribute(&name, true).unwrap();
+                    let attr = entry.find_attribute_bytes(&name, true).unwrap();
                     attr.values.push(value);
                 }
             }
@@ -502,15 +697,21 @@ impl<R: Read + Seek> LdapviParser<R> {
 
     /// Read the body of a rename record: `add|replace new_dn`.
     fn read_rename_body(&mut self) -> Result<(String, bool)> {
+        let pos = self.cr.tell()?;
+        let pos_line = self.cr.line();
         match self.read_line()? {
-            None => Err(self.parse_err("Rename record lacks dn line.")),
+            None => Err(self.parse_err_at(pos, pos_line, "Rename record lacks dn line.")),
             Some((action, value)) => {
-                let delete_old_rdn = if action == "replace" {
+                let delete_old_rdn = if action == b"replace" {
                     true
-                } else if action == "add" {
+                } else if action == b"add" {
                     false
                 } else {
-                    return Err(self.parse_err("Expected 'add' or 'replace' in rename record."));
+                    return Err(self.parse_err_at(
+                        pos,
+                        pos_line,
+                        "Expected 'add' or 'replace' in rename record.",
+                    ));
                 };
                 let new_dn = String::from_utf8_lossy(&value).into_owned();
 
@@ -544,6 +745,8 @@ impl<R: Read + Seek> LdapviParser<R> {
         let mut current_mod: Option<LdapMod> = None;
 
         loop {
+            let pos = self.cr.tell()?;
+            let pos_line = self.cr.line();
             match self.read_line1()? {
                 LineResult::Line(name, value) => {
                     if !name.is_empty() {
@@ -551,11 +754,11 @@ impl<R: Read + Seek> LdapviParser<R> {
                         if let Some(m) = current_mod.take() {
                             mods.push(m);
                         }
-                        let op = match name.as_str() {
-                            "add" => ModOp::Add,
-                            "delete" => ModOp::Delete,
-                            "replace" => ModOp::Replace,
-                            _ => return Err(self.parse_err("Invalid change marker.")),
+                        let op = match name.as_slice() {
+                            b"add" => ModOp::Add,
+                            b"delete" => ModOp::Delete,
+                            b"replace" => ModOp::Replace,
+                            _ => return Err(self.parse_err_at(pos, pos_line, "Invalid change marker.")),
                         };
                         let attr = String::from_utf8_lossy(&value).into_owned();
                         current_mod = Some(LdapMod {
@@ -584,6 +787,18 @@ impl<R: Read + Seek> LdapviParser<R> {
 
     /// Read a full attrval-record.
     /// Returns `Ok(Some((key, entry, pos)))` or `Ok(None)` at EOF.
+    ///
+    /// File-URL and numeric-length values are bounded (see
+    /// [`MAX_INLINE_VALUE_BYTES`]) and base64 is decoded incrementally, so
+    /// neither kind can exhaust memory and a base64 value is never held as
+    /// a raw and a decoded copy at once; other inline values are read in
+    /// full, with no size limit. We stopped
+    /// short of a `Read`-stream-yielding variant of this method, though:
+    /// `Entry`/`Attribute` are `Vec<u8>`-based throughout `data.rs` and every
+    /// consumer (diff, print, ldap), so a parallel streaming value type
+    /// would ripple across the whole crate for a capability nothing yet
+    /// calls for. Revisit if a caller actually needs to process a value
+    /// without buffering it.
     pub fn read_entry(&mut self, offset: Option<u64>) -> Result<Option<(String, Entry, u64)>> {
         let (key, dn, pos) = match self.read_header(offset)? {
             Some(h) => h,
@@ -603,6 +818,18 @@ impl<R: Read + Seek> LdapviParser<R> {
         }
     }
 
+    /// Peek at the next record's DN without consuming the body.
+    /// Returns `Ok(Some((dn, pos)))` or `Ok(None)` at EOF. Like
+    /// [`peek_entry`](Self::peek_entry), but for callers (e.g. [`crate::diff::DnIndex`])
+    /// that index by DN rather than by key, and so never need to materialize
+    /// the full entry.
+    pub fn peek_dn(&mut self, offset: Option<u64>) -> Result<Option<(String, u64)>> {
+        match self.read_header(offset)? {
+            Some((_, dn, pos)) => Ok(Some((dn, pos))),
+            None => Ok(None),
+        }
+    }
+
     /// Skip past an entry, returning its key.
     /// Returns `Ok(Some(key))` or `Ok(None)` at EOF.
     pub fn skip_entry(&mut self, offset: Option<u64>) -> Result<Option<String>> {
@@ -674,6 +901,8 @@ impl<R: Read + Seek> LdapviParser<R> {
     /// Read a profile record. Returns `Ok(None)` at EOF.
     pub fn read_profile(&mut self) -> Result<Option<Entry>> {
         loop {
+            let pos = self.cr.tell()?;
+            let pos_line = self.cr.line();
             match self.read_line()? {
                 None => {
                     if self.cr.at_eof()? {
@@ -682,14 +911,15 @@ impl<R: Read + Seek> LdapviParser<R> {
                     continue;
                 }
                 Some((key, value)) => {
-                    if key != "profile" {
-                        return Err(LdapviError::Parse {
-                            position: 0,
-                            message: format!(
+                    if key != b"profile" {
+                        return Err(self.parse_err_at(
+                            pos,
+                            pos_line,
+                            &format!(
                                 "Expected 'profile' in configuration, found '{}' instead",
-                                key
+                                String::from_utf8_lossy(&key)
                             ),
-                        });
+                        ));
                     }
                     let name = String::from_utf8_lossy(&value).into_owned();
                     let mut entry = Entry::new(name);
@@ -699,6 +929,96 @@ impl<R: Read + Seek> LdapviParser<R> {
             }
         }
     }
+
+    /// Read the next top-level record from a configuration file: either a
+    /// `profile NAME` block (same as [`LdapviParser::read_profile`]) or a
+    /// single-line `alias NAME = ARGS...` shorthand. Returns `Ok(None)` at
+    /// EOF. Unlike `read_profile`, an unrecognized header is an error that
+    /// names both accepted keywords, since a config file mixing profiles
+    /// and aliases is expected to use this reader for both.
+    pub fn read_config_entry(&mut self) -> Result<Option<ConfigEntry>> {
+        loop {
+            let pos = self.cr.tell()?;
+            let pos_line = self.cr.line();
+            match self.read_line()? {
+                None => {
+                    if self.cr.at_eof()? {
+                        return Ok(None);
+                    }
+                    continue;
+                }
+                Some((key, value)) => {
+                    if key == b"profile" {
+                        let name = String::from_utf8_lossy(&value).into_owned();
+                        let mut entry = Entry::new(name);
+                        self.read_attrval_body(&mut entry)?;
+                        return Ok(Some(ConfigEntry::Profile(entry)));
+                    } else if key == b"alias" {
+                        let rest = String::from_utf8_lossy(&value).into_owned();
+                        let (name, expansion) = rest.split_once('=').ok_or_else(|| {
+                            self.parse_err_at(
+                                pos,
+                                pos_line,
+                                "Expected 'alias NAME = ARGS...' in configuration",
+                            )
+                        })?;
+                        return Ok(Some(ConfigEntry::Alias {
+                            name: name.trim().to_string(),
+                            expansion: split_alias_expansion(expansion),
+                        }));
+                    } else {
+                        return Err(self.parse_err_at(
+                            pos,
+                            pos_line,
+                            &format!(
+                                "Expected 'profile' or 'alias' in configuration, found '{}' instead",
+                                String::from_utf8_lossy(&key)
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// One top-level record read by [`LdapviParser::read_config_entry`].
+pub enum ConfigEntry {
+    Profile(Entry),
+    Alias { name: String, expansion: Vec<String> },
+}
+
+/// Split the right-hand side of an `alias NAME = ARGS...` line into
+/// individual argv tokens: whitespace-separated, with `"..."` spans kept
+/// together as one token so an expansion can carry things like an LDAP
+/// filter containing spaces.
+fn split_alias_expansion(s: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.trim().chars().peekable();
+    while chars.peek().is_some() {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        if chars.peek().is_none() {
+            break;
+        }
+        let mut token = String::new();
+        if chars.peek() == Some(&'"') {
+            chars.next();
+            for c in chars.by_ref() {
+                if c == '"' {
+                    break;
+                }
+                token.push(c);
+            }
+        } else {
+            while chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                token.push(chars.next().unwrap());
+            }
+        }
+        tokens.push(token);
+    }
+    tokens
 }
 
 // ===========================================================================
@@ -836,6 +1156,25 @@ mod tests {
         assert_eq!(entry.dn, "cn=second,dc=example,dc=com");
     }
 
+    #[test]
+    fn non_utf8_attribute_name_parses_and_defers_validation() {
+        // An attribute descriptor carrying a raw byte >= 0x80 is not valid
+        // UTF-8, but parsing must still succeed -- only `ad.as_str()`
+        // should notice, and it should report the offset of the bad byte.
+        let mut data = b"add cn=foo,dc=example,dc=com\n".to_vec();
+        data.extend_from_slice(b"cn\xff bar\n\n");
+        let mut p = parser(&data);
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        assert_eq!(entry.attributes.len(), 1);
+
+        let attr = &entry.attributes[0];
+        assert_eq!(attr.ad.as_bytes(), b"cn\xff");
+        assert_eq!(attr.values[0], b"bar");
+
+        let err = attr.ad.as_str().unwrap_err();
+        assert_eq!(err.1, 2);
+    }
+
     #[test]
     fn entry_eof_terminates_record() {
         let mut p = parser(
@@ -871,7 +1210,10 @@ mod tests {
               cn foo\n\
               \n",
         );
-        assert!(p.read_entry(None).is_err());
+        match p.read_entry(None) {
+            Err(LdapviError::Parse { line, .. }) => assert_eq!(line, 1),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
     }
 
     // ── Group 4: Comments ─────────────────────────────────────────
@@ -969,7 +1311,76 @@ mod tests {
               cn:: !!!!\n\
               \n",
         );
-        assert!(p.read_entry(None).is_err());
+        match p.read_entry(None) {
+            Err(LdapviError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn base64_invalid_reports_position() {
+        let mut p = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              cn:: Zm9v!\n\
+              \n",
+        );
+        match p.read_entry(None) {
+            Err(LdapviError::Parse { message, line, .. }) => {
+                assert!(message.contains("Invalid Base64"));
+                assert_eq!(line, 2);
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn base64_folded_decodes_incrementally() {
+        let mut p = parser(b"add cn=foo,dc=example,dc=com\ncn:: Zm9v\n YmFy\n\n");
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        let a = find_attr(&entry, "cn").unwrap();
+        assert_eq!(a.values[0], b"foobar");
+    }
+
+    // ── Group 6b: Quoted-printable encoding ────────────────────────
+
+    #[test]
+    fn quoted_printable_value() {
+        let mut p = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              description:q =C3=A9t=C3=A9\n\
+              \n",
+        );
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        let a = find_attr(&entry, "description").unwrap();
+        assert_eq!(a.values[0], "été".as_bytes());
+    }
+
+    #[test]
+    fn quoted_printable_soft_line_break() {
+        // "ab=\n cd\n": the trailing '=' is a soft break, consumed together
+        // with the newline it precedes; since that newline also folds
+        // (next physical line starts with a space), "ab" and "cd" end up
+        // concatenated with nothing in between.
+        let mut p = parser(b"add cn=foo,dc=example,dc=com\ndescription:q ab=\n cd\n\n");
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        let a = find_attr(&entry, "description").unwrap();
+        assert_eq!(a.values[0], b"abcd");
+    }
+
+    #[test]
+    fn quoted_printable_invalid_escape() {
+        let mut p = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              description:q =ZZ\n\
+              \n",
+        );
+        match p.read_entry(None) {
+            Err(LdapviError::Parse { message, line, .. }) => {
+                assert!(message.contains("quoted-printable"));
+                assert_eq!(line, 2);
+            }
+            other => panic!("expected a parse error, got {:?}", other),
+        }
     }
 
     // ── Group 7: File URL encoding ────────────────────────────────
@@ -1005,11 +1416,45 @@ mod tests {
               cn:< http://example.com/data\n\
               \n",
         );
+        match p.read_entry(None) {
+            Err(LdapviError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn file_url_over_limit() {
+        let dir = std::env::temp_dir();
+        let path = dir.join("ldapvi_test_parse_file_url_over_limit");
+        {
+            // Sparse file: exercises the size check without actually
+            // writing hundreds of megabytes to disk.
+            let f = std::fs::File::create(&path).unwrap();
+            f.set_len(MAX_INLINE_VALUE_BYTES + 1).unwrap();
+        }
+
+        let input = format!(
+            "add cn=foo,dc=example,dc=com\ncn:< file://{}\n\n",
+            path.display()
+        );
+        let mut p = LdapviParser::new(Cursor::new(input.as_bytes()));
         assert!(p.read_entry(None).is_err());
+
+        std::fs::remove_file(&path).ok();
     }
 
     // ── Group 8: Numeric binary encoding ──────────────────────────
 
+    #[test]
+    fn numeric_encoding_over_limit() {
+        let input = format!(
+            "add cn=foo,dc=example,dc=com\ncn:{} foo\n\n",
+            MAX_INLINE_VALUE_BYTES + 1
+        );
+        let mut p = LdapviParser::new(Cursor::new(input.as_bytes()));
+        assert!(p.read_entry(None).is_err());
+    }
+
     #[test]
     fn numeric_encoding() {
         let mut p = parser(
@@ -1089,9 +1534,87 @@ mod tests {
         assert_eq!(&a.values[0][..6], b"{SMD5}");
     }
 
+    #[test]
+    fn ssha256_encoding() {
+        let mut p = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              userPassword:ssha256 secret\n\
+              \n",
+        );
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        let a = find_attr(&entry, "userPassword").unwrap();
+        assert!(a.values[0].len() >= 9);
+        assert_eq!(&a.values[0][..9], b"{SSHA256}");
+    }
+
+    #[test]
+    fn ssha512_encoding() {
+        let mut p = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              userPassword:ssha512 secret\n\
+              \n",
+        );
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        let a = find_attr(&entry, "userPassword").unwrap();
+        assert!(a.values[0].len() >= 9);
+        assert_eq!(&a.values[0][..9], b"{SSHA512}");
+    }
+
+    #[test]
+    fn ssha256_distinct_salt_per_invocation() {
+        let mut p1 = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              userPassword:ssha256 secret\n\
+              \n",
+        );
+        let mut p2 = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              userPassword:ssha256 secret\n\
+              \n",
+        );
+        let (_, e1, _) = p1.read_entry(None).unwrap().unwrap();
+        let (_, e2, _) = p2.read_entry(None).unwrap().unwrap();
+        let v1 = &find_attr(&e1, "userPassword").unwrap().values[0];
+        let v2 = &find_attr(&e2, "userPassword").unwrap().values[0];
+        assert_ne!(v1, v2);
+    }
+
+    #[test]
+    fn pbkdf2_encoding() {
+        let mut p = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              userPassword:pbkdf2 secret\n\
+              \n",
+        );
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        let a = find_attr(&entry, "userPassword").unwrap();
+        assert!(a.values[0].len() >= 15);
+        assert_eq!(&a.values[0][..15], b"{PBKDF2-SHA512}");
+        let tail = std::str::from_utf8(&a.values[0][15..]).unwrap();
+        assert_eq!(tail.split('$').count(), 3);
+    }
+
+    #[test]
+    fn pbkdf2_distinct_salt_per_invocation() {
+        let mut p1 = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              userPassword:pbkdf2 secret\n\
+              \n",
+        );
+        let mut p2 = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              userPassword:pbkdf2 secret\n\
+              \n",
+        );
+        let (_, e1, _) = p1.read_entry(None).unwrap().unwrap();
+        let (_, e2, _) = p2.read_entry(None).unwrap().unwrap();
+        let v1 = &find_attr(&e1, "userPassword").unwrap().values[0];
+        let v2 = &find_attr(&e2, "userPassword").unwrap().values[0];
+        assert_ne!(v1, v2);
+    }
+
     // ── Group 10: Crypt encodings ─────────────────────────────────
 
-    #[cfg(unix)]
     #[test]
     fn crypt_encoding() {
         let mut p = parser(
@@ -1105,6 +1628,100 @@ mod tests {
         assert_eq!(&a.values[0][..7], b"{CRYPT}");
     }
 
+    #[test]
+    fn cryptmd5_encoding() {
+        let mut p = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              userPassword:cryptmd5 secret\n\
+              \n",
+        );
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        let a = find_attr(&entry, "userPassword").unwrap();
+        assert_eq!(&a.values[0][..7], b"{CRYPT}");
+        assert!(a.values[0][7..].starts_with(b"$1$"));
+    }
+
+    #[test]
+    fn sha256crypt_encoding() {
+        let mut p = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              userPassword:sha256crypt secret\n\
+              \n",
+        );
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        let a = find_attr(&entry, "userPassword").unwrap();
+        assert_eq!(&a.values[0][..7], b"{CRYPT}");
+        assert!(a.values[0][7..].starts_with(b"$5$"));
+    }
+
+    #[test]
+    fn sha512crypt_encoding() {
+        let mut p = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              userPassword:sha512crypt secret\n\
+              \n",
+        );
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        let a = find_attr(&entry, "userPassword").unwrap();
+        assert_eq!(&a.values[0][..7], b"{CRYPT}");
+        assert!(a.values[0][7..].starts_with(b"$6$"));
+    }
+
+    // ── Group 10b: Custom encoding registry ────────────────────────
+
+    #[test]
+    fn register_encoding_is_used() {
+        let mut p = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              cn:hex 666f6f\n\
+              \n",
+        );
+        p.register_encoding(
+            "hex",
+            Rc::new(|raw| {
+                let s = std::str::from_utf8(raw).map_err(|e| LdapviError::Other(e.to_string()))?;
+                let mut out = Vec::with_capacity(s.len() / 2);
+                let mut chars = s.chars();
+                while let (Some(hi), Some(lo)) = (chars.next(), chars.next()) {
+                    let byte = u8::from_str_radix(&format!("{}{}", hi, lo), 16)
+                        .map_err(|e| LdapviError::Other(e.to_string()))?;
+                    out.push(byte);
+                }
+                Ok(out)
+            }),
+        );
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        let a = find_attr(&entry, "cn").unwrap();
+        assert_eq!(a.values[0], b"foo");
+    }
+
+    #[test]
+    fn register_encoding_overrides_builtin() {
+        let mut p = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              userPassword:sha secret\n\
+              \n",
+        );
+        p.register_encoding("sha", Rc::new(|_raw| Ok(b"{CUSTOM}".to_vec())));
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        let a = find_attr(&entry, "userPassword").unwrap();
+        assert_eq!(a.values[0], b"{CUSTOM}");
+    }
+
+    #[test]
+    fn unregistered_name_falls_back_to_numeric() {
+        // "3" is not a registered encoding name, so it's parsed as a
+        // fixed-length binary read, same as before the registry existed.
+        let mut p = parser(
+            b"add cn=foo,dc=example,dc=com\n\
+              cn:3 foo\n\
+              \n",
+        );
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        let a = find_attr(&entry, "cn").unwrap();
+        assert_eq!(a.values[0], b"foo");
+    }
+
     // ── Group 11: Key types ───────────────────────────────────────
 
     #[test]
@@ -1137,7 +1754,10 @@ mod tests {
               cn foo\n\
               \n",
         );
-        assert!(p.read_entry(None).is_err());
+        match p.read_entry(None) {
+            Err(LdapviError::Parse { line, .. }) => assert_eq!(line, 1),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
     }
 
     // ── Group 12: Delete record ───────────────────────────────────
@@ -1255,7 +1875,10 @@ mod tests {
               bogus mail\n\
               \n",
         );
-        assert!(p.read_modify(None).is_err());
+        match p.read_modify(None) {
+            Err(LdapviError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
     }
 
     // ── Group 14: Rename record ───────────────────────────────────
@@ -1424,6 +2047,70 @@ mod tests {
         assert!(p.read_profile().is_err());
     }
 
+    // ── Group 17b: read_config_entry ──────────────────────────────
+
+    #[test]
+    fn read_config_entry_profile() {
+        let mut p = parser(
+            b"profile myprofile\n\
+              host ldap.example.com\n\
+              \n",
+        );
+        match p.read_config_entry().unwrap().unwrap() {
+            ConfigEntry::Profile(entry) => assert_eq!(entry.dn, "myprofile"),
+            ConfigEntry::Alias { .. } => panic!("expected a profile"),
+        }
+    }
+
+    #[test]
+    fn read_config_entry_alias() {
+        let mut p = parser(b"alias fixmail = --profile prod \"(mail=*)\"\n");
+        match p.read_config_entry().unwrap().unwrap() {
+            ConfigEntry::Alias { name, expansion } => {
+                assert_eq!(name, "fixmail");
+                assert_eq!(expansion, vec!["--profile", "prod", "(mail=*)"]);
+            }
+            ConfigEntry::Profile(_) => panic!("expected an alias"),
+        }
+    }
+
+    #[test]
+    fn read_config_entry_mixed_profile_and_alias() {
+        let mut p = parser(
+            b"alias fixmail = --profile prod\n\
+              profile prod\n\
+              host ldap.example.com\n\
+              \n",
+        );
+        assert!(matches!(
+            p.read_config_entry().unwrap().unwrap(),
+            ConfigEntry::Alias { .. }
+        ));
+        assert!(matches!(
+            p.read_config_entry().unwrap().unwrap(),
+            ConfigEntry::Profile(_)
+        ));
+        assert!(p.read_config_entry().unwrap().is_none());
+    }
+
+    #[test]
+    fn read_config_entry_rejects_unknown_header() {
+        let mut p = parser(b"notprofile myprofile\nhost ldap.example.com\n\n");
+        assert!(p.read_config_entry().is_err());
+    }
+
+    #[test]
+    fn read_config_entry_alias_missing_equals_is_error() {
+        let mut p = parser(b"alias fixmail --profile prod\n");
+        assert!(p.read_config_entry().is_err());
+    }
+
+    #[test]
+    fn split_alias_expansion_quoted_segment_kept_together() {
+        let tokens = split_alias_expansion(" --base ou=people,dc=x,dc=com \"(mail=*)\" ");
+        assert_eq!(tokens, vec!["--base", "ou=people,dc=x,dc=com", "(mail=*)"]);
+    }
+
     // ── Group 18: Error conditions ────────────────────────────────
 
     #[test]
@@ -1433,7 +2120,10 @@ mod tests {
               cn:bogus val\n\
               \n",
         );
-        assert!(p.read_entry(None).is_err());
+        match p.read_entry(None) {
+            Err(LdapviError::Parse { line, .. }) => assert_eq!(line, 2),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
     }
 
     #[test]
@@ -0,0 +1,269 @@
+//! Operation queue for pipelined/asynchronous commit.
+//!
+//! `compare_streams` drives a `SyncDiffHandler` synchronously, one call per
+//! entry, in an order that already respects most ordering invariants
+//! (adds/renames/changes in stream order, deletions last).  `QueueingHandler`
+//! captures each call as an `Operation` instead of executing it immediately,
+//! so a caller can replay the queue with a concurrent, bounded-parallelism
+//! executor while still respecting the DN-hierarchy dependencies that allow
+//! operations to be reordered safely.
+
+use crate::data::{Entry, LdapMod};
+use crate::diff::{DiffHandler, DiffResult, SyncDiffHandler};
+
+/// The kind of change a queued `Operation` represents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpKind {
+    Add,
+    Delete,
+    Modify,
+    Rename,
+}
+
+/// A single queued change, independent of how (or whether) it is ever
+/// executed against a server.
+#[derive(Debug, Clone)]
+pub struct Operation {
+    pub entry_index: i32,
+    pub kind: OpKind,
+    pub dn: String,
+    /// Set for `Rename`: the DN the entry is moving to.
+    pub new_dn: Option<String>,
+    pub mods: Vec<LdapMod>,
+    pub delete_old_rdn: bool,
+}
+
+/// A `SyncDiffHandler` that records every call as an `Operation` instead of
+/// executing it, so the batch can be scheduled by a different backend.
+#[derive(Default)]
+pub struct QueueingHandler {
+    pub ops: Vec<Operation>,
+}
+
+impl DiffHandler for QueueingHandler {}
+
+impl SyncDiffHandler for QueueingHandler {
+    fn handle_add(&mut self, n: i32, dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
+        self.ops.push(Operation {
+            entry_index: n,
+            kind: OpKind::Add,
+            dn: dn.to_string(),
+            new_dn: None,
+            mods: mods.to_vec(),
+            delete_old_rdn: false,
+        });
+        Ok(())
+    }
+
+    fn handle_delete(&mut self, n: i32, dn: &str) -> DiffResult<()> {
+        self.ops.push(Operation {
+            entry_index: n,
+            kind: OpKind::Delete,
+            dn: dn.to_string(),
+            new_dn: None,
+            mods: Vec::new(),
+            delete_old_rdn: false,
+        });
+        Ok(())
+    }
+
+    fn handle_change(&mut self, n: i32, _old_dn: &str, new_dn: &str, mods: &[LdapMod]) -> DiffResult<()> {
+        self.ops.push(Operation {
+            entry_index: n,
+            kind: OpKind::Modify,
+            dn: new_dn.to_string(),
+            new_dn: None,
+            mods: mods.to_vec(),
+            delete_old_rdn: false,
+        });
+        Ok(())
+    }
+
+    fn handle_rename(&mut self, n: i32, old_dn: &str, entry: &Entry) -> DiffResult<()> {
+        self.ops.push(Operation {
+            entry_index: n,
+            kind: OpKind::Rename,
+            dn: old_dn.to_string(),
+            new_dn: Some(entry.dn.clone()),
+            mods: Vec::new(),
+            delete_old_rdn: false,
+        });
+        Ok(())
+    }
+
+    fn handle_rename0(&mut self, n: i32, old_dn: &str, new_dn: &str, deleteoldrdn: bool) -> DiffResult<()> {
+        self.ops.push(Operation {
+            entry_index: n,
+            kind: OpKind::Rename,
+            dn: old_dn.to_string(),
+            new_dn: Some(new_dn.to_string()),
+            mods: Vec::new(),
+            delete_old_rdn: deleteoldrdn,
+        });
+        Ok(())
+    }
+}
+
+/// True if `dn` is an immediate or transitive child of `ancestor`.
+fn is_under(ancestor: &str, dn: &str) -> bool {
+    if dn.len() <= ancestor.len() || !dn.ends_with(ancestor) {
+        return false;
+    }
+    let prefix_len = dn.len() - ancestor.len();
+    dn.as_bytes()[prefix_len - 1] == b','
+}
+
+/// Compute, for each operation, the indices of operations in the same
+/// batch it must wait for before it may run:
+///
+///   - An `Add` whose DN is a descendant of another `Add`'s DN depends on
+///     that parent add (the parent must exist first).
+///   - A `Delete` whose DN is an ancestor of another `Delete`'s DN depends
+///     on that child delete (the children must be gone before the parent
+///     can be removed), the mirror image of the add-parent rule.
+///   - Any operation (other than another rename) targeting a DN at or
+///     under a `Rename`'s old or new location depends on that rename,
+///     since the subtree move must complete before siblings underneath
+///     it are touched.
+pub fn compute_dependencies(ops: &[Operation]) -> Vec<Vec<usize>> {
+    let mut deps: Vec<Vec<usize>> = vec![Vec::new(); ops.len()];
+
+    for (i, op) in ops.iter().enumerate() {
+        for (j, other) in ops.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            if op.kind == OpKind::Add && other.kind == OpKind::Add && is_under(&other.dn, &op.dn) {
+                deps[i].push(j);
+                continue;
+            }
+            if op.kind == OpKind::Delete && other.kind == OpKind::Delete && is_under(&op.dn, &other.dn) {
+                deps[i].push(j);
+                continue;
+            }
+            if other.kind == OpKind::Rename && op.kind != OpKind::Rename {
+                let old_dn = other.dn.as_str();
+                let new_dn = other.new_dn.as_deref().unwrap_or(old_dn);
+                if op.dn == old_dn
+                    || op.dn == new_dn
+                    || is_under(old_dn, &op.dn)
+                    || is_under(new_dn, &op.dn)
+                {
+                    deps[i].push(j);
+                }
+            }
+        }
+    }
+
+    deps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(n: i32, dn: &str) -> Operation {
+        Operation {
+            entry_index: n,
+            kind: OpKind::Add,
+            dn: dn.to_string(),
+            new_dn: None,
+            mods: Vec::new(),
+            delete_old_rdn: false,
+        }
+    }
+
+    fn delete(n: i32, dn: &str) -> Operation {
+        Operation {
+            entry_index: n,
+            kind: OpKind::Delete,
+            dn: dn.to_string(),
+            new_dn: None,
+            mods: Vec::new(),
+            delete_old_rdn: false,
+        }
+    }
+
+    fn rename(n: i32, old_dn: &str, new_dn: &str) -> Operation {
+        Operation {
+            entry_index: n,
+            kind: OpKind::Rename,
+            dn: old_dn.to_string(),
+            new_dn: Some(new_dn.to_string()),
+            mods: Vec::new(),
+            delete_old_rdn: true,
+        }
+    }
+
+    fn modify(n: i32, dn: &str) -> Operation {
+        Operation {
+            entry_index: n,
+            kind: OpKind::Modify,
+            dn: dn.to_string(),
+            new_dn: None,
+            mods: Vec::new(),
+            delete_old_rdn: false,
+        }
+    }
+
+    #[test]
+    fn independent_adds_have_no_dependencies() {
+        let ops = vec![
+            add(0, "cn=a,dc=example,dc=com"),
+            add(1, "cn=b,dc=example,dc=com"),
+        ];
+        let deps = compute_dependencies(&ops);
+        assert!(deps[0].is_empty());
+        assert!(deps[1].is_empty());
+    }
+
+    #[test]
+    fn child_add_depends_on_parent_add() {
+        let ops = vec![
+            add(0, "ou=people,dc=example,dc=com"),
+            add(1, "cn=a,ou=people,dc=example,dc=com"),
+        ];
+        let deps = compute_dependencies(&ops);
+        assert_eq!(deps[1], vec![0]);
+        assert!(deps[0].is_empty());
+    }
+
+    #[test]
+    fn parent_delete_depends_on_child_delete() {
+        let ops = vec![
+            delete(0, "ou=people,dc=example,dc=com"),
+            delete(1, "cn=a,ou=people,dc=example,dc=com"),
+        ];
+        let deps = compute_dependencies(&ops);
+        assert_eq!(deps[0], vec![1]);
+        assert!(deps[1].is_empty());
+    }
+
+    #[test]
+    fn modify_under_rename_waits_for_rename() {
+        let ops = vec![
+            rename(
+                0,
+                "ou=old,dc=example,dc=com",
+                "ou=new,dc=example,dc=com",
+            ),
+            modify(1, "cn=a,ou=new,dc=example,dc=com"),
+        ];
+        let deps = compute_dependencies(&ops);
+        assert_eq!(deps[1], vec![0]);
+    }
+
+    #[test]
+    fn unrelated_modify_has_no_dependency() {
+        let ops = vec![
+            rename(
+                0,
+                "ou=old,dc=example,dc=com",
+                "ou=new,dc=example,dc=com",
+            ),
+            modify(1, "cn=a,dc=example,dc=com"),
+        ];
+        let deps = compute_dependencies(&ops);
+        assert!(deps[1].is_empty());
+    }
+}
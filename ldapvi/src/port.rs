@@ -1,5 +1,6 @@
 use md5::Md5;
-use sha1::{Digest as _, Sha1};
+use sha1::{Digest, Sha1};
+use sha2::{Sha224, Sha256, Sha384, Sha512};
 
 use crate::base64;
 
@@ -9,13 +10,21 @@ pub fn append_sha(dst: &mut String, key: &str) {
     base64::append_base64(dst, &hash);
 }
 
-/// Compute salted SHA1 hash of `key` and append as base64.
-/// Uses a random 4-byte salt appended after the hash.
-pub fn append_ssha(dst: &mut String, key: &str) {
-    append_ssha_with_salt(dst, key, &random_salt())
+/// Compute salted SHA1 hash of `key` and append as base64, using a fresh
+/// random salt of [`DEFAULT_SALT_LEN`] bytes appended after the hash.
+pub fn append_ssha(dst: &mut String, key: &str) -> Result<(), getrandom::Error> {
+    append_ssha_with_salt_len(dst, key, DEFAULT_SALT_LEN)
 }
 
-fn append_ssha_with_salt(dst: &mut String, key: &str, salt: &[u8; 4]) {
+/// Like [`append_ssha`], but with a configurable salt length in bytes
+/// (OpenLDAP commonly uses 8 rather than the traditional 4).
+pub fn append_ssha_with_salt_len(dst: &mut String, key: &str, salt_len: usize) -> Result<(), getrandom::Error> {
+    let salt = random_salt(salt_len)?;
+    append_ssha_with_salt(dst, key, &salt);
+    Ok(())
+}
+
+fn append_ssha_with_salt(dst: &mut String, key: &str, salt: &[u8]) {
     let mut hasher = Sha1::new();
     hasher.update(key.as_bytes());
     hasher.update(salt);
@@ -32,13 +41,21 @@ pub fn append_md5(dst: &mut String, key: &str) {
     base64::append_base64(dst, &hash);
 }
 
-/// Compute salted MD5 hash of `key` and append as base64.
-/// Uses a random 4-byte salt appended after the hash.
-pub fn append_smd5(dst: &mut String, key: &str) {
-    append_smd5_with_salt(dst, key, &random_salt())
+/// Compute salted MD5 hash of `key` and append as base64, using a fresh
+/// random salt of [`DEFAULT_SALT_LEN`] bytes appended after the hash.
+pub fn append_smd5(dst: &mut String, key: &str) -> Result<(), getrandom::Error> {
+    append_smd5_with_salt_len(dst, key, DEFAULT_SALT_LEN)
+}
+
+/// Like [`append_smd5`], but with a configurable salt length in bytes
+/// (OpenLDAP commonly uses 8 rather than the traditional 4).
+pub fn append_smd5_with_salt_len(dst: &mut String, key: &str, salt_len: usize) -> Result<(), getrandom::Error> {
+    let salt = random_salt(salt_len)?;
+    append_smd5_with_salt(dst, key, &salt);
+    Ok(())
 }
 
-fn append_smd5_with_salt(dst: &mut String, key: &str, salt: &[u8; 4]) {
+fn append_smd5_with_salt(dst: &mut String, key: &str, salt: &[u8]) {
     let mut hasher = Md5::new();
     hasher.update(key.as_bytes());
     hasher.update(salt);
@@ -49,17 +66,863 @@ fn append_smd5_with_salt(dst: &mut String, key: &str, salt: &[u8; 4]) {
     base64::append_base64(dst, &combined);
 }
 
-fn random_salt() -> [u8; 4] {
-    let mut salt = [0u8; 4];
-    // Use getrandom for portability; fall back to /dev/urandom
-    #[cfg(target_family = "unix")]
-    {
-        use std::io::Read;
-        if let Ok(mut f) = std::fs::File::open("/dev/urandom") {
-            let _ = f.read_exact(&mut salt);
+/// Compute SHA-256 hash of `key` and append as base64.
+pub fn append_sha256(dst: &mut String, key: &str) {
+    let hash = Sha256::digest(key.as_bytes());
+    base64::append_base64(dst, &hash);
+}
+
+/// Compute salted SHA-256 hash of `key` and append as base64.
+/// Uses a random [`DEFAULT_SALT_LEN`]-byte salt appended after the hash,
+/// like `append_ssha`.
+pub fn append_ssha256(dst: &mut String, key: &str) -> Result<(), getrandom::Error> {
+    let salt = random_salt(DEFAULT_SALT_LEN)?;
+    append_ssha256_with_salt(dst, key, &salt);
+    Ok(())
+}
+
+fn append_ssha256_with_salt(dst: &mut String, key: &str, salt: &[u8]) {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hasher.update(salt);
+    let hash = hasher.finalize();
+    let mut combined = Vec::with_capacity(hash.len() + salt.len());
+    combined.extend_from_slice(&hash);
+    combined.extend_from_slice(salt);
+    base64::append_base64(dst, &combined);
+}
+
+/// Compute SHA-512 hash of `key` and append as base64.
+pub fn append_sha512(dst: &mut String, key: &str) {
+    let hash = Sha512::digest(key.as_bytes());
+    base64::append_base64(dst, &hash);
+}
+
+/// Compute salted SHA-512 hash of `key` and append as base64.
+/// Uses a random [`DEFAULT_SALT_LEN`]-byte salt appended after the hash,
+/// like `append_ssha`.
+pub fn append_ssha512(dst: &mut String, key: &str) -> Result<(), getrandom::Error> {
+    let salt = random_salt(DEFAULT_SALT_LEN)?;
+    append_ssha512_with_salt(dst, key, &salt);
+    Ok(())
+}
+
+fn append_ssha512_with_salt(dst: &mut String, key: &str, salt: &[u8]) {
+    let mut hasher = Sha512::new();
+    hasher.update(key.as_bytes());
+    hasher.update(salt);
+    let hash = hasher.finalize();
+    let mut combined = Vec::with_capacity(hash.len() + salt.len());
+    combined.extend_from_slice(&hash);
+    combined.extend_from_slice(salt);
+    base64::append_base64(dst, &combined);
+}
+
+/// Compute SHA-224 hash of `key` and append as base64.
+pub fn append_sha224(dst: &mut String, key: &str) {
+    let hash = Sha224::digest(key.as_bytes());
+    base64::append_base64(dst, &hash);
+}
+
+/// Compute salted SHA-224 hash of `key` and append as base64.
+/// Uses a random [`DEFAULT_SALT_LEN`]-byte salt appended after the hash,
+/// like `append_ssha`.
+pub fn append_ssha224(dst: &mut String, key: &str) -> Result<(), getrandom::Error> {
+    let salt = random_salt(DEFAULT_SALT_LEN)?;
+    append_ssha224_with_salt(dst, key, &salt);
+    Ok(())
+}
+
+fn append_ssha224_with_salt(dst: &mut String, key: &str, salt: &[u8]) {
+    let mut hasher = Sha224::new();
+    hasher.update(key.as_bytes());
+    hasher.update(salt);
+    let hash = hasher.finalize();
+    let mut combined = Vec::with_capacity(hash.len() + salt.len());
+    combined.extend_from_slice(&hash);
+    combined.extend_from_slice(salt);
+    base64::append_base64(dst, &combined);
+}
+
+/// Compute SHA-384 hash of `key` and append as base64.
+pub fn append_sha384(dst: &mut String, key: &str) {
+    let hash = Sha384::digest(key.as_bytes());
+    base64::append_base64(dst, &hash);
+}
+
+/// Compute salted SHA-384 hash of `key` and append as base64.
+/// Uses a random [`DEFAULT_SALT_LEN`]-byte salt appended after the hash,
+/// like `append_ssha`.
+pub fn append_ssha384(dst: &mut String, key: &str) -> Result<(), getrandom::Error> {
+    let salt = random_salt(DEFAULT_SALT_LEN)?;
+    append_ssha384_with_salt(dst, key, &salt);
+    Ok(())
+}
+
+fn append_ssha384_with_salt(dst: &mut String, key: &str, salt: &[u8]) {
+    let mut hasher = Sha384::new();
+    hasher.update(key.as_bytes());
+    hasher.update(salt);
+    let hash = hasher.finalize();
+    let mut combined = Vec::with_capacity(hash.len() + salt.len());
+    combined.extend_from_slice(&hash);
+    combined.extend_from_slice(salt);
+    base64::append_base64(dst, &combined);
+}
+
+/// Which Unix crypt(3) algorithm [`append_crypt`] should produce, selected
+/// the same way the `crypt3` crate and glibc's `crypt()` do: by the `$id$`
+/// prefix of the resulting string (`$1$` MD5, `$5$` SHA-256, `$6$`
+/// SHA-512), or the bare 13-character form for traditional DES.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CryptScheme {
+    Des,
+    Md5,
+    Sha256 { rounds: usize },
+    Sha512 { rounds: usize },
+}
+
+/// Compute a Unix crypt(3) hash of `key` under `scheme` and append it to
+/// `dst`, with a fresh random salt (and `$id$rounds=N$salt$hash` framing
+/// for the SHA variants). Does not prepend a `{CRYPT}` tag; callers that
+/// want the RFC 2307 `{SCHEME}` convention add it themselves, as with the
+/// other `append_*` functions in this module.
+pub fn append_crypt(dst: &mut String, key: &str, scheme: CryptScheme) -> Result<(), getrandom::Error> {
+    let hash = match scheme {
+        CryptScheme::Des => crypt_des(key)?,
+        CryptScheme::Md5 => crypt_md5(key)?,
+        CryptScheme::Sha256 { rounds } => crypt_sha256_with_rounds(key, rounds)?,
+        CryptScheme::Sha512 { rounds } => crypt_sha512_with_rounds(key, rounds)?,
+    };
+    dst.push_str(&hash);
+    Ok(())
+}
+
+/// A digest-based RFC 2307 password scheme, unifying the `append_sha`/
+/// `append_ssha`/... family into a single entry point: `hash` produces a
+/// full `{SCHEME}base64...` value and `scheme_of`/`from_prefix` recover the
+/// scheme from one. Each variant's tag is looked up from [`SCHEME_TABLE`],
+/// and its digest and salting are chosen by one arm of `hash`, which calls
+/// the matching `append_*` function. `{CRYPT}` values are a separate format (see
+/// [`CryptScheme`]) and are not covered by this enum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordScheme {
+    Sha,
+    Ssha,
+    Md5,
+    Smd5,
+    Sha256,
+    Ssha256,
+    Sha512,
+    Ssha512,
+    Sha224,
+    Ssha224,
+    Sha384,
+    Ssha384,
+}
+
+/// `(tag, scheme)` pairs backing [`PasswordScheme::tag`], [`PasswordScheme::from_prefix`]
+/// and [`PasswordScheme::scheme_of`]. Adding a scheme is a single entry here
+/// plus a matching arm in [`PasswordScheme::hash`].
+const SCHEME_TABLE: &[(&str, PasswordScheme)] = &[
+    ("SHA", PasswordScheme::Sha),
+    ("SSHA", PasswordScheme::Ssha),
+    ("MD5", PasswordScheme::Md5),
+    ("SMD5", PasswordScheme::Smd5),
+    ("SHA256", PasswordScheme::Sha256),
+    ("SSHA256", PasswordScheme::Ssha256),
+    ("SHA512", PasswordScheme::Sha512),
+    ("SSHA512", PasswordScheme::Ssha512),
+    ("SHA224", PasswordScheme::Sha224),
+    ("SSHA224", PasswordScheme::Ssha224),
+    ("SHA384", PasswordScheme::Sha384),
+    ("SSHA384", PasswordScheme::Ssha384),
+];
+
+impl PasswordScheme {
+    /// The bare `{...}` tag for this scheme, e.g. `"SSHA"`.
+    pub fn tag(&self) -> &'static str {
+        SCHEME_TABLE.iter().find(|(_, scheme)| scheme == self).map(|(tag, _)| *tag).expect("every PasswordScheme variant has a SCHEME_TABLE entry")
+    }
+
+    /// Look up the scheme whose tag matches `tag` case-insensitively (no
+    /// surrounding braces, e.g. `"ssha"` or `"SSHA"`).
+    pub fn from_prefix(tag: &str) -> Option<Self> {
+        SCHEME_TABLE.iter().find(|(t, _)| t.eq_ignore_ascii_case(tag)).map(|(_, scheme)| *scheme)
+    }
+
+    /// Identify the scheme of an existing `{SCHEME}base64...` value, e.g.
+    /// produced by [`PasswordScheme::hash`] or the legacy `append_*`
+    /// functions. Returns `None` if `stored` has no recognized `{...}` tag.
+    pub fn scheme_of(stored: &str) -> Option<Self> {
+        let (tag, _) = parse_scheme(stored)?;
+        Self::from_prefix(tag)
+    }
+
+    /// Hash `key` under this scheme and return the full `{SCHEME}base64...`
+    /// value, with a fresh random salt for salted schemes.
+    pub fn hash(&self, key: &str) -> Result<String, getrandom::Error> {
+        let mut dst = format!("{{{}}}", self.tag());
+        match self {
+            PasswordScheme::Sha => append_sha(&mut dst, key),
+            PasswordScheme::Ssha => append_ssha(&mut dst, key)?,
+            PasswordScheme::Md5 => append_md5(&mut dst, key),
+            PasswordScheme::Smd5 => append_smd5(&mut dst, key)?,
+            PasswordScheme::Sha256 => append_sha256(&mut dst, key),
+            PasswordScheme::Ssha256 => append_ssha256(&mut dst, key)?,
+            PasswordScheme::Sha512 => append_sha512(&mut dst, key),
+            PasswordScheme::Ssha512 => append_ssha512(&mut dst, key)?,
+            PasswordScheme::Sha224 => append_sha224(&mut dst, key),
+            PasswordScheme::Ssha224 => append_ssha224(&mut dst, key)?,
+            PasswordScheme::Sha384 => append_sha384(&mut dst, key),
+            PasswordScheme::Ssha384 => append_ssha384(&mut dst, key)?,
+        }
+        Ok(dst)
+    }
+}
+
+/// Parse a `stored` value of the form `{SCHEME}base64`, as produced by the
+/// `append_*` functions above, and return it rehashed from `candidate`.
+///
+/// Unsalted schemes (`SHA`, `MD5`, `SHA256`, `SHA512`, `SHA224`, `SHA384`)
+/// hash `candidate` alone. Salted schemes (`SSHA`, `SMD5`, `SSHA256`,
+/// `SSHA512`, `SSHA224`, `SSHA384`) recover the trailing salt from the
+/// decoded payload -- the bytes after the digest, e.g. `[20..]` for
+/// `SSHA`'s 20-byte SHA1 digest or `[16..]` for `SMD5`'s 16-byte MD5
+/// digest -- and hash `candidate` followed by that salt. `CRYPT` is not
+/// base64 at all: its payload is the raw `crypt(3)` string, so the salt
+/// (and, for `$5$`/`$6$`, round count) is recovered from its `$id$...$`
+/// framing instead. Returns `false` for an unrecognized scheme or a
+/// payload that fails to base64-decode (or, for `CRYPT`, fails to parse).
+pub fn verify(stored: &str, candidate: &str) -> bool {
+    let Some((scheme, payload)) = parse_scheme(stored) else {
+        return false;
+    };
+
+    if scheme == "CRYPT" {
+        return verify_crypt(payload, candidate);
+    }
+
+    let Some(decoded) = base64::read_base64(payload) else {
+        return false;
+    };
+
+    match scheme {
+        "SHA" => ct_eq(&decoded, &Sha1::digest(candidate.as_bytes())),
+        "SSHA" => verify_salted::<Sha1>(&decoded, candidate, 20),
+        "MD5" => ct_eq(&decoded, &Md5::digest(candidate.as_bytes())),
+        "SMD5" => verify_salted::<Md5>(&decoded, candidate, 16),
+        "SHA256" => ct_eq(&decoded, &Sha256::digest(candidate.as_bytes())),
+        "SSHA256" => verify_salted::<Sha256>(&decoded, candidate, 32),
+        "SHA512" => ct_eq(&decoded, &Sha512::digest(candidate.as_bytes())),
+        "SSHA512" => verify_salted::<Sha512>(&decoded, candidate, 64),
+        "SHA224" => ct_eq(&decoded, &Sha224::digest(candidate.as_bytes())),
+        "SSHA224" => verify_salted::<Sha224>(&decoded, candidate, 28),
+        "SHA384" => ct_eq(&decoded, &Sha384::digest(candidate.as_bytes())),
+        "SSHA384" => verify_salted::<Sha384>(&decoded, candidate, 48),
+        _ => false,
+    }
+}
+
+/// Compare two byte slices for equality without short-circuiting on the
+/// first differing byte, so a `verify` call driven by attacker-controlled
+/// input doesn't leak how many leading bytes matched through timing.
+/// Unequal lengths are rejected up front (and the length itself isn't
+/// secret here -- every digest this is used with has a fixed, known size).
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
+
+/// Recompute the `crypt(3)` hash of `candidate` using the salt (and, for
+/// `$5$`/`$6$`, round count) recovered from `payload`, and compare against
+/// `payload` in constant time. Handles `$1$salt$hash` (MD5),
+/// `$5$[rounds=N$]salt$hash` (SHA-256), `$6$[rounds=N$]salt$hash`
+/// (SHA-512), and the bare 13-character DES form. Returns `false` for any
+/// other shape.
+fn verify_crypt(payload: &str, candidate: &str) -> bool {
+    if let Some(rest) = payload.strip_prefix("$1$") {
+        let salt = rest.split('$').next().unwrap_or("");
+        return ct_eq(crypt_md5_with_salt(candidate, salt).as_bytes(), payload.as_bytes());
+    }
+    if let Some(id) = payload.strip_prefix("$5$").map(|_| "5").or_else(|| payload.strip_prefix("$6$").map(|_| "6")) {
+        let rest = &payload[3..];
+        let mut parts = rest.splitn(2, '$');
+        let first = parts.next().unwrap_or("");
+        let (rounds, salt, explicit_rounds) = match first.strip_prefix("rounds=") {
+            Some(n) => (n.parse::<usize>().unwrap_or(5000), parts.next().unwrap_or("").split('$').next().unwrap_or(""), true),
+            None => (5000, first, false),
+        };
+        let mut recomputed = if id == "5" {
+            crypt_sha256_with_salt_and_rounds(candidate, salt, rounds)
+        } else {
+            crypt_sha512_with_salt_and_rounds(candidate, salt, rounds)
+        };
+        if !explicit_rounds {
+            // The encoder always writes `rounds=N$`; the default form omits it.
+            recomputed = recomputed.replacen("rounds=5000$", "", 1);
+        }
+        return ct_eq(recomputed.as_bytes(), payload.as_bytes());
+    }
+    if payload.len() == 13 && payload.is_char_boundary(2) {
+        let salt = &payload[..2];
+        return ct_eq(crypt_des_with_salt(candidate, salt).as_bytes(), payload.as_bytes());
+    }
+    false
+}
+
+/// Split `stored` into its leading `{SCHEME}` tag and trailing payload.
+fn parse_scheme(stored: &str) -> Option<(&str, &str)> {
+    let rest = stored.strip_prefix('{')?;
+    let end = rest.find('}')?;
+    Some((&rest[..end], &rest[end + 1..]))
+}
+
+/// Recompute `D`'s digest over `candidate` followed by the salt recovered
+/// from `decoded[hash_len..]`, and compare against `decoded[..hash_len]`.
+fn verify_salted<D: Digest>(decoded: &[u8], candidate: &str, hash_len: usize) -> bool {
+    if decoded.len() <= hash_len {
+        return false;
+    }
+    let (hash, salt) = decoded.split_at(hash_len);
+    let mut hasher = D::new();
+    hasher.update(candidate.as_bytes());
+    hasher.update(salt);
+    ct_eq(hasher.finalize().as_slice(), hash)
+}
+
+/// Default salt length in bytes for `append_ssha`/`append_smd5`, matching
+/// traditional ldapvi/OpenLDAP output. Deployments that want a longer salt
+/// (OpenLDAP itself commonly uses 8 bytes) should call
+/// `append_ssha_with_salt_len`/`append_smd5_with_salt_len` instead.
+pub const DEFAULT_SALT_LEN: usize = 4;
+
+/// Generate `len` bytes of cryptographically secure random salt via the
+/// `getrandom` crate, so every platform gets a real CSPRNG. Unlike the
+/// previous `/dev/urandom`-or-silent-zero-salt approach, an RNG failure is
+/// propagated to the caller rather than hidden behind a predictable salt.
+fn random_salt(len: usize) -> Result<Vec<u8>, getrandom::Error> {
+    let mut salt = vec![0u8; len];
+    getrandom::getrandom(&mut salt)?;
+    Ok(salt)
+}
+
+// ---------------------------------------------------------------------------
+// crypt(3)-style password encodings: DES, MD5, SHA-256 and SHA-512 crypt.
+//
+// These are pure-Rust re-implementations of the traditional Unix `crypt()`
+// algorithms (no FFI, no libcrypt), so `{CRYPT}` values can be produced on
+// any platform, including Windows and musl where `libcrypt` is unavailable.
+// ---------------------------------------------------------------------------
+
+/// The 64-character alphabet used by every crypt(3) variant, both to pick
+/// salt characters and to base64-encode the digest.
+const ITOA64: &[u8] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+fn random_salt_chars(n: usize) -> Result<String, getrandom::Error> {
+    let raw = random_salt(n)?;
+    Ok(raw.iter().map(|&b| ITOA64[(b & 63) as usize] as char).collect())
+}
+
+/// Encode `value`'s low `n * 6` bits, least-significant 6 bits first, using
+/// the crypt64 alphabet. Shared by the MD5, SHA-256 and SHA-512 encoders.
+fn to64(mut value: u32, n: usize) -> String {
+    let mut s = String::with_capacity(n);
+    for _ in 0..n {
+        s.push(ITOA64[(value & 0x3f) as usize] as char);
+        value >>= 6;
+    }
+    s
+}
+
+/// A pure-Rust implementation of the traditional DES-based `crypt()`
+/// algorithm: 25 rounds of salt-perturbed DES applied to an all-zero block,
+/// keyed by (up to) the first 8 bytes of the password.
+mod des_crypt {
+    const IP: [u8; 64] = [
+        58, 50, 42, 34, 26, 18, 10, 2, 60, 52, 44, 36, 28, 20, 12, 4, 62, 54, 46, 38, 30, 22, 14,
+        6, 64, 56, 48, 40, 32, 24, 16, 8, 57, 49, 41, 33, 25, 17, 9, 1, 59, 51, 43, 35, 27, 19,
+        11, 3, 61, 53, 45, 37, 29, 21, 13, 5, 63, 55, 47, 39, 31, 23, 15, 7,
+    ];
+
+    const FP: [u8; 64] = [
+        40, 8, 48, 16, 56, 24, 64, 32, 39, 7, 47, 15, 55, 23, 63, 31, 38, 6, 46, 14, 54, 22, 62,
+        30, 37, 5, 45, 13, 53, 21, 61, 29, 36, 4, 44, 12, 52, 20, 60, 28, 35, 3, 43, 11, 51, 19,
+        59, 27, 34, 2, 42, 10, 50, 18, 58, 26, 33, 1, 41, 9, 49, 17, 57, 25,
+    ];
+
+    const E: [u8; 48] = [
+        32, 1, 2, 3, 4, 5, 4, 5, 6, 7, 8, 9, 8, 9, 10, 11, 12, 13, 12, 13, 14, 15, 16, 17, 16, 17,
+        18, 19, 20, 21, 20, 21, 22, 23, 24, 25, 24, 25, 26, 27, 28, 29, 28, 29, 30, 31, 32, 1,
+    ];
+
+    const P: [u8; 32] = [
+        16, 7, 20, 21, 29, 12, 28, 17, 1, 15, 23, 26, 5, 18, 31, 10, 2, 8, 24, 14, 32, 27, 3, 9,
+        19, 13, 30, 6, 22, 11, 4, 25,
+    ];
+
+    const PC1: [u8; 56] = [
+        57, 49, 41, 33, 25, 17, 9, 1, 58, 50, 42, 34, 26, 18, 10, 2, 59, 51, 43, 35, 27, 19, 11,
+        3, 60, 52, 44, 36, 63, 55, 47, 39, 31, 23, 15, 7, 62, 54, 46, 38, 30, 22, 14, 6, 61, 53,
+        45, 37, 29, 21, 13, 5, 28, 20, 12, 4,
+    ];
+
+    const PC2: [u8; 48] = [
+        14, 17, 11, 24, 1, 5, 3, 28, 15, 6, 21, 10, 23, 19, 12, 4, 26, 8, 16, 7, 27, 20, 13, 2,
+        41, 52, 31, 37, 47, 55, 30, 40, 51, 45, 33, 48, 44, 49, 39, 56, 34, 53, 46, 42, 50, 36,
+        29, 32,
+    ];
+
+    const SHIFTS: [u8; 16] = [1, 1, 2, 2, 2, 2, 2, 2, 1, 2, 2, 2, 2, 2, 2, 1];
+
+    #[rustfmt::skip]
+    const S: [[[u8; 16]; 4]; 8] = [
+        [
+            [14, 4, 13, 1, 2, 15, 11, 8, 3, 10, 6, 12, 5, 9, 0, 7],
+            [0, 15, 7, 4, 14, 2, 13, 1, 10, 6, 12, 11, 9, 5, 3, 8],
+            [4, 1, 14, 8, 13, 6, 2, 11, 15, 12, 9, 7, 3, 10, 5, 0],
+            [15, 12, 8, 2, 4, 9, 1, 7, 5, 11, 3, 14, 10, 0, 6, 13],
+        ],
+        [
+            [15, 1, 8, 14, 6, 11, 3, 4, 9, 7, 2, 13, 12, 0, 5, 10],
+            [3, 13, 4, 7, 15, 2, 8, 14, 12, 0, 1, 10, 6, 9, 11, 5],
+            [0, 14, 7, 11, 10, 4, 13, 1, 5, 8, 12, 6, 9, 3, 2, 15],
+            [13, 8, 10, 1, 3, 15, 4, 2, 11, 6, 7, 12, 0, 5, 14, 9],
+        ],
+        [
+            [10, 0, 9, 14, 6, 3, 15, 5, 1, 13, 12, 7, 11, 4, 2, 8],
+            [13, 7, 0, 9, 3, 4, 6, 10, 2, 8, 5, 14, 12, 11, 15, 1],
+            [13, 6, 4, 9, 8, 15, 3, 0, 11, 1, 2, 12, 5, 10, 14, 7],
+            [1, 10, 13, 0, 6, 9, 8, 7, 4, 15, 14, 3, 11, 5, 2, 12],
+        ],
+        [
+            [7, 13, 14, 3, 0, 6, 9, 10, 1, 2, 8, 5, 11, 12, 4, 15],
+            [13, 8, 11, 5, 6, 15, 0, 3, 4, 7, 2, 12, 1, 10, 14, 9],
+            [10, 6, 9, 0, 12, 11, 7, 13, 15, 1, 3, 14, 5, 2, 8, 4],
+            [3, 15, 0, 6, 10, 1, 13, 8, 9, 4, 5, 11, 12, 7, 2, 14],
+        ],
+        [
+            [2, 12, 4, 1, 7, 10, 11, 6, 8, 5, 3, 15, 13, 0, 14, 9],
+            [14, 11, 2, 12, 4, 7, 13, 1, 5, 0, 15, 10, 3, 9, 8, 6],
+            [4, 2, 1, 11, 10, 13, 7, 8, 15, 9, 12, 5, 6, 3, 0, 14],
+            [11, 8, 12, 7, 1, 14, 2, 13, 6, 15, 0, 9, 10, 4, 5, 3],
+        ],
+        [
+            [12, 1, 10, 15, 9, 2, 6, 8, 0, 13, 3, 4, 14, 7, 5, 11],
+            [10, 15, 4, 2, 7, 12, 9, 5, 6, 1, 13, 14, 0, 11, 3, 8],
+            [9, 14, 15, 5, 2, 8, 12, 3, 7, 0, 4, 10, 1, 13, 11, 6],
+            [4, 3, 2, 12, 9, 5, 15, 10, 11, 14, 1, 7, 6, 0, 8, 13],
+        ],
+        [
+            [4, 11, 2, 14, 15, 0, 8, 13, 3, 12, 9, 7, 5, 10, 6, 1],
+            [13, 0, 11, 7, 4, 9, 1, 10, 14, 3, 5, 12, 2, 15, 8, 6],
+            [1, 4, 11, 13, 12, 3, 7, 14, 10, 15, 6, 8, 0, 5, 9, 2],
+            [6, 11, 13, 8, 1, 4, 10, 7, 9, 5, 0, 15, 14, 2, 3, 12],
+        ],
+        [
+            [13, 2, 8, 4, 6, 15, 11, 1, 10, 9, 3, 14, 5, 0, 12, 7],
+            [1, 15, 13, 8, 10, 3, 7, 4, 12, 5, 6, 11, 0, 14, 9, 2],
+            [7, 11, 4, 1, 9, 12, 14, 2, 0, 6, 10, 13, 15, 3, 5, 8],
+            [2, 1, 14, 7, 4, 10, 8, 13, 15, 12, 9, 0, 3, 5, 6, 11],
+        ],
+    ];
+
+    fn permute(input: &[u8], table: &[u8]) -> Vec<u8> {
+        table.iter().map(|&i| input[i as usize - 1]).collect()
+    }
+
+    fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+        a.iter().zip(b).map(|(x, y)| x ^ y).collect()
+    }
+
+    fn bytes_to_bits(bytes: &[u8]) -> Vec<u8> {
+        let mut bits = Vec::with_capacity(bytes.len() * 8);
+        for &b in bytes {
+            for i in (0..8).rev() {
+                bits.push((b >> i) & 1);
+            }
+        }
+        bits
+    }
+
+    /// Derive the 16 round keys (48 bits each) from a raw 64-bit (8-byte) key.
+    fn key_schedule(key: &[u8; 8]) -> Vec<Vec<u8>> {
+        let permuted = permute(&bytes_to_bits(key), &PC1);
+        let (mut c, mut d) = (permuted[..28].to_vec(), permuted[28..].to_vec());
+        let mut round_keys = Vec::with_capacity(16);
+        for &shift in &SHIFTS {
+            c.rotate_left(shift as usize);
+            d.rotate_left(shift as usize);
+            let mut cd = c.clone();
+            cd.extend_from_slice(&d);
+            round_keys.push(permute(&cd, &PC2));
+        }
+        round_keys
+    }
+
+    /// The Feistel round function, with the salt-perturbed E-expansion that
+    /// makes crypt(3) DES distinct from plain DES: bit `i` of the salt (for
+    /// `i` in 0..12) swaps E-output bits `i` and `i + 24`.
+    fn feistel(r: &[u8], round_key: &[u8], salt_bits: &[u8]) -> Vec<u8> {
+        let mut expanded = permute(r, &E);
+        for i in 0..12 {
+            if salt_bits[i] != 0 {
+                expanded.swap(i, i + 24);
+            }
+        }
+        let x = xor(&expanded, round_key);
+        let mut out = Vec::with_capacity(32);
+        for (box_idx, chunk) in x.chunks(6).enumerate() {
+            let row = ((chunk[0] << 1) | chunk[5]) as usize;
+            let col = ((chunk[1] << 3) | (chunk[2] << 2) | (chunk[3] << 1) | chunk[4]) as usize;
+            let val = S[box_idx][row][col];
+            for shift in (0..4).rev() {
+                out.push((val >> shift) & 1);
+            }
+        }
+        permute(&out, &P)
+    }
+
+    fn encrypt_block(block: &[u8], round_keys: &[Vec<u8>], salt_bits: &[u8]) -> Vec<u8> {
+        let permuted = permute(block, &IP);
+        let (mut l, mut r) = (permuted[..32].to_vec(), permuted[32..].to_vec());
+        for key in round_keys {
+            let new_r = xor(&l, &feistel(&r, key, salt_bits));
+            l = r;
+            r = new_r;
+        }
+        let mut combined = r;
+        combined.extend_from_slice(&l);
+        permute(&combined, &FP)
+    }
+
+    /// Encrypt an all-zero 64-bit block `rounds` times, chaining each
+    /// output into the next input, and return the resulting 64 bits.
+    pub(super) fn crypt_bits(key: &[u8; 8], salt_bits: &[u8], rounds: usize) -> Vec<u8> {
+        let round_keys = key_schedule(key);
+        let mut block = vec![0u8; 64];
+        for _ in 0..rounds {
+            block = encrypt_block(&block, &round_keys, salt_bits);
+        }
+        block
+    }
+}
+
+fn salt_char_value(c: u8) -> u8 {
+    ITOA64.iter().position(|&s| s == c).unwrap_or(0) as u8
+}
+
+fn salt_bits_from_chars(chars: &[u8]) -> Vec<u8> {
+    let mut bits = Vec::with_capacity(chars.len() * 6);
+    for &c in chars {
+        let v = salt_char_value(c);
+        // LSB first: crypt(3)'s salt->E-table-bit mapping takes each
+        // character's bit 0 as the first swapped bit, not its bit 5.
+        for i in 0..6 {
+            bits.push((v >> i) & 1);
+        }
+    }
+    bits
+}
+
+/// Encode a 64-bit DES crypt result (as 64 individual bits, MSB first) into
+/// the 11-character crypt64 tail that follows the 2-character salt.
+fn encode_des_bits(bits: &[u8]) -> String {
+    let mut s = String::with_capacity(11);
+    for chunk in bits.chunks(6) {
+        let mut v = 0u8;
+        for &b in chunk {
+            v = (v << 1) | b;
+        }
+        v <<= 6 - chunk.len() as u8;
+        s.push(ITOA64[v as usize] as char);
+    }
+    s
+}
+
+/// Traditional Unix crypt(3), DES variant: random 2-character salt, 25
+/// rounds, 13-character result (2-character salt + 11-character hash).
+/// Only the first 8 bytes of `key` are significant, matching the historical
+/// DES key-length limit.
+pub fn crypt_des(key: &str) -> Result<String, getrandom::Error> {
+    Ok(crypt_des_with_salt(key, &random_salt_chars(2)?))
+}
+
+fn crypt_des_with_salt(key: &str, salt: &str) -> String {
+    let mut key_bytes = [0u8; 8];
+    for (dst, &b) in key_bytes.iter_mut().zip(key.as_bytes()) {
+        // Shifted left by one: PC1 drops each byte's bit 8 (the classic
+        // DES parity bit), so the key's 7 significant bits need to sit in
+        // bits 1-7, not 0-6, or PC1 silently discards the real low bit.
+        *dst = (b & 0x7f) << 1;
+    }
+    let salt_bits = salt_bits_from_chars(salt.as_bytes());
+    let bits = des_crypt::crypt_bits(&key_bytes, &salt_bits, 25);
+    format!("{}{}", salt, encode_des_bits(&bits))
+}
+
+/// The FreeBSD/"$1$" MD5 crypt algorithm.
+pub fn crypt_md5(key: &str) -> Result<String, getrandom::Error> {
+    Ok(crypt_md5_with_salt(key, &random_salt_chars(8)?))
+}
+
+fn crypt_md5_with_salt(key: &str, salt: &str) -> String {
+    let password = key.as_bytes();
+    let salt_bytes = salt.as_bytes();
+
+    let mut alt = Md5::new();
+    alt.update(password);
+    alt.update(salt_bytes);
+    alt.update(password);
+    let alt_result = alt.finalize();
+
+    let mut ctx = Md5::new();
+    ctx.update(password);
+    ctx.update(b"$1$");
+    ctx.update(salt_bytes);
+    ctx.update(alt_result.iter().cycle().take(password.len()).copied().collect::<Vec<u8>>());
+
+    let mut i = password.len();
+    while i > 0 {
+        if i & 1 != 0 {
+            ctx.update([0u8]);
+        } else {
+            ctx.update(&password[..1]);
+        }
+        i >>= 1;
+    }
+    let mut result = ctx.finalize();
+
+    for i in 0..1000 {
+        let mut ctx1 = Md5::new();
+        if i & 1 != 0 {
+            ctx1.update(password);
+        } else {
+            ctx1.update(&result);
+        }
+        if i % 3 != 0 {
+            ctx1.update(salt_bytes);
+        }
+        if i % 7 != 0 {
+            ctx1.update(password);
+        }
+        if i & 1 != 0 {
+            ctx1.update(&result);
+        } else {
+            ctx1.update(password);
+        }
+        result = ctx1.finalize();
+    }
+
+    const GROUPS: [(usize, usize, usize); 5] = [(0, 6, 12), (1, 7, 13), (2, 8, 14), (3, 9, 15), (4, 10, 5)];
+    let mut hash = String::with_capacity(22);
+    for &(a, b, c) in &GROUPS {
+        let value = ((result[a] as u32) << 16) | ((result[b] as u32) << 8) | result[c] as u32;
+        hash.push_str(&to64(value, 4));
+    }
+    hash.push_str(&to64(result[11] as u32, 2));
+
+    format!("$1${}${}", salt, hash)
+}
+
+/// Shared core of the SHA-256/SHA-512 crypt algorithms (Drepper's spec):
+/// builds the per-round input digest chain `C` after `rounds` iterations.
+fn sha_crypt_rounds(password: &[u8], salt: &[u8], rounds: usize, hash: impl Fn(&[u8]) -> Vec<u8>) -> Vec<u8> {
+    let b = hash(&[password, salt, password].concat());
+
+    let mut a_input = Vec::new();
+    a_input.extend_from_slice(password);
+    a_input.extend_from_slice(salt);
+    a_input.extend(b.iter().cycle().take(password.len()));
+    let mut i = password.len();
+    while i > 0 {
+        if i & 1 != 0 {
+            a_input.extend_from_slice(&b);
+        } else {
+            a_input.extend_from_slice(password);
+        }
+        i >>= 1;
+    }
+    let a = hash(&a_input);
+
+    let dp = hash(&password.repeat(password.len()));
+    let p_seq: Vec<u8> = dp.iter().cycle().take(password.len()).copied().collect();
+
+    let ds_count = 16 + a[0] as usize;
+    let ds = hash(&salt.repeat(ds_count));
+    let s_seq: Vec<u8> = ds.iter().cycle().take(salt.len()).copied().collect();
+
+    let mut c = a;
+    for i in 0..rounds {
+        let mut input = Vec::new();
+        if i % 2 != 0 {
+            input.extend_from_slice(&p_seq);
+        } else {
+            input.extend_from_slice(&c);
+        }
+        if i % 3 != 0 {
+            input.extend_from_slice(&s_seq);
+        }
+        if i % 7 != 0 {
+            input.extend_from_slice(&p_seq);
+        }
+        if i % 2 != 0 {
+            input.extend_from_slice(&c);
+        } else {
+            input.extend_from_slice(&p_seq);
+        }
+        c = hash(&input);
+    }
+    c
+}
+
+/// `$6$` SHA-512 crypt, 5000 rounds, 16-character salt.
+pub fn crypt_sha512(key: &str) -> Result<String, getrandom::Error> {
+    crypt_sha512_with_rounds(key, 5000)
+}
+
+/// `$6$` SHA-512 crypt with a configurable round count (the `rounds=N` part
+/// of the `$6$rounds=N$salt$hash` format), random 16-character salt.
+pub fn crypt_sha512_with_rounds(key: &str, rounds: usize) -> Result<String, getrandom::Error> {
+    Ok(crypt_sha512_with_salt_and_rounds(key, &random_salt_chars(16)?, rounds))
+}
+
+fn crypt_sha512_with_salt_and_rounds(key: &str, salt: &str, rounds: usize) -> String {
+    let c = sha_crypt_rounds(key.as_bytes(), salt.as_bytes(), rounds, |d| Sha512::digest(d).to_vec());
+
+    #[rustfmt::skip]
+    const TRIPLES: [(usize, usize, usize); 21] = [
+        (0, 21, 42), (22, 43, 1), (44, 2, 23), (3, 24, 45), (25, 46, 4),
+        (47, 5, 26), (6, 27, 48), (28, 49, 7), (50, 8, 29), (9, 30, 51),
+        (31, 52, 10), (53, 11, 32), (12, 33, 54), (34, 55, 13), (56, 14, 35),
+        (15, 36, 57), (37, 58, 16), (59, 17, 38), (18, 39, 60), (40, 61, 19),
+        (62, 20, 41),
+    ];
+    let mut hash = String::with_capacity(86);
+    for &(a, b, d) in &TRIPLES {
+        let value = ((c[a] as u32) << 16) | ((c[b] as u32) << 8) | c[d] as u32;
+        hash.push_str(&to64(value, 4));
+    }
+    hash.push_str(&to64(c[63] as u32, 2));
+
+    format!("$6$rounds={}${}${}", rounds, salt, hash)
+}
+
+/// `$5$` SHA-256 crypt, 5000 rounds, 16-character salt.
+pub fn crypt_sha256(key: &str) -> Result<String, getrandom::Error> {
+    crypt_sha256_with_rounds(key, 5000)
+}
+
+/// `$5$` SHA-256 crypt with a configurable round count (the `rounds=N` part
+/// of the `$5$rounds=N$salt$hash` format), random 16-character salt.
+pub fn crypt_sha256_with_rounds(key: &str, rounds: usize) -> Result<String, getrandom::Error> {
+    Ok(crypt_sha256_with_salt_and_rounds(key, &random_salt_chars(16)?, rounds))
+}
+
+fn crypt_sha256_with_salt_and_rounds(key: &str, salt: &str, rounds: usize) -> String {
+    let c = sha_crypt_rounds(key.as_bytes(), salt.as_bytes(), rounds, |d| Sha256::digest(d).to_vec());
+
+    #[rustfmt::skip]
+    const TRIPLES: [(usize, usize, usize); 10] = [
+        (0, 10, 20), (21, 1, 11), (12, 22, 2), (3, 13, 23), (24, 4, 14),
+        (15, 25, 5), (6, 16, 26), (27, 7, 17), (18, 28, 8), (9, 19, 29),
+    ];
+    let mut hash = String::with_capacity(43);
+    for &(a, b, d) in &TRIPLES {
+        let value = ((c[a] as u32) << 16) | ((c[b] as u32) << 8) | c[d] as u32;
+        hash.push_str(&to64(value, 4));
+    }
+    hash.push_str(&to64(((c[31] as u32) << 8) | c[30] as u32, 3));
+
+    format!("$5$rounds={}${}${}", rounds, salt, hash)
+}
+
+// ---------------------------------------------------------------------------
+// PBKDF2-HMAC-SHA512, used by the `:pbkdf2` value encoding. Implemented by
+// hand (RFC 2104 HMAC, RFC 8018 PBKDF2) rather than pulling in `hmac`/
+// `pbkdf2` crates, in keeping with this module's hand-written crypt(3)
+// code, which likewise builds only on the digest crates.
+// ---------------------------------------------------------------------------
+
+const SHA512_BLOCK_LEN: usize = 128;
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut key_block = [0u8; SHA512_BLOCK_LEN];
+    if key.len() > SHA512_BLOCK_LEN {
+        let digest = Sha512::digest(key);
+        key_block[..digest.len()].copy_from_slice(&digest);
+    } else {
+        key_block[..key.len()].copy_from_slice(key);
+    }
+
+    let mut ipad = [0x36u8; SHA512_BLOCK_LEN];
+    let mut opad = [0x5cu8; SHA512_BLOCK_LEN];
+    for i in 0..SHA512_BLOCK_LEN {
+        ipad[i] ^= key_block[i];
+        opad[i] ^= key_block[i];
+    }
+
+    let mut inner = Sha512::new();
+    inner.update(ipad);
+    inner.update(data);
+    let inner_hash = inner.finalize();
+
+    let mut outer = Sha512::new();
+    outer.update(opad);
+    outer.update(inner_hash);
+    outer.finalize().to_vec()
+}
+
+/// PBKDF2 with HMAC-SHA512 (RFC 8018), producing a derived key the same
+/// length as the underlying hash (64 bytes). `dkLen` is fixed at one block,
+/// so this implements just the first (and only) iteration block of `F()`.
+fn pbkdf2_sha512(password: &[u8], salt: &[u8], rounds: u32) -> Vec<u8> {
+    let mut salted = Vec::with_capacity(salt.len() + 4);
+    salted.extend_from_slice(salt);
+    salted.extend_from_slice(&1u32.to_be_bytes());
+
+    let mut u = hmac_sha512(password, &salted);
+    let mut t = u.clone();
+    for _ in 1..rounds {
+        u = hmac_sha512(password, &u);
+        for (t_byte, u_byte) in t.iter_mut().zip(&u) {
+            *t_byte ^= *u_byte;
         }
     }
-    salt
+    t
+}
+
+/// Default iteration count for the `:pbkdf2` encoding, chosen to match
+/// current OWASP guidance for PBKDF2-HMAC-SHA512. Operators who want a
+/// different cost can register their own `pbkdf2` encoding built on
+/// `append_pbkdf2` with a custom round count (see
+/// [`crate::parse::LdapviParser::register_encoding`]).
+pub const DEFAULT_PBKDF2_ROUNDS: u32 = 210_000;
+
+/// Compute a PBKDF2-HMAC-SHA512 hash of `key` with a fresh random 16-byte
+/// salt and append as `rounds$salt$derivedkey`, with salt and derived key
+/// each base64-encoded, matching the format OpenLDAP's PBKDF2 password
+/// module produces after its `{PBKDF2-SHA512}` tag. Does not prepend that
+/// tag; callers add it themselves, as with [`append_crypt`].
+pub fn append_pbkdf2(dst: &mut String, key: &str, rounds: u32) -> Result<(), getrandom::Error> {
+    let salt = random_salt(16)?;
+    append_pbkdf2_with_salt(dst, key, &salt, rounds);
+    Ok(())
+}
+
+fn append_pbkdf2_with_salt(dst: &mut String, key: &str, salt: &[u8], rounds: u32) {
+    let derived = pbkdf2_sha512(key.as_bytes(), salt, rounds);
+    let mut salt_b64 = String::new();
+    base64::append_base64(&mut salt_b64, salt);
+    let mut hash_b64 = String::new();
+    base64::append_base64(&mut hash_b64, &derived);
+    dst.push_str(&format!("{}${}${}", rounds, salt_b64, hash_b64));
 }
 
 #[cfg(test)]
@@ -153,4 +1016,388 @@ mod tests {
         append_md5(&mut s2, "world");
         assert_ne!(s1, s2);
     }
+
+    #[test]
+    fn crypt_des_has_13_char_payload() {
+        let hash = crypt_des("hello").unwrap();
+        assert_eq!(hash.len(), 13);
+    }
+
+    #[test]
+    fn crypt_des_salt_is_prefix() {
+        let hash = crypt_des("hello").unwrap();
+        assert!(hash.is_char_boundary(2));
+        assert!(hash[..2].bytes().all(|b| ITOA64.contains(&b)));
+    }
+
+    #[test]
+    fn crypt_des_matches_system_crypt_known_vectors() {
+        // From glibc's crypt(3) -- verifies byte/bit packing against a
+        // real implementation, not just this module's own round-trip.
+        assert_eq!(crypt_des_with_salt("hello", "ab"), "abl0JrMf6tlhw");
+        assert_eq!(crypt_des_with_salt("passwd", "xy"), "xyD/ihLRoTZx.");
+    }
+
+    #[test]
+    fn crypt_md5_deterministic_with_fixed_salt() {
+        let s1 = crypt_md5_with_salt("hello", "abcdefgh");
+        let s2 = crypt_md5_with_salt("hello", "abcdefgh");
+        assert_eq!(s1, s2);
+    }
+
+    #[test]
+    fn crypt_md5_has_expected_shape() {
+        let hash = crypt_md5_with_salt("hello", "abcdefgh");
+        assert!(hash.starts_with("$1$abcdefgh$"));
+        assert_eq!(hash.split('$').nth(3).unwrap().len(), 22);
+    }
+
+    #[test]
+    fn crypt_md5_different_passwords_differ() {
+        let s1 = crypt_md5_with_salt("hello", "abcdefgh");
+        let s2 = crypt_md5_with_salt("world", "abcdefgh");
+        assert_ne!(s1, s2);
+    }
+
+    #[test]
+    fn crypt_sha512_has_expected_shape() {
+        let hash = crypt_sha512("hello").unwrap();
+        assert!(hash.starts_with("$6$rounds=5000$"));
+        assert_eq!(hash.split('$').nth(4).unwrap().len(), 86);
+    }
+
+    #[test]
+    fn crypt_sha256_has_expected_shape() {
+        let hash = crypt_sha256("hello").unwrap();
+        assert!(hash.starts_with("$5$rounds=5000$"));
+        assert_eq!(hash.split('$').nth(4).unwrap().len(), 43);
+    }
+
+    #[test]
+    fn sha_crypt_rounds_deterministic() {
+        let a = sha_crypt_rounds(b"hello", b"saltsalt", 5000, |d| Sha512::digest(d).to_vec());
+        let b = sha_crypt_rounds(b"hello", b"saltsalt", 5000, |d| Sha512::digest(d).to_vec());
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn ssha256_produces_36_bytes() {
+        let mut s = String::new();
+        append_ssha256_with_salt(&mut s, "hello", &[1, 2, 3, 4]);
+        let decoded = read_base64(&s).unwrap();
+        assert_eq!(decoded.len(), 36); // SHA256(32) + salt(4)
+    }
+
+    #[test]
+    fn ssha256_salt_appended() {
+        let salt = [0xAA, 0xBB, 0xCC, 0xDD];
+        let mut s = String::new();
+        append_ssha256_with_salt(&mut s, "hello", &salt);
+        let decoded = read_base64(&s).unwrap();
+        assert_eq!(&decoded[32..], &salt);
+    }
+
+    #[test]
+    fn ssha512_produces_68_bytes() {
+        let mut s = String::new();
+        append_ssha512_with_salt(&mut s, "hello", &[1, 2, 3, 4]);
+        let decoded = read_base64(&s).unwrap();
+        assert_eq!(decoded.len(), 68); // SHA512(64) + salt(4)
+    }
+
+    #[test]
+    fn ssha512_salt_appended() {
+        let salt = [0x11, 0x22, 0x33, 0x44];
+        let mut s = String::new();
+        append_ssha512_with_salt(&mut s, "hello", &salt);
+        let decoded = read_base64(&s).unwrap();
+        assert_eq!(&decoded[64..], &salt);
+    }
+
+    #[test]
+    fn sha256_produces_32_bytes() {
+        let mut s = String::new();
+        append_sha256(&mut s, "hello");
+        let decoded = read_base64(&s).unwrap();
+        assert_eq!(decoded.len(), 32);
+    }
+
+    #[test]
+    fn sha512_produces_64_bytes() {
+        let mut s = String::new();
+        append_sha512(&mut s, "hello");
+        let decoded = read_base64(&s).unwrap();
+        assert_eq!(decoded.len(), 64);
+    }
+
+    #[test]
+    fn sha224_produces_28_bytes() {
+        let mut s = String::new();
+        append_sha224(&mut s, "hello");
+        let decoded = read_base64(&s).unwrap();
+        assert_eq!(decoded.len(), 28);
+    }
+
+    #[test]
+    fn sha384_produces_48_bytes() {
+        let mut s = String::new();
+        append_sha384(&mut s, "hello");
+        let decoded = read_base64(&s).unwrap();
+        assert_eq!(decoded.len(), 48);
+    }
+
+    #[test]
+    fn ssha224_produces_32_bytes() {
+        let mut s = String::new();
+        append_ssha224_with_salt(&mut s, "hello", &[1, 2, 3, 4]);
+        let decoded = read_base64(&s).unwrap();
+        assert_eq!(decoded.len(), 32); // SHA224(28) + salt(4)
+    }
+
+    #[test]
+    fn ssha224_salt_appended() {
+        let salt = [0xAA, 0xBB, 0xCC, 0xDD];
+        let mut s = String::new();
+        append_ssha224_with_salt(&mut s, "hello", &salt);
+        let decoded = read_base64(&s).unwrap();
+        assert_eq!(&decoded[28..], &salt);
+    }
+
+    #[test]
+    fn ssha384_produces_52_bytes() {
+        let mut s = String::new();
+        append_ssha384_with_salt(&mut s, "hello", &[1, 2, 3, 4]);
+        let decoded = read_base64(&s).unwrap();
+        assert_eq!(decoded.len(), 52); // SHA384(48) + salt(4)
+    }
+
+    #[test]
+    fn ssha384_salt_appended() {
+        let salt = [0x11, 0x22, 0x33, 0x44];
+        let mut s = String::new();
+        append_ssha384_with_salt(&mut s, "hello", &salt);
+        let decoded = read_base64(&s).unwrap();
+        assert_eq!(&decoded[48..], &salt);
+    }
+
+    #[test]
+    fn verify_accepts_matching_unsalted_schemes() {
+        let mut sha = String::from("{SHA}");
+        append_sha(&mut sha, "hello");
+        assert!(verify(&sha, "hello"));
+
+        let mut md5 = String::from("{MD5}");
+        append_md5(&mut md5, "hello");
+        assert!(verify(&md5, "hello"));
+
+        let mut sha256 = String::from("{SHA256}");
+        append_sha256(&mut sha256, "hello");
+        assert!(verify(&sha256, "hello"));
+
+        let mut sha512 = String::from("{SHA512}");
+        append_sha512(&mut sha512, "hello");
+        assert!(verify(&sha512, "hello"));
+    }
+
+    #[test]
+    fn verify_accepts_matching_salted_schemes() {
+        let mut ssha = String::from("{SSHA}");
+        append_ssha_with_salt(&mut ssha, "hello", &[1, 2, 3, 4]);
+        assert!(verify(&ssha, "hello"));
+
+        let mut smd5 = String::from("{SMD5}");
+        append_smd5_with_salt(&mut smd5, "hello", &[1, 2, 3, 4]);
+        assert!(verify(&smd5, "hello"));
+
+        let mut ssha256 = String::from("{SSHA256}");
+        append_ssha256_with_salt(&mut ssha256, "hello", &[1, 2, 3, 4]);
+        assert!(verify(&ssha256, "hello"));
+
+        let mut ssha512 = String::from("{SSHA512}");
+        append_ssha512_with_salt(&mut ssha512, "hello", &[1, 2, 3, 4]);
+        assert!(verify(&ssha512, "hello"));
+
+        let mut ssha224 = String::from("{SSHA224}");
+        append_ssha224_with_salt(&mut ssha224, "hello", &[1, 2, 3, 4]);
+        assert!(verify(&ssha224, "hello"));
+
+        let mut ssha384 = String::from("{SSHA384}");
+        append_ssha384_with_salt(&mut ssha384, "hello", &[1, 2, 3, 4]);
+        assert!(verify(&ssha384, "hello"));
+    }
+
+    #[test]
+    fn verify_rejects_wrong_candidate() {
+        let mut ssha = String::from("{SSHA}");
+        append_ssha_with_salt(&mut ssha, "hello", &[1, 2, 3, 4]);
+        assert!(!verify(&ssha, "goodbye"));
+    }
+
+    #[test]
+    fn verify_rejects_unknown_scheme() {
+        assert!(!verify("{BOGUS}abcd", "hello"));
+    }
+
+    #[test]
+    fn verify_rejects_malformed_payload() {
+        assert!(!verify("{SHA}not valid base64!!", "hello"));
+    }
+
+    #[test]
+    fn verify_rejects_missing_braces() {
+        assert!(!verify("no-scheme-tag", "hello"));
+    }
+
+    #[test]
+    fn ct_eq_matches_and_rejects() {
+        assert!(ct_eq(b"abcdef", b"abcdef"));
+        assert!(!ct_eq(b"abcdef", b"abcxef"));
+        assert!(!ct_eq(b"abc", b"abcd"));
+    }
+
+    #[test]
+    fn append_crypt_des_verifies() {
+        let mut s = String::from("{CRYPT}");
+        append_crypt(&mut s, "hello", CryptScheme::Des).unwrap();
+        assert!(verify(&s, "hello"));
+        assert!(!verify(&s, "goodbye"));
+    }
+
+    #[test]
+    fn append_crypt_md5_verifies() {
+        let mut s = String::from("{CRYPT}");
+        append_crypt(&mut s, "hello", CryptScheme::Md5).unwrap();
+        assert!(s[7..].starts_with("$1$"));
+        assert!(verify(&s, "hello"));
+        assert!(!verify(&s, "goodbye"));
+    }
+
+    #[test]
+    fn append_crypt_sha256_verifies_with_custom_rounds() {
+        let mut s = String::from("{CRYPT}");
+        append_crypt(&mut s, "hello", CryptScheme::Sha256 { rounds: 1000 }).unwrap();
+        assert!(s[7..].starts_with("$5$rounds=1000$"));
+        assert!(verify(&s, "hello"));
+        assert!(!verify(&s, "goodbye"));
+    }
+
+    #[test]
+    fn append_crypt_sha512_verifies_with_custom_rounds() {
+        let mut s = String::from("{CRYPT}");
+        append_crypt(&mut s, "hello", CryptScheme::Sha512 { rounds: 1000 }).unwrap();
+        assert!(s[7..].starts_with("$6$rounds=1000$"));
+        assert!(verify(&s, "hello"));
+        assert!(!verify(&s, "goodbye"));
+    }
+
+    #[test]
+    fn verify_crypt_matches_fixed_md5_hash() {
+        let stored = format!("{{CRYPT}}{}", crypt_md5_with_salt("hello", "abcdefgh"));
+        assert!(verify(&stored, "hello"));
+        assert!(!verify(&stored, "goodbye"));
+    }
+
+    #[test]
+    fn verify_crypt_rejects_malformed_payload() {
+        assert!(!verify("{CRYPT}$9$not-a-real-scheme", "hello"));
+        assert!(!verify("{CRYPT}tooshort", "hello"));
+    }
+
+    #[test]
+    fn password_scheme_hash_round_trips_through_verify() {
+        for scheme in [
+            PasswordScheme::Sha,
+            PasswordScheme::Ssha,
+            PasswordScheme::Md5,
+            PasswordScheme::Smd5,
+            PasswordScheme::Sha256,
+            PasswordScheme::Ssha256,
+            PasswordScheme::Sha512,
+            PasswordScheme::Ssha512,
+            PasswordScheme::Sha224,
+            PasswordScheme::Ssha224,
+            PasswordScheme::Sha384,
+            PasswordScheme::Ssha384,
+        ] {
+            let stored = scheme.hash("hello").unwrap();
+            assert!(stored.starts_with(&format!("{{{}}}", scheme.tag())));
+            assert!(verify(&stored, "hello"));
+            assert!(!verify(&stored, "goodbye"));
+            assert_eq!(PasswordScheme::scheme_of(&stored), Some(scheme));
+        }
+    }
+
+    #[test]
+    fn password_scheme_from_prefix_is_case_insensitive() {
+        assert_eq!(PasswordScheme::from_prefix("ssha"), Some(PasswordScheme::Ssha));
+        assert_eq!(PasswordScheme::from_prefix("SSHA"), Some(PasswordScheme::Ssha));
+        assert_eq!(PasswordScheme::from_prefix("SsHa"), Some(PasswordScheme::Ssha));
+    }
+
+    #[test]
+    fn password_scheme_from_prefix_rejects_unknown_tag() {
+        assert_eq!(PasswordScheme::from_prefix("BOGUS"), None);
+    }
+
+    #[test]
+    fn password_scheme_of_rejects_crypt_and_unrecognized() {
+        assert_eq!(PasswordScheme::scheme_of("{CRYPT}$1$abcdefgh$hash"), None);
+        assert_eq!(PasswordScheme::scheme_of("no-scheme-tag"), None);
+    }
+
+    #[test]
+    fn hmac_sha512_matches_rfc4231_test_case_1() {
+        // RFC 4231 test case 1, HMAC-SHA512.
+        let key = [0x0bu8; 20];
+        let data = b"Hi There";
+        let expected = "87aa7cdea5ef619d4ff0b4241a1d6cb0\
+                          2379f4e2ce4ec2787ad0b30545e17cde\
+                          daa833b7d6b8a702038b274eaea3f4e4\
+                          be9d914eeb61f1702e696c203a126854";
+        let mac = hmac_sha512(&key, data);
+        assert_eq!(hex_encode(&mac), expected);
+    }
+
+    #[test]
+    fn pbkdf2_sha512_matches_known_vector() {
+        // PBKDF2-HMAC-SHA512("password", "salt", 1, 64), a widely cited
+        // known-answer test vector.
+        let dk = pbkdf2_sha512(b"password", b"salt", 1);
+        assert_eq!(
+            hex_encode(&dk),
+            "867f70cf1ade02cff3752599a3a53dc4af34c7a669815ae5d513554e1c8cf25\
+             2c02d470a285a0501bad999bfe943c08f050235d7d68b1da55e63f73b60a57fce"
+        );
+    }
+
+    #[test]
+    fn pbkdf2_sha512_rounds_affect_output() {
+        let a = pbkdf2_sha512(b"password", b"salt", 1);
+        let b = pbkdf2_sha512(b"password", b"salt", 2);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn append_pbkdf2_has_expected_shape() {
+        let mut s = String::new();
+        append_pbkdf2_with_salt(&mut s, "secret", b"0123456789abcdef", 1000);
+        let mut parts = s.split('$');
+        assert_eq!(parts.next(), Some("1000"));
+        assert!(parts.next().is_some());
+        assert!(parts.next().is_some());
+        assert_eq!(parts.next(), None);
+    }
+
+    #[test]
+    fn append_pbkdf2_different_salts_differ() {
+        let mut s1 = String::new();
+        let mut s2 = String::new();
+        append_pbkdf2(&mut s1, "secret", 1000).unwrap();
+        append_pbkdf2(&mut s2, "secret", 1000).unwrap();
+        assert_ne!(s1, s2);
+    }
+
+    fn hex_encode(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
 }
@@ -1,12 +1,17 @@
 //! Output formatting -- Rust port of print.c
 //!
-//! Prints entries and change records in both ldapvi and LDIF formats.
+//! Prints entries and change records in both ldapvi and LDIF formats, plus
+//! a user-templated format (`print_templated_entry`) for custom exports.
 
-use std::io::{self, Write};
+use std::io::{self, Read, Write};
 
 use crate::base64;
 use crate::data::{Entry, LdapMod, ModOp};
+use crate::error::Result;
+use crate::escape::{self, Mode};
+use crate::hash::{entry_hash, entry_hash_with, HashAlgorithm};
 use crate::schema::Entroid;
+use crate::template::{self, TemplateData};
 
 /// Controls how non-ASCII or binary values are detected.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -44,22 +49,46 @@ fn readable_string_p(data: &[u8]) -> bool {
     true
 }
 
-/// Check if the value can be printed as an LDIF SAFE-STRING:
-/// no leading space/colon/less-than, no null/CR/LF/non-ASCII bytes.
-fn safe_string_p(data: &[u8]) -> bool {
-    if data.is_empty() {
-        return true;
-    }
-    let c = data[0];
-    if c == b' ' || c == b':' || c == b'<' {
-        return false;
+/// Try to read `data` as UTF-16 text, in whichever endianness a BOM (if
+/// present) indicates, defaulting to little-endian with no BOM -- the form
+/// Active Directory attributes like `unicodePwd` actually use. Surrogate
+/// pairing is handled (and an unpaired surrogate rejected) by
+/// `char::decode_utf16`, the same primitive `String::from_utf16` is built
+/// on.
+fn decode_utf16_value(data: &[u8]) -> Option<String> {
+    let (data, big_endian) = match data {
+        [0xfe, 0xff, rest @ ..] => (rest, true),
+        [0xff, 0xfe, rest @ ..] => (rest, false),
+        _ => (data, false),
+    };
+    if data.is_empty() || data.len() % 2 != 0 {
+        return None;
     }
-    for &c in data {
-        if c == 0 || c == b'\r' || c == b'\n' || c >= 0x80 {
-            return false;
+    let units = data.chunks_exact(2).map(|pair| {
+        if big_endian {
+            u16::from_be_bytes([pair[0], pair[1]])
+        } else {
+            u16::from_le_bytes([pair[0], pair[1]])
         }
-    }
-    true
+    });
+    char::decode_utf16(units).collect::<Result<String, _>>().ok()
+}
+
+/// Is `data` plausible UTF-16 text worth *displaying* decoded instead of as
+/// Base64? True when it decodes cleanly (even length, no unpaired
+/// surrogates, no stray trailing byte) and the decoded text itself passes
+/// `readable_string_p`.
+fn utf16_string_p(data: &[u8]) -> bool {
+    decode_utf16_value(data).is_some_and(|s| readable_string_p(s.as_bytes()))
+}
+
+/// Check if the value can be printed as an LDIF SAFE-STRING:
+/// no leading space/colon/less-than, no null/CR/LF/non-ASCII bytes.
+///
+/// `pub(crate)` so `parseldif`'s tolerant scanner can flag plain-encoded
+/// values that violate this grammar (they should have been Base64).
+pub(crate) fn safe_string_p(data: &[u8]) -> bool {
+    escape::is_safe(data, Mode::LdifValue)
 }
 
 /// Is the value "readable" according to the given mode?
@@ -98,7 +127,7 @@ fn print_attrval(
 ) -> io::Result<()> {
     if !is_readable(data, mode) {
         w.write_all(b":: ")?;
-        base64::print_base64(data, w)?;
+        base64::print_base64(data, w, 76)?;
     } else if prefer_no_colon {
         w.write_all(b" ")?;
         write_backslashed(w, data)?;
@@ -112,15 +141,59 @@ fn print_attrval(
     Ok(())
 }
 
-/// Write an LDIF attribute line: `ad: value\n` or `ad:: base64\n`.
-fn print_ldif_line(w: &mut dyn Write, ad: &str, data: &[u8]) -> io::Result<()> {
-    w.write_all(ad.as_bytes())?;
+/// Fold `line` (a complete, un-terminated LDIF line) per RFC 2849: lines
+/// longer than `width` bytes are broken, with every continuation line
+/// starting with a single leading space (which counts against its own
+/// `width` budget). Folding counts bytes, not characters, and never splits
+/// a UTF-8 multi-byte sequence. `width == 0` means never fold.
+fn fold_line(w: &mut dyn Write, line: &[u8], width: usize) -> io::Result<()> {
+    if width == 0 || line.len() <= width {
+        return w.write_all(line);
+    }
+    let mut pos = 0;
+    let mut budget = width;
+    loop {
+        let mut end = (pos + budget).min(line.len());
+        if end < line.len() {
+            // Back off a UTF-8 continuation byte (10xxxxxx) to the start
+            // of its sequence so the fold doesn't split it.
+            while end > pos && (line[end] & 0xC0) == 0x80 {
+                end -= 1;
+            }
+            if end == pos {
+                // The leading character itself doesn't fit in the budget;
+                // emit it whole rather than producing an empty line.
+                end = (pos + budget).min(line.len());
+                while end < line.len() && (line[end] & 0xC0) == 0x80 {
+                    end += 1;
+                }
+            }
+        }
+        w.write_all(&line[pos..end])?;
+        pos = end;
+        if pos >= line.len() {
+            return Ok(());
+        }
+        w.write_all(b"\n ")?;
+        budget = width.saturating_sub(1).max(1);
+    }
+}
+
+/// Write an LDIF attribute line: `ad: value\n` or `ad:: base64\n`, folded
+/// to `width` bytes per line (`width == 0` means never fold).
+fn print_ldif_line(w: &mut dyn Write, ad: &str, data: &[u8], width: usize) -> io::Result<()> {
     if safe_string_p(data) {
-        w.write_all(b": ")?;
-        w.write_all(data)?;
+        let mut line = Vec::with_capacity(ad.len() + 2 + data.len());
+        line.extend_from_slice(ad.as_bytes());
+        line.extend_from_slice(b": ");
+        line.extend_from_slice(data);
+        fold_line(w, &line, width)?;
     } else {
-        w.write_all(b":: ")?;
-        base64::print_base64(data, w)?;
+        let mut line = Vec::with_capacity(ad.len() + 3 + data.len() * 4 / 3 + 4);
+        line.extend_from_slice(ad.as_bytes());
+        line.extend_from_slice(b":: ");
+        base64::print_base64(data, &mut line, 0)?;
+        fold_line(w, &line, width)?;
     }
     w.write_all(b"\n")?;
     Ok(())
@@ -213,9 +286,13 @@ pub fn print_ldapvi_entry_annotated(
 
     for attr in &entry.attributes {
         // Check if attribute is allowed by schema
-        let allowed = entroid.remove_ad(&attr.ad);
+        let allowed = entroid.remove_ad(attr.ad.as_str_lossy().as_ref());
         if !allowed {
-            write!(w, "# WARNING: {} not allowed by schema\n", attr.ad)?;
+            write!(
+                w,
+                "# WARNING: {} not allowed by schema\n",
+                attr.ad.as_str_lossy()
+            )?;
         }
         for value in &attr.values {
             w.write_all(attr.ad.as_bytes())?;
@@ -237,6 +314,20 @@ pub fn print_ldapvi_entry_annotated(
     Ok(())
 }
 
+/// Print an entry in ldapvi format, followed by a `# entry-hash: <hex>`
+/// comment carrying its canonical content hash (see
+/// [`crate::hash::entry_hash`]) -- cheap for a caller to compare against a
+/// previous dump without re-diffing every attribute.
+pub fn print_ldapvi_entry_with_hash(
+    w: &mut dyn Write,
+    entry: &Entry,
+    key: Option<&str>,
+    mode: BinaryMode,
+) -> io::Result<()> {
+    print_ldapvi_entry(w, entry, key, mode)?;
+    writeln!(w, "# entry-hash: {}", entry_hash(entry))
+}
+
 /// Print a single LDAPMod in ldapvi format.
 fn print_ldapvi_ldapmod(w: &mut dyn Write, m: &LdapMod, mode: BinaryMode) -> io::Result<()> {
     let op_str = match m.op {
@@ -346,25 +437,63 @@ pub fn print_ldapvi_delete(w: &mut dyn Write, dn: &str, mode: BinaryMode) -> io:
 // LDIF format printers
 // ---------------------------------------------------------------------------
 
-/// Print an entry in LDIF format.
-pub fn print_ldif_entry(w: &mut dyn Write, entry: &Entry, key: Option<&str>) -> io::Result<()> {
+/// Default line-folding width for LDIF output, per RFC 2849's convention.
+pub const DEFAULT_LDIF_WIDTH: usize = 76;
+
+/// Print an entry in LDIF format, folding lines to `width` bytes (`width ==
+/// 0` means never fold; see [`DEFAULT_LDIF_WIDTH`]).
+pub fn print_ldif_entry(
+    w: &mut dyn Write,
+    entry: &Entry,
+    key: Option<&str>,
+    width: usize,
+) -> io::Result<()> {
     w.write_all(b"\n")?;
-    print_ldif_line(w, "dn", entry.dn.as_bytes())?;
+    print_ldif_line(w, "dn", entry.dn.as_bytes(), width)?;
     if let Some(k) = key {
         write!(w, "ldapvi-key: {}\n", k)?;
     }
     for attr in &entry.attributes {
         for value in &attr.values {
-            print_ldif_line(w, &attr.ad, value)?;
+            print_ldif_line(w, &attr.ad.as_str_lossy(), value, width)?;
         }
     }
     Ok(())
 }
 
-/// Print a modify record in LDIF format.
-pub fn print_ldif_modify(w: &mut dyn Write, dn: &str, mods: &[LdapMod]) -> io::Result<()> {
+/// Like [`print_ldif_entry`], followed by a `# entry-hash: <algo>:<hex>`
+/// comment carrying its canonical content hash (see
+/// [`crate::hash::entry_hash_with`]). `LdifParser::read_entry` parses this
+/// back via `last_content_hash`, so a commit step can recompute the live
+/// server entry's hash and refuse to apply a modification when it no
+/// longer matches -- catching a change made on the server between read and
+/// write. Unlike [`print_ldapvi_entry_with_hash`]'s bare SHA-256 hex, the
+/// algorithm is always named here since this hash is meant to be read back.
+pub fn print_ldif_entry_with_hash(
+    w: &mut dyn Write,
+    entry: &Entry,
+    key: Option<&str>,
+    width: usize,
+    algo: HashAlgorithm,
+) -> io::Result<()> {
+    print_ldif_entry(w, entry, key, width)?;
+    writeln!(
+        w,
+        "# entry-hash: {}:{}",
+        algo.name(),
+        entry_hash_with(entry, algo)
+    )
+}
+
+/// Print a modify record in LDIF format, folding lines to `width` bytes.
+pub fn print_ldif_modify(
+    w: &mut dyn Write,
+    dn: &str,
+    mods: &[LdapMod],
+    width: usize,
+) -> io::Result<()> {
     w.write_all(b"\n")?;
-    print_ldif_line(w, "dn", dn.as_bytes())?;
+    print_ldif_line(w, "dn", dn.as_bytes(), width)?;
     w.write_all(b"changetype: modify\n")?;
 
     for m in mods {
@@ -375,30 +504,31 @@ pub fn print_ldif_modify(w: &mut dyn Write, dn: &str, mods: &[LdapMod]) -> io::R
         };
         write!(w, "{}: {}\n", op_str, m.attr)?;
         for value in &m.values {
-            print_ldif_line(w, &m.attr, value)?;
+            print_ldif_line(w, &m.attr, value, width)?;
         }
         w.write_all(b"-\n")?;
     }
     Ok(())
 }
 
-/// Print a rename record in LDIF format.
+/// Print a rename record in LDIF format, folding lines to `width` bytes.
 pub fn print_ldif_rename(
     w: &mut dyn Write,
     old_dn: &str,
     new_dn: &str,
     delete_old_rdn: bool,
+    width: usize,
 ) -> io::Result<()> {
     let rdns = explode_dn(new_dn);
 
     w.write_all(b"\n")?;
-    print_ldif_line(w, "dn", old_dn.as_bytes())?;
+    print_ldif_line(w, "dn", old_dn.as_bytes(), width)?;
     w.write_all(b"changetype: modrdn\n")?;
 
     if rdns.is_empty() {
-        print_ldif_line(w, "newrdn", b"")?;
+        print_ldif_line(w, "newrdn", b"", width)?;
     } else {
-        print_ldif_line(w, "newrdn", rdns[0].as_bytes())?;
+        print_ldif_line(w, "newrdn", rdns[0].as_bytes(), width)?;
     }
 
     write!(w, "deleteoldrdn: {}\n", if delete_old_rdn { 1 } else { 0 })?;
@@ -407,48 +537,528 @@ pub fn print_ldif_rename(
         w.write_all(b"newsuperior:\n")?;
     } else {
         let sup = rdns_to_dn(&rdns[1..]);
-        print_ldif_line(w, "newsuperior", sup.as_bytes())?;
+        print_ldif_line(w, "newsuperior", sup.as_bytes(), width)?;
     }
     Ok(())
 }
 
-/// Print a modrdn record in LDIF format (without newsuperior).
+/// Print a modrdn record in LDIF format (without newsuperior), folding
+/// lines to `width` bytes.
 pub fn print_ldif_modrdn(
     w: &mut dyn Write,
     old_dn: &str,
     new_rdn: &str,
     delete_old_rdn: bool,
+    width: usize,
 ) -> io::Result<()> {
     w.write_all(b"\n")?;
-    print_ldif_line(w, "dn", old_dn.as_bytes())?;
+    print_ldif_line(w, "dn", old_dn.as_bytes(), width)?;
     w.write_all(b"changetype: modrdn\n")?;
-    print_ldif_line(w, "newrdn", new_rdn.as_bytes())?;
+    print_ldif_line(w, "newrdn", new_rdn.as_bytes(), width)?;
     write!(w, "deleteoldrdn: {}\n", if delete_old_rdn { 1 } else { 0 })?;
     Ok(())
 }
 
-/// Print an add record in LDIF format.
-pub fn print_ldif_add(w: &mut dyn Write, dn: &str, mods: &[LdapMod]) -> io::Result<()> {
+/// Print an add record in LDIF format, folding lines to `width` bytes.
+pub fn print_ldif_add(
+    w: &mut dyn Write,
+    dn: &str,
+    mods: &[LdapMod],
+    width: usize,
+) -> io::Result<()> {
     w.write_all(b"\n")?;
-    print_ldif_line(w, "dn", dn.as_bytes())?;
+    print_ldif_line(w, "dn", dn.as_bytes(), width)?;
     w.write_all(b"changetype: add\n")?;
 
     for m in mods {
         for value in &m.values {
-            print_ldif_line(w, &m.attr, value)?;
+            print_ldif_line(w, &m.attr, value, width)?;
         }
     }
     Ok(())
 }
 
-/// Print a delete record in LDIF format.
-pub fn print_ldif_delete(w: &mut dyn Write, dn: &str) -> io::Result<()> {
+/// Print a delete record in LDIF format, folding lines to `width` bytes.
+pub fn print_ldif_delete(w: &mut dyn Write, dn: &str, width: usize) -> io::Result<()> {
     w.write_all(b"\n")?;
-    print_ldif_line(w, "dn", dn.as_bytes())?;
+    print_ldif_line(w, "dn", dn.as_bytes(), width)?;
     w.write_all(b"changetype: delete\n")?;
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Templated format printer
+// ---------------------------------------------------------------------------
+
+/// Render a value for use as template data: as-is if readable under `mode`;
+/// failing that, decoded text (annotated) if it's plausible UTF-16 --
+/// common for Active Directory binary attributes such as `unicodePwd`,
+/// which would otherwise only ever show up as Base64 noise; or its base64
+/// form otherwise (mirroring the `:: base64` fallback the ldapvi/LDIF
+/// printers use for binary values).
+fn value_to_template_string(data: &[u8], mode: BinaryMode) -> String {
+    if is_readable(data, mode) {
+        String::from_utf8_lossy(data).into_owned()
+    } else if utf16_string_p(data) {
+        format!("(utf16) {}", decode_utf16_value(data).unwrap())
+    } else {
+        let mut encoded = String::new();
+        base64::append_base64(&mut encoded, data);
+        encoded
+    }
+}
+
+/// Render `entry` through a user-supplied template rather than the fixed
+/// ldapvi/LDIF layouts, so callers can emit CSV, vCard, JSON lines, or HTML
+/// reports directly from a search dump.
+///
+/// The template sees `{{dn}}` plus one entry per attribute, addressed by
+/// name (e.g. `{{cn}}`, `{{mail}}`); a `{{#name}}...{{/name}}` section
+/// repeats for each value of a multi-valued attribute, with `{{.}}` bound
+/// to the value inside the section. Values that fail `is_readable` under
+/// `mode` are substituted with their decoded text if they are plausible
+/// UTF-16 (marked `(utf16) `), else with their base64 form. See [`template`] for the
+/// full syntax.
+pub fn print_templated_entry(
+    w: &mut dyn Write,
+    entry: &Entry,
+    template: &str,
+    mode: BinaryMode,
+) -> Result<()> {
+    let mut data = TemplateData::new();
+    data.set("dn", value_to_template_string(entry.dn.as_bytes(), mode));
+    for attr in &entry.attributes {
+        let values = attr
+            .values
+            .iter()
+            .map(|v| value_to_template_string(v, mode))
+            .collect();
+        data.set_values(attr.ad.as_str_lossy().into_owned(), values);
+    }
+    template::render(w, template, &data)
+}
+
+// ---------------------------------------------------------------------------
+// Binary dump format
+// ---------------------------------------------------------------------------
+//
+// A compact, lossless alternative to the ldapvi/LDIF/templated formats: every
+// field is a one-byte type tag, a little-endian `u32` byte length, then the
+// raw bytes, so no byte is special and arbitrary binary values (jpegPhoto,
+// userCertificate) round-trip verbatim with no escaping or base64 step.
+
+const BINARY_TAG_DN: u8 = 1;
+const BINARY_TAG_KEY: u8 = 2;
+const BINARY_TAG_ATTR: u8 = 3;
+const BINARY_TAG_VALUE: u8 = 4;
+const BINARY_TAG_END: u8 = 0;
+
+fn write_binary_field(w: &mut dyn Write, tag: u8, data: &[u8]) -> io::Result<()> {
+    w.write_all(&[tag])?;
+    w.write_all(&(data.len() as u32).to_le_bytes())?;
+    w.write_all(data)
+}
+
+/// Write `entry` (and `key`, if given) in the binary dump format.
+pub fn print_binary_entry(w: &mut dyn Write, entry: &Entry, key: Option<&str>) -> io::Result<()> {
+    write_binary_field(w, BINARY_TAG_DN, entry.dn.as_bytes())?;
+    if let Some(k) = key {
+        write_binary_field(w, BINARY_TAG_KEY, k.as_bytes())?;
+    }
+    for attr in &entry.attributes {
+        write_binary_field(w, BINARY_TAG_ATTR, attr.ad.as_bytes())?;
+        w.write_all(&(attr.values.len() as u32).to_le_bytes())?;
+        for value in &attr.values {
+            write_binary_field(w, BINARY_TAG_VALUE, value)?;
+        }
+    }
+    w.write_all(&[BINARY_TAG_END])
+}
+
+fn read_binary_body(r: &mut dyn Read) -> io::Result<Vec<u8>> {
+    let mut len_buf = [0u8; 4];
+    r.read_exact(&mut len_buf)?;
+    let mut buf = vec![0u8; u32::from_le_bytes(len_buf) as usize];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_binary_string(r: &mut dyn Read) -> io::Result<String> {
+    String::from_utf8(read_binary_body(r)?)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Read one entry written by [`print_binary_entry`]. Returns `Ok(None)` at
+/// a clean end of stream (no bytes left before the next entry's DN field).
+pub fn read_binary_entry(r: &mut dyn Read) -> io::Result<Option<(Option<String>, Entry)>> {
+    let mut tag = [0u8; 1];
+    if r.read(&mut tag)? == 0 {
+        return Ok(None);
+    }
+    if tag[0] != BINARY_TAG_DN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "binary entry does not start with a dn field",
+        ));
+    }
+    let mut entry = Entry::new(read_binary_string(r)?);
+    let mut key = None;
+
+    loop {
+        r.read_exact(&mut tag)?;
+        match tag[0] {
+            BINARY_TAG_END => break,
+            BINARY_TAG_KEY => key = Some(read_binary_string(r)?),
+            BINARY_TAG_ATTR => {
+                let ad = read_binary_body(r)?;
+                let mut count_buf = [0u8; 4];
+                r.read_exact(&mut count_buf)?;
+                let count = u32::from_le_bytes(count_buf);
+                let attr = entry.find_attribute_bytes(&ad, true).unwrap();
+                for _ in 0..count {
+                    r.read_exact(&mut tag)?;
+                    if tag[0] != BINARY_TAG_VALUE {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "expected a value field inside an attribute",
+                        ));
+                    }
+                    attr.values.push(read_binary_body(r)?);
+                }
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "unknown binary dump field tag",
+                ))
+            }
+        }
+    }
+    Ok(Some((key, entry)))
+}
+
+// ---------------------------------------------------------------------------
+// Canonical binary form
+// ---------------------------------------------------------------------------
+//
+// Thin `Read`/`Write` wrappers around `Entry::canonical_bytes` /
+// `Entry::from_canonical_bytes` (see `data.rs`), so a caller streaming
+// entries to or from a file doesn't need to buffer each one by hand. Unlike
+// `print_binary_entry`, which preserves whatever order the entry arrived
+// in, this always canonicalizes first: two servers returning the same
+// logical entry in different attribute/value order write identical bytes,
+// which is what makes the form usable for change detection and signing
+// (see `hash::entry_hash`, which hashes the same canonical bytes).
+
+/// Write `entry`'s canonical binary form (see module docs above).
+pub fn print_canonical_entry(w: &mut dyn Write, entry: &Entry) -> io::Result<()> {
+    w.write_all(&entry.canonical_bytes())
+}
+
+/// Read one entry written by [`print_canonical_entry`]. The encoding is
+/// already self-delimiting (every field carries its own length), so a
+/// sequence of entries written back-to-back needs no extra framing and can
+/// be read one at a time; returns `Ok(None)` at a clean end of stream.
+pub fn read_canonical_entry(r: &mut dyn Read) -> io::Result<Option<Entry>> {
+    let mut first_byte = [0u8; 1];
+    if r.read(&mut first_byte)? == 0 {
+        return Ok(None);
+    }
+    let mut rest = [0u8; 3];
+    r.read_exact(&mut rest)?;
+    let dn_len = u32::from_le_bytes([first_byte[0], rest[0], rest[1], rest[2]]) as usize;
+    let mut dn_buf = vec![0u8; dn_len];
+    r.read_exact(&mut dn_buf)?;
+    let dn = String::from_utf8(dn_buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut entry = Entry::new(dn);
+
+    let mut count_buf = [0u8; 4];
+    r.read_exact(&mut count_buf)?;
+    let attr_count = u32::from_le_bytes(count_buf);
+    for _ in 0..attr_count {
+        let ad = read_binary_body(r)?;
+        let attr = entry.find_attribute_bytes(&ad, true).unwrap();
+        r.read_exact(&mut count_buf)?;
+        let value_count = u32::from_le_bytes(count_buf);
+        for _ in 0..value_count {
+            attr.values.push(read_binary_body(r)?);
+        }
+    }
+    Ok(Some(entry))
+}
+
+// ---------------------------------------------------------------------------
+// Netencode export format
+// ---------------------------------------------------------------------------
+//
+// A text-ish, self-describing alternative to the binary dump format above:
+// every scalar is tagged and length-prefixed so parsing never needs to scan
+// for a delimiter, following the netencode grammar. Text is `t<len>:<utf8
+// bytes>,`, raw bytes are `b<len>:<raw bytes>,`, a list is `[<len>:
+// <elements>]`, and a record is `{<len>:<key><value>...}` where each key is
+// a text scalar. An `Entry` becomes `{dn: t...,attrs: [{type: t...,values:
+// [b...,b...]},...]}`. Because every `b` field carries its own byte length,
+// values containing NULs, newlines, or invalid UTF-8 survive a dump and
+// reload unchanged -- unlike LDIF (which has to base64-encode them) or the
+// default ldapvi format (which has to escape or "junk" them).
+
+fn ne_scalar(out: &mut Vec<u8>, tag: u8, data: &[u8]) {
+    out.push(tag);
+    out.extend_from_slice(data.len().to_string().as_bytes());
+    out.push(b':');
+    out.extend_from_slice(data);
+    out.push(b',');
+}
+
+fn ne_text(out: &mut Vec<u8>, s: &str) {
+    ne_scalar(out, b't', s.as_bytes());
+}
+
+fn ne_bytes(out: &mut Vec<u8>, data: &[u8]) {
+    ne_scalar(out, b'b', data);
+}
+
+/// Append a list, encoding its elements with `write_elements` into a
+/// temporary buffer first so the total byte length can be measured.
+fn ne_list(out: &mut Vec<u8>, write_elements: impl FnOnce(&mut Vec<u8>)) {
+    let mut body = Vec::new();
+    write_elements(&mut body);
+    out.push(b'[');
+    out.extend_from_slice(body.len().to_string().as_bytes());
+    out.push(b':');
+    out.extend_from_slice(&body);
+    out.push(b']');
+}
+
+fn ne_record(out: &mut Vec<u8>, write_fields: impl FnOnce(&mut Vec<u8>)) {
+    let mut body = Vec::new();
+    write_fields(&mut body);
+    out.push(b'{');
+    out.extend_from_slice(body.len().to_string().as_bytes());
+    out.push(b':');
+    out.extend_from_slice(&body);
+    out.push(b'}');
+}
+
+/// Encode `entry` in the netencode format (see module docs above).
+pub fn encode_netencode_entry(entry: &Entry) -> Vec<u8> {
+    let mut out = Vec::new();
+    ne_record(&mut out, |out| {
+        ne_text(out, "dn");
+        ne_text(out, &entry.dn);
+        ne_text(out, "attrs");
+        ne_list(out, |out| {
+            for attr in &entry.attributes {
+                ne_record(out, |out| {
+                    ne_text(out, "type");
+                    ne_text(out, &attr.ad.as_str_lossy());
+                    ne_text(out, "values");
+                    ne_list(out, |out| {
+                        for value in &attr.values {
+                            ne_bytes(out, value);
+                        }
+                    });
+                });
+            }
+        });
+    });
+    out
+}
+
+/// Write `entry` in the netencode format.
+pub fn print_netencode_entry(w: &mut dyn Write, entry: &Entry) -> io::Result<()> {
+    w.write_all(&encode_netencode_entry(entry))
+}
+
+/// One parsed netencode value, before it's interpreted as an `Entry`.
+#[derive(Debug, PartialEq, Eq)]
+enum NetencodeValue {
+    Text(String),
+    Bytes(Vec<u8>),
+    List(Vec<NetencodeValue>),
+    Record(Vec<(String, NetencodeValue)>),
+}
+
+struct NetencodeParser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+fn ne_err(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+impl<'a> NetencodeParser<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        NetencodeParser { data, pos: 0 }
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn peek(&self) -> io::Result<u8> {
+        self.data
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| ne_err("netencode: unexpected end of input"))
+    }
+
+    /// Read the `<len>:` prefix after the opening tag byte, returning `len`.
+    fn read_length(&mut self) -> io::Result<usize> {
+        let start = self.pos;
+        while self.peek()?.is_ascii_digit() {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(ne_err("netencode: expected a length digit"));
+        }
+        let len: usize = std::str::from_utf8(&self.data[start..self.pos])
+            .unwrap()
+            .parse()
+            .map_err(|_| ne_err("netencode: length overflow"))?;
+        if self.peek()? != b':' {
+            return Err(ne_err("netencode: expected ':' after length"));
+        }
+        self.pos += 1;
+        Ok(len)
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| ne_err("netencode: length runs past end of input"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn expect(&mut self, byte: u8) -> io::Result<()> {
+        if self.peek()? != byte {
+            return Err(ne_err(format!(
+                "netencode: expected '{}'",
+                byte as char
+            )));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn parse_value(&mut self) -> io::Result<NetencodeValue> {
+        match self.peek()? {
+            b't' => {
+                self.pos += 1;
+                let len = self.read_length()?;
+                let bytes = self.take(len)?;
+                self.expect(b',')?;
+                let text = String::from_utf8(bytes.to_vec())
+                    .map_err(|e| ne_err(format!("netencode: invalid utf-8 text: {}", e)))?;
+                Ok(NetencodeValue::Text(text))
+            }
+            b'b' => {
+                self.pos += 1;
+                let len = self.read_length()?;
+                let bytes = self.take(len)?.to_vec();
+                self.expect(b',')?;
+                Ok(NetencodeValue::Bytes(bytes))
+            }
+            b'[' => {
+                self.pos += 1;
+                let len = self.read_length()?;
+                let body = self.take(len)?;
+                self.expect(b']')?;
+                let mut inner = NetencodeParser::new(body);
+                let mut elements = Vec::new();
+                while !inner.at_end() {
+                    elements.push(inner.parse_value()?);
+                }
+                Ok(NetencodeValue::List(elements))
+            }
+            b'{' => {
+                self.pos += 1;
+                let len = self.read_length()?;
+                let body = self.take(len)?;
+                self.expect(b'}')?;
+                let mut inner = NetencodeParser::new(body);
+                let mut fields = Vec::new();
+                while !inner.at_end() {
+                    let key = match inner.parse_value()? {
+                        NetencodeValue::Text(key) => key,
+                        _ => return Err(ne_err("netencode: record key must be text")),
+                    };
+                    let value = inner.parse_value()?;
+                    fields.push((key, value));
+                }
+                Ok(NetencodeValue::Record(fields))
+            }
+            other => Err(ne_err(format!(
+                "netencode: unknown tag '{}'",
+                other as char
+            ))),
+        }
+    }
+}
+
+fn record_field<'a>(fields: &'a [(String, NetencodeValue)], key: &str) -> io::Result<&'a NetencodeValue> {
+    fields
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v)
+        .ok_or_else(|| ne_err(format!("netencode: entry record is missing '{}'", key)))
+}
+
+fn netencode_value_to_entry(value: NetencodeValue) -> io::Result<Entry> {
+    let fields = match value {
+        NetencodeValue::Record(fields) => fields,
+        _ => return Err(ne_err("netencode: expected an entry record")),
+    };
+    let dn = match record_field(&fields, "dn")? {
+        NetencodeValue::Text(dn) => dn.clone(),
+        _ => return Err(ne_err("netencode: 'dn' must be text")),
+    };
+    let mut entry = Entry::new(dn);
+    let attrs = match record_field(&fields, "attrs")? {
+        NetencodeValue::List(attrs) => attrs,
+        _ => return Err(ne_err("netencode: 'attrs' must be a list")),
+    };
+    for attr_value in attrs {
+        let attr_fields = match attr_value {
+            NetencodeValue::Record(fields) => fields,
+            _ => return Err(ne_err("netencode: attribute must be a record")),
+        };
+        let ad = match record_field(attr_fields, "type")? {
+            NetencodeValue::Text(ad) => ad.clone(),
+            _ => return Err(ne_err("netencode: attribute 'type' must be text")),
+        };
+        let values = match record_field(attr_fields, "values")? {
+            NetencodeValue::List(values) => values,
+            _ => return Err(ne_err("netencode: attribute 'values' must be a list")),
+        };
+        let attr = entry.find_attribute(&ad, true).unwrap();
+        for value in values {
+            match value {
+                NetencodeValue::Bytes(bytes) => attr.values.push(bytes.clone()),
+                _ => return Err(ne_err("netencode: attribute value must be raw bytes")),
+            }
+        }
+    }
+    Ok(entry)
+}
+
+/// Parse a stream of netencode-encoded entries (as written by
+/// [`print_netencode_entry`]), back into `Entry` values with every
+/// attribute value byte-for-byte as it was exported.
+pub fn parse_netencode_entries(data: &[u8]) -> io::Result<Vec<Entry>> {
+    let mut parser = NetencodeParser::new(data);
+    let mut entries = Vec::new();
+    while !parser.at_end() {
+        entries.push(netencode_value_to_entry(parser.parse_value()?)?);
+    }
+    Ok(entries)
+}
+
 // ===========================================================================
 // Tests -- ported from test_print.c (26 tests in 14 groups)
 // ===========================================================================
@@ -532,6 +1142,18 @@ mod tests {
         assert!(out.contains("cn:;  leading space\n"));
     }
 
+    #[test]
+    fn ldapvi_entry_with_hash_appends_comment() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "cn", b"foo");
+        let out = capture(|w| print_ldapvi_entry_with_hash(w, &e, Some("add"), BinaryMode::Utf8));
+        assert!(out.starts_with("\nadd cn=foo,dc=example,dc=com\ncn: foo\n"));
+        let hash_line = out.lines().last().unwrap();
+        assert!(hash_line.starts_with("# entry-hash: "));
+        assert_eq!(hash_line["# entry-hash: ".len()..].len(), 64);
+        assert_eq!(hash_line["# entry-hash: ".len()..], crate::hash::entry_hash(&e));
+    }
+
     // ── Group 2: print_ldapvi_modify ──────────────────────────────
 
     #[test]
@@ -641,7 +1263,7 @@ mod tests {
     fn ldif_entry_simple() {
         let mut e = make_entry("cn=foo,dc=example,dc=com");
         add_value(&mut e, "cn", b"foo");
-        let out = capture(|w| print_ldif_entry(w, &e, None));
+        let out = capture(|w| print_ldif_entry(w, &e, None, DEFAULT_LDIF_WIDTH));
         assert_eq!(out, "\ndn: cn=foo,dc=example,dc=com\ncn: foo\n");
     }
 
@@ -649,7 +1271,7 @@ mod tests {
     fn ldif_entry_with_key() {
         let mut e = make_entry("cn=foo,dc=example,dc=com");
         add_value(&mut e, "cn", b"foo");
-        let out = capture(|w| print_ldif_entry(w, &e, Some("42")));
+        let out = capture(|w| print_ldif_entry(w, &e, Some("42"), DEFAULT_LDIF_WIDTH));
         assert!(out.contains("ldapvi-key: 42\n"));
     }
 
@@ -657,10 +1279,62 @@ mod tests {
     fn ldif_entry_binary() {
         let mut e = make_entry("cn=foo,dc=example,dc=com");
         add_value(&mut e, "cn", &[0x00, 0x01, 0x02]);
-        let out = capture(|w| print_ldif_entry(w, &e, None));
+        let out = capture(|w| print_ldif_entry(w, &e, None, DEFAULT_LDIF_WIDTH));
         assert!(out.contains("cn:: "));
     }
 
+    #[test]
+    fn ldif_entry_folds_long_lines() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "description", "x".repeat(100).as_bytes());
+        let out = capture(|w| print_ldif_entry(w, &e, None, DEFAULT_LDIF_WIDTH));
+        assert!(out.contains("\n "), "expected folding in: {}", out);
+        for line in out.lines() {
+            assert!(line.len() <= DEFAULT_LDIF_WIDTH, "line too long: {:?}", line);
+        }
+    }
+
+    #[test]
+    fn ldif_entry_width_zero_never_folds() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "description", "x".repeat(100).as_bytes());
+        let out = capture(|w| print_ldif_entry(w, &e, None, 0));
+        assert!(out.lines().any(|line| line.len() > DEFAULT_LDIF_WIDTH));
+    }
+
+    #[test]
+    fn ldif_entry_with_hash_appends_a_named_hash_comment() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "cn", b"foo");
+        let out = capture(|w| {
+            print_ldif_entry_with_hash(w, &e, None, DEFAULT_LDIF_WIDTH, HashAlgorithm::Sha256)
+        });
+        assert_eq!(
+            out,
+            format!(
+                "\ndn: cn=foo,dc=example,dc=com\ncn: foo\n# entry-hash: sha256:{}\n",
+                crate::hash::entry_hash(&e)
+            )
+        );
+    }
+
+    #[test]
+    fn ldif_entry_with_hash_honors_the_selected_algorithm() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "cn", b"foo");
+        let out = capture(|w| {
+            print_ldif_entry_with_hash(w, &e, None, DEFAULT_LDIF_WIDTH, HashAlgorithm::Md5)
+        });
+        assert!(
+            out.contains(&format!(
+                "# entry-hash: md5:{}\n",
+                entry_hash_with(&e, HashAlgorithm::Md5)
+            )),
+            "got: {}",
+            out
+        );
+    }
+
     // ── Group 8: print_ldif_modify ────────────────────────────────
 
     #[test]
@@ -670,7 +1344,7 @@ mod tests {
             "mail",
             vec![b"foo@example.com".to_vec()],
         )];
-        let out = capture(|w| print_ldif_modify(w, "cn=foo,dc=example,dc=com", &mods));
+        let out = capture(|w| print_ldif_modify(w, "cn=foo,dc=example,dc=com", &mods, DEFAULT_LDIF_WIDTH));
         assert!(out.contains("dn: cn=foo,dc=example,dc=com\n"));
         assert!(out.contains("changetype: modify\n"));
         assert!(out.contains("add: mail\n"));
@@ -688,6 +1362,7 @@ mod tests {
                 "cn=old,dc=example,dc=com",
                 "cn=new,dc=example,dc=com",
                 true,
+                DEFAULT_LDIF_WIDTH,
             )
         });
         assert!(out.contains("dn: cn=old,dc=example,dc=com\n"));
@@ -701,7 +1376,9 @@ mod tests {
 
     #[test]
     fn ldif_modrdn() {
-        let out = capture(|w| print_ldif_modrdn(w, "cn=old,dc=example,dc=com", "cn=new", false));
+        let out = capture(|w| {
+            print_ldif_modrdn(w, "cn=old,dc=example,dc=com", "cn=new", false, DEFAULT_LDIF_WIDTH)
+        });
         assert!(out.contains("dn: cn=old,dc=example,dc=com\n"));
         assert!(out.contains("changetype: modrdn\n"));
         assert!(out.contains("newrdn: cn=new\n"));
@@ -713,7 +1390,7 @@ mod tests {
     #[test]
     fn ldif_add() {
         let mods = vec![make_mod(ModOp::Add, "cn", vec![b"foo".to_vec()])];
-        let out = capture(|w| print_ldif_add(w, "cn=foo,dc=example,dc=com", &mods));
+        let out = capture(|w| print_ldif_add(w, "cn=foo,dc=example,dc=com", &mods, DEFAULT_LDIF_WIDTH));
         assert!(out.contains("dn: cn=foo,dc=example,dc=com\n"));
         assert!(out.contains("changetype: add\n"));
         assert!(out.contains("cn: foo\n"));
@@ -723,7 +1400,7 @@ mod tests {
 
     #[test]
     fn ldif_delete() {
-        let out = capture(|w| print_ldif_delete(w, "cn=foo,dc=example,dc=com"));
+        let out = capture(|w| print_ldif_delete(w, "cn=foo,dc=example,dc=com", DEFAULT_LDIF_WIDTH));
         assert!(out.contains("dn: cn=foo,dc=example,dc=com\n"));
         assert!(out.contains("changetype: delete\n"));
     }
@@ -790,7 +1467,7 @@ mod tests {
         add_value(&mut e, "sn", b"bar");
 
         let mut buf = Vec::new();
-        print_ldif_entry(&mut buf, &e, Some("42")).unwrap();
+        print_ldif_entry(&mut buf, &e, Some("42"), DEFAULT_LDIF_WIDTH).unwrap();
 
         let mut p = LdifParser::new(Cursor::new(buf.as_slice()));
         let (key, result, _) = p.read_entry(None).unwrap().unwrap();
@@ -800,6 +1477,193 @@ mod tests {
         assert!(result.get_attribute("sn").is_some());
     }
 
+    // ── Group 15: print_templated_entry ───────────────────────────
+
+    #[test]
+    fn templated_entry_substitutes_dn_and_attrs() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "cn", b"foo");
+        let out = capture(|w| {
+            print_templated_entry(w, &e, "{{dn}}: {{cn}}\n", BinaryMode::Utf8)
+                .map_err(|e| io::Error::other(e.to_string()))
+        });
+        assert_eq!(out, "cn=foo,dc=example,dc=com: foo\n");
+    }
+
+    #[test]
+    fn templated_entry_expands_multivalued_section() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "mail", b"a@example.com");
+        add_value(&mut e, "mail", b"b@example.com");
+        let out = capture(|w| {
+            print_templated_entry(w, &e, "{{#mail}}mail: {{.}}\n{{/mail}}", BinaryMode::Utf8)
+                .map_err(|e| io::Error::other(e.to_string()))
+        });
+        assert_eq!(out, "mail: a@example.com\nmail: b@example.com\n");
+    }
+
+    #[test]
+    fn templated_entry_base64_encodes_unreadable_values() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "jpegPhoto", &[0x00, 0x01, 0x02]);
+        let out = capture(|w| {
+            print_templated_entry(w, &e, "{{jpegPhoto}}", BinaryMode::Utf8)
+                .map_err(|e| io::Error::other(e.to_string()))
+        });
+        assert_eq!(out, "AAEC");
+    }
+
+    // ── Group 16: binary dump format ──────────────────────────────
+
+    #[test]
+    fn roundtrip_binary() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "cn", b"foo");
+        add_value(&mut e, "mail", b"a@example.com");
+        add_value(&mut e, "mail", b"b@example.com");
+
+        let mut buf = Vec::new();
+        print_binary_entry(&mut buf, &e, Some("42")).unwrap();
+
+        let (key, result) = read_binary_entry(&mut &buf[..]).unwrap().unwrap();
+        assert_eq!(key.as_deref(), Some("42"));
+        assert_eq!(result.dn, e.dn);
+        assert_eq!(result.attributes, e.attributes);
+    }
+
+    #[test]
+    fn binary_roundtrip_preserves_arbitrary_bytes() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "jpegPhoto", &[0x00, 0x01, 0xff, b':', b'\n', b' ']);
+
+        let mut buf = Vec::new();
+        print_binary_entry(&mut buf, &e, None).unwrap();
+
+        let (key, result) = read_binary_entry(&mut &buf[..]).unwrap().unwrap();
+        assert_eq!(key, None);
+        assert_eq!(
+            result.get_attribute("jpegPhoto").unwrap().values[0],
+            vec![0x00, 0x01, 0xff, b':', b'\n', b' ']
+        );
+    }
+
+    #[test]
+    fn binary_read_at_eof_returns_none() {
+        let mut buf: &[u8] = &[];
+        assert!(read_binary_entry(&mut buf).unwrap().is_none());
+    }
+
+    // ── Group 17: canonical binary form ─────────────────────────────
+
+    #[test]
+    fn roundtrip_canonical() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "sn", b"z");
+        add_value(&mut e, "cn", b"foo");
+
+        let mut buf = Vec::new();
+        print_canonical_entry(&mut buf, &e).unwrap();
+
+        let result = read_canonical_entry(&mut &buf[..]).unwrap().unwrap();
+        assert_eq!(result.dn, e.canonicalize().dn);
+        assert_eq!(result.attributes, e.canonicalize().attributes);
+    }
+
+    #[test]
+    fn canonical_encoding_is_order_independent() {
+        let mut a = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut a, "cn", b"foo");
+        add_value(&mut a, "sn", b"bar");
+
+        let mut b = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut b, "sn", b"bar");
+        add_value(&mut b, "cn", b"foo");
+
+        let mut buf_a = Vec::new();
+        let mut buf_b = Vec::new();
+        print_canonical_entry(&mut buf_a, &a).unwrap();
+        print_canonical_entry(&mut buf_b, &b).unwrap();
+        assert_eq!(buf_a, buf_b);
+    }
+
+    #[test]
+    fn canonical_read_at_eof_returns_none() {
+        let mut buf: &[u8] = &[];
+        assert!(read_canonical_entry(&mut buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn canonical_concatenated_entries_parse_in_order() {
+        let a = make_entry("cn=a,dc=example,dc=com");
+        let b = make_entry("cn=b,dc=example,dc=com");
+
+        let mut buf = Vec::new();
+        print_canonical_entry(&mut buf, &a).unwrap();
+        print_canonical_entry(&mut buf, &b).unwrap();
+
+        let mut r = &buf[..];
+        assert_eq!(read_canonical_entry(&mut r).unwrap().unwrap().dn, a.dn);
+        assert_eq!(read_canonical_entry(&mut r).unwrap().unwrap().dn, b.dn);
+        assert!(read_canonical_entry(&mut r).unwrap().is_none());
+    }
+
+    // ── Group 18: netencode export format ───────────────────────────
+
+    #[test]
+    fn roundtrip_netencode() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "cn", b"foo");
+        add_value(&mut e, "mail", b"a@example.com");
+        add_value(&mut e, "mail", b"b@example.com");
+
+        let buf = encode_netencode_entry(&e);
+        let entries = parse_netencode_entries(&buf).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].dn, e.dn);
+        assert_eq!(entries[0].attributes, e.attributes);
+    }
+
+    #[test]
+    fn netencode_roundtrip_preserves_arbitrary_bytes() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(
+            &mut e,
+            "jpegPhoto",
+            &[0x00, 0x01, 0xff, b',', b'{', b'[', b'\n'],
+        );
+
+        let buf = encode_netencode_entry(&e);
+        let entries = parse_netencode_entries(&buf).unwrap();
+        assert_eq!(
+            entries[0].get_attribute("jpegPhoto").unwrap().values[0],
+            vec![0x00, 0x01, 0xff, b',', b'{', b'[', b'\n']
+        );
+    }
+
+    #[test]
+    fn netencode_concatenated_entries_parse_in_order() {
+        let mut a = make_entry("cn=a,dc=example,dc=com");
+        add_value(&mut a, "cn", b"a");
+        let mut b = make_entry("cn=b,dc=example,dc=com");
+        add_value(&mut b, "cn", b"b");
+
+        let mut buf = encode_netencode_entry(&a);
+        buf.extend(encode_netencode_entry(&b));
+
+        let entries = parse_netencode_entries(&buf).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].dn, "cn=a,dc=example,dc=com");
+        assert_eq!(entries[1].dn, "cn=b,dc=example,dc=com");
+    }
+
+    #[test]
+    fn netencode_rejects_truncated_input() {
+        let mut e = make_entry("cn=foo,dc=example,dc=com");
+        add_value(&mut e, "cn", b"foo");
+        let buf = encode_netencode_entry(&e);
+        assert!(parse_netencode_entries(&buf[..buf.len() - 5]).is_err());
+    }
+
     // ── Helpers: string classification ────────────────────────────
 
     #[test]
@@ -819,6 +1683,53 @@ mod tests {
         assert!(!readable_string_p(&[0x00])); // null
     }
 
+    #[test]
+    fn test_decode_utf16_value_le_no_bom() {
+        // "hi" as UTF-16LE, no BOM -- the form AD's unicodePwd etc. use.
+        assert_eq!(decode_utf16_value(&[0x68, 0x00, 0x69, 0x00]).unwrap(), "hi");
+    }
+
+    #[test]
+    fn test_decode_utf16_value_be_with_bom() {
+        let mut data = vec![0xfe, 0xff];
+        data.extend_from_slice(&[0x00, 0x68, 0x00, 0x69]);
+        assert_eq!(decode_utf16_value(&data).unwrap(), "hi");
+    }
+
+    #[test]
+    fn test_decode_utf16_value_handles_surrogate_pairs() {
+        // U+1F600 GRINNING FACE, UTF-16LE surrogate pair.
+        let data = [0x3d, 0xd8, 0x00, 0xde];
+        assert_eq!(decode_utf16_value(&data).unwrap(), "\u{1F600}");
+    }
+
+    #[test]
+    fn test_decode_utf16_value_rejects_unpaired_surrogate() {
+        let data = [0x3d, 0xd8, 0x41, 0x00]; // lone high surrogate, then 'A'
+        assert!(decode_utf16_value(&data).is_none());
+    }
+
+    #[test]
+    fn test_decode_utf16_value_rejects_odd_length() {
+        assert!(decode_utf16_value(&[0x68, 0x00, 0x69]).is_none());
+    }
+
+    #[test]
+    fn test_utf16_string_p_rejects_non_readable_decoded_text() {
+        // Decodes cleanly, but to a control character -- not worth showing
+        // in place of Base64.
+        assert!(!utf16_string_p(&[0x01, 0x00]));
+    }
+
+    #[test]
+    fn test_value_to_template_string_decodes_utf16_binary() {
+        let data = [0x68, 0x00, 0x69, 0x00];
+        assert_eq!(
+            value_to_template_string(&data, BinaryMode::Utf8),
+            "(utf16) hi"
+        );
+    }
+
     #[test]
     fn test_safe_string_p() {
         assert!(safe_string_p(b"hello"));
@@ -35,14 +35,15 @@ impl Hash for CaseFold {
 // ObjectClass, AttributeType
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ObjectClassKind {
     Abstract,
+    #[default]
     Structural,
     Auxiliary,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ObjectClass {
     pub oid: String,
     pub names: Vec<String>,
@@ -50,12 +51,50 @@ pub struct ObjectClass {
     pub kind: ObjectClassKind,
     pub must: Vec<String>,
     pub may: Vec<String>,
+    /// `X-*` extensions (e.g. `X-ORIGIN`, `X-SCHEMA-FILE`), in the order
+    /// they appeared, each with its qdstring or `( 'a' 'b' )` list values.
+    /// Kept around -- rather than discarded like other unrecognized
+    /// keywords -- so [`ObjectClass::to_definition`] can round-trip a
+    /// definition without losing the metadata deployments key off to
+    /// group and edit schema files.
+    pub extensions: Vec<(String, Vec<String>)>,
 }
 
 impl ObjectClass {
     pub fn name(&self) -> &str {
         self.names.first().map(|s| s.as_str()).unwrap_or(&self.oid)
     }
+
+    /// Re-serialize as an RFC 4512 `ObjectClassDescription`, including any
+    /// captured `X-*` extensions, so a definition read from schema and
+    /// written back out doesn't lose them.
+    pub fn to_definition(&self) -> String {
+        let mut out = format!("( {}", self.oid);
+        if !self.names.is_empty() {
+            out.push_str(" NAME ");
+            out.push_str(&format_qdstring_list(&self.names));
+        }
+        if !self.sup.is_empty() {
+            out.push_str(" SUP ");
+            out.push_str(&format_oid_list(&self.sup));
+        }
+        out.push_str(match self.kind {
+            ObjectClassKind::Abstract => " ABSTRACT",
+            ObjectClassKind::Structural => " STRUCTURAL",
+            ObjectClassKind::Auxiliary => " AUXILIARY",
+        });
+        if !self.must.is_empty() {
+            out.push_str(" MUST ");
+            out.push_str(&format_oid_list(&self.must));
+        }
+        if !self.may.is_empty() {
+            out.push_str(" MAY ");
+            out.push_str(&format_oid_list(&self.may));
+        }
+        out.push_str(&format_extensions(&self.extensions));
+        out.push_str(" )");
+        out
+    }
 }
 
 impl fmt::Display for ObjectClass {
@@ -64,16 +103,98 @@ impl fmt::Display for ObjectClass {
     }
 }
 
-#[derive(Debug, Clone)]
+/// The `USAGE` of an attribute type, per RFC 4512 section 4.1.2.
+/// Defaults to `UserApplications` when not specified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AttributeUsage {
+    #[default]
+    UserApplications,
+    DirectoryOperation,
+    DistributedOperation,
+    DsaOperation,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct AttributeType {
     pub oid: String,
     pub names: Vec<String>,
+    pub sup: Option<String>,
+    pub equality: Option<String>,
+    pub ordering: Option<String>,
+    pub substr: Option<String>,
+    pub syntax: Option<String>,
+    /// The `{n}` length suffix on `SYNTAX`, if any (e.g. `128` for
+    /// `1.3.6.1.4.1.1466.115.121.1.15{128}`).
+    pub syntax_length: Option<usize>,
+    pub single_value: bool,
+    pub no_user_modification: bool,
+    pub collective: bool,
+    pub usage: AttributeUsage,
+    /// `X-*` extensions (e.g. `X-ORIGIN`, `X-SCHEMA-FILE`), in the order
+    /// they appeared, each with its qdstring or `( 'a' 'b' )` list values.
+    /// See [`ObjectClass::extensions`] for why these are kept rather than
+    /// discarded like other unrecognized keywords.
+    pub extensions: Vec<(String, Vec<String>)>,
 }
 
 impl AttributeType {
     pub fn name(&self) -> &str {
         self.names.first().map(|s| s.as_str()).unwrap_or(&self.oid)
     }
+
+    /// Re-serialize as an RFC 4512 `AttributeTypeDescription`, including
+    /// any captured `X-*` extensions, so a definition read from schema
+    /// and written back out doesn't lose them.
+    pub fn to_definition(&self) -> String {
+        let mut out = format!("( {}", self.oid);
+        if !self.names.is_empty() {
+            out.push_str(" NAME ");
+            out.push_str(&format_qdstring_list(&self.names));
+        }
+        if let Some(sup) = &self.sup {
+            out.push_str(" SUP ");
+            out.push_str(sup);
+        }
+        if let Some(equality) = &self.equality {
+            out.push_str(" EQUALITY ");
+            out.push_str(equality);
+        }
+        if let Some(ordering) = &self.ordering {
+            out.push_str(" ORDERING ");
+            out.push_str(ordering);
+        }
+        if let Some(substr) = &self.substr {
+            out.push_str(" SUBSTR ");
+            out.push_str(substr);
+        }
+        if let Some(syntax) = &self.syntax {
+            match self.syntax_length {
+                Some(len) => out.push_str(&format!(" SYNTAX {}{{{}}}", syntax, len)),
+                None => out.push_str(&format!(" SYNTAX {}", syntax)),
+            }
+        }
+        if self.single_value {
+            out.push_str(" SINGLE-VALUE");
+        }
+        if self.no_user_modification {
+            out.push_str(" NO-USER-MODIFICATION");
+        }
+        if self.collective {
+            out.push_str(" COLLECTIVE");
+        }
+        if self.usage != AttributeUsage::UserApplications {
+            out.push_str(" USAGE ");
+            out.push_str(match self.usage {
+                AttributeUsage::DirectoryOperation => "directoryOperation",
+                AttributeUsage::DistributedOperation => "distributedOperation",
+                AttributeUsage::DsaOperation => "dSAOperation",
+                AttributeUsage::UserApplications => unreachable!(),
+            });
+        }
+        out.push_str(&format_extensions(&self.extensions));
+        out.push_str(" )");
+        out
+    }
 }
 
 impl fmt::Display for AttributeType {
@@ -209,6 +330,46 @@ impl<'a> SchemaTokenizer<'a> {
     }
 }
 
+/// Format a `NAME`-style qdstring list: a single quoted value, or a
+/// parenthesized space-separated list of quoted values.
+fn format_qdstring_list(values: &[String]) -> String {
+    if values.len() == 1 {
+        format!("'{}'", values[0])
+    } else {
+        format!(
+            "( {} )",
+            values
+                .iter()
+                .map(|v| format!("'{}'", v))
+                .collect::<Vec<_>>()
+                .join(" ")
+        )
+    }
+}
+
+/// Format a `SUP`/`MUST`/`MAY`-style OID list: a single bare value, or a
+/// parenthesized `$`-separated list of bare values.
+fn format_oid_list(values: &[String]) -> String {
+    if values.len() == 1 {
+        values[0].clone()
+    } else {
+        format!("( {} )", values.join(" $ "))
+    }
+}
+
+/// Format captured `X-*` extensions for re-serialization, in the same
+/// qdstring-or-qdstring-list shape they were parsed in.
+fn format_extensions(extensions: &[(String, Vec<String>)]) -> String {
+    let mut out = String::new();
+    for (keyword, values) in extensions {
+        out.push(' ');
+        out.push_str(keyword);
+        out.push(' ');
+        out.push_str(&format_qdstring_list(values));
+    }
+    out
+}
+
 /// Parse an RFC 4512 ObjectClassDescription string.
 pub fn parse_objectclass(s: &str) -> Result<ObjectClass, String> {
     let mut tok = SchemaTokenizer::new(s);
@@ -227,6 +388,7 @@ pub fn parse_objectclass(s: &str) -> Result<ObjectClass, String> {
     let mut kind = ObjectClassKind::Structural; // default per RFC 4512
     let mut must = Vec::new();
     let mut may = Vec::new();
+    let mut extensions = Vec::new();
 
     // Read keyword-value pairs until ')'
     loop {
@@ -243,9 +405,12 @@ pub fn parse_objectclass(s: &str) -> Result<ObjectClass, String> {
             "AUXILIARY" => kind = ObjectClassKind::Auxiliary,
             "MUST" => must = tok.read_oid_list(),
             "MAY" => may = tok.read_oid_list(),
-            "DESC" | "OBSOLETE" | "X-ORIGIN" | "X-SCHEMA-FILE" => {
+            "DESC" | "OBSOLETE" => {
                 tok.skip_value();
             }
+            _ if keyword.starts_with("X-") => {
+                extensions.push((keyword, tok.read_oid_list()));
+            }
             _ => {
                 // Unknown keyword — skip its value if any
                 tok.skip_value();
@@ -260,9 +425,33 @@ pub fn parse_objectclass(s: &str) -> Result<ObjectClass, String> {
         kind,
         must,
         may,
+        extensions,
     })
 }
 
+/// Split a `SYNTAX` value's OID from its optional `{length}` suffix, e.g.
+/// `1.3.6.1.4.1.1466.115.121.1.15{128}` → (`1.3.6.1.4.1.1466.115.121.1.15`,
+/// `Some(128)`).
+fn split_syntax_length(raw: &str) -> (String, Option<usize>) {
+    if let Some(open) = raw.find('{') {
+        if let Some(len) = raw[open + 1..].strip_suffix('}').and_then(|n| n.parse().ok()) {
+            return (raw[..open].to_string(), Some(len));
+        }
+    }
+    (raw.to_string(), None)
+}
+
+/// Parse a `USAGE` value. Unrecognized values fall back to the RFC 4512
+/// default of `userApplications`.
+fn parse_attribute_usage(s: &str) -> AttributeUsage {
+    match s {
+        "directoryOperation" => AttributeUsage::DirectoryOperation,
+        "distributedOperation" => AttributeUsage::DistributedOperation,
+        "dSAOperation" => AttributeUsage::DsaOperation,
+        _ => AttributeUsage::UserApplications,
+    }
+}
+
 /// Parse an RFC 4512 AttributeTypeDescription string.
 pub fn parse_attributetype(s: &str) -> Result<AttributeType, String> {
     let mut tok = SchemaTokenizer::new(s);
@@ -274,7 +463,10 @@ pub fn parse_attributetype(s: &str) -> Result<AttributeType, String> {
 
     let oid = tok.next_token().ok_or_else(|| "expected OID".to_string())?;
 
-    let mut names = Vec::new();
+    let mut at = AttributeType {
+        oid,
+        ..Default::default()
+    };
 
     loop {
         let keyword = match tok.next_token() {
@@ -283,14 +475,238 @@ pub fn parse_attributetype(s: &str) -> Result<AttributeType, String> {
             None => break,
         };
         match keyword.as_str() {
-            "NAME" => names = tok.read_oid_list(),
+            "NAME" => at.names = tok.read_oid_list(),
+            "SUP" => at.sup = tok.read_single_value(),
+            "EQUALITY" => at.equality = tok.read_single_value(),
+            "ORDERING" => at.ordering = tok.read_single_value(),
+            "SUBSTR" => at.substr = tok.read_single_value(),
+            "SYNTAX" => {
+                if let Some(raw) = tok.read_single_value() {
+                    let (syntax, length) = split_syntax_length(&raw);
+                    at.syntax = Some(syntax);
+                    at.syntax_length = length;
+                }
+            }
+            "SINGLE-VALUE" => at.single_value = true,
+            "NO-USER-MODIFICATION" => at.no_user_modification = true,
+            "COLLECTIVE" => at.collective = true,
+            "USAGE" => {
+                if let Some(v) = tok.read_single_value() {
+                    at.usage = parse_attribute_usage(&v);
+                }
+            }
+            _ if keyword.starts_with("X-") => {
+                at.extensions.push((keyword, tok.read_oid_list()));
+            }
+            _ => {
+                tok.skip_value();
+            }
+        }
+    }
+
+    Ok(at)
+}
+
+/// A parsed RFC 4512 §4.1.6 `DITContentRuleDescription`, keyed by the OID
+/// of the structural objectClass it governs.
+#[derive(Debug, Clone, Default)]
+pub struct DITContentRule {
+    pub oid: String,
+    pub names: Vec<String>,
+    /// Auxiliary classes permitted on an entry of the governed structural
+    /// class.
+    pub aux: Vec<String>,
+    pub must: Vec<String>,
+    pub may: Vec<String>,
+    /// Attributes forbidden even if the structural/auxiliary classes
+    /// would otherwise allow them.
+    pub not: Vec<String>,
+}
+
+impl DITContentRule {
+    pub fn name(&self) -> &str {
+        self.names.first().map(|s| s.as_str()).unwrap_or(&self.oid)
+    }
+}
+
+/// Parse an RFC 4512 DITContentRuleDescription string.
+pub fn parse_ditcontentrule(s: &str) -> Result<DITContentRule, String> {
+    let mut tok = SchemaTokenizer::new(s);
+
+    match tok.next_token() {
+        Some(t) if t == "(" => {}
+        _ => return Err("expected '('".to_string()),
+    }
+
+    let oid = tok.next_token().ok_or_else(|| "expected OID".to_string())?;
+
+    let mut rule = DITContentRule {
+        oid,
+        ..Default::default()
+    };
+
+    loop {
+        let keyword = match tok.next_token() {
+            Some(t) if t == ")" => break,
+            Some(t) => t,
+            None => break,
+        };
+        match keyword.as_str() {
+            "NAME" => rule.names = tok.read_oid_list(),
+            "AUX" => rule.aux = tok.read_oid_list(),
+            "MUST" => rule.must = tok.read_oid_list(),
+            "MAY" => rule.may = tok.read_oid_list(),
+            "NOT" => rule.not = tok.read_oid_list(),
+            _ => {
+                tok.skip_value();
+            }
+        }
+    }
+
+    Ok(rule)
+}
+
+/// A parsed RFC 4512 §4.1.5 `LDAPSyntaxDescription`: just an OID and an
+/// optional human-readable description, e.g.
+/// `( 1.3.6.1.4.1.1466.115.121.1.15 DESC 'Directory String' )`.
+#[derive(Debug, Clone, Default)]
+pub struct LdapSyntax {
+    pub oid: String,
+    pub desc: Option<String>,
+}
+
+/// Parse an RFC 4512 LDAPSyntaxDescription string.
+pub fn parse_ldapsyntax(s: &str) -> Result<LdapSyntax, String> {
+    let mut tok = SchemaTokenizer::new(s);
+
+    match tok.next_token() {
+        Some(t) if t == "(" => {}
+        _ => return Err("expected '('".to_string()),
+    }
+
+    let oid = tok.next_token().ok_or_else(|| "expected OID".to_string())?;
+
+    let mut syntax = LdapSyntax {
+        oid,
+        ..Default::default()
+    };
+
+    loop {
+        let keyword = match tok.next_token() {
+            Some(t) if t == ")" => break,
+            Some(t) => t,
+            None => break,
+        };
+        match keyword.as_str() {
+            "DESC" => syntax.desc = tok.read_single_value(),
+            _ => {
+                tok.skip_value();
+            }
+        }
+    }
+
+    Ok(syntax)
+}
+
+/// A parsed RFC 4512 §4.1.3 `MatchingRuleDescription`.
+#[derive(Debug, Clone, Default)]
+pub struct MatchingRule {
+    pub oid: String,
+    pub names: Vec<String>,
+    pub syntax: String,
+}
+
+impl MatchingRule {
+    pub fn name(&self) -> &str {
+        self.names.first().map(|s| s.as_str()).unwrap_or(&self.oid)
+    }
+}
+
+/// Parse an RFC 4512 MatchingRuleDescription string.
+pub fn parse_matchingrule(s: &str) -> Result<MatchingRule, String> {
+    let mut tok = SchemaTokenizer::new(s);
+
+    match tok.next_token() {
+        Some(t) if t == "(" => {}
+        _ => return Err("expected '('".to_string()),
+    }
+
+    let oid = tok.next_token().ok_or_else(|| "expected OID".to_string())?;
+
+    let mut rule = MatchingRule {
+        oid,
+        ..Default::default()
+    };
+
+    loop {
+        let keyword = match tok.next_token() {
+            Some(t) if t == ")" => break,
+            Some(t) => t,
+            None => break,
+        };
+        match keyword.as_str() {
+            "NAME" => rule.names = tok.read_oid_list(),
+            "SYNTAX" => rule.syntax = tok.read_single_value().unwrap_or_default(),
+            _ => {
+                tok.skip_value();
+            }
+        }
+    }
+
+    Ok(rule)
+}
+
+/// A parsed RFC 4512 §4.1.7.2 `NameFormDescription`.
+#[derive(Debug, Clone, Default)]
+pub struct NameForm {
+    pub oid: String,
+    pub names: Vec<String>,
+    /// The structural objectClass this name form governs.
+    pub oc: String,
+    pub must: Vec<String>,
+    pub may: Vec<String>,
+}
+
+impl NameForm {
+    pub fn name(&self) -> &str {
+        self.names.first().map(|s| s.as_str()).unwrap_or(&self.oid)
+    }
+}
+
+/// Parse an RFC 4512 NameFormDescription string.
+pub fn parse_nameform(s: &str) -> Result<NameForm, String> {
+    let mut tok = SchemaTokenizer::new(s);
+
+    match tok.next_token() {
+        Some(t) if t == "(" => {}
+        _ => return Err("expected '('".to_string()),
+    }
+
+    let oid = tok.next_token().ok_or_else(|| "expected OID".to_string())?;
+
+    let mut form = NameForm {
+        oid,
+        ..Default::default()
+    };
+
+    loop {
+        let keyword = match tok.next_token() {
+            Some(t) if t == ")" => break,
+            Some(t) => t,
+            None => break,
+        };
+        match keyword.as_str() {
+            "NAME" => form.names = tok.read_oid_list(),
+            "OC" => form.oc = tok.read_single_value().unwrap_or_default(),
+            "MUST" => form.must = tok.read_oid_list(),
+            "MAY" => form.may = tok.read_oid_list(),
             _ => {
                 tok.skip_value();
             }
         }
     }
 
-    Ok(AttributeType { oid, names })
+    Ok(form)
 }
 
 // ---------------------------------------------------------------------------
@@ -304,6 +720,16 @@ pub struct Schema {
     types: HashMap<CaseFold, AttributeType>,
     type_index: HashMap<CaseFold, usize>,
     type_list: Vec<String>,
+    // Keyed by the OID of the governed structural class, per RFC 4512.
+    content_rules: HashMap<CaseFold, DITContentRule>,
+    // Keyed directly by OID; LDAP syntaxes have no NAME.
+    syntaxes: HashMap<CaseFold, LdapSyntax>,
+    matching_rules: HashMap<CaseFold, MatchingRule>,
+    matching_rule_index: HashMap<CaseFold, usize>,
+    matching_rule_list: Vec<String>,
+    name_forms: HashMap<CaseFold, NameForm>,
+    name_form_index: HashMap<CaseFold, usize>,
+    name_form_list: Vec<String>,
 }
 
 impl Default for Schema {
@@ -321,6 +747,14 @@ impl Schema {
             types: HashMap::new(),
             type_index: HashMap::new(),
             type_list: Vec::new(),
+            content_rules: HashMap::new(),
+            syntaxes: HashMap::new(),
+            matching_rules: HashMap::new(),
+            matching_rule_index: HashMap::new(),
+            matching_rule_list: Vec::new(),
+            name_forms: HashMap::new(),
+            name_form_index: HashMap::new(),
+            name_form_list: Vec::new(),
         }
     }
 
@@ -361,6 +795,179 @@ impl Schema {
         let oid = &self.type_list[*idx];
         self.types.get(&CaseFold::new(oid))
     }
+
+    pub fn add_ditcontentrule(&mut self, rule: DITContentRule) {
+        self.content_rules.insert(CaseFold::new(&rule.oid), rule);
+    }
+
+    /// Look up the DIT content rule governing a structural objectClass,
+    /// by that class's OID (per RFC 4512, a content rule is identified by
+    /// the OID of the structural class it applies to, not by name).
+    pub fn get_ditcontentrule(&self, structural_oid: &str) -> Option<&DITContentRule> {
+        self.content_rules.get(&CaseFold::new(structural_oid))
+    }
+
+    pub fn add_ldapsyntax(&mut self, syntax: LdapSyntax) {
+        self.syntaxes.insert(CaseFold::new(&syntax.oid), syntax);
+    }
+
+    pub fn get_ldapsyntax(&self, oid: &str) -> Option<&LdapSyntax> {
+        self.syntaxes.get(&CaseFold::new(oid))
+    }
+
+    pub fn add_matchingrule(&mut self, rule: MatchingRule) {
+        let oid = rule.oid.clone();
+        let idx = self.matching_rule_list.len();
+        self.matching_rule_list.push(oid.clone());
+
+        self.matching_rule_index.insert(CaseFold::new(&oid), idx);
+        for name in &rule.names {
+            self.matching_rule_index.insert(CaseFold::new(name), idx);
+        }
+        self.matching_rules.insert(CaseFold::new(&oid), rule);
+    }
+
+    pub fn get_matchingrule(&self, name: &str) -> Option<&MatchingRule> {
+        let idx = self.matching_rule_index.get(&CaseFold::new(name))?;
+        let oid = &self.matching_rule_list[*idx];
+        self.matching_rules.get(&CaseFold::new(oid))
+    }
+
+    /// Resolve a matching rule reference, as it appears in `EQUALITY`,
+    /// `ORDERING`, or `SUBSTR` (by OID or name), to its human-readable
+    /// name. Falls back to the reference itself if the rule isn't known.
+    pub fn matching_rule_name<'a>(&'a self, reference: &'a str) -> &'a str {
+        self.get_matchingrule(reference)
+            .map(|r| r.name())
+            .unwrap_or(reference)
+    }
+
+    pub fn add_nameform(&mut self, form: NameForm) {
+        let oid = form.oid.clone();
+        let idx = self.name_form_list.len();
+        self.name_form_list.push(oid.clone());
+
+        self.name_form_index.insert(CaseFold::new(&oid), idx);
+        for name in &form.names {
+            self.name_form_index.insert(CaseFold::new(name), idx);
+        }
+        self.name_forms.insert(CaseFold::new(&oid), form);
+    }
+
+    pub fn get_nameform(&self, name: &str) -> Option<&NameForm> {
+        let idx = self.name_form_index.get(&CaseFold::new(name))?;
+        let oid = &self.name_form_list[*idx];
+        self.name_forms.get(&CaseFold::new(oid))
+    }
+
+    /// Find attribute types whose `SYNTAX` (resolved through their `SUP`
+    /// chain) references an OID with no corresponding [`LdapSyntax`] in
+    /// this schema. Intended to be run once after a whole `cn=subschema`
+    /// subentry has been loaded, to surface dangling syntax references.
+    pub fn check_dangling_syntaxes(&self) -> Vec<String> {
+        let mut diagnostics = Vec::new();
+        for oid in &self.type_list {
+            let at = match self.types.get(&CaseFold::new(oid)) {
+                Some(at) => at,
+                None => continue,
+            };
+            let resolved = match self.resolve_attributetype(&at.oid) {
+                Ok(resolved) => resolved,
+                Err(_) => continue, // SUP cycle or unknown SUP: not a syntax problem, not reported here
+            };
+            if let Some(syntax) = resolved.syntax {
+                if self.get_ldapsyntax(syntax).is_none() {
+                    diagnostics.push(format!(
+                        "attributeType '{}' references unknown SYNTAX: {}",
+                        at.name(),
+                        syntax
+                    ));
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// Resolve the `SUP` inheritance chain of an attribute type, so that
+    /// fields left unset (`SYNTAX`, `EQUALITY`, `ORDERING`, `SUBSTR`,
+    /// `SINGLE-VALUE`) are filled in from the nearest ancestor that
+    /// specifies them. A cycle in the `SUP` chain is reported as an error.
+    pub fn resolve_attributetype(&self, name: &str) -> Result<ResolvedAttributeType<'_>, String> {
+        let at = self
+            .get_attributetype(name)
+            .ok_or_else(|| format!("unknown attributeType: {}", name))?;
+
+        let mut syntax = at.syntax.as_deref();
+        let mut syntax_length = at.syntax_length;
+        let mut equality = at.equality.as_deref();
+        let mut ordering = at.ordering.as_deref();
+        let mut substr = at.substr.as_deref();
+        let mut single_value = at.single_value;
+
+        let mut seen = std::collections::HashSet::new();
+        seen.insert(CaseFold::new(&at.oid));
+        let mut current = at;
+        while syntax.is_none()
+            || equality.is_none()
+            || ordering.is_none()
+            || substr.is_none()
+            || !single_value
+        {
+            let sup_name = match &current.sup {
+                Some(s) => s,
+                None => break,
+            };
+            let sup_at = self
+                .get_attributetype(sup_name)
+                .ok_or_else(|| format!("SUP attributeType not found: {}", sup_name))?;
+            if !seen.insert(CaseFold::new(&sup_at.oid)) {
+                return Err(format!(
+                    "cycle detected in SUP chain of attributeType: {}",
+                    name
+                ));
+            }
+
+            if syntax.is_none() {
+                syntax = sup_at.syntax.as_deref();
+                syntax_length = sup_at.syntax_length;
+            }
+            if equality.is_none() {
+                equality = sup_at.equality.as_deref();
+            }
+            if ordering.is_none() {
+                ordering = sup_at.ordering.as_deref();
+            }
+            if substr.is_none() {
+                substr = sup_at.substr.as_deref();
+            }
+            single_value = single_value || sup_at.single_value;
+
+            current = sup_at;
+        }
+
+        Ok(ResolvedAttributeType {
+            attribute: at,
+            syntax,
+            syntax_length,
+            equality,
+            ordering,
+            substr,
+            single_value,
+        })
+    }
+}
+
+/// The effective properties of an attribute type after walking its `SUP`
+/// chain; see [`Schema::resolve_attributetype`].
+#[derive(Debug)]
+pub struct ResolvedAttributeType<'a> {
+    pub attribute: &'a AttributeType,
+    pub syntax: Option<&'a str>,
+    pub syntax_length: Option<usize>,
+    pub equality: Option<&'a str>,
+    pub ordering: Option<&'a str>,
+    pub substr: Option<&'a str>,
+    pub single_value: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -437,7 +1044,18 @@ impl<'a> Entroid<'a> {
     /// attributes into `self.must` and `self.may`.  Set `self.structural`
     /// to the structural objectclass, if any.  Trace output for user
     /// display goes into `self.comment`; errors into `self.error`.
+    ///
+    /// Attribute and objectClass `SUP` chains can both be multi-level, so
+    /// the resulting `must`/`may` are the union over each requested
+    /// class's entire superclass closure up to `top`, not just its own
+    /// directly listed attributes -- `self.classes` ends up holding that
+    /// whole closure by the time the loop below finishes. A cycle in the
+    /// `SUP` graph is reported as an error before that union is computed.
     pub fn compute(&mut self) -> Result<(), String> {
+        for cls in self.classes.clone() {
+            self.check_objectclass_cycle(cls, &mut Vec::new())?;
+        }
+
         // We need to iterate by index because compute_one may add new classes.
         let mut i = 0;
         while i < self.classes.len() {
@@ -446,6 +1064,10 @@ impl<'a> Entroid<'a> {
             i += 1;
         }
 
+        if let Some(structural) = self.structural {
+            self.apply_content_rule(structural)?;
+        }
+
         if self.structural.is_none() {
             self.comment
                 .push_str("### WARNING: no structural object class\n");
@@ -454,6 +1076,49 @@ impl<'a> Entroid<'a> {
         Ok(())
     }
 
+    /// Walk `cls`'s `SUP` chain depth-first, erroring if a class reappears
+    /// on the current path (an unknown superclass is left for
+    /// `compute_one` to report, since it isn't a cycle).
+    fn check_objectclass_cycle(
+        &self,
+        cls: &'a ObjectClass,
+        path: &mut Vec<CaseFold>,
+    ) -> Result<(), String> {
+        let key = CaseFold::new(&cls.oid);
+        if path.contains(&key) {
+            return Err(format!(
+                "cycle detected in SUP chain of objectClass: {}",
+                cls.name()
+            ));
+        }
+        path.push(key);
+        for sup_name in &cls.sup {
+            if let Some(sup_cls) = self.schema.get_objectclass(sup_name) {
+                self.check_objectclass_cycle(sup_cls, path)?;
+            }
+        }
+        path.pop();
+        Ok(())
+    }
+
+    /// Add an attribute to MUST, removing it from MAY if present there.
+    fn add_must(&mut self, at: &'a AttributeType) {
+        let at_ptr = at as *const AttributeType;
+        self.may.retain(|m| !std::ptr::eq(*m, at_ptr));
+        if !self.must.iter().any(|m| std::ptr::eq(*m, at_ptr)) {
+            self.must.push(at);
+        }
+    }
+
+    /// Add an attribute to MAY, unless it's already required by MUST.
+    fn add_may(&mut self, at: &'a AttributeType) {
+        let at_ptr = at as *const AttributeType;
+        let in_must = self.must.iter().any(|m| std::ptr::eq(*m, at_ptr));
+        if !in_must && !self.may.iter().any(|m| std::ptr::eq(*m, at_ptr)) {
+            self.may.push(at);
+        }
+    }
+
     fn compute_one(&mut self, cls: &'a ObjectClass) -> Result<(), String> {
         // Add superclasses
         for sup_name in &cls.sup {
@@ -482,13 +1147,7 @@ impl<'a> Entroid<'a> {
                 Some(at) => at,
                 None => return Err(format!("attribute type not found: {}", attr_name)),
             };
-            let at_ptr = at as *const AttributeType;
-            // Remove from MAY if present
-            self.may.retain(|m| !std::ptr::eq(*m, at_ptr));
-            // Add to MUST if not already present
-            if !self.must.iter().any(|m| std::ptr::eq(*m, at_ptr)) {
-                self.must.push(at);
-            }
+            self.add_must(at);
         }
 
         // Process MAY attributes
@@ -497,11 +1156,75 @@ impl<'a> Entroid<'a> {
                 Some(at) => at,
                 None => return Err(format!("attribute type not found: {}", attr_name)),
             };
+            self.add_may(at);
+        }
+
+        Ok(())
+    }
+
+    /// Apply the DIT content rule governing `structural`, if one is
+    /// defined: reject auxiliary classes not listed in `AUX`, add the
+    /// rule's extra `MUST`/`MAY`, and strip any `NOT` attribute from both
+    /// lists (warning in `comment` if it was a class-mandated MUST).
+    fn apply_content_rule(&mut self, structural: &'a ObjectClass) -> Result<(), String> {
+        let rule = match self.schema.get_ditcontentrule(&structural.oid) {
+            Some(rule) => rule,
+            None => return Ok(()),
+        };
+
+        let disallowed_aux: Vec<String> = self
+            .classes
+            .iter()
+            .filter(|c| c.kind == ObjectClassKind::Auxiliary)
+            .filter(|c| {
+                !rule
+                    .aux
+                    .iter()
+                    .any(|n| n.eq_ignore_ascii_case(c.name()) || n.eq_ignore_ascii_case(&c.oid))
+            })
+            .map(|c| c.name().to_string())
+            .collect();
+        for name in disallowed_aux {
+            self.error.push_str(&format!(
+                "auxiliary class not permitted by DIT content rule: {}\n",
+                name
+            ));
+        }
+
+        for attr_name in &rule.must {
+            let at = match self.get_attributetype(attr_name) {
+                Some(at) => at,
+                None => return Err(format!("attribute type not found: {}", attr_name)),
+            };
+            self.add_must(at);
+        }
+
+        for attr_name in &rule.may {
+            let at = match self.get_attributetype(attr_name) {
+                Some(at) => at,
+                None => return Err(format!("attribute type not found: {}", attr_name)),
+            };
+            self.add_may(at);
+        }
+
+        for attr_name in &rule.not {
+            let at = match self.schema.get_attributetype(attr_name) {
+                Some(at) => at,
+                None => continue,
+            };
             let at_ptr = at as *const AttributeType;
-            // Only add to MAY if not already in MUST
-            let in_must = self.must.iter().any(|m| std::ptr::eq(*m, at_ptr));
-            if !in_must {
-                self.may.push(at);
+
+            let must_len = self.must.len();
+            self.must.retain(|m| !std::ptr::eq(*m, at_ptr));
+            let removed_from_must = self.must.len() < must_len;
+
+            self.may.retain(|m| !std::ptr::eq(*m, at_ptr));
+
+            if removed_from_must {
+                self.comment.push_str(&format!(
+                    "### WARNING: DIT content rule NOT removed class-mandated MUST attribute: {}\n",
+                    at.name()
+                ));
             }
         }
 
@@ -542,6 +1265,449 @@ impl<'a> Entroid<'a> {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Entry validation — structural checks against a computed Entroid
+// ---------------------------------------------------------------------------
+
+/// Severity of a [`Finding`] reported by [`validate_entry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// One structural violation reported by [`validate_entry`], scoped to the
+/// attribute or objectClass it concerns (or `None` for entry-wide
+/// findings) so callers such as the editor can present it per-field
+/// instead of as free text.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub severity: Severity,
+    pub subject: Option<String>,
+    pub message: String,
+}
+
+impl Finding {
+    fn error(subject: Option<&str>, message: String) -> Self {
+        Finding {
+            severity: Severity::Error,
+            subject: subject.map(str::to_string),
+            message,
+        }
+    }
+}
+
+/// Strip an attribute descriptor's `;option` suffix (e.g. `cn;lang-en` →
+/// `cn`).
+fn strip_ad_options(ad: &str) -> &str {
+    match ad.find(';') {
+        Some(pos) => &ad[..pos],
+        None => ad,
+    }
+}
+
+/// Validate an entry against its schema-computed [`Entroid`], reporting
+/// RFC 4512 structural violations rather than folding them into the
+/// free-form `comment`/`error` strings `Entroid::compute` produces:
+/// unknown objectClasses, a missing or duplicated structural objectClass,
+/// absent `MUST` attributes, attributes covered by neither `MUST` nor
+/// `MAY` (unless `extensible` is set), and multiple values on a
+/// `SINGLE-VALUE` attribute. `entroid` should already have had
+/// [`Entroid::compute`] called on it — its partial result (`classes`,
+/// `must`, `may`) is still useful even when `compute` returned an error,
+/// e.g. for an unresolvable superclass.
+pub fn validate_entry(
+    schema: &Schema,
+    entroid: &Entroid,
+    entry: &crate::data::Entry,
+    extensible: bool,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for attr in &entry.attributes {
+        if !attr.ad.eq_ignore_ascii_case("objectClass") {
+            continue;
+        }
+        for value in &attr.values {
+            let name = String::from_utf8_lossy(value);
+            if !name.is_empty() && schema.get_objectclass(&name).is_none() {
+                findings.push(Finding::error(
+                    Some(&name),
+                    format!("unknown objectClass: {}", name),
+                ));
+            }
+        }
+    }
+
+    // A structural class named in another structural class's SUP is part of
+    // that class's chain; only the most derived class of each chain counts.
+    let structural: Vec<&ObjectClass> =
+        entroid.classes.iter().copied().filter(|c| c.kind == ObjectClassKind::Structural).collect();
+    let is_sup_of_other = |c: &ObjectClass| {
+        structural.iter().any(|d| d.sup.iter().any(|s| {
+            s.eq_ignore_ascii_case(&c.oid) || c.names.iter().any(|n| s.eq_ignore_ascii_case(n))
+        }))
+    };
+    match structural.iter().filter(|c| !is_sup_of_other(**c)).count() {
+        0 => findings.push(Finding::error(
+            None,
+            "no structural objectClass".to_string(),
+        )),
+        1 => {}
+        n => findings.push(Finding::error(
+            None,
+            format!("{} structural objectClasses present; exactly one is required", n),
+        )),
+    }
+
+    // Index the entry's attribute values by base name (options stripped),
+    // case-insensitively, for MUST/MAY/SINGLE-VALUE lookups below.
+    let mut entry_values: HashMap<CaseFold, Vec<&Vec<u8>>> = HashMap::new();
+    for attr in &entry.attributes {
+        let ad = attr.ad.as_str_lossy();
+        entry_values
+            .entry(CaseFold::new(strip_ad_options(&ad)))
+            .or_default()
+            .extend(attr.values.iter());
+    }
+
+    let names_of = |at: &AttributeType| -> Vec<CaseFold> {
+        at.names
+            .iter()
+            .chain(std::iter::once(&at.oid))
+            .map(|n| CaseFold::new(n))
+            .collect()
+    };
+
+    for at in &entroid.must {
+        if !names_of(at).iter().any(|n| entry_values.contains_key(n)) {
+            findings.push(Finding::error(
+                Some(at.name()),
+                format!("required attribute '{}' is missing", at.name()),
+            ));
+        }
+    }
+
+    for at in entroid.must.iter().chain(entroid.may.iter()) {
+        if !at.single_value {
+            continue;
+        }
+        if let Some(values) = names_of(at)
+            .iter()
+            .find_map(|n| entry_values.get(n))
+        {
+            if values.len() > 1 {
+                findings.push(Finding::error(
+                    Some(at.name()),
+                    format!(
+                        "'{}' is SINGLE-VALUE but has {} values",
+                        at.name(),
+                        values.len()
+                    ),
+                ));
+            }
+        }
+    }
+
+    if !extensible {
+        let allowed: std::collections::HashSet<CaseFold> = entroid
+            .must
+            .iter()
+            .chain(entroid.may.iter())
+            .flat_map(|at| names_of(at))
+            .collect();
+
+        for attr in &entry.attributes {
+            let ad = attr.ad.as_str_lossy();
+            let base = strip_ad_options(&ad);
+            if base.eq_ignore_ascii_case("objectClass") {
+                continue;
+            }
+            if !allowed.contains(&CaseFold::new(base)) {
+                findings.push(Finding::error(
+                    Some(base),
+                    format!(
+                        "attribute '{}' is not allowed by the entry's objectClasses",
+                        base
+                    ),
+                ));
+            }
+        }
+    }
+
+    findings
+}
+
+// ---------------------------------------------------------------------------
+// Attribute value syntax validation
+// ---------------------------------------------------------------------------
+
+const SYNTAX_DIRECTORY_STRING: &str = "1.3.6.1.4.1.1466.115.121.1.15";
+const SYNTAX_IA5_STRING: &str = "1.3.6.1.4.1.1466.115.121.1.26";
+const SYNTAX_INTEGER: &str = "1.3.6.1.4.1.1466.115.121.1.27";
+const SYNTAX_BOOLEAN: &str = "1.3.6.1.4.1.1466.115.121.1.7";
+const SYNTAX_DN: &str = "1.3.6.1.4.1.1466.115.121.1.12";
+const SYNTAX_GENERALIZED_TIME: &str = "1.3.6.1.4.1.1466.115.121.1.24";
+const SYNTAX_OCTET_STRING: &str = "1.3.6.1.4.1.1466.115.121.1.5";
+const SYNTAX_BINARY: &str = "1.3.6.1.4.1.1466.115.121.1.40";
+
+/// Whether `ad` (an attribute descriptor, options included) carries the
+/// `;binary` option, which by RFC 4522 convention marks the value as a
+/// raw encoding exempt from its attribute's textual `SYNTAX`.
+fn has_binary_option(ad: &str) -> bool {
+    ad.split(';').skip(1).any(|opt| opt.eq_ignore_ascii_case("binary"))
+}
+
+/// Whether `s` (with an optional leading `-`) is a valid RFC 4517 INTEGER:
+/// no leading zeros except a bare `0`, and `-0` is not a valid encoding.
+fn is_valid_integer(s: &str) -> bool {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    if digits.len() > 1 && digits.starts_with('0') {
+        return false;
+    }
+    !(negative && digits == "0")
+}
+
+/// Validate an RFC 4517 GeneralizedTime: `YYYYMMDDHHMMSS`, an optional
+/// `.`/`,`-introduced fraction, then either `Z` or a `+HHMM`/`-HHMM`
+/// offset.
+fn is_valid_generalized_time(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    if bytes.len() < 14 || !bytes[..14].iter().all(u8::is_ascii_digit) {
+        return false;
+    }
+    let month = &s[4..6];
+    let day = &s[6..8];
+    let hour = &s[8..10];
+    let minute = &s[10..12];
+    let second = &s[12..14];
+    if !(("01"..="12").contains(&month)
+        && ("01"..="31").contains(&day)
+        && ("00"..="23").contains(&hour)
+        && ("00"..="59").contains(&minute)
+        && ("00"..="60").contains(&second))
+    {
+        return false;
+    }
+
+    let mut rest = &s[14..];
+    if let Some(after_dot) = rest.strip_prefix('.').or_else(|| rest.strip_prefix(',')) {
+        let frac_len = after_dot.bytes().take_while(u8::is_ascii_digit).count();
+        if frac_len == 0 {
+            return false;
+        }
+        rest = &after_dot[frac_len..];
+    }
+
+    if rest == "Z" {
+        return true;
+    }
+    match rest.strip_prefix('+').or_else(|| rest.strip_prefix('-')) {
+        Some(offset) => offset.len() == 4 && offset.bytes().all(|b| b.is_ascii_digit()),
+        None => false,
+    }
+}
+
+/// Check one attribute value against its declared LDAP syntax, returning
+/// the violation reason on failure. Syntaxes without a decoder here (most
+/// of RFC 4517's catalogue) are treated as unconstrained, matching how
+/// [`validate_entry`] only reports what it can actually check.
+fn check_value_syntax(syntax: &str, value: &[u8]) -> Result<(), String> {
+    match syntax {
+        SYNTAX_DIRECTORY_STRING => {
+            let s = std::str::from_utf8(value).map_err(|_| "not valid UTF-8".to_string())?;
+            if s.is_empty() {
+                return Err("DirectoryString must not be empty".to_string());
+            }
+            Ok(())
+        }
+        SYNTAX_IA5_STRING => {
+            if !value.is_ascii() {
+                return Err("IA5String must be 7-bit ASCII".to_string());
+            }
+            Ok(())
+        }
+        SYNTAX_INTEGER => {
+            let s = std::str::from_utf8(value).map_err(|_| "not valid UTF-8".to_string())?;
+            if is_valid_integer(s) {
+                Ok(())
+            } else {
+                Err(format!("'{}' is not a valid INTEGER", s))
+            }
+        }
+        SYNTAX_BOOLEAN => {
+            let s = std::str::from_utf8(value).map_err(|_| "not valid UTF-8".to_string())?;
+            if s == "TRUE" || s == "FALSE" {
+                Ok(())
+            } else {
+                Err(format!("'{}' is not a valid Boolean (TRUE/FALSE)", s))
+            }
+        }
+        SYNTAX_DN => {
+            let s = std::str::from_utf8(value).map_err(|_| "not valid UTF-8".to_string())?;
+            crate::dn::parse_dn(s)
+                .map(|_| ())
+                .map_err(|e| format!("invalid DN: {}", e))
+        }
+        SYNTAX_GENERALIZED_TIME => {
+            let s = std::str::from_utf8(value).map_err(|_| "not valid UTF-8".to_string())?;
+            if is_valid_generalized_time(s) {
+                Ok(())
+            } else {
+                Err(format!("'{}' is not a valid GeneralizedTime", s))
+            }
+        }
+        SYNTAX_OCTET_STRING | SYNTAX_BINARY => Ok(()),
+        _ => Ok(()),
+    }
+}
+
+/// Validate every value of every attribute in `entry` against the LDAP
+/// syntax its attribute type resolves to (walking `SUP`, as
+/// [`Schema::resolve_attributetype`] does), returning `(attribute, value,
+/// reason)` for each violation so a caller such as the editor can warn
+/// rather than reject the entry outright. Values carrying the `;binary`
+/// option are exempt, since that option marks a raw encoding rather than
+/// the attribute's normal textual form. Attributes with no known type or
+/// no declared `SYNTAX` are left unchecked.
+pub fn check_entry_syntax(schema: &Schema, entry: &crate::data::Entry) -> Vec<(String, Vec<u8>, String)> {
+    let mut violations = Vec::new();
+    for attr in &entry.attributes {
+        let ad = attr.ad.as_str_lossy();
+        if has_binary_option(&ad) {
+            continue;
+        }
+        let base = strip_ad_options(&ad);
+        let resolved = match schema.resolve_attributetype(base) {
+            Ok(resolved) => resolved,
+            Err(_) => continue,
+        };
+        let syntax = match resolved.syntax {
+            Some(syntax) => syntax,
+            None => continue,
+        };
+        for value in &attr.values {
+            if let Err(reason) = check_value_syntax(syntax, value) {
+                violations.push((base.to_string(), value.clone(), reason));
+            }
+        }
+    }
+    violations
+}
+
+// ---------------------------------------------------------------------------
+// Matching-rule-aware value comparison
+// ---------------------------------------------------------------------------
+
+/// Trim outer whitespace and collapse internal whitespace runs to a
+/// single space, the normalization shared by `caseIgnoreMatch` and
+/// `caseExactMatch`.
+fn normalize_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Normalize a value into the canonical form its named `EQUALITY` matching
+/// rule (by descr or OID) would compare, falling back to `caseExactMatch`
+/// semantics for a rule this crate doesn't implement. Two values are equal
+/// under `rule` iff their normalized forms are byte-equal, which is how
+/// [`matching_rule_match`] and [`crate::diff::MatchingRule`] implementations
+/// use this. Unicode case-folding uses `str::to_lowercase`, which is close
+/// enough to full Unicode simple case folding for the scripts ldapvi's
+/// users actually hit and avoids pulling in a dedicated case-folding crate.
+pub(crate) fn matching_rule_normalize(rule: &str, v: &[u8]) -> Vec<u8> {
+    match rule {
+        "caseIgnoreMatch" => match std::str::from_utf8(v) {
+            Ok(s) => normalize_whitespace(s).to_lowercase().into_bytes(),
+            Err(_) => v.to_vec(),
+        },
+        "numericStringMatch" => match std::str::from_utf8(v) {
+            Ok(s) => strip_spaces(s).into_bytes(),
+            Err(_) => v.to_vec(),
+        },
+        "distinguishedNameMatch" => match std::str::from_utf8(v) {
+            Ok(s) => match crate::dn::parse_dn(s) {
+                Ok(rdns) => format!("{:?}", rdns).into_bytes(),
+                Err(_) => v.to_vec(),
+            },
+            Err(_) => v.to_vec(),
+        },
+        "octetStringMatch" => v.to_vec(),
+        // caseExactMatch, and the fallback for any rule we don't know.
+        _ => match std::str::from_utf8(v) {
+            Ok(s) => normalize_whitespace(s).into_bytes(),
+            Err(_) => v.to_vec(),
+        },
+    }
+}
+
+/// Compare two values the way a directory server would under the named
+/// `EQUALITY` matching rule. See [`matching_rule_normalize`].
+fn matching_rule_match(rule: &str, a: &[u8], b: &[u8]) -> bool {
+    matching_rule_normalize(rule, a) == matching_rule_normalize(rule, b)
+}
+
+fn strip_spaces(s: &str) -> String {
+    s.chars().filter(|c| *c != ' ').collect()
+}
+
+/// Compare two values of `attribute_name` per its resolved `EQUALITY`
+/// matching rule (walking `SUP`, like [`Schema::resolve_attributetype`]),
+/// falling back to `caseExactMatch` when the attribute is unknown or
+/// declares no `EQUALITY` rule. An OID-form `EQUALITY` reference is
+/// normalized to its descr first, via [`Schema::matching_rule_name`].
+pub fn attribute_values_match(schema: &Schema, attribute_name: &str, a: &[u8], b: &[u8]) -> bool {
+    let rule = schema
+        .resolve_attributetype(attribute_name)
+        .ok()
+        .and_then(|resolved| resolved.equality)
+        .map(|reference| schema.matching_rule_name(reference));
+    match rule {
+        Some(rule) => matching_rule_match(rule, a, b),
+        None => matching_rule_match("caseExactMatch", a, b),
+    }
+}
+
+/// Find a value that `attribute_name`'s `EQUALITY` rule considers a
+/// duplicate of an earlier one in `values`, returning its index -- the
+/// schema-aware counterpart of `LdapMod::find_duplicate_value`'s
+/// byte-identical check, e.g. catching `cn: Jane` / `cn: jane` as the
+/// same `caseIgnoreMatch` value instead of two distinct ones.
+pub fn find_duplicate_value(schema: &Schema, attribute_name: &str, values: &[Vec<u8>]) -> Option<usize> {
+    for i in 1..values.len() {
+        if values[..i]
+            .iter()
+            .any(|earlier| attribute_values_match(schema, attribute_name, earlier, &values[i]))
+        {
+            return Some(i);
+        }
+    }
+    None
+}
+
+/// Remove values from `values` that `attribute_name`'s `EQUALITY` rule
+/// considers duplicates of an earlier value, keeping the first occurrence
+/// of each distinct value -- e.g. before writing back a `MUST`/`MAY`
+/// value set, so `cn: Jane` and `cn: jane` aren't both kept just because
+/// they differ in case.
+pub fn dedup_values(schema: &Schema, attribute_name: &str, values: &mut Vec<Vec<u8>>) {
+    let mut kept: Vec<Vec<u8>> = Vec::with_capacity(values.len());
+    for value in values.drain(..) {
+        if !kept
+            .iter()
+            .any(|existing| attribute_values_match(schema, attribute_name, existing, &value))
+        {
+            kept.push(value);
+        }
+    }
+    *values = kept;
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -761,6 +1927,38 @@ mod tests {
         assert!(ent.error.len() > 0);
     }
 
+    #[test]
+    fn compute_entroid_grandchild_inherits_must_transitively() {
+        // organizationalPerson SUP person SUP top: MUST must include
+        // attributes from the entire two-level closure, not just the
+        // directly listed MAY on organizationalPerson itself.
+        let schema = make_test_schema();
+        let mut ent = Entroid::new(&schema);
+        ent.request_class("organizationalPerson");
+        ent.compute().unwrap();
+
+        let must_names: Vec<&str> = ent.must.iter().map(|at| at.name()).collect();
+        assert!(must_names.contains(&"sn"));
+        assert!(must_names.contains(&"cn"));
+        assert!(must_names.contains(&"objectClass"));
+    }
+
+    #[test]
+    fn compute_entroid_detects_objectclass_sup_cycle() {
+        let mut schema = Schema::new();
+        schema.add_attributetype(parse_attributetype("( 2.5.4.0 NAME 'objectClass' )").unwrap());
+        schema.add_objectclass(
+            parse_objectclass("( 1.1 NAME 'a' SUP b STRUCTURAL MUST objectClass )").unwrap(),
+        );
+        schema.add_objectclass(
+            parse_objectclass("( 1.2 NAME 'b' SUP a STRUCTURAL MUST objectClass )").unwrap(),
+        );
+        let mut ent = Entroid::new(&schema);
+        ent.request_class("a");
+        let err = ent.compute().unwrap_err();
+        assert!(err.contains("cycle"));
+    }
+
     // -- Group 7: Attribute removal --
 
     #[test]
@@ -848,17 +2046,830 @@ mod tests {
     }
 
     #[test]
-    fn parse_objectclass_unrecognized_keywords_skipped() {
+    fn parse_objectclass_x_extensions_retained() {
         let cls = parse_objectclass(
             "( 1.2.3 NAME 'test' X-ORIGIN 'RFC 1234' X-SCHEMA-FILE '00core.ldif' MUST cn )",
         )
         .unwrap();
         assert_eq!(cls.names, vec!["test"]);
         assert_eq!(cls.must, vec!["cn"]);
+        assert_eq!(
+            cls.extensions,
+            vec![
+                ("X-ORIGIN".to_string(), vec!["RFC 1234".to_string()]),
+                ("X-SCHEMA-FILE".to_string(), vec!["00core.ldif".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_objectclass_truly_unrecognized_keyword_skipped() {
+        let cls = parse_objectclass("( 1.2.3 NAME 'test' FOO 'bar' MUST cn )").unwrap();
+        assert_eq!(cls.names, vec!["test"]);
+        assert_eq!(cls.must, vec!["cn"]);
+        assert!(cls.extensions.is_empty());
+    }
+
+    #[test]
+    fn objectclass_to_definition_round_trips_extensions() {
+        let original = "( 1.2.3 NAME 'test' SUP top STRUCTURAL MUST cn \
+             X-ORIGIN 'RFC 1234' X-SCHEMA-FILE '00core.ldif' )";
+        let cls = parse_objectclass(original).unwrap();
+        let reparsed = parse_objectclass(&cls.to_definition()).unwrap();
+        assert_eq!(reparsed.extensions, cls.extensions);
+        assert_eq!(reparsed.names, cls.names);
+        assert_eq!(reparsed.must, cls.must);
     }
 
     #[test]
     fn parse_objectclass_malformed() {
         assert!(parse_objectclass("garbage").is_err());
     }
+
+    // -- Group 9: AttributeType field parsing --
+
+    #[test]
+    fn parse_attributetype_sup_and_matching_rules() {
+        let at = parse_attributetype(
+            "( 2.5.4.41 NAME 'name' EQUALITY caseIgnoreMatch \
+             SUBSTR caseIgnoreSubstringsMatch \
+             SYNTAX 1.3.6.1.4.1.1466.115.121.1.15{32768} )",
+        )
+        .unwrap();
+        assert_eq!(at.equality.as_deref(), Some("caseIgnoreMatch"));
+        assert_eq!(at.substr.as_deref(), Some("caseIgnoreSubstringsMatch"));
+        assert_eq!(at.ordering, None);
+        assert_eq!(at.syntax.as_deref(), Some("1.3.6.1.4.1.1466.115.121.1.15"));
+        assert_eq!(at.syntax_length, Some(32768));
+    }
+
+    #[test]
+    fn parse_attributetype_single_value_and_flags() {
+        let at = parse_attributetype(
+            "( 2.5.18.10 NAME 'subschemaSubentry' \
+             SINGLE-VALUE NO-USER-MODIFICATION USAGE directoryOperation )",
+        )
+        .unwrap();
+        assert!(at.single_value);
+        assert!(at.no_user_modification);
+        assert!(!at.collective);
+        assert_eq!(at.usage, AttributeUsage::DirectoryOperation);
+    }
+
+    #[test]
+    fn parse_attributetype_default_usage() {
+        let at = parse_attributetype("( 2.5.4.3 NAME 'cn' )").unwrap();
+        assert_eq!(at.usage, AttributeUsage::UserApplications);
+        assert!(!at.single_value);
+    }
+
+    #[test]
+    fn parse_attributetype_ordering() {
+        let at = parse_attributetype(
+            "( 1.3.6.1.4.1.1466.115.121.1.24 NAME 'generalizedTimeMatch' \
+             ORDERING generalizedTimeOrderingMatch )",
+        )
+        .unwrap();
+        assert_eq!(at.ordering.as_deref(), Some("generalizedTimeOrderingMatch"));
+    }
+
+    #[test]
+    fn parse_attributetype_sup_name_captured() {
+        let at = parse_attributetype("( 2.5.4.41 NAME 'name' SUP distinguishedName )").unwrap();
+        assert_eq!(at.sup.as_deref(), Some("distinguishedName"));
+    }
+
+    #[test]
+    fn parse_attributetype_x_extensions_retained() {
+        let at = parse_attributetype(
+            "( 2.5.4.3 NAME 'cn' X-ORIGIN 'RFC 4519' X-SCHEMA-FILE ( 'a.ldif' 'b.ldif' ) )",
+        )
+        .unwrap();
+        assert_eq!(
+            at.extensions,
+            vec![
+                ("X-ORIGIN".to_string(), vec!["RFC 4519".to_string()]),
+                (
+                    "X-SCHEMA-FILE".to_string(),
+                    vec!["a.ldif".to_string(), "b.ldif".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn attributetype_to_definition_round_trips_extensions() {
+        let original = "( 2.5.4.41 NAME 'name' SUP distinguishedName \
+             EQUALITY caseIgnoreMatch SYNTAX 1.3.6.1.4.1.1466.115.121.1.15{32768} \
+             SINGLE-VALUE X-ORIGIN 'RFC 4519' )";
+        let at = parse_attributetype(original).unwrap();
+        let reparsed = parse_attributetype(&at.to_definition()).unwrap();
+        assert_eq!(reparsed.extensions, at.extensions);
+        assert_eq!(reparsed.sup, at.sup);
+        assert_eq!(reparsed.equality, at.equality);
+        assert_eq!(reparsed.syntax, at.syntax);
+        assert_eq!(reparsed.syntax_length, at.syntax_length);
+        assert!(reparsed.single_value);
+    }
+
+    // -- Group 10: SUP chain resolution --
+
+    fn make_sup_chain_schema() -> Schema {
+        let mut schema = Schema::new();
+        schema.add_attributetype(
+            parse_attributetype(
+                "( 2.5.4.41 NAME 'name' EQUALITY caseIgnoreMatch \
+                 SUBSTR caseIgnoreSubstringsMatch \
+                 SYNTAX 1.3.6.1.4.1.1466.115.121.1.15{32768} )",
+            )
+            .unwrap(),
+        );
+        schema.add_attributetype(
+            parse_attributetype("( 2.5.4.3 NAME 'cn' SUP name SINGLE-VALUE )").unwrap(),
+        );
+        schema
+    }
+
+    #[test]
+    fn resolve_attributetype_inherits_from_sup() {
+        let schema = make_sup_chain_schema();
+        let resolved = schema.resolve_attributetype("cn").unwrap();
+        assert_eq!(resolved.equality, Some("caseIgnoreMatch"));
+        assert_eq!(resolved.substr, Some("caseIgnoreSubstringsMatch"));
+        assert_eq!(resolved.syntax, Some("1.3.6.1.4.1.1466.115.121.1.15"));
+        assert_eq!(resolved.syntax_length, Some(32768));
+        assert!(resolved.single_value);
+    }
+
+    #[test]
+    fn resolve_attributetype_own_fields_take_precedence() {
+        let mut schema = make_sup_chain_schema();
+        schema.add_attributetype(
+            parse_attributetype(
+                "( 1.2.3.4 NAME 'cnAlias' SUP name EQUALITY caseExactMatch )",
+            )
+            .unwrap(),
+        );
+        let resolved = schema.resolve_attributetype("cnAlias").unwrap();
+        assert_eq!(resolved.equality, Some("caseExactMatch"));
+        // SUBSTR/SYNTAX still inherited from 'name'.
+        assert_eq!(resolved.substr, Some("caseIgnoreSubstringsMatch"));
+    }
+
+    #[test]
+    fn resolve_attributetype_unknown_name() {
+        let schema = make_sup_chain_schema();
+        assert!(schema.resolve_attributetype("noSuchAttr").is_err());
+    }
+
+    #[test]
+    fn resolve_attributetype_unknown_sup() {
+        let mut schema = Schema::new();
+        schema.add_attributetype(
+            parse_attributetype("( 1.2.3 NAME 'orphan' SUP bogusParent )").unwrap(),
+        );
+        let err = schema.resolve_attributetype("orphan").unwrap_err();
+        assert!(err.contains("bogusParent"));
+    }
+
+    #[test]
+    fn resolve_attributetype_cycle_detected() {
+        let mut schema = Schema::new();
+        schema.add_attributetype(parse_attributetype("( 1.1 NAME 'a' SUP b )").unwrap());
+        schema.add_attributetype(parse_attributetype("( 1.2 NAME 'b' SUP a )").unwrap());
+        assert!(schema.resolve_attributetype("a").unwrap_err().contains("cycle"));
+    }
+
+    // -- Group 11: Entry validation --
+
+    fn entry_of(dn: &str, attrs: &[(&str, &[&str])]) -> crate::data::Entry {
+        crate::data::Entry {
+            dn: dn.to_string(),
+            attributes: attrs
+                .iter()
+                .map(|(ad, values)| crate::data::Attribute {
+                    ad: (*ad).into(),
+                    values: values.iter().map(|v| v.as_bytes().to_vec()).collect(),
+                })
+                .collect(),
+        }
+    }
+
+    fn validate(schema: &Schema, entry: &crate::data::Entry) -> Vec<Finding> {
+        let mut entroid = Entroid::new(schema);
+        entroid_set_entry_for_test(&mut entroid, entry);
+        validate_entry(schema, &entroid, entry, false)
+    }
+
+    fn entroid_set_entry_for_test(entroid: &mut Entroid, entry: &crate::data::Entry) {
+        for attr in &entry.attributes {
+            if attr.ad.eq_ignore_ascii_case("objectClass") {
+                for value in &attr.values {
+                    entroid.request_class(&String::from_utf8_lossy(value));
+                }
+            }
+        }
+        let _ = entroid.compute();
+    }
+
+    #[test]
+    fn validate_entry_accepts_well_formed_entry() {
+        let schema = make_test_schema();
+        let entry = entry_of(
+            "cn=Jane Doe,dc=example,dc=com",
+            &[
+                ("objectClass", &["top", "person"]),
+                ("cn", &["Jane Doe"]),
+                ("sn", &["Doe"]),
+            ],
+        );
+        let findings = validate(&schema, &entry);
+        assert!(findings.is_empty(), "{:?}", findings);
+    }
+
+    #[test]
+    fn validate_entry_reports_unknown_objectclass() {
+        let schema = make_test_schema();
+        let entry = entry_of(
+            "cn=x,dc=example,dc=com",
+            &[("objectClass", &["bogusClass"])],
+        );
+        let findings = validate(&schema, &entry);
+        assert!(findings
+            .iter()
+            .any(|f| f.message.contains("unknown objectClass")));
+    }
+
+    #[test]
+    fn validate_entry_reports_missing_structural_class() {
+        let schema = make_test_schema();
+        let entry = entry_of("cn=x,dc=example,dc=com", &[("objectClass", &["top"])]);
+        let findings = validate(&schema, &entry);
+        assert!(findings
+            .iter()
+            .any(|f| f.message.contains("no structural objectClass")));
+    }
+
+    #[test]
+    fn validate_entry_reports_missing_must_attribute() {
+        let schema = make_test_schema();
+        let entry = entry_of(
+            "cn=x,dc=example,dc=com",
+            &[("objectClass", &["top", "person"]), ("cn", &["x"])],
+        );
+        let findings = validate(&schema, &entry);
+        assert!(findings
+            .iter()
+            .any(|f| f.severity == Severity::Error && f.subject.as_deref() == Some("sn")));
+    }
+
+    #[test]
+    fn validate_entry_reports_disallowed_attribute() {
+        let schema = make_test_schema();
+        let entry = entry_of(
+            "cn=x,dc=example,dc=com",
+            &[
+                ("objectClass", &["top", "person"]),
+                ("cn", &["x"]),
+                ("sn", &["x"]),
+                ("notInSchema", &["x"]),
+            ],
+        );
+        let findings = validate(&schema, &entry);
+        assert!(findings
+            .iter()
+            .any(|f| f.subject.as_deref() == Some("notInSchema")));
+    }
+
+    #[test]
+    fn validate_entry_extensible_allows_unknown_attribute() {
+        let schema = make_test_schema();
+        let entry = entry_of(
+            "cn=x,dc=example,dc=com",
+            &[
+                ("objectClass", &["top", "person"]),
+                ("cn", &["x"]),
+                ("sn", &["x"]),
+                ("notInSchema", &["x"]),
+            ],
+        );
+        let mut entroid = Entroid::new(&schema);
+        entroid_set_entry_for_test(&mut entroid, &entry);
+        let findings = validate_entry(&schema, &entroid, &entry, true);
+        assert!(!findings
+            .iter()
+            .any(|f| f.subject.as_deref() == Some("notInSchema")));
+    }
+
+    #[test]
+    fn validate_entry_reports_single_value_violation() {
+        let mut schema = make_test_schema();
+        schema.add_attributetype(
+            parse_attributetype("( 1.2.3.4 NAME 'singleAttr' SINGLE-VALUE )").unwrap(),
+        );
+        schema.add_objectclass(
+            parse_objectclass("( 1.2.3 NAME 'withSingle' SUP top STRUCTURAL MAY singleAttr )")
+                .unwrap(),
+        );
+        let entry = entry_of(
+            "cn=x,dc=example,dc=com",
+            &[
+                ("objectClass", &["withSingle", "top"]),
+                ("singleAttr", &["a", "b"]),
+            ],
+        );
+        let findings = validate(&schema, &entry);
+        assert!(findings
+            .iter()
+            .any(|f| f.message.contains("SINGLE-VALUE")));
+    }
+
+    // -- Group 12: DIT content rules --
+
+    #[test]
+    fn parse_ditcontentrule_full() {
+        let rule = parse_ditcontentrule(
+            "( 2.5.6.6 NAME 'personRule' \
+             AUX ( posixAccount $ shadowAccount ) \
+             MUST extraMust MAY extraMay NOT seeAlso )",
+        )
+        .unwrap();
+        assert_eq!(rule.oid, "2.5.6.6");
+        assert_eq!(rule.names, vec!["personRule"]);
+        assert_eq!(rule.aux, vec!["posixAccount", "shadowAccount"]);
+        assert_eq!(rule.must, vec!["extraMust"]);
+        assert_eq!(rule.may, vec!["extraMay"]);
+        assert_eq!(rule.not, vec!["seeAlso"]);
+    }
+
+    #[test]
+    fn content_rule_adds_extra_must_and_may() {
+        let mut schema = make_test_schema();
+        schema.add_attributetype(
+            parse_attributetype("( 1.2.3.4 NAME 'extraMust' )").unwrap(),
+        );
+        schema.add_attributetype(parse_attributetype("( 1.2.3.5 NAME 'extraMay' )").unwrap());
+        schema.add_ditcontentrule(
+            parse_ditcontentrule("( 2.5.6.6 MUST extraMust MAY extraMay )").unwrap(),
+        );
+
+        let mut ent = Entroid::new(&schema);
+        ent.request_class("person");
+        ent.compute().unwrap();
+
+        let must_names: Vec<&str> = ent.must.iter().map(|at| at.name()).collect();
+        let may_names: Vec<&str> = ent.may.iter().map(|at| at.name()).collect();
+        assert!(must_names.contains(&"extraMust"));
+        assert!(may_names.contains(&"extraMay"));
+    }
+
+    #[test]
+    fn content_rule_not_strips_attribute_and_warns_if_must() {
+        let mut schema = make_test_schema();
+        schema.add_ditcontentrule(parse_ditcontentrule("( 2.5.6.6 NOT cn )").unwrap());
+
+        let mut ent = Entroid::new(&schema);
+        ent.request_class("person");
+        ent.compute().unwrap();
+
+        let must_names: Vec<&str> = ent.must.iter().map(|at| at.name()).collect();
+        assert!(!must_names.contains(&"cn"));
+        assert!(ent.comment.contains("WARNING"));
+        assert!(ent.comment.contains("cn"));
+    }
+
+    #[test]
+    fn content_rule_rejects_unlisted_auxiliary_class() {
+        let mut schema = make_test_schema();
+        schema.add_objectclass(
+            parse_objectclass("( 1.9.9 NAME 'someAux' AUXILIARY MAY description )").unwrap(),
+        );
+        schema.add_ditcontentrule(
+            parse_ditcontentrule("( 2.5.6.6 AUX otherAux )").unwrap(),
+        );
+
+        let mut ent = Entroid::new(&schema);
+        ent.request_class("person");
+        ent.request_class("someAux");
+        ent.compute().unwrap();
+
+        assert!(ent.error.contains("someAux"));
+    }
+
+    #[test]
+    fn content_rule_allows_listed_auxiliary_class() {
+        let mut schema = make_test_schema();
+        schema.add_objectclass(
+            parse_objectclass("( 1.9.9 NAME 'someAux' AUXILIARY MAY description )").unwrap(),
+        );
+        schema.add_ditcontentrule(parse_ditcontentrule("( 2.5.6.6 AUX someAux )").unwrap());
+
+        let mut ent = Entroid::new(&schema);
+        ent.request_class("person");
+        ent.request_class("someAux");
+        ent.compute().unwrap();
+
+        assert!(ent.error.is_empty());
+    }
+
+    #[test]
+    fn no_content_rule_is_a_noop() {
+        let schema = make_test_schema();
+        let mut ent = Entroid::new(&schema);
+        ent.request_class("person");
+        ent.compute().unwrap();
+        assert!(ent.error.is_empty());
+    }
+
+    // -- Group 13: LDAP syntaxes, matching rules, name forms --
+
+    #[test]
+    fn parse_ldapsyntax_with_desc() {
+        let syntax =
+            parse_ldapsyntax("( 1.3.6.1.4.1.1466.115.121.1.15 DESC 'Directory String' )").unwrap();
+        assert_eq!(syntax.oid, "1.3.6.1.4.1.1466.115.121.1.15");
+        assert_eq!(syntax.desc.as_deref(), Some("Directory String"));
+    }
+
+    #[test]
+    fn parse_ldapsyntax_oid_only() {
+        let syntax = parse_ldapsyntax("( 1.3.6.1.4.1.1466.115.121.1.27 )").unwrap();
+        assert_eq!(syntax.oid, "1.3.6.1.4.1.1466.115.121.1.27");
+        assert_eq!(syntax.desc, None);
+    }
+
+    #[test]
+    fn parse_matchingrule_full() {
+        let rule = parse_matchingrule(
+            "( 2.5.13.2 NAME 'caseIgnoreMatch' \
+             SYNTAX 1.3.6.1.4.1.1466.115.121.1.15 )",
+        )
+        .unwrap();
+        assert_eq!(rule.oid, "2.5.13.2");
+        assert_eq!(rule.names, vec!["caseIgnoreMatch"]);
+        assert_eq!(rule.syntax, "1.3.6.1.4.1.1466.115.121.1.15");
+    }
+
+    #[test]
+    fn parse_nameform_full() {
+        let form = parse_nameform(
+            "( 1.2.3 NAME 'personNameForm' OC person MUST cn MAY ( sn $ description ) )",
+        )
+        .unwrap();
+        assert_eq!(form.oid, "1.2.3");
+        assert_eq!(form.names, vec!["personNameForm"]);
+        assert_eq!(form.oc, "person");
+        assert_eq!(form.must, vec!["cn"]);
+        assert_eq!(form.may, vec!["sn", "description"]);
+    }
+
+    #[test]
+    fn schema_matching_rule_name_by_oid_or_name() {
+        let mut schema = Schema::new();
+        schema.add_matchingrule(
+            parse_matchingrule(
+                "( 2.5.13.2 NAME 'caseIgnoreMatch' SYNTAX 1.3.6.1.4.1.1466.115.121.1.15 )",
+            )
+            .unwrap(),
+        );
+        assert_eq!(schema.matching_rule_name("caseIgnoreMatch"), "caseIgnoreMatch");
+        assert_eq!(schema.matching_rule_name("2.5.13.2"), "caseIgnoreMatch");
+        assert_eq!(schema.matching_rule_name("unknownRule"), "unknownRule");
+    }
+
+    #[test]
+    fn schema_get_nameform_by_name() {
+        let mut schema = Schema::new();
+        schema.add_nameform(
+            parse_nameform("( 1.2.3 NAME 'personNameForm' OC person MUST cn )").unwrap(),
+        );
+        assert_eq!(schema.get_nameform("personNameForm").unwrap().oc, "person");
+        assert_eq!(schema.get_nameform("1.2.3").unwrap().oc, "person");
+    }
+
+    #[test]
+    fn check_dangling_syntaxes_detects_missing_syntax() {
+        let mut schema = Schema::new();
+        schema.add_attributetype(
+            parse_attributetype("( 2.5.4.3 NAME 'cn' SYNTAX 1.3.6.1.4.1.1466.115.121.1.15 )")
+                .unwrap(),
+        );
+        let diagnostics = schema.check_dangling_syntaxes();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0].contains("cn"));
+        assert!(diagnostics[0].contains("1.3.6.1.4.1.1466.115.121.1.15"));
+    }
+
+    #[test]
+    fn check_dangling_syntaxes_clean_when_syntax_present() {
+        let mut schema = Schema::new();
+        schema.add_ldapsyntax(parse_ldapsyntax("( 1.3.6.1.4.1.1466.115.121.1.15 )").unwrap());
+        schema.add_attributetype(
+            parse_attributetype("( 2.5.4.3 NAME 'cn' SYNTAX 1.3.6.1.4.1.1466.115.121.1.15 )")
+                .unwrap(),
+        );
+        assert!(schema.check_dangling_syntaxes().is_empty());
+    }
+
+    #[test]
+    fn check_dangling_syntaxes_inherited_via_sup() {
+        let mut schema = Schema::new();
+        schema.add_attributetype(
+            parse_attributetype("( 2.5.4.41 NAME 'name' SYNTAX 1.3.6.1.4.1.1466.115.121.1.15 )")
+                .unwrap(),
+        );
+        schema
+            .add_attributetype(parse_attributetype("( 2.5.4.3 NAME 'cn' SUP name )").unwrap());
+        let diagnostics = schema.check_dangling_syntaxes();
+        // Both 'name' and 'cn' resolve to the same dangling syntax.
+        assert_eq!(diagnostics.len(), 2);
+    }
+
+    // -- Group 14: attribute value syntax validation --
+
+    fn syntax_schema() -> Schema {
+        let mut schema = Schema::new();
+        schema.add_attributetype(
+            parse_attributetype("( 2.5.4.3 NAME 'cn' SYNTAX 1.3.6.1.4.1.1466.115.121.1.15 )")
+                .unwrap(),
+        );
+        schema.add_attributetype(
+            parse_attributetype(
+                "( 1.2.3.1 NAME 'anInt' SYNTAX 1.3.6.1.4.1.1466.115.121.1.27 SINGLE-VALUE )",
+            )
+            .unwrap(),
+        );
+        schema.add_attributetype(
+            parse_attributetype(
+                "( 1.2.3.2 NAME 'aBool' SYNTAX 1.3.6.1.4.1.1466.115.121.1.7 SINGLE-VALUE )",
+            )
+            .unwrap(),
+        );
+        schema.add_attributetype(
+            parse_attributetype(
+                "( 1.2.3.3 NAME 'anIA5' SYNTAX 1.3.6.1.4.1.1466.115.121.1.26 )",
+            )
+            .unwrap(),
+        );
+        schema.add_attributetype(
+            parse_attributetype("( 1.2.3.4 NAME 'aDn' SYNTAX 1.3.6.1.4.1.1466.115.121.1.12 )")
+                .unwrap(),
+        );
+        schema.add_attributetype(
+            parse_attributetype(
+                "( 1.2.3.5 NAME 'aTime' SYNTAX 1.3.6.1.4.1.1466.115.121.1.24 SINGLE-VALUE )",
+            )
+            .unwrap(),
+        );
+        schema.add_attributetype(
+            parse_attributetype("( 1.2.3.6 NAME 'aCert' SYNTAX 1.3.6.1.4.1.1466.115.121.1.15 )")
+                .unwrap(),
+        );
+        schema
+    }
+
+    #[test]
+    fn check_entry_syntax_accepts_well_formed_values() {
+        let schema = syntax_schema();
+        let entry = entry_of(
+            "cn=x,dc=example,dc=com",
+            &[
+                ("cn", &["Jane Doe"]),
+                ("anInt", &["-17"]),
+                ("aBool", &["TRUE"]),
+                ("anIA5", &["plain-ascii"]),
+                ("aDn", &["cn=Jane Doe,dc=example,dc=com"]),
+                ("aTime", &["20260729120000Z"]),
+            ],
+        );
+        let violations = check_entry_syntax(&schema, &entry);
+        assert!(violations.is_empty(), "{:?}", violations);
+    }
+
+    #[test]
+    fn check_entry_syntax_rejects_empty_directory_string() {
+        let schema = syntax_schema();
+        let entry = entry_of("cn=x,dc=example,dc=com", &[("cn", &[""])]);
+        let violations = check_entry_syntax(&schema, &entry);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].0, "cn");
+    }
+
+    #[test]
+    fn check_entry_syntax_rejects_non_ascii_ia5() {
+        let schema = syntax_schema();
+        let entry = entry_of("cn=x,dc=example,dc=com", &[("anIA5", &["café"])]);
+        let violations = check_entry_syntax(&schema, &entry);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].2.contains("IA5String"));
+    }
+
+    #[test]
+    fn check_entry_syntax_rejects_leading_zero_integer() {
+        let schema = syntax_schema();
+        let entry = entry_of("cn=x,dc=example,dc=com", &[("anInt", &["007"])]);
+        let violations = check_entry_syntax(&schema, &entry);
+        assert_eq!(violations.len(), 1);
+    }
+
+    #[test]
+    fn check_entry_syntax_accepts_zero_integer() {
+        let schema = syntax_schema();
+        let entry = entry_of("cn=x,dc=example,dc=com", &[("anInt", &["0"])]);
+        assert!(check_entry_syntax(&schema, &entry).is_empty());
+    }
+
+    #[test]
+    fn check_entry_syntax_rejects_bad_boolean() {
+        let schema = syntax_schema();
+        let entry = entry_of("cn=x,dc=example,dc=com", &[("aBool", &["yes"])]);
+        let violations = check_entry_syntax(&schema, &entry);
+        assert_eq!(violations.len(), 1);
+    }
+
+    #[test]
+    fn check_entry_syntax_rejects_malformed_dn() {
+        let schema = syntax_schema();
+        let entry = entry_of("cn=x,dc=example,dc=com", &[("aDn", &["=bogus"])]);
+        let violations = check_entry_syntax(&schema, &entry);
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].2.contains("invalid DN"));
+    }
+
+    #[test]
+    fn check_entry_syntax_rejects_malformed_generalized_time() {
+        let schema = syntax_schema();
+        let entry = entry_of("cn=x,dc=example,dc=com", &[("aTime", &["2026-07-29"])]);
+        let violations = check_entry_syntax(&schema, &entry);
+        assert_eq!(violations.len(), 1);
+    }
+
+    #[test]
+    fn check_entry_syntax_accepts_generalized_time_with_fraction_and_offset() {
+        let schema = syntax_schema();
+        let entry = entry_of(
+            "cn=x,dc=example,dc=com",
+            &[("aTime", &["20260729120000.5+0100"])],
+        );
+        assert!(check_entry_syntax(&schema, &entry).is_empty());
+    }
+
+    #[test]
+    fn check_entry_syntax_skips_binary_option() {
+        let schema = syntax_schema();
+        let entry = entry_of("cn=x,dc=example,dc=com", &[("aCert;binary", &[""])]);
+        assert!(check_entry_syntax(&schema, &entry).is_empty());
+    }
+
+    #[test]
+    fn check_entry_syntax_skips_unknown_attribute() {
+        let schema = syntax_schema();
+        let entry = entry_of("cn=x,dc=example,dc=com", &[("notInSchema", &["whatever"])]);
+        assert!(check_entry_syntax(&schema, &entry).is_empty());
+    }
+
+    // -- Group 15: matching-rule-aware value comparison --
+
+    fn matching_rule_schema() -> Schema {
+        let mut schema = Schema::new();
+        schema.add_attributetype(
+            parse_attributetype("( 2.5.4.3 NAME 'cn' EQUALITY caseIgnoreMatch )").unwrap(),
+        );
+        schema.add_attributetype(
+            parse_attributetype(
+                "( 1.2.3.1 NAME 'exactAttr' EQUALITY caseExactMatch )",
+            )
+            .unwrap(),
+        );
+        schema.add_attributetype(
+            parse_attributetype(
+                "( 1.2.3.2 NAME 'numAttr' EQUALITY numericStringMatch )",
+            )
+            .unwrap(),
+        );
+        schema.add_attributetype(
+            parse_attributetype(
+                "( 1.2.3.3 NAME 'dnAttr' EQUALITY distinguishedNameMatch )",
+            )
+            .unwrap(),
+        );
+        schema.add_attributetype(
+            parse_attributetype("( 1.2.3.4 NAME 'plainAttr' )").unwrap(),
+        );
+        schema
+    }
+
+    #[test]
+    fn case_ignore_match_folds_case_and_collapses_whitespace() {
+        let schema = matching_rule_schema();
+        assert!(attribute_values_match(
+            &schema,
+            "cn",
+            b"  Jane   Doe ",
+            b"jane doe"
+        ));
+    }
+
+    #[test]
+    fn case_exact_match_rejects_case_difference() {
+        let schema = matching_rule_schema();
+        assert!(!attribute_values_match(
+            &schema,
+            "exactAttr",
+            b"Jane",
+            b"jane"
+        ));
+        assert!(attribute_values_match(
+            &schema,
+            "exactAttr",
+            b" Jane  Doe ",
+            b"Jane Doe"
+        ));
+    }
+
+    #[test]
+    fn numeric_string_match_strips_spaces() {
+        let schema = matching_rule_schema();
+        assert!(attribute_values_match(
+            &schema,
+            "numAttr",
+            b"123 456",
+            b"123456"
+        ));
+    }
+
+    #[test]
+    fn distinguished_name_match_compares_structurally() {
+        let schema = matching_rule_schema();
+        assert!(attribute_values_match(
+            &schema,
+            "dnAttr",
+            b"cn=Jane\\,Doe+sn=Doe,dc=example,dc=com",
+            b"cn=Jane\\,Doe+sn=Doe,dc=example,dc=com"
+        ));
+        assert!(!attribute_values_match(
+            &schema,
+            "dnAttr",
+            b"cn=Jane,dc=example,dc=com",
+            b"cn=Jane,dc=example,dc=net"
+        ));
+    }
+
+    #[test]
+    fn unknown_attribute_falls_back_to_case_exact() {
+        let schema = matching_rule_schema();
+        assert!(attribute_values_match(
+            &schema,
+            "notInSchema",
+            b"foo",
+            b"foo"
+        ));
+        assert!(!attribute_values_match(
+            &schema,
+            "notInSchema",
+            b"foo",
+            b"FOO"
+        ));
+    }
+
+    #[test]
+    fn attribute_with_no_equality_falls_back_to_case_exact() {
+        let schema = matching_rule_schema();
+        assert!(!attribute_values_match(
+            &schema,
+            "plainAttr",
+            b"foo",
+            b"FOO"
+        ));
+    }
+
+    #[test]
+    fn dedup_values_removes_case_insensitive_duplicates() {
+        let schema = matching_rule_schema();
+        let mut values = vec![
+            b"Jane Doe".to_vec(),
+            b"jane  doe".to_vec(),
+            b"Someone Else".to_vec(),
+        ];
+        dedup_values(&schema, "cn", &mut values);
+        assert_eq!(values, vec![b"Jane Doe".to_vec(), b"Someone Else".to_vec()]);
+    }
+
+    #[test]
+    fn find_duplicate_value_reports_case_insensitive_duplicate() {
+        let schema = matching_rule_schema();
+        let values = vec![
+            b"Jane Doe".to_vec(),
+            b"Someone Else".to_vec(),
+            b"jane  doe".to_vec(),
+        ];
+        assert_eq!(find_duplicate_value(&schema, "cn", &values), Some(2));
+    }
+
+    #[test]
+    fn find_duplicate_value_none_when_all_distinct() {
+        let schema = matching_rule_schema();
+        let values = vec![b"Jane Doe".to_vec(), b"Someone Else".to_vec()];
+        assert_eq!(find_duplicate_value(&schema, "cn", &values), None);
+    }
 }
@@ -0,0 +1,241 @@
+//! Minimal mustache-style template rendering, used by `print_templated_entry`
+//! to drive custom export formats (CSV, vCard, JSON lines, HTML reports...)
+//! from a search dump without hard-coding a new format flag per output.
+//!
+//! Supported syntax:
+//! - `{{name}}` -- substitute the first value of `name` (empty if absent).
+//! - `{{#name}}...{{/name}}` -- repeat the enclosed section once per value
+//!   of the (possibly multi-valued) attribute `name`; inside the section,
+//!   `{{.}}` refers to the current value.
+//!
+//! There is no escaping, conditionals, or nested-attribute lookup beyond
+//! this -- just enough structure to expand multi-valued attributes into
+//! repeated rows/cards/lines.
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::error::{LdapviError, Result};
+
+/// The record a template renders against: `dn` plus each attribute name
+/// mapped to its values, built from an `Entry` by the caller.
+pub struct TemplateData {
+    fields: HashMap<String, Vec<String>>,
+}
+
+impl TemplateData {
+    pub fn new() -> Self {
+        TemplateData {
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Bind `name` to a single value (e.g. `dn`).
+    pub fn set(&mut self, name: impl Into<String>, value: String) {
+        self.fields.insert(name.into(), vec![value]);
+    }
+
+    /// Bind `name` to all of its values, preserving order, so a `{{#name}}`
+    /// section expands once per value.
+    pub fn set_values(&mut self, name: impl Into<String>, values: Vec<String>) {
+        self.fields.insert(name.into(), values);
+    }
+
+    fn get(&self, name: &str) -> &[String] {
+        self.fields.get(name).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+impl Default for TemplateData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Node {
+    Text(String),
+    Var(String),
+    Section(String, Vec<Node>),
+}
+
+/// Parse `template` into a node tree, and write an error message naming the
+/// offending tag for a caller to wrap in `LdapviError::User`.
+fn parse(template: &str) -> std::result::Result<Vec<Node>, String> {
+    let mut nodes = Vec::new();
+    let mut stack: Vec<(String, Vec<Node>)> = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let text = &rest[..start];
+        if !text.is_empty() {
+            push_text(&mut stack, &mut nodes, text);
+        }
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| "unterminated {{ tag".to_string())?;
+        let tag = after[..end].trim();
+        rest = &after[end + 2..];
+
+        if let Some(name) = tag.strip_prefix('#') {
+            stack.push((name.trim().to_string(), Vec::new()));
+        } else if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim();
+            let (opened, children) = stack
+                .pop()
+                .ok_or_else(|| format!("{{{{/{}}}}} has no matching {{{{#{}}}}}", name, name))?;
+            if opened != name {
+                return Err(format!(
+                    "{{{{/{}}}}} does not match open section {{{{#{}}}}}",
+                    name, opened
+                ));
+            }
+            let section = Node::Section(opened, children);
+            match stack.last_mut() {
+                Some((_, parent)) => parent.push(section),
+                None => nodes.push(section),
+            }
+        } else {
+            push_var(&mut stack, &mut nodes, tag.to_string());
+        }
+    }
+    if !rest.is_empty() {
+        push_text(&mut stack, &mut nodes, rest);
+    }
+    if let Some((name, _)) = stack.last() {
+        return Err(format!("{{{{#{}}}}} is never closed", name));
+    }
+    Ok(nodes)
+}
+
+fn push_text(stack: &mut [(String, Vec<Node>)], nodes: &mut Vec<Node>, text: &str) {
+    let node = Node::Text(text.to_string());
+    match stack.last_mut() {
+        Some((_, children)) => children.push(node),
+        None => nodes.push(node),
+    }
+}
+
+fn push_var(stack: &mut [(String, Vec<Node>)], nodes: &mut Vec<Node>, name: String) {
+    let node = Node::Var(name);
+    match stack.last_mut() {
+        Some((_, children)) => children.push(node),
+        None => nodes.push(node),
+    }
+}
+
+fn render_nodes(
+    w: &mut dyn Write,
+    nodes: &[Node],
+    data: &TemplateData,
+    current: Option<&str>,
+) -> io::Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => w.write_all(text.as_bytes())?,
+            Node::Var(name) if name == "." => {
+                w.write_all(current.unwrap_or("").as_bytes())?
+            }
+            Node::Var(name) => {
+                if let Some(value) = data.get(name).first() {
+                    w.write_all(value.as_bytes())?
+                }
+            }
+            Node::Section(name, children) => {
+                for value in data.get(name) {
+                    render_nodes(w, children, data, Some(value))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Render `template` against `data`, writing the result to `w`.
+pub fn render(w: &mut dyn Write, template: &str, data: &TemplateData) -> Result<()> {
+    let nodes = parse(template).map_err(LdapviError::User)?;
+    render_nodes(w, &nodes, data, None)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_str(template: &str, data: &TemplateData) -> String {
+        let mut buf = Vec::new();
+        render(&mut buf, template, data).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    // ── Group 1: plain substitution ───────────────────────────────
+
+    #[test]
+    fn substitutes_single_value() {
+        let mut data = TemplateData::new();
+        data.set("cn", "foo".to_string());
+        assert_eq!(render_str("cn={{cn}}", &data), "cn=foo");
+    }
+
+    #[test]
+    fn missing_variable_renders_empty() {
+        let data = TemplateData::new();
+        assert_eq!(render_str("cn={{cn}}", &data), "cn=");
+    }
+
+    #[test]
+    fn text_outside_tags_passes_through() {
+        let data = TemplateData::new();
+        assert_eq!(render_str("hello, world\n", &data), "hello, world\n");
+    }
+
+    // ── Group 2: sections over multi-valued attributes ─────────────
+
+    #[test]
+    fn section_repeats_per_value() {
+        let mut data = TemplateData::new();
+        data.set_values("mail", vec!["a@example.com".to_string(), "b@example.com".to_string()]);
+        let out = render_str("{{#mail}}mail: {{.}}\n{{/mail}}", &data);
+        assert_eq!(out, "mail: a@example.com\nmail: b@example.com\n");
+    }
+
+    #[test]
+    fn section_over_empty_attribute_renders_nothing() {
+        let data = TemplateData::new();
+        let out = render_str("{{#mail}}mail: {{.}}\n{{/mail}}", &data);
+        assert_eq!(out, "");
+    }
+
+    #[test]
+    fn section_can_reference_other_fields() {
+        let mut data = TemplateData::new();
+        data.set("cn", "foo".to_string());
+        data.set_values("mail", vec!["a@example.com".to_string()]);
+        let out = render_str("{{#mail}}{{cn}}: {{.}}\n{{/mail}}", &data);
+        assert_eq!(out, "foo: a@example.com\n");
+    }
+
+    // ── Group 3: malformed templates ───────────────────────────────
+
+    #[test]
+    fn unterminated_tag_is_an_error() {
+        let data = TemplateData::new();
+        let mut buf = Vec::new();
+        assert!(render(&mut buf, "cn={{cn", &data).is_err());
+    }
+
+    #[test]
+    fn mismatched_section_close_is_an_error() {
+        let data = TemplateData::new();
+        let mut buf = Vec::new();
+        assert!(render(&mut buf, "{{#mail}}x{{/phone}}", &data).is_err());
+    }
+
+    #[test]
+    fn unclosed_section_is_an_error() {
+        let data = TemplateData::new();
+        let mut buf = Vec::new();
+        assert!(render(&mut buf, "{{#mail}}x", &data).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! Lossless conversion between the ldapvi and LDIF record syntaxes.
+//!
+//! Built entirely on the existing parser (`LdapviParser`/`LdifParser`, via
+//! the `EntryParser` trait `diff` already uses to treat them uniformly) and
+//! printer (`print::print_ldapvi_*`/`print::print_ldif_*`) functions -- every
+//! value, the per-entry key, and the change-record type (add/modify/rename/
+//! delete) survive the trip, so `transcode(ldapvi -> LDIF -> ldapvi)` is a
+//! fixed point for any input.
+
+use std::io::{Read, Seek, Write};
+
+use crate::diff::EntryParser;
+use crate::error::{LdapviError, Result};
+use crate::parse::LdapviParser;
+use crate::parseldif::LdifParser;
+use crate::print::{self, BinaryMode, DEFAULT_LDIF_WIDTH};
+
+/// Which record syntax a `transcode` endpoint reads or writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ldapvi,
+    Ldif,
+}
+
+/// Read every record from `reader` (in `from` syntax) and re-emit it to
+/// `writer` in `to` syntax. `mode` controls binary-value detection for an
+/// ldapvi-format `writer` (LDIF always uses its own SAFE-STRING rule).
+pub fn transcode(
+    reader: impl Read + Seek,
+    writer: &mut dyn Write,
+    from: Format,
+    to: Format,
+    mode: BinaryMode,
+) -> Result<()> {
+    match from {
+        Format::Ldapvi => transcode_from(&mut LdapviParser::new(reader), writer, to, mode),
+        Format::Ldif => transcode_from(&mut LdifParser::new(reader), writer, to, mode),
+    }
+}
+
+/// True when `err` is the I/O error a writer gets once its downstream
+/// consumer has closed its end of the pipe (e.g. `| head`, or a bulk
+/// loader that exited early after reading as much as it wanted). Streaming
+/// many records out treats that as a clean place to stop, not a failure.
+fn is_broken_pipe(err: &LdapviError) -> bool {
+    matches!(err, LdapviError::Io(e) if e.kind() == std::io::ErrorKind::BrokenPipe)
+}
+
+/// Dispatch on the next record's key exactly as `EntryParser::skip_entry`
+/// does: `modify`/`rename`/`delete` are structured change records, anything
+/// else (`add`, `entry`, a numeric ldapvi-key, ...) is a full attrval entry.
+fn transcode_from(
+    parser: &mut dyn EntryParser,
+    writer: &mut dyn Write,
+    to: Format,
+    mode: BinaryMode,
+) -> Result<()> {
+    while let Some((key, pos)) = parser.peek_entry(None)? {
+        let written = match key.as_str() {
+            "modify" => {
+                let rec = parser.read_modify(Some(pos))?;
+                write_modify(writer, &rec.dn, &rec.mods, to, mode)
+            }
+            "rename" => {
+                let rec = parser.read_rename(Some(pos))?;
+                write_rename(writer, &rec.old_dn, &rec.new_dn, rec.delete_old_rdn, to, mode)
+            }
+            "delete" => {
+                let dn = parser.read_delete(Some(pos))?;
+                write_delete(writer, &dn, to, mode)
+            }
+            _ => {
+                let (key, entry, _) = parser
+                    .read_entry(Some(pos))?
+                    .expect("just-peeked entry must still be readable");
+                write_entry(writer, &entry, Some(&key), to, mode)
+            }
+        };
+        match written {
+            Ok(()) => {}
+            Err(e) if is_broken_pipe(&e) => return Ok(()),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+fn write_entry(
+    w: &mut dyn Write,
+    entry: &crate::data::Entry,
+    key: Option<&str>,
+    to: Format,
+    mode: BinaryMode,
+) -> Result<()> {
+    match to {
+        Format::Ldapvi => print::print_ldapvi_entry(w, entry, key, mode)?,
+        Format::Ldif => print::print_ldif_entry(w, entry, key, DEFAULT_LDIF_WIDTH)?,
+    }
+    Ok(())
+}
+
+fn write_modify(
+    w: &mut dyn Write,
+    dn: &str,
+    mods: &[crate::data::LdapMod],
+    to: Format,
+    mode: BinaryMode,
+) -> Result<()> {
+    match to {
+        Format::Ldapvi => print::print_ldapvi_modify(w, dn, mods, mode)?,
+        Format::Ldif => print::print_ldif_modify(w, dn, mods, DEFAULT_LDIF_WIDTH)?,
+    }
+    Ok(())
+}
+
+fn write_rename(
+    w: &mut dyn Write,
+    old_dn: &str,
+    new_dn: &str,
+    delete_old_rdn: bool,
+    to: Format,
+    mode: BinaryMode,
+) -> Result<()> {
+    match to {
+        Format::Ldapvi => print::print_ldapvi_rename(w, old_dn, new_dn, delete_old_rdn, mode)?,
+        Format::Ldif => {
+            print::print_ldif_rename(w, old_dn, new_dn, delete_old_rdn, DEFAULT_LDIF_WIDTH)?
+        }
+    }
+    Ok(())
+}
+
+fn write_delete(w: &mut dyn Write, dn: &str, to: Format, mode: BinaryMode) -> Result<()> {
+    match to {
+        Format::Ldapvi => print::print_ldapvi_delete(w, dn, mode)?,
+        Format::Ldif => print::print_ldif_delete(w, dn, DEFAULT_LDIF_WIDTH)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn transcode_str(input: &str, from: Format, to: Format) -> String {
+        let mut out = Vec::new();
+        transcode(
+            Cursor::new(input.as_bytes().to_vec()),
+            &mut out,
+            from,
+            to,
+            BinaryMode::Utf8,
+        )
+        .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    // ── Group 1: entry records ─────────────────────────────────────
+
+    #[test]
+    fn ldapvi_entry_to_ldif() {
+        let out = transcode_str(
+            "\nadd cn=foo,dc=example,dc=com\ncn: foo\n",
+            Format::Ldapvi,
+            Format::Ldif,
+        );
+        assert!(out.contains("dn: cn=foo,dc=example,dc=com\n"));
+        assert!(out.contains("ldapvi-key: add\n"));
+        assert!(out.contains("cn: foo\n"));
+    }
+
+    #[test]
+    fn ldif_entry_to_ldapvi() {
+        let out = transcode_str(
+            "dn: cn=foo,dc=example,dc=com\nldapvi-key: add\ncn: foo\n",
+            Format::Ldif,
+            Format::Ldapvi,
+        );
+        assert_eq!(out, "\nadd cn=foo,dc=example,dc=com\ncn: foo\n");
+    }
+
+    // ── Group 2: change records ────────────────────────────────────
+
+    #[test]
+    fn modify_record_transcodes() {
+        let out = transcode_str(
+            "\nmodify cn=foo,dc=example,dc=com\nadd mail\n foo@example.com\n",
+            Format::Ldapvi,
+            Format::Ldif,
+        );
+        assert!(out.contains("changetype: modify\n"));
+        assert!(out.contains("add: mail\n"));
+        assert!(out.contains("mail: foo@example.com\n"));
+    }
+
+    #[test]
+    fn delete_record_transcodes() {
+        let out = transcode_str(
+            "\ndelete cn=foo,dc=example,dc=com\n",
+            Format::Ldapvi,
+            Format::Ldif,
+        );
+        assert!(out.contains("changetype: delete\n"));
+    }
+
+    #[test]
+    fn rename_record_transcodes() {
+        let out = transcode_str(
+            "\nrename cn=old,dc=example,dc=com\nadd cn=new,dc=example,dc=com\n",
+            Format::Ldapvi,
+            Format::Ldif,
+        );
+        assert!(out.contains("changetype: modrdn\n"));
+        assert!(out.contains("newrdn: cn=new\n"));
+        assert!(out.contains("deleteoldrdn: 0\n"));
+    }
+
+    // ── Group 3: fixed point ───────────────────────────────────────
+
+    #[test]
+    fn ldapvi_to_ldif_to_ldapvi_is_a_fixed_point() {
+        let original = "\nadd cn=foo,dc=example,dc=com\ncn: foo\nmail: a@example.com\nmail: b@example.com\n";
+        let ldif = transcode_str(original, Format::Ldapvi, Format::Ldif);
+        let back = transcode_str(&ldif, Format::Ldif, Format::Ldapvi);
+        assert_eq!(back, original);
+    }
+
+    #[test]
+    fn binary_value_survives_the_round_trip() {
+        use crate::parse::LdapviParser;
+
+        let mut original_buf = Vec::new();
+        print::print_ldapvi_entry(
+            &mut original_buf,
+            &{
+                let mut e = crate::data::Entry::new("cn=foo,dc=example,dc=com".to_string());
+                e.find_attribute("jpegPhoto", true)
+                    .unwrap()
+                    .values
+                    .push(vec![0x00, 0x01, 0xff]);
+                e
+            },
+            Some("add"),
+            BinaryMode::Utf8,
+        )
+        .unwrap();
+
+        let mut ldif = Vec::new();
+        transcode(
+            Cursor::new(original_buf.clone()),
+            &mut ldif,
+            Format::Ldapvi,
+            Format::Ldif,
+            BinaryMode::Utf8,
+        )
+        .unwrap();
+
+        let mut back = Vec::new();
+        transcode(
+            Cursor::new(ldif),
+            &mut back,
+            Format::Ldif,
+            Format::Ldapvi,
+            BinaryMode::Utf8,
+        )
+        .unwrap();
+
+        let mut p = LdapviParser::new(Cursor::new(back));
+        let (_, entry, _) = p.read_entry(None).unwrap().unwrap();
+        assert_eq!(
+            entry.get_attribute("jpegPhoto").unwrap().values[0],
+            vec![0x00, 0x01, 0xff]
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! A minimal RFC 3986 URL parser for the `attr:< <url>` value-from-URL
+//! syntax in LDIF (RFC 2849). Just enough of the grammar to split an
+//! absolute URL into scheme, authority/host, percent-decoded path, query,
+//! and fragment -- not a general-purpose URI library: no relative
+//! references, no userinfo, no IPv6-literal validation beyond passing the
+//! bracketed text through untouched.
+//!
+//! [`crate::parseldif`] is the only consumer: [`Url::parse`] turns an
+//! `attr:< <url>` value into a [`Url`], and a `UrlFetcher` keyed by
+//! `scheme` decides how (or whether) to dereference it.
+
+use crate::error::{LdapviError, Result};
+
+/// An absolute URL, per RFC 3986 section 3: `scheme://host/path?query#fragment`.
+/// `path` and `fragment` have already been percent-decoded; `query` is kept
+/// raw since its internal structure (if any) is scheme-specific.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Url {
+    pub scheme: String,
+    pub host: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+impl Url {
+    /// Parse an absolute URL of the form `scheme://[host]/path[?query][#fragment]`.
+    pub fn parse(input: &str) -> Result<Url> {
+        let (scheme, rest) = input
+            .split_once("://")
+            .ok_or_else(|| LdapviError::Other(format!("'{}' is not an absolute URL.", input)))?;
+        if scheme.is_empty()
+            || !scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        {
+            return Err(LdapviError::Other(format!(
+                "'{}' has an invalid URL scheme.",
+                input
+            )));
+        }
+
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((head, frag)) => (head, Some(percent_decode(frag)?)),
+            None => (rest, None),
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((head, q)) => (head, Some(q.to_string())),
+            None => (rest, None),
+        };
+        let (host, path) = match rest.find('/') {
+            Some(idx) => (&rest[..idx], &rest[idx..]),
+            None => (rest, ""),
+        };
+
+        Ok(Url {
+            scheme: scheme.to_ascii_lowercase(),
+            host: host.to_string(),
+            path: percent_decode(path)?,
+            query,
+            fragment,
+        })
+    }
+}
+
+/// Decode `%XX` percent-escapes to raw bytes, then interpret the result as
+/// UTF-8; a malformed escape or bytes that are not valid UTF-8 are an error.
+fn percent_decode(s: &str) -> Result<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .and_then(|h| std::str::from_utf8(h).ok())
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                .ok_or_else(|| {
+                    LdapviError::Other(format!("'{}' has a malformed percent-escape.", s))
+                })?;
+            out.push(hex);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out)
+        .map_err(|_| LdapviError::Other(format!("'{}' decodes to invalid UTF-8.", s)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scheme_host_and_path() {
+        let url = Url::parse("file://example.com/tmp/foo").unwrap();
+        assert_eq!(url.scheme, "file");
+        assert_eq!(url.host, "example.com");
+        assert_eq!(url.path, "/tmp/foo");
+        assert_eq!(url.query, None);
+        assert_eq!(url.fragment, None);
+    }
+
+    #[test]
+    fn empty_host_for_triple_slash_form() {
+        let url = Url::parse("file:///tmp/foo.jpg").unwrap();
+        assert_eq!(url.host, "");
+        assert_eq!(url.path, "/tmp/foo.jpg");
+    }
+
+    #[test]
+    fn scheme_is_lowercased() {
+        let url = Url::parse("FILE:///tmp/foo").unwrap();
+        assert_eq!(url.scheme, "file");
+    }
+
+    #[test]
+    fn path_is_percent_decoded() {
+        let url = Url::parse("file:///tmp/a%20b%2Fc").unwrap();
+        assert_eq!(url.path, "/tmp/a b/c");
+    }
+
+    #[test]
+    fn query_and_fragment_are_split_off() {
+        let url = Url::parse("http://example.com/search?q=a+b#top").unwrap();
+        assert_eq!(url.path, "/search");
+        assert_eq!(url.query.as_deref(), Some("q=a+b"));
+        assert_eq!(url.fragment.as_deref(), Some("top"));
+    }
+
+    #[test]
+    fn fragment_is_percent_decoded() {
+        let url = Url::parse("http://example.com/#a%20b").unwrap();
+        assert_eq!(url.fragment.as_deref(), Some("a b"));
+    }
+
+    #[test]
+    fn no_path_defaults_to_empty_string() {
+        let url = Url::parse("http://example.com").unwrap();
+        assert_eq!(url.host, "example.com");
+        assert_eq!(url.path, "");
+    }
+
+    #[test]
+    fn missing_scheme_separator_is_an_error() {
+        assert!(Url::parse("/tmp/foo").is_err());
+    }
+
+    #[test]
+    fn empty_scheme_is_an_error() {
+        assert!(Url::parse("://tmp/foo").is_err());
+    }
+
+    #[test]
+    fn truncated_percent_escape_is_an_error() {
+        assert!(Url::parse("file:///tmp/a%2").is_err());
+    }
+
+    #[test]
+    fn invalid_percent_hex_digits_are_an_error() {
+        assert!(Url::parse("file:///tmp/a%zz").is_err());
+    }
+}
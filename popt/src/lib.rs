@@ -9,6 +9,9 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
 // ============================================================================
 // Result and Error types
 // ============================================================================
@@ -25,6 +28,11 @@ pub enum Error {
     ConfigFile(String),
     NotFound(String),
     Other(String),
+    AmbiguousOption(String),
+    UnknownOption {
+        given: String,
+        suggestion: Option<String>,
+    },
 }
 
 impl std::fmt::Display for Error {
@@ -38,12 +46,174 @@ impl std::fmt::Display for Error {
             Error::ConfigFile(s) => write!(f, "{}", s),
             Error::NotFound(s) => write!(f, "option not found: {}", s),
             Error::Other(s) => write!(f, "{}", s),
+            Error::AmbiguousOption(s) => write!(f, "{}", s),
+            Error::UnknownOption { given, suggestion } => match suggestion {
+                Some(s) => write!(f, "unknown option {}: did you mean '{}'?", given, s),
+                None => write!(f, "unknown option {}", given),
+            },
         }
     }
 }
 
 impl std::error::Error for Error {}
 
+/// Which kind of failure an [`Error`] represents, independent of its
+/// message text. Used by [`Error::with_description`] to build an error of
+/// a given kind but with an application-supplied message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    BadOption,
+    MissingArg,
+    UnwantedArg,
+    BadNumber,
+    BadQuote,
+    ConfigFile,
+    NotFound,
+    Other,
+    AmbiguousOption,
+    UnknownOption,
+}
+
+/// Whether ANSI color escapes should be emitted, matching the `NO_COLOR`
+/// convention plus an explicit always/never override (see
+/// [`set_color_choice`]); `Auto` is the default and additionally checks
+/// that the target stream is a TTY. Only takes effect when this crate is
+/// built with the `color` feature -- without it, [`colors_enabled`]-gated
+/// output is compiled out entirely and this choice is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[cfg(feature = "color")]
+static COLOR_CHOICE: std::sync::atomic::AtomicU8 = std::sync::atomic::AtomicU8::new(0);
+
+/// Override the auto-detected color policy, e.g. from a `--color=always`
+/// command-line flag. Affects both [`Error::exit`] and help/usage
+/// coloring. A no-op unless built with the `color` feature.
+pub fn set_color_choice(choice: ColorChoice) {
+    #[cfg(feature = "color")]
+    {
+        let v = match choice {
+            ColorChoice::Auto => 0,
+            ColorChoice::Always => 1,
+            ColorChoice::Never => 2,
+        };
+        COLOR_CHOICE.store(v, std::sync::atomic::Ordering::Relaxed);
+    }
+    #[cfg(not(feature = "color"))]
+    let _ = choice;
+}
+
+#[cfg(feature = "color")]
+fn color_choice() -> ColorChoice {
+    match COLOR_CHOICE.load(std::sync::atomic::Ordering::Relaxed) {
+        1 => ColorChoice::Always,
+        2 => ColorChoice::Never,
+        _ => ColorChoice::Auto,
+    }
+}
+
+fn colors_enabled(is_terminal: bool) -> bool {
+    #[cfg(feature = "color")]
+    {
+        match color_choice() {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => std::env::var_os("NO_COLOR").is_none() && is_terminal,
+        }
+    }
+    #[cfg(not(feature = "color"))]
+    {
+        let _ = is_terminal;
+        false
+    }
+}
+
+fn stderr_colors_enabled() -> bool {
+    use std::io::IsTerminal;
+    colors_enabled(std::io::stderr().is_terminal())
+}
+
+fn stdout_colors_enabled() -> bool {
+    use std::io::IsTerminal;
+    colors_enabled(std::io::stdout().is_terminal())
+}
+
+fn bold_red(s: &str) -> String {
+    format!("\x1b[1;31m{}\x1b[0m", s)
+}
+
+fn bold(s: &str) -> String {
+    format!("\x1b[1m{}\x1b[0m", s)
+}
+
+impl Error {
+    /// The [`ErrorKind`] this error represents, independent of its message.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::BadOption(_) => ErrorKind::BadOption,
+            Error::MissingArg(_) => ErrorKind::MissingArg,
+            Error::UnwantedArg(_) => ErrorKind::UnwantedArg,
+            Error::BadNumber(_) => ErrorKind::BadNumber,
+            Error::BadQuote(_) => ErrorKind::BadQuote,
+            Error::ConfigFile(_) => ErrorKind::ConfigFile,
+            Error::NotFound(_) => ErrorKind::NotFound,
+            Error::Other(_) => ErrorKind::Other,
+            Error::AmbiguousOption(_) => ErrorKind::AmbiguousOption,
+            Error::UnknownOption { .. } => ErrorKind::UnknownOption,
+        }
+    }
+
+    /// Build an error of `kind` carrying a caller-supplied `description`
+    /// instead of the message the parser would normally generate -- e.g.
+    /// replacing "unknown option" with a domain-specific message.
+    pub fn with_description(kind: ErrorKind, description: impl Into<String>) -> Error {
+        let description = description.into();
+        match kind {
+            ErrorKind::BadOption => Error::BadOption(description),
+            ErrorKind::MissingArg => Error::MissingArg(description),
+            ErrorKind::UnwantedArg => Error::UnwantedArg(description),
+            ErrorKind::BadNumber => Error::BadNumber(description),
+            ErrorKind::BadQuote => Error::BadQuote(description),
+            ErrorKind::ConfigFile => Error::ConfigFile(description),
+            ErrorKind::NotFound => Error::NotFound(description),
+            ErrorKind::Other => Error::Other(description),
+            ErrorKind::AmbiguousOption => Error::AmbiguousOption(description),
+            ErrorKind::UnknownOption => Error::UnknownOption {
+                given: description,
+                suggestion: None,
+            },
+        }
+    }
+
+    /// Print this error to stderr with an `error:` prefix (bold red when
+    /// colors are enabled, see [`set_color_choice`]/`NO_COLOR`) and exit the
+    /// process: conventional exit code 2 for command-line usage errors
+    /// (bad/unknown/ambiguous options, missing or unwanted arguments), 1
+    /// for everything else (bad numbers, config files, etc).
+    pub fn exit(&self) -> ! {
+        let prefix = "error:";
+        let prefix = if stderr_colors_enabled() {
+            bold_red(prefix)
+        } else {
+            prefix.to_string()
+        };
+        eprintln!("{} {}", prefix, self);
+        let code = match self.kind() {
+            ErrorKind::BadOption
+            | ErrorKind::MissingArg
+            | ErrorKind::UnwantedArg
+            | ErrorKind::AmbiguousOption
+            | ErrorKind::UnknownOption => 2,
+            _ => 1,
+        };
+        std::process::exit(code);
+    }
+}
+
 // ============================================================================
 // ArgType — type-safe argument type enum
 // ============================================================================
@@ -74,6 +244,19 @@ pub enum BitOp {
     Xor,
 }
 
+// ============================================================================
+// Shell — target shell for Context::generate_completion
+// ============================================================================
+
+/// Which shell's completion syntax [`Context::generate_completion`] should
+/// emit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
 // ============================================================================
 // StoredValue — internal typed value storage
 // ============================================================================
@@ -101,6 +284,22 @@ pub enum StoredValue {
 pub type OptionCallback =
     Arc<dyn Fn(Option<&str>, Option<&str>) -> Result<()> + Send + Sync + 'static>;
 
+/// Outcome of a [`TokenRewriteHook`] applied to one raw argument token.
+#[derive(Debug, Clone)]
+pub enum TokenRewrite {
+    /// Pass the token through unmodified.
+    Unchanged,
+    /// Substitute a single token in its place.
+    Replace(String),
+    /// Replace the token with several tokens, pushed as a new parse frame
+    /// (subject to the same recursion-depth guard as alias/exec expansion).
+    Expand(Vec<String>),
+}
+
+/// Called on each raw argument token before alias/exec/option resolution,
+/// with the token and the current parse (alias/exec expansion) depth.
+pub type TokenRewriteHook = Arc<dyn Fn(&str, usize) -> TokenRewrite + Send + Sync + 'static>;
+
 pub struct Opt {
     long_name: String,
     short_name: Option<char>,
@@ -117,6 +316,7 @@ pub struct Opt {
     flags_doc_hidden: bool,
     flags_show_default: bool,
     flags_random: bool,
+    flags_required: bool,
 }
 
 impl Opt {
@@ -137,6 +337,7 @@ impl Opt {
             flags_doc_hidden: false,
             flags_show_default: false,
             flags_random: false,
+            flags_required: false,
         }
     }
 
@@ -158,6 +359,7 @@ impl Opt {
             flags_doc_hidden: false,
             flags_show_default: false,
             flags_random: false,
+            flags_required: false,
         }
     }
 
@@ -221,6 +423,15 @@ impl Opt {
         self
     }
 
+    /// Mark this option as required: [`Context::parse`] returns
+    /// [`Error::MissingArg`] if it was never given on the command line (or
+    /// a config file). Mirrors `getopts::Options::reqopt`; see
+    /// [`OptionTable::reqopt`].
+    pub fn required(mut self) -> Self {
+        self.flags_required = true;
+        self
+    }
+
     pub fn bit_or(mut self) -> Self {
         self.bit_op = Some(BitOp::Or);
         self
@@ -409,6 +620,34 @@ impl FromStoredValue for BloomFilter {
     }
 }
 
+/// Like [`FromStoredValue`], but also given the chance to handle an
+/// absent option, so `Context::get` can dispatch through this instead of
+/// `FromStoredValue` directly. Any `T: FromStoredValue` gets this for
+/// free via the blanket impl below (missing stays a [`Error::NotFound`]);
+/// `Option<T>` overrides it to make "never set" a first-class `Ok(None)`
+/// instead.
+pub trait FromOptionalStoredValue: Sized {
+    fn from_optional_stored_value(name: &str, v: Option<&StoredValue>) -> Result<Self>;
+}
+
+impl<T: FromStoredValue> FromOptionalStoredValue for T {
+    fn from_optional_stored_value(name: &str, v: Option<&StoredValue>) -> Result<Self> {
+        match v {
+            Some(v) => T::from_stored_value(v),
+            None => Err(Error::NotFound(name.to_string())),
+        }
+    }
+}
+
+impl<T: FromStoredValue> FromOptionalStoredValue for Option<T> {
+    fn from_optional_stored_value(_name: &str, v: Option<&StoredValue>) -> Result<Self> {
+        match v {
+            Some(v) => T::from_stored_value(v).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
 // ============================================================================
 // OptionTable
 // ============================================================================
@@ -423,6 +662,57 @@ enum TableEntry {
     },
     AutoHelp,
     AutoAlias,
+    Alias {
+        short_name: Option<char>,
+        long_name: Option<String>,
+        expansion: Vec<String>,
+    },
+    #[cfg(feature = "exec")]
+    Exec {
+        short_name: Option<char>,
+        long_name: Option<String>,
+        argv: Vec<String>,
+    },
+}
+
+/// Validate a getopts-style bare option name pair (no leading dashes),
+/// the same way [`Context::parse_config_line`] validates a `.popt` alias
+/// line's `-x`/`--name` token: a short name is empty or exactly one
+/// printable character, a long name is empty or free of leading dashes
+/// and whitespace. At least one of the two must be given.
+fn validate_opt_names(short: &str, long: &str) -> Result<(Option<char>, Option<String>)> {
+    let short_name = if short.is_empty() {
+        None
+    } else {
+        let mut chars = short.chars();
+        let c = chars.next().unwrap();
+        if chars.next().is_some() || !c.is_ascii_graphic() {
+            return Err(Error::Other(format!(
+                "invalid short option name '{}': must be a single printable character",
+                short
+            )));
+        }
+        Some(c)
+    };
+
+    let long_name = if long.is_empty() {
+        None
+    } else if long.starts_with('-') || long.chars().any(|c| c.is_ascii_whitespace()) {
+        return Err(Error::Other(format!(
+            "invalid long option name '{}': must not start with '-' or contain whitespace",
+            long
+        )));
+    } else {
+        Some(long.to_string())
+    };
+
+    if short_name.is_none() && long_name.is_none() {
+        return Err(Error::Other(
+            "option needs a short or long name".to_string(),
+        ));
+    }
+
+    Ok((short_name, long_name))
 }
 
 pub struct OptionTable {
@@ -482,6 +772,82 @@ impl OptionTable {
         self.entries.push(TableEntry::AutoAlias);
         self
     }
+
+    /// getopts-style boolean flag, e.g. `.optflag("v", "verbose", "be
+    /// verbose")?`. `short` is `""` or a single character; `long` is the
+    /// bare long name (no leading dashes). See [`validate_opt_names`].
+    pub fn optflag(self, short: &str, long: &str, desc: &str) -> Result<Self> {
+        let (short_name, long_name) = validate_opt_names(short, long)?;
+        let long_name = long_name
+            .ok_or_else(|| Error::Other("optflag requires a long option name".to_string()))?;
+        let mut opt = Opt::new(&long_name).description(desc);
+        if let Some(c) = short_name {
+            opt = opt.short(c);
+        }
+        Ok(self.option(opt))
+    }
+
+    /// getopts-style option taking an optional string value, e.g.
+    /// `.optopt("o", "output", "output file", "FILE")?`.
+    pub fn optopt(self, short: &str, long: &str, desc: &str, hint: &str) -> Result<Self> {
+        let (short_name, long_name) = validate_opt_names(short, long)?;
+        let long_name = long_name
+            .ok_or_else(|| Error::Other("optopt requires a long option name".to_string()))?;
+        let mut opt = Opt::new(&long_name)
+            .arg_type(ArgType::String)
+            .description(desc)
+            .arg_description(hint);
+        if let Some(c) = short_name {
+            opt = opt.short(c);
+        }
+        Ok(self.option(opt))
+    }
+
+    /// Like [`Self::optopt`], but the option must be given or
+    /// [`Context::parse`] fails with [`Error::MissingArg`]. Mirrors
+    /// `getopts::Options::reqopt`.
+    pub fn reqopt(self, short: &str, long: &str, desc: &str, hint: &str) -> Result<Self> {
+        let (short_name, long_name) = validate_opt_names(short, long)?;
+        let long_name = long_name
+            .ok_or_else(|| Error::Other("reqopt requires a long option name".to_string()))?;
+        let mut opt = Opt::new(&long_name)
+            .arg_type(ArgType::String)
+            .description(desc)
+            .arg_description(hint)
+            .required();
+        if let Some(c) = short_name {
+            opt = opt.short(c);
+        }
+        Ok(self.option(opt))
+    }
+
+    /// Register an alias programmatically, equivalent to declaring
+    /// `<name> alias -x|--name <expansion...>` in a `.popt` config file
+    /// (see [`Context::parse_config_line`]) but without needing a file. At
+    /// least one of `short`/`long` must be given.
+    pub fn add_alias(mut self, short: &str, long: &str, expansion: Vec<String>) -> Result<Self> {
+        let (short_name, long_name) = validate_opt_names(short, long)?;
+        self.entries.push(TableEntry::Alias {
+            short_name,
+            long_name,
+            expansion,
+        });
+        Ok(self)
+    }
+
+    /// Register an exec alias programmatically, equivalent to declaring
+    /// `<name> exec -x|--name <path> <args...>` in a `.popt` config file.
+    /// At least one of `short`/`long` must be given.
+    #[cfg(feature = "exec")]
+    pub fn add_exec(mut self, short: &str, long: &str, argv: Vec<String>) -> Result<Self> {
+        let (short_name, long_name) = validate_opt_names(short, long)?;
+        self.entries.push(TableEntry::Exec {
+            short_name,
+            long_name,
+            argv,
+        });
+        Ok(self)
+    }
 }
 
 impl Default for OptionTable {
@@ -511,6 +877,7 @@ struct OptionDef {
     flags_doc_hidden: bool,
     flags_show_default: bool,
     _flags_random: bool,
+    flags_required: bool,
     // Which callback index applies to this option (from its table)
     callback_idx: Option<usize>,
 }
@@ -525,6 +892,31 @@ impl OptionDef {
     }
 }
 
+/// Coerce `value` toward the numeric representation `arg_type` declares,
+/// so retrieval isn't limited to whichever `StoredValue` variant happened
+/// to get stored (e.g. a default built from a plain `i32` literal under
+/// an option declared `ArgType::Long`). Returns `None` when `value`
+/// already matches (or `arg_type` has no narrower/wider counterpart),
+/// leaving `FromStoredValue`'s own matching to handle it.
+fn coerce_for_arg_type(value: &StoredValue, arg_type: &ArgType) -> Option<StoredValue> {
+    use StoredValue::*;
+    match (arg_type, value) {
+        (ArgType::Long, Int(n)) => Some(Long(*n as i64)),
+        (ArgType::Long, Short(n)) => Some(Long(*n as i64)),
+        (ArgType::LongLong, Int(n)) => Some(LongLong(*n as i64)),
+        (ArgType::LongLong, Long(n)) => Some(LongLong(*n)),
+        (ArgType::LongLong, Short(n)) => Some(LongLong(*n as i64)),
+        (ArgType::Int, Long(n)) => Some(Int(*n as i32)),
+        (ArgType::Int, LongLong(n)) => Some(Int(*n as i32)),
+        (ArgType::Int, Short(n)) => Some(Int(*n as i32)),
+        (ArgType::Short, Int(n)) => Some(Short(*n as i16)),
+        (ArgType::Short, Long(n)) => Some(Short(*n as i16)),
+        (ArgType::Double, Float(n)) => Some(Double(*n as f64)),
+        (ArgType::Float, Double(n)) => Some(Float(*n as f32)),
+        _ => None,
+    }
+}
+
 #[derive(Clone)]
 struct CallbackDef {
     func: OptionCallback,
@@ -569,6 +961,10 @@ pub struct ContextBuilder {
     #[cfg(feature = "exec")]
     exec_path: Option<(String, bool)>,
     read_default_config: bool,
+    interpolate_env: bool,
+    prompt_missing: bool,
+    allow_abbreviations: bool,
+    token_rewrite: Option<TokenRewriteHook>,
 }
 
 impl ContextBuilder {
@@ -580,6 +976,10 @@ impl ContextBuilder {
             #[cfg(feature = "exec")]
             exec_path: None,
             read_default_config: false,
+            interpolate_env: false,
+            prompt_missing: false,
+            allow_abbreviations: false,
+            token_rewrite: None,
         }
     }
 
@@ -599,8 +999,42 @@ impl ContextBuilder {
         self
     }
 
-    pub fn default_config(mut self, _use_env: bool) -> Self {
+    /// Mark config files as loadable, optionally turning on `$VAR`/`${VAR}`
+    /// environment-variable interpolation inside `key = value` config
+    /// entries (see [`Context::parse`]'s config-file handling).
+    pub fn default_config(mut self, use_env: bool) -> Self {
         self.read_default_config = true;
+        self.interpolate_env = use_env;
+        self
+    }
+
+    /// When a required option (anything but `BitSet`/`Val`/toggle, which
+    /// take no argument) is missing its value and stdin is a TTY, prompt
+    /// for it interactively instead of failing with [`Error::MissingArg`].
+    /// Handy for connection parameters like a bind DN or password that are
+    /// often left off the command line on purpose.
+    pub fn prompt_missing(mut self, enable: bool) -> Self {
+        self.prompt_missing = enable;
+        self
+    }
+
+    /// Let users type any unambiguous prefix of a long option name (GNU
+    /// `getopt_long`-style abbreviation), e.g. `--verb` for `--verbose`
+    /// when no other option shares that prefix. Off by default, since
+    /// abbreviation can silently change meaning if a new option is added
+    /// later and scripts rely on a short prefix that becomes ambiguous.
+    pub fn allow_abbreviations(mut self, enable: bool) -> Self {
+        self.allow_abbreviations = enable;
+        self
+    }
+
+    /// Register a hook run on every raw argument token before alias, exec,
+    /// and option resolution, letting callers implement custom prefixes
+    /// (e.g. mapping `+x`/`-x` to `--x`/`--no-x`), extra interpolation
+    /// beyond the built-in `!#:+` substitution, or shorthand vocabularies
+    /// without forking the core matcher. See [`TokenRewrite`].
+    pub fn token_rewrite_hook(mut self, hook: TokenRewriteHook) -> Self {
+        self.token_rewrite = Some(hook);
         self
     }
 
@@ -615,6 +1049,9 @@ impl ContextBuilder {
         let mut has_auto_help = false;
         let mut has_auto_alias = false;
         let mut table_sections = Vec::new();
+        let mut aliases = Vec::new();
+        #[cfg(feature = "exec")]
+        let mut execs = Vec::new();
         flatten_table(
             &table,
             &mut options,
@@ -622,6 +1059,9 @@ impl ContextBuilder {
             &mut table_sections,
             &mut has_auto_help,
             &mut has_auto_alias,
+            &mut aliases,
+            #[cfg(feature = "exec")]
+            &mut execs,
             None,
             None,
         );
@@ -642,6 +1082,10 @@ impl ContextBuilder {
             #[cfg(feature = "exec")]
             exec_path: self.exec_path,
             _read_default_config: self.read_default_config,
+            interpolate_env: self.interpolate_env,
+            prompt_missing: self.prompt_missing,
+            allow_abbreviations: self.allow_abbreviations,
+            token_rewrite: self.token_rewrite,
             has_auto_help,
             _has_auto_alias: has_auto_alias,
             table_sections,
@@ -649,9 +1093,9 @@ impl ContextBuilder {
             values,
             present: HashSet::new(),
             remaining: Vec::new(),
-            aliases: Vec::new(),
+            aliases,
             #[cfg(feature = "exec")]
-            execs: Vec::new(),
+            execs,
             #[cfg(feature = "exec")]
             exec_av: Vec::new(),
         })
@@ -666,6 +1110,8 @@ fn flatten_table(
     table_sections: &mut Vec<(usize, usize, Option<String>)>,
     has_auto_help: &mut bool,
     has_auto_alias: &mut bool,
+    aliases: &mut Vec<Alias>,
+    #[cfg(feature = "exec")] execs: &mut Vec<ExecAlias>,
     parent_callback_idx: Option<usize>,
     include_description: Option<&str>,
 ) {
@@ -710,6 +1156,7 @@ fn flatten_table(
                     flags_doc_hidden: opt.flags_doc_hidden,
                     flags_show_default: opt.flags_show_default,
                     _flags_random: opt.flags_random,
+                    flags_required: opt.flags_required,
                     callback_idx: current_callback_idx,
                 });
             }
@@ -722,6 +1169,9 @@ fn flatten_table(
                     table_sections,
                     has_auto_help,
                     has_auto_alias,
+                    aliases,
+                    #[cfg(feature = "exec")]
+                    execs,
                     parent_callback_idx,
                     description.as_deref(),
                 );
@@ -748,6 +1198,7 @@ fn flatten_table(
                     flags_doc_hidden: false,
                     flags_show_default: false,
                     _flags_random: false,
+                    flags_required: false,
                     callback_idx: None,
                 });
                 options.push(OptionDef {
@@ -766,6 +1217,7 @@ fn flatten_table(
                     flags_doc_hidden: false,
                     flags_show_default: false,
                     _flags_random: false,
+                    flags_required: false,
                     callback_idx: None,
                 });
                 let end_idx = options.len();
@@ -780,6 +1232,32 @@ fn flatten_table(
                     Some("Options implemented via popt alias/exec:".to_string()),
                 ));
             }
+            TableEntry::Alias {
+                short_name,
+                long_name,
+                expansion,
+            } => {
+                aliases.push(Alias {
+                    short_name: *short_name,
+                    long_name: long_name.clone(),
+                    expansion: expansion.clone(),
+                    description: None,
+                    arg_description: None,
+                    doc_hidden: false,
+                });
+            }
+            #[cfg(feature = "exec")]
+            TableEntry::Exec {
+                short_name,
+                long_name,
+                argv,
+            } => {
+                execs.push(ExecAlias {
+                    short_name: *short_name,
+                    long_name: long_name.clone(),
+                    argv: argv.clone(),
+                });
+            }
         }
     }
 }
@@ -902,6 +1380,13 @@ fn push_exec_av(
 // Help/Usage formatting helpers (free functions)
 // ============================================================================
 
+/// Display width of `s` in terminal columns: the sum of each grapheme
+/// cluster's width, so combining marks (zero width) and CJK characters
+/// (double width) measure correctly instead of `str::len()`'s byte count.
+fn display_width(s: &str) -> usize {
+    s.graphemes(true).map(UnicodeWidthStr::width).sum()
+}
+
 /// Calculate the left column width for an option in help output
 fn calc_option_left_width(opt: &OptionDef) -> usize {
     if opt.flags_doc_hidden {
@@ -911,7 +1396,7 @@ fn calc_option_left_width(opt: &OptionDef) -> usize {
 
     if !opt.long_name.is_empty() {
         len += if opt.flags_onedash { 1 } else { 2 }; // "-" or "--"
-        len += opt.long_name.len();
+        len += display_width(&opt.long_name);
     }
 
     let arg_descrip = Context::get_arg_descrip(opt);
@@ -919,7 +1404,7 @@ fn calc_option_left_width(opt: &OptionDef) -> usize {
         if !ad.starts_with(' ') && !ad.starts_with('=') && !ad.starts_with('(') {
             len += 1; // "="
         }
-        len += ad.len();
+        len += display_width(ad);
     }
 
     if opt.flags_optional {
@@ -936,13 +1421,13 @@ fn calc_alias_left_width(alias: &Alias) -> usize {
     }
     let mut len: usize = 2 + 4; // "  " + "-X, "
     if let Some(ref name) = alias.long_name {
-        len += 2 + name.len(); // "--" + name
+        len += 2 + display_width(name); // "--" + name
     }
     if let Some(ref ad) = alias.arg_description {
         if !ad.starts_with(' ') && !ad.starts_with('=') && !ad.starts_with('(') {
             len += 1; // "="
         }
-        len += ad.len();
+        len += display_width(ad);
     }
     len
 }
@@ -958,7 +1443,7 @@ fn calc_exec_left_width(exec: &ExecAlias) -> usize {
 }
 
 /// Format an alias/exec item for usage output, returning new cursor position
-fn format_item_usage<W: std::io::Write>(
+fn format_item_usage<W: std::fmt::Write>(
     out: &mut W,
     short_name: Option<char>,
     long_name: Option<&str>,
@@ -983,13 +1468,13 @@ fn format_item_usage<W: std::io::Write>(
             len += 1;
         } // "|"
         len += if onedash { 1 } else { 2 }; // "-" or "--"
-        len += long_name.unwrap().len();
+        len += display_width(long_name.unwrap());
     }
     if let Some(ad) = arg_descrip {
         if !ad.starts_with(' ') && !ad.starts_with('=') && !ad.starts_with('(') {
             len += 1; // "="
         }
-        len += ad.len();
+        len += display_width(ad);
     }
 
     let mut cur = cur;
@@ -1018,20 +1503,35 @@ fn format_item_usage<W: std::io::Write>(
     cur + len + 1
 }
 
-/// Word-wrap text at word boundaries with indentation
-fn write_wrapped_text<W: std::io::Write>(
+/// Word-wrap text at word boundaries with indentation, measuring columns by
+/// display width (see [`display_width`]) and breaking at grapheme-cluster
+/// boundaries rather than byte offsets, so multi-byte UTF-8 text (combining
+/// marks, CJK) neither panics nor mismeasures the wrap column.
+fn write_wrapped_text<W: std::fmt::Write>(
     out: &mut W,
     text: &str,
     indent_length: usize,
     line_length: usize,
 ) {
     let mut help = text;
-    while help.len() > line_length {
-        // Find the last space within line_length
-        let search_range = &help[..line_length];
-        let break_pos = match search_range.rfind(' ') {
-            Some(pos) if pos > 0 => pos,
-            _ => break, // give up if no space found
+    while display_width(help) > line_length {
+        // Walk graphemes accumulating width, remembering the last word
+        // boundary (a space) seen within the budget.
+        let mut width = 0;
+        let mut break_pos = None;
+        for (byte_pos, g) in help.grapheme_indices(true) {
+            width += UnicodeWidthStr::width(g);
+            if width > line_length {
+                break;
+            }
+            if g == " " && byte_pos > 0 {
+                break_pos = Some(byte_pos);
+            }
+        }
+
+        let break_pos = match break_pos {
+            Some(pos) => pos,
+            None => break, // give up if no space found within the budget
         };
 
         // Print up to the break point
@@ -1053,6 +1553,93 @@ fn write_wrapped_text<W: std::io::Write>(
     }
 }
 
+static WIDTH_OVERRIDE: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+/// Force [`detect_terminal_width`] to a fixed value regardless of `COLUMNS`
+/// or the TTY probe, or pass `None` to resume auto-detection. Useful for
+/// deterministic help-text tests, or to pick a very large width to disable
+/// wrapping entirely (e.g. when piping `--help` output to a file). Mirrors
+/// [`set_color_choice`]'s global-override pattern.
+pub fn set_terminal_width_override(width: Option<usize>) {
+    let v = width.map(|w| w.saturating_add(1)).unwrap_or(0);
+    WIDTH_OVERRIDE.store(v, std::sync::atomic::Ordering::Relaxed);
+}
+
+fn terminal_width_override() -> Option<usize> {
+    match WIDTH_OVERRIDE.load(std::sync::atomic::Ordering::Relaxed) {
+        0 => None,
+        v => Some(v - 1),
+    }
+}
+
+/// Detect the output width to wrap help/usage text to: an explicit
+/// [`set_terminal_width_override`] wins first, then `COLUMNS` if set and
+/// parses to a positive number, otherwise an ioctl `TIOCGWINSZ` probe of
+/// stdout's terminal size is used when stdout is a TTY, and `None` means the
+/// caller should fall back to its own fixed width (e.g. when piping to a
+/// file).
+fn detect_terminal_width() -> Option<usize> {
+    if let Some(w) = terminal_width_override() {
+        return Some(w);
+    }
+    if let Ok(cols) = std::env::var("COLUMNS") {
+        if let Ok(n) = cols.trim().parse::<usize>() {
+            if n > 0 {
+                return Some(n);
+            }
+        }
+    }
+
+    use std::io::IsTerminal;
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    unsafe {
+        let mut ws: libc::winsize = std::mem::zeroed();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut ws) == 0 && ws.ws_col > 0 {
+            return Some(ws.ws_col as usize);
+        }
+    }
+    None
+}
+
+/// Damerau-Levenshtein edit distance between `a` and `b` (insertions,
+/// deletions, substitutions, and adjacent transpositions), used to suggest
+/// the nearest known option name for a typo'd one. Transpositions let
+/// `--revrese` match `--reverse` at distance 1 instead of 2.
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a_chars: Vec<char> = a.chars().collect();
+    let b_chars: Vec<char> = b.chars().collect();
+    let (alen, blen) = (a_chars.len(), b_chars.len());
+
+    let mut d = vec![vec![0usize; blen + 1]; alen + 1];
+    for i in 0..=alen {
+        d[i][0] = i;
+    }
+    for j in 0..=blen {
+        d[0][j] = j;
+    }
+
+    for i in 1..=alen {
+        for j in 1..=blen {
+            let cost = if a_chars[i - 1] == b_chars[j - 1] { 0 } else { 1 };
+            d[i][j] = std::cmp::min(
+                std::cmp::min(d[i - 1][j] + 1, d[i][j - 1] + 1),
+                d[i - 1][j - 1] + cost,
+            );
+            if i > 1
+                && j > 1
+                && a_chars[i - 1] == b_chars[j - 2]
+                && a_chars[i - 2] == b_chars[j - 1]
+            {
+                d[i][j] = std::cmp::min(d[i][j], d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[alen][blen]
+}
+
 /// Mimic C's %g format for a double value
 fn c_format_g(v: f64) -> String {
     if v == 0.0 {
@@ -1091,6 +1678,10 @@ pub struct Context {
     #[cfg(feature = "exec")]
     exec_path: Option<(String, bool)>,
     _read_default_config: bool,
+    interpolate_env: bool,
+    prompt_missing: bool,
+    allow_abbreviations: bool,
+    token_rewrite: Option<TokenRewriteHook>,
     has_auto_help: bool,
     _has_auto_alias: bool,
     table_sections: Vec<(usize, usize, Option<String>)>, // (start, end, description)
@@ -1112,25 +1703,55 @@ impl Context {
     }
 
     pub fn parse(&mut self) -> Result<()> {
-        // Load config files for aliases
-        for cfg in &self.config_files.clone() {
-            let _ = self.load_config_file(cfg);
-        }
-
-        // Get argv
         let argv: Vec<String> = std::env::args().collect();
         let args: Vec<String> = if argv.len() > 1 {
             argv[1..].to_vec()
         } else {
             vec![]
         };
+        self.parse_args(args)
+    }
+
+    /// Like [`Context::parse`], but take the argument vector (already
+    /// stripped of `argv[0]`) from the caller instead of `std::env::args()`.
+    /// Lets a caller splice in its own tokens -- e.g. an application-level
+    /// alias expansion -- before popt ever sees them.
+    pub fn parse_args(&mut self, args: Vec<String>) -> Result<()> {
+        // Load config files for aliases and typed `key = value` entries
+        for cfg in &self.config_files.clone() {
+            self.load_config_file(cfg)?;
+        }
 
         // Check for POSIXLY_CORRECT
         let posixly_correct =
             std::env::var("POSIXLY_CORRECT").is_ok() || std::env::var("POSIX_ME_HARDER").is_ok();
 
         // Parse using option stack
-        self.parse_with_stack(args, posixly_correct)
+        self.parse_with_stack(args, posixly_correct)?;
+
+        // Enforce options registered via `.required()` / `OptionTable::reqopt`
+        self.check_required_options()
+    }
+
+    /// Return [`Error::MissingArg`] naming the first `.required()` option
+    /// (see [`Opt::required`]) that was never given, if any.
+    fn check_required_options(&self) -> Result<()> {
+        for opt in &self.options {
+            if opt.flags_required && !self.present.contains(&opt.long_name) {
+                let name = if !opt.long_name.is_empty() {
+                    format!("--{}", opt.long_name)
+                } else if let Some(c) = opt.short_name {
+                    format!("-{}", c)
+                } else {
+                    continue;
+                };
+                return Err(Error::MissingArg(format!(
+                    "{}: option {} is required",
+                    self.name, name
+                )));
+            }
+        }
+        Ok(())
     }
 
     fn parse_with_stack(&mut self, args: Vec<String>, posixly_correct: bool) -> Result<()> {
@@ -1247,7 +1868,8 @@ impl Context {
                             Some(&val),
                         );
                     } else {
-                        let value = consume_next_value(&self.name, &mut stack, &opt_name, false)?;
+                        let value =
+                            self.consume_value_or_prompt(&mut stack, opt_idx, &opt_name, false)?;
                         if let Some(val) = &value {
                             let val = expand_next_arg(val, &mut stack);
                             self.store_option(opt_idx, Some(&val), false, true)?;
@@ -1297,6 +1919,54 @@ impl Context {
             let arg = stack[depth].args[stack[depth].next].clone();
             stack[depth].next += 1;
 
+            // Run the opt-in token-rewrite hook before any alias/exec/option
+            // resolution. Rewritten tokens are pushed as a new frame so they
+            // flow back through short/long detection and `expand_next_arg`
+            // exactly like an alias expansion, reusing its depth guard.
+            if !rest_leftover {
+                if let Some(hook) = self.token_rewrite.clone() {
+                    let rewritten = hook(&arg, depth);
+                    match rewritten {
+                        TokenRewrite::Unchanged => {}
+                        TokenRewrite::Replace(new_arg) => {
+                            if stack.len() >= 10 {
+                                return Err(Error::Other(
+                                    "token rewrite expansion too deep".to_string(),
+                                ));
+                            }
+                            stack.push(ParseFrame {
+                                args: vec![new_arg],
+                                next: 0,
+                                consumed: HashSet::new(),
+                                next_char_arg: None,
+                                curr_alias_long: None,
+                                curr_alias_short: None,
+                            });
+                            continue;
+                        }
+                        TokenRewrite::Expand(tokens) => {
+                            if tokens.is_empty() {
+                                continue;
+                            }
+                            if stack.len() >= 10 {
+                                return Err(Error::Other(
+                                    "token rewrite expansion too deep".to_string(),
+                                ));
+                            }
+                            stack.push(ParseFrame {
+                                args: tokens,
+                                next: 0,
+                                consumed: HashSet::new(),
+                                next_char_arg: None,
+                                curr_alias_long: None,
+                                curr_alias_short: None,
+                            });
+                            continue;
+                        }
+                    }
+                }
+            }
+
             // Handle rest_leftover and positional args
             if rest_leftover {
                 self.remaining.push(arg);
@@ -1341,6 +2011,16 @@ impl Context {
                     (name.to_string(), false)
                 };
 
+                // GNU-style unambiguous prefix abbreviation (opt-in)
+                let actual_name = if self.allow_abbreviations && !self.has_exact_long_name(&actual_name) {
+                    match self.resolve_long_abbreviation(&actual_name)? {
+                        Some(resolved) => resolved,
+                        None => actual_name,
+                    }
+                } else {
+                    actual_name
+                };
+
                 // Check alias (recursion detection)
                 let is_curr_alias = stack[depth].curr_alias_long.as_deref() == Some(&actual_name);
                 if !is_curr_alias {
@@ -1389,10 +2069,12 @@ impl Context {
                 let opt_idx = match self.find_option_idx_by_long(&actual_name) {
                     Some(idx) => idx,
                     None => {
-                        return Err(Error::BadOption(format!(
-                            "{}: bad argument {}: unknown option",
-                            self.name, arg
-                        )));
+                        return Err(Error::UnknownOption {
+                            given: arg.clone(),
+                            suggestion: self
+                                .suggest_long_option(&actual_name)
+                                .map(|n| format!("--{}", n)),
+                        });
                     }
                 };
 
@@ -1416,8 +2098,12 @@ impl Context {
                     if let Some(v) = long_arg {
                         Some(expand_next_arg(v, &mut stack))
                     } else {
-                        let value =
-                            consume_next_value(&self.name, &mut stack, &opt_name, is_optional)?;
+                        let value = self.consume_value_or_prompt(
+                            &mut stack,
+                            opt_idx,
+                            &opt_name,
+                            is_optional,
+                        )?;
                         value.map(|v| expand_next_arg(&v, &mut stack))
                     }
                 } else {
@@ -1462,7 +2148,7 @@ impl Context {
                         if let Some(v) = onedash_val {
                             Some(v.to_string())
                         } else {
-                            consume_next_value(&self.name, &mut stack, &opt_name, false)?
+                            self.consume_value_or_prompt(&mut stack, idx, &opt_name, false)?
                         }
                     } else {
                         None
@@ -1481,6 +2167,30 @@ impl Context {
                     continue;
                 }
 
+                // A whole word passed with a single dash (e.g. `-verbose`
+                // for `--verbose`) fails the short-cluster parse on its
+                // very first character; that's a stronger signal of a
+                // missing dash than a genuine typo'd short flag, so check
+                // for a close long-option match before falling through to
+                // character-by-character short option processing.
+                let first_char = after_dash.chars().next().unwrap();
+                #[cfg(feature = "exec")]
+                let first_char_is_exec = self.find_exec_by_short(first_char).is_some();
+                #[cfg(not(feature = "exec"))]
+                let first_char_is_exec = false;
+                if after_dash.chars().count() > 1
+                    && self.find_option_idx_by_short(first_char).is_none()
+                    && self.find_alias_by_short(first_char).is_none()
+                    && !first_char_is_exec
+                {
+                    if let Some(suggestion) = self.suggest_long_option(after_dash) {
+                        return Err(Error::UnknownOption {
+                            given: arg.clone(),
+                            suggestion: Some(format!("--{}", suggestion)),
+                        });
+                    }
+                }
+
                 // Set up short option processing via next_char_arg
                 stack[depth].next_char_arg = Some(after_dash.to_string());
             } else {
@@ -1530,6 +2240,86 @@ impl Context {
         Ok(())
     }
 
+    /// Consume the next argument value for a required option, falling back
+    /// to an interactive prompt (see [`ContextBuilder::prompt_missing`])
+    /// instead of propagating [`Error::MissingArg`] when that's enabled and
+    /// applicable.
+    fn consume_value_or_prompt(
+        &mut self,
+        stack: &mut Vec<ParseFrame>,
+        opt_idx: usize,
+        opt_name: &str,
+        is_optional: bool,
+    ) -> Result<Option<String>> {
+        match consume_next_value(&self.name, stack, opt_name, is_optional) {
+            Err(Error::MissingArg(_)) if self.should_prompt_for(opt_idx) => {
+                self.prompt_for_value(opt_idx).map(Some)
+            }
+            other => other,
+        }
+    }
+
+    /// Whether a missing value for `opt_idx` should be prompted for rather
+    /// than reported as [`Error::MissingArg`].
+    fn should_prompt_for(&self, opt_idx: usize) -> bool {
+        if !self.prompt_missing {
+            return false;
+        }
+        let opt = &self.options[opt_idx];
+        if !opt.takes_arg() || opt.flags_toggle {
+            return false;
+        }
+        if matches!(opt.arg_type, ArgType::Val(_) | ArgType::BitSet) {
+            return false;
+        }
+        use std::io::IsTerminal;
+        std::io::stdin().is_terminal()
+    }
+
+    /// Interactively read a value for `opt_idx` from stdin. A line ending in
+    /// a trailing `\` continues onto the next line (the backslash is
+    /// dropped, the lines joined with a newline) so a quoted value can be
+    /// typed across several lines; the joined text is then re-tokenized with
+    /// [`parse_argv_string`], the same as a config file or alias expansion,
+    /// so quoting rules match the rest of the option parser.
+    fn prompt_for_value(&self, opt_idx: usize) -> Result<String> {
+        use std::io::{BufRead, Write};
+
+        let opt = &self.options[opt_idx];
+        let label = opt.arg_description.as_deref().unwrap_or(&opt.long_name);
+        let stdin = std::io::stdin();
+        let mut joined = String::new();
+        loop {
+            eprint!("{}: ", label);
+            std::io::stderr().flush().ok();
+            let mut chunk = String::new();
+            let read = stdin
+                .lock()
+                .read_line(&mut chunk)
+                .map_err(|e| Error::Other(format!("error reading from stdin: {}", e)))?;
+            if read == 0 {
+                return Err(Error::MissingArg(format!(
+                    "{}: bad argument --{}: missing argument",
+                    self.name, opt.long_name
+                )));
+            }
+            let chunk = chunk.trim_end_matches(['\r', '\n']);
+            match chunk.strip_suffix('\\') {
+                Some(prefix) => {
+                    joined.push_str(prefix);
+                    joined.push('\n');
+                }
+                None => {
+                    joined.push_str(chunk);
+                    break;
+                }
+            }
+        }
+
+        let tokens = parse_argv_string(&joined)?;
+        Ok(tokens.join(" "))
+    }
+
     /// Store a parsed option value
     fn store_option(
         &mut self,
@@ -1550,6 +2340,16 @@ impl Context {
             }
         }
 
+        // Numeric parse failures below carry their own bold-red "error: "
+        // prefix so they read the same whether the caller routes them
+        // through `Error::exit` or prints them directly; empty when colors
+        // are disabled or the `color` feature is off.
+        let err_prefix = if stderr_colors_enabled() {
+            format!("{} ", bold_red("error:"))
+        } else {
+            String::new()
+        };
+
         match &opt.arg_type {
             ArgType::None => {
                 if negated {
@@ -1567,8 +2367,8 @@ impl Context {
                 if let Some(val) = value_str {
                     let n: i32 = val.parse().map_err(|_| {
                         Error::BadNumber(format!(
-                            "{}: bad argument --{}: invalid numeric value",
-                            self.name, opt.long_name
+                            "{}{}: bad argument --{}: invalid numeric value",
+                            err_prefix, self.name, opt.long_name
                         ))
                     })?;
                     self.values.insert(key, StoredValue::Int(n));
@@ -1578,8 +2378,8 @@ impl Context {
                 if let Some(val) = value_str {
                     let n: i64 = val.parse().map_err(|_| {
                         Error::BadNumber(format!(
-                            "{}: bad argument --{}: invalid numeric value",
-                            self.name, opt.long_name
+                            "{}{}: bad argument --{}: invalid numeric value",
+                            err_prefix, self.name, opt.long_name
                         ))
                     })?;
                     self.values.insert(key, StoredValue::Long(n));
@@ -1589,8 +2389,8 @@ impl Context {
                 if let Some(val) = value_str {
                     let n: i64 = val.parse().map_err(|_| {
                         Error::BadNumber(format!(
-                            "{}: bad argument --{}: invalid numeric value",
-                            self.name, opt.long_name
+                            "{}{}: bad argument --{}: invalid numeric value",
+                            err_prefix, self.name, opt.long_name
                         ))
                     })?;
                     self.values.insert(key, StoredValue::LongLong(n));
@@ -1600,8 +2400,8 @@ impl Context {
                 if let Some(val) = value_str {
                     let n: i16 = val.parse().map_err(|_| {
                         Error::BadNumber(format!(
-                            "{}: bad argument --{}: invalid numeric value",
-                            self.name, opt.long_name
+                            "{}{}: bad argument --{}: invalid numeric value",
+                            err_prefix, self.name, opt.long_name
                         ))
                     })?;
                     self.values.insert(key, StoredValue::Short(n));
@@ -1611,8 +2411,8 @@ impl Context {
                 if let Some(val) = value_str {
                     let n: f32 = val.parse().map_err(|_| {
                         Error::BadNumber(format!(
-                            "{}: bad argument --{}: invalid numeric value",
-                            self.name, opt.long_name
+                            "{}{}: bad argument --{}: invalid numeric value",
+                            err_prefix, self.name, opt.long_name
                         ))
                     })?;
                     self.values.insert(key, StoredValue::Float(n));
@@ -1622,8 +2422,8 @@ impl Context {
                 if let Some(val) = value_str {
                     let n: f64 = val.parse().map_err(|_| {
                         Error::BadNumber(format!(
-                            "{}: bad argument --{}: invalid numeric value",
-                            self.name, opt.long_name
+                            "{}{}: bad argument --{}: invalid numeric value",
+                            err_prefix, self.name, opt.long_name
                         ))
                     })?;
                     self.values.insert(key, StoredValue::Double(n));
@@ -1690,10 +2490,128 @@ impl Context {
         self.options.iter().find(|o| o.long_name == name)
     }
 
+    fn find_option_by_storage_key(&self, name: &str) -> Option<&OptionDef> {
+        self.options.iter().find(|o| o.storage_key() == name)
+    }
+
+    fn find_option_idx_by_storage_key(&self, name: &str) -> Option<usize> {
+        self.options.iter().position(|o| o.storage_key() == name)
+    }
+
     fn find_option_idx_by_long(&self, name: &str) -> Option<usize> {
         self.options.iter().position(|o| o.long_name == name)
     }
 
+    /// The single closest registered long option/alias name to `name`, for
+    /// an `Error::UnknownOption` suggestion, or `None` if nothing is close
+    /// enough (edit distance at most `max(name.len() / 3, 1)`).
+    fn suggest_long_option(&self, name: &str) -> Option<String> {
+        self.suggest_long_options(name).into_iter().next()
+    }
+
+    /// Up to three long option/alias names near `name` by edit distance,
+    /// best first. A lone best match is kept on its own; when several
+    /// candidates are within a Damerau-Levenshtein distance of 1 of each
+    /// other, all (up to three) are returned so the caller can list them.
+    fn suggest_long_options(&self, name: &str) -> Vec<String> {
+        let max_dist = std::cmp::max(name.len() / 3, 1);
+
+        let mut scored: Vec<(usize, String)> = self
+            .options
+            .iter()
+            .map(|o| &o.long_name)
+            .chain(self.aliases.iter().filter_map(|a| a.long_name.as_ref()))
+            .filter(|n| !n.is_empty())
+            .map(|n| (levenshtein_distance(name, n), n.clone()))
+            .filter(|(d, _)| *d <= max_dist)
+            .collect();
+
+        scored.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.len().cmp(&b.1.len())));
+        scored.dedup_by(|a, b| a.1 == b.1);
+
+        let best = match scored.first() {
+            Some((d, _)) => *d,
+            None => return Vec::new(),
+        };
+        if best > 1 {
+            return vec![scored.remove(0).1];
+        }
+        scored
+            .into_iter()
+            .take_while(|(d, _)| *d <= 1)
+            .take(3)
+            .map(|(_, n)| n)
+            .collect()
+    }
+
+    /// Whether `name` is already a full, exact long option/alias/exec name
+    /// (or "help"/"usage" under auto-help). Abbreviation resolution only
+    /// kicks in when this is false, so an option whose name happens to be
+    /// a prefix of another's is never second-guessed.
+    fn has_exact_long_name(&self, name: &str) -> bool {
+        if (name == "help" || name == "usage") && self.has_auto_help {
+            return true;
+        }
+        if self.options.iter().any(|o| o.long_name == name) {
+            return true;
+        }
+        if self.aliases.iter().any(|a| a.long_name.as_deref() == Some(name)) {
+            return true;
+        }
+        #[cfg(feature = "exec")]
+        if self.execs.iter().any(|e| e.long_name.as_deref() == Some(name)) {
+            return true;
+        }
+        false
+    }
+
+    /// Resolve `prefix` to the single long option/alias/exec name it
+    /// unambiguously abbreviates (GNU `getopt_long`-style). Returns
+    /// `Ok(None)` when nothing matches, so the caller falls through to the
+    /// ordinary unknown-option error, and `Err(Error::AmbiguousOption(_))`
+    /// when more than one candidate matches.
+    fn resolve_long_abbreviation(&self, prefix: &str) -> Result<Option<String>> {
+        if prefix.is_empty() {
+            return Ok(None);
+        }
+
+        let mut candidates: Vec<String> = self
+            .options
+            .iter()
+            .map(|o| o.long_name.clone())
+            .chain(self.aliases.iter().filter_map(|a| a.long_name.clone()))
+            .filter(|n| !n.is_empty())
+            .collect();
+
+        #[cfg(feature = "exec")]
+        candidates.extend(self.execs.iter().filter_map(|e| e.long_name.clone()));
+
+        if self.has_auto_help {
+            candidates.push("help".to_string());
+            candidates.push("usage".to_string());
+        }
+
+        candidates.retain(|n| n.starts_with(prefix));
+        candidates.sort();
+        candidates.dedup();
+
+        match candidates.len() {
+            0 => Ok(None),
+            1 => Ok(Some(candidates.remove(0))),
+            _ => {
+                let list = candidates
+                    .iter()
+                    .map(|c| format!("--{}", c))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                Err(Error::AmbiguousOption(format!(
+                    "{}: option '--{}' is ambiguous; could be {}",
+                    self.name, prefix, list
+                )))
+            }
+        }
+    }
+
     fn find_option_idx_by_short(&self, c: char) -> Option<usize> {
         self.options.iter().position(|o| o.short_name == Some(c))
     }
@@ -1720,10 +2638,29 @@ impl Context {
         self.execs.iter().position(|e| e.short_name == Some(c))
     }
 
+    /// Load one config file. Besides the popt-style `appname alias/exec ...`
+    /// lines handled by [`Context::parse_config_line`], a file may carry
+    /// `[name]`-bracketed sections of plain `key = value` entries; entries
+    /// under the section matching [`Context::name`] are converted through
+    /// the same per-`ArgType` logic as a command-line value (see
+    /// [`Context::store_option`]) and stored without touching `present`, so
+    /// `present` still reflects only what was actually given on the command
+    /// line. Values optionally go through `$VAR`/`${VAR}` interpolation,
+    /// per [`ContextBuilder::default_config`].
     fn load_config_file(&mut self, path: &str) -> Result<()> {
         let content = match std::fs::read_to_string(path) {
             Ok(c) => c,
-            Err(_) => return Ok(()), // silently ignore missing files
+            // A missing config file is expected (e.g. an optional
+            // per-user rc file) and silently ignored; anything else --
+            // permission denied, a directory where a file was expected,
+            // etc. -- is a real failure the caller should see.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => {
+                return Err(Error::with_description(
+                    ErrorKind::ConfigFile,
+                    format!("{}: cannot read config file {}: {}", self.name, path, e),
+                ));
+            }
         };
 
         // Handle \ line continuations
@@ -1739,16 +2676,45 @@ impl Context {
             joined.push(c);
         }
 
+        let mut section: Option<String> = None;
         for line in joined.lines() {
             let l = line.trim();
             if l.is_empty() || l.starts_with('#') {
                 continue;
             }
-            self.parse_config_line(l);
-        }
-        Ok(())
-    }
-
+            if let Some(name) = l.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                section = Some(name.trim().to_string());
+                continue;
+            }
+            if section.as_deref() == Some(self.name.as_str()) {
+                if let Some((key, value)) = l.split_once('=') {
+                    let key = key.trim();
+                    if !key.is_empty() && !key.chars().any(|c| c.is_ascii_whitespace()) {
+                        self.apply_config_entry(key, value.trim())?;
+                        continue;
+                    }
+                }
+            }
+            self.parse_config_line(l);
+        }
+        Ok(())
+    }
+
+    /// Apply one `key = value` config entry: interpolate env vars if
+    /// enabled, then store it exactly as [`Context::store_option`] would
+    /// store a command-line value, without marking it `present`.
+    fn apply_config_entry(&mut self, key: &str, raw_value: &str) -> Result<()> {
+        let value = if self.interpolate_env {
+            interpolate_env_vars(raw_value)?
+        } else {
+            raw_value.to_string()
+        };
+        if let Some(opt_idx) = self.find_option_idx_by_storage_key(key) {
+            self.store_option(opt_idx, Some(&value), false, true)?;
+        }
+        Ok(())
+    }
+
     fn parse_config_line(&mut self, line: &str) {
         let mut parts = line.splitn(4, |c: char| c.is_ascii_whitespace());
 
@@ -1876,11 +2842,19 @@ impl Context {
         }
     }
 
+    /// Print the full `--help` listing to stdout. A thin wrapper around
+    /// [`Self::render_help`].
     pub fn print_help(&self) {
-        use std::io::Write;
-        let stdout = std::io::stdout();
-        let mut out = stdout.lock();
-        let max_col_width: usize = 79;
+        print!("{}", self.render_help());
+    }
+
+    /// Render the full `--help` listing (header, options, sections) as a
+    /// string, for callers that want to capture or post-process it instead
+    /// of writing straight to stdout. See [`Self::print_help`].
+    pub fn render_help(&self) -> String {
+        use std::fmt::Write;
+        let mut out = String::new();
+        let max_col_width: usize = detect_terminal_width().unwrap_or(79);
 
         // Print header
         let _ = write!(out, "Usage: {} [OPTION...]\n", self.name);
@@ -1929,6 +2903,7 @@ impl Context {
 
             // Print section header
             if let Some(desc) = description {
+                let desc = if stdout_colors_enabled() { bold(desc) } else { desc.clone() };
                 let _ = write!(out, "\n{}\n", desc);
             }
 
@@ -1962,13 +2937,22 @@ impl Context {
                 }
             }
         }
+
+        out
     }
 
+    /// Print the one-line `--usage` summary to stdout. A thin wrapper
+    /// around [`Self::render_usage`].
     fn print_usage(&self) {
-        use std::io::Write;
-        let stdout = std::io::stdout();
-        let mut out = stdout.lock();
-        let max_col: usize = 79;
+        print!("{}", self.render_usage());
+    }
+
+    /// Render the one-line `--usage` summary as a string. See
+    /// [`Self::print_usage`].
+    fn render_usage(&self) -> String {
+        use std::fmt::Write;
+        let mut out = String::new();
+        let max_col: usize = detect_terminal_width().unwrap_or(79);
 
         // Print "Usage: test1"
         let intro = format!("Usage: {}", self.name);
@@ -2016,6 +3000,7 @@ impl Context {
         // Exec aliases are always DOC_HIDDEN in C popt, don't show in usage
 
         let _ = write!(out, "\n");
+        out
     }
 
     /// Collect short options (NONE type only, not doc_hidden) for the [-abc] group
@@ -2100,7 +3085,7 @@ impl Context {
     }
 
     /// Format a single option for the usage line, returning the new cursor position
-    fn format_option_usage<W: std::io::Write>(
+    fn format_option_usage<W: std::fmt::Write>(
         &self,
         out: &mut W,
         opt: &OptionDef,
@@ -2128,13 +3113,13 @@ impl Context {
                 len += 1;
             } // "|"
             len += if opt.flags_onedash { 1 } else { 2 }; // "-" or "--"
-            len += opt.long_name.len();
+            len += display_width(&opt.long_name);
         }
         if let Some(ref ad) = arg_descrip {
             if !ad.starts_with(' ') && !ad.starts_with('=') && !ad.starts_with('(') {
                 len += 1; // "="
             }
-            len += ad.len();
+            len += display_width(ad);
         }
 
         let mut cur = cur;
@@ -2164,7 +3149,7 @@ impl Context {
     }
 
     /// Format a single option for the help display
-    fn format_option_help<W: std::io::Write>(
+    fn format_option_help<W: std::fmt::Write>(
         &self,
         out: &mut W,
         opt: &OptionDef,
@@ -2249,11 +3234,15 @@ impl Context {
         }
 
         if !help_text.is_empty() {
-            // Print with alignment
-            let _ = write!(out, "  {:width$}   ", left, width = max_left_col);
+            // Print with alignment (pad first, then color, so ANSI escapes
+            // don't get counted against the column width)
+            let padded = format!("{:width$}", left, width = max_left_col);
+            let left_col = if stdout_colors_enabled() { bold(&padded) } else { padded };
+            let _ = write!(out, "  {}   ", left_col);
             // Word-wrap the help text
             write_wrapped_text(out, &help_text, indent_length, line_length);
         } else {
+            let left = if stdout_colors_enabled() { bold(&left) } else { left };
             let _ = write!(out, "  {}\n", left);
         }
     }
@@ -2309,7 +3298,7 @@ impl Context {
     }
 
     /// Format alias for help display
-    fn format_alias_help<W: std::io::Write>(
+    fn format_alias_help<W: std::fmt::Write>(
         &self,
         out: &mut W,
         alias: &Alias,
@@ -2357,11 +3346,37 @@ impl Context {
         }
     }
 
-    /// Get a typed value by option name (or store_as name)
-    pub fn get<T: FromStoredValue>(&self, name: &str) -> Result<T> {
-        match self.values.get(name) {
-            Some(v) => T::from_stored_value(v),
-            None => Err(Error::NotFound(name.to_string())),
+    /// The stored value for `name`, coerced toward its declared
+    /// `ArgType` (see [`coerce_for_arg_type`]) when that differs from
+    /// the variant actually stored. `None` if `name` was never set.
+    fn coerced_value(&self, name: &str) -> Option<StoredValue> {
+        let v = self.values.get(name)?;
+        let coerced = self
+            .find_option_by_storage_key(name)
+            .and_then(|opt| coerce_for_arg_type(v, &opt.arg_type));
+        Some(coerced.unwrap_or_else(|| v.clone()))
+    }
+
+    /// Get a typed value by option name (or store_as name). `T` may be
+    /// `Option<U>` to treat an option that was never set as `Ok(None)`
+    /// instead of an `Err(Error::NotFound)`.
+    pub fn get<T: FromOptionalStoredValue>(&self, name: &str) -> Result<T> {
+        T::from_optional_stored_value(name, self.coerced_value(name).as_ref())
+    }
+
+    /// Like [`Context::get`], but returning `default` instead of an error
+    /// if `name` was never set or doesn't convert to `T`.
+    pub fn get_or<T: FromStoredValue>(&self, name: &str, default: T) -> T {
+        self.coerced_value(name)
+            .and_then(|v| T::from_stored_value(&v).ok())
+            .unwrap_or(default)
+    }
+
+    /// Like [`Context::get_or`], but computing the fallback lazily.
+    pub fn get_with<T: FromStoredValue, F: FnOnce() -> T>(&self, name: &str, f: F) -> T {
+        match self.coerced_value(name).and_then(|v| T::from_stored_value(&v).ok()) {
+            Some(v) => v,
+            None => f(),
         }
     }
 
@@ -2374,6 +3389,256 @@ impl Context {
     pub fn args(&self) -> Vec<String> {
         self.remaining.clone()
     }
+
+    /// Render the flattened option tree as a Graphviz `digraph`, for
+    /// auditing large `include_table` hierarchies -- e.g. spotting an
+    /// accidentally duplicated short flag across two included tables. Each
+    /// `table_sections` entry (one per `include_table`/`auto_help`/
+    /// `auto_alias`) becomes a cluster subgraph headed by its description,
+    /// with an edge from that header down to every option node in its
+    /// range; options outside of any section hang directly off the root.
+    /// Doc-hidden options are drawn with a dashed outline.
+    pub fn to_dot(&self, w: &mut impl std::fmt::Write) -> std::fmt::Result {
+        writeln!(w, "digraph popt {{")?;
+        writeln!(w, "    root [label={:?}, shape=box];", self.name)?;
+
+        let mut in_section = vec![false; self.options.len()];
+        for (i, (start, end, description)) in self.table_sections.iter().enumerate() {
+            for opt_idx in *start..*end {
+                in_section[opt_idx] = true;
+            }
+            let label = description.as_deref().unwrap_or("(section)");
+            writeln!(
+                w,
+                "    section_{} [label={:?}, shape=box, style=filled, fillcolor=lightgrey];",
+                i, label
+            )?;
+            writeln!(w, "    root -> section_{};", i)?;
+            writeln!(w, "    subgraph cluster_{} {{", i)?;
+            writeln!(w, "        label={:?};", label)?;
+            for opt_idx in *start..*end {
+                self.write_dot_option_node(w, opt_idx)?;
+                writeln!(w, "        section_{} -> opt_{};", i, opt_idx)?;
+            }
+            writeln!(w, "    }}")?;
+        }
+
+        for opt_idx in 0..self.options.len() {
+            if !in_section[opt_idx] {
+                self.write_dot_option_node(w, opt_idx)?;
+                writeln!(w, "    root -> opt_{};", opt_idx)?;
+            }
+        }
+
+        writeln!(w, "}}")
+    }
+
+    fn write_dot_option_node(
+        &self,
+        w: &mut impl std::fmt::Write,
+        opt_idx: usize,
+    ) -> std::fmt::Result {
+        let opt = &self.options[opt_idx];
+        let short = opt
+            .short_name
+            .map(|c| format!(" -{}", c))
+            .unwrap_or_default();
+        let label = format!("--{}{}\\n{:?}", opt.long_name, short, opt.arg_type);
+        let style = if opt.flags_doc_hidden {
+            ", style=dashed"
+        } else {
+            ""
+        };
+        writeln!(w, "    opt_{} [label={:?}{}];", opt_idx, label, style)
+    }
+
+    /// Gather every completable flag -- declared options, aliases, and (with
+    /// the `exec` feature) exec aliases -- skipping `flags_doc_hidden`
+    /// options and nameless entries.
+    fn completion_entries(&self) -> Vec<CompletionEntry> {
+        let mut entries = Vec::new();
+
+        for opt in &self.options {
+            if opt.flags_doc_hidden {
+                continue;
+            }
+            let long = if opt.long_name.is_empty() {
+                None
+            } else {
+                Some(opt.long_name.clone())
+            };
+            if long.is_none() && opt.short_name.is_none() {
+                continue;
+            }
+            let takes_arg = opt.takes_arg();
+            entries.push(CompletionEntry {
+                long,
+                short: opt.short_name,
+                takes_arg,
+                hint_file: takes_arg && matches!(opt.arg_type, ArgType::String | ArgType::Argv),
+                description: opt.description.clone(),
+            });
+        }
+
+        for alias in &self.aliases {
+            if alias.doc_hidden || (alias.long_name.is_none() && alias.short_name.is_none()) {
+                continue;
+            }
+            entries.push(CompletionEntry {
+                long: alias.long_name.clone(),
+                short: alias.short_name,
+                takes_arg: false,
+                hint_file: false,
+                description: alias.description.clone(),
+            });
+        }
+
+        #[cfg(feature = "exec")]
+        for exec in &self.execs {
+            if exec.long_name.is_none() && exec.short_name.is_none() {
+                continue;
+            }
+            entries.push(CompletionEntry {
+                long: exec.long_name.clone(),
+                short: exec.short_name,
+                takes_arg: false,
+                hint_file: false,
+                description: None,
+            });
+        }
+
+        entries
+    }
+
+    /// Emit a shell-completion script for this program's options, aliases,
+    /// and exec aliases. See [`Shell`] for the supported targets; which
+    /// completion mechanism is actually installed is left to the caller.
+    pub fn generate_completion(
+        &self,
+        shell: Shell,
+        w: &mut impl std::io::Write,
+    ) -> std::io::Result<()> {
+        let entries = self.completion_entries();
+        match shell {
+            Shell::Bash => self.write_bash_completion(&entries, w),
+            Shell::Zsh => self.write_zsh_completion(&entries, w),
+            Shell::Fish => self.write_fish_completion(&entries, w),
+        }
+    }
+
+    fn write_bash_completion(
+        &self,
+        entries: &[CompletionEntry],
+        w: &mut impl std::io::Write,
+    ) -> std::io::Result<()> {
+        let fn_name = format!(
+            "_{}_completions",
+            self.name.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+        );
+
+        let mut all_flags = Vec::new();
+        let mut file_flags = Vec::new();
+        for e in entries {
+            if let Some(l) = &e.long {
+                all_flags.push(format!("--{}", l));
+                if e.hint_file {
+                    file_flags.push(format!("--{}", l));
+                }
+            }
+            if let Some(s) = e.short {
+                all_flags.push(format!("-{}", s));
+                if e.hint_file {
+                    file_flags.push(format!("-{}", s));
+                }
+            }
+        }
+
+        writeln!(w, "# bash completion for {}", self.name)?;
+        writeln!(w, "{}() {{", fn_name)?;
+        writeln!(w, "    local cur prev opts")?;
+        writeln!(w, "    COMPREPLY=()")?;
+        writeln!(w, "    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"")?;
+        writeln!(w, "    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"")?;
+        writeln!(w, "    opts=\"{}\"", all_flags.join(" "))?;
+        if !file_flags.is_empty() {
+            writeln!(w, "    case \"$prev\" in")?;
+            writeln!(w, "        {})", file_flags.join("|"))?;
+            writeln!(w, "            COMPREPLY=( $(compgen -f -- \"$cur\") )")?;
+            writeln!(w, "            return 0")?;
+            writeln!(w, "            ;;")?;
+            writeln!(w, "    esac")?;
+        }
+        writeln!(w, "    COMPREPLY=( $(compgen -W \"$opts\" -- \"$cur\") )")?;
+        writeln!(w, "}}")?;
+        writeln!(w, "complete -F {} {}", fn_name, self.name)
+    }
+
+    fn write_zsh_completion(
+        &self,
+        entries: &[CompletionEntry],
+        w: &mut impl std::io::Write,
+    ) -> std::io::Result<()> {
+        writeln!(w, "#compdef {}", self.name)?;
+        writeln!(w, "_arguments \\")?;
+
+        let mut lines = Vec::new();
+        for e in entries {
+            let desc = e.description.as_deref().unwrap_or("");
+            let hint = if !e.takes_arg {
+                ""
+            } else if e.hint_file {
+                ":value:_files"
+            } else {
+                ":value:"
+            };
+            if let Some(l) = &e.long {
+                lines.push(format!("  '--{}[{}]{}'", l, desc, hint));
+            }
+            if let Some(s) = e.short {
+                lines.push(format!("  '-{}[{}]{}'", s, desc, hint));
+            }
+        }
+        writeln!(w, "{}", lines.join(" \\\n"))
+    }
+
+    fn write_fish_completion(
+        &self,
+        entries: &[CompletionEntry],
+        w: &mut impl std::io::Write,
+    ) -> std::io::Result<()> {
+        for e in entries {
+            let mut line = format!("complete -c {}", self.name);
+            if let Some(s) = e.short {
+                line.push_str(&format!(" -s {}", s));
+            }
+            if let Some(l) = &e.long {
+                line.push_str(&format!(" -l {}", l));
+            }
+            if e.takes_arg {
+                line.push_str(" -r");
+                if !e.hint_file {
+                    line.push_str(" -x");
+                }
+            }
+            if let Some(d) = &e.description {
+                if !d.is_empty() {
+                    line.push_str(&format!(" -d \"{}\"", d.replace('"', "'")));
+                }
+            }
+            writeln!(w, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// One flag produced by [`Context::completion_entries`] for rendering in any
+/// of the shell-specific completion formats.
+struct CompletionEntry {
+    long: Option<String>,
+    short: Option<char>,
+    takes_arg: bool,
+    hint_file: bool,
+    description: Option<String>,
 }
 
 // ============================================================================
@@ -2502,6 +3767,9 @@ fn jlu32lpair(key: &[u8]) -> (u32, u32) {
 const BLOOM_DEFAULT_N: u32 = 1024;
 const BLOOM_DEFAULT_K: u32 = 16;
 
+/// Magic/version tag prefixing [`BloomFilter::to_bytes`] output.
+const BLOOM_MAGIC: &[u8; 4] = b"BLF1";
+
 #[derive(Debug, Clone)]
 pub struct BloomFilter {
     bits: Vec<u32>,
@@ -2526,6 +3794,40 @@ impl BloomFilter {
         }
     }
 
+    /// Construct a filter sized for `expected_items` elements at a target
+    /// false-positive rate `p` (e.g. `0.01` for 1%), using the standard
+    /// formulas `m = ceil(-(n * ln p) / (ln 2)^2)` and
+    /// `k = max(1, round((m / n) * ln 2))`. `m` is rounded up to a whole
+    /// number of 32-bit words and `k` is clamped to `1..=32`, matching the
+    /// range `insert`/`contains`'s double-hashing loop assumes.
+    pub fn with_error_rate(expected_items: u32, p: f64) -> Self {
+        let n = if expected_items == 0 {
+            BLOOM_DEFAULT_N
+        } else {
+            expected_items
+        };
+        let m_bits = (-(n as f64) * p.ln() / std::f64::consts::LN_2.powi(2)).ceil();
+        let m = (m_bits.max(1.0) as u32).max(1);
+        let k = (((m as f64 / n as f64) * std::f64::consts::LN_2).round() as u32)
+            .max(1)
+            .min(32);
+        let nwords = (m as usize).saturating_sub(1) / 32 + 1;
+        BloomFilter {
+            bits: vec![0u32; nwords],
+            k,
+            m,
+        }
+    }
+
+    /// Estimate the current false-positive rate given `inserted` elements,
+    /// via `(1 - e^(-k*inserted/m))^k`. Callers can use this to detect when a
+    /// filter sized with [`with_error_rate`](Self::with_error_rate) has
+    /// become overfull.
+    pub fn estimated_fp_rate(&self, inserted: u32) -> f64 {
+        let exponent = -(self.k as f64) * (inserted as f64) / (self.m as f64);
+        (1.0 - exponent.exp()).powi(self.k as i32)
+    }
+
     pub fn insert(&mut self, s: &str) {
         let (h0, h1) = jlu32lpair(s.as_bytes());
         for i in 0..self.k {
@@ -2579,6 +3881,65 @@ impl BloomFilter {
         any != 0
     }
 
+    /// Serialize this filter to a compact binary form: a magic/version
+    /// header (`b"BLF1"`), `k`, `m` and the word count, followed by the raw
+    /// `bits` words, all little-endian. Round-trips with
+    /// [`from_bytes`](Self::from_bytes) without re-hashing the original keys.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(BLOOM_MAGIC.len() + 12 + self.bits.len() * 4);
+        out.extend_from_slice(BLOOM_MAGIC);
+        out.extend_from_slice(&self.k.to_le_bytes());
+        out.extend_from_slice(&self.m.to_le_bytes());
+        out.extend_from_slice(&(self.bits.len() as u32).to_le_bytes());
+        for word in &self.bits {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+        out
+    }
+
+    /// Deserialize a filter previously written by
+    /// [`to_bytes`](Self::to_bytes). Returns `Error` on truncated input, a
+    /// bad magic/version tag, or a word count that doesn't match the
+    /// remaining data.
+    pub fn from_bytes(data: &[u8]) -> Result<BloomFilter> {
+        let header_len = BLOOM_MAGIC.len() + 12;
+        if data.len() < header_len {
+            return Err(Error::Other(
+                "BloomFilter::from_bytes: truncated header".to_string(),
+            ));
+        }
+        if &data[..BLOOM_MAGIC.len()] != BLOOM_MAGIC {
+            return Err(Error::Other(
+                "BloomFilter::from_bytes: bad magic/version tag".to_string(),
+            ));
+        }
+        let mut off = BLOOM_MAGIC.len();
+        let read_u32 = |off: &mut usize| -> u32 {
+            let v = u32::from_le_bytes(data[*off..*off + 4].try_into().unwrap());
+            *off += 4;
+            v
+        };
+        let k = read_u32(&mut off);
+        let m = read_u32(&mut off);
+        let nwords = read_u32(&mut off) as usize;
+
+        let expected_len = header_len + nwords * 4;
+        if data.len() != expected_len {
+            return Err(Error::Other(format!(
+                "BloomFilter::from_bytes: word count mismatch (header says {} words, data has {} bytes remaining)",
+                nwords,
+                data.len() - header_len
+            )));
+        }
+
+        let mut bits = Vec::with_capacity(nwords);
+        for _ in 0..nwords {
+            bits.push(read_u32(&mut off));
+        }
+
+        Ok(BloomFilter { bits, k, m })
+    }
+
     /// Parse comma-separated items into the bloom filter.
     /// Items prefixed with '!' are removed (if present).
     pub fn save_bits(&mut self, s: &str) {
@@ -2603,18 +3964,193 @@ impl Default for BloomFilter {
     }
 }
 
+// ============================================================================
+// CountingBloomFilter
+// ============================================================================
+
+const COUNTING_BLOOM_COUNTER_MAX: u8 = 0x0F;
+
+/// Bloom filter variant with small saturating counters instead of single bits.
+///
+/// [`BloomFilter::remove`] clears every bit an element hashed to, which
+/// corrupts the filter whenever another inserted element shares one of those
+/// bit positions. `CountingBloomFilter` replaces the bit array with 4-bit
+/// counters (two packed per byte, saturating at 15) so `insert`/`remove` pairs
+/// can't silently poison membership for unrelated elements.
+#[derive(Debug, Clone)]
+pub struct CountingBloomFilter {
+    counters: Vec<u8>,
+    k: u32,
+    m: u32,
+}
+
+impl CountingBloomFilter {
+    pub fn new() -> Self {
+        Self::with_sizing(BLOOM_DEFAULT_K, BLOOM_DEFAULT_N)
+    }
+
+    pub fn with_sizing(k: u32, n: u32) -> Self {
+        let k = if k == 0 || k > 32 { BLOOM_DEFAULT_K } else { k };
+        let n = if n == 0 { BLOOM_DEFAULT_N } else { n };
+        let m = (3 * n) / 2;
+        let nbytes = (m as usize).saturating_sub(1) / 2 + 1;
+        CountingBloomFilter {
+            counters: vec![0u8; nbytes],
+            k,
+            m,
+        }
+    }
+
+    fn counter(&self, ix: u32) -> u8 {
+        let byte = self.counters[(ix / 2) as usize];
+        if ix % 2 == 0 {
+            byte & 0x0F
+        } else {
+            byte >> 4
+        }
+    }
+
+    fn set_counter(&mut self, ix: u32, val: u8) {
+        let slot = &mut self.counters[(ix / 2) as usize];
+        if ix % 2 == 0 {
+            *slot = (*slot & 0xF0) | (val & 0x0F);
+        } else {
+            *slot = (*slot & 0x0F) | (val << 4);
+        }
+    }
+
+    pub fn insert(&mut self, s: &str) {
+        let (h0, h1) = jlu32lpair(s.as_bytes());
+        for i in 0..self.k {
+            let h = h0.wrapping_add(i.wrapping_mul(h1));
+            let ix = h % self.m;
+            let c = self.counter(ix);
+            if c < COUNTING_BLOOM_COUNTER_MAX {
+                self.set_counter(ix, c + 1);
+            }
+        }
+    }
+
+    pub fn contains(&self, s: &str) -> bool {
+        let (h0, h1) = jlu32lpair(s.as_bytes());
+        for i in 0..self.k {
+            let h = h0.wrapping_add(i.wrapping_mul(h1));
+            let ix = h % self.m;
+            if self.counter(ix) == 0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Decrement the counter at each of the element's `k` positions.
+    /// Counters already at zero stay at zero, and counters saturated at the
+    /// max representable count are left untouched (their true count is
+    /// unknown, so decrementing could make another element's membership
+    /// disappear).
+    pub fn remove(&mut self, s: &str) {
+        let (h0, h1) = jlu32lpair(s.as_bytes());
+        for i in 0..self.k {
+            let h = h0.wrapping_add(i.wrapping_mul(h1));
+            let ix = h % self.m;
+            let c = self.counter(ix);
+            if c > 0 && c < COUNTING_BLOOM_COUNTER_MAX {
+                self.set_counter(ix, c - 1);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for b in &mut self.counters {
+            *b = 0;
+        }
+    }
+
+    /// Parse comma-separated items into the bloom filter.
+    /// Items prefixed with '!' are removed (if present).
+    pub fn save_bits(&mut self, s: &str) {
+        for token in s.split(',') {
+            if token.is_empty() {
+                continue;
+            }
+            if let Some(rest) = token.strip_prefix('!') {
+                if self.contains(rest) {
+                    self.remove(rest);
+                }
+            } else {
+                self.insert(token);
+            }
+        }
+    }
+}
+
+impl Default for CountingBloomFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // ============================================================================
 // Config file utilities
 // ============================================================================
 
+/// Maximum `include`/`@path` nesting depth, guarding against runaway recursion.
+const CONFIG_INCLUDE_MAX_DEPTH: usize = 16;
+
 /// Convert a config file to an argv-style string.
 ///
 /// Each non-empty, non-comment line becomes `--key` (bare) or `--key="value"` (with value).
 /// Lines with spaces in the key name (before `=`) are silently ignored.
 /// Lines with empty values after `=` are silently ignored.
+///
+/// A line of the form `include <path>` or `@path` splices another config
+/// file's expansion inline at that point, so later keys can still override
+/// earlier ones. Relative include paths are resolved against the directory
+/// of the file containing the directive. Include cycles and nesting deeper
+/// than [`CONFIG_INCLUDE_MAX_DEPTH`] return `Error::ConfigFile`.
 pub fn config_file_to_string(path: &str) -> Result<String> {
-    let content = std::fs::read_to_string(path)
+    let canonical = std::fs::canonicalize(path)
         .map_err(|e| Error::ConfigFile(format!("Failed to open {}: {}", path, e)))?;
+    let mut seen = HashSet::new();
+    config_file_to_string_inner(&canonical, &mut seen, 0, false)
+}
+
+/// Like [`config_file_to_string`], but also expands `$VAR`/`${VAR}`
+/// references and a leading `~/`/bare `~` home-directory reference in each
+/// value, so credentials and paths can live in the environment rather than
+/// plaintext config (e.g. `bindpw=$LDAP_PW`, `certfile=~/certs/ca.pem`).
+/// Unset variables expand to an empty string; an unterminated `${` returns
+/// [`Error::BadQuote`].
+pub fn config_file_to_string_expand(path: &str) -> Result<String> {
+    let canonical = std::fs::canonicalize(path)
+        .map_err(|e| Error::ConfigFile(format!("Failed to open {}: {}", path, e)))?;
+    let mut seen = HashSet::new();
+    config_file_to_string_inner(&canonical, &mut seen, 0, true)
+}
+
+fn config_file_to_string_inner(
+    path: &std::path::Path,
+    seen: &mut HashSet<std::path::PathBuf>,
+    depth: usize,
+    expand: bool,
+) -> Result<String> {
+    if depth > CONFIG_INCLUDE_MAX_DEPTH {
+        return Err(Error::ConfigFile(format!(
+            "{}: include nesting too deep (max {})",
+            path.display(),
+            CONFIG_INCLUDE_MAX_DEPTH
+        )));
+    }
+    if !seen.insert(path.to_path_buf()) {
+        return Err(Error::ConfigFile(format!(
+            "{}: include cycle detected",
+            path.display()
+        )));
+    }
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| Error::ConfigFile(format!("Failed to open {}: {}", path.display(), e)))?;
+    let base_dir = path.parent().unwrap_or_else(|| std::path::Path::new("."));
 
     let mut result = std::string::String::new();
 
@@ -2627,6 +4163,45 @@ pub fn config_file_to_string(path: &str) -> Result<String> {
             continue;
         }
 
+        // `include <path>` or `@path` directive: splice the included file's
+        // expansion inline.
+        let include_target = if let Some(rest) = l.strip_prefix('@') {
+            Some(rest.trim())
+        } else if let Some(rest) = l.strip_prefix("include") {
+            if rest.starts_with(|c: char| c.is_ascii_whitespace()) {
+                Some(rest.trim())
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        if let Some(target) = include_target {
+            if !target.is_empty() {
+                let include_path = std::path::Path::new(target);
+                let resolved = if include_path.is_absolute() {
+                    include_path.to_path_buf()
+                } else {
+                    base_dir.join(include_path)
+                };
+                let canonical = std::fs::canonicalize(&resolved).map_err(|e| {
+                    Error::ConfigFile(format!(
+                        "Failed to open included file {}: {}",
+                        resolved.display(),
+                        e
+                    ))
+                })?;
+                result.push_str(&config_file_to_string_inner(
+                    &canonical,
+                    seen,
+                    depth + 1,
+                    expand,
+                )?);
+                continue;
+            }
+        }
+
         // Find key: non-space, non-= characters from the start
         let key_end = l
             .find(|c: char| c.is_ascii_whitespace() || c == '=')
@@ -2664,6 +4239,13 @@ pub fn config_file_to_string(path: &str) -> Result<String> {
             continue;
         }
 
+        let value = if expand {
+            expand_env_and_tilde(value)?
+        } else {
+            value.to_string()
+        };
+        let value = value.as_str();
+
         // Append --key="value"
         result.push_str(" --");
         result.push_str(key);
@@ -2672,6 +4254,7 @@ pub fn config_file_to_string(path: &str) -> Result<String> {
         result.push('"');
     }
 
+    seen.remove(&path.to_path_buf());
     Ok(result)
 }
 
@@ -2680,6 +4263,21 @@ pub fn config_file_to_string(path: &str) -> Result<String> {
 /// Handles single and double quoting, backslash escaping.
 /// Matches popt's `poptParseArgvString` behavior.
 pub fn parse_argv_string(s: &str) -> Result<Vec<String>> {
+    parse_argv_string_inner(s, false)
+}
+
+/// Like [`parse_argv_string`], but also expands `$VAR`/`${VAR}` references
+/// (read from the process environment; unset variables expand to an empty
+/// string) and a leading `~`/`~/` home-directory reference in each
+/// resulting argument. Expansion follows shell quoting semantics: it is
+/// suppressed inside single quotes and performed everywhere else,
+/// including inside double quotes. An unterminated `${` is reported as
+/// [`Error::BadQuote`].
+pub fn parse_argv_string_expand(s: &str) -> Result<Vec<String>> {
+    parse_argv_string_inner(s, true)
+}
+
+fn parse_argv_string_inner(s: &str, expand: bool) -> Result<Vec<String>> {
     let mut args = Vec::new();
     let mut current = std::string::String::new();
     let mut quote: Option<char> = None;
@@ -2702,13 +4300,19 @@ pub fn parse_argv_string(s: &str) -> Result<Vec<String>> {
                         current.push(next);
                     }
                 }
+            } else if expand && q == '"' && c == '$' {
+                // Expansion is permitted inside double quotes.
+                match expand_var_ref(&mut chars)? {
+                    Some(expanded) => current.push_str(&expanded),
+                    None => current.push('$'),
+                }
             } else {
                 current.push(c);
             }
         } else if c.is_ascii_whitespace() {
             // Outside quotes, whitespace delimits tokens
             if !current.is_empty() {
-                args.push(std::mem::take(&mut current));
+                args.push(finish_argv_token(std::mem::take(&mut current), expand));
             }
         } else {
             match c {
@@ -2721,6 +4325,10 @@ pub fn parse_argv_string(s: &str) -> Result<Vec<String>> {
                         current.push(next);
                     }
                 },
+                '$' if expand => match expand_var_ref(&mut chars)? {
+                    Some(expanded) => current.push_str(&expanded),
+                    None => current.push('$'),
+                },
                 _ => {
                     current.push(c);
                 }
@@ -2730,8 +4338,168 @@ pub fn parse_argv_string(s: &str) -> Result<Vec<String>> {
 
     // Don't forget the last token
     if !current.is_empty() {
-        args.push(current);
+        args.push(finish_argv_token(current, expand));
     }
 
     Ok(args)
 }
+
+/// Apply tilde expansion to a finished token, if expansion is enabled.
+fn finish_argv_token(token: String, expand: bool) -> String {
+    if expand {
+        expand_leading_tilde(&token)
+    } else {
+        token
+    }
+}
+
+/// Expand `$VAR`/`${VAR}` references in a config value. An unset variable
+/// expands to an empty string; a `$` that isn't followed by a valid
+/// reference (a bare `$`, an unterminated `${`, or an empty `${}`) is
+/// reported as [`Error::ConfigFile`] rather than copied through literally.
+fn interpolate_env_vars(s: &str) -> Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.peek() {
+            Some('{') => {
+                chars.next(); // consume '{'
+                let mut name = String::new();
+                let mut closed = false;
+                for c2 in chars.by_ref() {
+                    if c2 == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(c2);
+                }
+                if !closed {
+                    return Err(Error::ConfigFile(format!(
+                        "unterminated '${{{}' reference (missing '}}')",
+                        name
+                    )));
+                }
+                if name.is_empty() {
+                    return Err(Error::ConfigFile("empty '${}' reference".to_string()));
+                }
+                out.push_str(&std::env::var(&name).unwrap_or_default());
+            }
+            Some(&c2) if c2.is_ascii_alphabetic() || c2 == '_' => {
+                let mut name = String::new();
+                while let Some(&c2) = chars.peek() {
+                    if c2.is_ascii_alphanumeric() || c2 == '_' {
+                        name.push(c2);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                out.push_str(&std::env::var(&name).unwrap_or_default());
+            }
+            _ => {
+                return Err(Error::ConfigFile(
+                    "'$' not followed by a variable name".to_string(),
+                ));
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Consume a `$NAME` or `${NAME}` reference from `chars` (the leading `$`
+/// has already been consumed). Returns `Ok(None)` if `$` wasn't followed
+/// by a valid reference, in which case it should be treated as a literal
+/// `$`. An unterminated `${` is reported as [`Error::BadQuote`].
+fn expand_var_ref(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<Option<String>> {
+    match chars.peek() {
+        Some('{') => {
+            chars.next(); // consume '{'
+            let mut name = String::new();
+            let mut closed = false;
+            for c2 in chars.by_ref() {
+                if c2 == '}' {
+                    closed = true;
+                    break;
+                }
+                name.push(c2);
+            }
+            if !closed {
+                return Err(Error::BadQuote(format!(
+                    "unterminated '${{{}' reference (missing '}}')",
+                    name
+                )));
+            }
+            Ok(Some(std::env::var(&name).unwrap_or_default()))
+        }
+        Some(&c2) if c2.is_ascii_alphabetic() || c2 == '_' => {
+            let mut name = String::new();
+            while let Some(&c2) = chars.peek() {
+                if c2.is_ascii_alphanumeric() || c2 == '_' {
+                    name.push(c2);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            Ok(Some(std::env::var(&name).unwrap_or_default()))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Expand a leading `~/` or bare `~` to the user's home directory, as
+/// reported by the `HOME` environment variable. Left unchanged if `HOME`
+/// isn't set (or is empty) or the string doesn't start with `~`.
+fn expand_leading_tilde(s: &str) -> String {
+    let home = match std::env::var("HOME") {
+        Ok(home) if !home.is_empty() => home,
+        _ => return s.to_string(),
+    };
+    if s == "~" {
+        home
+    } else if let Some(rest) = s.strip_prefix("~/") {
+        format!("{}/{}", home, rest)
+    } else {
+        s.to_string()
+    }
+}
+
+/// Expand `$VAR`/`${VAR}` references and a leading `~`/`~/`
+/// home-directory reference in a raw config value, following shell
+/// quoting semantics: single quotes suppress expansion and are stripped
+/// from the result, while everything else is expanded normally. Unset
+/// variables expand to an empty string; an unterminated `${` is reported
+/// as [`Error::BadQuote`].
+fn expand_env_and_tilde(s: &str) -> Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    let mut in_single_quote = false;
+
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            in_single_quote = !in_single_quote;
+        } else if in_single_quote {
+            out.push(c);
+        } else if c == '\\' {
+            if let Some(next) = chars.next() {
+                out.push(next);
+            }
+        } else if c == '$' {
+            match expand_var_ref(&mut chars)? {
+                Some(expanded) => out.push_str(&expanded),
+                None => out.push('$'),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+
+    Ok(expand_leading_tilde(&out))
+}